@@ -0,0 +1,2070 @@
+//! High-level operations for using the crate as a library
+//!
+//! The CLI commands work on the current directory, print their progress and exit
+//! with a status code. [`Workspace`] runs the same operations against an explicit
+//! project directory and returns structured results instead, so the tool can be
+//! embedded in other build tools without spawning the binary:
+//!
+//! ```no_run
+//! # async fn example() -> anyhow::Result<()> {
+//! use apicurio_cli::api::{LockOptions, Workspace};
+//!
+//! let workspace = Workspace::open("path/to/project")?;
+//! let report = workspace.pull(None, LockOptions::default()).await?;
+//! for dependency in &report.written {
+//!     println!("{} -> {}", dependency.name, dependency.output_path);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Progress is reported to the [`EventSink`] set with [`Workspace::with_events`].
+
+use anyhow::{anyhow, bail, Context, Result};
+use semver::Version;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    commands::{lock, pull},
+    config::{
+        check_placeholders, load_global_config, load_repo_config, read_merged_config, GlobalConfig,
+        GraphqlCheck, PublishConfig, RegistryConfig, RepoConfig, StalenessPolicy, UpdateSeverity,
+        VersionSource, VersionStrategy,
+    },
+    config_edit::{list_named_entries, read_editable, upsert_named_entry, write_edited},
+    constants::{APICURIO_CONFIG, APICURIO_LOCK},
+    dependency::Dependency,
+    events::{Event, EventSink, NullSink},
+    gitignore, graphql,
+    identifier::{find_dependency, resolve_artifact},
+    lint::{LintReport, Linter, SchemaKind},
+    local_refs,
+    lockfile::{check_output_conflicts, LockFile, LockedDependency},
+    naming, openapi, output_path, paths, plugins, protobuf,
+    protobuf::DescriptorSet,
+    provenance,
+    registry::{
+        content_matches, encode_publish_content, RegistryClient, RegistryPool, RuleViolation,
+    },
+};
+
+pub use crate::commands::{
+    lock::LockOptions, publish::PublishOptions, pull::PullOptions, status::StatusOptions,
+};
+
+/// A project directory with its configuration and registry clients
+pub struct Workspace {
+    root: PathBuf,
+    config_path: PathBuf,
+    config: RepoConfig,
+    /// Merged config text, used for the lock file's config hash
+    config_content: String,
+    clients: HashMap<String, RegistryClient>,
+    /// Merged registry configurations, for their naming conventions
+    registries: Vec<RegistryConfig>,
+    events: Arc<dyn EventSink>,
+}
+
+impl Workspace {
+    /// Open the project in `root`, using the global registries of the current user
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_global(root, load_global_config()?)
+    }
+
+    /// Open the project in the current directory, keeping reported paths relative
+    ///
+    /// The config is read from `--config`/`APICURIO_CONFIG_PATH` when set, and
+    /// the project is then rooted in its directory.
+    pub fn current() -> Result<Self> {
+        if paths::project_config_overridden() {
+            return Self::open_config_file(paths::project_config());
+        }
+        Self::open(PathBuf::new())
+    }
+
+    /// Open the project whose config is `config_path`, rooted in the directory
+    /// of that file
+    pub fn open_config_file(config_path: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_config_file_with_global(config_path.into(), load_global_config()?)
+    }
+
+    fn open_config_file_with_global(config_path: PathBuf, global: GlobalConfig) -> Result<Self> {
+        if !config_path.exists() {
+            bail!("Config file {} not found", config_path.display());
+        }
+        let root = config_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        Self::open_config(root, config_path, global)
+    }
+
+    /// Open the project in `root` with explicitly provided global registries
+    pub fn open_with_global(root: impl Into<PathBuf>, global: GlobalConfig) -> Result<Self> {
+        let root = root.into();
+        let config_path = paths::config_in(&root);
+        if !config_path.exists() {
+            let location = if root.as_os_str().is_empty() {
+                "current directory".to_string()
+            } else {
+                root.display().to_string()
+            };
+            bail!("No {APICURIO_CONFIG} found in {location}. Run 'apicurio init' first.");
+        }
+        Self::open_config(root, config_path, global)
+    }
+
+    /// Open the project in `root` whose config is `config_path`
+    fn open_config(root: PathBuf, config_path: PathBuf, global: GlobalConfig) -> Result<Self> {
+        let config_content = read_merged_config(&config_path)?;
+        let mut config = load_repo_config(&config_path)?;
+        if let Some(path) = &mut config.external_registries_file {
+            if !path.is_empty() && Path::new(path.as_str()).is_relative() {
+                *path = root.join(&*path).to_string_lossy().into_owned();
+            }
+        }
+
+        let registries = config.merge_registries(global)?;
+        let clients = RegistryPool::global().clients(&registries)?;
+        Ok(Workspace {
+            root,
+            config_path,
+            config,
+            config_content,
+            clients,
+            registries,
+            events: Arc::new(NullSink),
+        })
+    }
+
+    /// Report progress to `events` instead of discarding it
+    pub fn with_events(mut self, events: Arc<dyn EventSink>) -> Self {
+        self.clients = self
+            .clients
+            .into_iter()
+            .map(|(name, client)| (name, client.with_events(events.clone())))
+            .collect();
+        self.events = events;
+        self
+    }
+
+    /// The project directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The repository configuration, with `include` fragments applied
+    pub fn config(&self) -> &RepoConfig {
+        &self.config
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.config_path.clone()
+    }
+
+    pub fn lock_path(&self) -> PathBuf {
+        paths::lock_beside(&self.config_path)
+    }
+
+    /// `publish` with the naming conventions of its registry applied: a missing
+    /// group is taken from `defaultGroup`, coordinates breaking them are refused
+    fn apply_naming(&self, publish: &PublishConfig) -> Result<PublishConfig> {
+        let Some(registry) = self.registries.iter().find(|r| r.name == publish.registry) else {
+            return Ok(publish.clone());
+        };
+        let publish = naming::with_default_group(registry, publish);
+        let problems = naming::violations(
+            registry,
+            &publish.resolved_group_id(),
+            &publish.resolved_artifact_id(),
+        );
+        if !problems.is_empty() {
+            bail!(
+                "Publish '{}' breaks the naming conventions of registry '{}':\n  - {}",
+                publish.name,
+                registry.name,
+                problems.join("\n  - ")
+            );
+        }
+        Ok(publish)
+    }
+
+    /// Client for a configured registry
+    pub fn client(&self, registry: &str) -> Option<&RegistryClient> {
+        self.clients.get(registry)
+    }
+
+    /// Resolve every dependency and its references without writing anything
+    pub async fn resolve(&self) -> Result<Vec<LockedDependency>> {
+        self.resolve_reusing(&[]).await
+    }
+
+    /// Resolve like [`Workspace::resolve`], keeping the hashes of unchanged
+    /// `previous` entries instead of downloading their content again
+    pub(crate) async fn resolve_reusing(
+        &self,
+        previous: &[LockedDependency],
+    ) -> Result<Vec<LockedDependency>> {
+        let locked =
+            lock::resolve_dependencies(&self.config, &self.clients, None, previous, &*self.events)
+                .await?;
+        check_output_conflicts(&locked)?;
+        Ok(locked)
+    }
+
+    /// Bring the lock file in sync with the configuration
+    ///
+    /// With [`LockOptions::locked`] or [`LockOptions::frozen`] an outdated lock
+    /// file is an error instead of being rewritten. Unless
+    /// [`LockOptions::allow_unset_env`] is set, `${VAR}` placeholders of unset
+    /// variables are an error too.
+    pub async fn lock(&self, opts: LockOptions) -> Result<LockOutcome> {
+        let (outcome, stale) = self.lock_deferring_removals(opts).await?;
+        lock::remove_stale_outputs(&self.root, &stale, &*self.events);
+        Ok(outcome)
+    }
+
+    /// [`Workspace::lock`], returning the output files of the previous lock
+    /// that the new one no longer writes instead of deleting them
+    async fn lock_deferring_removals(
+        &self,
+        opts: LockOptions,
+    ) -> Result<(LockOutcome, Vec<lock::StaleOutput>)> {
+        let config_path = self.config_path();
+        if !opts.allow_unset_env {
+            check_placeholders(&config_path)?;
+        }
+        let lock_path = self.lock_path();
+        let config_hash =
+            LockFile::compute_config_hash(&self.config_content, &self.config.dependencies);
+        let config_inputs =
+            LockFile::config_hash_inputs(&self.config_content, &self.config.dependencies);
+
+        if opts.locked || opts.frozen {
+            let lock = lock::check_lock_unchanged(
+                &lock_path,
+                &config_hash,
+                &config_inputs,
+                &self.clients,
+                opts,
+                &*self.events,
+            )
+            .await?;
+            return Ok((LockOutcome::UpToDate(lock), Vec::new()));
+        }
+
+        let existing_lock = LockFile::load(&lock_path).ok();
+        let mut reason = None;
+        if let Some(existing) = &existing_lock {
+            reason = Some(if !existing.is_compatible_with_config(&config_hash) {
+                LockUpdateReason::ConfigHashChanged
+            } else if !existing.is_newer_than_config(&config_path).unwrap_or(false) {
+                LockUpdateReason::ConfigModified
+            } else if !lock::verify_lock_is_still_valid(existing, &self.clients, &*self.events)
+                .await?
+            {
+                LockUpdateReason::VersionsUnavailable
+            } else if opts.force {
+                LockUpdateReason::Forced
+            } else if opts.update {
+                LockUpdateReason::UpdateRequested
+            } else {
+                let existing = existing_lock.unwrap();
+                self.sync_gitignore(&existing)?;
+                return Ok((LockOutcome::UpToDate(existing), Vec::new()));
+            });
+        }
+
+        // --force downloads everything again, refreshing hashes of unchanged versions
+        let previous = match &existing_lock {
+            Some(existing) if !opts.force => existing.locked_dependencies.as_slice(),
+            _ => &[],
+        };
+        let locked = self.resolve_reusing(previous).await?;
+        let config_modified = LockFile::get_config_modification_time(&config_path).ok();
+        let mut lock_file = LockFile::with_config_modified(locked, config_hash, config_modified);
+        lock_file.config_inputs = LockFile::config_input_checksums(&config_inputs);
+
+        // Re-resolving gave the same result: keep the file as is, or only record
+        // the new config modification time, keeping generatedAt
+        if let Some(existing) = existing_lock
+            .as_ref()
+            .filter(|existing| !opts.force && existing.same_resolution(&lock_file))
+        {
+            if existing.config_modified == lock_file.config_modified {
+                self.sync_gitignore(existing)?;
+                return Ok((LockOutcome::UpToDate(existing_lock.unwrap()), Vec::new()));
+            }
+            lock_file.generated_at = existing.generated_at.clone();
+        }
+
+        let stale = existing_lock
+            .map(|old| {
+                lock::stale_outputs(&old.locked_dependencies, &lock_file.locked_dependencies)
+            })
+            .unwrap_or_default();
+        lock_file
+            .force_save(&lock_path)
+            .with_context(|| format!("writing {}", lock_path.display()))?;
+        self.sync_gitignore(&lock_file)?;
+        Ok((
+            LockOutcome::Updated {
+                lock: lock_file,
+                reason,
+            },
+            stale,
+        ))
+    }
+
+    /// [`Workspace::lock_deferring_removals`] re-resolving only the dependency
+    /// named `name` and what it references when the lock is stale, keeping the
+    /// entries of every other dependency as they are
+    async fn lock_one_deferring_removals(
+        &self,
+        name: &str,
+        opts: LockOptions,
+    ) -> Result<(LockOutcome, Vec<lock::StaleOutput>)> {
+        let lock_path = self.lock_path();
+        let existing = match LockFile::load(&lock_path) {
+            Ok(existing) if !opts.locked && !opts.frozen => existing,
+            _ => return self.lock_deferring_removals(opts).await,
+        };
+        let config_path = self.config_path();
+        if !opts.allow_unset_env {
+            check_placeholders(&config_path)?;
+        }
+        let config_hash =
+            LockFile::compute_config_hash(&self.config_content, &self.config.dependencies);
+        let reason = if !existing.is_compatible_with_config(&config_hash) {
+            LockUpdateReason::ConfigHashChanged
+        } else if !existing.is_newer_than_config(&config_path).unwrap_or(false) {
+            LockUpdateReason::ConfigModified
+        } else if opts.force {
+            LockUpdateReason::Forced
+        } else if opts.update {
+            LockUpdateReason::UpdateRequested
+        } else {
+            self.sync_gitignore(&existing)?;
+            return Ok((LockOutcome::UpToDate(existing), Vec::new()));
+        };
+
+        let (lock_file, stale) = self.relock_one(name, &existing, opts.force).await?;
+        if !lock_file
+            .save(&lock_path)
+            .with_context(|| format!("writing {}", lock_path.display()))?
+        {
+            self.sync_gitignore(&existing)?;
+            return Ok((LockOutcome::UpToDate(existing), Vec::new()));
+        }
+        self.sync_gitignore(&lock_file)?;
+        Ok((
+            LockOutcome::Updated {
+                lock: lock_file,
+                reason: Some(reason),
+            },
+            stale,
+        ))
+    }
+
+    /// `existing` with the entries of the dependency named `name` (every
+    /// artifact of a `group` dependency) and what it references resolved again,
+    /// without saving it; also returns the output files it no longer writes
+    ///
+    /// The config hash is only brought up to date when nothing but this
+    /// dependency changed in the config, so other changes still re-lock.
+    pub(crate) async fn relock_one(
+        &self,
+        name: &str,
+        existing: &LockFile,
+        force: bool,
+    ) -> Result<(LockFile, Vec<lock::StaleOutput>)> {
+        let previous = match force {
+            true => &[],
+            false => existing.locked_dependencies.as_slice(),
+        };
+        let resolved = lock::resolve_dependencies(
+            &self.config,
+            &self.clients,
+            Some(name),
+            previous,
+            &*self.events,
+        )
+        .await?;
+
+        let config_hash =
+            LockFile::compute_config_hash(&self.config_content, &self.config.dependencies);
+        let config_inputs =
+            LockFile::config_hash_inputs(&self.config_content, &self.config.dependencies);
+        let own_input = |input: &str| {
+            input
+                .strip_prefix("dependency ")
+                .and_then(|rest| rest.strip_prefix(name))
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        };
+        let only_this_changed = existing.is_compatible_with_config(&config_hash)
+            || (!existing.config_inputs.is_empty()
+                && existing
+                    .changed_config_inputs(&config_inputs)
+                    .iter()
+                    .all(|change| own_input(change.input())));
+        let mut lock_file = if only_this_changed {
+            let config_modified = LockFile::get_config_modification_time(&self.config_path()).ok();
+            let mut lock_file = LockFile::with_config_modified(
+                existing.locked_dependencies.clone(),
+                config_hash,
+                config_modified,
+            );
+            lock_file.config_inputs = LockFile::config_input_checksums(&config_inputs);
+            lock_file
+        } else {
+            let mut lock_file = LockFile::with_config_modified(
+                existing.locked_dependencies.clone(),
+                existing.config_hash.clone(),
+                existing.config_modified.clone(),
+            );
+            lock_file.config_inputs = existing.config_inputs.clone();
+            lock_file
+        };
+        lock_file.replace_dependency_closure(name, resolved);
+        check_output_conflicts(&lock_file.locked_dependencies)?;
+        if existing.same_resolution(&lock_file) {
+            lock_file.generated_at = existing.generated_at.clone();
+        }
+        let stale = lock::stale_outputs(
+            &existing.locked_dependencies,
+            &lock_file.locked_dependencies,
+        );
+        Ok((lock_file, stale))
+    }
+
+    /// Explain, check by check, whether [`Workspace::lock`] would regenerate
+    /// the lock file and why, without changing anything
+    pub async fn explain_lock(&self) -> Result<LockExplanation> {
+        let config_path = self.config_path();
+        let mut explanation = LockExplanation {
+            lock_path: self.lock_path().display().to_string(),
+            ..Default::default()
+        };
+        let Ok(existing) = LockFile::load(&self.lock_path()) else {
+            return Ok(explanation);
+        };
+        explanation.exists = true;
+        explanation.generated_at = nanos_to_rfc3339(&existing.generated_at);
+        explanation.locked = existing.locked_dependencies.len();
+
+        let config_hash =
+            LockFile::compute_config_hash(&self.config_content, &self.config.dependencies);
+        let config_inputs =
+            LockFile::config_hash_inputs(&self.config_content, &self.config.dependencies);
+        explanation.config_hash_changed = !existing.is_compatible_with_config(&config_hash);
+        explanation.config_inputs_recorded = !existing.config_inputs.is_empty();
+        if explanation.config_hash_changed {
+            explanation.changed_inputs = existing
+                .changed_config_inputs(&config_inputs)
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+        }
+
+        explanation.config_modified_recorded = existing
+            .config_modified
+            .as_deref()
+            .and_then(nanos_to_rfc3339);
+        explanation.config_modified_actual = LockFile::get_config_modification_time(&config_path)
+            .ok()
+            .as_deref()
+            .and_then(nanos_to_rfc3339);
+        explanation.config_modified_since =
+            !existing.is_newer_than_config(&config_path).unwrap_or(false);
+
+        explanation.registries_checked = !lock::recently_generated(&existing);
+        if explanation.registries_checked {
+            explanation.unavailable =
+                lock::unavailable_versions(&existing, &self.clients, false).await;
+        }
+
+        explanation.reason = if explanation.config_hash_changed {
+            Some(LockUpdateReason::ConfigHashChanged)
+        } else if explanation.config_modified_since {
+            Some(LockUpdateReason::ConfigModified)
+        } else if !explanation.unavailable.is_empty() {
+            Some(LockUpdateReason::VersionsUnavailable)
+        } else {
+            None
+        }
+        .map(|reason| reason.to_string());
+        Ok(explanation)
+    }
+
+    /// Update the managed `.gitignore` block to list the locked output paths
+    fn sync_gitignore(&self, lock_file: &LockFile) -> Result<()> {
+        let descriptor_set = self.config.protobuf.descriptor_set.then(|| {
+            self.config
+                .protobuf
+                .descriptor_set_path
+                .as_deref()
+                .unwrap_or(protobuf::DEFAULT_DESCRIPTOR_SET_PATH)
+        });
+        let bundles = openapi::bundle_paths(&self.config.openapi, &lock_file.locked_dependencies);
+        let mut generated: Vec<&str> = bundles.iter().map(String::as_str).collect();
+        generated.extend(descriptor_set);
+        let changed = gitignore::sync(
+            &self.root,
+            &self.config.gitignore,
+            &lock_file.locked_dependencies,
+            &generated,
+        )?;
+        if changed {
+            self.events.emit(Event::GitignoreUpdated);
+        }
+        Ok(())
+    }
+
+    /// Report duplicate and unresolved types across the pulled GraphQL schemas
+    /// (`graphql.check`): as warnings, or as an error failing the pull
+    fn check_graphql(&self, dependencies: &[LockedDependency]) -> Result<()> {
+        let problems = graphql::check(&self.root, &self.config.graphql, dependencies)?;
+        if problems.is_empty() {
+            return Ok(());
+        }
+        if self.config.graphql.check == GraphqlCheck::Error {
+            bail!(
+                "GraphQL schemas do not stitch together:\n{}",
+                problems
+                    .iter()
+                    .map(|p| format!("  - {p}"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            );
+        }
+        for problem in problems {
+            self.events
+                .emit(Event::Warning(format!("GraphQL: {problem}")));
+        }
+        Ok(())
+    }
+
+    /// Fail unless the given pulled files are committed to git
+    /// (`gitignore.ensureCommitted` or `pull --ensure-committed`)
+    pub fn ensure_committed(&self, dependencies: &[LockedDependency]) -> Result<()> {
+        let paths: Vec<&str> = dependencies
+            .iter()
+            .map(|d| d.output_path.as_str())
+            .collect();
+        gitignore::ensure_committed(&self.root, &paths)
+    }
+
+    /// Lock, then download the locked artifacts to their output paths
+    ///
+    /// With an `identifier`, only that dependency and what it references are
+    /// resolved again (when the lock is stale) and downloaded. If any download fails or does not match its locked `sha256`
+    /// (unless [`PullOptions::no_verify`] is set), nothing is written, the
+    /// output files of the previous lock are left in place and the previous
+    /// lock file is restored.
+    pub async fn pull(
+        &self,
+        identifier: Option<&str>,
+        opts: impl Into<PullOptions>,
+    ) -> Result<PullReport> {
+        let opts = opts.into();
+        let lock_path = self.lock_path();
+        let previous_lock = fs::read(&lock_path).ok();
+        let (mut lock, stale) = match identifier {
+            Some(identifier) => {
+                let dep = find_dependency(identifier, &self.config.dependencies)?;
+                self.lock_one_deferring_removals(&dep.name, opts.lock)
+                    .await?
+            }
+            None => self.lock_deferring_removals(opts.lock).await?,
+        };
+        let result = match self.reconcile_registry_urls(&mut lock, opts.reconcile_registry_url) {
+            Ok(()) => {
+                self.download(lock.lock_file(), identifier, &stale, !opts.no_verify)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok((written, summary)) => {
+                let descriptor_set = protobuf::write_descriptor_set(
+                    &self.root,
+                    &self.config.protobuf,
+                    &lock.lock_file().locked_dependencies,
+                )?;
+                let bundles = openapi::write_bundles(
+                    &self.root,
+                    &self.config.openapi,
+                    &lock.lock_file().locked_dependencies,
+                )?;
+                self.check_graphql(&lock.lock_file().locked_dependencies)?;
+                Ok(PullReport {
+                    lock,
+                    written,
+                    summary,
+                    descriptor_set,
+                    bundles,
+                })
+            }
+            Err(e) => {
+                match previous_lock {
+                    Some(previous) => fs::write(&lock_path, previous)?,
+                    None => {
+                        let _ = fs::remove_file(&lock_path);
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Fail if a registry's URL changed since the lock was made, since its
+    /// download URLs point at the old host; with `reconcile`, rewrite them to
+    /// the configured URL instead and save the lock
+    fn reconcile_registry_urls(&self, lock: &mut LockOutcome, reconcile: bool) -> Result<()> {
+        let moved = lock
+            .lock_file()
+            .moved_registries(|name| self.clients.get(name).map(|c| c.base_url.as_str()));
+        if moved.is_empty() {
+            return Ok(());
+        }
+        if !reconcile {
+            let (registry, from, to) = &moved[0];
+            bail!(
+                "registry '{registry}' is configured at {to} but {APICURIO_LOCK} downloads from {from}; \
+                 pass --reconcile-registry-url to download from {to} and rewrite the lock's URLs \
+                 (the checksums are still verified), or point the registry back at {from}"
+            );
+        }
+        let lock_file = match lock {
+            LockOutcome::UpToDate(lock) | LockOutcome::Updated { lock, .. } => lock,
+        };
+        for (registry, from, to) in &moved {
+            let rebased = lock_file.rebase_registry_url(registry, from, to);
+            self.events.emit(Event::Warning(format!(
+                "Registry '{registry}' moved from {from} to {to}; rewrote {rebased} download URL(s) in {APICURIO_LOCK}"
+            )));
+        }
+        lock_file.save(&self.lock_path())?;
+        Ok(())
+    }
+
+    async fn download(
+        &self,
+        lock_file: &LockFile,
+        identifier: Option<&str>,
+        stale: &[lock::StaleOutput],
+        verify: bool,
+    ) -> Result<(Vec<LockedDependency>, PullSummary)> {
+        check_output_conflicts(&lock_file.locked_dependencies)?;
+        let selected: Vec<&LockedDependency> = match identifier {
+            Some(identifier) => {
+                let dep = find_dependency(identifier, &self.config.dependencies)?;
+                let closure = lock_file.dependency_closure(&dep.name);
+                if closure.is_empty() {
+                    bail!("dependency '{}' is not in {}", dep.name, APICURIO_LOCK);
+                }
+                closure
+            }
+            None => lock_file.locked_dependencies.iter().collect(),
+        };
+        let summary = pull::download_locked(
+            &self.root,
+            &self.clients,
+            &selected,
+            stale,
+            &self.config,
+            verify,
+            &*self.events,
+        )
+        .await?;
+        Ok((selected.into_iter().cloned().collect(), summary))
+    }
+
+    /// Compare the lock file with the versions available in the registries
+    pub async fn status(&self, opts: StatusOptions) -> Result<StatusReport> {
+        let lock = LockFile::load(&self.lock_path()).ok();
+        let mut report = StatusReport::default();
+
+        // New artifacts of a group show up as dependencies without a locked version
+        let config =
+            lock::expand_group_dependencies(&self.config, &self.clients, None, &*self.events)
+                .await?;
+        let fail_on = opts
+            .fail_on
+            .or(config.staleness.fail_on)
+            .unwrap_or(UpdateSeverity::Patch);
+        for dep_cfg in &config.dependencies {
+            let dep = Dependency::from_config_with_defaults(dep_cfg, &config.dependency_defaults)?;
+            let client = self
+                .client(&dep.registry)
+                .ok_or_else(|| anyhow!("registry '{}' is not configured", dep.registry))?;
+            let latest = client
+                .list_versions(&dep.group_id, &dep.artifact_id)
+                .await?
+                .into_iter()
+                .filter(|v| dep.req.matches(v))
+                .max()
+                .ok_or_else(|| anyhow!("no matching version for {}", dep.name))?;
+            let locked = lock
+                .iter()
+                .flat_map(|lf| &lf.locked_dependencies)
+                .find(|d| d.name == dep.name && !d.is_transitive)
+                .map(|d| Version::parse(&d.resolved_version))
+                .transpose()?;
+            // The publication date only matters for outdated versions
+            let age_days = match &locked {
+                Some(locked) if config.staleness.max_age_days.is_some() && *locked < latest => {
+                    client
+                        .get_version_metadata(&dep.group_id, &dep.artifact_id, locked)
+                        .await?
+                        .created_on
+                        .and_then(|created| chrono::DateTime::parse_from_rfc3339(&created).ok())
+                        .map(|created| (chrono::Utc::now() - created.to_utc()).num_days())
+                }
+                _ => None,
+            };
+            if let Some(reason) = staleness_violation(
+                &config.staleness,
+                fail_on,
+                locked.as_ref(),
+                &latest,
+                age_days,
+            ) {
+                report.violations.push(StalenessViolation {
+                    name: dep.name.clone(),
+                    reason,
+                });
+            }
+            report.dependencies.push(DependencyStatus {
+                name: dep.name,
+                registry: dep.registry,
+                locked,
+                latest: Some(latest),
+            });
+        }
+
+        let Some(lock) = lock else {
+            return Ok(report);
+        };
+
+        if opts.transitive {
+            for ld in lock.locked_dependencies.iter().filter(|d| d.is_transitive) {
+                let latest = match self.client(&ld.registry) {
+                    Some(client) => client
+                        .list_versions(&ld.group_id, &ld.artifact_id)
+                        .await?
+                        .into_iter()
+                        .max(),
+                    None => None,
+                };
+                report.transitive.push(DependencyStatus {
+                    name: ld.name.clone(),
+                    registry: ld.registry.clone(),
+                    locked: Some(Version::parse(&ld.resolved_version)?),
+                    latest,
+                });
+            }
+        }
+
+        if opts.drift {
+            let mut drift = Vec::new();
+            for ld in &lock.locked_dependencies {
+                let problem = match self.client(&ld.registry) {
+                    Some(client) => check_drift(client, ld).await?,
+                    None => Some(format!("unknown registry '{}'", ld.registry)),
+                };
+                if let Some(problem) = problem {
+                    drift.push(Drift {
+                        name: ld.name.clone(),
+                        version: ld.resolved_version.clone(),
+                        problem,
+                    });
+                }
+            }
+            report.drift = Some(drift);
+        }
+
+        if opts.content {
+            let mut changes = Vec::new();
+            for ld in &lock.locked_dependencies {
+                let problem = self
+                    .check_local_content(&lock.locked_dependencies, ld)
+                    .await?;
+                if let Some(problem) = problem {
+                    changes.push(LocalChange {
+                        name: ld.name.clone(),
+                        output_path: ld.output_path.clone(),
+                        problem,
+                    });
+                }
+            }
+            report.local_changes = Some(changes);
+        }
+        Ok(report)
+    }
+
+    /// Compare a pulled file with the registry content of its locked version
+    async fn check_local_content(
+        &self,
+        locked: &[LockedDependency],
+        ld: &LockedDependency,
+    ) -> Result<Option<String>> {
+        let path = output_path::native(&self.root, &ld.output_path);
+        let local = match fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Some("file is missing".to_string()))
+            }
+            Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+        };
+        let local = local_refs::to_registry(locked, ld, &provenance::strip_header(&local));
+        let local_sha = hex::encode(Sha256::digest(local));
+        let client = self
+            .client(&ld.registry)
+            .ok_or_else(|| anyhow!("registry '{}' is not configured", ld.registry))?;
+        // The locked sha256 is of the representation negotiated at lock time
+        let (_, registry_data) = client
+            .download_accepting(
+                &ld.group_id,
+                &ld.artifact_id,
+                &ld.resolved_version,
+                ld.content_type.as_deref(),
+            )
+            .await
+            .with_context(|| format!("downloading {}", ld.name))?;
+        let registry_sha = hex::encode(Sha256::digest(ld.file_content(&registry_data)?));
+
+        Ok(if local_sha == registry_sha {
+            None
+        } else if local_sha == ld.sha256 {
+            Some(format!(
+                "matches the lock file, but the registry content changed (sha256={registry_sha})"
+            ))
+        } else {
+            Some(format!(
+                "edited on disk (local sha256={local_sha}, registry sha256={registry_sha})"
+            ))
+        })
+    }
+
+    /// The configured publishes, or only the one called `name`
+    fn selected_publishes(&self, name: Option<&str>) -> Result<Vec<&PublishConfig>> {
+        let publishes: Vec<&PublishConfig> = self
+            .config
+            .publishes
+            .iter()
+            .filter(|p| name.is_none_or(|name| p.name == name))
+            .collect();
+        if let (Some(name), true) = (name, publishes.is_empty()) {
+            bail!("No publish configuration found with name '{}'", name);
+        }
+        Ok(publishes)
+    }
+
+    /// Run the built-in lint rules on the input files of the configured
+    /// publishes, or only the one called `name`
+    pub fn lint(&self, name: Option<&str>) -> Result<LintReport> {
+        let linter = Linter::new(&self.config.lint.rules)?;
+        let mut report = LintReport::default();
+        let mut files = Vec::new();
+        // Binary files have no text rules to check
+        for publish in self
+            .selected_publishes(name)?
+            .into_iter()
+            .filter(|p| !p.binary)
+        {
+            let input_path = self.root.join(&publish.input_path);
+            let content = fs::read_to_string(&input_path)
+                .with_context(|| format!("Failed to read file: {}", input_path.display()))?;
+            let kind = publish
+                .r#type
+                .as_ref()
+                .and_then(|_| SchemaKind::from_artifact_type(&publish.resolved_artifact_type()));
+            report
+                .files
+                .push(linter.lint(&publish.input_path, &content, kind));
+            files.push((publish.input_path.clone(), content));
+        }
+        let plugins = plugins::enabled(&self.config.plugins)?;
+        plugins::lint(&plugins, &linter, &files, &mut report)?;
+        Ok(report)
+    }
+
+    /// Publish the configured artifacts, or only the one called `name`
+    ///
+    /// Plugins enabled under `plugins:` that handle the `publish` hook are asked
+    /// first and can block an artifact.
+    pub async fn publish(
+        &self,
+        name: Option<&str>,
+        opts: PublishOptions,
+    ) -> Result<Vec<PublishedArtifact>> {
+        let plugins = plugins::enabled(&self.config.plugins)?;
+        let mut published = Vec::new();
+        for publish in self.selected_publishes(name)? {
+            for reference in &publish.references {
+                reference
+                    .validate_exact_version()
+                    .with_context(|| format!("Invalid reference in publish '{}'", publish.name))?;
+            }
+            let client = self
+                .client(&publish.registry)
+                .ok_or_else(|| anyhow!("Registry '{}' not found", publish.registry))?;
+            let publish = &self.apply_naming(publish)?;
+            let input_path = self.root.join(&publish.input_path);
+            let content = fs::read(&input_path)
+                .with_context(|| format!("Failed to read file: {}", input_path.display()))?;
+            let strategy = publish.version_strategy();
+            let sourced = sourced_version(&self.root, &publish.version_source)?;
+            let publish = &match (&sourced, strategy) {
+                (Some((version, source)), _) => {
+                    self.events.emit(Event::VersionSourced {
+                        name: publish.name.clone(),
+                        version: version.clone(),
+                        source: source.clone(),
+                    });
+                    PublishConfig {
+                        version: version.clone(),
+                        ..publish.clone()
+                    }
+                }
+                (None, Some(strategy)) => PublishConfig {
+                    version: self
+                        .next_publish_version(publish, strategy, client, &content)
+                        .await?,
+                    ..publish.clone()
+                },
+                (None, None) => publish.clone(),
+            };
+            plugins::check_publish(
+                &plugins,
+                &serde_json::json!({
+                    "name": publish.name,
+                    "registry": publish.registry,
+                    "groupId": publish.resolved_group_id(),
+                    "artifactId": publish.resolved_artifact_id(),
+                    "version": publish.version,
+                    "path": publish.input_path,
+                    "binary": publish.binary,
+                    "content": encode_publish_content(publish, &content)?,
+                }),
+            )?;
+            self.events.emit(Event::PublishStarted {
+                name: publish.name.clone(),
+                version: publish.version.clone(),
+                registry: publish.registry.clone(),
+            });
+            client
+                .publish_artifact(publish, &content, opts.draft, &*self.events)
+                .await?;
+            if (sourced.is_some() || strategy.is_some()) && opts.write_version {
+                self.write_publish_version(publish)?;
+            }
+            if opts.tag {
+                self.tag_publish(publish)?;
+            }
+            published.push(PublishedArtifact {
+                name: publish.name.clone(),
+                registry: publish.registry.clone(),
+                group_id: publish.resolved_group_id(),
+                artifact_id: publish.resolved_artifact_id(),
+                version: publish.version.clone(),
+                draft: opts.draft,
+            });
+        }
+        Ok(published)
+    }
+
+    /// Check the input files of the configured publishes, or only the one
+    /// called `name`, against the rules of their registry without publishing
+    pub async fn test_publishes(&self, name: Option<&str>) -> Result<Vec<PublishTest>> {
+        let mut tests = Vec::new();
+        for publish in self.selected_publishes(name)? {
+            let client = self
+                .client(&publish.registry)
+                .ok_or_else(|| anyhow!("Registry '{}' not found", publish.registry))?;
+            let publish = &self.apply_naming(publish)?;
+            let input_path = self.root.join(&publish.input_path);
+            let content = fs::read(&input_path)
+                .with_context(|| format!("Failed to read file: {}", input_path.display()))?;
+            let violations = client
+                .test_publish(publish, &content)
+                .await
+                .with_context(|| format!("testing publish '{}'", publish.name))?;
+            tests.push(PublishTest {
+                name: publish.name.clone(),
+                registry: publish.registry.clone(),
+                group_id: publish.resolved_group_id(),
+                artifact_id: publish.resolved_artifact_id(),
+                violations,
+            });
+        }
+        Ok(tests)
+    }
+
+    /// Compute the version to publish for a bump strategy
+    ///
+    /// When the newest registry version already has the same content, that
+    /// version is reused so unchanged files are not published again.
+    async fn next_publish_version(
+        &self,
+        publish: &PublishConfig,
+        strategy: VersionStrategy,
+        client: &RegistryClient,
+        content: &[u8],
+    ) -> Result<String> {
+        if strategy == VersionStrategy::GitDescribe {
+            return git_describe_version(&self.root);
+        }
+        let group_id = publish.resolved_group_id();
+        let artifact_id = publish.resolved_artifact_id();
+        let latest = match client.list_versions(&group_id, &artifact_id).await {
+            Ok(versions) => versions.into_iter().max(),
+            Err(e) if e.is_not_found() => None,
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(latest) = &latest {
+            let existing = client
+                .get_version_bytes(&group_id, &artifact_id, &latest.to_string())
+                .await?;
+            if content_matches(publish.binary, &existing, content) {
+                return Ok(latest.to_string());
+            }
+        }
+        Ok(next_version(strategy, latest.as_ref()).to_string())
+    }
+
+    /// Write the version chosen by a bump strategy into the publish entry of
+    /// the config file
+    fn write_publish_version(&self, publish: &PublishConfig) -> Result<()> {
+        let config_path = self.config_path();
+        let content = read_editable(&config_path)?;
+        let Some(mut entry) = list_named_entries(&content, "publishes")?
+            .into_iter()
+            .find(|e| e.get("name").and_then(|n| n.as_str()) == Some(&publish.name))
+        else {
+            bail!(
+                "publish '{}' is not defined in {} (is it in an include?)",
+                publish.name,
+                config_path.display()
+            );
+        };
+        if let Some(map) = entry.as_mapping_mut() {
+            map.insert("version".into(), publish.version.clone().into());
+        }
+        write_edited(
+            &config_path,
+            &upsert_named_entry(&content, "publishes", &entry)?,
+        )?;
+        Ok(())
+    }
+
+    /// Create the git tag `<artifactId>-v<version>` for a published version
+    fn tag_publish(&self, publish: &PublishConfig) -> Result<()> {
+        let tag = format!("{}-v{}", publish.resolved_artifact_id(), publish.version);
+        let status = std::process::Command::new("git")
+            .args(["tag", &tag])
+            .current_dir(self.git_dir())
+            .status()
+            .context("running git tag")?;
+        if !status.success() {
+            bail!("git tag {tag} failed ({status})");
+        }
+        self.events.emit(Event::Tagged { tag });
+        Ok(())
+    }
+
+    fn git_dir(&self) -> &Path {
+        if self.root.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            &self.root
+        }
+    }
+
+    /// Promote a `DRAFT` version to `ENABLED`
+    ///
+    /// `identifier` is a publish name (its configured version is used unless
+    /// `@version` is given) or `[registry/]group/artifact@version`. A version
+    /// that is already enabled is left as is.
+    pub async fn finalize(&self, identifier: &str) -> Result<PublishedArtifact> {
+        let (base, version) = match identifier.rsplit_once('@') {
+            Some((base, version)) if !version.is_empty() => (base, Some(version.to_string())),
+            _ => (identifier, None),
+        };
+        let artifact = match self.config.publishes.iter().find(|p| p.name == base) {
+            Some(publish) => PublishedArtifact {
+                name: publish.name.clone(),
+                registry: publish.registry.clone(),
+                group_id: publish.resolved_group_id(),
+                artifact_id: publish.resolved_artifact_id(),
+                version: match version {
+                    Some(version) => version,
+                    None => sourced_version(&self.root, &publish.version_source)?
+                        .map_or_else(|| publish.version.clone(), |(version, _)| version),
+                },
+                draft: false,
+            },
+            None => {
+                let target = resolve_artifact(identifier, &self.config, &self.registry_names())?;
+                let version = target
+                    .version
+                    .ok_or_else(|| anyhow!("'{identifier}' needs an @version to finalize"))?;
+                PublishedArtifact {
+                    name: format!("{}/{}", target.group_id, target.artifact_id),
+                    registry: target.registry,
+                    group_id: target.group_id,
+                    artifact_id: target.artifact_id,
+                    version,
+                    draft: false,
+                }
+            }
+        };
+
+        let client = self
+            .client(&artifact.registry)
+            .ok_or_else(|| anyhow!("Registry '{}' not found", artifact.registry))?;
+        let metadata = client
+            .get_version_metadata(&artifact.group_id, &artifact.artifact_id, &artifact.version)
+            .await
+            .with_context(|| {
+                format!(
+                    "looking up {}/{}@{}",
+                    artifact.group_id, artifact.artifact_id, artifact.version
+                )
+            })?;
+        match metadata.state.as_deref() {
+            Some("DRAFT") => {
+                client
+                    .set_version_state(
+                        &artifact.group_id,
+                        &artifact.artifact_id,
+                        &artifact.version,
+                        "ENABLED",
+                    )
+                    .await?
+            }
+            Some("ENABLED") | None => {}
+            Some(state) => bail!(
+                "{}/{}@{} is {state}, not a draft",
+                artifact.group_id,
+                artifact.artifact_id,
+                artifact.version
+            ),
+        }
+        Ok(artifact)
+    }
+
+    /// Names of the configured registries, sorted
+    pub fn registry_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.clients.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+/// What a configuration resolves to, as returned by [`resolve_all`]
+#[derive(Debug, Clone)]
+pub struct ResolutionPlan {
+    /// Lock entries of the direct dependencies and their references, in lock file order
+    pub dependencies: Vec<LockedDependency>,
+    /// Wall-clock time the resolution took
+    pub elapsed: std::time::Duration,
+}
+
+impl ResolutionPlan {
+    /// Entries of the configured dependencies
+    pub fn direct(&self) -> impl Iterator<Item = &LockedDependency> {
+        self.dependencies.iter().filter(|d| !d.is_transitive)
+    }
+
+    /// Entries reached through references
+    pub fn transitive(&self) -> impl Iterator<Item = &LockedDependency> {
+        self.dependencies.iter().filter(|d| d.is_transitive)
+    }
+}
+
+/// Resolve every dependency of `config` and what it references, using the
+/// global registries of the current user, without writing anything
+///
+/// Each level of the reference graph is resolved concurrently, and version
+/// lists, artifact metadata and references are requested once per artifact
+/// (even when `conflictPolicy: highest` repeats the resolution), which makes
+/// this the entry point for benchmarking large workspaces.
+pub async fn resolve_all(config: &RepoConfig) -> Result<ResolutionPlan> {
+    resolve_all_with_global(config, load_global_config()?).await
+}
+
+/// [`resolve_all`] with explicitly provided global registries
+pub async fn resolve_all_with_global(
+    config: &RepoConfig,
+    global: GlobalConfig,
+) -> Result<ResolutionPlan> {
+    let started = std::time::Instant::now();
+    let clients = RegistryPool::global().clients(&config.merge_registries(global)?)?;
+    let dependencies = lock::resolve_dependencies(config, &clients, None, &[], &NullSink).await?;
+    check_output_conflicts(&dependencies)?;
+    Ok(ResolutionPlan {
+        dependencies,
+        elapsed: started.elapsed(),
+    })
+}
+
+/// Result of [`Workspace::lock`]
+#[derive(Debug)]
+pub enum LockOutcome {
+    /// The existing lock file still matches the configuration
+    UpToDate(LockFile),
+    /// A new lock file was written; `reason` is `None` if there was none before
+    Updated {
+        lock: LockFile,
+        reason: Option<LockUpdateReason>,
+    },
+}
+
+impl LockOutcome {
+    /// The lock file now on disk
+    pub fn lock_file(&self) -> &LockFile {
+        match self {
+            LockOutcome::UpToDate(lock) | LockOutcome::Updated { lock, .. } => lock,
+        }
+    }
+}
+
+/// Why an existing lock file had to be regenerated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockUpdateReason {
+    /// The dependencies in the configuration changed
+    ConfigHashChanged,
+    /// The configuration file is newer than the lock file
+    ConfigModified,
+    /// Some locked versions are no longer available in their registry
+    VersionsUnavailable,
+    /// [`LockOptions::force`] asked to resolve again
+    Forced,
+    /// [`LockOptions::update`] asked to move to newer versions
+    UpdateRequested,
+}
+
+impl fmt::Display for LockUpdateReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LockUpdateReason::ConfigHashChanged => "config hash changed",
+            LockUpdateReason::ConfigModified => "config file has been modified",
+            LockUpdateReason::VersionsUnavailable => "some dependencies are no longer available",
+            LockUpdateReason::Forced => "re-resolution requested with --force",
+            LockUpdateReason::UpdateRequested => "update requested",
+        })
+    }
+}
+
+/// Result of [`Workspace::explain_lock`]
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockExplanation {
+    pub lock_path: String,
+    /// Whether the lock file exists; nothing else is set when it does not
+    pub exists: bool,
+    /// When the lock was generated (RFC 3339)
+    pub generated_at: Option<String>,
+    /// Number of locked entries
+    pub locked: usize,
+    /// The config hash differs from the lock's `configHash`
+    pub config_hash_changed: bool,
+    /// The lock records a checksum per hash input (`configInputs`), so
+    /// `changed_inputs` can name what changed
+    pub config_inputs_recorded: bool,
+    /// Hash inputs added, removed or changed since the lock, e.g.
+    /// `dependency users changed`
+    pub changed_inputs: Vec<String>,
+    /// Config modification time recorded in the lock (RFC 3339)
+    pub config_modified_recorded: Option<String>,
+    /// Current config modification time (RFC 3339)
+    pub config_modified_actual: Option<String>,
+    /// The config file was modified after the lock recorded it
+    pub config_modified_since: bool,
+    /// Whether the registries were asked for the locked versions (they are not
+    /// for locks generated less than 5 minutes ago)
+    pub registries_checked: bool,
+    /// Locked versions that cannot be downloaded again
+    pub unavailable: Vec<String>,
+    /// Why `lock` would regenerate the lock file; `None` when it is up to date
+    pub reason: Option<String>,
+}
+
+/// A nanoseconds-since-epoch timestamp of the lock file as RFC 3339
+fn nanos_to_rfc3339(nanos: &str) -> Option<String> {
+    let nanos = nanos.parse::<i64>().ok()?;
+    Some(
+        chrono::DateTime::from_timestamp_nanos(nanos)
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    )
+}
+
+/// Result of [`Workspace::pull`]
+#[derive(Debug)]
+pub struct PullReport {
+    pub lock: LockOutcome,
+    /// Lock entries whose files were pulled, written or already up to date
+    pub written: Vec<LockedDependency>,
+    /// Output files created, updated or left unchanged
+    pub summary: PullSummary,
+    /// Descriptor set compiled from the pulled protos (`protobuf.descriptorSet`)
+    pub descriptor_set: Option<DescriptorSet>,
+    /// Bundled OpenAPI documents written (`openapi.bundle`)
+    pub bundles: Vec<String>,
+}
+
+/// Output paths of a pull, by what happened to their files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PullSummary {
+    /// Files that did not exist
+    pub created: Vec<String>,
+    /// Files whose content changed
+    pub updated: Vec<String>,
+    /// Files already holding the locked content, left untouched
+    pub unchanged: Vec<String>,
+}
+
+/// Version status of one dependency
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyStatus {
+    pub name: String,
+    pub registry: String,
+    /// Version in the lock file, `None` if it was not locked yet
+    pub locked: Option<Version>,
+    /// Newest registry version (within the version range for direct dependencies),
+    /// `None` if the registry is not configured or has no versions
+    pub latest: Option<Version>,
+}
+
+impl DependencyStatus {
+    /// Not locked yet, or a newer version is available
+    pub fn is_outdated(&self) -> bool {
+        match (&self.locked, &self.latest) {
+            (None, _) => true,
+            (Some(locked), Some(latest)) => locked < latest,
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// A direct dependency further behind than the staleness policy allows
+#[derive(Debug, Clone, PartialEq)]
+pub struct StalenessViolation {
+    pub name: String,
+    pub reason: String,
+}
+
+/// Why a direct dependency fails the staleness policy, if it does
+///
+/// `age_days` is how long ago the locked version was published, when known.
+fn staleness_violation(
+    policy: &StalenessPolicy,
+    fail_on: UpdateSeverity,
+    locked: Option<&Version>,
+    latest: &Version,
+    age_days: Option<i64>,
+) -> Option<String> {
+    let Some(locked) = locked else {
+        return Some("not locked yet".to_string());
+    };
+    let severity = UpdateSeverity::between(locked, latest)?;
+    if let (Some(max), Some(age)) = (policy.max_age_days, age_days) {
+        if age > max as i64 {
+            return Some(format!(
+                "locked version was published {age} days ago (max {max})"
+            ));
+        }
+    }
+    let minors_behind = latest.minor.saturating_sub(locked.minor);
+    match severity {
+        severity if severity < fail_on => None,
+        UpdateSeverity::Minor if minors_behind <= policy.allowed_minors_behind => None,
+        UpdateSeverity::Minor => Some(format!(
+            "{minors_behind} minor versions behind (allowed {})",
+            policy.allowed_minors_behind
+        )),
+        severity => Some(format!("{severity} update available")),
+    }
+}
+
+/// A locked version that no longer matches the registry
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drift {
+    pub name: String,
+    pub version: String,
+    pub problem: String,
+}
+
+/// A pulled file whose content differs from its locked registry version
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalChange {
+    pub name: String,
+    pub output_path: String,
+    pub problem: String,
+}
+
+/// Result of [`Workspace::status`]
+#[derive(Debug, Default)]
+pub struct StatusReport {
+    /// Direct dependencies from the configuration
+    pub dependencies: Vec<DependencyStatus>,
+    /// Transitive lock entries, only filled with [`StatusOptions::transitive`]
+    pub transitive: Vec<DependencyStatus>,
+    /// Drift found, only checked with [`StatusOptions::drift`]
+    pub drift: Option<Vec<Drift>>,
+    /// Pulled files differing from the registry, only checked with
+    /// [`StatusOptions::content`]
+    pub local_changes: Option<Vec<LocalChange>>,
+    /// Direct dependencies further behind than the staleness policy allows
+    pub violations: Vec<StalenessViolation>,
+}
+
+impl StatusReport {
+    /// Whether `status` should fail: a direct dependency is further behind than
+    /// the staleness policy allows (by default: outdated), or a lock entry
+    /// drifted or changed on disk
+    ///
+    /// Transitive versions are pinned by their parents' references, so a newer
+    /// one is informational only.
+    pub fn is_outdated(&self) -> bool {
+        !self.violations.is_empty()
+            || self.drift.as_ref().is_some_and(|d| !d.is_empty())
+            || self.local_changes.as_ref().is_some_and(|c| !c.is_empty())
+    }
+}
+
+/// An artifact version published by [`Workspace::publish`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublishedArtifact {
+    pub name: String,
+    pub registry: String,
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: String,
+    /// Created in the `DRAFT` state
+    pub draft: bool,
+}
+
+/// The registry's verdict on a publish, from [`Workspace::test_publishes`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublishTest {
+    pub name: String,
+    pub registry: String,
+    pub group_id: String,
+    pub artifact_id: String,
+    /// Rules the content violates; empty when the registry would accept it
+    pub violations: Vec<RuleViolation>,
+}
+
+/// The version after `latest` for a registry bump strategy (`0.1.0` if there
+/// is no version yet)
+fn next_version(strategy: VersionStrategy, latest: Option<&Version>) -> Version {
+    let Some(latest) = latest else {
+        return Version::new(0, 1, 0);
+    };
+    match strategy {
+        VersionStrategy::AutoMajor => Version::new(latest.major + 1, 0, 0),
+        VersionStrategy::AutoMinor => Version::new(latest.major, latest.minor + 1, 0),
+        VersionStrategy::AutoPatch | VersionStrategy::GitDescribe => {
+            Version::new(latest.major, latest.minor, latest.patch + 1)
+        }
+    }
+}
+
+/// Version from `git describe --tags`, e.g. `v1.2.3-4-gabc1234` → `1.2.3-4-gabc1234`
+fn git_describe_version(root: &Path) -> Result<String> {
+    describe_tags(root, &[])
+}
+
+/// Version of the newest tag reachable from HEAD, e.g. `v1.2.3` → `1.2.3`
+fn git_tag_version(root: &Path) -> Result<String> {
+    describe_tags(root, &["--abbrev=0"])
+}
+
+fn describe_tags(root: &Path, extra: &[&str]) -> Result<String> {
+    let dir = if root.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        root
+    };
+    let output = std::process::Command::new("git")
+        .args(["describe", "--tags"])
+        .args(extra)
+        .current_dir(dir)
+        .output()
+        .context("running git describe")?;
+    if !output.status.success() {
+        bail!(
+            "git describe --tags failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let described = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let version = described.strip_prefix('v').unwrap_or(&described);
+    Version::parse(version)
+        .with_context(|| format!("git describe returned '{described}', which is not semver"))?;
+    Ok(version.to_string())
+}
+
+/// The version named by a publish's version source, with the file (or `git tag`)
+/// it came from; `None` if no source is set
+fn sourced_version(root: &Path, source: &VersionSource) -> Result<Option<(String, String)>> {
+    let read = |file: &str| {
+        let path = root.join(file);
+        fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read version from {}", path.display()))
+    };
+    let (version, from) = if let Some(file) = &source.from_file {
+        (read(file)?.trim().to_string(), file)
+    } else if let Some(manifest) = &source.from_cargo {
+        let version = cargo_package_version(&read(manifest)?)
+            .with_context(|| format!("Failed to read the package version of {manifest}"))?;
+        (version, manifest)
+    } else if let Some(pom) = &source.from_maven {
+        let version = maven_project_version(&read(pom)?)
+            .ok_or_else(|| anyhow!("{pom} has no project <version>"))?;
+        if version.contains("${") {
+            bail!("{pom} sets the version through a property ({version}); use fromFile or fromGitTag instead");
+        }
+        (version, pom)
+    } else if source.from_git_tag {
+        return Ok(Some((git_tag_version(root)?, "git tag".to_string())));
+    } else {
+        return Ok(None);
+    };
+    let version = version.strip_prefix('v').unwrap_or(&version);
+    Version::parse(version)
+        .with_context(|| format!("{from} holds '{version}', which is not a semver version"))?;
+    Ok(Some((version.to_string(), from.clone())))
+}
+
+/// `package.version` of a `Cargo.toml`, or `workspace.package.version` for a
+/// workspace root or a package inheriting it
+fn cargo_package_version(manifest: &str) -> Result<String> {
+    let manifest: toml::Value = manifest.parse()?;
+    let package = manifest.get("package").and_then(|p| p.get("version"));
+    let workspace = manifest
+        .get("workspace")
+        .and_then(|w| w.get("package"))
+        .and_then(|p| p.get("version"));
+    package
+        .and_then(toml::Value::as_str)
+        .or_else(|| workspace.and_then(toml::Value::as_str))
+        .map(str::to_string)
+        .ok_or_else(|| match package {
+            Some(_) => anyhow!("the version is inherited from a workspace; point fromCargo at the workspace Cargo.toml"),
+            None => anyhow!("no package.version"),
+        })
+}
+
+/// The `<version>` directly under `<project>` of a `pom.xml`, not the one of
+/// `<parent>` or of a dependency
+fn maven_project_version(pom: &str) -> Option<String> {
+    let mut depth = 0usize;
+    let mut rest = pom;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.split_once("-->")?.1;
+            continue;
+        }
+        let end = rest.find('>')?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with(['?', '!']) || tag.ends_with('/') {
+            continue;
+        }
+        if tag.starts_with('/') {
+            depth = depth.saturating_sub(1);
+            continue;
+        }
+        if depth == 1 && tag.split_whitespace().next() == Some("version") {
+            let (text, _) = rest.split_once("</version>")?;
+            return Some(text.trim().to_string());
+        }
+        depth += 1;
+    }
+    None
+}
+
+/// Compare a locked entry with the registry, describing any drift found
+async fn check_drift(client: &RegistryClient, ld: &LockedDependency) -> Result<Option<String>> {
+    let version = Version::parse(&ld.resolved_version)?;
+    let metadata = match client
+        .get_version_metadata(&ld.group_id, &ld.artifact_id, &version)
+        .await
+    {
+        Ok(metadata) => metadata,
+        Err(e) if e.is_not_found() => {
+            return Ok(Some("version was deleted from the registry".to_string()))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    match metadata.state.as_deref() {
+        Some("DISABLED") => return Ok(Some("version is disabled".to_string())),
+        Some("DEPRECATED") => return Ok(Some("version is deprecated".to_string())),
+        _ => {}
+    }
+
+    let (_, data) = client
+        .download_accepting(
+            &ld.group_id,
+            &ld.artifact_id,
+            &ld.resolved_version,
+            ld.content_type.as_deref(),
+        )
+        .await?;
+    let actual = hex::encode(Sha256::digest(ld.file_content(&data)?));
+    if actual != ld.sha256 {
+        return Ok(Some(format!(
+            "content changed (locked sha256={}, registry sha256={})",
+            ld.sha256, actual
+        )));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_lock_in_explicit_directory() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join(APICURIO_CONFIG), "dependencies: []\n").unwrap();
+
+        let workspace = Workspace::open_with_global(temp.path(), GlobalConfig::default()).unwrap();
+        let outcome = workspace.lock(LockOptions::default()).await.unwrap();
+        assert!(matches!(outcome, LockOutcome::Updated { reason: None, .. }));
+        assert!(temp.path().join(APICURIO_LOCK).exists());
+
+        let outcome = workspace.lock(LockOptions::default()).await.unwrap();
+        assert!(matches!(outcome, LockOutcome::UpToDate(_)));
+
+        let report = workspace.status(StatusOptions::default()).await.unwrap();
+        assert!(report.dependencies.is_empty());
+        assert!(!report.is_outdated());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_all_follows_references() {
+        let server = crate::dev_server::DevServer::start("127.0.0.1:0")
+            .await
+            .unwrap();
+        server.add_version("com.example", "common", "PROTOBUF", "1.0.0", "common v1");
+        let client = RegistryClient::new(&server.registry_config("dev")).unwrap();
+        let publish: PublishConfig = serde_yaml::from_str(
+            "name: users\ninputPath: users.proto\nversion: 1.2.0\nregistry: dev\n\
+             groupId: com.example\ntype: protobuf\n\
+             references:\n  - name: com.example/common\n    version: 1.0.0\n",
+        )
+        .unwrap();
+        client
+            .publish_artifact(&publish, b"users v1.2", false, &NullSink)
+            .await
+            .unwrap();
+
+        let config: RepoConfig = serde_yaml::from_str(&format!(
+            "registries:\n  - name: dev\n    url: {}\n\
+             referenceResolution:\n  enabled: true\n\
+             dependencies:\n  - name: com.example/users\n    version: ^1.0.0\n    \
+             registry: dev\n    outputPath: protos/users.proto\n",
+            server.url()
+        ))
+        .unwrap();
+        let plan = resolve_all_with_global(&config, GlobalConfig::default())
+            .await
+            .unwrap();
+        let direct: Vec<_> = plan.direct().collect();
+        assert_eq!(direct.len(), 1);
+        assert_eq!(direct[0].resolved_version, "1.2.0");
+        assert_eq!(direct[0].references, ["com.example/common"]);
+        let transitive: Vec<_> = plan.transitive().collect();
+        assert_eq!(transitive.len(), 1);
+        assert_eq!(transitive[0].artifact_id, "common");
+    }
+
+    #[tokio::test]
+    async fn test_lock_and_pull_report_events() {
+        use crate::events::CollectingSink;
+        let server = crate::dev_server::DevServer::start("127.0.0.1:0")
+            .await
+            .unwrap();
+        server.add_version("com.example", "users", "PROTOBUF", "1.0.0", "users v1");
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join(APICURIO_CONFIG),
+            "dependencies:\n  - name: com.example/users\n    version: ^1.0.0\n    \
+             registry: dev\n    outputPath: protos/users.proto\n",
+        )
+        .unwrap();
+        let global = GlobalConfig {
+            registries: vec![server.registry_config("dev")],
+            ..Default::default()
+        };
+        let events = Arc::new(CollectingSink::default());
+        let workspace = Workspace::open_with_global(temp.path(), global)
+            .unwrap()
+            .with_events(events.clone());
+
+        workspace.pull(None, LockOptions::default()).await.unwrap();
+        assert_eq!(
+            events.events(),
+            [
+                Event::Resolved {
+                    name: "com.example/users".to_string(),
+                    registry: "dev".to_string(),
+                    version: "1.0.0".to_string(),
+                    transitive: false,
+                    bytes: Some(8),
+                },
+                Event::DownloadStarted {
+                    name: "com.example/users".to_string(),
+                    version: "1.0.0".to_string(),
+                },
+                Event::DownloadFinished {
+                    name: "com.example/users".to_string(),
+                    version: "1.0.0".to_string(),
+                    output_path: "protos/users.proto".to_string(),
+                    bytes: 8,
+                },
+            ]
+        );
+
+        workspace.pull(None, LockOptions::default()).await.unwrap();
+        assert_eq!(
+            events.events()[3..],
+            [Event::UpToDate {
+                name: "com.example/users".to_string(),
+                output_path: "protos/users.proto".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_config_file_roots_the_workspace_in_its_directory() {
+        let server = crate::dev_server::DevServer::start("127.0.0.1:0")
+            .await
+            .unwrap();
+        server.add_version("com.example", "users", "PROTOBUF", "1.0.0", "users v1");
+        let temp = TempDir::new().unwrap();
+        let service = temp.path().join("services/users");
+        fs::create_dir_all(&service).unwrap();
+        fs::write(
+            service.join(APICURIO_CONFIG),
+            format!(
+                "registries:\n  - name: dev\n    url: {}\n\
+                 gitignore:\n  manage: true\n\
+                 dependencies:\n  - name: com.example/users\n    version: ^1.0.0\n    \
+                 registry: dev\n    outputPath: protos/users.proto\n",
+                server.url()
+            ),
+        )
+        .unwrap();
+
+        let workspace = Workspace::open_config_file_with_global(
+            service.join(APICURIO_CONFIG),
+            GlobalConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(workspace.root(), service);
+        workspace.pull(None, LockOptions::default()).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(service.join("protos/users.proto")).unwrap(),
+            "users v1"
+        );
+        assert!(service.join(APICURIO_LOCK).exists());
+        assert!(service.join(".gitignore").exists());
+        assert!(!temp.path().join("protos").exists());
+    }
+
+    #[tokio::test]
+    async fn test_pull_one_keeps_other_entries() {
+        use crate::events::CollectingSink;
+        let server = crate::dev_server::DevServer::start("127.0.0.1:0")
+            .await
+            .unwrap();
+        server.add_version("com.example", "users", "PROTOBUF", "1.0.0", "users v1");
+        server.add_version("com.example", "orders", "PROTOBUF", "1.0.0", "orders v1");
+        let temp = TempDir::new().unwrap();
+        let config = |users: &str| {
+            format!(
+                "registries:\n  - name: dev\n    url: {}\n\
+                 dependencies:\n  - name: users\n    groupId: com.example\n    \
+                 artifactId: users\n    version: {users}\n    registry: dev\n    \
+                 outputPath: protos/users.proto\n  - name: orders\n    \
+                 groupId: com.example\n    artifactId: orders\n    version: ^1.0.0\n    \
+                 registry: dev\n    outputPath: protos/orders.proto\n",
+                server.url()
+            )
+        };
+        fs::write(temp.path().join(APICURIO_CONFIG), config("~1.0.0")).unwrap();
+        Workspace::open_with_global(temp.path(), GlobalConfig::default())
+            .unwrap()
+            .pull(None, LockOptions::default())
+            .await
+            .unwrap();
+        let before = LockFile::load(&temp.path().join(APICURIO_LOCK)).unwrap();
+
+        server.add_version("com.example", "users", "PROTOBUF", "1.1.0", "users v1.1");
+        server.add_version("com.example", "orders", "PROTOBUF", "1.1.0", "orders v1.1");
+        fs::write(temp.path().join("protos/orders.proto"), "edited").unwrap();
+        fs::write(temp.path().join(APICURIO_CONFIG), config("^1.0.0")).unwrap();
+        let events = Arc::new(CollectingSink::default());
+        let workspace = Workspace::open_with_global(temp.path(), GlobalConfig::default())
+            .unwrap()
+            .with_events(events.clone());
+        workspace
+            .pull(Some("users"), LockOptions::default())
+            .await
+            .unwrap();
+
+        let after = LockFile::load(&temp.path().join(APICURIO_LOCK)).unwrap();
+        let entry = |lock: &LockFile, name: &str| {
+            lock.locked_dependencies
+                .iter()
+                .find(|d| d.name == name)
+                .cloned()
+                .unwrap()
+        };
+        assert_eq!(entry(&after, "users").resolved_version, "1.1.0");
+        assert_eq!(entry(&after, "orders"), entry(&before, "orders"));
+        assert!(!events
+            .events()
+            .iter()
+            .any(|e| matches!(e, Event::Resolved { name, .. } if name == "orders")));
+        assert_eq!(
+            fs::read_to_string(temp.path().join("protos/users.proto")).unwrap(),
+            "users v1.1"
+        );
+        assert_eq!(
+            fs::read_to_string(temp.path().join("protos/orders.proto")).unwrap(),
+            "edited"
+        );
+        // Only the range of users changed, so the lock matches the config again
+        let config_content = fs::read_to_string(temp.path().join(APICURIO_CONFIG)).unwrap();
+        let hash = LockFile::compute_config_hash(&config_content, &workspace.config.dependencies);
+        assert!(after.is_compatible_with_config(&hash));
+        assert!(!after.config_inputs.is_empty());
+    }
+
+    #[test]
+    fn test_staleness_policy() {
+        let v = |s| Version::parse(s).unwrap();
+        let check = |policy: &StalenessPolicy, fail_on, locked, latest, age| {
+            staleness_violation(policy, fail_on, Some(&v(locked)), &v(latest), age)
+        };
+        let default = StalenessPolicy::default();
+        let patch = UpdateSeverity::Patch;
+        assert!(check(&default, patch, "1.2.0", "1.2.0", None).is_none());
+        assert!(check(&default, patch, "1.2.0", "1.2.1", None).is_some());
+        assert!(staleness_violation(&default, patch, None, &v("1.0.0"), None).is_some());
+
+        // Fail only beyond one minor version behind, or after 90 days
+        let policy: StalenessPolicy =
+            serde_yaml::from_str("failOn: minor\nallowedMinorsBehind: 1\nmaxAgeDays: 90\n")
+                .unwrap();
+        let minor = policy.fail_on.unwrap();
+        assert!(check(&policy, minor, "1.2.0", "1.2.7", Some(10)).is_none());
+        assert!(check(&policy, minor, "1.2.0", "1.3.0", Some(10)).is_none());
+        assert_eq!(
+            check(&policy, minor, "1.2.0", "1.4.0", Some(10)).unwrap(),
+            "2 minor versions behind (allowed 1)"
+        );
+        assert_eq!(
+            check(&policy, minor, "1.2.0", "2.0.0", None).unwrap(),
+            "major update available"
+        );
+        assert!(check(&policy, minor, "1.2.0", "1.2.1", Some(91))
+            .unwrap()
+            .contains("91 days ago"));
+        assert!(check(&policy, UpdateSeverity::Major, "1.2.0", "1.9.0", Some(10)).is_none());
+    }
+
+    #[test]
+    fn test_next_version() {
+        let latest = Version::parse("1.4.2").unwrap();
+        assert_eq!(
+            next_version(VersionStrategy::AutoPatch, Some(&latest)).to_string(),
+            "1.4.3"
+        );
+        assert_eq!(
+            next_version(VersionStrategy::AutoMinor, Some(&latest)).to_string(),
+            "1.5.0"
+        );
+        assert_eq!(
+            next_version(VersionStrategy::AutoMajor, Some(&latest)).to_string(),
+            "2.0.0"
+        );
+        assert_eq!(
+            next_version(VersionStrategy::AutoMinor, None).to_string(),
+            "0.1.0"
+        );
+    }
+
+    #[test]
+    fn test_sourced_version() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(root.join("VERSION"), "v2.3.4\n").unwrap();
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"users\"\nversion = \"1.5.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("pom.xml"),
+            "<?xml version=\"1.0\"?>\n<project>\n  <parent><version>9.0.0</version></parent>\n  \
+             <!-- <version>0.0.1</version> -->\n  <artifactId>users</artifactId>\n  \
+             <version>3.1.0-SNAPSHOT</version>\n  <dependencies><dependency>\
+             <version>4.0.0</version></dependency></dependencies>\n</project>\n",
+        )
+        .unwrap();
+        let source = |yaml: &str| serde_yaml::from_str::<VersionSource>(yaml).unwrap();
+
+        assert_eq!(
+            sourced_version(root, &VersionSource::default()).unwrap(),
+            None
+        );
+        assert_eq!(
+            sourced_version(root, &source("fromFile: VERSION")).unwrap(),
+            Some(("2.3.4".to_string(), "VERSION".to_string()))
+        );
+        assert_eq!(
+            sourced_version(root, &source("fromCargo: Cargo.toml"))
+                .unwrap()
+                .unwrap()
+                .0,
+            "1.5.0"
+        );
+        assert_eq!(
+            sourced_version(root, &source("fromMaven: pom.xml"))
+                .unwrap()
+                .unwrap()
+                .0,
+            "3.1.0-SNAPSHOT"
+        );
+
+        fs::write(root.join("VERSION"), "next\n").unwrap();
+        let err = sourced_version(root, &source("fromFile: VERSION")).unwrap_err();
+        assert!(err.to_string().contains("not a semver version"), "{err}");
+        fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"users\"\nversion.workspace = true\n",
+        )
+        .unwrap();
+        let err = sourced_version(root, &source("fromCargo: Cargo.toml")).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("inherited from a workspace"),
+            "{err:#}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_content_reports_missing_file() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join(APICURIO_CONFIG), "dependencies: []\n").unwrap();
+        let workspace = Workspace::open_with_global(temp.path(), GlobalConfig::default()).unwrap();
+
+        let ld = LockedDependency {
+            name: "users".to_string(),
+            registry: "main".to_string(),
+            resolved_version: "1.0.0".to_string(),
+            download_url: String::new(),
+            registry_url: None,
+            sha256: String::new(),
+            output_path: "protos/users.proto".to_string(),
+            group_id: "com.example".to_string(),
+            artifact_id: "users".to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
+        };
+        assert_eq!(
+            workspace
+                .check_local_content(&[], &ld)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("file is missing")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_check_drift_negotiates_the_locked_content_type() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                // The registry serves YAML only when asked for it
+                let body = if !request.contains("/content ") {
+                    r#"{"version":"1.0.0","state":"ENABLED"}"#
+                } else if request.contains("accept: application/x-yaml") {
+                    "openapi: 3.0.0"
+                } else {
+                    r#"{"openapi":"3.0.0"}"#
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        let client = RegistryClient::new(&RegistryConfig {
+            name: "mock".to_string(),
+            url: format!("http://{addr}"),
+            ..Default::default()
+        })
+        .unwrap();
+        let ld = LockedDependency {
+            name: "users".to_string(),
+            registry: "mock".to_string(),
+            resolved_version: "1.0.0".to_string(),
+            download_url: String::new(),
+            registry_url: None,
+            sha256: hex::encode(Sha256::digest(b"openapi: 3.0.0")),
+            output_path: "openapi/users.yaml".to_string(),
+            group_id: "com.example".to_string(),
+            artifact_id: "users".to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: Some("application/x-yaml".to_string()),
+            member_of: None,
+        };
+        assert_eq!(check_drift(&client, &ld).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_open_requires_config() {
+        let temp = TempDir::new().unwrap();
+        let err = Workspace::open_with_global(temp.path(), GlobalConfig::default())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Run 'apicurio init' first"));
+    }
+}
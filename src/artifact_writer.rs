@@ -0,0 +1,464 @@
+//! Where locked artifacts are written, and writing them there
+//!
+//! `lock`, `update` and `pull` all go through this module, so the path recorded
+//! in a lock entry is exactly the file that gets written: [`explicit_path`],
+//! [`pattern_path`] and [`reference_path`] compute the `outputPath` of an entry,
+//! and [`ArtifactWriter`]
+//! writes its content to that path below the project directory, creating parent
+//! directories, pointing references at local files (see [`crate::local_refs`]),
+//! adding the provenance header and applying `writeProtection`.
+
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    config::{ProvenanceHeaderConfig, RepoConfig},
+    constants::APICURIO_STAGING_DIR,
+    local_refs,
+    lockfile::{resolve_output_path, LockedDependency},
+    output_path::{self, PatternMetadata},
+    provenance,
+};
+
+/// Lock entry path of a dependency with an explicit `outputPath`
+pub fn explicit_path(path: &str) -> String {
+    output_path::portable(path)
+}
+
+/// Lock entry path of a dependency placed by `dependencyDefaults.outputPatterns`
+pub fn pattern_path(
+    repo_cfg: &RepoConfig,
+    group_id: &str,
+    artifact_id: &str,
+    version: &str,
+    metadata: &PatternMetadata,
+) -> Result<String> {
+    let pattern = repo_cfg
+        .dependency_defaults
+        .output_patterns
+        .resolve(&metadata.artifact_type, None);
+    let path =
+        output_path::generate_output_path(&pattern, group_id, artifact_id, version, metadata)?;
+    Ok(output_path::portable(&path))
+}
+
+/// Lock entry path of an artifact pulled in through references, placed by
+/// `referenceResolution`; `None` when its `outputOverrides` map it to null
+pub fn reference_path(
+    repo_cfg: &RepoConfig,
+    group_id: &str,
+    artifact_id: &str,
+    version: &str,
+    metadata: &PatternMetadata,
+) -> Result<Option<String>> {
+    let pattern = repo_cfg.reference_resolution.output_patterns.resolve(
+        &metadata.artifact_type,
+        Some(&repo_cfg.dependency_defaults.output_patterns),
+    );
+    let path = resolve_output_path(
+        &pattern,
+        &repo_cfg.reference_resolution.output_overrides,
+        group_id,
+        artifact_id,
+        version,
+        metadata,
+    )?;
+    Ok(path.map(|path| output_path::portable(&path)))
+}
+
+/// What writing a lock entry does to its file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Created,
+    Updated,
+    /// The file already holds the content and is left untouched
+    Unchanged,
+}
+
+/// Writes the content of lock entries to their output paths, all or nothing
+///
+/// Nothing is written or deleted before [`commit`](Self::commit), and a failed
+/// commit restores the previous files, including those staged for deletion.
+pub struct ArtifactWriter {
+    root: PathBuf,
+    header: ProvenanceHeaderConfig,
+    write_protection: bool,
+    /// Reference syntaxes pointed at the pulled files
+    rewritten: Vec<local_refs::RefSyntax>,
+    /// Lock entries the written ones may reference
+    dependencies: Vec<LockedDependency>,
+    staged: StagedOutputs,
+}
+
+impl ArtifactWriter {
+    /// A writer for the project in `root`, honouring its `provenanceHeader`
+    /// and `writeProtection` settings
+    pub fn new(root: &Path, repo_cfg: &RepoConfig) -> Result<Self> {
+        Ok(ArtifactWriter {
+            root: root.to_path_buf(),
+            header: repo_cfg.provenance_header.clone(),
+            write_protection: repo_cfg.write_protection,
+            rewritten: local_refs::rewritten_syntaxes(repo_cfg),
+            dependencies: Vec::new(),
+            staged: StagedOutputs::new(root)?.read_only(repo_cfg.write_protection),
+        })
+    }
+
+    /// Point references of the written files at these lock entries
+    pub fn with_dependencies<'a>(
+        mut self,
+        dependencies: impl IntoIterator<Item = &'a LockedDependency>,
+    ) -> Self {
+        self.dependencies = dependencies.into_iter().cloned().collect();
+        self
+    }
+
+    /// The registry content of a written file: provenance header removed and
+    /// references restored to their registry names
+    pub fn registry_content(&self, dependency: &LockedDependency, on_disk: &[u8]) -> Vec<u8> {
+        local_refs::to_registry(
+            &self.dependencies,
+            dependency,
+            &provenance::strip_header(on_disk),
+        )
+    }
+
+    /// The file a lock entry is written to
+    pub fn target(&self, dependency: &LockedDependency) -> PathBuf {
+        output_path::native(&self.root, &dependency.output_path)
+    }
+
+    /// Stage the locked content `data` of `dependency`, skipping files that
+    /// already hold it
+    pub fn stage(&mut self, dependency: &LockedDependency, data: &[u8]) -> Result<Change> {
+        let target = self.target(dependency);
+        let rewrites = local_refs::syntax_for(&dependency.output_path)
+            .is_some_and(|syntax| self.rewritten.contains(&syntax));
+        let content = if rewrites {
+            local_refs::to_local(&self.dependencies, dependency, data)
+        } else {
+            data.to_vec()
+        };
+        let content = provenance::apply_header(&self.header, dependency, &content);
+        let change = match fs::read(&target) {
+            Ok(existing) if existing == content => {
+                if self.write_protection {
+                    set_read_only(&target, true)?;
+                }
+                return Ok(Change::Unchanged);
+            }
+            Ok(_) => Change::Updated,
+            Err(_) => Change::Created,
+        };
+        self.staged.stage(&target, &content)?;
+        Ok(change)
+    }
+
+    /// Stage the deletion of the output file at `path` (relative to the project
+    /// directory); `false` if there is no such file
+    pub fn remove(&mut self, path: &str) -> bool {
+        let target = output_path::native(&self.root, path);
+        if !target.exists() {
+            return false;
+        }
+        self.staged.remove(&target);
+        true
+    }
+
+    /// Delete the files staged for deletion and move every staged file into place
+    pub fn commit(self) -> Result<()> {
+        self.staged.commit()
+    }
+}
+
+/// Output files written to a staging directory first and moved into place together
+///
+/// Files are only moved once everything has been staged. If moving one fails, the
+/// files already moved are reverted to their previous content and the files
+/// staged for deletion put back, so the workspace never mixes old and new
+/// outputs. The staging directory lives in the project directory so the final
+/// moves are cheap renames on the same filesystem.
+struct StagedOutputs {
+    dir: PathBuf,
+    files: Vec<(PathBuf, PathBuf)>,
+    /// Files to delete on commit
+    removals: Vec<PathBuf>,
+    read_only: bool,
+}
+
+impl StagedOutputs {
+    fn new(root: &Path) -> Result<Self> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let dir = root.join(format!(
+            "{APICURIO_STAGING_DIR}-{}-{}",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("creating staging directory {}", dir.display()))?;
+        Ok(StagedOutputs {
+            dir,
+            files: Vec::new(),
+            removals: Vec::new(),
+            read_only: false,
+        })
+    }
+
+    /// Mark committed files read-only (`writeProtection`)
+    fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Write `data` to the staging area, to be moved to `target` on commit
+    fn stage(&mut self, target: &Path, data: &[u8]) -> Result<()> {
+        let staged = self.dir.join(self.files.len().to_string());
+        fs::write(&staged, data).with_context(|| format!("staging {}", target.display()))?;
+        self.files.push((staged, target.to_path_buf()));
+        Ok(())
+    }
+
+    /// Delete `target` on commit
+    fn remove(&mut self, target: &Path) {
+        self.removals.push(target.to_path_buf());
+    }
+
+    /// Delete the files staged for deletion and move every staged file into
+    /// place, rolling back on failure
+    fn commit(mut self) -> Result<()> {
+        let files = std::mem::take(&mut self.files);
+        let removals = std::mem::take(&mut self.removals);
+        let backup_dir = self.dir.join("backup");
+        fs::create_dir_all(&backup_dir)?;
+        let mut moved: Vec<(PathBuf, Option<PathBuf>)> = Vec::new();
+
+        // Deleted files are kept in the backup directory until everything is in place
+        for (index, target) in removals.iter().enumerate() {
+            let backup = backup_dir.join(format!("removed-{index}"));
+            let result = set_read_only(target, false).and_then(|()| move_file(target, &backup));
+            if let Err(e) = result {
+                rollback(&moved);
+                return Err(e.context(format!(
+                    "deleting {}; previous files were restored",
+                    target.display()
+                )));
+            }
+            moved.push((target.clone(), Some(backup)));
+        }
+
+        for (index, (staged, target)) in files.iter().enumerate() {
+            let result = (|| -> Result<Option<PathBuf>> {
+                if let Some(parent) = target.parent() {
+                    if !parent.as_os_str().is_empty() {
+                        fs::create_dir_all(parent)?;
+                    }
+                }
+                let backup = if target.exists() {
+                    // A previous pull may have write-protected the file
+                    set_read_only(target, false)?;
+                    let backup = backup_dir.join(index.to_string());
+                    move_file(target, &backup)?;
+                    Some(backup)
+                } else {
+                    None
+                };
+                if let Err(e) = move_file(staged, target) {
+                    if let Some(backup) = &backup {
+                        let _ = move_file(backup, target);
+                    }
+                    return Err(e);
+                }
+                if self.read_only {
+                    set_read_only(target, true)?;
+                }
+                Ok(backup)
+            })();
+
+            match result {
+                Ok(backup) => moved.push((target.clone(), backup)),
+                Err(e) => {
+                    rollback(&moved);
+                    return Err(e.context(format!(
+                        "writing {}; previous files were restored",
+                        target.display()
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Put back the files replaced or deleted by a commit, latest first
+fn rollback(moved: &[(PathBuf, Option<PathBuf>)]) {
+    for (target, backup) in moved.iter().rev() {
+        let _ = remove_output_file(target);
+        if let Some(backup) = backup {
+            let _ = move_file(backup, target);
+        }
+    }
+}
+
+impl Drop for StagedOutputs {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Set or clear the read-only flag of an output file
+pub fn set_read_only(path: &Path, read_only: bool) -> Result<()> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    if permissions.readonly() != read_only {
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(read_only);
+        fs::set_permissions(path, permissions)
+            .with_context(|| format!("changing permissions of {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Delete an output file, clearing a write protection first
+pub fn remove_output_file(path: &Path) -> Result<()> {
+    set_read_only(path, false)?;
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Rename a file, falling back to copy + delete across filesystems
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_err() {
+        fs::copy(from, to)
+            .with_context(|| format!("moving {} to {}", from.display(), to.display()))?;
+        fs::remove_file(from)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_writer_records_and_writes_the_same_path() {
+        let temp = TempDir::new().unwrap();
+        let repo_cfg: RepoConfig = serde_yaml::from_str(
+            "dependencyDefaults:\n  outputPatterns:\n    protobuf: \"protos/{groupId.path}/{artifactId}.{ext}\"\n\
+             referenceResolution:\n  outputOverrides:\n    com.example/skipped: null\n",
+        )
+        .unwrap();
+        let metadata = PatternMetadata {
+            registry: "dev".to_string(),
+            artifact_type: "PROTOBUF".to_string(),
+            ..Default::default()
+        };
+        let path = pattern_path(&repo_cfg, "com.example", "users", "1.0.0", &metadata).unwrap();
+        assert_eq!(path, "protos/com/example/users.proto");
+        assert_eq!(
+            reference_path(&repo_cfg, "com.example", "skipped", "1.0.0", &metadata).unwrap(),
+            None
+        );
+        assert_eq!(explicit_path("protos\\users.proto"), "protos/users.proto");
+
+        let entry = LockedDependency {
+            name: "users".to_string(),
+            registry: "dev".to_string(),
+            resolved_version: "1.0.0".to_string(),
+            download_url: String::new(),
+            registry_url: None,
+            sha256: String::new(),
+            output_path: path,
+            group_id: "com.example".to_string(),
+            artifact_id: "users".to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
+        };
+        let mut writer = ArtifactWriter::new(temp.path(), &repo_cfg).unwrap();
+        assert_eq!(writer.stage(&entry, b"v1").unwrap(), Change::Created);
+        writer.commit().unwrap();
+        let written = temp.path().join("protos/com/example/users.proto");
+        assert_eq!(fs::read_to_string(&written).unwrap(), "v1");
+
+        let mut writer = ArtifactWriter::new(temp.path(), &repo_cfg).unwrap();
+        assert_eq!(writer.target(&entry), written);
+        assert_eq!(writer.stage(&entry, b"v1").unwrap(), Change::Unchanged);
+        assert_eq!(writer.stage(&entry, b"v2").unwrap(), Change::Updated);
+    }
+
+    #[test]
+    fn test_staged_outputs_commit_replaces_files() {
+        let temp = TempDir::new().unwrap();
+        let existing = temp.path().join("a.proto");
+        let created = temp.path().join("nested/b.proto");
+        let stale = temp.path().join("c.proto");
+        fs::write(&existing, "old").unwrap();
+        fs::write(&stale, "old c").unwrap();
+
+        let mut staged = StagedOutputs::new(temp.path()).unwrap();
+        staged.stage(&existing, b"new a").unwrap();
+        staged.stage(&created, b"new b").unwrap();
+        staged.remove(&stale);
+        // Nothing is touched before commit
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!created.exists());
+        assert!(stale.exists());
+
+        staged.commit().unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "new a");
+        assert_eq!(fs::read_to_string(&created).unwrap(), "new b");
+        assert!(!stale.exists());
+    }
+
+    #[test]
+    fn test_staged_outputs_roll_back_on_failure() {
+        let temp = TempDir::new().unwrap();
+        let existing = temp.path().join("a.proto");
+        let stale = temp.path().join("c.proto");
+        fs::write(&existing, "old").unwrap();
+        fs::write(&stale, "old c").unwrap();
+        // A file where a directory is needed makes the second move fail
+        let blocker = temp.path().join("blocker");
+        fs::write(&blocker, "").unwrap();
+
+        let mut staged = StagedOutputs::new(temp.path()).unwrap();
+        staged.stage(&existing, b"new a").unwrap();
+        staged.stage(&blocker.join("b.proto"), b"new b").unwrap();
+        staged.remove(&stale);
+        let dir = staged.dir.clone();
+
+        assert!(staged.commit().is_err());
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert_eq!(fs::read_to_string(&stale).unwrap(), "old c");
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_write_protection_is_cleared_before_rewrite() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("a.proto");
+
+        let mut staged = StagedOutputs::new(temp.path()).unwrap().read_only(true);
+        staged.stage(&target, b"v1").unwrap();
+        staged.commit().unwrap();
+        assert!(fs::metadata(&target).unwrap().permissions().readonly());
+
+        let mut staged = StagedOutputs::new(temp.path()).unwrap().read_only(true);
+        staged.stage(&target, b"v2").unwrap();
+        staged.commit().unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "v2");
+        assert!(fs::metadata(&target).unwrap().permissions().readonly());
+
+        remove_output_file(&target).unwrap();
+        assert!(!target.exists());
+    }
+}
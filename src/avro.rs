@@ -0,0 +1,278 @@
+//! Avro Parsing Canonical Form and schema fingerprints
+//!
+//! Two schemas that differ only in formatting, documentation, defaults or
+//! aliases have the same [Parsing Canonical Form], and therefore the same
+//! fingerprints. The lock file records the fingerprints of pulled Avro
+//! schemas, and `apicurio fingerprint` prints them for comparison with the
+//! schemas producers register in Kafka:
+//!
+//! - `rabin` - CRC-64-AVRO, the 8 little-endian bytes used by Avro's single
+//!   object encoding, as hex
+//! - `sha256` - SHA-256 of the canonical form, as hex
+//!
+//! [Parsing Canonical Form]: https://avro.apache.org/docs/1.11.1/specification/#parsing-canonical-form-for-schemas
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use crate::lint::SchemaKind;
+
+const PRIMITIVES: [&str; 8] = [
+    "null", "boolean", "int", "long", "float", "double", "bytes", "string",
+];
+
+/// Fingerprints of a schema's canonical form
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AvroFingerprint {
+    /// CRC-64-AVRO (Rabin) fingerprint, little-endian bytes as hex
+    pub rabin: String,
+    /// SHA-256 of the canonical form, as hex
+    pub sha256: String,
+}
+
+impl AvroFingerprint {
+    /// Fingerprints of an already canonical schema
+    pub fn of_canonical(canonical: &str) -> Self {
+        AvroFingerprint {
+            rabin: hex::encode(rabin(canonical.as_bytes()).to_le_bytes()),
+            sha256: hex::encode(Sha256::digest(canonical.as_bytes())),
+        }
+    }
+
+    /// Canonicalize `schema` and fingerprint it
+    pub fn of_schema(schema: &str) -> Result<Self> {
+        Ok(Self::of_canonical(&canonical_form(schema)?))
+    }
+}
+
+/// Fingerprints recorded in the lock for a pulled file, if it is an Avro schema
+pub fn lock_fingerprint(output_path: &str, data: &[u8]) -> Option<AvroFingerprint> {
+    let content = std::str::from_utf8(data).ok()?;
+    if SchemaKind::detect(output_path, content) != Some(SchemaKind::Avro) {
+        return None;
+    }
+    AvroFingerprint::of_schema(content).ok()
+}
+
+/// CRC-64-AVRO fingerprint of `data`
+pub fn rabin(data: &[u8]) -> u64 {
+    const EMPTY: u64 = 0xc15d_213a_a4d7_a795;
+    let mut table = [0u64; 256];
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut fp = i as u64;
+        for _ in 0..8 {
+            fp = (fp >> 1) ^ (EMPTY & (fp & 1).wrapping_neg());
+        }
+        *entry = fp;
+    }
+    data.iter().fold(EMPTY, |fp, &b| {
+        (fp >> 8) ^ table[((fp ^ b as u64) & 0xff) as usize]
+    })
+}
+
+/// Parsing Canonical Form of an Avro schema given as JSON
+pub fn canonical_form(schema: &str) -> Result<String> {
+    let schema: Value = serde_json::from_str(schema).context("invalid Avro schema JSON")?;
+    let mut out = String::new();
+    Canonicalizer::default().write(&schema, None, &mut out)?;
+    Ok(out)
+}
+
+#[derive(Default)]
+struct Canonicalizer {
+    /// Full names of the named types already written; later uses are written by name
+    defined: HashSet<String>,
+}
+
+impl Canonicalizer {
+    fn write(&mut self, schema: &Value, namespace: Option<&str>, out: &mut String) -> Result<()> {
+        match schema {
+            Value::String(name) => {
+                write_string(&self.full_name(name, namespace), out);
+                Ok(())
+            }
+            Value::Array(union) => {
+                out.push('[');
+                for (i, branch) in union.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    self.write(branch, namespace, out)?;
+                }
+                out.push(']');
+                Ok(())
+            }
+            Value::Object(map) => {
+                let Some(ty) = map.get("type") else {
+                    bail!("Avro schema object without \"type\"");
+                };
+                let ty = match ty {
+                    Value::String(ty) => ty.as_str(),
+                    // `{"type": {...}}` wraps another schema
+                    nested => return self.write(nested, namespace, out),
+                };
+                match ty {
+                    "record" | "error" | "enum" | "fixed" => {
+                        let Some(name) = map.get("name").and_then(Value::as_str) else {
+                            bail!("Avro {ty} without a name");
+                        };
+                        let namespace = map.get("namespace").and_then(Value::as_str).or(namespace);
+                        let full_name = self.full_name(name, namespace);
+                        if !self.defined.insert(full_name.clone()) {
+                            write_string(&full_name, out);
+                            return Ok(());
+                        }
+                        let inner_namespace = full_name.rsplit_once('.').map(|(ns, _)| ns);
+                        out.push_str("{\"name\":");
+                        write_string(&full_name, out);
+                        out.push_str(",\"type\":");
+                        write_string(ty, out);
+                        match ty {
+                            "enum" => {
+                                out.push_str(",\"symbols\":[");
+                                let symbols = map
+                                    .get("symbols")
+                                    .and_then(Value::as_array)
+                                    .map(Vec::as_slice)
+                                    .unwrap_or_default();
+                                for (i, symbol) in symbols.iter().enumerate() {
+                                    if i > 0 {
+                                        out.push(',');
+                                    }
+                                    write_string(symbol.as_str().unwrap_or_default(), out);
+                                }
+                                out.push(']');
+                            }
+                            "fixed" => {
+                                let size = map.get("size").and_then(Value::as_u64).unwrap_or(0);
+                                out.push_str(&format!(",\"size\":{size}"));
+                            }
+                            _ => {
+                                out.push_str(",\"fields\":[");
+                                let fields = map
+                                    .get("fields")
+                                    .and_then(Value::as_array)
+                                    .map(Vec::as_slice)
+                                    .unwrap_or_default();
+                                for (i, field) in fields.iter().enumerate() {
+                                    if i > 0 {
+                                        out.push(',');
+                                    }
+                                    let (Some(name), Some(field_type)) = (
+                                        field.get("name").and_then(Value::as_str),
+                                        field.get("type"),
+                                    ) else {
+                                        bail!("field of {full_name} without name or type");
+                                    };
+                                    out.push_str("{\"name\":");
+                                    write_string(name, out);
+                                    out.push_str(",\"type\":");
+                                    self.write(field_type, inner_namespace, out)?;
+                                    out.push('}');
+                                }
+                                out.push(']');
+                            }
+                        }
+                        out.push('}');
+                        Ok(())
+                    }
+                    "array" => {
+                        out.push_str("{\"type\":\"array\",\"items\":");
+                        let items = map.get("items").context("Avro array without items")?;
+                        self.write(items, namespace, out)?;
+                        out.push('}');
+                        Ok(())
+                    }
+                    "map" => {
+                        out.push_str("{\"type\":\"map\",\"values\":");
+                        let values = map.get("values").context("Avro map without values")?;
+                        self.write(values, namespace, out)?;
+                        out.push('}');
+                        Ok(())
+                    }
+                    // Primitives, including those with a logicalType, reduce to their name
+                    primitive => {
+                        write_string(&self.full_name(primitive, namespace), out);
+                        Ok(())
+                    }
+                }
+            }
+            other => bail!("unexpected Avro schema {other}"),
+        }
+    }
+
+    fn full_name(&self, name: &str, namespace: Option<&str>) -> String {
+        match namespace {
+            _ if PRIMITIVES.contains(&name) || name.contains('.') => name.to_string(),
+            Some(ns) if !ns.is_empty() => format!("{ns}.{name}"),
+            _ => name.to_string(),
+        }
+    }
+}
+
+/// Write a JSON string, escaping only what JSON requires
+fn write_string(value: &str, out: &mut String) {
+    out.push_str(&Value::String(value.to_string()).to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_form() {
+        let schema = r#"{
+            "type": "record", "name": "User", "namespace": "com.example",
+            "doc": "A user", "aliases": ["Person"],
+            "fields": [
+                {"name": "id", "type": {"type": "string", "logicalType": "uuid"}, "doc": "key"},
+                {"name": "address", "type": {"type": "record", "name": "Address",
+                    "fields": [{"name": "city", "type": "string"}]}},
+                {"name": "previous", "type": ["null", "Address"], "default": null},
+                {"name": "kind", "type": {"type": "enum", "name": "Kind", "namespace": "other", "symbols": ["A", "B"]}},
+                {"name": "tags", "type": {"type": "map", "values": {"type": "array", "items": "long"}}},
+                {"name": "hash", "type": {"type": "fixed", "name": "Hash", "size": 16}}
+            ]
+        }"#;
+        assert_eq!(
+            canonical_form(schema).unwrap(),
+            concat!(
+                r#"{"name":"com.example.User","type":"record","fields":["#,
+                r#"{"name":"id","type":"string"},"#,
+                r#"{"name":"address","type":{"name":"com.example.Address","type":"record","fields":[{"name":"city","type":"string"}]}},"#,
+                r#"{"name":"previous","type":["null","com.example.Address"]},"#,
+                r#"{"name":"kind","type":{"name":"other.Kind","type":"enum","symbols":["A","B"]}},"#,
+                r#"{"name":"tags","type":{"type":"map","values":{"type":"array","items":"long"}}},"#,
+                r#"{"name":"hash","type":{"name":"com.example.Hash","type":"fixed","size":16}}"#,
+                "]}"
+            )
+        );
+        assert_eq!(canonical_form(r#"{"type": "int"}"#).unwrap(), r#""int""#);
+    }
+
+    #[test]
+    fn test_fingerprints_ignore_formatting() {
+        // Value from the Avro specification's test suite
+        assert_eq!(rabin(br#""null""#) as i64, 7195948357588979594);
+
+        let compact = r#"{"type":"record","name":"a.B","fields":[{"name":"x","type":"int"}]}"#;
+        let verbose = r#"{
+            "type": "record", "name": "B", "namespace": "a", "doc": "ignored",
+            "fields": [{"name": "x", "type": "int", "default": 0}]
+        }"#;
+        assert_eq!(
+            AvroFingerprint::of_schema(compact).unwrap(),
+            AvroFingerprint::of_schema(verbose).unwrap()
+        );
+        assert_ne!(
+            AvroFingerprint::of_schema(compact).unwrap(),
+            AvroFingerprint::of_schema(r#""int""#).unwrap()
+        );
+
+        assert!(lock_fingerprint("schemas/b.avsc", compact.as_bytes()).is_some());
+        assert!(lock_fingerprint("protos/b.proto", b"syntax = \"proto3\";").is_none());
+    }
+}
@@ -1,11 +1,12 @@
 use crate::{
-    config::{load_global_config, load_repo_config, DependencyConfig},
-    constants::APICURIO_CONFIG,
+    config::{
+        load_global_config, load_repo_config, load_repo_config_without_includes, DependencyConfig,
+    },
     identifier::Identifier,
-    registry::RegistryClient,
+    naming, paths,
+    registry::RegistryPool,
 };
 use anyhow::{anyhow, Result};
-use std::path::PathBuf;
 
 pub async fn run(identifier_str: Option<String>, latest: bool) -> Result<()> {
     // Parse the identifier string (if provided)
@@ -16,10 +17,12 @@ pub async fn run(identifier_str: Option<String>, latest: bool) -> Result<()> {
     };
 
     // Load configuration
-    let repo_path = PathBuf::from(APICURIO_CONFIG);
-    let mut repo = load_repo_config(&repo_path)?;
+    let repo_path = paths::project_config();
+    // Edit only the base file so values from `include` overlays aren't baked into it
+    let mut repo = load_repo_config_without_includes(&repo_path)?;
+    let merged = load_repo_config(&repo_path)?;
     let global = load_global_config()?;
-    let regs = repo.merge_registries(global)?;
+    let regs = merged.merge_registries(global)?;
 
     if regs.is_empty() {
         return Err(anyhow!(
@@ -36,20 +39,30 @@ pub async fn run(identifier_str: Option<String>, latest: bool) -> Result<()> {
             .iter()
             .find(|r| &r.name == registry_name)
             .ok_or_else(|| anyhow!("Registry '{}' not found", registry_name))?;
-        Some(RegistryClient::new(registry_config)?)
+        Some(RegistryPool::global().client(registry_config)?)
     } else if regs.len() == 1 {
         // Only one registry, use it
-        Some(RegistryClient::new(&regs[0])?)
+        Some(RegistryPool::global().client(&regs[0])?)
     } else {
         // Multiple registries, will be selected during complete_interactive
         None
     };
 
+    // Take the group from the registry's `defaultGroup` instead of prompting
+    if identifier.group_id.is_none() {
+        let registry = match &identifier.registry {
+            Some(name) => regs.iter().find(|r| &r.name == name),
+            None if regs.len() == 1 => regs.first(),
+            None => None,
+        };
+        identifier.group_id = registry.and_then(|r| r.default_group.clone());
+    }
+
     // Complete the identifier interactively (except version)
     identifier
         .complete_interactive(
             &registry_names,
-            &repo.dependencies,
+            &merged.dependencies,
             registry_client.as_ref(),
         )
         .await?;
@@ -63,7 +76,7 @@ pub async fn run(identifier_str: Option<String>, latest: bool) -> Result<()> {
             .iter()
             .find(|r| &r.name == registry_name)
             .ok_or_else(|| anyhow!("Registry '{}' not found", registry_name))?;
-        RegistryClient::new(registry_config)?
+        RegistryPool::global().client(registry_config)?
     };
 
     // Resolve version
@@ -106,6 +119,23 @@ pub async fn run(identifier_str: Option<String>, latest: bool) -> Result<()> {
         ));
     }
 
+    // The artifact exists already, so broken conventions are only reported
+    if let Some(registry) = regs
+        .iter()
+        .find(|r| identifier.registry.as_ref() == Some(&r.name))
+    {
+        for problem in naming::violations(
+            registry,
+            identifier.group_id.as_ref().unwrap(),
+            identifier.artifact_id.as_ref().unwrap(),
+        ) {
+            println!(
+                "⚠️  {problem} (naming conventions of registry '{}')",
+                registry.name
+            );
+        }
+    }
+
     // Generate a unique name for the dependency if needed
     let dep_name = format!(
         "{}/{}",
@@ -147,6 +177,14 @@ pub async fn run(identifier_str: Option<String>, latest: bool) -> Result<()> {
         registry: Some(identifier.registry.unwrap()),
         output_path: None,
         resolve_references: None,
+        binary: false,
+        content_type: None,
+        group: None,
+        labels: Default::default(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+        if_no_match: Default::default(),
+        member_of: None,
     };
 
     if let Some(index) = existing_index {
@@ -163,7 +201,7 @@ pub async fn run(identifier_str: Option<String>, latest: bool) -> Result<()> {
     crate::config::save_repo_config(&repo, &repo_path)?;
 
     // Pull the dependency immediately
-    crate::commands::pull::run().await?;
+    crate::commands::pull::run(None, Default::default(), false, Default::default()).await?;
 
     Ok(())
 }
@@ -0,0 +1,171 @@
+use crate::{
+    commands::{
+        rules::{apply, default_registry, find_config, find_registry, RuleChange},
+        OutputFormat,
+    },
+    config::{load_global_config, load_repo_config},
+    paths,
+    registry::{RegistryClient, RuleScope, RuleType},
+};
+use anyhow::{anyhow, bail, Result};
+use clap::Subcommand;
+use dialoguer::Confirm;
+use std::io::IsTerminal;
+
+#[derive(Subcommand, Debug)]
+pub enum AdminCommands {
+    /// List the configuration properties of the registry
+    Properties {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Show one configuration property
+    Get {
+        /// Property name, e.g. apicurio.rest.deletion.artifact.enabled
+        name: String,
+    },
+    /// Override a configuration property
+    Set {
+        /// Property name, e.g. apicurio.rest.deletion.artifact.enabled
+        name: String,
+        value: String,
+    },
+    /// Restore a configuration property to its default
+    Reset {
+        /// Property name, e.g. apicurio.rest.deletion.artifact.enabled
+        name: String,
+    },
+    /// Show the global rules, or set one with RULE and CONFIG
+    Rules {
+        /// validity, compatibility or integrity
+        rule: Option<RuleType>,
+        /// New configuration, e.g. BACKWARD or SYNTAX_ONLY
+        #[arg(requires = "rule")]
+        config: Option<String>,
+        /// Remove the rule instead of setting it
+        #[arg(long, requires = "rule", conflicts_with = "config")]
+        remove: bool,
+    },
+}
+
+pub async fn run(cmd: AdminCommands, registry: Option<String>, i_am_an_admin: bool) -> Result<()> {
+    let repo_cfg = load_repo_config(&paths::project_config())?;
+    let registries = repo_cfg.merge_registries(load_global_config()?)?;
+    let name = default_registry(registry, &repo_cfg, &registries)?;
+    let client = RegistryClient::new(find_registry(&registries, &name)?)?;
+
+    match cmd {
+        AdminCommands::Properties { format } => {
+            let properties = client.list_config_properties().await?;
+            match format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&properties)?),
+                OutputFormat::Text => {
+                    println!("Configuration properties of registry '{name}'");
+                    let width = properties.iter().map(|p| p.name.len()).max().unwrap_or(0);
+                    for property in &properties {
+                        println!("  {:<width$}  {}", property.name, property.value);
+                    }
+                }
+            }
+        }
+        AdminCommands::Get { name: property } => {
+            let property = client.get_config_property(&property).await?;
+            println!("{} = {}", property.name, property.value);
+            if let Some(label) = &property.label {
+                println!("  {label}");
+            }
+            if let Some(description) = &property.description {
+                println!("  {description}");
+            }
+        }
+        AdminCommands::Set {
+            name: property,
+            value,
+        } => {
+            let current = client.get_config_property(&property).await?;
+            if current.value == value {
+                println!("{property} is already {value}");
+                return Ok(());
+            }
+            let prompt = format!(
+                "Change {property} of registry '{name}' from {} to {value}?",
+                current.value
+            );
+            if !confirm(&prompt, i_am_an_admin)? {
+                return Ok(());
+            }
+            client.set_config_property(&property, &value).await?;
+            println!("✅ {property} of registry '{name}' set to {value}");
+        }
+        AdminCommands::Reset { name: property } => {
+            let prompt = format!("Reset {property} of registry '{name}' to its default?");
+            if !confirm(&prompt, i_am_an_admin)? {
+                return Ok(());
+            }
+            client.reset_config_property(&property).await?;
+            let restored = client.get_config_property(&property).await?;
+            println!(
+                "✅ {property} of registry '{name}' reset to {}",
+                restored.value
+            );
+        }
+        AdminCommands::Rules {
+            rule,
+            config,
+            remove,
+        } => match rule {
+            Some(rule) => {
+                if config.is_none() && !remove {
+                    return Err(anyhow!("pass a CONFIG for {} or --remove", rule.as_str()));
+                }
+                let prompt = match &config {
+                    Some(config) => format!(
+                        "Set the global {} rule of registry '{name}' to {config}?",
+                        rule.as_str()
+                    ),
+                    None => format!(
+                        "Remove the global {} rule of registry '{name}'?",
+                        rule.as_str()
+                    ),
+                };
+                if !confirm(&prompt, i_am_an_admin)? {
+                    return Ok(());
+                }
+                let change = RuleChange {
+                    rule,
+                    config,
+                    remove,
+                };
+                let subject = format!("registry '{name}' (global)");
+                apply(&client, RuleScope::Global, &change, &subject).await?;
+            }
+            None => {
+                let rules = client.list_rules(RuleScope::Global).await?;
+                println!("Global rules of registry '{name}'");
+                for rule in RuleType::ALL {
+                    let config = find_config(&rules, rule).unwrap_or("not set");
+                    println!("  {:<14} {config}", rule.as_str());
+                }
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Registry-wide changes affect every user, so ask first unless `--i-am-an-admin` was given
+fn confirm(prompt: &str, i_am_an_admin: bool) -> Result<bool> {
+    if i_am_an_admin {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!("{prompt} Pass --i-am-an-admin to change registry-wide settings without a terminal to confirm");
+    }
+    let confirmed = Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?;
+    if !confirmed {
+        println!("Nothing was changed");
+    }
+    Ok(confirmed)
+}
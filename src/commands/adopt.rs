@@ -0,0 +1,266 @@
+use crate::{
+    api::{LockOptions, Workspace},
+    config_edit::{read_editable, upsert_named_entry, write_edited},
+    events::{ConsoleSink, WarningSink},
+    lockfile::LockFile,
+    output_path, provenance,
+    registry::RegistryClient,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use dialoguer::Select;
+use serde_yaml::{Mapping, Value};
+use std::{env, fs, path::Path, sync::Arc};
+
+/// A registry version an adopted file may be a copy of
+#[derive(Debug, Clone, PartialEq)]
+struct Candidate {
+    registry: String,
+    group_id: String,
+    artifact_id: String,
+    version: String,
+    /// The file has exactly the content of this version
+    identical: bool,
+}
+
+/// Turn a hand-copied schema file into a dependency
+///
+/// Searches the configured registries (or only `registry`) for a version with
+/// the content of the file, falling back to artifacts named like the file,
+/// then adds a dependency pinned to that version with the file as its output
+/// path and locks it.
+pub async fn run(path: String, registry: Option<String>) -> Result<()> {
+    let workspace = Workspace::current()?.with_events(Arc::new(WarningSink));
+    let output_path = project_path(&path)?;
+    let file = output_path::native(workspace.root(), &output_path);
+    let data = fs::read(&file).with_context(|| format!("Failed to read file: {path}"))?;
+    let content = provenance::strip_header(&data);
+
+    if let Ok(lock) = LockFile::load(&workspace.lock_path()) {
+        if let Some(owner) = lock
+            .locked_dependencies
+            .iter()
+            .find(|d| d.output_path == output_path)
+        {
+            bail!("{output_path} is already pulled for '{}'", owner.name);
+        }
+    }
+
+    let registries = match registry {
+        Some(name) => {
+            workspace
+                .client(&name)
+                .ok_or_else(|| anyhow!("Registry '{name}' not found"))?;
+            vec![name]
+        }
+        None => workspace.registry_names(),
+    };
+    if registries.is_empty() {
+        bail!(
+            "No registries configured. Please add a registry first using 'apicurio registry add'."
+        );
+    }
+
+    let mut candidates = Vec::new();
+    for name in &registries {
+        let client = workspace.client(name).expect("listed registry");
+        match client
+            .search_versions_by_content(&content, content_type_for(&output_path))
+            .await
+        {
+            Ok(found) => {
+                candidates.extend(found.into_iter().map(|(group_id, artifact_id, version)| {
+                    Candidate {
+                        registry: name.clone(),
+                        group_id,
+                        artifact_id,
+                        version,
+                        identical: true,
+                    }
+                }))
+            }
+            Err(e) => println!("⚠️  Could not search registry '{name}' by content: {e}"),
+        }
+    }
+    if candidates.is_empty() {
+        let stem = Path::new(&output_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default()
+            .to_string();
+        for name in &registries {
+            let client = workspace.client(name).expect("listed registry");
+            candidates.extend(by_name(client, name, &stem, &content).await?);
+        }
+    }
+
+    let candidate = match candidates.len() {
+        0 => bail!(
+            "No artifact in {} has the content of {output_path} or is named like it",
+            registries
+                .iter()
+                .map(|r| format!("'{r}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        1 => candidates.remove(0),
+        _ => {
+            let items: Vec<String> = candidates
+                .iter()
+                .map(|c| {
+                    format!(
+                        "{}/{}@{} from {}{}",
+                        c.group_id,
+                        c.artifact_id,
+                        c.version,
+                        c.registry,
+                        if c.identical {
+                            ""
+                        } else {
+                            " (content differs)"
+                        }
+                    )
+                })
+                .collect();
+            let selection = Select::new()
+                .with_prompt(format!("Select the artifact {output_path} is a copy of"))
+                .items(&items)
+                .default(0)
+                .interact()?;
+            candidates.remove(selection)
+        }
+    };
+
+    let name = format!("{}/{}", candidate.group_id, candidate.artifact_id);
+    if workspace
+        .config()
+        .dependencies
+        .iter()
+        .any(|d| d.name == name)
+    {
+        bail!("A dependency named '{name}' exists already");
+    }
+    let mut entry = Mapping::new();
+    entry.insert("name".into(), name.clone().into());
+    entry.insert("version".into(), format!("={}", candidate.version).into());
+    entry.insert("registry".into(), candidate.registry.clone().into());
+    entry.insert("outputPath".into(), output_path.clone().into());
+
+    let config_path = workspace.config_path();
+    let original = fs::read(&config_path)?;
+    let content = read_editable(&config_path)?;
+    write_edited(
+        &config_path,
+        &upsert_named_entry(&content, "dependencies", &Value::Mapping(entry))?,
+    )?;
+    let locked = match Workspace::current() {
+        Ok(adopted) => {
+            adopted
+                .with_events(Arc::new(ConsoleSink))
+                .lock(LockOptions::default())
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = locked {
+        fs::write(&config_path, &original)?;
+        return Err(e.context(format!(
+            "Failed to adopt {output_path}, the config was restored"
+        )));
+    }
+
+    println!(
+        "✅ Adopted {output_path} as {name}@{} from '{}'",
+        candidate.version, candidate.registry
+    );
+    if !candidate.identical {
+        println!(
+            "⚠️  {output_path} differs from the registry content; 'apicurio pull' will replace it"
+        );
+    }
+    Ok(())
+}
+
+/// Versions of the artifacts of `registry` whose ID or name is `stem`: the
+/// one with the content of the file, or else the latest
+async fn by_name(
+    client: &RegistryClient,
+    registry: &str,
+    stem: &str,
+    content: &[u8],
+) -> Result<Vec<Candidate>> {
+    let mut candidates = Vec::new();
+    for (group_id, artifact_id) in client.search_artifacts_by_name(stem).await? {
+        let mut versions = client.list_versions(&group_id, &artifact_id).await?;
+        versions.sort();
+        let mut chosen = None;
+        for version in versions.iter().rev() {
+            let version = version.to_string();
+            let bytes = client
+                .get_version_bytes(&group_id, &artifact_id, &version)
+                .await?;
+            if bytes.as_ref() == content {
+                chosen = Some((version, true));
+                break;
+            }
+        }
+        let Some((version, identical)) =
+            chosen.or_else(|| versions.last().map(|v| (v.to_string(), false)))
+        else {
+            continue;
+        };
+        candidates.push(Candidate {
+            registry: registry.to_string(),
+            group_id,
+            artifact_id,
+            version,
+            identical,
+        });
+    }
+    Ok(candidates)
+}
+
+/// `path` relative to the project directory, `/`-separated
+fn project_path(path: &str) -> Result<String> {
+    let absolute = Path::new(path);
+    let relative = if absolute.is_absolute() {
+        absolute
+            .strip_prefix(env::current_dir()?)
+            .map_err(|_| anyhow!("{path} is outside the project directory"))?
+    } else {
+        absolute
+    };
+    let portable = output_path::portable(&relative.to_string_lossy());
+    let trimmed = portable.trim_start_matches("./").to_string();
+    if trimmed.split('/').any(|segment| segment == "..") {
+        bail!("{path} is outside the project directory");
+    }
+    Ok(trimmed)
+}
+
+/// Content type the registry expects for the content of `path`
+fn content_type_for(path: &str) -> &'static str {
+    match path.rsplit('.').next().unwrap_or_default() {
+        "proto" => "application/x-protobuf",
+        "json" | "avsc" => "application/json",
+        "yaml" | "yml" => "application/x-yaml",
+        "graphql" | "graphqls" | "gql" => "application/graphql",
+        "xml" | "xsd" | "wsdl" => "application/xml",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_path() {
+        assert_eq!(
+            project_path("./protos/users.proto").unwrap(),
+            "protos/users.proto"
+        );
+        assert_eq!(project_path("users.proto").unwrap(), "users.proto");
+        assert!(project_path("../elsewhere/users.proto").is_err());
+        assert_eq!(content_type_for("schemas/order.avsc"), "application/json");
+    }
+}
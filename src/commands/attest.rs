@@ -0,0 +1,223 @@
+use crate::{
+    config::{load_global_config, load_repo_config},
+    constants::{APICURIO_CONFIG, APICURIO_LOCK},
+    local_refs,
+    lockfile::LockFile,
+    output_path, paths, provenance,
+};
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, fs, path::Path};
+
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+const BUILD_TYPE: &str = "https://github.com/YassineElbouchaibi/apicurio-cli/pull/v1";
+
+/// A pulled file and the SHA-256 of its bytes on disk
+#[derive(Debug, Clone, PartialEq)]
+struct Subject {
+    path: String,
+    sha256: String,
+}
+
+/// Write an in-toto provenance statement (and optionally a checksums file)
+/// covering every file pulled for the lock
+pub fn run(out: Option<String>, checksums: Option<String>) -> Result<()> {
+    let lock = LockFile::load(&paths::project_lock())
+        .with_context(|| format!("reading {APICURIO_LOCK}; run `apicurio lock` first"))?;
+    let repo_cfg = load_repo_config(&paths::project_config())?;
+    let registries: BTreeMap<String, String> = repo_cfg
+        .merge_registries(load_global_config()?)?
+        .into_iter()
+        .map(|r| (r.name, r.url))
+        .collect();
+
+    let subjects = collect_subjects(Path::new(""), &lock)?;
+    let finished_on = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let statement = statement(&lock, &registries, &subjects, &finished_on);
+    let rendered = format!("{}\n", serde_json::to_string_pretty(&statement)?);
+    match &out {
+        Some(path) => {
+            fs::write(path, rendered).with_context(|| format!("writing {path}"))?;
+            eprintln!(
+                "✅ Wrote a provenance statement for {} file(s) to {path}",
+                subjects.len()
+            );
+        }
+        None => print!("{rendered}"),
+    }
+    if let Some(path) = checksums {
+        fs::write(&path, render_checksums(&subjects)).with_context(|| format!("writing {path}"))?;
+        eprintln!("✅ Wrote checksums to {path}");
+    }
+    Ok(())
+}
+
+/// Hash every locked file, failing when one is missing or differs from the lock
+fn collect_subjects(root: &Path, lock: &LockFile) -> Result<Vec<Subject>> {
+    let mut subjects = Vec::new();
+    let mut problems = Vec::new();
+    for dep in &lock.locked_dependencies {
+        let file = output_path::native(root, &dep.output_path);
+        let Ok(data) = fs::read(&file) else {
+            problems.push(format!("  - {} ({}): missing", dep.name, dep.output_path));
+            continue;
+        };
+        // The lock records the registry content, without a provenance header
+        // and with references not pointed at local files
+        let content = local_refs::to_registry(
+            &lock.locked_dependencies,
+            dep,
+            &provenance::strip_header(&data),
+        );
+        let content = hex::encode(Sha256::digest(content));
+        if content != dep.sha256 {
+            problems.push(format!(
+                "  - {} ({}): differs from the lock",
+                dep.name, dep.output_path
+            ));
+            continue;
+        }
+        subjects.push(Subject {
+            path: dep.output_path.clone(),
+            sha256: hex::encode(Sha256::digest(&data)),
+        });
+    }
+    if !problems.is_empty() {
+        bail!(
+            "cannot attest {} file(s); run `apicurio pull` (or `apicurio verify` for details):\n{}",
+            problems.len(),
+            problems.join("\n")
+        );
+    }
+    subjects.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(subjects)
+}
+
+/// in-toto Statement v1 with a SLSA Provenance v1 predicate
+fn statement(
+    lock: &LockFile,
+    registries: &BTreeMap<String, String>,
+    subjects: &[Subject],
+    finished_on: &str,
+) -> Value {
+    let subject: Vec<Value> = subjects
+        .iter()
+        .map(|s| json!({ "name": s.path, "digest": { "sha256": s.sha256 } }))
+        .collect();
+    let resolved: Vec<Value> = lock
+        .locked_dependencies
+        .iter()
+        .map(|dep| {
+            json!({
+                "uri": dep.download_url,
+                "digest": { "sha256": dep.sha256 },
+                "name": dep.name,
+                "annotations": {
+                    "registry": dep.registry,
+                    "registryUrl": registries.get(&dep.registry),
+                    "groupId": dep.group_id,
+                    "artifactId": dep.artifact_id,
+                    "version": dep.resolved_version,
+                    "transitive": dep.is_transitive,
+                    "outputPath": dep.output_path,
+                },
+            })
+        })
+        .collect();
+    json!({
+        "_type": STATEMENT_TYPE,
+        "subject": subject,
+        "predicateType": PREDICATE_TYPE,
+        "predicate": {
+            "buildDefinition": {
+                "buildType": BUILD_TYPE,
+                "externalParameters": {
+                    "config": APICURIO_CONFIG,
+                    "lockFile": APICURIO_LOCK,
+                    "configHash": lock.config_hash,
+                },
+                "resolvedDependencies": resolved,
+            },
+            "runDetails": {
+                "builder": {
+                    "id": env!("CARGO_PKG_REPOSITORY"),
+                    "version": { "apicurio-cli": env!("CARGO_PKG_VERSION") },
+                },
+                "metadata": { "finishedOn": finished_on },
+            },
+        },
+    })
+}
+
+/// `sha256sum`-compatible lines (`<sha256>  <path>`)
+fn render_checksums(subjects: &[Subject]) -> String {
+    subjects
+        .iter()
+        .map(|s| format!("{}  {}\n", s.sha256, s.path))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lockfile::LockedDependency;
+
+    #[test]
+    fn test_statement_covers_pulled_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("protos")).unwrap();
+        fs::write(
+            dir.path().join("protos/users.proto"),
+            b"syntax = \"proto3\";",
+        )
+        .unwrap();
+        let dep = LockedDependency {
+            name: "users".to_string(),
+            registry: "prod".to_string(),
+            resolved_version: "1.2.0".to_string(),
+            download_url: "https://registry.example/users/1.2.0/content".to_string(),
+            registry_url: None,
+            sha256: hex::encode(Sha256::digest(b"syntax = \"proto3\";")),
+            output_path: "protos/users.proto".to_string(),
+            group_id: "com.example".to_string(),
+            artifact_id: "users".to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
+        };
+        let mut lock = LockFile::new(vec![dep], "hash".to_string());
+
+        let subjects = collect_subjects(dir.path(), &lock).unwrap();
+        let registries =
+            BTreeMap::from([("prod".to_string(), "https://registry.example".to_string())]);
+        let statement = statement(&lock, &registries, &subjects, "2026-01-01T00:00:00Z");
+        assert_eq!(statement["_type"], STATEMENT_TYPE);
+        assert_eq!(statement["subject"][0]["name"], "protos/users.proto");
+        assert_eq!(
+            statement["subject"][0]["digest"]["sha256"],
+            lock.locked_dependencies[0].sha256.as_str()
+        );
+        let resolved = &statement["predicate"]["buildDefinition"]["resolvedDependencies"][0];
+        assert_eq!(
+            resolved["annotations"]["registryUrl"],
+            "https://registry.example"
+        );
+        assert_eq!(
+            render_checksums(&subjects),
+            format!("{}  protos/users.proto\n", subjects[0].sha256)
+        );
+
+        lock.locked_dependencies[0].sha256 = "0".repeat(64);
+        let err = collect_subjects(dir.path(), &lock).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("users (protos/users.proto): differs from the lock"));
+    }
+}
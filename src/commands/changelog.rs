@@ -0,0 +1,150 @@
+use crate::{
+    commands::OutputFormat,
+    config::{load_global_config, load_repo_config},
+    events::WarningSink,
+    identifier::resolve_artifact,
+    lint::SchemaKind,
+    paths,
+    registry::{ArtifactVersionMetadata, RegistryClient},
+    schema_diff::{self, Change},
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Changes introduced by one version compared with the version before it
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionChanges {
+    pub version: String,
+    pub previous_version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_on: Option<String>,
+    pub changes: Vec<Change>,
+}
+
+pub async fn run(
+    identifier: String,
+    since: Option<String>,
+    until: Option<String>,
+    format: OutputFormat,
+) -> Result<()> {
+    let repo_cfg = load_repo_config(&paths::project_config())?;
+    let registries = repo_cfg.merge_registries(load_global_config()?)?;
+    let names: Vec<String> = registries.iter().map(|r| r.name.clone()).collect();
+    let target = resolve_artifact(&identifier, &repo_cfg, &names)?;
+    let registry = registries
+        .iter()
+        .find(|r| r.name == target.registry)
+        .ok_or_else(|| anyhow!("Registry '{}' not found", target.registry))?;
+    let client = RegistryClient::new(registry)?.with_events(Arc::new(WarningSink));
+
+    let since = since.as_deref().map(Version::parse).transpose()?;
+    let until = until.as_deref().map(Version::parse).transpose()?;
+    let versions = select_versions(
+        client
+            .list_version_metadata(&target.group_id, &target.artifact_id)
+            .await?,
+        since.as_ref(),
+        until.as_ref(),
+    );
+    if versions.len() < 2 {
+        println!(
+            "Not enough versions of {}/{}/{} to compare",
+            target.registry, target.group_id, target.artifact_id
+        );
+        return Ok(());
+    }
+
+    let mut entries = Vec::new();
+    let mut previous: Option<(&ArtifactVersionMetadata, String)> = None;
+    for metadata in &versions {
+        let content = client
+            .get_version_content(&target.group_id, &target.artifact_id, &metadata.version)
+            .await?;
+        if let Some((prev, prev_content)) = &previous {
+            let kind = SchemaKind::from_artifact_type(&metadata.artifact_type);
+            entries.push(VersionChanges {
+                version: metadata.version.clone(),
+                previous_version: prev.version.clone(),
+                created_on: metadata.created_on.clone(),
+                changes: schema_diff::diff(kind, prev_content, &content),
+            });
+        }
+        previous = Some((metadata, content));
+    }
+    // Newest release first, as in a changelog file
+    entries.reverse();
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+        OutputFormat::Text => {
+            println!(
+                "# Changelog for {}/{}/{}",
+                target.registry, target.group_id, target.artifact_id
+            );
+            for entry in &entries {
+                println!();
+                match &entry.created_on {
+                    Some(date) => println!("## {} ({date})", entry.version),
+                    None => println!("## {}", entry.version),
+                }
+                println!();
+                if entry.changes.is_empty() {
+                    println!("- No schema changes since {}", entry.previous_version);
+                }
+                for change in &entry.changes {
+                    println!("- {change}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Semver versions in ascending order, from `since` (the baseline the first
+/// listed changes are compared with) up to `until`; disabled versions are left out
+fn select_versions(
+    versions: Vec<ArtifactVersionMetadata>,
+    since: Option<&Version>,
+    until: Option<&Version>,
+) -> Vec<ArtifactVersionMetadata> {
+    let mut selected: Vec<(Version, ArtifactVersionMetadata)> = versions
+        .into_iter()
+        .filter(|v| v.state.as_deref() != Some("DISABLED"))
+        .filter_map(|v| Some((Version::parse(&v.version).ok()?, v)))
+        .filter(|(parsed, _)| since.is_none_or(|since| parsed >= since))
+        .filter(|(parsed, _)| until.is_none_or(|until| parsed <= until))
+        .collect();
+    selected.sort_by(|a, b| a.0.cmp(&b.0));
+    selected.into_iter().map(|(_, v)| v).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(version: &str, state: &str) -> ArtifactVersionMetadata {
+        serde_json::from_value(serde_json::json!({ "version": version, "state": state })).unwrap()
+    }
+
+    #[test]
+    fn test_select_versions() {
+        let versions = vec![
+            metadata("1.2.0", "ENABLED"),
+            metadata("0.9.0", "ENABLED"),
+            metadata("1.0.0", "ENABLED"),
+            metadata("1.1.0", "DISABLED"),
+            metadata("latest", "ENABLED"),
+            metadata("2.0.0", "ENABLED"),
+        ];
+        let since = Version::parse("1.0.0").unwrap();
+        let until = Version::parse("1.2.0").unwrap();
+        let selected: Vec<String> = select_versions(versions, Some(&since), Some(&until))
+            .into_iter()
+            .map(|v| v.version)
+            .collect();
+        assert_eq!(selected, ["1.0.0", "1.2.0"]);
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::{bail, Context, Result};
+use std::{fs, path::Path};
+
+use crate::{
+    config::{load_repo_config, RepoConfig},
+    paths,
+};
+
+/// CI systems `ci-setup` generates pipelines for
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiProvider {
+    /// GitHub Actions workflow
+    Github,
+    /// GitLab CI job, included from `.gitlab-ci.yml`
+    Gitlab,
+}
+
+impl CiProvider {
+    /// Where `--write` puts the generated file
+    pub fn default_path(self) -> &'static str {
+        match self {
+            CiProvider::Github => ".github/workflows/apicurio.yml",
+            CiProvider::Gitlab => ".gitlab/ci/apicurio.yml",
+        }
+    }
+}
+
+/// Options of the generated pipeline
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CiOptions {
+    /// Also fail when newer versions match the configured ranges
+    pub status: bool,
+    /// Pass `--ensure-committed` to `pull` (pulled files are vendored)
+    pub ensure_committed: bool,
+    /// Pass `--ignore-header` to `verify` (provenance headers are written)
+    pub ignore_header: bool,
+}
+
+impl CiOptions {
+    /// Gates matching the repository configuration
+    pub fn for_config(config: &RepoConfig, status: bool) -> Self {
+        CiOptions {
+            status,
+            ensure_committed: config.gitignore.ensure_committed,
+            ignore_header: !config.provenance_header.is_empty(),
+        }
+    }
+
+    /// The gate commands, in order
+    pub fn commands(&self) -> Vec<String> {
+        let mut pull = "apicurio pull --frozen".to_string();
+        if self.ensure_committed {
+            pull.push_str(" --ensure-committed");
+        }
+        let mut commands = vec![pull];
+        commands.push(if self.ignore_header {
+            "apicurio verify --ignore-header".to_string()
+        } else {
+            "apicurio verify".to_string()
+        });
+        if self.status {
+            commands.push("apicurio status".to_string());
+        }
+        commands
+    }
+}
+
+/// Print a CI pipeline running the `pull --frozen`, `verify` and `status`
+/// gates, or write it into the repository with `write`
+pub fn run(provider: CiProvider, write: bool, force: bool, status: bool) -> Result<()> {
+    let config_path = paths::project_config();
+    let options = if config_path.exists() {
+        CiOptions::for_config(&load_repo_config(&config_path)?, status)
+    } else {
+        CiOptions {
+            status,
+            ..Default::default()
+        }
+    };
+    let pipeline = render(provider, &options);
+    if !write {
+        print!("{pipeline}");
+        return Ok(());
+    }
+
+    let path = Path::new(provider.default_path());
+    if path.exists() && !force {
+        bail!(
+            "{} already exists; pass --force to overwrite it",
+            path.display()
+        );
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, pipeline).with_context(|| format!("writing {}", path.display()))?;
+    println!("✅ Wrote {}", path.display());
+    if provider == CiProvider::Gitlab {
+        println!(
+            "   Include it from .gitlab-ci.yml:\n   include:\n     - local: {}",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// The pipeline file for `provider`
+pub fn render(provider: CiProvider, options: &CiOptions) -> String {
+    let install = format!(
+        "cargo install apicurio-cli --version {} --locked",
+        env!("CARGO_PKG_VERSION")
+    );
+    let mut out = String::from(
+        "# Generated by `apicurio ci-setup`: checks that the lock file is current and\n\
+         # pulled schemas match it. Registry credentials referenced from\n\
+         # apicurioconfig.yaml (passwordEnv, tokenEnv, ...) must be set as CI secrets.\n",
+    );
+    match provider {
+        CiProvider::Github => {
+            out.push_str(
+                "name: apicurio\n\
+                 \n\
+                 on:\n  \
+                   pull_request:\n  \
+                   push:\n    \
+                     branches: [main]\n\
+                 \n\
+                 jobs:\n  \
+                   schemas:\n    \
+                     runs-on: ubuntu-latest\n    \
+                     steps:\n      \
+                       - uses: actions/checkout@v4\n      \
+                       - uses: actions/cache@v4\n        \
+                         with:\n          \
+                           path: ~/.cargo/bin/apicurio\n          \
+                           key: apicurio-cli-",
+            );
+            out.push_str(env!("CARGO_PKG_VERSION"));
+            out.push_str("\n      - name: Install apicurio-cli\n");
+            out.push_str(&format!("        run: command -v apicurio || {install}\n"));
+            for command in options.commands() {
+                out.push_str(&format!("      - run: {command}\n"));
+            }
+        }
+        CiProvider::Gitlab => {
+            out.push_str(
+                "apicurio:\n  \
+                   image: rust:1\n  \
+                   stage: test\n  \
+                   cache:\n    \
+                     key: apicurio-cli\n    \
+                     paths: [.cargo/bin]\n  \
+                   variables:\n    \
+                     CARGO_HOME: $CI_PROJECT_DIR/.cargo\n  \
+                   before_script:\n",
+            );
+            out.push_str(&format!("    - command -v apicurio || {install}\n"));
+            out.push_str("  script:\n");
+            for command in options.commands() {
+                out.push_str(&format!("    - {command}\n"));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gates_follow_config() {
+        let mut config = RepoConfig::default();
+        config.gitignore.ensure_committed = true;
+        config.provenance_header.protobuf = true;
+        let options = CiOptions::for_config(&config, true);
+        assert_eq!(
+            options.commands(),
+            vec![
+                "apicurio pull --frozen --ensure-committed",
+                "apicurio verify --ignore-header",
+                "apicurio status"
+            ]
+        );
+
+        let github = render(CiProvider::Github, &CiOptions::default());
+        let workflow: serde_yaml::Value = serde_yaml::from_str(&github).unwrap();
+        let steps = workflow["jobs"]["schemas"]["steps"].as_sequence().unwrap();
+        assert_eq!(steps.last().unwrap()["run"], "apicurio verify");
+
+        let gitlab = render(CiProvider::Gitlab, &options);
+        let job: serde_yaml::Value = serde_yaml::from_str(&gitlab).unwrap();
+        assert_eq!(job["apicurio"]["script"].as_sequence().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    commands::OutputFormat,
+    config::{load_global_config, load_repo_config, RepoConfig},
+    config_edit::{get_value, read_editable, set_value, unset_value, write_edited},
+    constants::APICURIO_CONFIG,
+    paths,
+};
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use serde_yaml::Value;
+use std::path::Path;
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Print the value at a dotted key path (e.g. dependencyDefaults.registry)
+    Get { key: String },
+    /// Set the value at a dotted key path, keeping comments and formatting
+    Set {
+        key: String,
+        /// Parsed as YAML, so `true`, `30` and `[a, b]` keep their types;
+        /// anything else is stored as a string
+        value: String,
+    },
+    /// Remove the value at a dotted key path
+    Unset { key: String },
+    /// Print the configuration
+    Show {
+        /// Print the merged config: includes, environment variables, global
+        /// registries and the active profile applied
+        #[arg(long)]
+        resolved: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+pub async fn run(cmd: ConfigCommands) -> Result<()> {
+    let path = paths::project_config();
+    match cmd {
+        ConfigCommands::Get { key } => {
+            let content = read_editable(&path)?;
+            let value = get_value(&content, &key)?
+                .ok_or_else(|| anyhow!("'{key}' is not set in {}", path.display()))?;
+            print_value(&value)?;
+        }
+        ConfigCommands::Set { key, value } => {
+            let content = read_editable(&path)?;
+            // Anything that is not valid YAML (e.g. `{artifactId}.proto`) is a plain string
+            let value: Value =
+                serde_yaml::from_str(&value).unwrap_or_else(|_| Value::String(value.clone()));
+            let updated = set_value(&content, &key, &value)?;
+            check_config(&updated, &key)?;
+            write_edited(&path, &updated)?;
+            println!("✅ Set {key}");
+        }
+        ConfigCommands::Unset { key } => {
+            let content = read_editable(&path)?;
+            let Some(updated) = unset_value(&content, &key)? else {
+                println!("'{key}' is not set");
+                return Ok(());
+            };
+            check_config(&updated, &key)?;
+            write_edited(&path, &updated)?;
+            println!("🗑️  Unset {key}");
+        }
+        ConfigCommands::Show { resolved, format } => {
+            let value = if resolved {
+                serde_yaml::to_value(resolved_config(&path)?)?
+            } else {
+                serde_yaml::from_str(&read_editable(&path)?)?
+            };
+            match format {
+                OutputFormat::Text => print!("{}", serde_yaml::to_string(&value)?),
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&value)?),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Refuse edits that would leave the config unreadable
+fn check_config(content: &str, key: &str) -> Result<()> {
+    let cfg: RepoConfig = serde_yaml::from_str(content)
+        .with_context(|| format!("'{key}' would make {APICURIO_CONFIG} invalid"))?;
+    cfg.validate_output_patterns()?;
+    cfg.validate_overrides()?;
+    Ok(())
+}
+
+/// The repo config with every registry source merged into `registries`
+fn resolved_config(path: &Path) -> Result<RepoConfig> {
+    let mut cfg = load_repo_config(path)?;
+    cfg.registries = cfg.merge_registries(load_global_config()?)?;
+    cfg.registries.sort_by(|a, b| a.name.cmp(&b.name));
+    // Already applied above
+    cfg.external_registries_file = None;
+    cfg.include.clear();
+    cfg.profiles.clear();
+    Ok(cfg)
+}
+
+fn print_value(value: &Value) -> Result<()> {
+    match value {
+        Value::String(s) => println!("{s}"),
+        Value::Bool(b) => println!("{b}"),
+        Value::Number(n) => println!("{n}"),
+        Value::Null => println!("null"),
+        other => print!("{}", serde_yaml::to_string(other)?),
+    }
+    Ok(())
+}
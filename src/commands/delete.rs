@@ -0,0 +1,140 @@
+use crate::{
+    config::{load_global_config, load_repo_config},
+    error::ApicurioError,
+    events::WarningSink,
+    identifier::{resolve_artifact, resolve_group},
+    paths,
+    registry::RegistryClient,
+};
+use anyhow::{anyhow, bail, Result};
+use dialoguer::Confirm;
+use std::{io::IsTerminal, sync::Arc};
+
+/// What `delete` removes from the registry
+#[derive(Debug, Clone, PartialEq)]
+enum DeleteTarget {
+    Group {
+        group_id: String,
+    },
+    Artifact {
+        group_id: String,
+        artifact_id: String,
+    },
+    Version {
+        group_id: String,
+        artifact_id: String,
+        version: String,
+    },
+}
+
+pub async fn run(identifier: String, recursive: bool, yes: bool) -> Result<()> {
+    let repo_cfg = load_repo_config(&paths::project_config())?;
+    let registries = repo_cfg.merge_registries(load_global_config()?)?;
+    let names: Vec<String> = registries.iter().map(|r| r.name.clone()).collect();
+
+    // `--recursive` deletes a whole group, so the identifier names a group
+    let (registry, target) = if recursive {
+        let (registry, group_id) = resolve_group(&identifier, &repo_cfg, &names)?;
+        (registry, DeleteTarget::Group { group_id })
+    } else {
+        let artifact = resolve_artifact(&identifier, &repo_cfg, &names)?;
+        let (group_id, artifact_id) = (artifact.group_id, artifact.artifact_id);
+        let target = match artifact.version {
+            Some(version) => DeleteTarget::Version {
+                group_id,
+                artifact_id,
+                version,
+            },
+            None => DeleteTarget::Artifact {
+                group_id,
+                artifact_id,
+            },
+        };
+        (artifact.registry, target)
+    };
+    let registry_cfg = registries
+        .iter()
+        .find(|r| r.name == registry)
+        .ok_or_else(|| anyhow!("Registry '{}' not found", registry))?;
+    let client = RegistryClient::new(registry_cfg)?.with_events(Arc::new(WarningSink));
+
+    let prompt = match &target {
+        DeleteTarget::Group { group_id } => {
+            let artifacts = client.list_artifacts(group_id).await?;
+            format!(
+                "Delete group {registry}/{group_id} and its {} artifact(s)?",
+                artifacts.len()
+            )
+        }
+        DeleteTarget::Artifact {
+            group_id,
+            artifact_id,
+        } => {
+            let versions = client.list_versions(group_id, artifact_id).await?;
+            format!(
+                "Delete artifact {registry}/{group_id}/{artifact_id} and its {} version(s)?",
+                versions.len()
+            )
+        }
+        DeleteTarget::Version {
+            group_id,
+            artifact_id,
+            version,
+        } => format!("Delete version {registry}/{group_id}/{artifact_id}@{version}?"),
+    };
+    if !yes {
+        if !std::io::stdin().is_terminal() {
+            bail!("{prompt} Pass --yes to delete without a terminal to confirm");
+        }
+        if !Confirm::new()
+            .with_prompt(prompt)
+            .default(false)
+            .interact()?
+        {
+            println!("Nothing was deleted");
+            return Ok(());
+        }
+    }
+
+    let result = match &target {
+        DeleteTarget::Group { group_id } => client.delete_group(group_id).await,
+        DeleteTarget::Artifact {
+            group_id,
+            artifact_id,
+        } => client.delete_artifact(group_id, artifact_id).await,
+        DeleteTarget::Version {
+            group_id,
+            artifact_id,
+            version,
+        } => client.delete_version(group_id, artifact_id, version).await,
+    };
+    match result {
+        Ok(()) => {}
+        // Registries refuse deletes unless apicurio.rest.deletion.*.enabled is set
+        Err(ApicurioError::Network {
+            status: Some(405), ..
+        }) => bail!(
+            "registry '{registry}' does not allow this delete; \
+             enable the matching apicurio.rest.deletion.*.enabled setting \
+             (e.g. with `apicurio admin set`)"
+        ),
+        Err(e) => return Err(e.into()),
+    }
+    println!("🗑️  Deleted {}", describe(&registry, &target));
+    Ok(())
+}
+
+fn describe(registry: &str, target: &DeleteTarget) -> String {
+    match target {
+        DeleteTarget::Group { group_id } => format!("group {registry}/{group_id}"),
+        DeleteTarget::Artifact {
+            group_id,
+            artifact_id,
+        } => format!("artifact {registry}/{group_id}/{artifact_id}"),
+        DeleteTarget::Version {
+            group_id,
+            artifact_id,
+            version,
+        } => format!("version {registry}/{group_id}/{artifact_id}@{version}"),
+    }
+}
@@ -0,0 +1,16 @@
+use crate::dev_server::DevServer;
+use anyhow::{Context, Result};
+
+/// Serve an in-memory registry on `host:port` until interrupted
+pub async fn run(host: String, port: u16) -> Result<()> {
+    let server = DevServer::start((host.as_str(), port))
+        .await
+        .with_context(|| format!("listening on {host}:{port}"))?;
+    let url = server.url();
+    println!("🚀 In-memory registry listening on {url} (data is lost on exit)");
+    println!("   Point a registry at it, e.g. in apicurioconfig.yaml:");
+    println!("     registries:\n       - name: dev\n         url: {url}");
+    println!("   Press Ctrl+C to stop");
+    server.wait().await;
+    Ok(())
+}
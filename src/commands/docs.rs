@@ -0,0 +1,430 @@
+use crate::{
+    api::Workspace,
+    commands::graph::{self, GraphFormat},
+    constants::APICURIO_LOCK,
+    lockfile::{find_reference, LockFile, LockedDependency},
+};
+use anyhow::{anyhow, Context, Result};
+use std::{collections::BTreeMap, fmt::Write, fs};
+
+/// Output format of `apicurio docs`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DocsFormat {
+    /// Markdown page with a Mermaid reference graph
+    #[default]
+    Markdown,
+    /// Standalone HTML page
+    Html,
+}
+
+/// One locked dependency with what the registry knows about it
+#[derive(Debug, Clone, PartialEq)]
+struct DocEntry {
+    dep: LockedDependency,
+    artifact_type: String,
+    /// Version description, or the artifact's when the version has none
+    description: Option<String>,
+    /// Artifact labels refined by the version labels
+    labels: BTreeMap<String, String>,
+}
+
+/// Describe every locked dependency, with its registry metadata, in one page
+pub async fn run(format: DocsFormat, out: Option<String>) -> Result<()> {
+    let workspace = Workspace::current()?;
+    let lock = LockFile::load(&workspace.lock_path())
+        .with_context(|| format!("reading {APICURIO_LOCK}; run `apicurio lock` first"))?;
+
+    let mut entries = Vec::new();
+    for dep in &lock.locked_dependencies {
+        let client = workspace
+            .client(&dep.registry)
+            .ok_or_else(|| anyhow!("Registry '{}' not found", dep.registry))?;
+        let artifact = client
+            .get_artifact_metadata(&dep.group_id, &dep.artifact_id)
+            .await
+            .with_context(|| format!("reading {}/{}", dep.group_id, dep.artifact_id))?;
+        let version = client
+            .get_version_metadata(&dep.group_id, &dep.artifact_id, &dep.resolved_version)
+            .await
+            .with_context(|| {
+                format!(
+                    "reading {}/{}@{}",
+                    dep.group_id, dep.artifact_id, dep.resolved_version
+                )
+            })?;
+        let mut labels: BTreeMap<String, String> =
+            artifact.labels.unwrap_or_default().into_iter().collect();
+        labels.extend(version.labels.unwrap_or_default());
+        entries.push(DocEntry {
+            dep: dep.clone(),
+            artifact_type: artifact.artifact_type,
+            description: version
+                .description
+                .or(artifact.description)
+                .filter(|d| !d.trim().is_empty()),
+            labels,
+        });
+    }
+
+    let rendered = match format {
+        DocsFormat::Markdown => render_markdown(&entries)?,
+        DocsFormat::Html => render_html(&entries)?,
+    };
+    match out {
+        Some(path) => {
+            fs::write(&path, rendered).with_context(|| format!("writing {path}"))?;
+            println!(
+                "✅ Wrote the documentation of {} dependencies to {path}",
+                entries.len()
+            );
+        }
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
+
+/// Entries `entry` references and entries referencing it; `deps` are the
+/// lock entries of `entries`, in the same order
+fn links<'a>(
+    entries: &'a [DocEntry],
+    deps: &[LockedDependency],
+    entry: &DocEntry,
+) -> (Vec<&'a DocEntry>, Vec<&'a DocEntry>) {
+    let position = |found: &LockedDependency| {
+        deps.iter()
+            .position(|d| std::ptr::eq(d, found))
+            .map(|i| &entries[i])
+    };
+    let references = entry
+        .dep
+        .references
+        .iter()
+        .filter_map(|r| find_reference(deps, &entry.dep, r))
+        .filter_map(position)
+        .collect();
+    let referenced_by = entries
+        .iter()
+        .zip(deps)
+        .filter(|(_, parent)| {
+            parent
+                .references
+                .iter()
+                .filter_map(|r| find_reference(deps, parent, r))
+                .any(|child| child.artifact_key() == entry.dep.artifact_key())
+        })
+        .map(|(e, _)| e)
+        .collect();
+    (references, referenced_by)
+}
+
+/// Heading anchor as GitHub generates it: lowercase, punctuation dropped,
+/// spaces turned into dashes
+fn anchor(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn kind(dep: &LockedDependency) -> &'static str {
+    if dep.is_transitive {
+        "transitive"
+    } else {
+        "direct"
+    }
+}
+
+fn render_markdown(entries: &[DocEntry]) -> Result<String> {
+    let deps: Vec<LockedDependency> = entries.iter().map(|e| e.dep.clone()).collect();
+    let cell = |value: &str| value.replace('|', "\\|");
+    let mut out = String::from("# Schema dependencies\n\n");
+    writeln!(
+        out,
+        "_Generated by `apicurio docs` from `{APICURIO_LOCK}`; run it again after `apicurio update`._\n"
+    )?;
+    out.push_str("| Dependency | Artifact | Version | Range | Kind | Output |\n");
+    out.push_str("|---|---|---|---|---|---|\n");
+    for entry in entries {
+        let dep = &entry.dep;
+        writeln!(
+            out,
+            "| [{}](#{}) | `{}/{}` | {} | `{}` | {} | `{}` |",
+            cell(&dep.name),
+            anchor(&dep.name),
+            dep.group_id,
+            dep.artifact_id,
+            dep.resolved_version,
+            cell(&dep.version_spec),
+            kind(dep),
+            dep.output_path
+        )?;
+    }
+
+    for entry in entries {
+        let dep = &entry.dep;
+        writeln!(out, "\n## {}\n", dep.name)?;
+        if let Some(description) = &entry.description {
+            writeln!(out, "{}\n", description.trim())?;
+        }
+        writeln!(
+            out,
+            "- **Artifact:** `{}/{}` in registry `{}` ({})",
+            dep.group_id, dep.artifact_id, dep.registry, entry.artifact_type
+        )?;
+        writeln!(
+            out,
+            "- **Version:** {} (range `{}`, {})",
+            dep.resolved_version,
+            dep.version_spec,
+            kind(dep)
+        )?;
+        writeln!(out, "- **Output:** `{}`", dep.output_path)?;
+        if !entry.labels.is_empty() {
+            let labels: Vec<String> = entry
+                .labels
+                .iter()
+                .map(|(k, v)| format!("`{k}={v}`"))
+                .collect();
+            writeln!(out, "- **Labels:** {}", labels.join(", "))?;
+        }
+        let (references, referenced_by) = links(entries, &deps, entry);
+        let list = |entries: Vec<&DocEntry>| {
+            entries
+                .iter()
+                .map(|e| format!("[{}](#{})", e.dep.name, anchor(&e.dep.name)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        if !references.is_empty() {
+            writeln!(out, "- **References:** {}", list(references))?;
+        }
+        if !referenced_by.is_empty() {
+            writeln!(out, "- **Referenced by:** {}", list(referenced_by))?;
+        }
+    }
+
+    out.push_str("\n## Reference graph\n\n```mermaid\n");
+    out.push_str(&graph::render(&deps, GraphFormat::Mermaid)?);
+    out.push_str("```\n");
+    Ok(out)
+}
+
+fn render_html(entries: &[DocEntry]) -> Result<String> {
+    let deps: Vec<LockedDependency> = entries.iter().map(|e| e.dep.clone()).collect();
+    let e = html_escape;
+    let mut out = String::from(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Schema dependencies</title>\n</head>\n<body>\n<h1>Schema dependencies</h1>\n",
+    );
+    writeln!(
+        out,
+        "<p><em>Generated by <code>apicurio docs</code> from <code>{APICURIO_LOCK}</code>.</em></p>"
+    )?;
+    out.push_str(
+        "<table>\n<tr><th>Dependency</th><th>Artifact</th><th>Version</th>\
+         <th>Range</th><th>Kind</th><th>Output</th></tr>\n",
+    );
+    for entry in entries {
+        let dep = &entry.dep;
+        writeln!(
+            out,
+            "<tr><td><a href=\"#{}\">{}</a></td><td><code>{}/{}</code></td><td>{}</td>\
+             <td><code>{}</code></td><td>{}</td><td><code>{}</code></td></tr>",
+            e(&anchor(&dep.name)),
+            e(&dep.name),
+            e(&dep.group_id),
+            e(&dep.artifact_id),
+            e(&dep.resolved_version),
+            e(&dep.version_spec),
+            kind(dep),
+            e(&dep.output_path)
+        )?;
+    }
+    out.push_str("</table>\n");
+
+    for entry in entries {
+        let dep = &entry.dep;
+        writeln!(
+            out,
+            "<h2 id=\"{}\">{}</h2>",
+            e(&anchor(&dep.name)),
+            e(&dep.name)
+        )?;
+        if let Some(description) = &entry.description {
+            writeln!(out, "<p>{}</p>", e(description.trim()))?;
+        }
+        out.push_str("<ul>\n");
+        writeln!(
+            out,
+            "<li><strong>Artifact:</strong> <code>{}/{}</code> in registry <code>{}</code> ({})</li>",
+            e(&dep.group_id),
+            e(&dep.artifact_id),
+            e(&dep.registry),
+            e(&entry.artifact_type)
+        )?;
+        writeln!(
+            out,
+            "<li><strong>Version:</strong> {} (range <code>{}</code>, {})</li>",
+            e(&dep.resolved_version),
+            e(&dep.version_spec),
+            kind(dep)
+        )?;
+        writeln!(
+            out,
+            "<li><strong>Output:</strong> <code>{}</code></li>",
+            e(&dep.output_path)
+        )?;
+        if !entry.labels.is_empty() {
+            let labels: Vec<String> = entry
+                .labels
+                .iter()
+                .map(|(k, v)| format!("<code>{}={}</code>", e(k), e(v)))
+                .collect();
+            writeln!(
+                out,
+                "<li><strong>Labels:</strong> {}</li>",
+                labels.join(", ")
+            )?;
+        }
+        let (references, referenced_by) = links(entries, &deps, entry);
+        let list = |entries: Vec<&DocEntry>| {
+            entries
+                .iter()
+                .map(|d| {
+                    format!(
+                        "<a href=\"#{}\">{}</a>",
+                        e(&anchor(&d.dep.name)),
+                        e(&d.dep.name)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        if !references.is_empty() {
+            writeln!(
+                out,
+                "<li><strong>References:</strong> {}</li>",
+                list(references)
+            )?;
+        }
+        if !referenced_by.is_empty() {
+            writeln!(
+                out,
+                "<li><strong>Referenced by:</strong> {}</li>",
+                list(referenced_by)
+            )?;
+        }
+        out.push_str("</ul>\n");
+    }
+
+    // The graph as nested lists, starting from the direct dependencies
+    out.push_str("<h2 id=\"reference-graph\">Reference graph</h2>\n<ul>\n");
+    for entry in entries.iter().filter(|e| !e.dep.is_transitive) {
+        render_tree(&mut out, entries, &deps, entry, &mut Vec::new())?;
+    }
+    out.push_str("</ul>\n</body>\n</html>\n");
+    Ok(out)
+}
+
+/// One `<li>` for `entry` with its references nested below; `path` holds the
+/// entries above it, so a reference cycle stops instead of recursing forever
+fn render_tree<'a>(
+    out: &mut String,
+    entries: &'a [DocEntry],
+    deps: &[LockedDependency],
+    entry: &'a DocEntry,
+    path: &mut Vec<String>,
+) -> Result<()> {
+    let dep = &entry.dep;
+    write!(
+        out,
+        "<li><a href=\"#{}\">{}</a> {}",
+        html_escape(&anchor(&dep.name)),
+        html_escape(&dep.name),
+        html_escape(&dep.resolved_version)
+    )?;
+    let key = dep.artifact_key();
+    let (references, _) = links(entries, deps, entry);
+    if path.contains(&key) || references.is_empty() {
+        out.push_str("</li>\n");
+        return Ok(());
+    }
+    path.push(key);
+    out.push_str("\n<ul>\n");
+    for reference in references {
+        render_tree(out, entries, deps, reference, path)?;
+    }
+    out.push_str("</ul></li>\n");
+    path.pop();
+    Ok(())
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, transitive: bool, references: &[&str]) -> DocEntry {
+        DocEntry {
+            dep: LockedDependency {
+                name: name.to_string(),
+                registry: "main".to_string(),
+                resolved_version: "1.2.0".to_string(),
+                download_url: String::new(),
+                registry_url: None,
+                sha256: String::new(),
+                output_path: format!("protos/{}.proto", name.replace('/', "_")),
+                group_id: "com.example".to_string(),
+                artifact_id: name.rsplit('/').next().unwrap().to_string(),
+                version_spec: if transitive { "1.2.0" } else { "^1" }.to_string(),
+                is_transitive: transitive,
+                references: references.iter().map(|r| r.to_string()).collect(),
+                reference_names: Default::default(),
+                binary: false,
+                avro_fingerprint: None,
+                content_type: None,
+                member_of: None,
+            },
+            artifact_type: "PROTOBUF".to_string(),
+            description: None,
+            labels: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_docs_link_references_both_ways() {
+        let mut users = entry("users", false, &["com.example/common"]);
+        users.description = Some("Users & <accounts>".to_string());
+        users
+            .labels
+            .insert("owner".to_string(), "team-a".to_string());
+        let entries = vec![users, entry("com.example/common", true, &[])];
+
+        let markdown = render_markdown(&entries).unwrap();
+        assert!(markdown.contains(
+            "| [users](#users) | `com.example/users` | 1.2.0 | `^1` | direct | `protos/users.proto` |"
+        ));
+        assert!(markdown.contains("- **Labels:** `owner=team-a`\n"));
+        assert!(markdown.contains("- **References:** [com.example/common](#comexamplecommon)\n"));
+        assert!(markdown.contains("- **Referenced by:** [users](#users)\n"));
+        assert!(markdown.contains("```mermaid\ngraph LR\n"));
+
+        let html = render_html(&entries).unwrap();
+        assert!(html.contains("<p>Users &amp; &lt;accounts&gt;</p>"));
+        assert!(html.contains(
+            "<li><a href=\"#users\">users</a> 1.2.0\n<ul>\n\
+             <li><a href=\"#comexamplecommon\">com.example/common</a> 1.2.0</li>\n</ul></li>"
+        ));
+    }
+}
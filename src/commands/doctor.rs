@@ -1,73 +1,707 @@
 use crate::{
-    config::{load_global_config, load_repo_config, GlobalConfig},
+    commands::OutputFormat,
+    config::{
+        find_unset_placeholders, global_registries_path, load_global_config, load_repo_config,
+        read_merged_config, save_registries_file, GlobalConfig, RegistryConfig, RepoConfig,
+    },
+    config_edit::{
+        list_named_entries, read_editable, remove_named_entry, upsert_named_entry, write_edited,
+    },
     constants::{APICURIO_CONFIG, APICURIO_LOCK},
-    lockfile::LockFile,
-    registry::RegistryClient,
+    diagnostics::{diagnose_registry, CheckStatus, RegistryDiagnostics},
+    lockfile::{find_output_conflicts, LockFile, LockedDependency},
+    paths,
 };
 use anyhow::{Context, Result};
+use dialoguer::Confirm;
 use semver::Version;
-use std::{collections::HashSet, fs, path::PathBuf};
+use serde::Serialize;
+use std::{collections::HashSet, fs, path::Path};
 
-pub async fn run() -> Result<()> {
-    // 1) load repo + external + global, check duplicate names
-    let repo_cfg = load_repo_config(&PathBuf::from(APICURIO_CONFIG))?;
-    let global_cfg = load_global_config()?;
-    let mut seen = HashSet::new();
+/// How serious a doctor finding is; only errors make `doctor` fail
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn icon(&self) -> &'static str {
+        match self {
+            Severity::Info => "ℹ️ ",
+            Severity::Warning => "⚠️ ",
+            Severity::Error => "❌",
+        }
+    }
+}
+
+/// Area a doctor finding belongs to, in the order they are printed
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum Category {
+    Config,
+    Environment,
+    Registries,
+    Network,
+    Dependencies,
+    OutputPaths,
+    LockFile,
+}
 
-    for r in &repo_cfg.registries {
-        if !seen.insert(r.name.clone()) {
-            return Err(anyhow::anyhow!("duplicate registry '{}'", r.name));
+impl Category {
+    fn title(&self) -> &'static str {
+        match self {
+            Category::Config => "Configuration",
+            Category::Environment => "Environment",
+            Category::Registries => "Registries",
+            Category::Network => "Network",
+            Category::Dependencies => "Dependencies",
+            Category::OutputPaths => "Output paths",
+            Category::LockFile => "Lock file",
         }
     }
-    if let Some(path) = &repo_cfg.external_registries_file {
-        let ext_content = fs::read_to_string(path)?;
-        let ext: GlobalConfig = serde_yaml::from_str(&ext_content)?;
-        for r in ext.registries.into_iter() {
-            if !seen.insert(r.name.clone()) {
-                return Err(anyhow::anyhow!("duplicate registry '{}'", r.name));
+}
+
+/// One problem (or note) found by `doctor`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub category: Category,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Everything `doctor` checked, collected instead of stopping at the first problem
+#[derive(Serialize, Debug, Default)]
+pub struct DoctorReport {
+    pub findings: Vec<Finding>,
+    /// Layer-by-layer network diagnostics of each registry
+    pub registries: Vec<RegistryDiagnostics>,
+}
+
+impl DoctorReport {
+    fn add(&mut self, category: Category, severity: Severity, message: impl Into<String>) {
+        self.findings.push(Finding {
+            category,
+            severity,
+            message: message.into(),
+        });
+    }
+
+    fn error(&mut self, category: Category, message: impl Into<String>) {
+        self.add(category, Severity::Error, message);
+    }
+
+    fn warning(&mut self, category: Category, message: impl Into<String>) {
+        self.add(category, Severity::Warning, message);
+    }
+
+    fn info(&mut self, category: Category, message: impl Into<String>) {
+        self.add(category, Severity::Info, message);
+    }
+
+    /// Number of findings with the given severity
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+
+    /// Print the findings grouped by category
+    fn print(&self) {
+        let mut categories: Vec<Category> = self.findings.iter().map(|f| f.category).collect();
+        categories.sort();
+        categories.dedup();
+        for category in categories {
+            println!("{}", category.title());
+            for finding in self.findings.iter().filter(|f| f.category == category) {
+                println!("  {} {}", finding.severity.icon(), finding.message);
             }
         }
+        let (errors, warnings) = (self.count(Severity::Error), self.count(Severity::Warning));
+        if errors == 0 && warnings == 0 {
+            println!("✅ doctor checks passed");
+        } else {
+            println!("{errors} error(s), {warnings} warning(s)");
+        }
     }
-    for r in &global_cfg.registries {
-        if !seen.insert(r.name.clone()) {
-            return Err(anyhow::anyhow!("duplicate registry '{}'", r.name));
+}
+
+pub async fn run(fix: bool, format: OutputFormat) -> Result<()> {
+    if fix {
+        fix_problems().await?;
+    }
+
+    let report = check(format == OutputFormat::Text).await;
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Text => report.print(),
+    }
+    // Warnings and notes alone do not fail the command
+    if report.count(Severity::Error) > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Report credential environment variables of a registry that are unset or empty
+fn check_credential_env(report: &mut DoctorReport, registry: &RegistryConfig) {
+    for (field, var) in registry.auth.env_vars() {
+        match std::env::var_os(var) {
+            None => report.error(
+                Category::Environment,
+                format!("registry '{}': {var} ({field}) is not set", registry.name),
+            ),
+            Some(value) if value.is_empty() => report.error(
+                Category::Environment,
+                format!("registry '{}': {var} ({field}) is empty", registry.name),
+            ),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Run every check, printing the network diagnostics as they complete if `live`
+pub async fn check(live: bool) -> DoctorReport {
+    let mut report = DoctorReport::default();
+
+    // 1) load repo + global config (validating output patterns), after checking
+    // placeholders since an unset variable often is why loading fails
+    if let Ok(unset) = find_unset_placeholders(&paths::project_config()) {
+        for placeholder in unset {
+            report.error(
+                Category::Environment,
+                format!(
+                    "${{{var}}} at {}:{} has no default and {var} is not set",
+                    placeholder.file.display(),
+                    placeholder.line,
+                    var = placeholder.var
+                ),
+            );
+        }
+    }
+    let repo_cfg = match load_repo_config(&paths::project_config()) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            report.error(Category::Config, format!("{APICURIO_CONFIG}: {e:#}"));
+            return report;
+        }
+    };
+    let global_cfg = match load_global_config() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            report.error(Category::Config, format!("global registries: {e:#}"));
+            GlobalConfig::default()
+        }
+    };
+
+    // 2) registry names must be unique within a file; later layers override earlier ones
+    let mut layers = vec![(APICURIO_CONFIG.to_string(), repo_cfg.registries.clone())];
+    if let Some(path) = repo_cfg
+        .external_registries_file
+        .as_ref()
+        .filter(|p| !p.is_empty())
+    {
+        let external = fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_yaml::from_str::<GlobalConfig>(&content)?));
+        match external {
+            Ok(ext) => layers.push((path.clone(), ext.registries)),
+            Err(e) => report.error(
+                Category::Registries,
+                format!("external registries file {path}: {e:#}"),
+            ),
+        }
+    }
+    layers.push((
+        global_registries_path().display().to_string(),
+        global_cfg.registries.clone(),
+    ));
+    let mut defined_in: Vec<(String, &str)> = Vec::new();
+    for (file, registries) in &layers {
+        let mut seen = HashSet::new();
+        for r in registries {
+            if !seen.insert(r.name.as_str()) {
+                report.error(
+                    Category::Registries,
+                    format!("duplicate registry '{}' in {file}", r.name),
+                );
+            } else if let Some((_, first)) = defined_in.iter().find(|(n, _)| *n == r.name) {
+                report.warning(
+                    Category::Registries,
+                    format!(
+                        "registry '{}' is defined in both {first} and {file}; {first} wins",
+                        r.name
+                    ),
+                );
+            } else {
+                defined_in.push((r.name.clone(), file));
+            }
         }
     }
+    let known: HashSet<&str> = defined_in.iter().map(|(n, _)| n.as_str()).collect();
 
-    // 2) merge and try to ping each registry
-    let merged = repo_cfg.merge_registries(global_cfg.clone())?;
-    for r in &merged {
-        let client = RegistryClient::new(r)?;
-        client
-            .get_system_info()
-            .await
-            .with_context(|| format!("cannot reach registry '{}'", r.name))?;
+    // 3) merge, check credential variables, then diagnose each registry layer by layer
+    match repo_cfg.merge_registries(global_cfg) {
+        Ok(merged) => {
+            for r in &merged {
+                check_credential_env(&mut report, r);
+            }
+            for r in &merged {
+                let diagnostics = diagnose_registry(r).await;
+                if live {
+                    print!("{diagnostics}");
+                }
+                for check in &diagnostics.checks {
+                    let message = format!("'{}' {}: {}", r.name, check.name, check.detail);
+                    match check.status {
+                        CheckStatus::Fail => {
+                            report.error(Category::Network, format!("cannot reach {message}"))
+                        }
+                        CheckStatus::Warn => report.warning(Category::Network, message),
+                        CheckStatus::Ok | CheckStatus::Skipped => {}
+                    }
+                }
+                report.registries.push(diagnostics);
+            }
+        }
+        Err(e) => report.error(Category::Registries, format!("{e:#}")),
     }
 
-    // 3) check each dependency’s semver & registry existence
-    for dep in repo_cfg.dependencies_with_defaults()? {
-        if !seen.contains(&dep.registry) {
-            return Err(anyhow::anyhow!(
-                "dependency '{}' references unknown registry '{}'",
-                dep.name,
-                dep.registry
-            ));
+    // 4) check each dependency’s semver & registry existence
+    match repo_cfg.dependencies_with_defaults() {
+        Ok(dependencies) => {
+            for dep in &dependencies {
+                if !known.contains(dep.registry.as_str()) {
+                    report.error(
+                        Category::Dependencies,
+                        format!(
+                            "dependency '{}' references unknown registry '{}'",
+                            dep.name, dep.registry
+                        ),
+                    );
+                }
+            }
+            report.info(
+                Category::Dependencies,
+                format!("dependencies configured: {}", dependencies.len()),
+            );
         }
+        Err(e) => report.error(Category::Dependencies, format!("{e:#}")),
+    }
+
+    // 5) explicit output paths and overrides must not overlap
+    let explicit_outputs = repo_cfg
+        .dependencies
+        .iter()
+        .filter_map(|d| Some((d.name.as_str(), d.output_path.as_deref()?)))
+        .chain(
+            repo_cfg
+                .reference_resolution
+                .output_overrides
+                .iter()
+                .filter_map(|(key, path)| Some((key.as_str(), path.as_deref()?))),
+        );
+    for conflict in find_output_conflicts(explicit_outputs) {
+        report.error(
+            Category::OutputPaths,
+            format!("overlapping output paths in {APICURIO_CONFIG}: {conflict}"),
+        );
     }
 
-    // 4) check lockfile semantic
-    let lf = LockFile::load(&PathBuf::from(APICURIO_LOCK)).context("loading lockfile")?;
+    // 6) check lockfile semantic
+    let lock_path = paths::project_lock();
+    if !lock_path.exists() {
+        report.warning(
+            Category::LockFile,
+            format!("no {APICURIO_LOCK}; run `apicurio lock`"),
+        );
+        return report;
+    }
+    let lf = match LockFile::load(&lock_path) {
+        Ok(lf) => lf,
+        Err(e) => {
+            report.error(Category::LockFile, format!("loading lockfile: {e:#}"));
+            return report;
+        }
+    };
+    // outputs generated from patterns only become visible once resolved
+    for conflict in find_output_conflicts(
+        lf.locked_dependencies
+            .iter()
+            .map(|d| (d.name.as_str(), d.output_path.as_str())),
+    ) {
+        report.error(
+            Category::OutputPaths,
+            format!("overlapping output paths in {APICURIO_LOCK}: {conflict}"),
+        );
+    }
+    for ld in unresolved_output_paths(&repo_cfg, &lf) {
+        report.warning(
+            Category::OutputPaths,
+            if ld.output_path.is_empty() {
+                format!("'{}' has no output path in {APICURIO_LOCK}; set outputPath", ld.name)
+            } else {
+                format!(
+                    "output path '{}' of '{}' did not fully resolve from the output patterns; set outputPath",
+                    ld.output_path, ld.name
+                )
+            },
+        );
+    }
     for ld in &lf.locked_dependencies {
-        if !seen.contains(&ld.registry) {
-            return Err(anyhow::anyhow!(
-                "lockfile references unknown registry '{}'",
-                ld.registry
-            ));
+        if !known.contains(ld.registry.as_str()) {
+            report.error(
+                Category::LockFile,
+                format!(
+                    "lock entry '{}' references unknown registry '{}'",
+                    ld.name, ld.registry
+                ),
+            );
+        }
+        if Version::parse(&ld.resolved_version).is_err() {
+            report.error(
+                Category::LockFile,
+                format!(
+                    "invalid version '{}' in lock for '{}'",
+                    ld.resolved_version, ld.name
+                ),
+            );
         }
-        let _ = Version::parse(&ld.resolved_version)
-            .with_context(|| format!("invalid version in lock for '{}'", ld.name))?;
     }
+    report.info(
+        Category::LockFile,
+        format!("locked entries: {}", lf.locked_dependencies.len()),
+    );
+
+    report
+}
+
+/// Repair the problems doctor knows how to fix safely
+async fn fix_problems() -> Result<()> {
+    let config_path = paths::project_config();
+    let lock_path = paths::project_lock();
 
-    println!("✅ doctor checks passed");
+    fix_duplicate_registries(&config_path)?;
+    fix_missing_output_paths(&config_path, &lock_path)?;
+    prune_lock_entries(&config_path, &lock_path)?;
+
+    // Regenerate the lock if it is missing or no longer matches the config
+    let repo_cfg = load_repo_config(&config_path)?;
+    let config_hash =
+        LockFile::compute_config_hash(&read_merged_config(&config_path)?, &repo_cfg.dependencies);
+    let stale = match LockFile::load(&lock_path) {
+        Ok(lock) => !lock.is_compatible_with_config(&config_hash),
+        Err(_) => true,
+    };
+    if stale {
+        println!("🔧 Regenerating stale lock file");
+        crate::commands::lock::run(Default::default(), Default::default()).await?;
+    }
     Ok(())
 }
+
+/// Drop repeated registry definitions, keeping the first one, and repo-local
+/// registries that are identical to a global definition
+fn fix_duplicate_registries(config_path: &Path) -> Result<()> {
+    let content = read_editable(config_path)?;
+    let local: Vec<RegistryConfig> = list_named_entries(&content, "registries")?
+        .into_iter()
+        .map(serde_yaml::from_value)
+        .collect::<Result<_, _>>()?;
+    let global_path = global_registries_path();
+    let mut global_cfg = load_global_config()?;
+
+    let mut seen = HashSet::new();
+    let repeated_local: Vec<String> = local
+        .iter()
+        .filter(|r| !seen.insert(r.name.clone()))
+        .map(|r| r.name.clone())
+        .collect();
+    let redundant_local: Vec<String> = local
+        .iter()
+        .filter(|r| global_cfg.registries.contains(r))
+        .map(|r| r.name.clone())
+        .collect();
+
+    let mut seen = HashSet::new();
+    let global_before = global_cfg.registries.len();
+    global_cfg
+        .registries
+        .retain(|r| seen.insert(r.name.clone()));
+    let global_changed = global_cfg.registries.len() != global_before;
+
+    if repeated_local.is_empty() && redundant_local.is_empty() && !global_changed {
+        return Ok(());
+    }
+    for name in &repeated_local {
+        println!("⚠️  Registry '{name}' is defined more than once in {APICURIO_CONFIG}");
+    }
+    for name in &redundant_local {
+        println!("⚠️  Registry '{name}' in {APICURIO_CONFIG} is identical to the global one");
+    }
+    if global_changed {
+        println!(
+            "⚠️  {} contains duplicate registry definitions",
+            global_path.display()
+        );
+    }
+    if !confirm("Normalize these registry definitions?")? {
+        return Ok(());
+    }
+
+    let mut updated = content;
+    for name in repeated_local.iter().chain(&redundant_local) {
+        let kept = local.iter().find(|r| &r.name == name).cloned();
+        while let Some(next) = remove_named_entry(&updated, "registries", name)? {
+            updated = next;
+        }
+        if let Some(kept) = kept.filter(|_| !redundant_local.contains(name)) {
+            updated = upsert_named_entry(&updated, "registries", &serde_yaml::to_value(kept)?)?;
+        }
+    }
+    write_edited(config_path, &updated)?;
+    if global_changed {
+        save_registries_file(&global_cfg, &global_path)?;
+    }
+    println!("🔧 Normalized registry definitions");
+    Ok(())
+}
+
+/// Direct dependencies placed by `dependencyDefaults.outputPatterns` whose
+/// locked output path is missing or did not fully resolve, e.g. an empty
+/// segment left by a `labels.<key>` the artifact does not carry
+fn unresolved_output_paths<'a>(
+    repo_cfg: &RepoConfig,
+    lock: &'a LockFile,
+) -> Vec<&'a LockedDependency> {
+    let pattern_placed: HashSet<&str> = repo_cfg
+        .dependencies
+        .iter()
+        .filter(|d| d.output_path.is_none())
+        .map(|d| d.name.as_str())
+        .collect();
+    lock.locked_dependencies
+        .iter()
+        .filter(|d| !d.is_transitive && pattern_placed.contains(d.name.as_str()))
+        .filter(|d| {
+            let path = d.output_path.trim_start_matches('/');
+            path.is_empty() || path.split('/').any(str::is_empty)
+        })
+        .collect()
+}
+
+/// Pin the output path of the dependencies doctor reports as unresolved to
+/// the locked one without its empty segments, after confirmation
+fn fix_missing_output_paths(config_path: &Path, lock_path: &Path) -> Result<()> {
+    let Ok(lock) = LockFile::load(lock_path) else {
+        return Ok(());
+    };
+    let repo_cfg = load_repo_config(config_path)?;
+    let paths: Vec<(String, String)> = unresolved_output_paths(&repo_cfg, &lock)
+        .into_iter()
+        .filter(|d| !d.output_path.is_empty())
+        .map(|d| {
+            let absolute = if d.output_path.starts_with('/') {
+                "/"
+            } else {
+                ""
+            };
+            let segments: Vec<&str> = d.output_path.split('/').filter(|s| !s.is_empty()).collect();
+            (d.name.clone(), format!("{absolute}{}", segments.join("/")))
+        })
+        .collect();
+    if paths.is_empty() {
+        return Ok(());
+    }
+    for (name, path) in &paths {
+        println!("⚠️  Output path of '{name}' did not fully resolve; it would be set to {path}");
+    }
+    if !confirm("Set these output paths?")? {
+        return Ok(());
+    }
+
+    let updated = set_output_paths(&read_editable(config_path)?, &paths)?;
+    write_edited(config_path, &updated)?;
+    println!("🔧 Set {} output path(s)", paths.len());
+    Ok(())
+}
+
+/// Set `outputPath` of the named dependencies in the config content
+fn set_output_paths(content: &str, paths: &[(String, String)]) -> Result<String> {
+    let mut content = content.to_string();
+    for mut entry in list_named_entries(&content, "dependencies")? {
+        let Some(name) = entry.get("name").and_then(|n| n.as_str()) else {
+            continue;
+        };
+        let Some((_, path)) = paths.iter().find(|(n, _)| n == name) else {
+            continue;
+        };
+        let path = path.clone();
+        if let Some(map) = entry.as_mapping_mut() {
+            map.insert("outputPath".into(), path.into());
+        }
+        content = upsert_named_entry(&content, "dependencies", &entry)?;
+    }
+    Ok(content)
+}
+
+/// Remove lock entries for dependencies that are no longer in the config
+fn prune_lock_entries(config_path: &Path, lock_path: &Path) -> Result<()> {
+    let Ok(mut lock) = LockFile::load(lock_path) else {
+        return Ok(());
+    };
+    let repo_cfg = load_repo_config(config_path)?;
+    let configured: HashSet<&str> = repo_cfg
+        .dependencies
+        .iter()
+        .map(|d| d.name.as_str())
+        .collect();
+    let removed: Vec<String> = lock
+        .locked_dependencies
+        .iter()
+        .filter(|d| {
+            !d.is_transitive
+                && !configured.contains(d.name.as_str())
+                && !d
+                    .member_of
+                    .as_deref()
+                    .is_some_and(|group| configured.contains(group))
+        })
+        .map(|d| d.name.clone())
+        .collect();
+    if removed.is_empty() {
+        return Ok(());
+    }
+
+    for name in &removed {
+        println!("🔧 Removing lock entry for deleted dependency '{name}'");
+        lock.replace_dependency_closure(name, Vec::new());
+    }
+    lock.save(lock_path)
+        .with_context(|| format!("writing {}", lock_path.display()))?;
+    Ok(())
+}
+
+fn confirm(prompt: &str) -> Result<bool> {
+    Ok(Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_counts_and_json() {
+        let mut report = DoctorReport::default();
+        report.error(Category::Registries, "duplicate registry 'a'");
+        report.warning(Category::Network, "'a' Auth: 404");
+        report.info(Category::LockFile, "locked entries: 2");
+        report.error(Category::OutputPaths, "overlapping output paths");
+        assert_eq!(report.count(Severity::Error), 2);
+        assert_eq!(report.count(Severity::Warning), 1);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["findings"][3]["category"], "outputPaths");
+        assert_eq!(json["findings"][3]["severity"], "error");
+        assert_eq!(json["registries"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_credential_env_findings() {
+        std::env::set_var("APICURIO_DOCTOR_TEST_EMPTY", "");
+        let mut report = DoctorReport::default();
+        for (name, var) in [
+            ("unset", "APICURIO_DOCTOR_TEST_UNSET"),
+            ("empty", "APICURIO_DOCTOR_TEST_EMPTY"),
+        ] {
+            let registry = RegistryConfig {
+                name: name.to_string(),
+                auth: crate::config::AuthConfig::Bearer {
+                    token_env: var.to_string(),
+                    secret_ref: None,
+                },
+                ..Default::default()
+            };
+            check_credential_env(&mut report, &registry);
+        }
+        let messages: Vec<&str> = report.findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "registry 'unset': APICURIO_DOCTOR_TEST_UNSET (tokenEnv) is not set",
+                "registry 'empty': APICURIO_DOCTOR_TEST_EMPTY (tokenEnv) is empty",
+            ]
+        );
+        assert!(report
+            .findings
+            .iter()
+            .all(|f| f.category == Category::Environment));
+    }
+
+    #[test]
+    fn test_fix_only_fills_unresolved_output_paths() {
+        let content = r#"registries:
+  - name: prod
+    url: http://localhost:8080
+dependencyDefaults:
+  registry: prod
+dependencies:
+  - name: users
+    version: ^1
+  - name: orders
+    version: ^1
+"#;
+        let repo_cfg: RepoConfig = serde_yaml::from_str(content).unwrap();
+        let locked = |name: &str, output_path: &str| LockedDependency {
+            name: name.to_string(),
+            registry: "prod".to_string(),
+            resolved_version: "1.0.0".to_string(),
+            download_url: String::new(),
+            registry_url: None,
+            sha256: String::new(),
+            output_path: output_path.to_string(),
+            group_id: "default".to_string(),
+            artifact_id: name.to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
+        };
+        let lock = LockFile::new(
+            vec![
+                locked("users", "protos/users.proto"),
+                locked("orders", "protos//orders.proto"),
+            ],
+            String::new(),
+        );
+
+        let unresolved: Vec<&str> = unresolved_output_paths(&repo_cfg, &lock)
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(unresolved, vec!["orders"]);
+
+        let fixed = set_output_paths(
+            content,
+            &[("orders".to_string(), "protos/orders.proto".to_string())],
+        )
+        .unwrap();
+        let fixed: RepoConfig = serde_yaml::from_str(&fixed).unwrap();
+        // users still follows the output patterns
+        assert_eq!(fixed.dependencies[0].output_path, None);
+        assert_eq!(
+            fixed.dependencies[1].output_path.as_deref(),
+            Some("protos/orders.proto")
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{
+    api::Workspace,
+    commands::OutputFormat,
+    duplicates::{self, Entry},
+    events::WarningSink,
+    local_refs,
+    lockfile::LockFile,
+    output_path, provenance,
+};
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{fs, sync::Arc};
+
+/// Report schemas registered more than once under different coordinates
+///
+/// Compares the locked dependencies, using their pulled files for the
+/// normalized comparison, and the latest version of every artifact of the
+/// `registries` to scan.
+pub async fn run(registries: Vec<String>, format: OutputFormat) -> Result<()> {
+    let workspace = Workspace::current()?.with_events(Arc::new(WarningSink));
+    let lock = match LockFile::load(&workspace.lock_path()) {
+        Ok(lock) => Some(lock),
+        Err(_) if !registries.is_empty() => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut entries = Vec::new();
+    let locked = lock
+        .as_ref()
+        .map(|l| l.locked_dependencies.as_slice())
+        .unwrap_or_default();
+    for dependency in locked {
+        let file = output_path::native(workspace.root(), &dependency.output_path);
+        let normalized = fs::read(&file)
+            .ok()
+            .filter(|_| !dependency.binary)
+            .and_then(|data| {
+                let data = provenance::strip_header(&data);
+                let data = local_refs::to_registry(locked, dependency, &data);
+                duplicates::normalize(&dependency.output_path, &data)
+            });
+        entries.push(Entry {
+            registry: dependency.registry.clone(),
+            group_id: dependency.group_id.clone(),
+            artifact_id: dependency.artifact_id.clone(),
+            version: dependency.resolved_version.clone(),
+            output_path: Some(dependency.output_path.clone()),
+            sha256: dependency.sha256.clone(),
+            normalized,
+        });
+    }
+
+    for name in &registries {
+        let client = workspace
+            .client(name)
+            .ok_or_else(|| anyhow!("Registry '{name}' not found"))?;
+        for group_id in client.list_groups().await? {
+            for artifact_id in client.list_artifacts(&group_id).await? {
+                let versions = client.list_versions(&group_id, &artifact_id).await?;
+                let Some(version) = versions.iter().max().map(|v| v.to_string()) else {
+                    continue;
+                };
+                let known = entries.iter().any(|e| {
+                    e.registry == *name
+                        && e.group_id == group_id
+                        && e.artifact_id == artifact_id
+                        && e.version == version
+                });
+                if known {
+                    continue;
+                }
+                let metadata = client
+                    .get_artifact_metadata(&group_id, &artifact_id)
+                    .await?;
+                let content = client
+                    .get_version_bytes(&group_id, &artifact_id, &version)
+                    .await?;
+                let path = format!(
+                    "{artifact_id}.{}",
+                    output_path::extension_for_type(&metadata.artifact_type)
+                );
+                entries.push(Entry {
+                    registry: name.clone(),
+                    group_id: group_id.clone(),
+                    artifact_id,
+                    version,
+                    output_path: None,
+                    sha256: hex::encode(Sha256::digest(&content)),
+                    normalized: duplicates::normalize(&path, &content),
+                });
+            }
+        }
+    }
+
+    let found = duplicates::find(&entries);
+    if format == OutputFormat::Json {
+        let out = json!({ "compared": entries.len(), "duplicates": found });
+        println!("{}", serde_json::to_string_pretty(&out)?);
+        return Ok(());
+    }
+    if found.is_empty() {
+        println!(
+            "✅ No duplicated content among {} artifact version(s)",
+            entries.len()
+        );
+        return Ok(());
+    }
+    for duplicate in &found {
+        match duplicate.normalization {
+            None => println!(
+                "🔁 Identical content in {} artifact versions (sha256 {}):",
+                duplicate.entries.len(),
+                duplicate.hash.get(..12).unwrap_or(&duplicate.hash)
+            ),
+            Some(normalization) => println!(
+                "≈  {} in {} artifact versions:",
+                normalization.describe(),
+                duplicate.entries.len()
+            ),
+        }
+        for entry in &duplicate.entries {
+            match &entry.output_path {
+                Some(path) => println!("   - {} ({path})", entry.label()),
+                None => println!("   - {}", entry.label()),
+            }
+        }
+    }
+    println!(
+        "\n{} group(s) of duplicated content among {} artifact version(s)",
+        found.len(),
+        entries.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use anyhow::Result;
+
+use crate::{
+    commands::OutputFormat,
+    paths::{self, EffectivePath},
+};
+
+/// Print where the CLI reads and writes its global and project files
+pub fn run(format: OutputFormat) -> Result<()> {
+    let mut locations = paths::effective();
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let env = paths::project_config_overridden().then_some(paths::PROJECT_CONFIG_ENV);
+    for (name, file) in [
+        ("projectConfig", paths::project_config()),
+        ("lockFile", paths::project_lock()),
+    ] {
+        locations.push(EffectivePath {
+            name,
+            path: cwd.join(file),
+            env,
+        });
+    }
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&locations)?),
+        OutputFormat::Text => print!("{}", render(&locations)),
+    }
+    Ok(())
+}
+
+/// One `name  path  (source)` line per location
+fn render(locations: &[EffectivePath]) -> String {
+    let width = locations.iter().map(|l| l.name.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for location in locations {
+        let mut source = match location.env {
+            Some(var) => format!("from ${var}"),
+            None => "default".to_string(),
+        };
+        if !location.path.exists() {
+            source.push_str(", not found");
+        }
+        out.push_str(&format!(
+            "{:<width$}  {}  ({source})\n",
+            location.name,
+            location.path.display()
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_render_names_sources() {
+        let locations = vec![
+            EffectivePath {
+                name: "cacheDir",
+                path: PathBuf::from("/build/cache"),
+                env: Some(paths::CACHE_DIR_ENV),
+            },
+            EffectivePath {
+                name: "lockFile",
+                path: PathBuf::from("/nonexistent/apicuriolock.yaml"),
+                env: None,
+            },
+        ];
+        assert_eq!(
+            render(&locations),
+            "cacheDir  /build/cache  (from $APICURIO_CACHE_DIR, not found)\n\
+             lockFile  /nonexistent/apicuriolock.yaml  (default, not found)\n"
+        );
+    }
+}
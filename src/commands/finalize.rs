@@ -0,0 +1,12 @@
+use crate::api::Workspace;
+use anyhow::Result;
+
+pub async fn run(identifier: String) -> Result<()> {
+    let workspace = Workspace::current()?;
+    let artifact = workspace.finalize(&identifier).await?;
+    println!(
+        "✅ {}/{}@{} is enabled in registry '{}'",
+        artifact.group_id, artifact.artifact_id, artifact.version, artifact.registry
+    );
+    Ok(())
+}
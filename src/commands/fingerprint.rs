@@ -0,0 +1,126 @@
+use crate::{
+    avro::{canonical_form, AvroFingerprint},
+    commands::OutputFormat,
+    config::{load_global_config, load_repo_config},
+    constants::APICURIO_LOCK,
+    events::WarningSink,
+    identifier::resolve_artifact,
+    lockfile::LockFile,
+    paths,
+    registry::RegistryClient,
+};
+use anyhow::{anyhow, Context, Result};
+use semver::Version;
+use serde_json::json;
+use std::{fs, path::PathBuf, sync::Arc};
+
+/// Print the canonical-form fingerprints of an Avro schema
+///
+/// `identifier` is a local file, or a configured dependency name or
+/// `registry/group_id/artifact_id` optionally with `@version` (defaults to the
+/// locked, then the newest version).
+pub async fn run(identifier: String, show_canonical: bool, format: OutputFormat) -> Result<()> {
+    let (source, content, locked) = if PathBuf::from(&identifier).is_file() {
+        let content = fs::read_to_string(&identifier)
+            .with_context(|| format!("Failed to read file: {identifier}"))?;
+        (identifier, content, None)
+    } else {
+        fetch(&identifier).await?
+    };
+
+    let canonical = canonical_form(&content).with_context(|| format!("canonicalizing {source}"))?;
+    let fingerprint = AvroFingerprint::of_canonical(&canonical);
+    let matches_lock = locked.as_ref().map(|l| l == &fingerprint);
+
+    match format {
+        OutputFormat::Json => {
+            let mut out = json!({
+                "source": source,
+                "rabin": fingerprint.rabin,
+                "sha256": fingerprint.sha256,
+            });
+            if show_canonical {
+                out["canonicalForm"] = json!(canonical);
+            }
+            if let Some(matches) = matches_lock {
+                out["matchesLock"] = json!(matches);
+            }
+            println!("{}", serde_json::to_string_pretty(&out)?);
+        }
+        OutputFormat::Text => {
+            println!("{source}");
+            println!("  rabin (CRC-64-AVRO): {}", fingerprint.rabin);
+            println!("  sha256:              {}", fingerprint.sha256);
+            match matches_lock {
+                Some(true) => println!("  ✔️  matches {APICURIO_LOCK}"),
+                Some(false) => println!(
+                    "  ⚠️  differs from {APICURIO_LOCK} (rabin {})",
+                    locked.as_ref().map(|l| l.rabin.as_str()).unwrap_or("-")
+                ),
+                None => {}
+            }
+            if show_canonical {
+                println!();
+                println!("{canonical}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Download a registry version, returning a label, its content and the
+/// fingerprint recorded in the lock for it
+async fn fetch(identifier: &str) -> Result<(String, String, Option<AvroFingerprint>)> {
+    let repo_cfg = load_repo_config(&paths::project_config())?;
+    let registries = repo_cfg.merge_registries(load_global_config()?)?;
+    let names: Vec<String> = registries.iter().map(|r| r.name.clone()).collect();
+    let target = resolve_artifact(identifier, &repo_cfg, &names)?;
+    let registry = registries
+        .iter()
+        .find(|r| r.name == target.registry)
+        .ok_or_else(|| anyhow!("Registry '{}' not found", target.registry))?;
+    let client = RegistryClient::new(registry)?.with_events(Arc::new(WarningSink));
+
+    let lock = LockFile::load(&paths::project_lock()).ok();
+    let locked = lock.as_ref().and_then(|lock| {
+        lock.locked_dependencies.iter().find(|d| {
+            d.registry == target.registry
+                && d.group_id == target.group_id
+                && d.artifact_id == target.artifact_id
+        })
+    });
+    let version = match target
+        .version
+        .clone()
+        .or(locked.map(|d| d.resolved_version.clone()))
+    {
+        Some(version) => version,
+        None => client
+            .list_versions(&target.group_id, &target.artifact_id)
+            .await?
+            .into_iter()
+            .max()
+            .map(|v: Version| v.to_string())
+            .ok_or_else(|| {
+                anyhow!(
+                    "no versions found for {}/{}",
+                    target.group_id,
+                    target.artifact_id
+                )
+            })?,
+    };
+    let content = client
+        .get_version_content(&target.group_id, &target.artifact_id, &version)
+        .await?;
+    let locked_fingerprint = locked
+        .filter(|d| d.resolved_version == version)
+        .and_then(|d| d.avro_fingerprint.clone());
+    Ok((
+        format!(
+            "{}/{}/{}@{version}",
+            target.registry, target.group_id, target.artifact_id
+        ),
+        content,
+        locked_fingerprint,
+    ))
+}
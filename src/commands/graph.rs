@@ -0,0 +1,195 @@
+use crate::{
+    api::Workspace,
+    constants::APICURIO_LOCK,
+    events::WarningSink,
+    lockfile::{find_reference, LockFile, LockedDependency},
+    paths,
+};
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::{fmt::Write, sync::Arc};
+
+/// Output format of `apicurio graph`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    #[default]
+    Dot,
+    /// Mermaid flowchart
+    Mermaid,
+    /// Nodes and edges as JSON
+    Json,
+}
+
+pub async fn run(format: GraphFormat, live: bool) -> Result<()> {
+    let dependencies = if live {
+        Workspace::current()?
+            .with_events(Arc::new(WarningSink))
+            .resolve()
+            .await?
+    } else {
+        LockFile::load(&paths::project_lock())
+            .with_context(|| {
+                format!("reading {APICURIO_LOCK}; run `apicurio lock` or pass --live")
+            })?
+            .locked_dependencies
+    };
+    print!("{}", render(&dependencies, format)?);
+    Ok(())
+}
+
+/// Dependency edges as (parent index, child index)
+fn edges(dependencies: &[LockedDependency]) -> Vec<(usize, usize)> {
+    let mut edges = Vec::new();
+    for (from, dep) in dependencies.iter().enumerate() {
+        for reference in &dep.references {
+            if let Some(child) = find_reference(dependencies, dep, reference) {
+                let to = dependencies
+                    .iter()
+                    .position(|d| std::ptr::eq(d, child))
+                    .expect("child is an element of dependencies");
+                edges.push((from, to));
+            }
+        }
+    }
+    edges
+}
+
+/// Render the graph of locked dependencies and their references
+pub fn render(dependencies: &[LockedDependency], format: GraphFormat) -> Result<String> {
+    let edges = edges(dependencies);
+    let mut out = String::new();
+    match format {
+        GraphFormat::Dot => {
+            out.push_str("digraph dependencies {\n    rankdir=LR;\n    node [shape=box];\n");
+            for (i, dep) in dependencies.iter().enumerate() {
+                let style = if dep.is_transitive {
+                    ""
+                } else {
+                    ", style=bold"
+                };
+                writeln!(
+                    out,
+                    "    n{i} [label=\"{}\\n{}\"{style}];",
+                    dep.name.replace('"', "\\\""),
+                    dep.resolved_version
+                )?;
+            }
+            for (from, to) in edges {
+                writeln!(out, "    n{from} -> n{to};")?;
+            }
+            out.push_str("}\n");
+        }
+        GraphFormat::Mermaid => {
+            out.push_str("graph LR\n");
+            for (i, dep) in dependencies.iter().enumerate() {
+                let (open, close) = if dep.is_transitive {
+                    ("(", ")")
+                } else {
+                    ("[", "]")
+                };
+                writeln!(
+                    out,
+                    "    n{i}{open}\"{}@{}\"{close}",
+                    dep.name.replace('"', "#quot;"),
+                    dep.resolved_version
+                )?;
+            }
+            for (from, to) in edges {
+                writeln!(out, "    n{from} --> n{to}")?;
+            }
+        }
+        GraphFormat::Json => {
+            let nodes: Vec<_> = dependencies
+                .iter()
+                .map(|d| {
+                    json!({
+                        "id": d.artifact_key(),
+                        "name": d.name,
+                        "registry": d.registry,
+                        "groupId": d.group_id,
+                        "artifactId": d.artifact_id,
+                        "version": d.resolved_version,
+                        "transitive": d.is_transitive,
+                    })
+                })
+                .collect();
+            let edges: Vec<_> = edges
+                .into_iter()
+                .map(|(from, to)| {
+                    json!({
+                        "from": dependencies[from].artifact_key(),
+                        "to": dependencies[to].artifact_key(),
+                    })
+                })
+                .collect();
+            out = serde_json::to_string_pretty(&json!({ "nodes": nodes, "edges": edges }))?;
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dep(name: &str, artifact_id: &str, transitive: bool, refs: &[&str]) -> LockedDependency {
+        LockedDependency {
+            name: name.to_string(),
+            registry: "main".to_string(),
+            resolved_version: "1.0.0".to_string(),
+            download_url: String::new(),
+            registry_url: None,
+            sha256: String::new(),
+            output_path: format!("protos/{artifact_id}.proto"),
+            group_id: "com.example".to_string(),
+            artifact_id: artifact_id.to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive: transitive,
+            references: refs.iter().map(|r| r.to_string()).collect(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
+        }
+    }
+
+    fn sample() -> Vec<LockedDependency> {
+        vec![
+            dep("orders", "orders", false, &["com.example/common"]),
+            dep(
+                "com.example/common",
+                "common",
+                true,
+                &["com.example/missing"],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_render_dot_and_mermaid() {
+        let dot = render(&sample(), GraphFormat::Dot).unwrap();
+        assert!(dot.contains("n0 [label=\"orders\\n1.0.0\", style=bold];"));
+        assert!(dot.contains("n1 [label=\"com.example/common\\n1.0.0\"];"));
+        assert!(dot.contains("n0 -> n1;"));
+        // References that are not locked (e.g. excluded) have no edge
+        assert_eq!(dot.matches("->").count(), 1);
+
+        let mermaid = render(&sample(), GraphFormat::Mermaid).unwrap();
+        assert!(mermaid.starts_with("graph LR\n"));
+        assert!(mermaid.contains("n0[\"orders@1.0.0\"]"));
+        assert!(mermaid.contains("n1(\"com.example/common@1.0.0\")"));
+        assert!(mermaid.contains("n0 --> n1"));
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&render(&sample(), GraphFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["nodes"].as_array().unwrap().len(), 2);
+        assert_eq!(json["edges"][0]["from"], "main:com.example:orders");
+        assert_eq!(json["edges"][0]["to"], "main:com.example:common");
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{api::Workspace, commands::OutputFormat, constants::APICURIO_LOCK, lockfile::LockFile};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Registry IDs of a locked version, as used by Kafka serializers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ArtifactIds {
+    #[serde(skip)]
+    name: String,
+    registry: String,
+    group_id: String,
+    artifact_id: String,
+    version: String,
+    global_id: Option<i64>,
+    content_id: Option<i64>,
+}
+
+/// Print (or write to `out`) the globalId/contentId of the locked versions
+pub async fn run(
+    identifier: Option<String>,
+    format: OutputFormat,
+    out: Option<String>,
+) -> Result<()> {
+    let workspace = Workspace::current()?;
+    let lock = LockFile::load(&workspace.lock_path())
+        .with_context(|| format!("reading {APICURIO_LOCK}; run `apicurio lock` first"))?;
+    let locked: Vec<_> = lock
+        .locked_dependencies
+        .iter()
+        .filter(|d| identifier.as_deref().is_none_or(|name| d.locked_for(name)))
+        .collect();
+    if let (Some(name), true) = (&identifier, locked.is_empty()) {
+        bail!("'{name}' is not in {APICURIO_LOCK}");
+    }
+
+    let mut ids = Vec::new();
+    for dep in locked {
+        let client = workspace
+            .client(&dep.registry)
+            .ok_or_else(|| anyhow!("Registry '{}' not found", dep.registry))?;
+        let metadata = client
+            .get_version_metadata(&dep.group_id, &dep.artifact_id, &dep.resolved_version)
+            .await
+            .with_context(|| {
+                format!(
+                    "reading {}/{}@{}",
+                    dep.group_id, dep.artifact_id, dep.resolved_version
+                )
+            })?;
+        ids.push(ArtifactIds {
+            name: dep.name.clone(),
+            registry: dep.registry.clone(),
+            group_id: dep.group_id.clone(),
+            artifact_id: dep.artifact_id.clone(),
+            version: dep.resolved_version.clone(),
+            global_id: metadata.global_id,
+            content_id: metadata.content_id,
+        });
+    }
+
+    if let Some(out) = out {
+        let path = Path::new(&out);
+        let content = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => render_json(&ids)?,
+            Some("properties") => render_properties(&ids),
+            _ => bail!("cannot tell the format of {out}; use a .properties or .json file"),
+        };
+        fs::write(path, content).with_context(|| format!("writing {out}"))?;
+        println!("✅ Wrote the IDs of {} artifact(s) to {out}", ids.len());
+        return Ok(());
+    }
+    match format {
+        OutputFormat::Json => print!("{}", render_json(&ids)?),
+        OutputFormat::Text => {
+            println!(
+                "{:<30} {:<12} {:>10} {:>10}",
+                "NAME", "VERSION", "GLOBAL ID", "CONTENT ID"
+            );
+            for entry in &ids {
+                println!(
+                    "{:<30} {:<12} {:>10} {:>10}",
+                    entry.name,
+                    entry.version,
+                    display_id(entry.global_id),
+                    display_id(entry.content_id)
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn display_id(id: Option<i64>) -> String {
+    id.map_or_else(|| "-".to_string(), |id| id.to_string())
+}
+
+/// Entries keyed by dependency name
+fn render_json(ids: &[ArtifactIds]) -> Result<String> {
+    let by_name: BTreeMap<&str, &ArtifactIds> = ids
+        .iter()
+        .map(|entry| (entry.name.as_str(), entry))
+        .collect();
+    Ok(format!("{}\n", serde_json::to_string_pretty(&by_name)?))
+}
+
+/// `<name>.globalId=...` lines, loadable by Java applications
+fn render_properties(ids: &[ArtifactIds]) -> String {
+    let mut out = format!("# Generated by `apicurio ids` from {APICURIO_LOCK}\n");
+    for entry in ids {
+        // `:` and `=` separate keys from values in .properties files
+        let key = entry.name.replace([':', '='], "_");
+        out.push_str(&format!("{key}.version={}\n", entry.version));
+        for (field, id) in [
+            ("globalId", entry.global_id),
+            ("contentId", entry.content_id),
+        ] {
+            if let Some(id) = id {
+                out.push_str(&format!("{key}.{field}={id}\n"));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_properties_and_json() {
+        let ids = vec![ArtifactIds {
+            name: "orders".to_string(),
+            registry: "prod".to_string(),
+            group_id: "com.example".to_string(),
+            artifact_id: "orders".to_string(),
+            version: "1.2.0".to_string(),
+            global_id: Some(42),
+            content_id: None,
+        }];
+        assert_eq!(
+            render_properties(&ids),
+            "# Generated by `apicurio ids` from apicuriolock.yaml\n\
+             orders.version=1.2.0\n\
+             orders.globalId=42\n"
+        );
+        let json: serde_json::Value = serde_json::from_str(&render_json(&ids).unwrap()).unwrap();
+        assert_eq!(json["orders"]["globalId"], 42);
+        assert_eq!(json["orders"]["artifactId"], "orders");
+    }
+}
@@ -1,25 +1,35 @@
 use anyhow::Result;
-use std::{fs, path::Path};
+use std::fs;
 
-use crate::config::{save_repo_config, RepoConfig};
-use crate::constants::{APICURIO_CONFIG, APICURIO_LOCK};
+use crate::config::{is_json_file, save_repo_config, GitignoreConfig, RepoConfig};
+use crate::paths;
 
 pub async fn run() -> Result<()> {
-    let cfg = Path::new(APICURIO_CONFIG);
+    let cfg = paths::project_config();
     if cfg.exists() {
         println!("Config already exists at {}", cfg.display());
     } else {
         let repo = RepoConfig {
             external_registries_file: Some("${APICURIO_REGISTRIES_PATH:-}".into()),
+            // Pulled files are generated; keep them out of commits by default
+            gitignore: GitignoreConfig {
+                manage: true,
+                ..Default::default()
+            },
             ..Default::default()
         };
-        save_repo_config(&repo, cfg)?;
+        save_repo_config(&repo, &cfg)?;
         println!("Created {}", cfg.display());
     }
 
-    let lock = Path::new(APICURIO_LOCK);
+    let lock = paths::project_lock();
     if !lock.exists() {
-        fs::write(lock, "lockedDependencies: []")?;
+        let empty = if is_json_file(&lock) {
+            "{ \"lockedDependencies\": [] }\n"
+        } else {
+            "lockedDependencies: []"
+        };
+        fs::write(&lock, empty)?;
         println!("Created {}", lock.display());
     }
 
@@ -0,0 +1,254 @@
+use crate::{
+    config::{glob_match, load_global_config, load_repo_config},
+    events::WarningSink,
+    identifier::resolve_artifact,
+    paths,
+    registry::RegistryClient,
+};
+use anyhow::{anyhow, Context, Result};
+use clap::Subcommand;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+#[derive(Subcommand, Debug)]
+pub enum LabelsCommands {
+    /// Print the labels of the selected artifacts
+    List {
+        /// `[registry/]group[/artifact]`; group and artifact may be globs
+        selector: String,
+    },
+    /// Set labels on every selected artifact, replacing existing values
+    Add {
+        /// `[registry/]group[/artifact]`; group and artifact may be globs
+        selector: String,
+        /// Labels as `key=value`
+        #[arg(required = true, value_name = "KEY=VALUE")]
+        labels: Vec<String>,
+        /// Print the changes without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Remove label keys from every selected artifact
+    Remove {
+        /// `[registry/]group[/artifact]`; group and artifact may be globs
+        selector: String,
+        /// Label keys to remove
+        #[arg(required = true, value_name = "KEY")]
+        keys: Vec<String>,
+        /// Print the changes without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// A change applied to the labels of each selected artifact
+#[derive(Debug)]
+enum LabelEdit {
+    Add(BTreeMap<String, String>),
+    Remove(Vec<String>),
+}
+
+impl LabelEdit {
+    fn apply(&self, labels: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut labels = labels.clone();
+        match self {
+            LabelEdit::Add(added) => labels.extend(added.clone()),
+            LabelEdit::Remove(keys) => labels.retain(|key, _| !keys.contains(key)),
+        }
+        labels
+    }
+}
+
+pub async fn run(cmd: LabelsCommands) -> Result<()> {
+    let (selector, edit, dry_run) = match cmd {
+        LabelsCommands::List { selector } => (selector, None, false),
+        LabelsCommands::Add {
+            selector,
+            labels,
+            dry_run,
+        } => {
+            let labels = labels
+                .iter()
+                .map(|label| parse_label(label))
+                .collect::<Result<_>>()?;
+            (selector, Some(LabelEdit::Add(labels)), dry_run)
+        }
+        LabelsCommands::Remove {
+            selector,
+            keys,
+            dry_run,
+        } => (selector, Some(LabelEdit::Remove(keys)), dry_run),
+    };
+
+    let repo_cfg = load_repo_config(&paths::project_config())?;
+    let registries = repo_cfg.merge_registries(load_global_config()?)?;
+    let names: Vec<String> = registries.iter().map(|r| r.name.clone()).collect();
+    // A bare group selects all of its artifacts
+    let selector = if selector.contains('/') {
+        selector
+    } else {
+        format!("{selector}/*")
+    };
+    let target = resolve_artifact(&selector, &repo_cfg, &names)?;
+    let registry = registries
+        .iter()
+        .find(|r| r.name == target.registry)
+        .ok_or_else(|| anyhow!("Registry '{}' not found", target.registry))?;
+    let client = RegistryClient::new(registry)?.with_events(Arc::new(WarningSink));
+
+    let artifacts = select_artifacts(&client, &target.group_id, &target.artifact_id).await?;
+    if artifacts.is_empty() {
+        println!(
+            "No artifacts match '{selector}' in registry '{}'",
+            target.registry
+        );
+        return Ok(());
+    }
+
+    let mut changed = 0;
+    for (group_id, artifact_id) in &artifacts {
+        let labels = client
+            .get_artifact_metadata(group_id, artifact_id)
+            .await
+            .with_context(|| format!("reading labels of {group_id}/{artifact_id}"))?
+            .labels
+            .unwrap_or_default();
+        let Some(edit) = &edit else {
+            println!("{group_id}/{artifact_id}  {}", render_labels(&labels));
+            continue;
+        };
+        let updated = edit.apply(&labels);
+        if updated == labels {
+            continue;
+        }
+        println!(
+            "~ {group_id}/{artifact_id}: {}",
+            describe_changes(&labels, &updated).join(", ")
+        );
+        if !dry_run {
+            client
+                .set_artifact_labels(group_id, artifact_id, &updated)
+                .await
+                .with_context(|| format!("updating labels of {group_id}/{artifact_id}"))?;
+        }
+        changed += 1;
+    }
+
+    if edit.is_some() {
+        let unchanged = artifacts.len() - changed;
+        if dry_run {
+            println!("Would update {changed} artifact(s) ({unchanged} already up to date)");
+        } else {
+            println!("✅ Updated {changed} artifact(s) ({unchanged} already up to date)");
+        }
+    }
+    Ok(())
+}
+
+/// Artifacts whose group and artifact ID match the (glob) patterns
+async fn select_artifacts(
+    client: &RegistryClient,
+    group_pattern: &str,
+    artifact_pattern: &str,
+) -> Result<Vec<(String, String)>> {
+    let is_glob = |pattern: &str| pattern.contains(['*', '?']);
+    let groups = if is_glob(group_pattern) {
+        client
+            .list_groups()
+            .await?
+            .into_iter()
+            .filter(|group_id| glob_match(group_pattern.as_bytes(), group_id.as_bytes()))
+            .collect()
+    } else {
+        vec![group_pattern.to_string()]
+    };
+    let mut selected = Vec::new();
+    for group_id in groups {
+        if !is_glob(artifact_pattern) {
+            if client.artifact_exists(&group_id, artifact_pattern).await? {
+                selected.push((group_id, artifact_pattern.to_string()));
+            }
+            continue;
+        }
+        let artifacts = client
+            .list_artifacts(&group_id)
+            .await
+            .with_context(|| format!("listing artifacts of group '{group_id}'"))?;
+        selected.extend(
+            artifacts
+                .into_iter()
+                .filter(|a| glob_match(artifact_pattern.as_bytes(), a.as_bytes()))
+                .map(|a| (group_id.clone(), a)),
+        );
+    }
+    Ok(selected)
+}
+
+/// Parse `key=value`
+fn parse_label(input: &str) -> Result<(String, String)> {
+    match input.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(anyhow!("invalid label '{input}'; expected key=value")),
+    }
+}
+
+fn render_labels(labels: &HashMap<String, String>) -> String {
+    if labels.is_empty() {
+        return "(no labels)".to_string();
+    }
+    let sorted: BTreeMap<_, _> = labels.iter().collect();
+    sorted
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// `+key=value`, `-key` and `key: old → new` entries, sorted by key
+fn describe_changes(
+    before: &HashMap<String, String>,
+    after: &HashMap<String, String>,
+) -> Vec<String> {
+    let keys: std::collections::BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| match (before.get(key), after.get(key)) {
+            (None, Some(new)) => Some(format!("+{key}={new}")),
+            (Some(_), None) => Some(format!("-{key}")),
+            (Some(old), Some(new)) if old != new => Some(format!("{key}: {old} → {new}")),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_edits_and_descriptions() {
+        let before = HashMap::from([
+            ("owner".to_string(), "team-a".to_string()),
+            ("tier".to_string(), "gold".to_string()),
+        ]);
+        let add = LabelEdit::Add(BTreeMap::from([
+            parse_label("owner=team-b").unwrap(),
+            parse_label("domain=payments").unwrap(),
+        ]));
+        let after = add.apply(&before);
+        assert_eq!(
+            describe_changes(&before, &after),
+            vec!["+domain=payments", "owner: team-a → team-b"]
+        );
+
+        let remove = LabelEdit::Remove(vec!["tier".to_string(), "missing".to_string()]);
+        assert_eq!(
+            describe_changes(&before, &remove.apply(&before)),
+            vec!["-tier"]
+        );
+        assert!(parse_label("=value").is_err());
+        assert!(parse_label("owner").is_err());
+        assert_eq!(render_labels(&HashMap::new()), "(no labels)");
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    api::Workspace,
+    commands::OutputFormat,
+    config::{load_repo_config, RepoConfig},
+    constants::APICURIO_CONFIG,
+    lint::{LintReport, Linter, Severity},
+    paths, plugins,
+};
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+
+/// Lint the given files, or the input files of every configured publish
+pub async fn run(paths: Vec<String>, format: OutputFormat) -> Result<()> {
+    let report = if paths.is_empty() {
+        let workspace = Workspace::current()?;
+        if workspace.config().publishes.is_empty() {
+            println!("No publishes configured in {APICURIO_CONFIG}; pass files to lint");
+            return Ok(());
+        }
+        workspace.lint(None)?
+    } else {
+        lint_files(&paths)?
+    };
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Text => print_report(&report),
+    }
+    check(&report)
+}
+
+/// Lint explicit files, using the rule severities and plugins of the config if
+/// there is one
+fn lint_files(paths: &[String]) -> Result<LintReport> {
+    let config_path = paths::project_config();
+    let config = if config_path.exists() {
+        load_repo_config(&config_path)?
+    } else {
+        RepoConfig::default()
+    };
+    let linter = Linter::new(&config.lint.rules)?;
+    let mut report = LintReport::default();
+    let mut files = Vec::new();
+    for path in paths {
+        let content = fs::read_to_string(path).with_context(|| format!("reading {path}"))?;
+        report.files.push(linter.lint(path, &content, None));
+        files.push((path.clone(), content));
+    }
+    plugins::lint(
+        &plugins::enabled(&config.plugins)?,
+        &linter,
+        &files,
+        &mut report,
+    )?;
+    Ok(report)
+}
+
+/// Print findings grouped by file, followed by a summary line
+pub fn print_report(report: &LintReport) {
+    for file in &report.files {
+        if file.kind.is_none() && file.findings.is_empty() {
+            println!("⏭️  {}: no built-in checks for this file type", file.path);
+            continue;
+        }
+        if file.findings.is_empty() {
+            println!("✔️  {}", file.path);
+            continue;
+        }
+        println!("{}", file.path);
+        for finding in &file.findings {
+            let (icon, label) = match finding.severity {
+                Severity::Error => ("❌", "error"),
+                _ => ("⚠️ ", "warning"),
+            };
+            let line = finding
+                .line
+                .map(|l| format!(" (line {l})"))
+                .unwrap_or_default();
+            println!(
+                "  {icon} {label:<7} {}: {}{line}",
+                finding.rule, finding.message
+            );
+        }
+    }
+    println!(
+        "{} file(s) linted: {} error(s), {} warning(s)",
+        report.files.len(),
+        report.error_count(),
+        report.warning_count()
+    );
+}
+
+/// Fail if the report contains errors
+pub fn check(report: &LintReport) -> Result<()> {
+    if report.has_errors() {
+        return Err(anyhow!(
+            "lint failed with {} error(s)",
+            report.error_count()
+        ));
+    }
+    Ok(())
+}
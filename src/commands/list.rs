@@ -1,13 +1,12 @@
 use crate::{
     config::{load_global_config, load_repo_config},
-    constants::{APICURIO_CONFIG, APICURIO_LOCK},
     lockfile::LockFile,
+    paths,
 };
 use anyhow::Result;
-use std::path::PathBuf;
 
 pub async fn run() -> Result<()> {
-    let repo_cfg = load_repo_config(&PathBuf::from(APICURIO_CONFIG))?;
+    let repo_cfg = load_repo_config(&paths::project_config())?;
     let global_cfg = load_global_config()?;
     let regs = repo_cfg.merge_registries(global_cfg)?;
 
@@ -20,12 +19,31 @@ pub async fn run() -> Result<()> {
         }
     }
 
-    let lock = LockFile::load(&PathBuf::from(APICURIO_LOCK)).ok();
+    let lock = LockFile::load(&paths::project_lock()).ok();
     println!("\nDependencies:");
     if repo_cfg.dependencies.is_empty() {
         println!(" - No dependencies found.");
     } else {
         for dep in repo_cfg.dependencies {
+            if dep.is_group() {
+                let members = lock.iter().flat_map(|lf| &lf.locked_dependencies);
+                let locked = members.filter(|d| d.locked_for(&dep.name)).count();
+                let mut selector: Vec<String> =
+                    dep.group.iter().map(|g| format!("group={g}")).collect();
+                if !dep.labels.is_empty() {
+                    let labels: Vec<String> =
+                        dep.labels.iter().map(|(k, v)| format!("{k}:{v}")).collect();
+                    selector.push(format!("labels={}", labels.join(",")));
+                }
+                println!(
+                    " - {}: {} spec={} locked={} artifact(s)",
+                    dep.name,
+                    selector.join(" "),
+                    dep.version,
+                    locked
+                );
+                continue;
+            }
             if let Some(lf) = &lock {
                 if let Some(ld) = lf.locked_dependencies.iter().find(|d| d.name == dep.name) {
                     println!(
@@ -1,390 +1,1319 @@
 use anyhow::{Context, Result};
+use futures_util::{stream, StreamExt};
 use sha2::{Digest, Sha256};
 use std::{
-    collections::{HashMap, HashSet},
-    path::PathBuf,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::Path,
+    sync::Mutex,
 };
 
 use crate::{
-    config::{load_global_config, load_repo_config},
-    constants::{APICURIO_CONFIG, APICURIO_LOCK},
+    api::{LockOutcome, Workspace},
+    artifact_writer, avro,
+    commands::OutputFormat,
+    config::{ConflictPolicy, DependencyConfig, NoMatchPolicy, RepoConfig},
     dependency::Dependency,
-    lockfile::{resolve_output_path, LockFile, LockedDependency},
+    events::{Event, EventSink},
+    json_schema, local_refs,
+    lockfile::{sort_locked_dependencies, LockFile, LockedDependency},
     output_path,
-    registry::RegistryClient,
+    registry::{
+        self, decode_binary_content, ArtifactMetadata, ArtifactVersionReference, RegistryClient,
+    },
+    summary::SummarySink,
 };
 
+/// Artifacts of one level of the reference graph resolved at the same time
+const RESOLVE_CONCURRENCY: usize = 16;
+
 /// Represents a dependency to be resolved (either direct or transitive)
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct DependencyToResolve {
+    /// Config name of a direct dependency; `None` for references
+    name: Option<String>,
     group_id: String,
     artifact_id: String,
     version_req: String, // For direct deps, this is semver. For transitive, exact version
     registry: String,
     output_path: Option<String>, // None for transitive deps
     is_transitive: bool,
+    /// Set for direct dependencies configured with `binary: true`
+    binary: bool,
+    /// `contentType` override of a direct dependency
+    content_type: Option<String>,
     depth: u32,
 }
 
-pub async fn run() -> Result<()> {
-    // 1) load repo + global + merge registries
-    let config_path = PathBuf::from(APICURIO_CONFIG);
-    let config_content = std::fs::read_to_string(&config_path)
-        .with_context(|| format!("reading config from {}", config_path.display()))?;
-    let repo_cfg = load_repo_config(&config_path)?;
-    let global_cfg = load_global_config()?;
-    let registries = repo_cfg.merge_registries(global_cfg)?;
-
-    // Compute config hash for lock integrity
-    let config_hash = LockFile::compute_config_hash(&config_content, &repo_cfg.dependencies);
-
-    let mut clients = HashMap::new();
-    for reg in &registries {
-        clients.insert(reg.name.clone(), RegistryClient::new(reg)?);
-    }
-
-    // 2) Check if existing lock is up-to-date with enhanced validation
-    let lock_path = PathBuf::from(APICURIO_LOCK);
-    let existing_lock = if let Ok(existing_lock) = LockFile::load(&lock_path) {
-        // First, quick check: is config hash the same?
-        if existing_lock.is_compatible_with_config(&config_hash) {
-            // Second, check modification time if available
-            if existing_lock
-                .is_newer_than_config(&config_path)
-                .unwrap_or(false)
-            {
-                // Third, verify all dependencies can still be resolved
-                if verify_lock_is_still_valid(&existing_lock, &clients).await? {
-                    println!("🔒 Lock file already up-to-date");
-                    return Ok(());
-                } else {
-                    println!("🔓 Lock file outdated: some dependencies are no longer available");
+/// Options controlling whether `lock` may modify the lockfile
+///
+/// Mirrors cargo's `--locked`/`--frozen` so CI can guarantee that no
+/// resolution happens implicitly.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LockOptions {
+    /// Fail if the lockfile is missing or out of sync with the config
+    pub locked: bool,
+    /// Like `locked`, but also skip checking the registries for availability
+    pub frozen: bool,
+    /// Expand `${VAR}` placeholders of unset variables to "" instead of failing
+    pub allow_unset_env: bool,
+    /// Rewrite the lock file (and its `generatedAt`) even when nothing changed
+    pub force: bool,
+    /// Resolve every range again even when the lock file is up to date, moving
+    /// to newer versions (`update`); unchanged versions keep their hashes
+    pub update: bool,
+}
+
+impl LockOptions {
+    fn flag_name(&self) -> &'static str {
+        if self.frozen {
+            "--frozen"
+        } else {
+            "--locked"
+        }
+    }
+}
+
+pub async fn run(opts: LockOptions, format: OutputFormat) -> Result<()> {
+    let sink = SummarySink::for_format(format);
+    let workspace = Workspace::current()?.with_events(sink.clone());
+    let before = LockFile::load(&workspace.lock_path())
+        .map(|lock| lock.locked_dependencies)
+        .unwrap_or_default();
+    let outcome = workspace.lock(opts).await?;
+    let summary = sink.summary(&before, &outcome.lock_file().locked_dependencies);
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+
+    match outcome {
+        LockOutcome::UpToDate(_) if opts.locked || opts.frozen => {
+            println!("🔒 Lock file up-to-date")
+        }
+        LockOutcome::UpToDate(_) => println!("🔒 Lock file already up-to-date"),
+        LockOutcome::Updated { reason, .. } => {
+            if let Some(reason) = reason {
+                println!("🔓 Lock file outdated: {reason}");
+            }
+            println!("🔒 Updated {}", workspace.lock_path().display());
+        }
+    }
+    summary.print();
+    Ok(())
+}
+
+/// Print why `lock` would keep or regenerate the lock file (`lock --explain`)
+pub async fn explain(format: OutputFormat) -> Result<()> {
+    let workspace = Workspace::current()?;
+    let explanation = workspace.explain_lock().await?;
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&explanation)?);
+        return Ok(());
+    }
+    if !explanation.exists {
+        println!(
+            "🔓 {} does not exist; `apicurio lock` creates it",
+            explanation.lock_path
+        );
+        return Ok(());
+    }
+
+    let mark = |ok: bool| if ok { "✅" } else { "❌" };
+    println!(
+        "🔍 {} ({} entry(ies), generated {})",
+        explanation.lock_path,
+        explanation.locked,
+        explanation
+            .generated_at
+            .as_deref()
+            .unwrap_or("at an unknown time")
+    );
+    if !explanation.config_hash_changed {
+        println!("   {} config hash matches", mark(true));
+    } else if explanation.changed_inputs.is_empty() {
+        println!(
+            "   {} config hash differs (the lock does not record its inputs; \
+             `apicurio lock` records them for next time)",
+            mark(false)
+        );
+    } else {
+        println!("   {} config hash differs:", mark(false));
+        for change in &explanation.changed_inputs {
+            println!("      - {change}");
+        }
+    }
+    let recorded = explanation
+        .config_modified_recorded
+        .as_deref()
+        .unwrap_or("not recorded");
+    let actual = explanation
+        .config_modified_actual
+        .as_deref()
+        .unwrap_or("unknown");
+    if explanation.config_modified_since {
+        println!(
+            "   {} config file modified since the lock (recorded {recorded}, now {actual})",
+            mark(false)
+        );
+    } else {
+        println!(
+            "   {} config file not modified since the lock ({recorded})",
+            mark(true)
+        );
+    }
+    if !explanation.registries_checked {
+        println!(
+            "   {} locked versions trusted: the lock is less than 5 minutes old",
+            mark(true)
+        );
+    } else if explanation.unavailable.is_empty() {
+        println!("   {} all locked versions are available", mark(true));
+    } else {
+        println!("   {} locked versions unavailable:", mark(false));
+        for problem in &explanation.unavailable {
+            println!("      - {problem}");
+        }
+    }
+    match &explanation.reason {
+        Some(reason) => println!("🔓 `apicurio lock` would regenerate the lock: {reason}"),
+        None => println!("🔒 Lock file up-to-date"),
+    }
+    Ok(())
+}
+
+/// Resolve direct dependencies from the config and all of their transitive
+/// references into sorted lock entries
+///
+/// When `only` is set, just the direct dependency with that name (and what it
+/// references) is resolved.
+///
+/// Artifacts referenced at different exact versions are handled according to
+/// `referenceResolution.conflictPolicy`: with `highest`, resolution is repeated
+/// with the highest requested versions pinned until no conflicts remain.
+///
+/// Entries of `previous` resolved again to the same version, URL and output
+/// path keep their hash instead of having their content downloaded.
+pub(crate) async fn resolve_dependencies(
+    repo_cfg: &RepoConfig,
+    clients: &HashMap<String, RegistryClient>,
+    only: Option<&str>,
+    previous: &[LockedDependency],
+    events: &dyn EventSink,
+) -> Result<Vec<LockedDependency>> {
+    let repo_cfg = &expand_group_dependencies(repo_cfg, clients, only, events).await?;
+    let mut pins = BTreeMap::new();
+    let memo = RegistryMemo::reusing(previous);
+    loop {
+        let (locked, requested_versions) =
+            resolve_with_pins(repo_cfg, clients, only, &pins, &memo, events).await?;
+        let conflicts = find_version_conflicts(repo_cfg, &requested_versions, &pins);
+        if conflicts.is_empty() {
+            return Ok(locked);
+        }
+        match repo_cfg.reference_resolution.conflict_policy {
+            ConflictPolicy::Error => anyhow::bail!(
+                "conflicting versions of referenced artifacts:\n{}\n\
+                 Add `overrides` entries or set `referenceResolution.conflictPolicy: highest`",
+                conflicts
+                    .iter()
+                    .map(|c| format!("  - {c}"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            ConflictPolicy::Highest => {
+                for conflict in conflicts {
+                    events.emit(Event::Warning(format!("{conflict}; using the highest")));
+                    pins.insert(conflict.artifact, conflict.highest.to_string());
                 }
-            } else {
-                println!("🔓 Lock file outdated: config file has been modified");
             }
+        }
+    }
+}
+
+/// Replace `group` and `labels` dependencies by one dependency per artifact they select
+///
+/// Label selectors are matched by the registry search API. Expanded entries are
+/// named `<name>/<artifactId>` (`<name>/<groupId>/<artifactId>` unless `group` is
+/// an exact group ID) and remember the group dependency in `member_of`.
+/// Artifacts without a version matching the range are skipped, or fail with
+/// `ifNoMatch: error`. When `only` is set, other group dependencies are dropped
+/// instead of being expanded.
+pub(crate) async fn expand_group_dependencies(
+    repo_cfg: &RepoConfig,
+    clients: &HashMap<String, RegistryClient>,
+    only: Option<&str>,
+    events: &dyn EventSink,
+) -> Result<RepoConfig> {
+    let mut expanded = repo_cfg.clone();
+    expanded.dependencies.clear();
+    for dep_cfg in &repo_cfg.dependencies {
+        if !dep_cfg.is_group() {
+            expanded.dependencies.push(dep_cfg.clone());
+            continue;
+        }
+        if only.is_some_and(|name| name != dep_cfg.name) {
+            continue;
+        }
+        if dep_cfg.group_id.is_some()
+            || dep_cfg.artifact_id.is_some()
+            || dep_cfg.output_path.is_some()
+        {
+            anyhow::bail!(
+                "group dependency '{}' cannot set groupId, artifactId or outputPath; \
+                 its artifacts are placed with dependencyDefaults.outputPatterns",
+                dep_cfg.name
+            );
+        }
+        let registry = dep_cfg
+            .registry
+            .clone()
+            .or_else(|| repo_cfg.dependency_defaults.registry.clone())
+            .with_context(|| format!("No registry specified for dependency '{}'", dep_cfg.name))?;
+        let client = clients
+            .get(&registry)
+            .with_context(|| format!("registry '{registry}' is not configured"))?;
+        let req = semver::VersionReq::parse(&dep_cfg.version).with_context(|| {
+            format!(
+                "invalid version '{}' of dependency '{}'",
+                dep_cfg.version, dep_cfg.name
+            )
+        })?;
+
+        // Members are named `name/artifactId` when they all share one exact group
+        let exact_group = dep_cfg
+            .group
+            .as_deref()
+            .filter(|pattern| !pattern.contains(['*', '?']));
+        let candidates = if !dep_cfg.labels.is_empty() {
+            client
+                .search_artifacts_by_labels(&dep_cfg.labels, exact_group)
+                .await
+                .with_context(|| format!("searching artifacts for dependency '{}'", dep_cfg.name))?
+                .into_iter()
+                .filter(|(group_id, _)| dep_cfg.matches_group(group_id))
+                .collect()
         } else {
-            println!("🔓 Lock file outdated: config hash changed");
+            let groups = match exact_group {
+                Some(group_id) => vec![group_id.to_string()],
+                None => client
+                    .list_groups()
+                    .await
+                    .with_context(|| format!("listing groups for dependency '{}'", dep_cfg.name))?
+                    .into_iter()
+                    .filter(|group_id| dep_cfg.matches_group(group_id))
+                    .collect(),
+            };
+            let mut candidates = Vec::new();
+            for group_id in groups {
+                let artifacts = client
+                    .list_artifacts(&group_id)
+                    .await
+                    .with_context(|| format!("listing artifacts of group '{group_id}'"))?;
+                candidates.extend(artifacts.into_iter().map(|a| (group_id.clone(), a)));
+            }
+            candidates
+        };
+
+        let mut selected = 0;
+        for (group_id, artifact_id) in candidates {
+            if !dep_cfg.selects_artifact(&artifact_id) {
+                continue;
+            }
+            let name = if exact_group.is_some() {
+                format!("{}/{artifact_id}", dep_cfg.name)
+            } else {
+                format!("{}/{group_id}/{artifact_id}", dep_cfg.name)
+            };
+            let versions = client
+                .list_versions(&group_id, &artifact_id)
+                .await
+                .with_context(|| format!("listing versions for {group_id}/{artifact_id}"))?;
+            if !versions.iter().any(|v| req.matches(v)) {
+                match dep_cfg.if_no_match {
+                    NoMatchPolicy::Skip => {
+                        events.emit(Event::Skipped {
+                            name,
+                            reason: format!("no version matching '{}'", dep_cfg.version),
+                        });
+                        continue;
+                    }
+                    NoMatchPolicy::Error => anyhow::bail!(
+                        "no version matching '{}' for {group_id}/{artifact_id} of group dependency '{}'",
+                        dep_cfg.version,
+                        dep_cfg.name
+                    ),
+                }
+            }
+            expanded.dependencies.push(DependencyConfig {
+                name,
+                group_id: Some(group_id.clone()),
+                artifact_id: Some(artifact_id),
+                registry: Some(registry.clone()),
+                group: None,
+                labels: Default::default(),
+                include: Vec::new(),
+                exclude: Vec::new(),
+                member_of: Some(dep_cfg.name.clone()),
+                ..dep_cfg.clone()
+            });
+            selected += 1;
         }
-        Some(existing_lock)
-    } else {
-        None
-    };
+        if selected == 0 {
+            events.emit(Event::Warning(format!(
+                "group dependency '{}' selects no artifacts",
+                dep_cfg.name
+            )));
+        }
+    }
+    Ok(expanded)
+}
 
-    // 3) Build initial set of dependencies to resolve
-    let mut dependencies_to_resolve = Vec::new();
+/// Exact versions referenced per "groupId/artifactId", with the referencing parent
+type RequestedVersions = BTreeMap<String, Vec<(String, String)>>;
+
+/// One resolution pass; `pins` force versions like `overrides` do
+///
+/// Dependencies are resolved breadth-first: the direct dependencies, then the
+/// artifacts they reference, and so on. The artifacts of one level are resolved
+/// concurrently (up to [`RESOLVE_CONCURRENCY`] at a time) and merged in order,
+/// so the result does not depend on which answer arrives first.
+async fn resolve_with_pins(
+    repo_cfg: &RepoConfig,
+    clients: &HashMap<String, RegistryClient>,
+    only: Option<&str>,
+    pins: &BTreeMap<String, String>,
+    memo: &RegistryMemo,
+    events: &dyn EventSink,
+) -> Result<(Vec<LockedDependency>, RequestedVersions)> {
+    let mut wave = Vec::new();
 
     // Add direct dependencies from config
     for dep_cfg in &repo_cfg.dependencies {
+        if only
+            .is_some_and(|name| name != dep_cfg.name && dep_cfg.member_of.as_deref() != Some(name))
+        {
+            continue;
+        }
         let dep = Dependency::from_config_with_defaults(dep_cfg, &repo_cfg.dependency_defaults)?;
-        dependencies_to_resolve.push(DependencyToResolve {
+        wave.push(DependencyToResolve {
+            name: Some(dep_cfg.name.clone()),
             group_id: dep.group_id.clone(),
             artifact_id: dep.artifact_id.clone(),
             version_req: dep_cfg.version.clone(),
             registry: dep.registry.clone(),
             output_path: dep.output_path.clone(),
             is_transitive: false,
+            binary: dep_cfg.binary,
+            content_type: dep_cfg.content_type.clone(),
             depth: 0,
         });
     }
 
-    // 4) Resolve all dependencies including transitive references
+    for registry in &repo_cfg.reference_resolution.registry_fallbacks {
+        if !clients.contains_key(registry) {
+            anyhow::bail!(
+                "referenceResolution.registryFallbacks names unknown registry '{registry}'"
+            );
+        }
+    }
+
+    // Resolve all dependencies including transitive references
     let mut resolved_dependencies = HashMap::new();
     let mut processed = HashSet::new();
+    let mut requested_versions = RequestedVersions::new();
+
+    while !wave.is_empty() {
+        let mut batch = Vec::new();
+        for mut dep_to_resolve in wave.drain(..) {
+            let artifact_key = format!(
+                "{}:{}:{}",
+                dep_to_resolve.registry, dep_to_resolve.group_id, dep_to_resolve.artifact_id
+            );
+            // Direct dependencies are keyed by name, so two entries may lock the same
+            // artifact (e.g. v1 and v2 side by side); references by artifact
+            let key = match &dep_to_resolve.name {
+                Some(name) => format!("name:{name}"),
+                None => artifact_key.clone(),
+            };
 
-    while let Some(dep_to_resolve) = dependencies_to_resolve.pop() {
-        let key = format!(
-            "{}:{}:{}",
-            dep_to_resolve.registry, dep_to_resolve.group_id, dep_to_resolve.artifact_id
-        );
+            // Skip if already processed
+            if processed.contains(&key) {
+                continue;
+            }
+            processed.insert(key.clone());
+            // References to a direct dependency's artifact reuse its entry
+            processed.insert(artifact_key);
+
+            // Skip if depth exceeds maximum
+            if dep_to_resolve.depth > repo_cfg.reference_resolution.max_depth {
+                events.emit(Event::Warning(format!(
+                    "Skipping reference resolution for {} at depth {} (exceeds max depth {})",
+                    key, dep_to_resolve.depth, repo_cfg.reference_resolution.max_depth
+                )));
+                continue;
+            }
 
-        // Skip if already processed
-        if processed.contains(&key) {
-            continue;
+            // Skip references matching an exclude pattern
+            if dep_to_resolve.is_transitive
+                && repo_cfg
+                    .reference_resolution
+                    .is_excluded(&dep_to_resolve.group_id, &dep_to_resolve.artifact_id)
+            {
+                events.emit(Event::Skipped {
+                    name: format!("{}:{}", dep_to_resolve.group_id, dep_to_resolve.artifact_id),
+                    reason: "excluded by referenceResolution.exclude".to_string(),
+                });
+                continue;
+            }
+
+            // A version from `overrides` replaces whatever the parents reference
+            if dep_to_resolve.is_transitive {
+                let artifact =
+                    format!("{}/{}", dep_to_resolve.group_id, dep_to_resolve.artifact_id);
+                if let Some(version) = repo_cfg
+                    .version_override(&dep_to_resolve.group_id, &dep_to_resolve.artifact_id)
+                    .or_else(|| pins.get(&artifact))
+                {
+                    dep_to_resolve.version_req = version.clone();
+                }
+            }
+            batch.push((key, dep_to_resolve));
         }
-        processed.insert(key.clone());
 
-        // Skip if depth exceeds maximum
-        if dep_to_resolve.depth > repo_cfg.reference_resolution.max_depth {
-            eprintln!(
-                "Warning: Skipping reference resolution for {} at depth {} (exceeds max depth {})",
-                key, dep_to_resolve.depth, repo_cfg.reference_resolution.max_depth
-            );
-            continue;
+        let results: Vec<_> = stream::iter(batch.into_iter().map(|(key, dep)| async move {
+            let depth = dep.depth;
+            let resolved = resolve_one(repo_cfg, clients, memo, dep, events).await;
+            (key, depth, resolved)
+        }))
+        .buffered(RESOLVE_CONCURRENCY)
+        .collect()
+        .await;
+
+        for (key, depth, resolved) in results {
+            let Some(Resolved {
+                mut locked_dep,
+                references,
+                bytes,
+            }) = resolved?
+            else {
+                continue;
+            };
+            for reference in references {
+                // Use "default" as the group_id if the reference doesn't specify one
+                let ref_group_id = reference.group_id.as_deref().unwrap_or("default");
+                locked_dep
+                    .references
+                    .push(format!("{ref_group_id}/{}", reference.artifact_id));
+                // Needed to point the reference at the pulled file
+                if let Some(name) = reference
+                    .name
+                    .as_ref()
+                    .filter(|_| local_refs::records_names(repo_cfg, &locked_dep.output_path))
+                {
+                    locked_dep.reference_names.insert(
+                        format!("{ref_group_id}/{}", reference.artifact_id),
+                        name.clone(),
+                    );
+                }
+                requested_versions
+                    .entry(format!("{ref_group_id}/{}", reference.artifact_id))
+                    .or_default()
+                    .push((locked_dep.name.clone(), reference.version.clone()));
+
+                let ref_key = format!(
+                    "{}:{}:{}",
+                    locked_dep.registry, ref_group_id, reference.artifact_id
+                );
+
+                // Only add if not already processed or in queue
+                if !processed.contains(&ref_key)
+                    && !wave.iter().any(|d| {
+                        format!("{}:{}:{}", d.registry, d.group_id, d.artifact_id) == ref_key
+                    })
+                {
+                    wave.push(DependencyToResolve {
+                        name: None,
+                        group_id: ref_group_id.to_string(),
+                        artifact_id: reference.artifact_id,
+                        version_req: reference.version, // References use exact versions
+                        registry: locked_dep.registry.clone(), // Use same registry as parent
+                        output_path: None,              // Will be generated using pattern
+                        is_transitive: true,
+                        binary: false,
+                        content_type: None,
+                        depth: depth + 1,
+                    });
+                }
+            }
+
+            events.emit(Event::Resolved {
+                name: locked_dep.name.clone(),
+                registry: locked_dep.registry.clone(),
+                version: locked_dep.resolved_version.clone(),
+                transitive: locked_dep.is_transitive,
+                bytes,
+            });
+            resolved_dependencies.insert(key, locked_dep);
         }
+    }
 
-        let client = &clients[&dep_to_resolve.registry];
+    // Convert resolved dependencies to vector
+    let mut new_locks: Vec<LockedDependency> = resolved_dependencies.into_values().collect();
+    check_output_paths(&new_locks)?;
 
-        // Resolve version
-        let resolved_version = if dep_to_resolve.is_transitive {
-            // For transitive deps, version_req is already exact
-            semver::Version::parse(&dep_to_resolve.version_req)?
-        } else {
-            // For direct deps, resolve semver range
-            let dep = Dependency {
-                name: format!("{}/{}", dep_to_resolve.group_id, dep_to_resolve.artifact_id),
-                group_id: dep_to_resolve.group_id.clone(),
-                artifact_id: dep_to_resolve.artifact_id.clone(),
-                req: semver::VersionReq::parse(&dep_to_resolve.version_req)?,
-                registry: dep_to_resolve.registry.clone(),
-                output_path: dep_to_resolve.output_path.clone(),
-            };
+    // Sort to ensure consistent ordering (direct deps first, then alphabetical)
+    sort_locked_dependencies(&mut new_locks);
+    Ok((new_locks, requested_versions))
+}
 
-            let all_versions = client
-                .list_versions(&dep.group_id, &dep.artifact_id)
-                .await
-                .with_context(|| {
-                    format!("listing versions for {}/{}", dep.group_id, dep.artifact_id)
-                })?;
+/// A resolved dependency with the references still to be followed
+struct Resolved {
+    locked_dep: LockedDependency,
+    references: Vec<ArtifactVersionReference>,
+    /// Bytes downloaded to hash the content, `None` if the previous hash was reused
+    bytes: Option<usize>,
+}
 
-            let selected = all_versions
-                .iter()
-                .filter(|v| dep.req.matches(v))
-                .max()
-                .with_context(|| {
-                    format!(
-                        "no version matching '{}' for dependency '{}'",
-                        dep_to_resolve.version_req, dep.name
-                    )
-                })?;
-            selected.clone()
+/// Resolve the version, content and output path of one dependency; `None`
+/// when its output path is mapped to null
+async fn resolve_one(
+    repo_cfg: &RepoConfig,
+    clients: &HashMap<String, RegistryClient>,
+    memo: &RegistryMemo,
+    mut dep_to_resolve: DependencyToResolve,
+    events: &dyn EventSink,
+) -> Result<Option<Resolved>> {
+    let client = &clients[&dep_to_resolve.registry];
+
+    // Resolve version
+    let resolved_version = if dep_to_resolve.is_transitive {
+        // For transitive deps, version_req is already exact
+        semver::Version::parse(&dep_to_resolve.version_req)?
+    } else {
+        // For direct deps, resolve semver range
+        let dep = Dependency {
+            name: format!("{}/{}", dep_to_resolve.group_id, dep_to_resolve.artifact_id),
+            group_id: dep_to_resolve.group_id.clone(),
+            artifact_id: dep_to_resolve.artifact_id.clone(),
+            req: semver::VersionReq::parse(&dep_to_resolve.version_req)?,
+            registry: dep_to_resolve.registry.clone(),
+            output_path: dep_to_resolve.output_path.clone(),
         };
 
-        // Download content for hashing
-        let data = client
-            .download(
-                &dep_to_resolve.group_id,
-                &dep_to_resolve.artifact_id,
-                &resolved_version,
-            )
+        let all_versions = memo
+            .versions(client, &dep.group_id, &dep.artifact_id)
             .await
+            .with_context(|| {
+                format!("listing versions for {}/{}", dep.group_id, dep.artifact_id)
+            })?;
+
+        let selected = all_versions
+            .iter()
+            .filter(|v| dep.req.matches(v))
+            .max()
             .with_context(|| {
                 format!(
-                    "downloading content for {}:{} v{}",
-                    dep_to_resolve.group_id, dep_to_resolve.artifact_id, resolved_version
+                    "no version matching '{}' for dependency '{}'",
+                    dep_to_resolve.version_req, dep.name
                 )
             })?;
+        selected.clone()
+    };
 
-        // Compute SHA256
-        let sha256 = {
-            let mut hasher = Sha256::new();
-            hasher.update(&data);
-            hex::encode(hasher.finalize())
-        };
-
-        // Determine output path
-        let output_path = if let Some(path) = dep_to_resolve.output_path {
-            Some(path)
-        } else {
-            let metadata = client
-                .get_artifact_metadata(&dep_to_resolve.group_id, &dep_to_resolve.artifact_id)
-                .await?;
-            if dep_to_resolve.is_transitive {
-                let base_pattern = repo_cfg.reference_resolution.output_patterns.resolve(
-                    &metadata.artifact_type,
-                    Some(&repo_cfg.dependency_defaults.output_patterns),
-                );
-                resolve_output_path(
-                    &base_pattern,
-                    &repo_cfg.reference_resolution.output_overrides,
-                    &dep_to_resolve.registry,
-                    &dep_to_resolve.group_id,
-                    &dep_to_resolve.artifact_id,
-                    &resolved_version.to_string(),
-                    &metadata.artifact_type,
-                )
-            } else {
-                let pattern = repo_cfg
-                    .dependency_defaults
-                    .output_patterns
-                    .resolve(&metadata.artifact_type, None);
-                Some(output_path::generate_output_path(
-                    &pattern,
-                    &dep_to_resolve.group_id,
-                    &dep_to_resolve.artifact_id,
-                    &resolved_version.to_string(),
-                    &metadata.artifact_type,
-                ))
-            }
-        };
+    // An unchanged entry of the previous lock already knows the content hash
+    let reused = match memo.previous(&dep_to_resolve, &resolved_version) {
+        Some(previous) => {
+            reuse_previous(
+                repo_cfg,
+                client,
+                memo,
+                &dep_to_resolve,
+                &resolved_version,
+                previous,
+            )
+            .await?
+        }
+        None => None,
+    };
+    let ((output_path, sha256, avro_fingerprint, content_type), content) = match reused {
+        Some(reused) => (reused, None),
+        None => {
+            let Some((fetched, content)) = download_and_hash(
+                repo_cfg,
+                clients,
+                memo,
+                &mut dep_to_resolve,
+                &resolved_version,
+            )
+            .await?
+            else {
+                events.emit(Event::Skipped {
+                    name: format!("{}:{}", dep_to_resolve.group_id, dep_to_resolve.artifact_id),
+                    reason: "mapped to null".to_string(),
+                });
+                return Ok(None);
+            };
+            (fetched, Some(content))
+        }
+    };
+    let bytes = content.as_ref().map(|content| content.len());
+    let client = &clients[&dep_to_resolve.registry];
+
+    // The config entry of a direct dependency
+    let original_dep_config = dep_to_resolve
+        .name
+        .as_ref()
+        .and_then(|name| repo_cfg.dependencies.iter().find(|cfg| &cfg.name == name));
+
+    // Create locked dependency
+    let locked_dep = LockedDependency {
+        name: original_dep_config
+            .map(|cfg| cfg.name.clone())
+            .unwrap_or_else(|| {
+                format!("{}/{}", dep_to_resolve.group_id, dep_to_resolve.artifact_id)
+            }),
+        registry: dep_to_resolve.registry.clone(),
+        resolved_version: resolved_version.to_string(),
+        download_url: client.get_download_url(
+            &dep_to_resolve.group_id,
+            &dep_to_resolve.artifact_id,
+            &resolved_version,
+        ),
+        registry_url: Some(client.base_url.clone()),
+        sha256,
+        output_path,
+        group_id: dep_to_resolve.group_id.clone(),
+        artifact_id: dep_to_resolve.artifact_id.clone(),
+        version_spec: dep_to_resolve.version_req.clone(),
+        is_transitive: dep_to_resolve.is_transitive,
+        references: Vec::new(),
+        reference_names: Default::default(),
+        binary: dep_to_resolve.binary,
+        avro_fingerprint,
+        content_type,
+        member_of: original_dep_config.and_then(|cfg| cfg.member_of.clone()),
+    };
 
-        // Skip this dependency if it's mapped to null (excluded from resolution)
-        let output_path = match output_path {
-            Some(path) => path,
-            None => {
-                println!(
-                    "  ⏭️  Skipping transitive dependency {}:{} (mapped to null)",
-                    dep_to_resolve.group_id, dep_to_resolve.artifact_id
-                );
-                continue; // Skip to next dependency
-            }
-        };
+    // Determine if reference resolution should be enabled for this dependency
+    let should_resolve_references = if dep_to_resolve.is_transitive {
+        // For transitive dependencies, always use global setting
+        repo_cfg.reference_resolution.enabled
+    } else {
+        // For direct dependencies, check per-dependency override first
+        match original_dep_config.and_then(|cfg| cfg.resolve_references) {
+            Some(override_setting) => override_setting,
+            None => repo_cfg.reference_resolution.enabled,
+        }
+    };
 
-        // Create locked dependency
-        let locked_dep = LockedDependency {
-            name: if dep_to_resolve.is_transitive {
-                format!("{}/{}", dep_to_resolve.group_id, dep_to_resolve.artifact_id)
-            } else {
-                // Find the original name from config
-                repo_cfg
-                    .dependencies
-                    .iter()
-                    .find(|cfg| {
-                        let dep = Dependency::from_config_with_defaults(
-                            cfg,
-                            &repo_cfg.dependency_defaults,
-                        )
-                        .unwrap();
-                        dep.group_id == dep_to_resolve.group_id
-                            && dep.artifact_id == dep_to_resolve.artifact_id
-                    })
-                    .map(|cfg| cfg.name.clone())
-                    .unwrap_or_else(|| {
-                        format!("{}/{}", dep_to_resolve.group_id, dep_to_resolve.artifact_id)
-                    })
-            },
-            registry: dep_to_resolve.registry.clone(),
-            resolved_version: resolved_version.to_string(),
-            download_url: client.get_download_url(
+    // If reference resolution is enabled, get version references
+    let mut references = Vec::new();
+    if should_resolve_references && dep_to_resolve.depth < repo_cfg.reference_resolution.max_depth {
+        match memo
+            .references(
+                client,
                 &dep_to_resolve.group_id,
                 &dep_to_resolve.artifact_id,
                 &resolved_version,
-            ),
-            sha256,
-            output_path,
-            group_id: dep_to_resolve.group_id.clone(),
-            artifact_id: dep_to_resolve.artifact_id.clone(),
-            version_spec: dep_to_resolve.version_req.clone(),
-            is_transitive: dep_to_resolve.is_transitive,
+            )
+            .await
+        {
+            Ok(found) => references = found,
+            Err(e) => {
+                events.emit(Event::Warning(format!(
+                    "Failed to get version references for {}:{} v{}: {}",
+                    dep_to_resolve.group_id, dep_to_resolve.artifact_id, resolved_version, e
+                )));
+            }
+        }
+    }
+
+    let json_schema = &repo_cfg.json_schema;
+    if !json_schema.is_empty() && !locked_dep.binary {
+        let content = match content {
+            Some(content) => content,
+            None => {
+                client
+                    .download_accepting(
+                        &dep_to_resolve.group_id,
+                        &dep_to_resolve.artifact_id,
+                        &locked_dep.resolved_version,
+                        locked_dep.content_type.as_deref(),
+                    )
+                    .await
+                    .with_context(|| format!("downloading {}", locked_dep.output_path))?
+                    .1
+            }
         };
+        let follow = json_schema.resolve_refs
+            && should_resolve_references
+            && dep_to_resolve.depth < repo_cfg.reference_resolution.max_depth;
+        let problems = check_json_schema(
+            repo_cfg,
+            client,
+            memo,
+            &locked_dep,
+            &content,
+            &mut references,
+            follow,
+        )
+        .await;
+        for problem in problems {
+            events.emit(Event::Warning(format!(
+                "{} ({}): {problem}",
+                locked_dep.name, locked_dep.output_path
+            )));
+        }
+    }
+    Ok(Some(Resolved {
+        locked_dep,
+        references,
+        bytes,
+    }))
+}
 
-        resolved_dependencies.insert(key, locked_dep);
+/// Check a locked JSON Schema (`jsonSchema`), returning its malformed keywords
+/// and dangling `$ref`s
+///
+/// With `follow`, `$ref`s naming artifacts of the same registry by URL are
+/// added to `references`, to be locked as transitive dependencies.
+async fn check_json_schema(
+    repo_cfg: &RepoConfig,
+    client: &RegistryClient,
+    memo: &RegistryMemo,
+    locked_dep: &LockedDependency,
+    content: &[u8],
+    references: &mut Vec<ArtifactVersionReference>,
+    follow: bool,
+) -> Vec<String> {
+    let Some(schema) = json_schema::parse(&locked_dep.output_path, content) else {
+        return Vec::new();
+    };
+    let found = json_schema::check(&schema);
+    let mut problems = Vec::new();
+    if repo_cfg.json_schema.validate {
+        problems.extend(found.problems.iter().cloned());
+    }
 
-        // Determine if reference resolution should be enabled for this dependency
-        let should_resolve_references = if dep_to_resolve.is_transitive {
-            // For transitive dependencies, always use global setting
-            repo_cfg.reference_resolution.enabled
-        } else {
-            // For direct dependencies, check per-dependency override first
-            let original_dep_config = repo_cfg.dependencies.iter().find(|cfg| {
-                let dep = Dependency::from_config_with_defaults(cfg, &repo_cfg.dependency_defaults)
-                    .unwrap();
-                dep.group_id == dep_to_resolve.group_id
-                    && dep.artifact_id == dep_to_resolve.artifact_id
+    let mut names: Vec<String> = references.iter().filter_map(|r| r.name.clone()).collect();
+    if follow {
+        for reference in &found.refs {
+            let Some(target) = json_schema::registry_ref(&client.base_url, reference) else {
+                continue;
+            };
+            if names.contains(&target.name) {
+                continue;
+            }
+            // a missing artifact or version is a dangling reference, not an error
+            let versions = memo
+                .versions(client, &target.group_id, &target.artifact_id)
+                .await
+                .unwrap_or_default();
+            let version = match &target.version {
+                Some(version) => semver::Version::parse(version)
+                    .ok()
+                    .filter(|v| versions.contains(v)),
+                None => versions.into_iter().max(),
+            };
+            let Some(version) = version else {
+                problems.push(format!(
+                    "$ref '{reference}' names no version of an artifact in registry '{}'",
+                    client.name
+                ));
+                continue;
+            };
+            references.push(ArtifactVersionReference {
+                group_id: Some(target.group_id),
+                artifact_id: target.artifact_id,
+                version: version.to_string(),
+                name: Some(target.name.clone()),
             });
+            names.push(target.name);
+        }
+    }
+    problems.extend(json_schema::dangling(&schema, &found.refs, &names));
+    problems
+}
 
-            match original_dep_config.and_then(|cfg| cfg.resolve_references) {
-                Some(override_setting) => override_setting,
-                None => repo_cfg.reference_resolution.enabled,
-            }
-        };
+/// Output path, sha256, Avro fingerprint and content type of a lock entry
+type Hashed = (
+    String,
+    String,
+    Option<avro::AvroFingerprint>,
+    Option<String>,
+);
+
+/// The hash of the previous lock entry for the same registry, artifact and
+/// version, when it would be downloaded from the same URL in the same format
+/// to the same path; `None` when the content has to be downloaded
+async fn reuse_previous(
+    repo_cfg: &RepoConfig,
+    client: &RegistryClient,
+    memo: &RegistryMemo,
+    dep_to_resolve: &DependencyToResolve,
+    resolved_version: &semver::Version,
+    previous: LockedDependency,
+) -> Result<Option<Hashed>> {
+    let download_url = client.get_download_url(
+        &dep_to_resolve.group_id,
+        &dep_to_resolve.artifact_id,
+        resolved_version,
+    );
+    let same_format = dep_to_resolve.content_type.as_deref().is_none_or(|accept| {
+        registry::content_type_matches(previous.content_type.as_deref(), accept)
+    });
+    if previous.binary != dep_to_resolve.binary
+        || previous.download_url != download_url
+        || !same_format
+    {
+        return Ok(None);
+    }
+    let (output_path, _) =
+        output_path_for(repo_cfg, client, memo, dep_to_resolve, resolved_version).await?;
+    if output_path != Some(previous.output_path.clone()) {
+        return Ok(None);
+    }
+    Ok(Some((
+        previous.output_path,
+        previous.sha256,
+        previous.avro_fingerprint,
+        previous.content_type,
+    )))
+}
+
+/// Download the content of a dependency to hash it, returning the hash and the
+/// content; `None` when its output path is mapped to null
+///
+/// A reference missing from its parent's registry is looked up in the
+/// fallback registries, in order; `dep_to_resolve.registry` is updated to the
+/// registry that had it.
+async fn download_and_hash(
+    repo_cfg: &RepoConfig,
+    clients: &HashMap<String, RegistryClient>,
+    memo: &RegistryMemo,
+    dep_to_resolve: &mut DependencyToResolve,
+    resolved_version: &semver::Version,
+) -> Result<Option<(Hashed, bytes::Bytes)>> {
+    let mut candidates = vec![dep_to_resolve.registry.as_str()];
+    if dep_to_resolve.is_transitive {
+        candidates.extend(
+            repo_cfg
+                .reference_resolution
+                .registry_fallbacks
+                .iter()
+                .map(String::as_str)
+                .filter(|name| *name != dep_to_resolve.registry),
+        );
+    }
+    // An explicit output path already tells which format to ask for
+    let mut accept = dep_to_resolve.content_type.clone().or_else(|| {
+        registry::accept_for(None, dep_to_resolve.output_path.as_deref()).map(str::to_string)
+    });
+    let (registry, mut content_type, mut data) = download_from_first(
+        clients,
+        &candidates,
+        &dep_to_resolve.group_id,
+        &dep_to_resolve.artifact_id,
+        resolved_version,
+        accept.as_deref(),
+    )
+    .await
+    .with_context(|| {
+        format!(
+            "downloading content for {}:{} v{}",
+            dep_to_resolve.group_id, dep_to_resolve.artifact_id, resolved_version
+        )
+    })?;
+    dep_to_resolve.registry = registry.to_string();
+    let client = &clients[&dep_to_resolve.registry];
+
+    let (output_path, artifact_type) =
+        output_path_for(repo_cfg, client, memo, dep_to_resolve, resolved_version).await?;
+    let Some(output_path) = output_path else {
+        return Ok(None);
+    };
 
-        // If reference resolution is enabled, get version references
-        if should_resolve_references
-            && dep_to_resolve.depth < repo_cfg.reference_resolution.max_depth
+    // With the artifact type known, ask again if the registry picked another format
+    if accept.is_none() {
+        accept =
+            registry::accept_for(artifact_type.as_deref(), Some(&output_path)).map(str::to_string);
+        if let Some(accept) = accept
+            .as_deref()
+            .filter(|a| !registry::content_type_matches(content_type.as_deref(), a))
         {
-            match client
-                .get_version_references(
+            (content_type, data) = client
+                .download_accepting(
                     &dep_to_resolve.group_id,
                     &dep_to_resolve.artifact_id,
-                    &resolved_version,
-                    None,
+                    &resolved_version.to_string(),
+                    Some(accept),
                 )
                 .await
-            {
-                Ok(references) => {
-                    for reference in references {
-                        // Use "default" as the group_id if the reference doesn't specify one
-                        let ref_group_id = reference.group_id.as_deref().unwrap_or("default");
-
-                        let ref_key = format!(
-                            "{}:{}:{}",
-                            dep_to_resolve.registry, ref_group_id, reference.artifact_id
-                        );
-
-                        // Only add if not already processed or in queue
-                        if !processed.contains(&ref_key)
-                            && !dependencies_to_resolve.iter().any(|d| {
-                                format!("{}:{}:{}", d.registry, d.group_id, d.artifact_id)
-                                    == ref_key
-                            })
-                        {
-                            dependencies_to_resolve.push(DependencyToResolve {
-                                group_id: ref_group_id.to_string(),
-                                artifact_id: reference.artifact_id,
-                                version_req: reference.version, // References use exact versions
-                                registry: dep_to_resolve.registry.clone(), // Use same registry as parent
-                                output_path: None, // Will be generated using pattern
-                                is_transitive: true,
-                                depth: dep_to_resolve.depth + 1,
-                            });
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Warning: Failed to get version references for {}:{} v{}: {}",
-                        dep_to_resolve.group_id, dep_to_resolve.artifact_id, resolved_version, e
-                    );
-                }
-            }
+                .with_context(|| format!("downloading {output_path} as {accept}"))?;
         }
     }
+    let data = if dep_to_resolve.binary {
+        decode_binary_content(&data)
+            .with_context(|| format!("decoding {output_path}"))?
+            .into()
+    } else {
+        data
+    };
+    let sha256 = hex::encode(Sha256::digest(&data));
+    let avro_fingerprint = avro::lock_fingerprint(&output_path, &data);
+    Ok(Some((
+        (output_path, sha256, avro_fingerprint, content_type),
+        data,
+    )))
+}
 
-    // Convert resolved dependencies to vector
-    let mut new_locks: Vec<LockedDependency> = resolved_dependencies.into_values().collect();
+/// Output path of a dependency (`None` when mapped to null) and, when its
+/// metadata had to be fetched for the output pattern, its artifact type
+async fn output_path_for(
+    repo_cfg: &RepoConfig,
+    client: &RegistryClient,
+    memo: &RegistryMemo,
+    dep_to_resolve: &DependencyToResolve,
+    resolved_version: &semver::Version,
+) -> Result<(Option<String>, Option<String>)> {
+    if let Some(path) = &dep_to_resolve.output_path {
+        return Ok((Some(artifact_writer::explicit_path(path)), None));
+    }
+    let metadata = memo
+        .metadata(
+            client,
+            &dep_to_resolve.group_id,
+            &dep_to_resolve.artifact_id,
+        )
+        .await?;
+    let pattern_metadata = metadata.pattern_metadata(&dep_to_resolve.registry);
+    let (group_id, artifact_id) = (&dep_to_resolve.group_id, &dep_to_resolve.artifact_id);
+    let version = resolved_version.to_string();
+    let output_path = if dep_to_resolve.is_transitive {
+        artifact_writer::reference_path(
+            repo_cfg,
+            group_id,
+            artifact_id,
+            &version,
+            &pattern_metadata,
+        )?
+    } else {
+        Some(artifact_writer::pattern_path(
+            repo_cfg,
+            group_id,
+            artifact_id,
+            &version,
+            &pattern_metadata,
+        )?)
+    };
+    Ok((output_path, Some(metadata.artifact_type)))
+}
 
-    // Sort to ensure consistent ordering (direct deps first, then alphabetical)
-    new_locks.sort_by(|a, b| match (a.is_transitive, b.is_transitive) {
-        (false, true) => std::cmp::Ordering::Less,
-        (true, false) => std::cmp::Ordering::Greater,
-        _ => a.name.cmp(&b.name),
-    });
+/// Registry answers shared by the dependencies of a resolution and by the
+/// passes `conflictPolicy: highest` repeats, plus the entries of the previous
+/// lock whose hashes can be reused
+#[derive(Default)]
+pub(crate) struct RegistryMemo {
+    versions: Mutex<HashMap<String, Vec<semver::Version>>>,
+    metadata: Mutex<HashMap<String, ArtifactMetadata>>,
+    references: Mutex<HashMap<String, Vec<ArtifactVersionReference>>>,
+    previous: HashMap<String, LockedDependency>,
+}
 
-    // 4) Create new lockfile with metadata including config modification time
-    let config_modified = LockFile::get_config_modification_time(&config_path).ok();
-    let lf = LockFile::with_config_modified(new_locks, config_hash, config_modified);
+impl RegistryMemo {
+    /// A memo reusing the hashes of `previous` lock entries
+    pub(crate) fn reusing(previous: &[LockedDependency]) -> Self {
+        let previous = previous
+            .iter()
+            .map(|dep| {
+                let key = format!(
+                    "{}:{}:{}:{}",
+                    dep.registry, dep.group_id, dep.artifact_id, dep.resolved_version
+                );
+                (key, dep.clone())
+            })
+            .collect();
+        RegistryMemo {
+            previous,
+            ..Default::default()
+        }
+    }
 
-    // 5) Clean up old output paths if they changed
-    if let Some(ref old_lock) = existing_lock {
-        cleanup_changed_output_paths(&old_lock.locked_dependencies, &lf.locked_dependencies)?;
+    fn previous(
+        &self,
+        dep_to_resolve: &DependencyToResolve,
+        version: &semver::Version,
+    ) -> Option<LockedDependency> {
+        let key = format!(
+            "{}:{}:{}:{version}",
+            dep_to_resolve.registry, dep_to_resolve.group_id, dep_to_resolve.artifact_id
+        );
+        self.previous.get(&key).cloned()
     }
 
-    lf.save(&lock_path)
-        .with_context(|| format!("writing {}", lock_path.display()))?;
-    println!("🔒 Updated {}", lock_path.display());
+    async fn versions(
+        &self,
+        client: &RegistryClient,
+        group_id: &str,
+        artifact_id: &str,
+    ) -> crate::error::Result<Vec<semver::Version>> {
+        let key = format!("{}:{group_id}:{artifact_id}", client.name);
+        if let Some(versions) = self.versions.lock().unwrap().get(&key) {
+            return Ok(versions.clone());
+        }
+        let versions = client.list_versions(group_id, artifact_id).await?;
+        self.versions.lock().unwrap().insert(key, versions.clone());
+        Ok(versions)
+    }
+
+    async fn metadata(
+        &self,
+        client: &RegistryClient,
+        group_id: &str,
+        artifact_id: &str,
+    ) -> crate::error::Result<ArtifactMetadata> {
+        let key = format!("{}:{group_id}:{artifact_id}", client.name);
+        if let Some(metadata) = self.metadata.lock().unwrap().get(&key) {
+            return Ok(metadata.clone());
+        }
+        let metadata = client.get_artifact_metadata(group_id, artifact_id).await?;
+        self.metadata.lock().unwrap().insert(key, metadata.clone());
+        Ok(metadata)
+    }
+
+    async fn references(
+        &self,
+        client: &RegistryClient,
+        group_id: &str,
+        artifact_id: &str,
+        version: &semver::Version,
+    ) -> crate::error::Result<Vec<ArtifactVersionReference>> {
+        let key = format!("{}:{group_id}:{artifact_id}:{version}", client.name);
+        if let Some(references) = self.references.lock().unwrap().get(&key) {
+            return Ok(references.clone());
+        }
+        let references = client
+            .get_version_references(group_id, artifact_id, version, None)
+            .await?;
+        self.references
+            .lock()
+            .unwrap()
+            .insert(key, references.clone());
+        Ok(references)
+    }
+}
 
+/// Fail when two lock entries would write the same file, e.g. two copies of an
+/// artifact placed by the same output pattern
+fn check_output_paths(dependencies: &[LockedDependency]) -> Result<()> {
+    let mut writers: BTreeMap<&str, &str> = BTreeMap::new();
+    for dep in dependencies {
+        if let Some(other) = writers.insert(&dep.output_path, &dep.name) {
+            let (first, second) = if other < dep.name.as_str() {
+                (other, dep.name.as_str())
+            } else {
+                (dep.name.as_str(), other)
+            };
+            anyhow::bail!(
+                "dependencies '{first}' and '{second}' both write {}; give one of them its own outputPath",
+                dep.output_path
+            );
+        }
+    }
     Ok(())
 }
 
+/// An artifact referenced at different exact versions
+#[derive(Debug, PartialEq)]
+struct VersionConflict {
+    /// "groupId/artifactId"
+    artifact: String,
+    /// (referencing parent, requested version)
+    requests: Vec<(String, String)>,
+    highest: semver::Version,
+}
+
+impl std::fmt::Display for VersionConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let requests = self
+            .requests
+            .iter()
+            .map(|(parent, version)| format!("{version} (from {parent})"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{} is referenced as {requests}", self.artifact)
+    }
+}
+
+/// Artifacts referenced at different exact versions that neither an `overrides`
+/// entry nor a pin at the highest requested version settles
+fn find_version_conflicts(
+    repo_cfg: &RepoConfig,
+    requested_versions: &RequestedVersions,
+    pins: &BTreeMap<String, String>,
+) -> Vec<VersionConflict> {
+    let mut conflicts = Vec::new();
+    for (artifact, requests) in requested_versions {
+        let versions: BTreeSet<semver::Version> = requests
+            .iter()
+            .filter_map(|(_, v)| semver::Version::parse(v).ok())
+            .collect();
+        let (group_id, artifact_id) = artifact.split_once('/').unwrap_or(("", artifact));
+        let Some(highest) = versions.last() else {
+            continue;
+        };
+        if versions.len() < 2
+            || repo_cfg.version_override(group_id, artifact_id).is_some()
+            || pins.get(artifact) == Some(&highest.to_string())
+        {
+            continue;
+        }
+        conflicts.push(VersionConflict {
+            artifact: artifact.clone(),
+            requests: requests.clone(),
+            highest: highest.clone(),
+        });
+    }
+    conflicts
+}
+
+/// Download from the first of `registries` that has the version, asking for
+/// the `accept` media type
+///
+/// Only a "not found" answer moves on to the next registry; any other error is
+/// returned right away.
+async fn download_from_first<'a>(
+    clients: &HashMap<String, RegistryClient>,
+    registries: &[&'a str],
+    group_id: &str,
+    artifact_id: &str,
+    version: &semver::Version,
+    accept: Option<&str>,
+) -> Result<(&'a str, Option<String>, bytes::Bytes)> {
+    let mut not_found = None;
+    for registry in registries {
+        let client = clients
+            .get(*registry)
+            .with_context(|| format!("registry '{registry}' is not configured"))?;
+        match client
+            .download_accepting(group_id, artifact_id, &version.to_string(), accept)
+            .await
+        {
+            Ok((content_type, data)) => return Ok((registry, content_type, data)),
+            Err(e) if e.is_not_found() => not_found = Some(e),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    match not_found {
+        Some(e) => Err(e.into()),
+        None => Err(anyhow::anyhow!("no registry to download from")),
+    }
+}
+
+/// Ensure the existing lock can be used as-is, failing instead of re-resolving
+///
+/// With `--locked` the lock must exist, match the config hash, and every locked
+/// version must still be available. `--frozen` skips the registry round-trip.
+pub(crate) async fn check_lock_unchanged(
+    lock_path: &Path,
+    config_hash: &str,
+    config_inputs: &[(String, String)],
+    clients: &HashMap<String, RegistryClient>,
+    opts: LockOptions,
+    events: &dyn EventSink,
+) -> Result<LockFile> {
+    let flag = opts.flag_name();
+    if !lock_path.exists() {
+        anyhow::bail!(
+            "{} is missing and {} was passed to prevent creating it",
+            lock_path.display(),
+            flag
+        );
+    }
+    let lock =
+        LockFile::load(lock_path).with_context(|| format!("reading {}", lock_path.display()))?;
+    if !lock.is_compatible_with_config(config_hash) {
+        let changes: Vec<String> = lock
+            .changed_config_inputs(config_inputs)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let what = match changes.is_empty() {
+            true => String::new(),
+            false => format!(": {}", changes.join(", ")),
+        };
+        anyhow::bail!(
+            "{} needs to be updated (config has changed{what}) but {} was passed to prevent this",
+            lock_path.display(),
+            flag
+        );
+    }
+    if !opts.frozen && !verify_lock_is_still_valid(&lock, clients, events).await? {
+        anyhow::bail!(
+            "{} needs to be updated (some locked versions are unavailable) but {} was passed to prevent this",
+            lock_path.display(),
+            flag
+        );
+    }
+    Ok(lock)
+}
+
 /// Verify that an existing lock file can still be resolved with the same versions
 /// This performs a more lightweight check than re-resolving all dependencies
-async fn verify_lock_is_still_valid(
+pub(crate) async fn verify_lock_is_still_valid(
     lock: &LockFile,
     clients: &HashMap<String, RegistryClient>,
+    events: &dyn EventSink,
 ) -> Result<bool> {
     // Quick optimization: if the lockfile is very recent (< 5 minutes),
     // trust it without checking registries
-    if let Ok(generated_nanos) = lock.generated_at.parse::<i64>() {
-        let now_nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0);
+    if recently_generated(lock) {
+        return Ok(true);
+    }
 
-        // If lockfile was generated within the last 5 minutes, trust it
-        let five_minutes_nanos = 5 * 60 * 1_000_000_000i64; // 5 minutes in nanoseconds
-        if now_nanos.saturating_sub(generated_nanos) < five_minutes_nanos {
-            return Ok(true);
+    // Otherwise, verify each dependency can still be resolved
+    match unavailable_versions(lock, clients, true).await.first() {
+        Some(problem) => {
+            events.emit(Event::Warning(problem.clone()));
+            Ok(false)
         }
+        None => Ok(true),
     }
+}
 
-    // Otherwise, verify each dependency can still be resolved
+/// Whether the lock was generated less than 5 minutes ago, recently enough to
+/// trust its versions without asking the registries
+pub(crate) fn recently_generated(lock: &LockFile) -> bool {
+    let Ok(generated_nanos) = lock.generated_at.parse::<i64>() else {
+        return false;
+    };
+    let now_nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or(0);
+    let five_minutes_nanos = 5 * 60 * 1_000_000_000i64; // 5 minutes in nanoseconds
+    now_nanos.saturating_sub(generated_nanos) < five_minutes_nanos
+}
+
+/// Why locked versions cannot be downloaded again: registries no longer
+/// configured, versions gone, or registries that could not be asked
+///
+/// With `first_only`, stops at the first problem.
+pub(crate) async fn unavailable_versions(
+    lock: &LockFile,
+    clients: &HashMap<String, RegistryClient>,
+    first_only: bool,
+) -> Vec<String> {
+    let mut problems = Vec::new();
     for locked_dep in &lock.locked_dependencies {
+        if first_only && !problems.is_empty() {
+            break;
+        }
         let client = match clients.get(&locked_dep.registry) {
             Some(c) => c,
             None => {
-                eprintln!(
-                    "Warning: Registry '{}' is no longer configured",
+                problems.push(format!(
+                    "Registry '{}' is no longer configured",
                     locked_dep.registry
-                );
-                return Ok(false);
+                ));
+                continue;
             }
         };
 
@@ -398,101 +1327,106 @@ async fn verify_lock_is_still_valid(
                     .iter()
                     .any(|v| v.to_string() == locked_dep.resolved_version)
                 {
-                    eprintln!(
-                        "Warning: Version '{}' of '{}:{}' is no longer available",
+                    problems.push(format!(
+                        "Version '{}' of '{}:{}' is no longer available",
                         locked_dep.resolved_version, locked_dep.group_id, locked_dep.artifact_id
-                    );
-                    return Ok(false);
+                    ));
                 }
             }
-            Err(e) => {
-                eprintln!(
-                    "Warning: Failed to check availability of '{}:{}': {}",
-                    locked_dep.group_id, locked_dep.artifact_id, e
-                );
-                // On network errors, etc., we'll be conservative and re-generate
-                return Ok(false);
-            }
+            // On network errors, etc., we'll be conservative and re-generate
+            Err(e) => problems.push(format!(
+                "Failed to check availability of '{}:{}': {}",
+                locked_dep.group_id, locked_dep.artifact_id, e
+            )),
         }
     }
-    Ok(true)
+    problems
+}
+
+/// An output file of the previous lock that no entry of the new lock writes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StaleOutput {
+    /// Output path, relative to the project directory
+    pub path: String,
+    /// Set if the dependency was removed from the config entirely
+    pub removed_dependency: Option<String>,
 }
 
-/// Clean up old output files when their paths change during locking
-fn cleanup_changed_output_paths(
+/// Output files of `old_dependencies` left behind by `new_dependencies`: those
+/// of dependencies removed from the config or whose output path changed
+///
+/// A path some new entry writes is never stale, even if another dependency
+/// owned it before.
+pub(crate) fn stale_outputs(
     old_dependencies: &[LockedDependency],
     new_dependencies: &[LockedDependency],
-) -> Result<()> {
-    use std::collections::HashMap;
-
-    // Create a map of dependency name to output path for old and new dependencies
-    let old_paths: HashMap<&str, &str> = old_dependencies
+) -> Vec<StaleOutput> {
+    let new_paths: HashSet<&str> = new_dependencies
         .iter()
-        .map(|dep| (dep.name.as_str(), dep.output_path.as_str()))
+        .map(|dep| dep.output_path.as_str())
         .collect();
-
-    let new_paths: HashMap<&str, &str> = new_dependencies
+    let new_names: HashSet<&str> = new_dependencies
         .iter()
-        .map(|dep| (dep.name.as_str(), dep.output_path.as_str()))
+        .map(|dep| dep.name.as_str())
         .collect();
 
-    // Check for dependencies with changed output paths
-    for (dep_name, old_path) in &old_paths {
-        if let Some(new_path) = new_paths.get(dep_name) {
-            // If the dependency still exists but the output path changed
-            if old_path != new_path {
-                let old_file = PathBuf::from(old_path);
-                if old_file.exists() {
-                    match std::fs::remove_file(&old_file) {
-                        Ok(()) => {
-                            println!("🗑️  Removed old output file: {old_path}");
-                        }
-                        Err(e) => {
-                            eprintln!(
-                                "Warning: Failed to remove old output file '{old_path}': {e}"
-                            );
-                        }
-                    }
-
-                    // Also try to remove empty parent directories
-                    if let Some(parent) = old_file.parent() {
-                        let _ = remove_empty_parent_dirs(parent);
-                    }
-                }
-            }
-        } else {
-            // Dependency was removed entirely - clean up its output file
-            let old_file = PathBuf::from(old_path);
-            if old_file.exists() {
-                match std::fs::remove_file(&old_file) {
-                    Ok(()) => {
-                        println!(
-                            "🗑️  Removed output file for removed dependency '{dep_name}': {old_path}"
-                        );
-                    }
-                    Err(e) => {
-                        eprintln!(
-                            "Warning: Failed to remove output file for removed dependency '{dep_name}': {e}"
-                        );
-                    }
-                }
+    let mut stale: Vec<StaleOutput> = Vec::new();
+    for dep in old_dependencies {
+        if new_paths.contains(dep.output_path.as_str())
+            || stale.iter().any(|s| s.path == dep.output_path)
+        {
+            continue;
+        }
+        stale.push(StaleOutput {
+            path: dep.output_path.clone(),
+            removed_dependency: (!new_names.contains(dep.name.as_str())).then(|| dep.name.clone()),
+        });
+    }
+    stale
+}
 
-                // Also try to remove empty parent directories
-                if let Some(parent) = old_file.parent() {
-                    let _ = remove_empty_parent_dirs(parent);
+/// Delete stale output files now, warning about those that cannot be deleted
+///
+/// Only for a lock not followed by a pull: `pull` deletes them together with
+/// writing the new files, so a failed download leaves them in place.
+pub(crate) fn remove_stale_outputs(root: &Path, stale: &[StaleOutput], events: &dyn EventSink) {
+    let mut removed = Vec::new();
+    for output in stale {
+        let file = output_path::native(root, &output.path);
+        if !file.exists() {
+            continue;
+        }
+        match artifact_writer::remove_output_file(&file) {
+            Ok(()) => removed.push(output.clone()),
+            Err(e) => events.emit(Event::Warning(match &output.removed_dependency {
+                None => format!("Failed to remove old output file '{}': {e}", output.path),
+                Some(name) => {
+                    format!("Failed to remove output file for removed dependency '{name}': {e}")
                 }
-            }
+            })),
         }
     }
+    stale_outputs_removed(root, &removed, events);
+}
 
-    Ok(())
+/// Report deleted stale output files and remove the directories they left empty
+pub(crate) fn stale_outputs_removed(root: &Path, removed: &[StaleOutput], events: &dyn EventSink) {
+    for output in removed {
+        events.emit(Event::OutputRemoved {
+            path: output.path.clone(),
+            removed_dependency: output.removed_dependency.clone(),
+        });
+        if let Some(parent) = output_path::native(root, &output.path).parent() {
+            let _ = remove_empty_parent_dirs(root, parent, events);
+        }
+    }
 }
 
-/// Recursively remove empty parent directories up to the current working directory
-fn remove_empty_parent_dirs(dir: &std::path::Path) -> Result<()> {
-    // Don't try to remove the current working directory or root
+/// Recursively remove empty parent directories up to the project directory
+fn remove_empty_parent_dirs(root: &Path, dir: &Path, events: &dyn EventSink) -> Result<()> {
+    // Don't try to remove the project directory itself or the filesystem root
     let cwd = std::env::current_dir().unwrap_or_default();
-    if dir == cwd || dir.parent().is_none() {
+    if dir == root || dir == cwd || dir.as_os_str().is_empty() || dir.parent().is_none() {
         return Ok(());
     }
 
@@ -502,10 +1436,12 @@ fn remove_empty_parent_dirs(dir: &std::path::Path) -> Result<()> {
             // Directory is empty, try to remove it
             match std::fs::remove_dir(dir) {
                 Ok(()) => {
-                    println!("🗑️  Removed empty directory: {}", dir.display());
+                    events.emit(Event::DirectoryRemoved {
+                        path: dir.display().to_string(),
+                    });
                     // Recursively try to remove parent directories
                     if let Some(parent) = dir.parent() {
-                        let _ = remove_empty_parent_dirs(parent);
+                        let _ = remove_empty_parent_dirs(root, parent, events);
                     }
                 }
                 Err(_) => {
@@ -521,6 +1457,323 @@ fn remove_empty_parent_dirs(dir: &std::path::Path) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::events::NullSink;
+
+    /// Serve every request with a fixed status and body
+    async fn serve(status: &'static str, body: &'static str) -> RegistryClient {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        RegistryClient::new(&crate::config::RegistryConfig {
+            name: "mock".to_string(),
+            url: format!("http://{addr}"),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    /// Serve JSON bodies by request path (without the query string), 404 otherwise
+    async fn serve_routes(routes: Vec<(&'static str, String)>) -> RegistryClient {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                let path = request.split_whitespace().nth(1).unwrap_or("");
+                let path = path.split('?').next().unwrap_or("");
+                let response = match routes.iter().find(|(route, _)| *route == path) {
+                    Some((_, body)) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    ),
+                    None => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        RegistryClient::new(&crate::config::RegistryConfig {
+            name: "mock".to_string(),
+            url: format!("http://{addr}"),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_group_dependencies_expand_to_selected_artifacts() {
+        let base = "/apis/registry/v3/groups/com.example.events/artifacts";
+        let versions = |list: &[&str]| {
+            let items: Vec<_> = list
+                .iter()
+                .map(|v| serde_json::json!({ "version": v }))
+                .collect();
+            serde_json::json!({ "count": items.len(), "versions": items }).to_string()
+        };
+        let client = serve_routes(vec![
+            (
+                "/apis/registry/v3/groups",
+                r#"{"count":2,"groups":[{"groupId":"com.example.events"},{"groupId":"org.other"}]}"#
+                    .to_string(),
+            ),
+            (
+                base,
+                r#"{"count":4,"artifacts":[{"artifactId":"order-created"},{"artifactId":"order-internal"},{"artifactId":"order-v2"},{"artifactId":"payment"}]}"#
+                    .to_string(),
+            ),
+            ("/apis/registry/v3/groups/com.example.events/artifacts/order-created/versions", versions(&["1.0.0", "1.2.0"])),
+            ("/apis/registry/v3/groups/com.example.events/artifacts/order-v2/versions", versions(&["2.0.0"])),
+        ])
+        .await;
+        let clients = HashMap::from([("mock".to_string(), client)]);
+        let mut repo_cfg = RepoConfig::default();
+        repo_cfg.dependency_defaults.registry = Some("mock".to_string());
+        repo_cfg.dependencies = vec![
+            DependencyConfig {
+                name: "users".to_string(),
+                version: "^1".to_string(),
+                ..Default::default()
+            },
+            DependencyConfig {
+                name: "events".to_string(),
+                version: "^1".to_string(),
+                group: Some("com.example.*".to_string()),
+                include: vec!["order-*".to_string()],
+                exclude: vec!["*-internal".to_string()],
+                ..Default::default()
+            },
+        ];
+
+        let expanded = expand_group_dependencies(&repo_cfg, &clients, None, &NullSink)
+            .await
+            .unwrap();
+        let names: Vec<&str> = expanded
+            .dependencies
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["users", "events/com.example.events/order-created"]
+        );
+        let member = &expanded.dependencies[1];
+        assert_eq!(member.group_id.as_deref(), Some("com.example.events"));
+        assert_eq!(member.member_of.as_deref(), Some("events"));
+
+        // Only the named dependency is expanded
+        let only_users = expand_group_dependencies(&repo_cfg, &clients, Some("users"), &NullSink)
+            .await
+            .unwrap();
+        assert_eq!(only_users.dependencies.len(), 1);
+
+        repo_cfg.dependencies[1].if_no_match = NoMatchPolicy::Error;
+        let err = expand_group_dependencies(&repo_cfg, &clients, None, &NullSink)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("com.example.events/order-v2"));
+    }
+
+    #[tokio::test]
+    async fn test_label_dependencies_expand_to_searched_artifacts() {
+        let versions = r#"{"count":1,"versions":[{"version":"1.0.0"}]}"#;
+        let client = serve_routes(vec![
+            (
+                "/apis/registry/v3/search/artifacts",
+                r#"{"count":3,"artifacts":[{"groupId":"payments","artifactId":"refund"},{"groupId":"payments","artifactId":"refund-internal"},{"groupId":null,"artifactId":"invoice"}]}"#
+                    .to_string(),
+            ),
+            ("/apis/registry/v3/groups/payments/artifacts/refund/versions", versions.to_string()),
+            ("/apis/registry/v3/groups/default/artifacts/invoice/versions", versions.to_string()),
+        ])
+        .await;
+        let clients = HashMap::from([("mock".to_string(), client)]);
+        let mut repo_cfg = RepoConfig::default();
+        repo_cfg.dependency_defaults.registry = Some("mock".to_string());
+        repo_cfg.dependencies = vec![DependencyConfig {
+            name: "payments".to_string(),
+            version: "^1".to_string(),
+            labels: [("domain".to_string(), "payments".to_string())].into(),
+            exclude: vec!["*-internal".to_string()],
+            ..Default::default()
+        }];
+
+        let expanded = expand_group_dependencies(&repo_cfg, &clients, None, &NullSink)
+            .await
+            .unwrap();
+        let names: Vec<&str> = expanded
+            .dependencies
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["payments/payments/refund", "payments/default/invoice"]
+        );
+        assert!(expanded
+            .dependencies
+            .iter()
+            .all(|d| d.member_of.as_deref() == Some("payments") && d.labels.is_empty()));
+
+        // `group` narrows the search and shortens member names
+        repo_cfg.dependencies[0].group = Some("payments".to_string());
+        let expanded = expand_group_dependencies(&repo_cfg, &clients, None, &NullSink)
+            .await
+            .unwrap();
+        assert_eq!(expanded.dependencies.len(), 1);
+        assert_eq!(expanded.dependencies[0].name, "payments/refund");
+    }
+
+    #[tokio::test]
+    async fn test_aliases_lock_the_same_artifact_twice() {
+        let base = "/apis/registry/v3/groups/com.example/artifacts/api/versions";
+        let client = serve_routes(vec![
+            (
+                base,
+                r#"{"count":2,"versions":[{"version":"1.4.0"},{"version":"2.1.0"}]}"#.to_string(),
+            ),
+            (
+                "/apis/registry/v3/groups/com.example/artifacts/api/versions/1.4.0/content",
+                r#"{"v":1}"#.to_string(),
+            ),
+            (
+                "/apis/registry/v3/groups/com.example/artifacts/api/versions/2.1.0/content",
+                r#"{"v":2}"#.to_string(),
+            ),
+        ])
+        .await;
+        let clients = HashMap::from([("mock".to_string(), client)]);
+        let alias = |name: &str, version: &str, output_path: &str| DependencyConfig {
+            name: name.to_string(),
+            group_id: Some("com.example".to_string()),
+            artifact_id: Some("api".to_string()),
+            version: version.to_string(),
+            registry: Some("mock".to_string()),
+            output_path: Some(output_path.to_string()),
+            ..Default::default()
+        };
+        let mut repo_cfg = RepoConfig {
+            dependencies: vec![
+                alias("api-v1", "^1", "v1/api.json"),
+                alias("api-v2", "^2", "v2/api.json"),
+            ],
+            ..Default::default()
+        };
+
+        let (locks, _) = resolve_with_pins(
+            &repo_cfg,
+            &clients,
+            None,
+            &BTreeMap::new(),
+            &RegistryMemo::default(),
+            &NullSink,
+        )
+        .await
+        .unwrap();
+        let entries: Vec<(&str, &str)> = locks
+            .iter()
+            .map(|d| (d.name.as_str(), d.resolved_version.as_str()))
+            .collect();
+        assert_eq!(entries, vec![("api-v1", "1.4.0"), ("api-v2", "2.1.0")]);
+        assert_ne!(locks[0].sha256, locks[1].sha256);
+
+        repo_cfg.dependencies[1].output_path = Some("v1/api.json".to_string());
+        let err = resolve_with_pins(
+            &repo_cfg,
+            &clients,
+            None,
+            &BTreeMap::new(),
+            &RegistryMemo::default(),
+            &NullSink,
+        )
+        .await
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'api-v1' and 'api-v2' both write v1/api.json"));
+    }
+
+    #[test]
+    fn test_version_conflicts_are_found_unless_settled() {
+        let mut requested = RequestedVersions::new();
+        requested.insert(
+            "com.example/common".to_string(),
+            vec![
+                ("orders".to_string(), "1.0.0".to_string()),
+                ("users".to_string(), "2.0.0".to_string()),
+            ],
+        );
+        requested.insert(
+            "com.example/money".to_string(),
+            vec![
+                ("orders".to_string(), "1.0.0".to_string()),
+                ("users".to_string(), "1.0.0".to_string()),
+            ],
+        );
+
+        let mut repo_cfg = RepoConfig::default();
+        let conflicts = find_version_conflicts(&repo_cfg, &requested, &BTreeMap::new());
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].highest, semver::Version::new(2, 0, 0));
+        assert_eq!(
+            conflicts[0].to_string(),
+            "com.example/common is referenced as 1.0.0 (from orders), 2.0.0 (from users)"
+        );
+
+        // Pinned at the highest version (the `highest` policy)
+        let mut pins = BTreeMap::new();
+        pins.insert("com.example/common".to_string(), "2.0.0".to_string());
+        assert!(find_version_conflicts(&repo_cfg, &requested, &pins).is_empty());
+
+        // Explicit override
+        repo_cfg
+            .overrides
+            .insert("com.example/common".to_string(), "1.0.0".to_string());
+        assert!(find_version_conflicts(&repo_cfg, &requested, &BTreeMap::new()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_download_falls_back_to_next_registry() {
+        let mut clients = HashMap::new();
+        clients.insert("primary".to_string(), serve("404 Not Found", "").await);
+        clients.insert("shared".to_string(), serve("200 OK", "syntax").await);
+        clients.insert(
+            "broken".to_string(),
+            serve("500 Internal Server Error", "").await,
+        );
+        let version = semver::Version::new(1, 0, 0);
+
+        let (registry, _, data) =
+            download_from_first(&clients, &["primary", "shared"], "g", "a", &version, None)
+                .await
+                .unwrap();
+        assert_eq!(registry, "shared");
+        assert_eq!(&data[..], b"syntax");
+
+        // Only "not found" moves on to the next registry
+        assert!(
+            download_from_first(&clients, &["broken", "shared"], "g", "a", &version, None)
+                .await
+                .is_err()
+        );
+        assert!(
+            download_from_first(&clients, &["primary"], "g", "a", &version, None)
+                .await
+                .is_err()
+        );
+    }
     use std::collections::HashMap;
     use tokio;
 
@@ -534,18 +1787,25 @@ mod tests {
             registry: "missing_registry".to_string(),
             resolved_version: "1.0.0".to_string(),
             download_url: "https://example.com/test".to_string(),
+            registry_url: None,
             sha256: "test_hash".to_string(),
             output_path: "./protos".to_string(),
             group_id: "com.example".to_string(),
             artifact_id: "test".to_string(),
             version_spec: "^1.0".to_string(),
             is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
         });
 
         let clients = HashMap::new(); // Empty clients map
 
         let rt = tokio::runtime::Runtime::new().unwrap();
-        let result = rt.block_on(verify_lock_is_still_valid(&lock, &clients));
+        let result = rt.block_on(verify_lock_is_still_valid(&lock, &clients, &NullSink));
 
         assert!(result.is_ok());
         assert!(
@@ -577,12 +1837,19 @@ mod tests {
             registry: "local".to_string(),
             resolved_version: "1.0.0".to_string(),
             download_url: "http://localhost/test".to_string(),
+            registry_url: None,
             sha256: "test_hash".to_string(),
             output_path: old_path.to_string_lossy().to_string(),
             group_id: "com.example".to_string(),
             artifact_id: "test".to_string(),
             version_spec: "^1.0".to_string(),
             is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
         }];
 
         let new_deps = vec![LockedDependency {
@@ -590,19 +1857,26 @@ mod tests {
             registry: "local".to_string(),
             resolved_version: "1.0.0".to_string(),
             download_url: "http://localhost/test".to_string(),
+            registry_url: None,
             sha256: "test_hash".to_string(),
             output_path: new_path.to_string_lossy().to_string(),
             group_id: "com.example".to_string(),
             artifact_id: "test".to_string(),
             version_spec: "^1.0".to_string(),
             is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
         }];
 
         // Verify old file exists before cleanup
         assert!(old_path.exists());
 
         // Run cleanup
-        cleanup_changed_output_paths(&old_deps, &new_deps).unwrap();
+        remove_stale_outputs(temp_path, &stale_outputs(&old_deps, &new_deps), &NullSink);
 
         // Verify old file was removed
         assert!(!old_path.exists());
@@ -633,12 +1907,19 @@ mod tests {
             registry: "local".to_string(),
             resolved_version: "1.0.0".to_string(),
             download_url: "http://localhost/test".to_string(),
+            registry_url: None,
             sha256: "test_hash".to_string(),
             output_path: old_path.to_string_lossy().to_string(),
             group_id: "com.example".to_string(),
             artifact_id: "test".to_string(),
             version_spec: "^1.0".to_string(),
             is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
         }];
 
         let new_deps = vec![]; // Empty - dependency removed
@@ -647,13 +1928,27 @@ mod tests {
         assert!(old_path.exists());
 
         // Run cleanup
-        cleanup_changed_output_paths(&old_deps, &new_deps).unwrap();
+        let events = crate::events::CollectingSink::default();
+        remove_stale_outputs(temp_path, &stale_outputs(&old_deps, &new_deps), &events);
 
         // Verify old file was removed
         assert!(!old_path.exists());
 
         // Verify old directory was also removed since it's empty
         assert!(!old_path.parent().unwrap().exists());
+
+        assert_eq!(
+            events.events(),
+            vec![
+                Event::OutputRemoved {
+                    path: old_path.to_string_lossy().to_string(),
+                    removed_dependency: Some("removed_dep".to_string()),
+                },
+                Event::DirectoryRemoved {
+                    path: old_path.parent().unwrap().display().to_string(),
+                },
+            ]
+        );
     }
 
     #[test]
@@ -678,21 +1973,188 @@ mod tests {
             registry: "local".to_string(),
             resolved_version: "1.0.0".to_string(),
             download_url: "http://localhost/test".to_string(),
+            registry_url: None,
             sha256: "test_hash".to_string(),
             output_path: file_path.to_string_lossy().to_string(),
             group_id: "com.example".to_string(),
             artifact_id: "test".to_string(),
             version_spec: "^1.0".to_string(),
             is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
         }];
 
         // Verify file exists before cleanup
         assert!(file_path.exists());
 
         // Run cleanup with same old and new deps
-        cleanup_changed_output_paths(&deps, &deps).unwrap();
+        remove_stale_outputs(temp_path, &stale_outputs(&deps, &deps), &NullSink);
 
         // Verify file still exists (unchanged)
         assert!(file_path.exists());
     }
+
+    #[tokio::test]
+    async fn test_unchanged_versions_reuse_previous_hashes() {
+        let server = crate::dev_server::DevServer::start("127.0.0.1:0")
+            .await
+            .unwrap();
+        server.add_version("com.example", "users", "PROTOBUF", "1.0.0", "users v1");
+        let clients = HashMap::from([(
+            "dev".to_string(),
+            RegistryClient::new(&server.registry_config("dev")).unwrap(),
+        )]);
+        let repo_cfg: RepoConfig = serde_yaml::from_str(
+            "dependencies:\n  - name: users\n    groupId: com.example\n    artifactId: users\n    \
+             version: ^1.0.0\n    registry: dev\n    outputPath: protos/users.proto\n",
+        )
+        .unwrap();
+        let resolve = |previous: Vec<LockedDependency>| {
+            let (repo_cfg, clients) = (&repo_cfg, &clients);
+            async move {
+                resolve_dependencies(repo_cfg, clients, None, &previous, &NullSink)
+                    .await
+                    .unwrap()
+                    .remove(0)
+            }
+        };
+        let fresh = resolve(Vec::new()).await;
+        assert_eq!(fresh.sha256, hex::encode(Sha256::digest(b"users v1")));
+
+        // The same version at the same path is not downloaded again
+        let mut previous = fresh.clone();
+        previous.sha256 = "known".to_string();
+        assert_eq!(resolve(vec![previous.clone()]).await.sha256, "known");
+
+        // A new version or another output path is
+        server.add_version("com.example", "users", "PROTOBUF", "1.1.0", "users v1.1");
+        let newer = resolve(vec![previous]).await;
+        assert_eq!(newer.resolved_version, "1.1.0");
+        assert_eq!(newer.sha256, hex::encode(Sha256::digest(b"users v1.1")));
+        let mut moved = newer.clone();
+        moved.sha256 = "known".to_string();
+        moved.output_path = "protos/old.proto".to_string();
+        assert_eq!(resolve(vec![moved]).await.sha256, newer.sha256);
+    }
+
+    #[tokio::test]
+    async fn test_locked_and_frozen_refuse_to_change_the_lock() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let lock_path = temp.path().join(crate::constants::APICURIO_LOCK);
+        let locked = LockOptions {
+            locked: true,
+            ..Default::default()
+        };
+        let frozen = LockOptions {
+            frozen: true,
+            ..Default::default()
+        };
+        let clients = HashMap::from([(
+            "mock".to_string(),
+            serve_routes(vec![(
+                "/apis/registry/v3/groups/com.example/artifacts/users/versions",
+                r#"{"count":1,"versions":[{"version":"1.1.0"}]}"#.to_string(),
+            )])
+            .await,
+        )]);
+        let check = |hash: &'static str, opts: LockOptions| {
+            let (lock_path, clients) = (&lock_path, &clients);
+            async move { check_lock_unchanged(lock_path, hash, &[], clients, opts, &NullSink).await }
+        };
+
+        for (opts, flag) in [(locked, "--locked"), (frozen, "--frozen")] {
+            let err = check("hash", opts).await.unwrap_err();
+            assert!(
+                err.to_string().ends_with(&format!(
+                    "is missing and {flag} was passed to prevent creating it"
+                )),
+                "{err}"
+            );
+        }
+
+        // 1.0.0 is locked but the registry only has 1.1.0 now
+        let mut lock = LockFile::new(
+            vec![LockedDependency {
+                name: "users".to_string(),
+                registry: "mock".to_string(),
+                resolved_version: "1.0.0".to_string(),
+                download_url: String::new(),
+                registry_url: None,
+                sha256: String::new(),
+                output_path: "protos/users.proto".to_string(),
+                group_id: "com.example".to_string(),
+                artifact_id: "users".to_string(),
+                version_spec: "^1.0".to_string(),
+                is_transitive: false,
+                references: Vec::new(),
+                reference_names: Default::default(),
+                binary: false,
+                avro_fingerprint: None,
+                content_type: None,
+                member_of: None,
+            }],
+            "hash".to_string(),
+        );
+        // Old enough for the registries to be asked
+        lock.generated_at = "1000000000000000000".to_string();
+        lock.save(&lock_path).unwrap();
+
+        for (opts, flag) in [(locked, "--locked"), (frozen, "--frozen")] {
+            let err = check("other", opts).await.unwrap_err();
+            assert!(
+                err.to_string().contains(&format!(
+                    "needs to be updated (config has changed) but {flag} was passed"
+                )),
+                "{err}"
+            );
+        }
+
+        let err = check("hash", locked).await.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("(some locked versions are unavailable) but --locked was passed"),
+            "{err}"
+        );
+        let lock = check("hash", frozen).await.unwrap();
+        assert_eq!(lock.locked_dependencies[0].resolved_version, "1.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_changed_overrides_fail_locked() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let lock_path = temp.path().join(crate::constants::APICURIO_LOCK);
+        let config = |version: &str| {
+            format!("dependencies: []\noverrides:\n  com.example/common: {version}\n")
+        };
+        let inputs = |content: &str| LockFile::config_hash_inputs(content, &[]);
+        let hash = |content: &str| LockFile::compute_config_hash(content, &[]);
+
+        let mut lock = LockFile::new(Vec::new(), hash(&config("1.0.0")));
+        lock.config_inputs = LockFile::config_input_checksums(&inputs(&config("1.0.0")));
+        lock.save(&lock_path).unwrap();
+        let locked = LockOptions {
+            locked: true,
+            ..Default::default()
+        };
+        let changed = config("1.1.0");
+        let err = check_lock_unchanged(
+            &lock_path,
+            &hash(&changed),
+            &inputs(&changed),
+            &HashMap::new(),
+            locked,
+            &NullSink,
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("override com.example/common changed"),
+            "{err}"
+        );
+    }
 }
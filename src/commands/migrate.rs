@@ -0,0 +1,35 @@
+use anyhow::{bail, Result};
+
+use crate::{migrate, paths};
+
+/// Upgrade the config (and its includes) and the lock file written by older
+/// releases in place, keeping a `.bak` copy of every rewritten file
+pub fn run(check: bool) -> Result<()> {
+    let migrations = migrate::plan(&paths::project_config(), &paths::project_lock())?;
+    if migrations.is_empty() {
+        println!("✅ Config and lock file already use the current format");
+        return Ok(());
+    }
+    for migration in &migrations {
+        if check {
+            println!("{} needs upgrading:", migration.path.display());
+        } else {
+            let backup = migration.apply()?;
+            println!(
+                "🔄 Upgraded {} (previous version kept as {}):",
+                migration.path.display(),
+                backup.display()
+            );
+        }
+        for change in &migration.changes {
+            println!("   {change}");
+        }
+    }
+    if check {
+        bail!(
+            "{} file(s) use an older format; run `apicurio migrate` to upgrade them",
+            migrations.len()
+        );
+    }
+    Ok(())
+}
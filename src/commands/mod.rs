@@ -11,38 +11,90 @@
 //! - `pull` - Fetch dependencies
 //! - `update` - Update dependencies to latest matching versions
 //! - `lock` - Update lock file without downloading
+//! - `upgrade-pr` - Update dependencies on a new branch and open a pull request
 //!
 //! ### Dependency Lifecycle
 //! - `add` - Add new dependencies
 //! - `remove` - Remove existing dependencies
 //! - `list` - List configured dependencies
 //! - `status` - Check for outdated dependencies
+//! - `versions` - List the versions available for an artifact
+//! - `show` - Inspect an artifact version's metadata and content
+//! - `graph` - Export the dependency graph (DOT, Mermaid, JSON)
+//! - `changelog` - Summarize schema changes between versions of an artifact
+//! - `fingerprint` - Print the canonical-form fingerprints of an Avro schema
+//! - `ids` - Map locked versions to the globalId/contentId used by serializers
+//! - `report` - Tabulate license/owner/contact labels of the locked dependencies
+//! - `docs` - Generate a markdown or HTML page describing every locked dependency
 //!
 //! ### Registry Operations
 //! - `registry` - Manage registry configurations
+//! - `config` - Read and edit `apicurioconfig.yaml` from scripts
 //! - `publish` - Publish artifacts to registries
+//! - `publish-file` - Publish a single file (or stdin) without a `publishes` entry
+//! - `finalize` - Promote a draft version to enabled
+//! - `delete` - Delete a version, an artifact or a whole group from a registry
+//! - `test` - Check publishes against the registry rules without publishing
+//! - `labels` - Add, remove or list labels on many registry artifacts at once
+//! - `rules` - View and configure validity/compatibility rules
+//! - `admin` - Read and change registry configuration properties and global rules
+//! - `lint` - Check schema files with the built-in lint rules
+//! - `plugin` - List external plugins and run their generators
 //!
 //! ### Validation & Utilities
 //! - `verify` - Verify integrity of downloaded files
+//! - `attest` - Export an in-toto provenance statement and checksums of the pulled files
 //! - `doctor` - Validate configuration and connectivity
+//! - `whoami` - Show the principal, roles and permissions each registry sees
 //! - `completions` - Generate shell completion scripts
+//! - `ci-setup` - Emit CI pipelines running the `pull`, `verify` and `status` gates
+//! - `env` - Print the effective config, cache and state locations
+//! - `dev-server` - Serve an in-memory registry for tests and demos
 
 use anyhow::Result;
 use clap::Subcommand;
 
 pub mod add;
+pub mod admin;
+pub mod adopt;
+pub mod attest;
+pub mod changelog;
+pub mod ci_setup;
 pub mod completions;
+pub mod config;
+pub mod delete;
+pub mod dev_server;
+pub mod docs;
 pub mod doctor;
+pub mod duplicates;
+pub mod env;
+pub mod finalize;
+pub mod fingerprint;
+pub mod graph;
+pub mod ids;
 pub mod init;
+pub mod labels;
+pub mod lint;
 pub mod list;
 pub mod lock;
+pub mod migrate;
+pub mod plugin;
 pub mod publish;
+pub mod publish_file;
 pub mod pull;
 pub mod registry;
 pub mod remove;
+pub mod rename;
+pub mod report;
+pub mod rules;
+pub mod show;
 pub mod status;
+pub mod test;
 pub mod update;
+pub mod upgrade_pr;
 pub mod verify;
+pub mod versions;
+pub mod whoami;
 
 /// All available CLI commands
 ///
@@ -58,11 +110,93 @@ pub enum Commands {
     #[command(
         about = "Fetch exactly what's in the lock; if no lock, resolve specs ⇒ download ⇒ lock"
     )]
-    Pull,
+    Pull {
+        #[arg(
+            help = "Only pull this dependency (and what it references), format registry/group_id/artifact_id or dependency name"
+        )]
+        identifier: Option<String>,
+        #[arg(long, help = "Fail if the lockfile is missing or out of date")]
+        locked: bool,
+        #[arg(
+            long,
+            help = "Like --locked, but also skip checking registries for locked versions"
+        )]
+        frozen: bool,
+        #[arg(
+            long,
+            help = "Fail unless the pulled files are committed to git (not ignored, untracked or modified)"
+        )]
+        ensure_committed: bool,
+        #[arg(
+            long,
+            help = "Expand ${VAR} placeholders of unset variables to an empty string instead of failing"
+        )]
+        allow_unset_env: bool,
+        #[arg(
+            long,
+            help = "Write downloads that do not match the locked sha256 instead of failing (unsafe)"
+        )]
+        no_verify: bool,
+        #[arg(
+            long,
+            help = "Download from the configured URL of a registry that moved since locking, rewriting the lock's URLs"
+        )]
+        reconcile_registry_url: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Format of the summary")]
+        format: OutputFormat,
+    },
     #[command(
         about = "Re-resolve semver ranges in config to latest matches; download ⇒ overwrite lock"
     )]
-    Update,
+    Update {
+        #[arg(
+            help = "Only re-resolve this dependency (and what it references), keeping other lock entries"
+        )]
+        identifier: Option<String>,
+        #[arg(
+            short,
+            long,
+            conflicts_with = "identifier",
+            help = "Pick the outdated dependencies to update after previewing their schema changes"
+        )]
+        interactive: bool,
+        #[arg(
+            long,
+            conflicts_with = "interactive",
+            help = "Print the version changes without writing files or the lock"
+        )]
+        dry_run: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Format of the version changes")]
+        format: OutputFormat,
+        #[arg(
+            long,
+            conflicts_with = "interactive",
+            help = "Post the version changes to the configured notifications"
+        )]
+        notify: bool,
+    },
+    #[command(
+        about = "Update dependencies, commit the changes on a new branch and optionally open a pull request"
+    )]
+    UpgradePr {
+        #[arg(long, help = "Branch to commit to (default: apicurio/upgrade-<date>)")]
+        branch: Option<String>,
+        #[arg(
+            long,
+            value_enum,
+            value_name = "FORGE",
+            help = "Push the branch and open a pull request with gh (github) or glab (gitlab)"
+        )]
+        open: Option<ci_setup::CiProvider>,
+        #[arg(long, requires = "open", help = "Branch the pull request targets")]
+        base: Option<String>,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Also write the markdown summary to FILE"
+        )]
+        summary: Option<String>,
+    },
     #[command(
         about = "Add a new dependency entry to the config using format registry/group_id/artifact_id@version"
     )]
@@ -81,23 +215,101 @@ pub enum Commands {
         )]
         identifier: String,
     },
+    #[command(
+        about = "Turn a hand-copied schema file into a dependency, finding its artifact in the registries"
+    )]
+    Adopt {
+        #[arg(help = "Existing schema file, which becomes the output path of the dependency")]
+        path: String,
+        /// Only search this registry
+        #[arg(long)]
+        registry: Option<String>,
+    },
+    #[command(
+        about = "Move a dependency to new coordinates, relocking and moving its output file"
+    )]
+    Rename {
+        #[arg(help = "Dependency to rename (name or partial identifier)")]
+        old: String,
+        #[arg(
+            help = "New coordinates: [registry/]group_id/artifact_id[@version], or artifact_id to keep the group"
+        )]
+        new: String,
+    },
     #[command(
         about = "Print all configured deps (spec'd & locked versions), and registries (no network)"
     )]
     List,
     #[command(about = "Compare lock vs. latest matching version in registry; flag outdated deps")]
-    Status,
+    Status {
+        #[arg(long, help = "Also check transitive (referenced) lock entries")]
+        transitive: bool,
+        #[arg(
+            long,
+            help = "Flag locked versions that were deleted, disabled or changed in the registry"
+        )]
+        drift: bool,
+        #[arg(
+            long,
+            help = "Compare pulled files with the registry content of their locked version"
+        )]
+        check_content: bool,
+        #[arg(
+            long,
+            value_enum,
+            help = "Least severe update that fails (overrides staleness.failOn; default patch)"
+        )]
+        fail_on_severity: Option<crate::config::UpdateSeverity>,
+        #[arg(long, help = "Post a summary to the configured notifications")]
+        notify: bool,
+    },
     #[command(about = "Re-hash downloaded files & confirm against lockfile hashes")]
-    Verify,
+    Verify {
+        #[arg(
+            long,
+            help = "Strip provenance headers written by pull before comparing hashes"
+        )]
+        ignore_header: bool,
+    },
+    #[command(about = "Write an in-toto/SLSA provenance statement covering every pulled file")]
+    Attest {
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write the statement to FILE instead of stdout"
+        )]
+        out: Option<String>,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Also write sha256sum-compatible checksums of the pulled files"
+        )]
+        checksums: Option<String>,
+    },
     #[command(about = "Subcommand: manage global registries file (add/list/remove)")]
     Registry {
         #[command(subcommand)]
         cmd: registry::RegistryCommands,
     },
+    #[command(
+        about = "Subcommand: get/set/unset keys of apicurioconfig.yaml or show the resolved config"
+    )]
+    Config {
+        #[command(subcommand)]
+        cmd: config::ConfigCommands,
+    },
     #[command(
         about = "Validate config + lock semantics (semver syntax, missing fields, unreachable URLs)"
     )]
-    Doctor,
+    Doctor {
+        #[arg(
+            long,
+            help = "Repair obvious problems (stale lock, orphaned lock entries, missing output paths, duplicate registries)"
+        )]
+        fix: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
     #[command(about = "Emit shell completion scripts (bash/zsh/fish)")]
     Completions { shell: String },
     #[command(about = "Publish to registries")]
@@ -106,9 +318,329 @@ pub enum Commands {
             help = "Specific publish name to publish (if not provided, publishes all configured artifacts)"
         )]
         name: Option<String>,
+        #[arg(long, help = "Lint the files first and abort on lint errors")]
+        lint: bool,
+        #[arg(
+            long,
+            help = "Create the versions as drafts, to be promoted with `finalize`"
+        )]
+        draft: bool,
+        #[arg(
+            long,
+            help = "Write versions computed by auto-patch/auto-minor/auto-major/git-describe or read from fromFile/fromCargo/fromMaven/fromGitTag back to the config"
+        )]
+        write_version: bool,
+        #[arg(
+            long,
+            help = "Create a git tag <artifactId>-v<version> for each published version"
+        )]
+        tag: bool,
+    },
+    #[command(
+        about = "Publish a single file (or stdin with `-`) to a registry without a publishes entry"
+    )]
+    PublishFile(publish_file::PublishFileArgs),
+    #[command(about = "Check publishes against the registry rules (dry run, nothing is created)")]
+    Test {
+        #[arg(
+            help = "Specific publish name to test (if not provided, tests all configured artifacts)"
+        )]
+        name: Option<String>,
+    },
+    #[command(about = "Promote a draft version to enabled after review")]
+    Finalize {
+        #[arg(
+            help = "Publish name (optionally @version) or registry/group_id/artifact_id@version"
+        )]
+        identifier: String,
+    },
+    #[command(about = "Delete an artifact version, an artifact or (with --recursive) a group")]
+    Delete {
+        #[arg(
+            help = "registry/group_id/artifact_id[@version], a dependency name, or [registry/]group_id with --recursive"
+        )]
+        identifier: String,
+        #[arg(
+            long,
+            help = "Delete the group named by the identifier with all its artifacts"
+        )]
+        recursive: bool,
+        #[arg(short, long, help = "Do not ask for confirmation")]
+        yes: bool,
+    },
+    #[command(
+        about = "Subcommand: add/remove/list labels on the artifacts selected by group/glob"
+    )]
+    Labels {
+        #[command(subcommand)]
+        cmd: labels::LabelsCommands,
+    },
+    #[command(
+        about = "Subcommand: get/set validity, compatibility and integrity rules of an artifact or the registry"
+    )]
+    Rules {
+        #[command(subcommand)]
+        cmd: rules::RulesCommands,
+    },
+    #[command(about = "Check schema files with built-in lint rules (Protobuf, Avro, OpenAPI)")]
+    Lint {
+        #[arg(help = "Files to lint (defaults to the input files of every configured publish)")]
+        paths: Vec<String>,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    #[command(
+        about = "Subcommand: list apicurio-plugin-* executables or run a plugin's generator"
+    )]
+    Plugin {
+        #[command(subcommand)]
+        cmd: plugin::PluginCommands,
     },
     #[command(about = "Update the lockfile based on current dependencies")]
-    Lock,
+    Lock {
+        #[arg(long, help = "Fail if the lockfile is missing or out of date")]
+        locked: bool,
+        #[arg(
+            long,
+            help = "Like --locked, but also skip checking registries for locked versions"
+        )]
+        frozen: bool,
+        #[arg(
+            long,
+            help = "Expand ${VAR} placeholders of unset variables to an empty string instead of failing"
+        )]
+        allow_unset_env: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["locked", "frozen"],
+            help = "Rewrite the lock file even when nothing changed (refreshes generatedAt)"
+        )]
+        force: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["locked", "frozen", "force"],
+            help = "Print why the lock file is up to date or stale, check by check, without changing it"
+        )]
+        explain: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Format of the summary")]
+        format: OutputFormat,
+    },
+    #[command(
+        about = "List all versions of an artifact with state, creation date and range matches"
+    )]
+    Versions {
+        #[arg(
+            help = "Configured dependency name or registry/group_id/artifact_id (registry optional)"
+        )]
+        identifier: String,
+    },
+    #[command(about = "Print an artifact version's metadata, labels, references and content type")]
+    Show {
+        #[arg(
+            help = "Configured dependency name or registry/group_id/artifact_id, optionally @version (defaults to the locked or latest version)"
+        )]
+        identifier: String,
+        #[arg(long, help = "Also print the artifact content")]
+        content: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+        #[arg(
+            long = "field",
+            value_name = "FIELD",
+            help = "Only print the given field (repeatable, e.g. --field labels --field version)"
+        )]
+        fields: Vec<String>,
+    },
+    #[command(
+        about = "Find identical or near-identical schemas locked or registered under different coordinates"
+    )]
+    Duplicates {
+        /// Also compare the latest version of every artifact of this registry (repeatable)
+        #[arg(long = "registry")]
+        registries: Vec<String>,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    #[command(about = "Export the direct and transitive dependency graph (DOT, Mermaid or JSON)")]
+    Graph {
+        #[arg(long, value_enum, default_value_t = graph::GraphFormat::Dot, help = "Output format")]
+        format: graph::GraphFormat,
+        #[arg(
+            long,
+            help = "Resolve references from the registries instead of reading the lock file"
+        )]
+        live: bool,
+    },
+    #[command(
+        about = "Summarize added, removed and changed elements between versions of an artifact"
+    )]
+    Changelog {
+        #[arg(
+            help = "Configured dependency name or registry/group_id/artifact_id (registry optional)"
+        )]
+        identifier: String,
+        #[arg(long, help = "Oldest version to compare from (e.g. 1.0.0)")]
+        since: Option<String>,
+        #[arg(long, help = "Newest version to include")]
+        until: Option<String>,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    #[command(
+        about = "Print the Avro canonical form fingerprints (CRC-64-AVRO, SHA-256) of a schema"
+    )]
+    Fingerprint {
+        #[arg(
+            help = "Local file, configured dependency name or registry/group_id/artifact_id, optionally @version"
+        )]
+        identifier: String,
+        #[arg(long, help = "Also print the Parsing Canonical Form")]
+        canonical: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    #[command(
+        about = "Print the globalId/contentId of locked versions, as used by Kafka serializers"
+    )]
+    Ids {
+        #[arg(help = "Dependency name (all locked dependencies when omitted)")]
+        identifier: Option<String>,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write the IDs to a .properties or .json file instead of printing them"
+        )]
+        out: Option<String>,
+    },
+    #[command(
+        about = "Generate a page describing every locked dependency: coordinates, version, description, labels, output path and references"
+    )]
+    Docs {
+        #[arg(long, value_enum, default_value_t = docs::DocsFormat::Markdown, help = "Output format")]
+        format: docs::DocsFormat,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write the page to FILE instead of stdout, e.g. docs/schemas.md"
+        )]
+        out: Option<String>,
+    },
+    #[command(
+        about = "Report labels such as license, owner and contact of every locked dependency"
+    )]
+    Report {
+        #[arg(
+            long = "label",
+            value_name = "KEY",
+            help = "Label to report (repeatable; default: license, owner, contact)"
+        )]
+        labels: Vec<String>,
+        #[arg(long, value_enum, default_value_t = report::ReportFormat::Markdown, help = "Output format")]
+        format: report::ReportFormat,
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = "Write the report to FILE instead of stdout"
+        )]
+        out: Option<String>,
+    },
+    #[command(about = "Emit a CI pipeline enforcing the pull --frozen, verify and status gates")]
+    CiSetup {
+        #[arg(value_enum, help = "CI system to generate the pipeline for")]
+        provider: ci_setup::CiProvider,
+        #[arg(
+            long,
+            help = "Write the pipeline into the repository instead of printing it"
+        )]
+        write: bool,
+        #[arg(long, requires = "write", help = "Overwrite an existing pipeline file")]
+        force: bool,
+        #[arg(
+            long,
+            help = "Leave out the `status` gate (newer matching versions do not fail the build)"
+        )]
+        no_status: bool,
+    },
+    #[command(about = "Print the effective config, cache and state directories and project files")]
+    Env {
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    #[command(
+        about = "Upgrade a config and lock file written by an older release, keeping .bak copies"
+    )]
+    Migrate {
+        #[arg(
+            long,
+            help = "Only report the files needing an upgrade; fail if there are any"
+        )]
+        check: bool,
+    },
+    #[command(about = "Show the authenticated principal, roles and permissions for each registry")]
+    Whoami {
+        #[arg(long, help = "Only ask this registry")]
+        registry: Option<String>,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text, help = "Output format")]
+        format: OutputFormat,
+    },
+    #[command(
+        about = "Subcommand: read and change registry configuration properties and global rules (operators)"
+    )]
+    Admin {
+        #[arg(long, global = true, help = "Registry to administer")]
+        registry: Option<String>,
+        #[arg(
+            long,
+            global = true,
+            help = "Apply registry-wide changes without asking for confirmation"
+        )]
+        i_am_an_admin: bool,
+        #[command(subcommand)]
+        cmd: admin::AdminCommands,
+    },
+    #[command(about = "Serve an in-memory Apicurio-compatible registry for tests and demos")]
+    DevServer {
+        #[arg(long, default_value = "127.0.0.1", help = "Address to listen on")]
+        host: String,
+        #[arg(
+            long,
+            default_value_t = 8080,
+            help = "Port to listen on (0 picks a free one)"
+        )]
+        port: u16,
+    },
+}
+
+/// Output format for commands that can emit machine-readable results
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// Pretty-printed JSON
+    Json,
+}
+
+impl Commands {
+    /// Whether the command writes the lock file, output files or the config,
+    /// and so takes the project's process lock
+    fn writes_project(&self) -> bool {
+        matches!(
+            self,
+            Commands::Pull { .. }
+                | Commands::Update { .. }
+                | Commands::UpgradePr { .. }
+                | Commands::Add { .. }
+                | Commands::Remove { .. }
+                | Commands::Rename { .. }
+                | Commands::Adopt { .. }
+                | Commands::Lock { .. }
+                | Commands::Publish { .. }
+                | Commands::Migrate { .. }
+        )
+    }
 }
 
 /// Command dispatcher that routes to the appropriate command implementation
@@ -122,19 +654,206 @@ pub enum Commands {
 /// # Returns
 /// Result indicating success or failure of the command execution
 pub async fn run(cmd: Commands) -> Result<()> {
+    // Held until the command returns, so concurrent runs do not interleave writes
+    let config = crate::paths::project_config();
+    let _lock = if cmd.writes_project() && config.exists() {
+        let dir = config.parent().filter(|d| !d.as_os_str().is_empty());
+        Some(crate::process_lock::acquire(
+            dir.unwrap_or(std::path::Path::new(".")),
+            crate::process_lock::timeout(),
+            |holder| eprintln!("⏳ Waiting for another apicurio process ({holder}) to finish..."),
+        )?)
+    } else {
+        None
+    };
     match cmd {
-        Commands::Pull => pull::run().await,
-        Commands::Update => update::run().await,
+        Commands::Pull {
+            identifier,
+            locked,
+            frozen,
+            ensure_committed,
+            allow_unset_env,
+            no_verify,
+            reconcile_registry_url,
+            format,
+        } => {
+            pull::run(
+                identifier,
+                pull::PullOptions {
+                    lock: lock::LockOptions {
+                        locked,
+                        frozen,
+                        allow_unset_env,
+                        force: false,
+                        update: false,
+                    },
+                    no_verify,
+                    reconcile_registry_url,
+                },
+                ensure_committed,
+                format,
+            )
+            .await
+        }
+        Commands::Update {
+            identifier,
+            interactive,
+            dry_run,
+            format,
+            notify,
+        } => {
+            let opts = update::UpdateOptions {
+                interactive,
+                dry_run,
+                format,
+                notify,
+            };
+            update::run(identifier, opts).await
+        }
+        Commands::UpgradePr {
+            branch,
+            open,
+            base,
+            summary,
+        } => {
+            let opts = upgrade_pr::UpgradePrOptions {
+                branch,
+                base,
+                open,
+                summary,
+            };
+            upgrade_pr::run(opts).await
+        }
         Commands::Init => init::run().await,
         Commands::Add { identifier, latest } => add::run(identifier, latest).await,
         Commands::Remove { identifier } => remove::run(identifier).await,
+        Commands::Rename { old, new } => rename::run(old, new).await,
+        Commands::Adopt { path, registry } => adopt::run(path, registry).await,
         Commands::List => list::run().await,
-        Commands::Status => status::run().await,
-        Commands::Verify => verify::run().await,
+        Commands::Status {
+            transitive,
+            drift,
+            check_content,
+            fail_on_severity,
+            notify,
+        } => {
+            status::run(
+                status::StatusOptions {
+                    transitive,
+                    drift,
+                    content: check_content,
+                    fail_on: fail_on_severity,
+                },
+                notify,
+            )
+            .await
+        }
+        Commands::Verify { ignore_header } => verify::run(ignore_header).await,
+        Commands::Attest { out, checksums } => attest::run(out, checksums),
         Commands::Registry { cmd } => registry::run(cmd).await,
-        Commands::Doctor => doctor::run().await,
+        Commands::Config { cmd } => config::run(cmd).await,
+        Commands::Doctor { fix, format } => doctor::run(fix, format).await,
         Commands::Completions { shell } => completions::run(shell),
-        Commands::Publish { name } => publish::run(name).await,
-        Commands::Lock => lock::run().await,
+        Commands::Publish {
+            name,
+            lint,
+            draft,
+            write_version,
+            tag,
+        } => {
+            publish::run(
+                name,
+                lint,
+                publish::PublishOptions {
+                    draft,
+                    write_version,
+                    tag,
+                },
+            )
+            .await
+        }
+        Commands::PublishFile(args) => publish_file::run(args).await,
+        Commands::Test { name } => test::run(name).await,
+        Commands::Finalize { identifier } => finalize::run(identifier).await,
+        Commands::Delete {
+            identifier,
+            recursive,
+            yes,
+        } => delete::run(identifier, recursive, yes).await,
+        Commands::Labels { cmd } => labels::run(cmd).await,
+        Commands::Rules { cmd } => rules::run(cmd).await,
+        Commands::Lint { paths, format } => lint::run(paths, format).await,
+        Commands::Plugin { cmd } => plugin::run(cmd).await,
+        Commands::Lock {
+            explain: true,
+            format,
+            ..
+        } => lock::explain(format).await,
+        Commands::Lock {
+            locked,
+            frozen,
+            allow_unset_env,
+            force,
+            explain: false,
+            format,
+        } => {
+            lock::run(
+                lock::LockOptions {
+                    locked,
+                    frozen,
+                    allow_unset_env,
+                    force,
+                    update: false,
+                },
+                format,
+            )
+            .await
+        }
+        Commands::Versions { identifier } => versions::run(identifier).await,
+        Commands::Show {
+            identifier,
+            content,
+            format,
+            fields,
+        } => show::run(identifier, content, format, fields).await,
+        Commands::Duplicates { registries, format } => duplicates::run(registries, format).await,
+        Commands::Graph { format, live } => graph::run(format, live).await,
+        Commands::Changelog {
+            identifier,
+            since,
+            until,
+            format,
+        } => changelog::run(identifier, since, until, format).await,
+        Commands::Fingerprint {
+            identifier,
+            canonical,
+            format,
+        } => fingerprint::run(identifier, canonical, format).await,
+        Commands::Ids {
+            identifier,
+            format,
+            out,
+        } => ids::run(identifier, format, out).await,
+        Commands::Report {
+            labels,
+            format,
+            out,
+        } => report::run(labels, format, out).await,
+        Commands::Docs { format, out } => docs::run(format, out).await,
+        Commands::CiSetup {
+            provider,
+            write,
+            force,
+            no_status,
+        } => ci_setup::run(provider, write, force, !no_status),
+        Commands::Env { format } => env::run(format),
+        Commands::Migrate { check } => migrate::run(check),
+        Commands::Whoami { registry, format } => whoami::run(registry, format).await,
+        Commands::Admin {
+            registry,
+            i_am_an_admin,
+            cmd,
+        } => admin::run(cmd, registry, i_am_an_admin).await,
+        Commands::DevServer { host, port } => dev_server::run(host, port).await,
     }
 }
@@ -0,0 +1,89 @@
+use crate::{
+    constants::APICURIO_LOCK,
+    lockfile::LockFile,
+    paths,
+    plugins::{self, GenerateResponse},
+};
+use anyhow::{bail, Context, Result};
+use clap::Subcommand;
+use serde_json::json;
+use std::{
+    env, fs,
+    path::{Component, Path},
+};
+
+#[derive(Subcommand, Debug)]
+pub enum PluginCommands {
+    /// List the `apicurio-plugin-*` executables found on PATH
+    List,
+    /// Run a plugin's `generate` hook on the locked dependencies
+    Run { name: String },
+}
+
+pub async fn run(cmd: PluginCommands) -> Result<()> {
+    match cmd {
+        PluginCommands::List => list(),
+        PluginCommands::Run { name } => generate(&name),
+    }
+}
+
+fn list() -> Result<()> {
+    let found = plugins::discover();
+    if found.is_empty() {
+        println!(
+            "No plugins found (executables named {}* on PATH)",
+            plugins::PLUGIN_PREFIX
+        );
+        return Ok(());
+    }
+    for plugin in found {
+        match plugin.describe() {
+            Ok(info) => println!(
+                "{} [{}] {}\n  {}",
+                plugin.name,
+                info.hooks.join(", "),
+                info.description.unwrap_or_default(),
+                plugin.path.display()
+            ),
+            Err(e) => println!("{} ⚠️  {e:#}\n  {}", plugin.name, plugin.path.display()),
+        }
+    }
+    Ok(())
+}
+
+fn generate(name: &str) -> Result<()> {
+    let plugin = plugins::find(name)?;
+    if !plugin.supports("generate")? {
+        bail!("plugin '{name}' does not handle the 'generate' hook");
+    }
+    let lock = LockFile::load(&paths::project_lock())
+        .with_context(|| format!("reading {APICURIO_LOCK}; run `apicurio lock` first"))?;
+    let root = env::current_dir()?;
+    let response: GenerateResponse = plugin.call(
+        "generate",
+        json!({ "root": root, "dependencies": lock.locked_dependencies }),
+    )?;
+
+    for message in &response.messages {
+        println!("{message}");
+    }
+    for file in &response.files {
+        let path = Path::new(&file.path);
+        if path.is_absolute() || path.components().any(|c| c == Component::ParentDir) {
+            bail!(
+                "plugin '{name}' tried to write {} outside the project",
+                file.path
+            );
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &file.content).with_context(|| format!("writing {}", file.path))?;
+        println!("  📝 {}", file.path);
+    }
+    println!(
+        "✅ Plugin '{name}' generated {} file(s)",
+        response.files.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,152 @@
+use crate::{
+    config::{
+        load_global_config, load_repo_config, ArtifactType, IfExistsAction, PublishConfig,
+        RepoConfig,
+    },
+    events::{ConsoleSink, Event, EventSink},
+    naming, paths,
+    registry::RegistryClient,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Args;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+};
+
+/// Coordinates and metadata of an ad hoc publish
+#[derive(Args, Debug)]
+pub struct PublishFileArgs {
+    /// File to publish, or `-` to read the content from stdin
+    pub path: String,
+    /// Target registry name
+    #[arg(long)]
+    pub registry: String,
+    /// Group ID (defaults to the registry's `defaultGroup`, then `default`)
+    #[arg(long = "group")]
+    pub group_id: Option<String>,
+    /// Artifact ID
+    #[arg(long = "artifact")]
+    pub artifact_id: String,
+    /// Exact version to publish
+    #[arg(long)]
+    pub version: String,
+    /// Artifact type (protobuf, avro, json-schema, openapi, ...); required with `-`
+    #[arg(long = "type", value_parser = parse_artifact_type)]
+    pub artifact_type: Option<ArtifactType>,
+    /// Human-readable description
+    #[arg(long)]
+    pub description: Option<String>,
+    /// Label to set on a new artifact (repeatable)
+    #[arg(long = "label", value_name = "KEY=VALUE", value_parser = parse_label)]
+    pub labels: Vec<(String, String)>,
+    /// Create the version as a draft, to be promoted with `finalize`
+    #[arg(long)]
+    pub draft: bool,
+    /// Send the content base64-encoded, for binary files such as descriptor sets
+    #[arg(long)]
+    pub binary: bool,
+}
+
+fn parse_artifact_type(value: &str) -> Result<ArtifactType, String> {
+    serde_yaml::from_str(value).map_err(|_| format!("unknown artifact type '{value}'"))
+}
+
+fn parse_label(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .ok_or_else(|| format!("label '{value}' must be KEY=VALUE"))
+}
+
+/// Publish one file without a `publishes` entry
+///
+/// Registries come from `apicurioconfig.yaml` when present, otherwise from the
+/// global registries file only.
+pub async fn run(args: PublishFileArgs) -> Result<()> {
+    let config_path = paths::project_config();
+    let repo_cfg = if config_path.exists() {
+        load_repo_config(&config_path)?
+    } else {
+        RepoConfig::default()
+    };
+    let registry = repo_cfg
+        .merge_registries(load_global_config()?)?
+        .into_iter()
+        .find(|r| r.name == args.registry)
+        .ok_or_else(|| anyhow!("Registry '{}' not found", args.registry))?;
+
+    let content = if args.path == "-" {
+        if args.artifact_type.is_none() {
+            bail!("--type is required when reading the artifact from stdin");
+        }
+        let mut content = Vec::new();
+        io::stdin()
+            .read_to_end(&mut content)
+            .context("reading artifact from stdin")?;
+        content
+    } else {
+        fs::read(&args.path).with_context(|| format!("Failed to read file: {}", args.path))?
+    };
+    semver::Version::parse(&args.version)
+        .with_context(|| format!("'{}' is not an exact version", args.version))?;
+    let group_id = args
+        .group_id
+        .or_else(|| registry.default_group.clone())
+        .unwrap_or_else(|| "default".to_string());
+    let problems = naming::violations(&registry, &group_id, &args.artifact_id);
+    if !problems.is_empty() {
+        bail!(
+            "'{group_id}/{}' breaks the naming conventions of registry '{}':\n  - {}",
+            args.artifact_id,
+            registry.name,
+            problems.join("\n  - ")
+        );
+    }
+
+    let publish = PublishConfig {
+        name: format!("{group_id}/{}", args.artifact_id),
+        input_path: args.path,
+        version: args.version,
+        version_source: Default::default(),
+        registry: args.registry,
+        group_id: Some(group_id),
+        artifact_id: Some(args.artifact_id),
+        r#type: args.artifact_type,
+        if_exists: IfExistsAction::Fail,
+        description: args.description,
+        labels: args.labels.into_iter().collect::<HashMap<_, _>>(),
+        references: Vec::new(),
+        binary: args.binary,
+    };
+    let events = ConsoleSink;
+    events.emit(Event::PublishStarted {
+        name: publish.name.clone(),
+        version: publish.version.clone(),
+        registry: publish.registry.clone(),
+    });
+    RegistryClient::new(&registry)?
+        .publish_artifact(&publish, &content, args.draft, &events)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arguments() {
+        assert_eq!(
+            parse_artifact_type("json-schema"),
+            Ok(ArtifactType::JsonSchema)
+        );
+        assert!(parse_artifact_type("cobol").is_err());
+        assert_eq!(
+            parse_label("team=payments=core"),
+            Ok(("team".to_string(), "payments=core".to_string()))
+        );
+        assert!(parse_label("team").is_err());
+    }
+}
@@ -1,44 +1,485 @@
-use anyhow::Result;
-use std::{collections::HashMap, fs, path::PathBuf};
+use anyhow::{bail, Context, Result};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 use crate::{
-    config::{load_global_config, load_repo_config},
-    constants::{APICURIO_CONFIG, APICURIO_LOCK},
-    lockfile::LockFile,
+    api::{LockOutcome, PullSummary, Workspace},
+    artifact_writer::{ArtifactWriter, Change},
+    commands::OutputFormat,
+    config::RepoConfig,
+    error::ApicurioError,
+    events::{Event, EventSink},
+    lockfile::{LockFile, LockedDependency},
+    output_path, paths,
     registry::RegistryClient,
+    summary::SummarySink,
 };
+use sha2::{Digest, Sha256};
 
-pub async fn run() -> Result<()> {
-    // 1) load configs
-    let repo_cfg = load_repo_config(&PathBuf::from(APICURIO_CONFIG))?;
-    let global_cfg = load_global_config()?;
-    let regs = repo_cfg.merge_registries(global_cfg)?;
-    // build clients
-    let mut clients = HashMap::new();
-    for r in &regs {
-        clients.insert(r.name.clone(), RegistryClient::new(r)?);
-    }
-
-    crate::commands::lock::run().await?;
-    let lock_path = PathBuf::from(APICURIO_LOCK);
-    let lock_file = LockFile::load(&lock_path)?;
-    for dependency in lock_file.locked_dependencies {
-        let client = &clients[&dependency.registry];
-        // download by exact URL, but we know API path from download_url
-        let data = client
-            .client
-            .get(&dependency.download_url)
-            .send()
-            .await?
-            .bytes()
-            .await?;
-        let file_path = PathBuf::from(&dependency.output_path);
-        if let Some(parent) = file_path.parent() {
-            fs::create_dir_all(parent)?;
+use super::lock::{self, LockOptions, StaleOutput};
+
+/// How `pull` locks and writes the dependencies
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PullOptions {
+    pub lock: LockOptions,
+    /// Write downloads even when they do not match the locked `sha256`,
+    /// warning instead of failing (`--no-verify`)
+    pub no_verify: bool,
+    /// Download from the configured URL of a registry that moved since it was
+    /// locked and rewrite the lock's URLs, instead of failing
+    /// (`--reconcile-registry-url`)
+    pub reconcile_registry_url: bool,
+}
+
+impl From<LockOptions> for PullOptions {
+    fn from(lock: LockOptions) -> Self {
+        Self {
+            lock,
+            no_verify: false,
+            reconcile_registry_url: false,
         }
-        fs::write(&file_path, &data)?;
+    }
+}
+
+pub async fn run(
+    identifier: Option<String>,
+    opts: PullOptions,
+    ensure_committed: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let sink = SummarySink::for_format(format);
+    let workspace = Workspace::current()?.with_events(sink.clone());
+    let before = LockFile::load(&workspace.lock_path())
+        .map(|lock| lock.locked_dependencies)
+        .unwrap_or_default();
+    let report = workspace.pull(identifier.as_deref(), opts).await?;
+    if ensure_committed || workspace.config().gitignore.ensure_committed {
+        workspace.ensure_committed(&report.written)?;
+    }
+    let summary = sink.summary(&before, &report.lock.lock_file().locked_dependencies);
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
     }
 
+    match report.lock {
+        LockOutcome::UpToDate(_) => println!("🔒 Lock file up-to-date"),
+        LockOutcome::Updated { reason, .. } => {
+            if let Some(reason) = reason {
+                println!("🔓 Lock file outdated: {reason}");
+            }
+            println!("🔒 Updated {}", workspace.lock_path().display());
+        }
+    }
+    if let Some(descriptor_set) = &report.descriptor_set {
+        println!(
+            "🧩 Wrote {} ({} proto file(s))",
+            descriptor_set.path, descriptor_set.files
+        );
+    }
+    for bundle in &report.bundles {
+        println!("🧩 Wrote {bundle}");
+    }
+    let files = &report.summary;
+    println!(
+        "📦 {} created, {} updated, {} unchanged",
+        files.created.len(),
+        files.updated.len(),
+        files.unchanged.len()
+    );
+    if ensure_committed || workspace.config().gitignore.ensure_committed {
+        println!("📌 Pulled files match what is committed");
+    }
+    summary.print();
     println!("✅ pull complete");
     Ok(())
 }
+
+/// Download locked dependencies and write them to their output paths
+///
+/// Output paths are relative to `root`, the project directory. Nothing is written
+/// unless every download succeeds and the [preflight checks](preflight_paths)
+/// pass; see [`ArtifactWriter`]. Files already holding the locked content are
+/// neither downloaded nor rewritten, so their modification times are kept.
+/// Downloads not matching the lock's `sha256` fail the pull unless `verify` is
+/// off, in which case they are written with a warning. The `stale` output files
+/// of the previous lock are deleted in the same commit, so they stay in place
+/// when the pull fails.
+pub(crate) async fn download_locked(
+    root: &Path,
+    clients: &HashMap<String, RegistryClient>,
+    dependencies: &[&LockedDependency],
+    stale: &[StaleOutput],
+    repo_cfg: &RepoConfig,
+    verify: bool,
+    events: &dyn EventSink,
+) -> Result<PullSummary> {
+    preflight_paths(root, dependencies)?;
+    let mut writer =
+        ArtifactWriter::new(root, repo_cfg)?.with_dependencies(dependencies.iter().copied());
+    let mut contents = Vec::with_capacity(dependencies.len());
+    let mut download_size = 0;
+    for dependency in dependencies {
+        let existing = fs::read(writer.target(dependency)).ok();
+        let data = match existing
+            .as_deref()
+            .and_then(|on_disk| locked_content(&writer, dependency, on_disk))
+        {
+            Some(content) => {
+                events.emit(Event::UpToDate {
+                    name: dependency.name.clone(),
+                    output_path: dependency.output_path.clone(),
+                });
+                content
+            }
+            None => {
+                let client = clients.get(&dependency.registry).with_context(|| {
+                    format!("registry '{}' is not configured", dependency.registry)
+                })?;
+                events.emit(Event::DownloadStarted {
+                    name: dependency.name.clone(),
+                    version: dependency.resolved_version.clone(),
+                });
+                let data = fetch_verified(client, dependency, verify, events).await?;
+                events.emit(Event::DownloadFinished {
+                    name: dependency.name.clone(),
+                    version: dependency.resolved_version.clone(),
+                    output_path: dependency.output_path.clone(),
+                    bytes: data.len(),
+                });
+                download_size += data.len() as u64;
+                data
+            }
+        };
+        contents.push(data);
+    }
+    preflight_space(root, download_size)?;
+
+    let mut summary = PullSummary::default();
+    for (dependency, data) in dependencies.iter().zip(&contents) {
+        let output = dependency.output_path.clone();
+        match writer.stage(dependency, data)? {
+            Change::Created => summary.created.push(output),
+            Change::Updated => summary.updated.push(output),
+            Change::Unchanged => summary.unchanged.push(output),
+        }
+    }
+    let removed: Vec<StaleOutput> = stale
+        .iter()
+        .filter(|output| writer.remove(&output.path))
+        .cloned()
+        .collect();
+    writer.commit()?;
+    lock::stale_outputs_removed(root, &removed, events);
+    Ok(summary)
+}
+
+/// The locked content of an output file, with any provenance header removed
+/// and references restored; `None` when the file holds something else
+fn locked_content(
+    writer: &ArtifactWriter,
+    dependency: &LockedDependency,
+    on_disk: &[u8],
+) -> Option<Vec<u8>> {
+    let matches = |data: &[u8]| hex::encode(Sha256::digest(data)) == dependency.sha256;
+    if matches(on_disk) {
+        return Some(on_disk.to_vec());
+    }
+    Some(writer.registry_content(dependency, on_disk)).filter(|data| matches(data))
+}
+
+/// Check every output path before anything is downloaded, reporting all
+/// problems at once: paths must stay inside `root` and fit the platform limits
+fn preflight_paths(root: &Path, dependencies: &[&LockedDependency]) -> Result<()> {
+    let problems: Vec<String> = dependencies
+        .iter()
+        .filter_map(|dependency| {
+            let problem = if !output_path::within_root(root, &dependency.output_path) {
+                "escapes the project directory".to_string()
+            } else {
+                output_path::length_problem(root, &dependency.output_path)?
+            };
+            Some(format!(
+                "  - {} ({}): {problem}",
+                dependency.name, dependency.output_path
+            ))
+        })
+        .collect();
+    if problems.is_empty() {
+        return Ok(());
+    }
+    bail!(
+        "cannot write {} output file(s); fix their outputPath or output pattern:\n{}",
+        problems.len(),
+        problems.join("\n")
+    )
+}
+
+/// Fail before staging when the filesystem of `root` cannot hold `needed` bytes
+fn preflight_space(root: &Path, needed: u64) -> Result<()> {
+    match available_space(root) {
+        Some(available) if available < needed => bail!(
+            "not enough disk space in {}: the pulled files need {needed} bytes, {available} are available",
+            root.display()
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Bytes the current user may still write on the filesystem holding `dir`
+#[cfg(unix)]
+fn available_space(dir: &Path) -> Option<u64> {
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let stat = rustix::fs::statvfs(dir).ok()?;
+    Some(stat.f_bavail.saturating_mul(stat.f_frsize))
+}
+
+/// Not determined on this platform; the space check is skipped
+#[cfg(not(unix))]
+fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+/// Download a locked dependency and check it against the lock's checksum
+///
+/// The download goes through a `.part` file in the cache directory, so one cut
+/// off by a broken connection (even in an earlier `pull`) resumes where it
+/// stopped. Content that does not match the lock's `sha256` is never returned
+/// unless `verify` is off; if it came from a resumed download it is fetched once
+/// more from the start.
+async fn fetch_verified(
+    client: &RegistryClient,
+    dependency: &LockedDependency,
+    verify: bool,
+    events: &dyn EventSink,
+) -> Result<Vec<u8>> {
+    let part = part_path(dependency)?;
+    let mut resumed = part.exists();
+    loop {
+        // download by exact URL, but we know API path from download_url
+        // (asking for the same representation the lock was hashed from)
+        client
+            .download_to(
+                &dependency.download_url,
+                dependency.content_type.as_deref(),
+                &part,
+            )
+            .await
+            .with_context(|| format!("downloading {}", dependency.name))?;
+        let raw = fs::read(&part).with_context(|| format!("reading {}", part.display()))?;
+        let _ = fs::remove_file(&part);
+        let data = dependency.file_content(&raw)?;
+        let sha256 = hex::encode(Sha256::digest(&data));
+        if sha256 == dependency.sha256 {
+            return Ok(data);
+        }
+        if !resumed && !verify {
+            events.emit(Event::Warning(format!(
+                "{} does not match the lock file (expected sha256={}, got sha256={sha256}); \
+                 writing it anyway because of --no-verify",
+                dependency.name, dependency.sha256
+            )));
+            return Ok(data);
+        }
+        if !resumed {
+            return Err(ApicurioError::integrity(format!(
+                "downloaded content of {} does not match the lock file \
+                 (expected sha256={}, got sha256={sha256}); \
+                 run `apicurio status --drift` to check the registry",
+                dependency.name, dependency.sha256
+            ))
+            .into());
+        }
+        resumed = false;
+    }
+}
+
+/// Partial download file of a locked dependency, named after its URL and checksum
+fn part_path(dependency: &LockedDependency) -> Result<PathBuf> {
+    let dir = paths::cache_dir().path.join("downloads");
+    fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+    let key = Sha256::digest(format!(
+        "{}\n{}",
+        dependency.download_url, dependency.sha256
+    ));
+    Ok(dir.join(format!("{}.part", hex::encode(key))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_preflight_reports_every_bad_path() {
+        let temp = TempDir::new().unwrap();
+        let entry = |name: &str, output_path: String| LockedDependency {
+            name: name.to_string(),
+            registry: "main".to_string(),
+            resolved_version: "1.0.0".to_string(),
+            download_url: String::new(),
+            registry_url: None,
+            sha256: String::new(),
+            output_path,
+            group_id: "com.example".to_string(),
+            artifact_id: name.to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
+        };
+        let fine = entry("fine", "protos/a.proto".to_string());
+        let escaping = entry("escaping", "protos/../../etc/b.proto".to_string());
+        let long = entry("long", format!("protos/{}.proto", "c".repeat(300)));
+        assert!(preflight_paths(temp.path(), &[&fine]).is_ok());
+
+        let message = preflight_paths(temp.path(), &[&fine, &escaping, &long])
+            .unwrap_err()
+            .to_string();
+        assert!(message.starts_with("cannot write 2 output file(s)"));
+        assert!(
+            message.contains("escaping (protos/../../etc/b.proto): escapes the project directory")
+        );
+        assert!(message.contains("long (protos/ccc"));
+        assert!(!message.contains("fine"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_preflight_space() {
+        let temp = TempDir::new().unwrap();
+        assert!(preflight_space(temp.path(), 1).is_ok());
+        assert!(preflight_space(temp.path(), u64::MAX)
+            .unwrap_err()
+            .to_string()
+            .starts_with("not enough disk space"));
+    }
+
+    #[tokio::test]
+    async fn test_pull_only_writes_changed_files() {
+        let temp = TempDir::new().unwrap();
+        let server = crate::dev_server::DevServer::start("127.0.0.1:0")
+            .await
+            .unwrap();
+        let client = RegistryClient::new(&server.registry_config("dev")).unwrap();
+        let entry = |name: &str, content: &str| {
+            server.add_version("com.example", name, "PROTOBUF", "1.0.0", content);
+            let version = semver::Version::new(1, 0, 0);
+            LockedDependency {
+                name: name.to_string(),
+                registry: "dev".to_string(),
+                resolved_version: version.to_string(),
+                download_url: client.get_download_url("com.example", name, &version),
+                registry_url: None,
+                sha256: hex::encode(Sha256::digest(content)),
+                output_path: format!("protos/{name}.proto"),
+                group_id: "com.example".to_string(),
+                artifact_id: name.to_string(),
+                version_spec: "^1".to_string(),
+                is_transitive: false,
+                references: Vec::new(),
+                reference_names: Default::default(),
+                binary: false,
+                avro_fingerprint: None,
+                content_type: None,
+                member_of: None,
+            }
+        };
+        let same = entry("same", "same v1");
+        let stale = entry("stale", "stale v1");
+        let created = entry("created", "created v1");
+        let mut tampered = entry("tampered", "tampered v1");
+        tampered.sha256 = hex::encode(Sha256::digest("something else"));
+        fs::create_dir_all(temp.path().join("protos")).unwrap();
+        fs::write(temp.path().join("protos/same.proto"), "same v1").unwrap();
+        fs::write(temp.path().join("protos/stale.proto"), "stale v0").unwrap();
+        fs::write(temp.path().join("protos/old.proto"), "old v1").unwrap();
+        let old = StaleOutput {
+            path: "protos/old.proto".to_string(),
+            removed_dependency: Some("old".to_string()),
+        };
+        let clients = HashMap::from([("dev".to_string(), client)]);
+
+        let summary = download_locked(
+            temp.path(),
+            &clients,
+            &[&same, &stale, &created],
+            &[],
+            &RepoConfig::default(),
+            true,
+            &crate::events::NullSink,
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.unchanged, ["protos/same.proto"]);
+        assert_eq!(summary.updated, ["protos/stale.proto"]);
+        assert_eq!(summary.created, ["protos/created.proto"]);
+        let read = |name| fs::read_to_string(temp.path().join("protos").join(name)).unwrap();
+        assert_eq!(read("stale.proto"), "stale v1");
+        assert_eq!(read("created.proto"), "created v1");
+
+        // Content not matching the lock fails the pull unless verification is off
+        let err = download_locked(
+            temp.path(),
+            &clients,
+            &[&tampered],
+            std::slice::from_ref(&old),
+            &RepoConfig::default(),
+            true,
+            &crate::events::NullSink,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ApicurioError>(),
+            Some(ApicurioError::Integrity { .. })
+        ));
+        assert!(!temp.path().join("protos/tampered.proto").exists());
+        // Stale outputs of the previous lock are only deleted by a successful pull
+        assert!(temp.path().join("protos/old.proto").exists());
+        let events = crate::events::CollectingSink::default();
+        download_locked(
+            temp.path(),
+            &clients,
+            &[&tampered],
+            &[old],
+            &RepoConfig::default(),
+            false,
+            &events,
+        )
+        .await
+        .unwrap();
+        assert_eq!(read("tampered.proto"), "tampered v1");
+        assert!(!temp.path().join("protos/old.proto").exists());
+        assert!(events
+            .events()
+            .iter()
+            .any(|event| matches!(event, Event::Warning(_))));
+
+        // Up-to-date files are not even downloaded
+        drop(server);
+        let summary = download_locked(
+            temp.path(),
+            &clients,
+            &[&same, &stale, &created],
+            &[],
+            &RepoConfig::default(),
+            true,
+            &crate::events::NullSink,
+        )
+        .await
+        .unwrap();
+        assert_eq!(summary.unchanged.len(), 3);
+    }
+}
@@ -1,17 +1,186 @@
-use crate::config::{load_global_config, save_global_config, AuthConfig, RegistryConfig};
+use crate::config::{
+    global_registries_path, load_global_config, load_registries_file, load_repo_config,
+    save_registries_file, AuthConfig, GlobalConfig, RegistryConfig,
+};
+use crate::config_edit::{
+    list_named_entries, read_editable, remove_named_entry, upsert_named_entry, write_edited,
+};
+use crate::diagnostics::{diagnose_registry, CheckStatus, RegistryDiagnostics};
+use crate::paths;
 use anyhow::{anyhow, Result};
-use clap::Subcommand;
-use dialoguer::Select;
+use clap::{Args, Subcommand};
+use dialoguer::{Input, Select};
+use std::env;
 use std::io::{stdin, stdout, Write};
+use std::path::{Path, PathBuf};
 
 #[derive(Subcommand, Debug)]
 pub enum RegistryCommands {
     /// List all global registries
-    List,
+    List {
+        /// List the registries defined in apicurioconfig.yaml instead
+        #[arg(long)]
+        local: bool,
+    },
     /// Add a new global registry
-    Add,
+    Add {
+        /// Add the registry to apicurioconfig.yaml instead
+        #[arg(long)]
+        local: bool,
+    },
     /// Remove a global registry by name
-    Remove { name: String },
+    Remove {
+        name: String,
+        /// Remove the registry from apicurioconfig.yaml instead
+        #[arg(long)]
+        local: bool,
+    },
+    /// Show full details of a registry (secrets masked)
+    Show {
+        name: String,
+        #[command(flatten)]
+        target: RegistryTarget,
+    },
+    /// Interactively edit the URL and auth of a registry
+    Edit {
+        name: String,
+        #[command(flatten)]
+        target: RegistryTarget,
+    },
+    /// Check reachability, auth, API version and latency of one or all registries
+    Ping {
+        /// Registry to check (all registries when omitted)
+        name: Option<String>,
+        #[command(flatten)]
+        target: RegistryTarget,
+    },
+}
+
+/// Which registries file a command reads or writes
+#[derive(Args, Debug, Default)]
+pub struct RegistryTarget {
+    /// Use the global registries file
+    #[arg(long, conflicts_with_all = ["file", "local"])]
+    pub global: bool,
+    /// Use the `registries:` section of apicurioconfig.yaml
+    #[arg(long, conflicts_with = "file")]
+    pub local: bool,
+    /// Use a specific registries file (e.g. the repo's external registries file)
+    #[arg(long, value_name = "PATH")]
+    pub file: Option<PathBuf>,
+}
+
+impl RegistryTarget {
+    fn is_set(&self) -> bool {
+        self.global || self.local || self.file.is_some()
+    }
+
+    /// The store this target writes to (`--local`, `--file`, otherwise the global file)
+    fn store(&self) -> RegistryStore {
+        if self.local {
+            RegistryStore::Local(paths::project_config())
+        } else {
+            RegistryStore::File(self.file.clone().unwrap_or_else(global_registries_path))
+        }
+    }
+}
+
+/// A place registries can be read from and written to
+enum RegistryStore {
+    /// The `registries:` section of a repository config, edited in place
+    Local(PathBuf),
+    /// A standalone registries file such as the global one
+    File(PathBuf),
+}
+
+impl RegistryStore {
+    fn global() -> Self {
+        RegistryStore::File(global_registries_path())
+    }
+
+    fn local_or_global(local: bool) -> Self {
+        RegistryTarget {
+            local,
+            ..Default::default()
+        }
+        .store()
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            RegistryStore::Local(path) | RegistryStore::File(path) => path,
+        }
+    }
+
+    fn load(&self) -> Result<Vec<RegistryConfig>> {
+        match self {
+            RegistryStore::Local(path) => {
+                let content = read_editable(path)?;
+                list_named_entries(&content, "registries")?
+                    .into_iter()
+                    .map(|entry| Ok(serde_yaml::from_value(entry)?))
+                    .collect()
+            }
+            RegistryStore::File(path) if path.exists() => {
+                Ok(load_registries_file(path)?.registries)
+            }
+            RegistryStore::File(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Insert the registry, replacing any existing one with the same name
+    fn save(&self, registry: &RegistryConfig) -> Result<()> {
+        match self {
+            RegistryStore::Local(path) => {
+                let content = read_editable(path)?;
+                let entry = serde_yaml::to_value(registry)?;
+                write_edited(path, &upsert_named_entry(&content, "registries", &entry)?)?;
+            }
+            RegistryStore::File(path) => {
+                let mut file = self.load_file()?;
+                match file.registries.iter_mut().find(|r| r.name == registry.name) {
+                    Some(existing) => *existing = registry.clone(),
+                    None => file.registries.push(registry.clone()),
+                }
+                save_registries_file(&file, path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove a registry by name, returning whether it existed
+    fn remove(&self, name: &str) -> Result<bool> {
+        match self {
+            RegistryStore::Local(path) => {
+                let content = read_editable(path)?;
+                match remove_named_entry(&content, "registries", name)? {
+                    Some(updated) => {
+                        write_edited(path, &updated)?;
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                }
+            }
+            RegistryStore::File(path) => {
+                let mut file = self.load_file()?;
+                let before = file.registries.len();
+                file.registries.retain(|r| r.name != name);
+                if file.registries.len() == before {
+                    return Ok(false);
+                }
+                save_registries_file(&file, path)?;
+                Ok(true)
+            }
+        }
+    }
+
+    fn load_file(&self) -> Result<GlobalConfig> {
+        if self.path().exists() {
+            Ok(load_registries_file(self.path())?)
+        } else {
+            Ok(GlobalConfig::default())
+        }
+    }
 }
 
 fn prompt(msg: &str) -> Result<String> {
@@ -27,74 +196,501 @@ fn prompt(msg: &str) -> Result<String> {
     }
 }
 
-pub async fn run(cmd: RegistryCommands) -> Result<()> {
-    let mut global = load_global_config()?;
+/// Prompt for a value that may be left empty
+fn prompt_optional(msg: &str, default: &str) -> Result<Option<String>> {
+    let value: String = Input::new()
+        .with_prompt(msg)
+        .default(default.to_string())
+        .allow_empty(true)
+        .interact_text()?;
+    let value = value.trim().to_string();
+    Ok((!value.is_empty()).then_some(value))
+}
+
+fn prompt_with_default(msg: &str, default: &str) -> Result<String> {
+    if default.is_empty() {
+        return prompt(msg);
+    }
+    Ok(Input::new()
+        .with_prompt(msg)
+        .default(default.to_string())
+        .interact_text()?)
+}
+
+/// Prompt for an auth configuration, pre-filling values from `current`
+fn prompt_auth(current: &AuthConfig) -> Result<AuthConfig> {
+    // Use select menu for auth types
+    let auth_options = vec![
+        "none",
+        "basic",
+        "token",
+        "bearer",
+        "aws-sigv4",
+        "netrc",
+        "credential-helper",
+        "exec",
+    ];
+    let current_index = match current {
+        AuthConfig::None => 0,
+        AuthConfig::Basic { .. } => 1,
+        AuthConfig::Token { .. } => 2,
+        AuthConfig::Bearer { .. } => 3,
+        AuthConfig::AwsSigv4 { .. } => 4,
+        AuthConfig::Netrc { .. } => 5,
+        AuthConfig::CredentialHelper { .. } => 6,
+        AuthConfig::Exec { .. } => 7,
+    };
+    let selection = Select::new()
+        .with_prompt("Auth type")
+        .items(&auth_options)
+        .default(current_index)
+        .interact()?;
+
+    let (current_user, current_env, current_ref) = match current {
+        AuthConfig::None
+        | AuthConfig::AwsSigv4 { .. }
+        | AuthConfig::Netrc { .. }
+        | AuthConfig::CredentialHelper { .. }
+        | AuthConfig::Exec { .. } => ("", "", None),
+        AuthConfig::Basic {
+            username,
+            password_env,
+            secret_ref,
+        } => (
+            username.as_str(),
+            password_env.as_str(),
+            secret_ref.as_deref(),
+        ),
+        AuthConfig::Token {
+            token_env,
+            secret_ref,
+        }
+        | AuthConfig::Bearer {
+            token_env,
+            secret_ref,
+        } => ("", token_env.as_str(), secret_ref.as_deref()),
+    };
 
+    // A secret reference replaces the env var; ask for one only when none is given
+    let credential = |env_prompt: &str| -> Result<(String, Option<String>)> {
+        let secret_ref = prompt_optional(
+            "Secret reference (e.g. vault://kv/app#token, op://..., empty to use an env var)",
+            current_ref.unwrap_or(""),
+        )?;
+        match secret_ref {
+            Some(secret_ref) => Ok((String::new(), Some(secret_ref))),
+            None => Ok((prompt_with_default(env_prompt, current_env)?, None)),
+        }
+    };
+
+    let auth = match auth_options[selection] {
+        "none" => AuthConfig::None,
+        "basic" => {
+            let user = prompt_with_default("Username", current_user)?;
+            let (password_env, secret_ref) = credential("Password env var")?;
+            AuthConfig::Basic {
+                username: user,
+                password_env,
+                secret_ref,
+            }
+        }
+        "token" => {
+            let (token_env, secret_ref) = credential("Token env var")?;
+            AuthConfig::Token {
+                token_env,
+                secret_ref,
+            }
+        }
+        "bearer" => {
+            let (token_env, secret_ref) = credential("Bearer-token env var")?;
+            AuthConfig::Bearer {
+                token_env,
+                secret_ref,
+            }
+        }
+        "aws-sigv4" => {
+            let (region, service, profile) = match current {
+                AuthConfig::AwsSigv4 {
+                    region,
+                    service,
+                    profile,
+                } => (region.as_str(), service.as_str(), profile.as_deref()),
+                _ => ("", "execute-api", None),
+            };
+            AuthConfig::AwsSigv4 {
+                region: prompt_with_default("AWS region", region)?,
+                service: prompt_with_default("AWS service", service)?,
+                profile: prompt_optional(
+                    "AWS profile (empty for AWS_PROFILE / default)",
+                    profile.unwrap_or(""),
+                )?,
+            }
+        }
+        "netrc" => {
+            let (file, machine) = match current {
+                AuthConfig::Netrc { file, machine } => (file.as_deref(), machine.as_deref()),
+                _ => (None, None),
+            };
+            AuthConfig::Netrc {
+                file: prompt_optional(
+                    "netrc file (empty for $NETRC / ~/.netrc)",
+                    file.unwrap_or(""),
+                )?,
+                machine: prompt_optional(
+                    "Machine (empty for the host of the URL)",
+                    machine.unwrap_or(""),
+                )?,
+            }
+        }
+        "credential-helper" => {
+            let command = match current {
+                AuthConfig::CredentialHelper { command } => command.as_str(),
+                _ => "git credential-store",
+            };
+            AuthConfig::CredentialHelper {
+                command: prompt_with_default("Helper command (run with `get`)", command)?,
+            }
+        }
+        "exec" => {
+            let current_line = match current {
+                AuthConfig::Exec { command, args } => std::iter::once(command.as_str())
+                    .chain(args.iter().map(String::as_str))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => String::new(),
+            };
+            let line = prompt_with_default(
+                "Command printing the token (e.g. kubectl oidc-login get-token ...)",
+                &current_line,
+            )?;
+            let mut words = line.split_whitespace().map(str::to_string);
+            AuthConfig::Exec {
+                command: words
+                    .next()
+                    .ok_or_else(|| anyhow!("the exec command is empty"))?,
+                args: words.collect(),
+            }
+        }
+        other => return Err(anyhow!("unknown auth type '{}'", other)),
+    };
+    Ok(auth)
+}
+
+/// Mask a secret so only a hint of it is displayed
+fn mask_secret(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 8 {
+        "****".to_string()
+    } else {
+        let head: String = chars[..2].iter().collect();
+        let tail: String = chars[chars.len() - 2..].iter().collect();
+        format!("{head}****{tail}")
+    }
+}
+
+/// Describe an env var holding a secret without revealing it
+fn describe_secret_env(var: &str) -> String {
+    match env::var(var) {
+        Ok(value) if !value.is_empty() => format!("${var} = {}", mask_secret(&value)),
+        Ok(_) => format!("${var} (set but empty)"),
+        Err(_) => format!("${var} (not set)"),
+    }
+}
+
+/// Describe where a credential comes from; secret references are not resolved
+fn describe_credential(env_var: &str, secret_ref: &Option<String>) -> String {
+    match secret_ref {
+        Some(secret_ref) => format!("secretRef {secret_ref}"),
+        None => describe_secret_env(env_var),
+    }
+}
+
+fn print_registry(reg: &RegistryConfig, source: &str) {
+    println!("Name:   {}", reg.name);
+    println!("URL:    {}", reg.url);
+    println!("Source: {source}");
+    match &reg.auth {
+        AuthConfig::None => println!("Auth:   none"),
+        AuthConfig::Basic {
+            username,
+            password_env,
+            secret_ref,
+        } => {
+            println!("Auth:   basic");
+            println!("  username: {username}");
+            println!(
+                "  password: {}",
+                describe_credential(password_env, secret_ref)
+            );
+        }
+        AuthConfig::Token {
+            token_env,
+            secret_ref,
+        } => {
+            println!("Auth:   token");
+            println!("  token:    {}", describe_credential(token_env, secret_ref));
+        }
+        AuthConfig::Bearer {
+            token_env,
+            secret_ref,
+        } => {
+            println!("Auth:   bearer");
+            println!("  token:    {}", describe_credential(token_env, secret_ref));
+        }
+        AuthConfig::AwsSigv4 {
+            region,
+            service,
+            profile,
+        } => {
+            println!("Auth:   aws-sigv4");
+            println!("  region:   {region}");
+            println!("  service:  {service}");
+            println!(
+                "  profile:  {}",
+                profile.as_deref().unwrap_or("(AWS_PROFILE or default)")
+            );
+        }
+        AuthConfig::Netrc { file, machine } => {
+            println!("Auth:   netrc");
+            println!(
+                "  file:     {}",
+                file.as_deref().unwrap_or("($NETRC or ~/.netrc)")
+            );
+            println!(
+                "  machine:  {}",
+                machine.as_deref().unwrap_or("(host of the URL)")
+            );
+        }
+        AuthConfig::CredentialHelper { command } => {
+            println!("Auth:   credential helper");
+            println!("  command:  {command}");
+        }
+        AuthConfig::Exec { command, args } => {
+            println!("Auth:   exec");
+            println!("  command:  {command} {}", args.join(" "));
+        }
+    }
+}
+
+/// Registries of the targeted store, or the effective merged registries when
+/// no target is given (returns the registries and where they came from)
+fn load_targeted(target: &RegistryTarget) -> Result<(Vec<RegistryConfig>, String)> {
+    Ok(if target.is_set() {
+        let store = target.store();
+        (store.load()?, store.path().display().to_string())
+    } else if paths::project_config().exists() {
+        // Effective registry after merging global, external, repo-local and profile
+        let repo = load_repo_config(&paths::project_config())?;
+        (
+            repo.merge_registries(load_global_config()?)?,
+            "merged configuration".to_string(),
+        )
+    } else {
+        let store = RegistryStore::global();
+        (store.load()?, store.path().display().to_string())
+    })
+}
+
+/// Find a registry by name in the registries of [`load_targeted`]
+fn find_registry(name: &str, target: &RegistryTarget) -> Result<(RegistryConfig, String)> {
+    let (candidates, source) = load_targeted(target)?;
+    candidates
+        .into_iter()
+        .find(|r| r.name == name)
+        .map(|r| (r, source))
+        .ok_or_else(|| anyhow!("no such registry '{}'", name))
+}
+
+/// One line per registry: overall status, failing step, auth, API version and latency
+fn ping_table(reports: &[RegistryDiagnostics]) -> String {
+    let cell = |status: Option<CheckStatus>| match status {
+        Some(CheckStatus::Ok) => "ok",
+        Some(CheckStatus::Warn) => "warn",
+        Some(CheckStatus::Fail) => "fail",
+        Some(CheckStatus::Skipped) | None => "-",
+    };
+    let mut rows = vec![[
+        "REGISTRY".to_string(),
+        "STATUS".to_string(),
+        "AUTH".to_string(),
+        "API".to_string(),
+        "LATENCY".to_string(),
+        "URL".to_string(),
+    ]];
+    for report in reports {
+        let status = match report.first_failure() {
+            Some(check) => format!("fail ({})", check.name),
+            None if report.checks.iter().any(|c| c.status == CheckStatus::Warn) => {
+                "warn".to_string()
+            }
+            None => "ok".to_string(),
+        };
+        rows.push([
+            report.registry.clone(),
+            status,
+            cell(report.status_of("Auth")).to_string(),
+            report
+                .api_version
+                .clone()
+                .unwrap_or_else(|| cell(report.status_of("API")).to_string()),
+            report
+                .latency_ms
+                .map_or_else(|| "-".to_string(), |ms| format!("{ms}ms")),
+            report.url.clone(),
+        ]);
+    }
+
+    let mut widths = [0; 6];
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+    let mut out = String::new();
+    for row in &rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(value, width)| format!("{value:<width$}"))
+            .collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+pub async fn run(cmd: RegistryCommands) -> Result<()> {
     match cmd {
-        RegistryCommands::List => {
-            if global.registries.is_empty() {
-                println!("(no global registries defined)");
+        RegistryCommands::List { local } => {
+            let registries = RegistryStore::local_or_global(local).load()?;
+            let scope = if local { "repo-local" } else { "global" };
+            if registries.is_empty() {
+                println!("(no {scope} registries defined)");
             } else {
-                for r in &global.registries {
+                for r in &registries {
                     println!(" - {} → {} (type={:?})", r.name, r.url, r.auth);
                 }
             }
         }
-        RegistryCommands::Add => {
+        RegistryCommands::Add { local } => {
+            let store = RegistryStore::local_or_global(local);
+            let registries = store.load()?;
             let name = prompt("Registry name")?;
-            if global.registries.iter().any(|r| r.name == name) {
+            if registries.iter().any(|r| r.name == name) {
                 return Err(anyhow!("registry '{}' already exists", name));
             }
             let url = prompt("Registry URL")?;
-
-            // Use select menu for auth types
-            let auth_options = vec!["none", "basic", "token", "bearer"];
-            let selection = Select::new()
-                .with_prompt("Auth type")
-                .items(&auth_options)
-                .default(0)
-                .interact()?;
-
-            let auth_type = auth_options[selection];
-            let auth = match auth_type {
-                "none" => AuthConfig::None,
-                "basic" => {
-                    let user = prompt("Username")?;
-                    let pw_env = prompt("Password env var")?;
-                    AuthConfig::Basic {
-                        username: user,
-                        password_env: pw_env,
-                    }
-                }
-                "token" => {
-                    let ev = prompt("Token env var")?;
-                    AuthConfig::Token { token_env: ev }
-                }
-                "bearer" => {
-                    let ev = prompt("Bearer-token env var")?;
-                    AuthConfig::Bearer { token_env: ev }
-                }
-                other => return Err(anyhow!("unknown auth type '{}'", other)),
-            };
-            global.registries.push(RegistryConfig {
+            let auth = prompt_auth(&AuthConfig::None)?;
+            store.save(&RegistryConfig {
                 name: name.clone(),
                 url,
                 auth,
-            });
-            save_global_config(&global)?;
-            println!("✅ Added registry '{name}' successfully");
-        }
-        RegistryCommands::Remove { name } => {
-            let before = global.registries.len();
-            global.registries.retain(|r| r.name != name);
-            if global.registries.len() == before {
-                println!("no such registry '{name}'");
-            } else {
-                save_global_config(&global)?;
+                ..Default::default()
+            })?;
+            println!("✅ Added registry '{name}' to {}", store.path().display());
+        }
+        RegistryCommands::Remove { name, local } => {
+            let store = RegistryStore::local_or_global(local);
+            if store.remove(&name)? {
                 println!("removed '{name}'");
+            } else {
+                println!("no such registry '{name}'");
+            }
+        }
+        RegistryCommands::Show { name, target } => {
+            let (reg, source) = find_registry(&name, &target)?;
+            print_registry(&reg, &source);
+        }
+        RegistryCommands::Edit { name, target } => {
+            let store = target.store();
+            let mut reg = store
+                .load()?
+                .into_iter()
+                .find(|r| r.name == name)
+                .ok_or_else(|| {
+                    anyhow!("no such registry '{}' in {}", name, store.path().display())
+                })?;
+
+            reg.url = prompt_with_default("Registry URL", &reg.url)?;
+            reg.auth = prompt_auth(&reg.auth)?;
+
+            store.save(&reg)?;
+            println!("✅ Updated registry '{name}' in {}", store.path().display());
+        }
+        RegistryCommands::Ping { name, target } => {
+            let registries = match name {
+                Some(name) => vec![find_registry(&name, &target)?.0],
+                None => load_targeted(&target)?.0,
+            };
+            if registries.is_empty() {
+                println!("(no registries defined)");
+                return Ok(());
+            }
+            let mut reports = Vec::new();
+            for registry in &registries {
+                reports.push(diagnose_registry(registry).await);
+            }
+            print!("{}", ping_table(&reports));
+            let failed: Vec<&str> = reports
+                .iter()
+                .filter(|r| !r.is_healthy())
+                .map(|r| r.registry.as_str())
+                .collect();
+            if !failed.is_empty() {
+                return Err(anyhow!(
+                    "unhealthy registries: {} (run `apicurio doctor` for details)",
+                    failed.join(", ")
+                ));
             }
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::CheckResult;
+
+    #[test]
+    fn test_ping_table() {
+        let check = |name, status| CheckResult {
+            name,
+            status,
+            detail: String::new(),
+        };
+        let healthy = RegistryDiagnostics {
+            registry: "prod".to_string(),
+            url: "https://registry.example.com".to_string(),
+            checks: vec![
+                check("TCP", CheckStatus::Ok),
+                check("Auth", CheckStatus::Ok),
+                check("API", CheckStatus::Ok),
+            ],
+            latency_ms: Some(42),
+            api_version: Some("3.0.6".to_string()),
+        };
+        let down = RegistryDiagnostics {
+            registry: "local".to_string(),
+            url: "http://localhost:8080".to_string(),
+            checks: vec![
+                check("TCP", CheckStatus::Fail),
+                check("Auth", CheckStatus::Skipped),
+                check("API", CheckStatus::Skipped),
+            ],
+            latency_ms: None,
+            api_version: None,
+        };
+        let table = ping_table(&[healthy, down]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "REGISTRY  STATUS      AUTH  API    LATENCY  URL",
+                "prod      ok          ok    3.0.6  42ms     https://registry.example.com",
+                "local     fail (TCP)  -     -      -        http://localhost:8080",
+            ]
+        );
+    }
+}
@@ -1,11 +1,10 @@
-use crate::{config::load_repo_config, constants::APICURIO_CONFIG, identifier::Identifier};
+use crate::{config::load_repo_config_without_includes, identifier::Identifier, paths};
 use anyhow::{anyhow, Result};
 use dialoguer::Select;
-use std::path::PathBuf;
 
 pub async fn run(identifier_str: String) -> Result<()> {
-    let repo_path = PathBuf::from(APICURIO_CONFIG);
-    let mut repo = load_repo_config(&repo_path)?;
+    let repo_path = paths::project_config();
+    let mut repo = load_repo_config_without_includes(&repo_path)?;
 
     if repo.dependencies.is_empty() {
         println!("No dependencies to remove.");
@@ -69,7 +68,7 @@ pub async fn run(identifier_str: String) -> Result<()> {
         println!("✅ Removed dependency: {dependency_name}");
 
         // Pull the dependency immediately
-        crate::commands::pull::run().await?;
+        crate::commands::pull::run(None, Default::default(), false, Default::default()).await?;
     } else {
         return Err(anyhow!("Failed to remove dependency: {}", dependency_name));
     }
@@ -0,0 +1,160 @@
+use crate::{
+    api::{PullOptions, Workspace},
+    artifact_writer,
+    config_edit::{list_named_entries, read_editable, replace_named_entry, write_edited},
+    events::ConsoleSink,
+    identifier::{find_dependency, Identifier},
+    lockfile::LockFile,
+    output_path,
+};
+use anyhow::{anyhow, bail, Result};
+use serde_yaml::Value;
+use std::{fs, path::PathBuf, sync::Arc};
+
+/// Move a dependency to new coordinates
+///
+/// Rewrites its config entry in place (name, group, artifact, registry and,
+/// with `@version`, version), then relocks and pulls: the old output file is
+/// removed and the artifact written to its new path. If any step fails the
+/// config, the lock file and the old output files are restored.
+pub async fn run(old: String, new: String) -> Result<()> {
+    let workspace = Workspace::current()?;
+    let config_path = workspace.config_path();
+    let dep = find_dependency(&old, &workspace.config().dependencies)?.clone();
+    if dep.group.is_some() || !dep.labels.is_empty() {
+        bail!(
+            "'{}' locks several artifacts (group or labels) and cannot be renamed; edit it in {}",
+            dep.name,
+            config_path.display()
+        );
+    }
+
+    let target = Identifier::parse(&new);
+    let artifact_id = target
+        .artifact_id
+        .clone()
+        .ok_or_else(|| anyhow!("'{new}' names no artifact ID"))?;
+    let group_id = target
+        .group_id
+        .clone()
+        .unwrap_or_else(|| dep.resolved_group_id());
+    let name = format!("{group_id}/{artifact_id}");
+    if name != dep.name
+        && workspace
+            .config()
+            .dependencies
+            .iter()
+            .any(|d| d.name == name)
+    {
+        bail!("A dependency named '{name}' exists already");
+    }
+    let registry_changed = target
+        .registry
+        .as_ref()
+        .is_some_and(|r| dep.registry.as_ref() != Some(r));
+    let version_changed = target.version.as_ref().is_some_and(|v| *v != dep.version);
+    if name == dep.name
+        && group_id == dep.resolved_group_id()
+        && artifact_id == dep.resolved_artifact_id()
+        && !registry_changed
+        && !version_changed
+    {
+        println!(
+            "Nothing to rename: '{}' already has these coordinates",
+            dep.name
+        );
+        return Ok(());
+    }
+
+    let content = read_editable(&config_path)?;
+    let Some(mut entry) = list_named_entries(&content, "dependencies")?
+        .into_iter()
+        .find(|e| e.get("name").and_then(Value::as_str) == Some(dep.name.as_str()))
+    else {
+        bail!(
+            "dependency '{}' is not defined in {} (is it in an include?)",
+            dep.name,
+            config_path.display()
+        );
+    };
+    if let Some(map) = entry.as_mapping_mut() {
+        map.insert("name".into(), name.clone().into());
+        // The new name carries both
+        map.remove("groupId");
+        map.remove("artifactId");
+        if let Some(registry) = &target.registry {
+            map.insert("registry".into(), registry.clone().into());
+        }
+        if let Some(version) = &target.version {
+            map.insert("version".into(), version.clone().into());
+        }
+    }
+    let updated = replace_named_entry(&content, "dependencies", &dep.name, &entry)?
+        .ok_or_else(|| anyhow!("dependency '{}' vanished from the config", dep.name))?;
+
+    // Pulling removes the old output files before writing the new ones; keep
+    // them to put back if the pull fails
+    let old_entries = LockFile::load(&workspace.lock_path())
+        .map(|lock| {
+            lock.dependency_closure(&dep.name)
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let backups: Vec<(PathBuf, Vec<u8>)> = old_entries
+        .iter()
+        .map(|d| output_path::native(workspace.root(), &d.output_path))
+        .filter_map(|path| fs::read(&path).ok().map(|data| (path, data)))
+        .collect();
+    let original = fs::read(&config_path)?;
+    let read_only = workspace.config().write_protection;
+
+    write_edited(&config_path, &updated)?;
+    let pulled = match Workspace::current() {
+        Ok(renamed) => {
+            renamed
+                .with_events(Arc::new(ConsoleSink))
+                .pull(None, PullOptions::default())
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    let report = match pulled {
+        Ok(report) => report,
+        Err(e) => {
+            fs::write(&config_path, &original)?;
+            for (path, data) in &backups {
+                if !path.exists() {
+                    if let Some(parent) = path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::write(path, data)?;
+                    artifact_writer::set_read_only(path, read_only)?;
+                }
+            }
+            return Err(e.context(format!(
+                "Failed to rename '{}', the config and pulled files were restored",
+                dep.name
+            )));
+        }
+    };
+
+    println!("✅ Renamed dependency: {} → {name}", dep.name);
+    let old_path = old_entries.iter().find(|d| d.name == dep.name);
+    let new_path = report
+        .lock
+        .lock_file()
+        .locked_dependencies
+        .iter()
+        .find(|d| d.name == name);
+    if let (Some(old_path), Some(new_path)) = (old_path, new_path) {
+        if old_path.output_path != new_path.output_path {
+            println!(
+                "📦 Moved {} → {}",
+                old_path.output_path, new_path.output_path
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,220 @@
+use crate::{api::Workspace, constants::APICURIO_LOCK, lockfile::LockFile};
+use anyhow::{anyhow, Context, Result};
+use serde_json::{json, Map, Value};
+use std::{collections::HashMap, fs};
+
+/// Labels reported when no `--label` is given
+const DEFAULT_LABELS: &[&str] = &["license", "owner", "contact"];
+
+/// Output format of `apicurio report`
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Markdown table
+    #[default]
+    Markdown,
+    /// Comma-separated values with a header row
+    Csv,
+    /// One object per dependency
+    Json,
+}
+
+/// One locked dependency with the requested label values
+#[derive(Debug, Clone, PartialEq)]
+struct ReportRow {
+    name: String,
+    registry: String,
+    group_id: String,
+    artifact_id: String,
+    version: String,
+    transitive: bool,
+    /// Values in the order of the requested labels; `None` when unset
+    values: Vec<Option<String>>,
+}
+
+/// Collect the requested labels of every locked dependency from the registry
+pub async fn run(labels: Vec<String>, format: ReportFormat, out: Option<String>) -> Result<()> {
+    let labels = if labels.is_empty() {
+        DEFAULT_LABELS.iter().map(|l| l.to_string()).collect()
+    } else {
+        labels
+    };
+    let workspace = Workspace::current()?;
+    let lock = LockFile::load(&workspace.lock_path())
+        .with_context(|| format!("reading {APICURIO_LOCK}; run `apicurio lock` first"))?;
+
+    let mut rows = Vec::new();
+    for dep in &lock.locked_dependencies {
+        let client = workspace
+            .client(&dep.registry)
+            .ok_or_else(|| anyhow!("Registry '{}' not found", dep.registry))?;
+        // Version labels refine the labels of the artifact
+        let mut found: HashMap<String, String> = client
+            .get_artifact_metadata(&dep.group_id, &dep.artifact_id)
+            .await
+            .with_context(|| format!("reading {}/{}", dep.group_id, dep.artifact_id))?
+            .labels
+            .unwrap_or_default();
+        let version = client
+            .get_version_metadata(&dep.group_id, &dep.artifact_id, &dep.resolved_version)
+            .await
+            .with_context(|| {
+                format!(
+                    "reading {}/{}@{}",
+                    dep.group_id, dep.artifact_id, dep.resolved_version
+                )
+            })?;
+        found.extend(version.labels.unwrap_or_default());
+        if let Some(owner) = version.owner.filter(|o| !o.is_empty()) {
+            found.entry("owner".to_string()).or_insert(owner);
+        }
+        rows.push(ReportRow {
+            name: dep.name.clone(),
+            registry: dep.registry.clone(),
+            group_id: dep.group_id.clone(),
+            artifact_id: dep.artifact_id.clone(),
+            version: dep.resolved_version.clone(),
+            transitive: dep.is_transitive,
+            values: labels.iter().map(|l| found.get(l).cloned()).collect(),
+        });
+    }
+
+    let rendered = match format {
+        ReportFormat::Markdown => render_markdown(&labels, &rows),
+        ReportFormat::Csv => render_csv(&labels, &rows),
+        ReportFormat::Json => render_json(&labels, &rows)?,
+    };
+    match out {
+        Some(path) => {
+            fs::write(&path, rendered).with_context(|| format!("writing {path}"))?;
+            println!(
+                "✅ Wrote the report of {} dependencies to {path}",
+                rows.len()
+            );
+        }
+        None => print!("{rendered}"),
+    }
+    Ok(())
+}
+
+fn fixed_columns(row: &ReportRow) -> [String; 5] {
+    [
+        row.name.clone(),
+        row.registry.clone(),
+        format!("{}/{}", row.group_id, row.artifact_id),
+        row.version.clone(),
+        if row.transitive {
+            "transitive"
+        } else {
+            "direct"
+        }
+        .to_string(),
+    ]
+}
+
+const FIXED_HEADERS: [&str; 5] = ["name", "registry", "artifact", "version", "kind"];
+
+fn render_markdown(labels: &[String], rows: &[ReportRow]) -> String {
+    let headers: Vec<&str> = FIXED_HEADERS
+        .iter()
+        .copied()
+        .chain(labels.iter().map(String::as_str))
+        .collect();
+    let mut out = format!("| {} |\n", headers.join(" | "));
+    out.push_str(&format!("|{}\n", "---|".repeat(headers.len())));
+    for row in rows {
+        let cells: Vec<String> = fixed_columns(row)
+            .into_iter()
+            .chain(
+                row.values
+                    .iter()
+                    .map(|v| v.clone().unwrap_or_else(|| "-".to_string())),
+            )
+            .map(|cell| cell.replace('|', "\\|"))
+            .collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    out
+}
+
+fn render_csv(labels: &[String], rows: &[ReportRow]) -> String {
+    // RFC 4180: quote fields containing separators, quotes or line breaks
+    let field = |value: &str| {
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    };
+    let headers: Vec<String> = FIXED_HEADERS
+        .iter()
+        .map(|h| h.to_string())
+        .chain(labels.iter().cloned())
+        .map(|h| field(&h))
+        .collect();
+    let mut out = format!("{}\n", headers.join(","));
+    for row in rows {
+        let cells: Vec<String> = fixed_columns(row)
+            .into_iter()
+            .chain(row.values.iter().map(|v| v.clone().unwrap_or_default()))
+            .map(|cell| field(&cell))
+            .collect();
+        out.push_str(&format!("{}\n", cells.join(",")));
+    }
+    out
+}
+
+fn render_json(labels: &[String], rows: &[ReportRow]) -> Result<String> {
+    let entries: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            let values: Map<String, Value> = labels
+                .iter()
+                .zip(&row.values)
+                .map(|(label, value)| (label.clone(), json!(value)))
+                .collect();
+            json!({
+                "name": row.name,
+                "registry": row.registry,
+                "groupId": row.group_id,
+                "artifactId": row.artifact_id,
+                "version": row.version,
+                "transitive": row.transitive,
+                "labels": values,
+            })
+        })
+        .collect();
+    Ok(format!("{}\n", serde_json::to_string_pretty(&entries)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_formats() {
+        let labels = vec!["license".to_string(), "owner".to_string()];
+        let rows = vec![ReportRow {
+            name: "users".to_string(),
+            registry: "prod".to_string(),
+            group_id: "com.example".to_string(),
+            artifact_id: "users".to_string(),
+            version: "1.2.0".to_string(),
+            transitive: false,
+            values: vec![Some("Apache-2.0, MIT".to_string()), None],
+        }];
+        assert_eq!(
+            render_markdown(&labels, &rows),
+            "| name | registry | artifact | version | kind | license | owner |\n\
+             |---|---|---|---|---|---|---|\n\
+             | users | prod | com.example/users | 1.2.0 | direct | Apache-2.0, MIT | - |\n"
+        );
+        assert_eq!(
+            render_csv(&labels, &rows),
+            "name,registry,artifact,version,kind,license,owner\n\
+             users,prod,com.example/users,1.2.0,direct,\"Apache-2.0, MIT\",\n"
+        );
+        let json: Value = serde_json::from_str(&render_json(&labels, &rows).unwrap()).unwrap();
+        assert_eq!(json[0]["labels"]["license"], "Apache-2.0, MIT");
+        assert!(json[0]["labels"]["owner"].is_null());
+    }
+}
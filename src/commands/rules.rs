@@ -0,0 +1,234 @@
+use crate::{
+    config::{load_global_config, load_repo_config, RegistryConfig, RepoConfig},
+    identifier::resolve_artifact,
+    paths,
+    registry::{RegistryClient, RuleScope, RuleType},
+};
+use anyhow::{anyhow, Result};
+use clap::{Args, Subcommand};
+
+#[derive(Subcommand, Debug)]
+pub enum RulesCommands {
+    /// Show the rules of an artifact, with the global rules it falls back to
+    Get {
+        /// Configured dependency name or registry/group_id/artifact_id (registry optional)
+        identifier: String,
+    },
+    /// Set (or remove) a rule of an artifact
+    Set {
+        /// Configured dependency name or registry/group_id/artifact_id (registry optional)
+        identifier: String,
+        #[command(flatten)]
+        change: RuleChange,
+    },
+    /// Show the global rules, or set one with RULE and CONFIG
+    Global {
+        /// Registry to use (defaults to dependencyDefaults.registry or the only registry)
+        #[arg(long)]
+        registry: Option<String>,
+        /// validity, compatibility or integrity
+        rule: Option<RuleType>,
+        /// New configuration, e.g. BACKWARD or SYNTAX_ONLY
+        #[arg(requires = "rule")]
+        config: Option<String>,
+        /// Remove the rule instead of setting it
+        #[arg(long, requires = "rule", conflicts_with = "config")]
+        remove: bool,
+    },
+}
+
+/// A rule and its new configuration
+#[derive(Args, Debug)]
+pub struct RuleChange {
+    /// validity, compatibility or integrity
+    pub(super) rule: RuleType,
+    /// New configuration, e.g. BACKWARD or SYNTAX_ONLY
+    #[arg(required_unless_present = "remove")]
+    pub(super) config: Option<String>,
+    /// Remove the rule instead of setting it
+    #[arg(long, conflicts_with = "config")]
+    pub(super) remove: bool,
+}
+
+pub async fn run(cmd: RulesCommands) -> Result<()> {
+    let repo_cfg = load_repo_config(&paths::project_config())?;
+    let registries = repo_cfg.merge_registries(load_global_config()?)?;
+    match cmd {
+        RulesCommands::Get { identifier } => {
+            let names: Vec<String> = registries.iter().map(|r| r.name.clone()).collect();
+            let target = resolve_artifact(&identifier, &repo_cfg, &names)?;
+            let client = RegistryClient::new(find_registry(&registries, &target.registry)?)?;
+            let scope = RuleScope::Artifact {
+                group_id: &target.group_id,
+                artifact_id: &target.artifact_id,
+            };
+            let own = client.list_rules(scope).await?;
+            // Reading global rules may need admin rights; show what we can
+            let global = match client.list_rules(RuleScope::Global).await {
+                Ok(rules) => Some(rules),
+                Err(e) => {
+                    eprintln!("⚠️  Cannot read the global rules: {e}");
+                    None
+                }
+            };
+            println!(
+                "Rules of {}/{}/{}",
+                target.registry, target.group_id, target.artifact_id
+            );
+            for rule in RuleType::ALL {
+                let line = match find_config(&own, rule) {
+                    Some(config) => config.to_string(),
+                    None => match global.as_deref().and_then(|g| find_config(g, rule)) {
+                        Some(config) => format!("{config} (global)"),
+                        None => "not set".to_string(),
+                    },
+                };
+                println!("  {:<14} {line}", rule.as_str());
+            }
+        }
+        RulesCommands::Set { identifier, change } => {
+            let names: Vec<String> = registries.iter().map(|r| r.name.clone()).collect();
+            let target = resolve_artifact(&identifier, &repo_cfg, &names)?;
+            let client = RegistryClient::new(find_registry(&registries, &target.registry)?)?;
+            let scope = RuleScope::Artifact {
+                group_id: &target.group_id,
+                artifact_id: &target.artifact_id,
+            };
+            let subject = format!(
+                "{}/{}/{}",
+                target.registry, target.group_id, target.artifact_id
+            );
+            apply(&client, scope, &change, &subject).await?;
+        }
+        RulesCommands::Global {
+            registry,
+            rule,
+            config,
+            remove,
+        } => {
+            let name = default_registry(registry, &repo_cfg, &registries)?;
+            let client = RegistryClient::new(find_registry(&registries, &name)?)?;
+            match rule {
+                Some(rule) => {
+                    if config.is_none() && !remove {
+                        return Err(anyhow!("pass a CONFIG for {} or --remove", rule.as_str()));
+                    }
+                    let change = RuleChange {
+                        rule,
+                        config,
+                        remove,
+                    };
+                    let subject = format!("registry '{name}' (global)");
+                    apply(&client, RuleScope::Global, &change, &subject).await?;
+                }
+                None => {
+                    let rules = client.list_rules(RuleScope::Global).await?;
+                    println!("Global rules of registry '{name}'");
+                    for rule in RuleType::ALL {
+                        let config = find_config(&rules, rule).unwrap_or("not set");
+                        println!("  {:<14} {config}", rule.as_str());
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(super) async fn apply(
+    client: &RegistryClient,
+    scope: RuleScope<'_>,
+    change: &RuleChange,
+    subject: &str,
+) -> Result<()> {
+    let rule = change.rule;
+    match &change.config {
+        Some(config) if !change.remove => {
+            let config = normalize_config(rule, config)?;
+            client.set_rule(scope, rule, &config).await?;
+            println!("✅ {} of {subject} set to {config}", rule.as_str());
+        }
+        _ => {
+            client.delete_rule(scope, rule).await?;
+            println!("✅ Removed {} from {subject}", rule.as_str());
+        }
+    }
+    Ok(())
+}
+
+/// Uppercase `config` (`backward-transitive` → `BACKWARD_TRANSITIVE`) and check it
+/// is accepted by `rule`
+fn normalize_config(rule: RuleType, config: &str) -> Result<String> {
+    let normalized = config.to_ascii_uppercase().replace('-', "_");
+    if rule.configs().contains(&normalized.as_str()) {
+        Ok(normalized)
+    } else {
+        Err(anyhow!(
+            "'{config}' is not a {} configuration; expected one of: {}",
+            rule.as_str(),
+            rule.configs().join(", ")
+        ))
+    }
+}
+
+pub(super) fn find_config(rules: &[(RuleType, String)], rule: RuleType) -> Option<&str> {
+    rules
+        .iter()
+        .find(|(r, _)| *r == rule)
+        .map(|(_, config)| config.as_str())
+}
+
+pub(super) fn find_registry<'a>(
+    registries: &'a [RegistryConfig],
+    name: &str,
+) -> Result<&'a RegistryConfig> {
+    registries
+        .iter()
+        .find(|r| r.name == name)
+        .ok_or_else(|| anyhow!("Registry '{}' not found", name))
+}
+
+/// `--registry`, `dependencyDefaults.registry` or the only configured registry
+pub(super) fn default_registry(
+    registry: Option<String>,
+    repo_cfg: &RepoConfig,
+    registries: &[RegistryConfig],
+) -> Result<String> {
+    if let Some(registry) = registry.or_else(|| repo_cfg.dependency_defaults.registry.clone()) {
+        return Ok(registry);
+    }
+    match registries {
+        [only] => Ok(only.name.clone()),
+        _ => Err(anyhow!(
+            "Cannot tell which registry to use; pass --registry with one of: {}",
+            registries
+                .iter()
+                .map(|r| r.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_config() {
+        assert_eq!(
+            normalize_config(RuleType::Compatibility, "backward-transitive").unwrap(),
+            "BACKWARD_TRANSITIVE"
+        );
+        assert_eq!(
+            normalize_config(RuleType::Validity, "syntax_only").unwrap(),
+            "SYNTAX_ONLY"
+        );
+        let err = normalize_config(RuleType::Validity, "backward").unwrap_err();
+        assert!(err.to_string().contains("NONE, SYNTAX_ONLY, FULL"));
+        assert_eq!(
+            "Compatibility".parse::<RuleType>().unwrap(),
+            RuleType::Compatibility
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use crate::{
+    commands::OutputFormat,
+    config::{load_global_config, load_repo_config},
+    events::WarningSink,
+    identifier::{resolve_artifact, ArtifactTarget},
+    lockfile::LockFile,
+    paths,
+    registry::RegistryClient,
+};
+use anyhow::{anyhow, Result};
+use semver::Version;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+
+pub async fn run(
+    identifier: String,
+    content: bool,
+    format: OutputFormat,
+    fields: Vec<String>,
+) -> Result<()> {
+    let repo_cfg = load_repo_config(&paths::project_config())?;
+    let registries = repo_cfg.merge_registries(load_global_config()?)?;
+    let names: Vec<String> = registries.iter().map(|r| r.name.clone()).collect();
+    let target = resolve_artifact(&identifier, &repo_cfg, &names)?;
+    let registry = registries
+        .iter()
+        .find(|r| r.name == target.registry)
+        .ok_or_else(|| anyhow!("Registry '{}' not found", target.registry))?;
+    let client = RegistryClient::new(registry)?.with_events(Arc::new(WarningSink));
+    let lock = LockFile::load(&paths::project_lock()).ok();
+
+    let details = details(&client, &target, lock.as_ref(), content, format, &fields).await?;
+    match format {
+        OutputFormat::Json => {
+            let object: Map<String, Value> = details
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&object)?);
+        }
+        OutputFormat::Text => print_text(&details),
+    }
+    Ok(())
+}
+
+/// The fields `show` prints for `target`, only the given `fields` if any
+async fn details(
+    client: &RegistryClient,
+    target: &ArtifactTarget,
+    lock: Option<&LockFile>,
+    content: bool,
+    format: OutputFormat,
+    fields: &[String],
+) -> Result<Vec<(&'static str, Value)>> {
+    // Explicit version, else the locked one, else the newest published version
+    let locked = target.dependency.as_ref().and_then(|(name, _)| {
+        lock?
+            .locked_dependencies
+            .iter()
+            .find(|d| &d.name == name && !d.is_transitive)
+            .map(|d| d.resolved_version.clone())
+    });
+    let version = match target.version.clone().or(locked) {
+        Some(version) => version,
+        None => client
+            .list_versions(&target.group_id, &target.artifact_id)
+            .await?
+            .into_iter()
+            .max()
+            .map(|v: Version| v.to_string())
+            .ok_or_else(|| {
+                anyhow!(
+                    "no versions found for {}/{}",
+                    target.group_id,
+                    target.artifact_id
+                )
+            })?,
+    };
+
+    let metadata = client
+        .get_version_metadata(&target.group_id, &target.artifact_id, &version)
+        .await?;
+    let references = client
+        .get_version_references(&target.group_id, &target.artifact_id, &version, None)
+        .await?;
+    // Only download the content when it is shown; its type alone takes a HEAD request
+    let (content_type, data) = if content {
+        let (content_type, data) = client
+            .download_with_content_type(&target.group_id, &target.artifact_id, &version)
+            .await?;
+        (content_type, Some(data))
+    } else {
+        let content_type = client
+            .get_content_type(&target.group_id, &target.artifact_id, &version)
+            .await?;
+        (content_type, None)
+    };
+
+    let mut details: Vec<(&str, Value)> = vec![
+        ("registry", json!(target.registry)),
+        ("groupId", json!(target.group_id)),
+        ("artifactId", json!(target.artifact_id)),
+        ("version", json!(metadata.version)),
+        ("name", json!(metadata.name)),
+        ("description", json!(metadata.description)),
+        ("artifactType", json!(metadata.artifact_type)),
+        ("contentType", json!(content_type)),
+        ("state", json!(metadata.state)),
+        ("owner", json!(metadata.owner)),
+        ("createdOn", json!(metadata.created_on)),
+        ("globalId", json!(metadata.global_id)),
+        ("contentId", json!(metadata.content_id)),
+        ("labels", json!(metadata.labels.unwrap_or_default())),
+        ("references", serde_json::to_value(&references)?),
+    ];
+    if let Some(data) = data {
+        // Binary content is base64-encoded in JSON and only sized in text
+        match (std::str::from_utf8(&data), format) {
+            (Ok(text), _) => details.push(("content", json!(text))),
+            (Err(_), OutputFormat::Json) => {
+                details.push(("content", json!(base64::encode(&data))));
+                details.push(("contentEncoding", json!("base64")));
+            }
+            (Err(_), OutputFormat::Text) => details.push((
+                "content",
+                json!(format!("(binary content, {} bytes)", data.len())),
+            )),
+        }
+    }
+    if !fields.is_empty() {
+        if let Some(unknown) = fields.iter().find(|f| !details.iter().any(|(k, _)| k == f)) {
+            return Err(anyhow!(
+                "unknown field '{}'. Available fields: {}",
+                unknown,
+                details
+                    .iter()
+                    .map(|(k, _)| *k)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        details.retain(|(k, _)| fields.iter().any(|f| f == k));
+    }
+    Ok(details)
+}
+
+fn print_text(details: &[(&str, Value)]) {
+    for (key, value) in details {
+        match value {
+            Value::Null => println!("{key}: -"),
+            Value::String(s) if *key == "content" => println!("{key}:\n{s}"),
+            Value::String(s) => println!("{key}: {s}"),
+            Value::Object(map) if map.is_empty() => println!("{key}: (none)"),
+            Value::Object(map) => {
+                println!("{key}:");
+                for (k, v) in map {
+                    println!("  {k}={}", v.as_str().unwrap_or(&v.to_string()));
+                }
+            }
+            Value::Array(items) if items.is_empty() => println!("{key}: (none)"),
+            Value::Array(items) => {
+                println!("{key}:");
+                for item in items {
+                    let field = |name: &str| item.get(name).and_then(Value::as_str).unwrap_or("-");
+                    println!(
+                        "  - {}/{}@{} (as {})",
+                        field("groupId"),
+                        field("artifactId"),
+                        field("version"),
+                        field("name")
+                    );
+                }
+            }
+            other => println!("{key}: {other}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::RepoConfig, dev_server::DevServer, lockfile::LockedDependency};
+
+    fn keys(details: &[(&str, Value)]) -> Vec<String> {
+        details.iter().map(|(k, _)| k.to_string()).collect()
+    }
+
+    fn field<'a>(details: &'a [(&str, Value)], key: &str) -> &'a Value {
+        &details.iter().find(|(k, _)| *k == key).unwrap().1
+    }
+
+    #[tokio::test]
+    async fn test_show_details() {
+        let server = DevServer::start("127.0.0.1:0").await.unwrap();
+        server.add_version("com.example", "users", "PROTOBUF", "1.0.0", "users v1");
+        server.add_version("com.example", "users", "PROTOBUF", "1.1.0", "users v1.1");
+        server.add_version(
+            "com.example",
+            "blob",
+            "AVRO",
+            "1.0.0",
+            vec![0xff, 0xfe, 0, 1],
+        );
+        let client = RegistryClient::new(&server.registry_config("dev")).unwrap();
+        let repo_cfg: RepoConfig = serde_yaml::from_str(
+            "dependencies:\n  - name: users\n    groupId: com.example\n    \
+             artifactId: users\n    version: ^1.0.0\n    registry: dev\n",
+        )
+        .unwrap();
+        let registries = ["dev".to_string()];
+        let target = |identifier: &str| resolve_artifact(identifier, &repo_cfg, &registries);
+        let lock = LockFile::new(
+            vec![LockedDependency {
+                name: "users".to_string(),
+                registry: "dev".to_string(),
+                resolved_version: "1.0.0".to_string(),
+                download_url: String::new(),
+                registry_url: None,
+                sha256: String::new(),
+                output_path: "protos/users.proto".to_string(),
+                group_id: "com.example".to_string(),
+                artifact_id: "users".to_string(),
+                version_spec: "^1.0.0".to_string(),
+                is_transitive: false,
+                references: Vec::new(),
+                reference_names: Default::default(),
+                binary: false,
+                avro_fingerprint: None,
+                content_type: None,
+                member_of: None,
+            }],
+            "hash".to_string(),
+        );
+        let version = |details: &[(&str, Value)]| field(details, "version").clone();
+        let text = OutputFormat::Text;
+
+        // Explicit version, else the locked one, else the newest
+        let users = target("users").unwrap();
+        let newest = details(&client, &users, None, false, text, &[])
+            .await
+            .unwrap();
+        assert_eq!(version(&newest), "1.1.0");
+        let locked = details(&client, &users, Some(&lock), false, text, &[])
+            .await
+            .unwrap();
+        assert_eq!(version(&locked), "1.0.0");
+        let explicit = target("users@1.1.0").unwrap();
+        let pinned = details(&client, &explicit, Some(&lock), true, text, &[])
+            .await
+            .unwrap();
+        assert_eq!(version(&pinned), "1.1.0");
+        assert_eq!(field(&pinned, "content"), "users v1.1");
+        assert!(!keys(&locked).contains(&"content".to_string()));
+
+        let fields = ["labels".to_string(), "version".to_string()];
+        let selected = details(&client, &users, Some(&lock), false, text, &fields)
+            .await
+            .unwrap();
+        assert_eq!(keys(&selected), ["version", "labels"]);
+        let err = details(&client, &users, None, false, text, &["nope".to_string()])
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("unknown field 'nope'. Available fields: registry,"),
+            "{err}"
+        );
+
+        let blob = target("dev/com.example/blob").unwrap();
+        let json = details(&client, &blob, None, true, OutputFormat::Json, &[])
+            .await
+            .unwrap();
+        assert_eq!(field(&json, "content"), "//4AAQ==");
+        assert_eq!(field(&json, "contentEncoding"), "base64");
+        let sized = details(&client, &blob, None, true, text, &[])
+            .await
+            .unwrap();
+        assert_eq!(field(&sized, "content"), "(binary content, 4 bytes)");
+        assert!(!keys(&sized).contains(&"contentEncoding".to_string()));
+    }
+}
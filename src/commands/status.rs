@@ -1,57 +1,144 @@
 use crate::{
-    config::{load_global_config, load_repo_config},
-    constants::{APICURIO_CONFIG, APICURIO_LOCK},
-    dependency::Dependency,
-    lockfile::LockFile,
-    registry::RegistryClient,
+    api::{DependencyStatus, StatusReport, Workspace},
+    config::UpdateSeverity,
+    events::ConsoleSink,
+    notify::{self, Notice, NoticeItem},
 };
 use anyhow::Result;
-use semver::Version;
-use std::{collections::HashMap, path::PathBuf};
-
-pub async fn run() -> Result<()> {
-    let repo_cfg = load_repo_config(&PathBuf::from(APICURIO_CONFIG))?;
-    let global_cfg = load_global_config()?;
-    let regs = repo_cfg.merge_registries(global_cfg)?;
-    let mut clients = HashMap::new();
-    for r in &regs {
-        clients.insert(r.name.clone(), RegistryClient::new(r)?);
+use std::sync::Arc;
+
+/// Extra checks `status` can run beyond comparing direct dependencies
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StatusOptions {
+    /// Also compare transitive lock entries with the latest registry version
+    pub transitive: bool,
+    /// Flag locked versions that were deleted, disabled or changed in the registry
+    pub drift: bool,
+    /// Compare pulled files with the registry content of their locked version
+    pub content: bool,
+    /// Least severe update that fails `status`, overriding `staleness.failOn`
+    pub fail_on: Option<UpdateSeverity>,
+}
+
+pub async fn run(opts: StatusOptions, notify: bool) -> Result<()> {
+    let workspace = Workspace::current()?.with_events(Arc::new(ConsoleSink));
+    let report = workspace.status(opts).await?;
+
+    for status in &report.dependencies {
+        let DependencyStatus {
+            name,
+            locked,
+            latest,
+            ..
+        } = status;
+        let latest = latest.as_ref().map(ToString::to_string).unwrap_or_default();
+        let violation = report.violations.iter().find(|v| &v.name == name);
+        match locked {
+            Some(locked) if status.is_outdated() => match violation {
+                Some(violation) => println!(
+                    "🔴 {name}: locked={locked} latest={latest} ({})",
+                    violation.reason
+                ),
+                None => println!(
+                    "🟡 {name}: locked={locked} latest={latest} (allowed by staleness policy)"
+                ),
+            },
+            Some(locked) => println!("✔️  {name} up-to-date ({locked})"),
+            None => println!("⚪ {name} not pulled yet (latest={latest})"),
+        }
+    }
+
+    // Transitive versions are pinned by their parents' references, so a newer
+    // version is informational and does not make the lock outdated
+    for status in &report.transitive {
+        let Some(locked) = &status.locked else {
+            continue;
+        };
+        if workspace.client(&status.registry).is_none() {
+            println!(
+                "⚠️  {}: unknown registry '{}'",
+                status.name, status.registry
+            );
+        } else if status.is_outdated() {
+            println!(
+                "🟡 {} (transitive): locked={} latest={}",
+                status.name,
+                locked,
+                status.latest.as_ref().unwrap()
+            );
+        } else {
+            println!("✔️  {} (transitive) up-to-date ({})", status.name, locked);
+        }
+    }
+
+    match &report.drift {
+        Some(drift) if drift.is_empty() => {
+            println!("✔️  No drift between lock file and registries")
+        }
+        Some(drift) => {
+            for d in drift {
+                println!("🟠 {} {}: {}", d.name, d.version, d.problem);
+            }
+        }
+        None => {}
     }
 
-    let lock = LockFile::load(&PathBuf::from(APICURIO_LOCK)).ok();
-    let mut any_outdated = false;
-
-    for dep_cfg in &repo_cfg.dependencies {
-        let dep = Dependency::from_config_with_defaults(dep_cfg, &repo_cfg.dependency_defaults)?;
-        let client = &clients[&dep.registry];
-        let versions = client
-            .list_versions(&dep.group_id, &dep.artifact_id)
-            .await?;
-        let latest = versions
-            .into_iter()
-            .filter(|v| dep.req.matches(v))
-            .max()
-            .ok_or_else(|| anyhow::anyhow!("no matching version for {}", dep.name))?;
-
-        if let Some(lf) = &lock {
-            if let Some(ld) = lf.locked_dependencies.iter().find(|d| d.name == dep.name) {
-                let locked_ver = Version::parse(&ld.resolved_version)?;
-                if locked_ver < latest {
-                    println!("🔴 {}: locked={} latest={}", dep.name, locked_ver, latest);
-                    any_outdated = true;
-                } else {
-                    println!("✔️  {} up-to-date ({})", dep.name, locked_ver);
-                }
-                continue;
+    match &report.local_changes {
+        Some(changes) if changes.is_empty() => {
+            println!("✔️  Pulled files match the registry content")
+        }
+        Some(changes) => {
+            for c in changes {
+                println!("✏️  {} ({}): {}", c.name, c.output_path, c.problem);
             }
         }
+        None => {}
+    }
 
-        println!("⚪ {} not pulled yet (latest={})", dep.name, latest);
-        any_outdated = true;
+    if notify {
+        notify::send(
+            &workspace.config().notifications,
+            &notice(&workspace, &report),
+        )
+        .await?;
+        println!("📣 Sent the status summary");
     }
 
-    if any_outdated {
+    if report.is_outdated() {
         std::process::exit(1);
     }
     Ok(())
 }
+
+/// Digest of the outdated direct dependencies for `--notify`
+fn notice(workspace: &Workspace, report: &StatusReport) -> Notice {
+    let items: Vec<NoticeItem> = report
+        .dependencies
+        .iter()
+        .filter(|status| status.is_outdated())
+        .map(|status| {
+            let violation = report.violations.iter().find(|v| v.name == status.name);
+            NoticeItem {
+                name: status.name.clone(),
+                from: status.locked.as_ref().map(ToString::to_string),
+                to: status.latest.as_ref().map(ToString::to_string),
+                note: Some(match violation {
+                    Some(violation) => violation.reason.clone(),
+                    None => "allowed by staleness policy".to_string(),
+                }),
+            }
+        })
+        .collect();
+    let title = match items.len() {
+        0 => format!(
+            "All {} dependencies are up-to-date",
+            report.dependencies.len()
+        ),
+        n => format!(
+            "{n} of {} dependencies are outdated ({} failing)",
+            report.dependencies.len(),
+            report.violations.len()
+        ),
+    };
+    Notice::new("status", workspace.root(), title, items)
+}
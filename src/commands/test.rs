@@ -0,0 +1,38 @@
+use crate::api::Workspace;
+use crate::constants::APICURIO_CONFIG;
+use anyhow::{bail, Result};
+
+/// Validate publishes against the registry rules without creating versions
+pub async fn run(name: Option<String>) -> Result<()> {
+    let workspace = Workspace::current()?;
+    if workspace.config().publishes.is_empty() {
+        println!("No publishes configured in {APICURIO_CONFIG}");
+        return Ok(());
+    }
+
+    let tests = workspace.test_publishes(name.as_deref()).await?;
+    let mut failed = 0;
+    for test in &tests {
+        let target = format!("{}/{}/{}", test.registry, test.group_id, test.artifact_id);
+        if test.violations.is_empty() {
+            println!("✅ {}: accepted by {target}", test.name);
+            continue;
+        }
+        failed += 1;
+        println!(
+            "❌ {}: {} rule violation(s) in {target}",
+            test.name,
+            test.violations.len()
+        );
+        for violation in &test.violations {
+            match violation.context.as_deref().filter(|c| !c.is_empty()) {
+                Some(context) => println!("   - {context}: {}", violation.description),
+                None => println!("   - {}", violation.description),
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{failed} of {} publish(es) would be rejected", tests.len());
+    }
+    Ok(())
+}
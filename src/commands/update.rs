@@ -1,94 +1,534 @@
-use anyhow::Result;
-use std::{collections::HashMap, fs, path::PathBuf};
+use anyhow::{anyhow, bail, Context, Result};
+use dialoguer::MultiSelect;
+use semver::Version;
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    io::IsTerminal,
+    path::Path,
+};
 
 use crate::{
-    config::{load_global_config, load_repo_config},
-    constants::{APICURIO_CONFIG, APICURIO_LOCK},
-    dependency::Dependency,
+    api::{DependencyStatus, LockOptions, StatusOptions, Workspace},
+    commands::OutputFormat,
+    config::{DependencyConfig, RepoConfig},
+    constants::APICURIO_LOCK,
+    identifier::find_dependency,
+    lint::SchemaKind,
     lockfile::{LockFile, LockedDependency},
-    output_path,
+    notify::{self, Notice, NoticeItem},
     registry::RegistryClient,
+    schema_diff::{self, Change},
+    summary::{RunSummary, SummarySink},
 };
-use sha2::{Digest, Sha256};
-
-pub async fn run() -> Result<()> {
-    // load configs
-    let repo_cfg = load_repo_config(&PathBuf::from(APICURIO_CONFIG))?;
-    let global_cfg = load_global_config()?;
-    let regs = repo_cfg.merge_registries(global_cfg)?;
-
-    // build clients
-    let mut clients = HashMap::new();
-    for r in &regs {
-        clients.insert(r.name.clone(), RegistryClient::new(r)?);
-    }
-
-    let mut locked: Vec<LockedDependency> = Vec::new();
-    // re-resolve every semver range, download, re-lock
-    for dep_cfg in &repo_cfg.dependencies {
-        let dep = Dependency::from_config_with_defaults(dep_cfg, &repo_cfg.dependency_defaults)?;
-        let client = &clients[&dep.registry];
-        let versions = client
-            .list_versions(&dep.group_id, &dep.artifact_id)
-            .await?;
-        let selected = versions
+
+/// How `update` runs and reports what it changed
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UpdateOptions {
+    /// Pick the dependencies to update from the outdated ones
+    pub interactive: bool,
+    /// Print the resolution delta without writing files or the lock
+    pub dry_run: bool,
+    /// Format of the resolution delta
+    pub format: OutputFormat,
+    /// Post the resolution delta to the configured notifications
+    pub notify: bool,
+}
+
+pub async fn run(identifier: Option<String>, opts: UpdateOptions) -> Result<()> {
+    let sink = SummarySink::for_format(opts.format);
+    let workspace = Workspace::current()?.with_events(sink.clone());
+
+    if let Some(identifier) = identifier {
+        let name = find_dependency(&identifier, &workspace.config().dependencies)?
+            .name
+            .clone();
+        return update_one(&workspace, &sink, &name, opts).await;
+    }
+    if opts.interactive {
+        return update_interactive(&workspace).await;
+    }
+
+    let mut delta = update_all(&workspace, opts.dry_run).await?;
+    if opts.notify {
+        notify_delta(workspace.config(), &delta, opts).await?;
+    }
+    delta.summary = Some(sink.summary_with(delta.locked, (&delta).into()));
+    delta.print(opts.format, opts.dry_run)?;
+    if opts.format == OutputFormat::Text && !opts.dry_run {
+        println!("✅ update complete");
+    }
+    Ok(())
+}
+
+/// Re-resolve every dependency to the newest version in its range and, unless
+/// `dry_run`, pull the result; returns the version changes
+pub(crate) async fn update_all(workspace: &Workspace, dry_run: bool) -> Result<UpdateDelta> {
+    let previous = LockFile::load(&workspace.lock_path())
+        .map(|lf| lf.locked_dependencies)
+        .unwrap_or_default();
+    if dry_run {
+        let locked = workspace.resolve_reusing(&previous).await?;
+        return Ok(UpdateDelta::between(&previous, &locked));
+    }
+    let report = workspace.pull(None, update_options()).await?;
+    Ok(UpdateDelta::between(
+        &previous,
+        &report.lock.lock_file().locked_dependencies,
+    ))
+}
+
+/// Lock options re-resolving even an up-to-date lock
+fn update_options() -> LockOptions {
+    LockOptions {
+        update: true,
+        ..Default::default()
+    }
+}
+
+/// Re-resolve a single direct dependency (plus its references) and replace
+/// only its entries in the existing lockfile
+async fn update_one(
+    workspace: &Workspace,
+    sink: &SummarySink,
+    name: &str,
+    opts: UpdateOptions,
+) -> Result<()> {
+    let lock_path = workspace.lock_path();
+    let lock = LockFile::load(&lock_path).with_context(|| {
+        format!(
+            "reading {}; run `apicurio lock` before updating a single dependency",
+            lock_path.display()
+        )
+    })?;
+
+    let updated = if opts.dry_run {
+        workspace.relock_one(name, &lock, false).await?.0
+    } else {
+        workspace
+            .pull(Some(name), update_options())
+            .await?
+            .lock
+            .lock_file()
+            .clone()
+    };
+    let mut delta = UpdateDelta::between(&lock.locked_dependencies, &updated.locked_dependencies);
+    if opts.notify {
+        notify_delta(workspace.config(), &delta, opts).await?;
+    }
+    delta.summary = Some(sink.summary_with(delta.locked, (&delta).into()));
+    delta.print(opts.format, opts.dry_run)?;
+    if opts.format == OutputFormat::Text && !opts.dry_run {
+        println!("✅ updated {name}");
+    }
+    Ok(())
+}
+
+/// A lock entry added, removed or moved to another version by an update
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeltaEntry {
+    pub name: String,
+    pub registry: String,
+    pub group_id: String,
+    pub artifact_id: String,
+    pub transitive: bool,
+    /// Locked version before the update, `None` for added entries
+    pub from: Option<String>,
+    /// Locked version after the update, `None` for removed entries
+    pub to: Option<String>,
+}
+
+/// Resolution delta between the current and the updated lock entries
+#[derive(Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateDelta {
+    pub dry_run: bool,
+    pub updated: Vec<DeltaEntry>,
+    pub added: Vec<DeltaEntry>,
+    pub removed: Vec<DeltaEntry>,
+    /// Counts and timing of the run, printed after the changes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<RunSummary>,
+    /// Lock entries after the update
+    #[serde(skip)]
+    pub locked: usize,
+}
+
+impl UpdateDelta {
+    /// Compare lock entries by [`LockedDependency::entry_key`]
+    pub(crate) fn between(old: &[LockedDependency], new: &[LockedDependency]) -> Self {
+        let entry = |d: &LockedDependency, from: Option<&LockedDependency>| DeltaEntry {
+            name: d.name.clone(),
+            registry: d.registry.clone(),
+            group_id: d.group_id.clone(),
+            artifact_id: d.artifact_id.clone(),
+            transitive: d.is_transitive,
+            from: from.map(|f| f.resolved_version.clone()),
+            to: None,
+        };
+        let old_by_key: HashMap<String, &LockedDependency> =
+            old.iter().map(|d| (d.entry_key(), d)).collect();
+        let new_keys: HashSet<String> = new.iter().map(LockedDependency::entry_key).collect();
+        let mut delta = UpdateDelta {
+            locked: new.len(),
+            ..Default::default()
+        };
+        for dep in new {
+            let to = Some(dep.resolved_version.clone());
+            match old_by_key.get(&dep.entry_key()) {
+                Some(prev) if prev.resolved_version == dep.resolved_version => {}
+                Some(prev) => delta.updated.push(DeltaEntry {
+                    to,
+                    ..entry(dep, Some(prev))
+                }),
+                None => delta.added.push(DeltaEntry {
+                    to,
+                    ..entry(dep, None)
+                }),
+            }
+        }
+        for dep in old.iter().filter(|d| !new_keys.contains(&d.entry_key())) {
+            delta.removed.push(entry(dep, Some(dep)));
+        }
+        delta
+    }
+
+    fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+
+    /// Summary for `--notify`; with `dry_run` the changes were not applied
+    fn notice(&self, dry_run: bool) -> Notice {
+        let item = |e: &DeltaEntry, note: Option<&str>| NoticeItem {
+            name: e.name.clone(),
+            from: e.from.clone(),
+            to: e.to.clone(),
+            note: match (e.transitive, note) {
+                (true, Some(note)) => Some(format!("transitive, {note}")),
+                (true, None) => Some("transitive".to_string()),
+                (false, note) => note.map(str::to_string),
+            },
+        };
+        let items: Vec<NoticeItem> = self
+            .updated
             .iter()
-            .filter(|v| dep.req.matches(v))
-            .max()
-            .ok_or_else(|| anyhow::anyhow!("no matching version for {}", dep.name))?;
-        let metadata = client
-            .get_artifact_metadata(&dep.group_id, &dep.artifact_id)
-            .await?;
-        let output_path = dep.output_path.clone().unwrap_or_else(|| {
-            let pattern = repo_cfg
-                .dependency_defaults
-                .output_patterns
-                .resolve(&metadata.artifact_type, None);
-            output_path::generate_output_path(
-                &pattern,
-                &dep.group_id,
-                &dep.artifact_id,
-                &selected.to_string(),
-                &metadata.artifact_type,
-            )
-        });
-
-        let data = client
-            .download(&dep.group_id, &dep.artifact_id, selected)
-            .await?;
-        let file_path = PathBuf::from(&output_path);
-        if let Some(parent) = file_path.parent() {
-            fs::create_dir_all(parent)?;
+            .map(|e| item(e, None))
+            .chain(self.added.iter().map(|e| item(e, Some("added"))))
+            .chain(self.removed.iter().map(|e| item(e, Some("removed"))))
+            .collect();
+        let title = match (items.len(), dry_run) {
+            (0, _) => "No version changes".to_string(),
+            (n, false) => format!("{n} lock entries changed"),
+            (n, true) => format!("{n} lock entries would change (dry run)"),
+        };
+        Notice::new("update", Path::new("."), title, items)
+    }
+
+    /// Print the delta; with `dry_run` the lock file was left unchanged
+    fn print(mut self, format: OutputFormat, dry_run: bool) -> Result<()> {
+        self.dry_run = dry_run;
+        if format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&self)?);
+            return Ok(());
         }
-        fs::write(&file_path, &data)?;
-        let sha = {
-            let mut h = Sha256::new();
-            h.update(&data);
-            hex::encode(h.finalize())
+        if self.is_empty() {
+            println!("✔️  No version changes");
+            if let Some(summary) = &self.summary {
+                summary.print();
+            }
+            return Ok(());
+        }
+        let label = |e: &DeltaEntry| {
+            if e.transitive {
+                format!("{} (transitive)", e.name)
+            } else {
+                e.name.clone()
+            }
         };
-        locked.push(LockedDependency {
-            name: dep.name.clone(),
-            registry: dep.registry.clone(),
-            resolved_version: selected.to_string(),
-            download_url: client.get_download_url(&dep.group_id, &dep.artifact_id, selected),
-            sha256: sha,
-            output_path,
-            group_id: dep.group_id.clone(),
-            artifact_id: dep.artifact_id.clone(),
-            version_spec: dep_cfg.version.clone(),
-            is_transitive: false,
-        });
+        for e in &self.updated {
+            let (from, to) = (e.from.as_deref().unwrap(), e.to.as_deref().unwrap());
+            println!("⬆️  {}: {from} → {to}", label(e));
+        }
+        for e in &self.added {
+            println!("➕ {}: {}", label(e), e.to.as_deref().unwrap());
+        }
+        for e in &self.removed {
+            println!("➖ {}: {}", label(e), e.from.as_deref().unwrap());
+        }
+        if dry_run {
+            println!("Dry run: no files were written and {APICURIO_LOCK} is unchanged");
+        }
+        if let Some(summary) = &self.summary {
+            summary.print();
+        }
+        Ok(())
     }
+}
 
-    // save new lockfile with config modification time
-    let lock_path = PathBuf::from(APICURIO_LOCK);
-    let config_path = PathBuf::from(APICURIO_CONFIG);
-    let config_content = std::fs::read_to_string(&config_path)?;
-    let config_hash = LockFile::compute_config_hash(&config_content, &repo_cfg.dependencies);
-    let config_modified = LockFile::get_config_modification_time(&config_path).ok();
-    let lf = LockFile::with_config_modified(locked, config_hash, config_modified);
-    lf.save(&lock_path)?;
+/// Post the version changes of an update (`--notify`)
+async fn notify_delta(
+    repo_cfg: &RepoConfig,
+    delta: &UpdateDelta,
+    opts: UpdateOptions,
+) -> Result<()> {
+    notify::send(&repo_cfg.notifications, &delta.notice(opts.dry_run)).await?;
+    if opts.format == OutputFormat::Text {
+        println!("📣 Sent the update summary");
+    }
+    Ok(())
+}
+
+/// An outdated lock entry offered by `update --interactive`
+#[derive(Debug)]
+struct UpdateCandidate<'a> {
+    entry: &'a LockedDependency,
+    locked: Version,
+    latest: Version,
+    /// Configured dependency to re-resolve: the entry itself, or the group
+    /// dependency it was expanded from
+    target: String,
+}
+
+/// Locked direct dependencies with a newer version in their range
+fn update_candidates<'a>(
+    statuses: &[DependencyStatus],
+    lock: &'a LockFile,
+    configured: &[DependencyConfig],
+) -> Vec<UpdateCandidate<'a>> {
+    statuses
+        .iter()
+        .filter(|status| status.is_outdated())
+        .filter_map(|status| {
+            let (locked, latest) = (status.locked.clone()?, status.latest.clone()?);
+            let entry = lock
+                .locked_dependencies
+                .iter()
+                .find(|d| d.name == status.name && !d.is_transitive)?;
+            let target = if configured.iter().any(|d| d.name == status.name) {
+                status.name.clone()
+            } else {
+                entry.member_of.clone()?
+            };
+            Some(UpdateCandidate {
+                entry,
+                locked,
+                latest,
+                target,
+            })
+        })
+        .collect()
+}
+
+/// Preview the schema changes of every outdated dependency and update the
+/// ones the user picks, leaving the other lock entries untouched
+async fn update_interactive(workspace: &Workspace) -> Result<()> {
+    if !std::io::stdin().is_terminal() {
+        bail!("--interactive needs a terminal; pass a dependency name to update a single one");
+    }
+    let lock = LockFile::load(&workspace.lock_path())
+        .with_context(|| format!("reading {APICURIO_LOCK}; run `apicurio lock` first"))?;
+    let report = workspace.status(StatusOptions::default()).await?;
+    let candidates = update_candidates(
+        &report.dependencies,
+        &lock,
+        &workspace.config().dependencies,
+    );
+    if candidates.is_empty() {
+        println!("✔️  All locked dependencies are up-to-date");
+        return Ok(());
+    }
+
+    let mut items = Vec::new();
+    for candidate in &candidates {
+        let entry = candidate.entry;
+        let client = workspace
+            .client(&entry.registry)
+            .ok_or_else(|| anyhow!("Registry '{}' not found", entry.registry))?;
+        let changes = schema_changes(
+            client,
+            &entry.group_id,
+            &entry.artifact_id,
+            &candidate.locked.to_string(),
+            &candidate.latest.to_string(),
+        )
+        .await?;
+        println!(
+            "🔴 {} {} → {}",
+            entry.name, candidate.locked, candidate.latest
+        );
+        for change in &changes {
+            println!("   - {change}");
+        }
+        let summary = match changes.len() {
+            0 => "no schema changes".to_string(),
+            n => format!("{n} schema change(s)"),
+        };
+        let via = if candidate.target == entry.name {
+            String::new()
+        } else {
+            format!(", with group '{}'", candidate.target)
+        };
+        items.push(format!(
+            "{} {} → {} ({summary}{via})",
+            entry.name, candidate.locked, candidate.latest
+        ));
+    }
+    println!();
 
-    println!("✅ update complete");
+    let selected = MultiSelect::new()
+        .with_prompt("Select the dependencies to update (space toggles, enter confirms)")
+        .items(&items)
+        .interact()?;
+    let mut targets: Vec<&str> = Vec::new();
+    for index in selected {
+        let target = candidates[index].target.as_str();
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    if targets.is_empty() {
+        println!("Nothing selected; the lock file is unchanged");
+        return Ok(());
+    }
+    for target in targets {
+        let opts = UpdateOptions::default();
+        let sink = SummarySink::for_format(opts.format);
+        let workspace = Workspace::current()?.with_events(sink.clone());
+        update_one(&workspace, &sink, target, opts).await?;
+    }
     Ok(())
 }
+
+/// Structural changes between two versions of an artifact, from the registry content
+pub(crate) async fn schema_changes(
+    client: &RegistryClient,
+    group_id: &str,
+    artifact_id: &str,
+    from: &str,
+    to: &str,
+) -> Result<Vec<Change>> {
+    let metadata = client
+        .get_version_metadata(group_id, artifact_id, &to)
+        .await?;
+    let old = client
+        .get_version_content(group_id, artifact_id, from)
+        .await?;
+    let new = client
+        .get_version_content(group_id, artifact_id, to)
+        .await?;
+    let kind = SchemaKind::from_artifact_type(&metadata.artifact_type);
+    Ok(schema_diff::diff(kind, &old, &new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked(name: &str, version: &str, member_of: Option<&str>) -> LockedDependency {
+        LockedDependency {
+            name: name.to_string(),
+            registry: "prod".to_string(),
+            resolved_version: version.to_string(),
+            download_url: String::new(),
+            registry_url: None,
+            sha256: String::new(),
+            output_path: format!("protos/{name}.proto"),
+            group_id: "com.example".to_string(),
+            artifact_id: name.rsplit('/').next().unwrap().to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: member_of.map(str::to_string),
+        }
+    }
+
+    fn status(name: &str, locked: &str, latest: &str) -> DependencyStatus {
+        DependencyStatus {
+            name: name.to_string(),
+            registry: "prod".to_string(),
+            locked: Some(Version::parse(locked).unwrap()),
+            latest: Some(Version::parse(latest).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_update_candidates() {
+        let lock = LockFile::new(
+            vec![
+                locked("users", "1.0.0", None),
+                locked("orders", "1.1.0", None),
+                locked("events/billing", "1.0.0", Some("events")),
+            ],
+            "hash".to_string(),
+        );
+        let configured = vec![
+            DependencyConfig {
+                name: "users".to_string(),
+                ..Default::default()
+            },
+            DependencyConfig {
+                name: "orders".to_string(),
+                ..Default::default()
+            },
+        ];
+        let statuses = vec![
+            status("users", "1.0.0", "1.2.0"),
+            status("orders", "1.1.0", "1.1.0"),
+            status("events/billing", "1.0.0", "1.0.1"),
+        ];
+        let candidates = update_candidates(&statuses, &lock, &configured);
+        let summary: Vec<(&str, String, &str)> = candidates
+            .iter()
+            .map(|c| {
+                (
+                    c.entry.name.as_str(),
+                    c.latest.to_string(),
+                    c.target.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("users", "1.2.0".to_string(), "users"),
+                ("events/billing", "1.0.1".to_string(), "events"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_delta() {
+        let mut referenced = locked("common", "1.0.0", None);
+        referenced.is_transitive = true;
+        let old = vec![
+            locked("users", "1.0.0", None),
+            locked("orders", "1.1.0", None),
+            referenced.clone(),
+        ];
+        let mut new_common = locked("common-types", "2.0.0", None);
+        new_common.is_transitive = true;
+        let new = vec![
+            locked("users", "1.2.0", None),
+            locked("orders", "1.1.0", None),
+            new_common,
+        ];
+        let delta = UpdateDelta::between(&old, &new);
+        assert_eq!(delta.updated.len(), 1);
+        assert_eq!(delta.updated[0].name, "users");
+        assert_eq!(delta.updated[0].from.as_deref(), Some("1.0.0"));
+        assert_eq!(delta.updated[0].to.as_deref(), Some("1.2.0"));
+        assert_eq!(delta.added[0].artifact_id, "common-types");
+        assert!(delta.added[0].from.is_none());
+        assert_eq!(delta.removed[0].artifact_id, "common");
+        assert!(delta.removed[0].to.is_none());
+
+        let json = serde_json::to_value(&delta).unwrap();
+        assert_eq!(json["updated"][0]["groupId"], "com.example");
+        assert!(UpdateDelta::between(&new, &new).is_empty());
+    }
+}
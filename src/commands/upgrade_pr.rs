@@ -0,0 +1,258 @@
+use crate::{
+    api::Workspace,
+    commands::{
+        ci_setup::CiProvider,
+        update::{self, DeltaEntry, UpdateDelta},
+    },
+    events::ConsoleSink,
+    lockfile::LockFile,
+    paths,
+};
+use anyhow::{bail, Context, Result};
+use std::{fs, process::Command, sync::Arc};
+
+/// Options of `apicurio upgrade-pr`
+#[derive(Debug, Default, Clone)]
+pub struct UpgradePrOptions {
+    /// Branch to commit to (default: `apicurio/upgrade-<date>`)
+    pub branch: Option<String>,
+    /// Branch the pull request targets (default: the forge's default branch)
+    pub base: Option<String>,
+    /// Push the branch and open a pull/merge request with `gh` or `glab`
+    pub open: Option<CiProvider>,
+    /// Also write the markdown summary to this file
+    pub summary: Option<String>,
+}
+
+/// Update every dependency, commit the changes on a new branch and optionally
+/// open a pull request describing the bumps
+pub async fn run(opts: UpgradePrOptions) -> Result<()> {
+    let workspace = Workspace::current()?.with_events(Arc::new(ConsoleSink));
+    let previous = LockFile::load(&workspace.lock_path())
+        .map(|lf| lf.locked_dependencies)
+        .unwrap_or_default();
+    let delta = update::update_all(&workspace, false).await?;
+    if delta.updated.is_empty() && delta.added.is_empty() && delta.removed.is_empty() {
+        println!("✔️  All dependencies are up-to-date; no pull request needed");
+        return Ok(());
+    }
+
+    // Schema changes of every bumped entry, for the pull request body
+    let mut changes = Vec::new();
+    for entry in &delta.updated {
+        let lines = match (workspace.client(&entry.registry), &entry.from, &entry.to) {
+            (Some(client), Some(from), Some(to)) => {
+                update::schema_changes(client, &entry.group_id, &entry.artifact_id, from, to)
+                    .await
+                    .with_context(|| format!("comparing {} {from} and {to}", entry.name))?
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        changes.push(lines);
+    }
+    let title = title(&delta);
+    let body = render_summary(&delta, &changes);
+    if let Some(path) = &opts.summary {
+        fs::write(path, &body).with_context(|| format!("writing {path}"))?;
+    }
+
+    let branch = opts.branch.clone().unwrap_or_else(|| {
+        format!(
+            "apicurio/upgrade-{}",
+            chrono::Utc::now().format("%Y%m%d-%H%M")
+        )
+    });
+    let lock_path = paths::project_lock();
+    let mut candidates = vec![
+        paths::project_config().to_string_lossy().into_owned(),
+        lock_path.to_string_lossy().into_owned(),
+        ".gitignore".to_string(),
+    ];
+    candidates.extend(previous.iter().map(|d| d.output_path.clone()));
+    if let Ok(lock) = LockFile::load(&lock_path) {
+        candidates.extend(lock.locked_dependencies.into_iter().map(|d| d.output_path));
+    }
+    let mut status = vec!["status", "--porcelain", "--untracked-files=all", "--"];
+    status.extend(candidates.iter().map(String::as_str));
+    let changed = changed_paths(&git(&status)?);
+
+    // Porcelain paths are relative to the top of the work tree
+    let top = git(&["rev-parse", "--show-toplevel"])?;
+    git(&["checkout", "-b", &branch])?;
+    let mut add = vec!["-C", top.trim(), "add", "-A", "--"];
+    add.extend(changed.iter().map(String::as_str));
+    git(&add)?;
+    git(&["commit", "-m", &title, "-m", &body])?;
+    println!("✅ Committed {} file(s) on branch {branch}", changed.len());
+
+    match opts.open {
+        None => {
+            if opts.summary.is_none() {
+                print!("{body}");
+            }
+        }
+        Some(provider) => {
+            git(&["push", "-u", "origin", &branch])?;
+            let mut args: Vec<&str> = Vec::new();
+            let program = match provider {
+                CiProvider::Github => {
+                    args.extend(["pr", "create", "--head", &branch]);
+                    args.extend(["--title", &title, "--body", &body]);
+                    if let Some(base) = &opts.base {
+                        args.extend(["--base", base]);
+                    }
+                    "gh"
+                }
+                CiProvider::Gitlab => {
+                    args.extend(["mr", "create", "--yes", "--source-branch", &branch]);
+                    args.extend(["--title", &title, "--description", &body]);
+                    if let Some(base) = &opts.base {
+                        args.extend(["--target-branch", base]);
+                    }
+                    "glab"
+                }
+            };
+            let output = run_tool(program, &args)?;
+            println!("✅ Opened {}", output.trim());
+        }
+    }
+    Ok(())
+}
+
+/// Commit and pull request title
+fn title(delta: &UpdateDelta) -> String {
+    let direct: Vec<&DeltaEntry> = delta.updated.iter().filter(|e| !e.transitive).collect();
+    match direct.as_slice() {
+        [only] => format!(
+            "Update {} to {}",
+            only.name,
+            only.to.as_deref().unwrap_or_default()
+        ),
+        _ => "Update schema dependencies".to_string(),
+    }
+}
+
+/// Markdown table of the version changes followed by the schema changes of
+/// each updated entry (`changes` is parallel to `delta.updated`)
+fn render_summary(delta: &UpdateDelta, changes: &[Vec<String>]) -> String {
+    let label = |e: &DeltaEntry| {
+        if e.transitive {
+            format!("`{}` (transitive)", e.name)
+        } else {
+            format!("`{}`", e.name)
+        }
+    };
+    let mut out = String::from("## Schema dependency updates\n\n");
+    out.push_str("| Dependency | Artifact | From | To |\n|---|---|---|---|\n");
+    for e in delta
+        .updated
+        .iter()
+        .chain(&delta.added)
+        .chain(&delta.removed)
+    {
+        out.push_str(&format!(
+            "| {} | {}/{} | {} | {} |\n",
+            label(e),
+            e.group_id,
+            e.artifact_id,
+            e.from.as_deref().unwrap_or("new"),
+            e.to.as_deref().unwrap_or("removed")
+        ));
+    }
+    for (e, lines) in delta.updated.iter().zip(changes) {
+        out.push_str(&format!(
+            "\n### {} {} → {}\n\n",
+            e.name,
+            e.from.as_deref().unwrap_or_default(),
+            e.to.as_deref().unwrap_or_default()
+        ));
+        if lines.is_empty() {
+            out.push_str("- No schema changes\n");
+        }
+        for line in lines {
+            out.push_str(&format!("- {line}\n"));
+        }
+    }
+    out.push_str("\n_Generated by `apicurio upgrade-pr`._\n");
+    out
+}
+
+/// Paths listed by `git status --porcelain` (the new name of renamed files)
+fn changed_paths(porcelain: &str) -> Vec<String> {
+    porcelain
+        .lines()
+        .filter_map(|line| line.get(3..))
+        .map(|path| match path.split_once(" -> ") {
+            Some((_, renamed)) => renamed,
+            None => path,
+        })
+        .map(|path| path.trim_matches('"').to_string())
+        .collect()
+}
+
+fn git(args: &[&str]) -> Result<String> {
+    run_tool("git", args)
+}
+
+/// Run `program`, failing with its stderr when it exits unsuccessfully
+fn run_tool(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("running {program}; is it installed and on PATH?"))?;
+    if !output.status.success() {
+        bail!(
+            "{program} {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, transitive: bool, from: Option<&str>, to: Option<&str>) -> DeltaEntry {
+        DeltaEntry {
+            name: name.to_string(),
+            registry: "prod".to_string(),
+            group_id: "com.example".to_string(),
+            artifact_id: name.to_string(),
+            transitive,
+            from: from.map(str::to_string),
+            to: to.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let delta = UpdateDelta {
+            dry_run: false,
+            updated: vec![entry("users", false, Some("1.0.0"), Some("1.2.0"))],
+            added: vec![entry("common", true, None, Some("2.0.0"))],
+            removed: Vec::new(),
+            ..Default::default()
+        };
+        let changes = vec![vec!["added field users.v1.User.email".to_string()]];
+        assert_eq!(title(&delta), "Update users to 1.2.0");
+        assert_eq!(
+            render_summary(&delta, &changes),
+            "## Schema dependency updates\n\n\
+             | Dependency | Artifact | From | To |\n|---|---|---|---|\n\
+             | `users` | com.example/users | 1.0.0 | 1.2.0 |\n\
+             | `common` (transitive) | com.example/common | new | 2.0.0 |\n\
+             \n### users 1.0.0 → 1.2.0\n\n\
+             - added field users.v1.User.email\n\
+             \n_Generated by `apicurio upgrade-pr`._\n"
+        );
+        assert_eq!(
+            changed_paths(" M apicuriolock.yaml\n?? protos/users.proto\nR  a.proto -> b.proto\n"),
+            ["apicuriolock.yaml", "protos/users.proto", "b.proto"]
+        );
+    }
+}
@@ -1,20 +1,30 @@
-use crate::{constants::APICURIO_LOCK, lockfile::LockFile};
+use crate::{local_refs, lockfile::LockFile, output_path, paths, provenance};
 use anyhow::{anyhow, Result};
 use sha2::{Digest, Sha256};
-use std::{fs, path::PathBuf};
+use std::fs;
 
-pub async fn run() -> Result<()> {
-    let lock = LockFile::load(&PathBuf::from(APICURIO_LOCK))?;
+/// Check every locked file against its recorded hash
+///
+/// With `ignore_header`, a provenance header written by `pull` is stripped before
+/// hashing, since the lock records the hash of the registry content. References
+/// `pull` pointed at local files are restored to their registry names either way.
+pub async fn run(ignore_header: bool) -> Result<()> {
+    let lock = LockFile::load(&paths::project_lock())?;
+    let root = paths::project_root();
     let mut all_ok = true;
 
     for ld in &lock.locked_dependencies {
-        let file = PathBuf::from(&ld.output_path);
+        let file = output_path::native(&root, &ld.output_path);
         if !file.exists() {
             println!("❌ missing file for {}: {}", ld.name, file.display());
             all_ok = false;
             continue;
         }
-        let data = fs::read(&file)?;
+        let mut data = fs::read(&file)?;
+        if ignore_header {
+            data = provenance::strip_header(&data);
+        }
+        let data = local_refs::to_registry(&lock.locked_dependencies, ld, &data);
         let mut hasher = Sha256::new();
         hasher.update(&data);
         let sha = hex::encode(hasher.finalize());
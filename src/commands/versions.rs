@@ -0,0 +1,91 @@
+use crate::{
+    config::{load_global_config, load_repo_config},
+    events::WarningSink,
+    identifier::resolve_artifact,
+    lockfile::LockFile,
+    paths,
+    registry::RegistryClient,
+};
+use anyhow::{anyhow, Result};
+use semver::{Version, VersionReq};
+use std::sync::Arc;
+
+pub async fn run(identifier: String) -> Result<()> {
+    let repo_cfg = load_repo_config(&paths::project_config())?;
+    let registries = repo_cfg.merge_registries(load_global_config()?)?;
+    let names: Vec<String> = registries.iter().map(|r| r.name.clone()).collect();
+    let target = resolve_artifact(&identifier, &repo_cfg, &names)?;
+    let registry = registries
+        .iter()
+        .find(|r| r.name == target.registry)
+        .ok_or_else(|| anyhow!("Registry '{}' not found", target.registry))?;
+    let client = RegistryClient::new(registry)?.with_events(Arc::new(WarningSink));
+
+    let req = match &target.dependency {
+        Some((_, spec)) => Some(VersionReq::parse(spec)?),
+        None => None,
+    };
+    let locked = target.dependency.as_ref().and_then(|(name, _)| {
+        LockFile::load(&paths::project_lock())
+            .ok()?
+            .locked_dependencies
+            .into_iter()
+            .find(|d| &d.name == name && !d.is_transitive)
+            .map(|d| d.resolved_version)
+    });
+
+    let mut versions = client
+        .list_version_metadata(&target.group_id, &target.artifact_id)
+        .await?;
+    if versions.is_empty() {
+        println!(
+            "No versions found for {}/{}/{}",
+            target.registry, target.group_id, target.artifact_id
+        );
+        return Ok(());
+    }
+    // Newest first: semver versions by precedence, anything else by creation date
+    versions.sort_by(
+        |a, b| match (Version::parse(&a.version), Version::parse(&b.version)) {
+            (Ok(va), Ok(vb)) => vb.cmp(&va),
+            (Ok(_), Err(_)) => std::cmp::Ordering::Less,
+            (Err(_), Ok(_)) => std::cmp::Ordering::Greater,
+            (Err(_), Err(_)) => b.created_on.cmp(&a.created_on),
+        },
+    );
+
+    println!(
+        "Versions of {}/{}/{}",
+        target.registry, target.group_id, target.artifact_id
+    );
+    if let Some((name, spec)) = &target.dependency {
+        println!("Configured as '{name}' with range {spec}");
+    }
+    println!();
+    println!(
+        "{:<20} {:<12} {:<26} MATCHES",
+        "VERSION", "STATE", "CREATED"
+    );
+    for v in &versions {
+        let matches = match (&req, Version::parse(&v.version)) {
+            (Some(req), Ok(parsed)) if req.matches(&parsed) => "✔️",
+            (Some(_), Ok(_)) => "✗",
+            _ => "-",
+        };
+        let marker = if locked.as_deref() == Some(v.version.as_str()) {
+            "  🔒 locked"
+        } else {
+            ""
+        };
+        println!(
+            "{:<20} {:<12} {:<26} {}{}",
+            v.version,
+            v.state.as_deref().unwrap_or("-"),
+            v.created_on.as_deref().unwrap_or("-"),
+            matches,
+            marker
+        );
+    }
+
+    Ok(())
+}
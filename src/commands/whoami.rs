@@ -0,0 +1,212 @@
+use crate::{
+    commands::OutputFormat,
+    config::{load_global_config, load_repo_config, AuthConfig, RegistryConfig},
+    paths,
+    registry::{RegistryClient, UserInfo},
+};
+use anyhow::{bail, Result};
+use serde::Serialize;
+
+/// Registry operations and the least role (1 viewer, 2 developer, 3 admin) they need
+const PERMISSIONS: &[(&str, u8)] = &[
+    ("read artifacts, versions and rules", 1),
+    ("publish versions and drafts", 2),
+    ("edit artifact metadata and labels", 2),
+    ("manage artifact rules", 2),
+    (
+        "delete artifacts and versions (when the registry enables deletion)",
+        2,
+    ),
+    ("manage global rules", 3),
+    ("manage role mappings and registry settings", 3),
+];
+
+/// Who a registry thinks the caller is, and what that allows
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryIdentity {
+    pub registry: String,
+    pub url: String,
+    /// Configured authentication method
+    pub auth: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<UserInfo>,
+    /// Role granted by an explicit role mapping, when the caller may list them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_mapping: Option<String>,
+    pub allowed: Vec<String>,
+    pub denied: Vec<String>,
+    /// Why the registry could not be asked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub async fn run(registry: Option<String>, format: OutputFormat) -> Result<()> {
+    let repo_cfg = load_repo_config(&paths::project_config())?;
+    let registries = repo_cfg.merge_registries(load_global_config()?)?;
+    let selected: Vec<&RegistryConfig> = registries
+        .iter()
+        .filter(|r| registry.as_deref().is_none_or(|name| r.name == name))
+        .collect();
+    if selected.is_empty() {
+        match registry {
+            Some(name) => bail!("Registry '{name}' not found"),
+            None => bail!("No registries are configured"),
+        }
+    }
+
+    let mut identities = Vec::new();
+    for registry in selected {
+        identities.push(identify(registry).await);
+    }
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&identities)?),
+        OutputFormat::Text => identities.iter().for_each(print_identity),
+    }
+    let failed = identities.iter().filter(|i| i.error.is_some()).count();
+    if failed > 0 {
+        bail!("{failed} registry(ies) could not tell who you are");
+    }
+    Ok(())
+}
+
+/// Ask `registry` for the current user and, if allowed, its role mappings
+async fn identify(registry: &RegistryConfig) -> RegistryIdentity {
+    let mut identity = RegistryIdentity {
+        registry: registry.name.clone(),
+        url: registry.url.clone(),
+        auth: describe_auth(&registry.auth),
+        user: None,
+        role_mapping: None,
+        allowed: Vec::new(),
+        denied: Vec::new(),
+        error: None,
+    };
+    let user = match RegistryClient::new(registry) {
+        Ok(client) => match client.get_current_user().await {
+            // Role mappings are only listed for admins
+            Ok(user) if user.admin => {
+                identity.role_mapping = client.list_role_mappings().await.ok().and_then(|m| {
+                    m.into_iter()
+                        .find(|m| m.principal_id == user.username)
+                        .map(|m| m.role)
+                });
+                user
+            }
+            Ok(user) => user,
+            Err(e) => {
+                identity.error = Some(e.to_string());
+                return identity;
+            }
+        },
+        Err(e) => {
+            identity.error = Some(e.to_string());
+            return identity;
+        }
+    };
+    (identity.allowed, identity.denied) = permissions(&user);
+    identity.user = Some(user);
+    identity
+}
+
+/// Operations the roles of `user` allow and deny
+fn permissions(user: &UserInfo) -> (Vec<String>, Vec<String>) {
+    let level = if user.admin {
+        3
+    } else if user.developer {
+        2
+    } else if user.viewer {
+        1
+    } else {
+        0
+    };
+    let (allowed, denied): (Vec<_>, Vec<_>) =
+        PERMISSIONS.iter().partition(|(_, needed)| level >= *needed);
+    let names = |list: Vec<&(&str, u8)>| list.iter().map(|(name, _)| name.to_string()).collect();
+    (names(allowed), names(denied))
+}
+
+fn describe_auth(auth: &AuthConfig) -> String {
+    match auth {
+        AuthConfig::None => "none".to_string(),
+        AuthConfig::Basic { username, .. } => format!("basic (username {username})"),
+        AuthConfig::Token { .. } => "token".to_string(),
+        AuthConfig::Bearer { .. } => "bearer".to_string(),
+        AuthConfig::AwsSigv4 { region, .. } => format!("aws-sigv4 ({region})"),
+        AuthConfig::Netrc { file, .. } => {
+            format!("netrc ({})", file.as_deref().unwrap_or("~/.netrc"))
+        }
+        AuthConfig::CredentialHelper { command } => format!("credential helper ({command})"),
+        AuthConfig::Exec { command, .. } => format!("exec ({command})"),
+    }
+}
+
+fn print_identity(identity: &RegistryIdentity) {
+    println!(
+        "🔑 {} ({}), auth: {}",
+        identity.registry, identity.url, identity.auth
+    );
+    if let Some(error) = &identity.error {
+        println!("   ❌ {error}");
+        return;
+    }
+    let Some(user) = &identity.user else {
+        return;
+    };
+    let principal = match (user.username.as_str(), user.display_name.as_deref()) {
+        ("", _) => "anonymous".to_string(),
+        (name, Some(display)) if !display.is_empty() && display != name => {
+            format!("{name} ({display})")
+        }
+        (name, _) => name.to_string(),
+    };
+    println!("   principal: {principal}");
+    let roles: Vec<&str> = [
+        (user.admin, "admin"),
+        (user.developer, "developer"),
+        (user.viewer, "viewer"),
+    ]
+    .into_iter()
+    .filter_map(|(has, role)| has.then_some(role))
+    .collect();
+    let roles = if roles.is_empty() {
+        "none".to_string()
+    } else {
+        roles.join(", ")
+    };
+    println!("   roles: {roles}");
+    if let Some(role) = &identity.role_mapping {
+        println!("   role mapping: {role}");
+    }
+    for allowed in &identity.allowed {
+        println!("   ✔️  {allowed}");
+    }
+    for denied in &identity.denied {
+        println!("   ✖️  {denied}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_identify_and_permissions() {
+        let server = crate::dev_server::DevServer::start("127.0.0.1:0")
+            .await
+            .unwrap();
+        let identity = identify(&server.registry_config("dev")).await;
+        assert_eq!(identity.error, None);
+        assert!(identity.user.unwrap().admin);
+        assert!(identity.denied.is_empty());
+
+        let viewer = UserInfo {
+            username: "alice".to_string(),
+            viewer: true,
+            ..Default::default()
+        };
+        let (allowed, denied) = permissions(&viewer);
+        assert_eq!(allowed, ["read artifacts, versions and rules"]);
+        assert!(denied.contains(&"publish versions and drafts".to_string()));
+    }
+}
@@ -12,8 +12,9 @@
 //! The main project configuration file that defines dependencies, registries, and publishing settings.
 //!
 //! ### Global Registries
-//! Shared registry definitions stored in `~/.config/apicurio/registries.yaml` or
-//! the path specified by `APICURIO_REGISTRIES_PATH`.
+//! Shared registry definitions stored in `~/.config/apicurio/registries.yaml` (see
+//! [`crate::paths`] for other platforms and `APICURIO_CONFIG_DIR`) or the path
+//! specified by `APICURIO_REGISTRIES_PATH`.
 //!
 //! ## Environment Variable Expansion
 //!
@@ -23,12 +24,24 @@
 //! - `${VAR-default}` - Use default if VAR is unset
 //! - `${VAR:+alt}` - Use alt if VAR is set and non-empty
 //! - `${VAR+alt}` - Use alt if VAR is set
+//!
+//! ## Includes
+//!
+//! A repository config may list additional YAML fragments under `include:` (paths are
+//! relative to the config file and may use env placeholders). Fragments are merged over
+//! the base file in order, so later fragments win:
+//! - Mappings are merged key by key
+//! - `registries`, `dependencies` and `publishes` entries replace base entries with the
+//!   same `name` and are appended otherwise
+//! - Any other value replaces the base value
+//!
+//! Merging happens on the raw YAML, before environment variable expansion.
 
-use anyhow::Context;
+use crate::error::{ApicurioError, Result};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
-use std::{env, fs, path::PathBuf};
+use std::{env, fmt, fs, path::PathBuf, sync::OnceLock};
 
 /// Configuration for automatic reference resolution
 ///
@@ -56,6 +69,58 @@ pub struct ReferenceResolutionConfig {
     /// Value: exact output path to use, or null to skip resolution entirely
     #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
     pub output_overrides: std::collections::HashMap<String, Option<String>>,
+    /// Glob patterns over "groupId/artifactId" of references to skip entirely
+    /// (`*` matches within a segment, `**` across segments, `?` one character)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Registries searched, in order, for references missing from the registry
+    /// of the artifact that references them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registry_fallbacks: Vec<String>,
+    /// What to do when references ask for different versions of one artifact
+    #[serde(default, skip_serializing_if = "is_default_conflict_policy")]
+    pub conflict_policy: ConflictPolicy,
+}
+
+/// How `lock` settles an artifact referenced at different exact versions
+///
+/// `overrides` entries always take precedence over the policy.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictPolicy {
+    /// Fail and list every conflict
+    #[default]
+    Error,
+    /// Lock the highest requested version
+    Highest,
+}
+
+fn is_default_conflict_policy(policy: &ConflictPolicy) -> bool {
+    *policy == ConflictPolicy::default()
+}
+
+impl ReferenceResolutionConfig {
+    /// Whether a referenced artifact matches one of the `exclude` patterns
+    pub fn is_excluded(&self, group_id: &str, artifact_id: &str) -> bool {
+        let key = format!("{group_id}/{artifact_id}");
+        self.exclude
+            .iter()
+            .any(|pattern| glob_match(pattern.as_bytes(), key.as_bytes()))
+    }
+}
+
+/// Match `text` against a glob pattern (`*`, `**` and `?`)
+pub(crate) fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        [b'*', rest @ ..] => {
+            let segment = text.iter().position(|&b| b == b'/').unwrap_or(text.len());
+            (0..=segment).any(|i| glob_match(rest, &text[i..]))
+        }
+        [b'?', rest @ ..] => matches!(text, [c, tail @ ..] if *c != b'/' && glob_match(rest, tail)),
+        [c, rest @ ..] => matches!(text, [t, tail @ ..] if t == c && glob_match(rest, tail)),
+    }
 }
 
 fn default_true() -> bool {
@@ -125,6 +190,23 @@ impl OutputPatterns {
         }
     }
 
+    /// Configured patterns with the artifact type they apply to
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &String)> {
+        [
+            ("protobuf", &self.protobuf),
+            ("avro", &self.avro),
+            ("json", &self.json),
+            ("openapi", &self.openapi),
+            ("asyncapi", &self.asyncapi),
+            ("graphql", &self.graphql),
+            ("xml", &self.xml),
+            ("wsdl", &self.wsdl),
+            ("other", &self.other),
+        ]
+        .into_iter()
+        .filter_map(|(kind, pattern)| pattern.as_ref().map(|p| (kind, p)))
+    }
+
     /// Resolve the pattern for an artifact type using optional fallback patterns
     pub fn resolve(&self, artifact_type: &str, fallback: Option<&OutputPatterns>) -> String {
         if let Some(p) = self.get(artifact_type) {
@@ -165,6 +247,51 @@ pub struct DependencyDefaultsConfig {
     pub output_patterns: OutputPatterns,
 }
 
+/// Artifact types that get a provenance header written into pulled files
+///
+/// The header is a "do not edit" comment naming the artifact coordinates, version
+/// and content hash. JSON-based types (Avro, JSON Schema) have no comment syntax
+/// and are therefore not listed.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProvenanceHeaderConfig {
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub protobuf: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub openapi: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub asyncapi: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub graphql: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub xml: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub wsdl: bool,
+}
+
+impl ProvenanceHeaderConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == ProvenanceHeaderConfig::default()
+    }
+
+    /// Whether a header should be written to an output file, judged by its extension
+    pub fn enabled_for_path(&self, path: &str) -> bool {
+        let ext = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        match ext.as_str() {
+            "proto" => self.protobuf,
+            "yaml" | "yml" => self.openapi || self.asyncapi,
+            "graphql" | "gql" => self.graphql,
+            "xsd" | "xml" => self.xml,
+            "wsdl" => self.wsdl,
+            _ => false,
+        }
+    }
+}
+
 /// Repository-specific configuration loaded from `apicurioconfig.yaml`
 ///
 /// This is the main configuration file for a project, containing:
@@ -189,7 +316,7 @@ pub struct DependencyDefaultsConfig {
 ///     registry: production
 ///     outputPath: protos/user-service.proto
 /// ```
-#[derive(Deserialize, Serialize, Debug, Default)]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct RepoConfig {
     /// Optional path to external registries file for additional registry definitions
@@ -210,6 +337,371 @@ pub struct RepoConfig {
     /// Artifacts to publish to registries
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub publishes: Vec<PublishConfig>,
+    /// Additional config fragments merged over this file, in order (later wins)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<IncludeConfig>,
+    /// Per-environment overrides selected with `--env` or `APICURIO_ENV`
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub profiles: std::collections::BTreeMap<String, ProfileConfig>,
+    /// Default timeouts for every registry (overrides the global default)
+    #[serde(default, skip_serializing_if = "TimeoutConfig::is_empty")]
+    pub timeouts: TimeoutConfig,
+    /// Artifact types that get a "do not edit" provenance header when pulled
+    #[serde(default, skip_serializing_if = "ProvenanceHeaderConfig::is_empty")]
+    pub provenance_header: ProvenanceHeaderConfig,
+    /// Mark pulled files read-only so in-place edits are noticed immediately
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub write_protection: bool,
+    /// Exact versions forced for transitive artifacts, keyed by "groupId/artifactId"
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub overrides: std::collections::BTreeMap<String, String>,
+    /// Severity overrides for `lint` rules
+    #[serde(default, skip_serializing_if = "LintConfig::is_empty")]
+    pub lint: LintConfig,
+    /// Plugins (`apicurio-plugin-<name>` on `PATH`) taking part in `lint` and `publish`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plugins: Vec<String>,
+    /// Protobuf tooling run after `pull`
+    #[serde(default, skip_serializing_if = "ProtobufConfig::is_empty")]
+    pub protobuf: ProtobufConfig,
+    /// How `pull` writes OpenAPI (and other JSON/YAML) documents referencing each other
+    #[serde(default, skip_serializing_if = "OpenApiConfig::is_empty")]
+    pub openapi: OpenApiConfig,
+    /// How `lock` follows and checks the `$ref`s of JSON Schema artifacts
+    #[serde(default, skip_serializing_if = "JsonSchemaConfig::is_empty")]
+    pub json_schema: JsonSchemaConfig,
+    /// How `pull` writes XSD and WSDL documents importing each other
+    #[serde(default, skip_serializing_if = "XsdConfig::is_empty")]
+    pub xsd: XsdConfig,
+    /// Stitching check of the pulled GraphQL schemas
+    #[serde(default, skip_serializing_if = "GraphqlConfig::is_empty")]
+    pub graphql: GraphqlConfig,
+    /// How pulled files are kept out of (or in) git
+    #[serde(default, skip_serializing_if = "GitignoreConfig::is_empty")]
+    pub gitignore: GitignoreConfig,
+    /// How far locked versions may fall behind before `status` fails
+    #[serde(default, skip_serializing_if = "StalenessPolicy::is_empty")]
+    pub staleness: StalenessPolicy,
+    /// Where `status --notify` and `update --notify` post their summary
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notifications: Vec<NotificationConfig>,
+}
+
+/// A target of `--notify` summaries (see [`crate::notify`])
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationConfig {
+    #[serde(rename = "type")]
+    pub kind: NotificationKind,
+    /// Webhook URL, usually a `${VAR}` placeholder since it embeds a secret
+    pub url: String,
+    /// Extra request headers, e.g. `Authorization`
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub headers: std::collections::BTreeMap<String, String>,
+}
+
+/// How a notification is posted
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationKind {
+    /// Slack incoming webhook: a `text` message
+    Slack,
+    /// Generic HTTP POST of the summary as JSON
+    Webhook,
+}
+
+/// How far a direct dependency may fall behind before `status` fails
+///
+/// The default fails on any newer version. A dependency that is tolerated by
+/// `failOn` and `allowedMinorsBehind` still fails once its locked version is
+/// older than `maxAgeDays`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StalenessPolicy {
+    /// Least severe update that fails `status` (default `patch`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fail_on: Option<UpdateSeverity>,
+    /// Minor versions a dependency may lag behind within its major version
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub allowed_minors_behind: u64,
+    /// Fail when an outdated locked version was published more than this many days ago
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+}
+
+impl StalenessPolicy {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+/// The semver part that differs between a locked version and a newer one
+#[derive(
+    Deserialize, Serialize, clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateSeverity {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl UpdateSeverity {
+    /// Severity of moving from `locked` to `latest`; `None` unless `latest` is newer
+    pub fn between(locked: &semver::Version, latest: &semver::Version) -> Option<Self> {
+        if latest <= locked {
+            None
+        } else if latest.major != locked.major {
+            Some(UpdateSeverity::Major)
+        } else if latest.minor != locked.minor {
+            Some(UpdateSeverity::Minor)
+        } else {
+            Some(UpdateSeverity::Patch)
+        }
+    }
+}
+
+impl fmt::Display for UpdateSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UpdateSeverity::Patch => "patch",
+            UpdateSeverity::Minor => "minor",
+            UpdateSeverity::Major => "major",
+        })
+    }
+}
+
+/// `.gitignore` handling of pulled files (see [`crate::gitignore`])
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitignoreConfig {
+    /// Keep a managed block listing the pulled output paths in `.gitignore`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub manage: bool,
+    /// Ignore this single (vendored) directory instead of every output path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory: Option<String>,
+    /// Fail `pull` unless the pulled files are committed, for vendoring teams
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ensure_committed: bool,
+}
+
+impl GitignoreConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Protobuf settings (see [`crate::protobuf`])
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtobufConfig {
+    /// Compile the pulled `.proto` files into a `FileDescriptorSet` after `pull`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub descriptor_set: bool,
+    /// Where the descriptor set is written (default `descriptors.binpb`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub descriptor_set_path: Option<String>,
+    /// Directories imports are resolved against (default `protos`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include_paths: Vec<String>,
+    /// Keep comments and source locations in the descriptor set
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub include_source_info: bool,
+}
+
+impl ProtobufConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// OpenAPI settings (see [`crate::openapi`] and [`crate::local_refs`])
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenApiConfig {
+    /// Where `$ref`s to other pulled artifacts point
+    #[serde(default, skip_serializing_if = "RefTargets::is_default")]
+    pub refs: RefTargets,
+    /// Also write `<file>.bundle.<ext>` next to every direct dependency that
+    /// references others, with the referenced schemas inlined
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub bundle: bool,
+}
+
+impl OpenApiConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// XSD and WSDL settings (see [`crate::local_refs`])
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct XsdConfig {
+    /// Where `schemaLocation`s of imports of other pulled artifacts point
+    #[serde(default, skip_serializing_if = "RefTargets::is_default")]
+    pub refs: RefTargets,
+}
+
+impl XsdConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// JSON Schema settings (see [`crate::json_schema`])
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonSchemaConfig {
+    /// Lock the artifacts `$ref`s name by registry URL as transitive dependencies
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub resolve_refs: bool,
+    /// Report malformed keywords of locked schemas
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub validate: bool,
+}
+
+impl JsonSchemaConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// GraphQL settings (see [`crate::graphql`])
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphqlConfig {
+    /// What `pull` does with duplicate or unresolved types across the pulled schemas
+    #[serde(default, skip_serializing_if = "GraphqlCheck::is_default")]
+    pub check: GraphqlCheck,
+    /// Types defined outside the pulled schemas (e.g. by a federation gateway)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub known_types: Vec<String>,
+}
+
+impl GraphqlConfig {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// How `pull` reports GraphQL stitching problems
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphqlCheck {
+    /// No check
+    #[default]
+    Off,
+    /// Print the problems as warnings
+    Warn,
+    /// Fail the pull
+    Error,
+}
+
+impl GraphqlCheck {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        *self != GraphqlCheck::Off
+    }
+}
+
+/// Where references between pulled files point
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RefTargets {
+    /// Keep the reference names the registry content uses
+    #[default]
+    Registry,
+    /// Rewrite them to the paths of the pulled files
+    Local,
+}
+
+impl RefTargets {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Settings of the built-in schema linter (see [`crate::lint`])
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LintConfig {
+    /// Severity per rule name (`error`, `warning` or `off`), replacing its default
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub rules: std::collections::BTreeMap<String, crate::lint::Severity>,
+}
+
+impl LintConfig {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// Environment-specific overrides (e.g. `dev`, `staging`, `prod`)
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileConfig {
+    /// Registry overrides applied on top of all other registry definitions
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub registries: Vec<ProfileRegistryConfig>,
+}
+
+/// Override of a registry's connection details within a profile
+///
+/// Only the fields that are set replace the values of the registry with the same
+/// name; a registry that isn't defined elsewhere must provide a `url`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileRegistryConfig {
+    /// Name of the registry to override
+    pub name: String,
+    /// Base URL to use in this environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Authentication to use in this environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth: Option<AuthConfig>,
+}
+
+/// A config fragment merged over the repository config
+///
+/// Either a plain path (the file must exist) or a mapping with `path` and
+/// `optional`, for overlays such as `apicurioconfig.local.yaml` that are not
+/// committed.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum IncludeConfig {
+    /// Path to a required fragment
+    Path(String),
+    /// Path to a fragment that may be optional
+    Detailed {
+        /// Path to the fragment, relative to the config file
+        path: String,
+        /// Skip the fragment silently when the file does not exist
+        #[serde(default)]
+        optional: bool,
+    },
+}
+
+impl IncludeConfig {
+    pub fn path(&self) -> &str {
+        match self {
+            IncludeConfig::Path(path) => path,
+            IncludeConfig::Detailed { path, .. } => path,
+        }
+    }
+
+    pub fn is_optional(&self) -> bool {
+        matches!(self, IncludeConfig::Detailed { optional: true, .. })
+    }
 }
 
 /// Registry configuration defining connection details and authentication
@@ -226,14 +718,103 @@ pub struct RegistryConfig {
     /// Authentication configuration
     #[serde(default)]
     pub auth: AuthConfig,
+    /// Connect/request timeouts for this registry (falls back to the top-level `timeouts`)
+    #[serde(default, skip_serializing_if = "TimeoutConfig::is_empty")]
+    pub timeouts: TimeoutConfig,
+    /// Group used by `add` and `publish` when none is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_group: Option<String>,
+    /// Case artifact IDs published to or added from this registry must use
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_id_case: Option<ArtifactIdCase>,
+    /// Group IDs must start with one of these (e.g. `com.acme.`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub group_prefixes: Vec<String>,
+    /// Artifact IDs must start with one of these
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artifact_id_prefixes: Vec<String>,
+}
+
+/// Naming convention for artifact IDs, see [`RegistryConfig::artifact_id_case`]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactIdCase {
+    /// `order-service`
+    #[serde(rename = "kebab-case")]
+    Kebab,
+    /// `order_service`
+    #[serde(rename = "snake_case")]
+    Snake,
+    /// `orderService`
+    #[serde(rename = "camelCase")]
+    Camel,
+    /// `OrderService`
+    #[serde(rename = "PascalCase")]
+    Pascal,
+}
+
+/// Timeouts applied to registry HTTP calls, in seconds
+///
+/// Unset values fall back to the next level: registry → repo `timeouts` →
+/// global `timeouts` → built-in defaults. The `--timeout` flag (or
+/// `APICURIO_TIMEOUT`) overrides the read timeout everywhere.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeoutConfig {
+    /// Maximum time to establish a connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect: Option<u64>,
+    /// Maximum time the registry may stay silent: before the response starts
+    /// and between two reads of its body, so a slow but steady download of a
+    /// large artifact is not cut off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read: Option<u64>,
+}
+
+impl TimeoutConfig {
+    pub fn is_empty(&self) -> bool {
+        self.connect.is_none() && self.read.is_none()
+    }
+
+    /// Fill unset values from `fallback`
+    pub fn or(self, fallback: TimeoutConfig) -> TimeoutConfig {
+        TimeoutConfig {
+            connect: self.connect.or(fallback.connect),
+            read: self.read.or(fallback.read),
+        }
+    }
+
+    /// Effective connect timeout
+    pub fn connect_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(
+            self.connect
+                .unwrap_or(crate::constants::DEFAULT_CONNECT_TIMEOUT_SECS),
+        )
+    }
+
+    /// Effective read timeout, honoring the `--timeout` and `APICURIO_TIMEOUT`
+    /// overrides
+    pub fn read_timeout(&self) -> std::time::Duration {
+        let override_secs = RunSettings::current().read_timeout.or_else(|| {
+            env::var("APICURIO_TIMEOUT")
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        });
+        std::time::Duration::from_secs(
+            override_secs
+                .or(self.read)
+                .unwrap_or(crate::constants::DEFAULT_READ_TIMEOUT_SECS),
+        )
+    }
 }
 
 /// Authentication configuration for registry access
 ///
 /// Supports multiple authentication methods commonly used with Apicurio Registry.
-/// Credentials are always sourced from environment variables for security.
+/// Credentials are never stored in the config: they come from an environment
+/// variable or from a `secretRef` resolved by [`crate::secrets`] (which wins when
+/// both are set). Snake-case field names are accepted for older configs.
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", rename_all_fields = "camelCase")]
 #[serde(tag = "type")]
 #[derive(Default)]
 pub enum AuthConfig {
@@ -245,20 +826,116 @@ pub enum AuthConfig {
         /// Username for basic auth
         username: String,
         /// Environment variable containing the password
+        #[serde(
+            default,
+            alias = "password_env",
+            skip_serializing_if = "String::is_empty"
+        )]
         password_env: String,
+        /// Secret reference for the password (e.g. `op://Dev/Registry/password`)
+        #[serde(default, alias = "secret_ref", skip_serializing_if = "Option::is_none")]
+        secret_ref: Option<String>,
     },
     /// Token-based authentication (custom header)
     Token {
         /// Environment variable containing the token
+        #[serde(default, alias = "token_env", skip_serializing_if = "String::is_empty")]
         token_env: String,
+        /// Secret reference for the token (e.g. `vault://kv/apicurio#token`)
+        #[serde(default, alias = "secret_ref", skip_serializing_if = "Option::is_none")]
+        secret_ref: Option<String>,
     },
     /// Bearer token authentication (Authorization header)
     Bearer {
         /// Environment variable containing the bearer token
+        #[serde(default, alias = "token_env", skip_serializing_if = "String::is_empty")]
         token_env: String,
+        /// Secret reference for the bearer token
+        #[serde(default, alias = "secret_ref", skip_serializing_if = "Option::is_none")]
+        secret_ref: Option<String>,
+    },
+    /// AWS Signature Version 4 (e.g. a registry behind API Gateway with IAM auth)
+    ///
+    /// Requires a build with the `aws-sigv4` feature.
+    #[serde(rename = "awsSigv4", alias = "aws_sigv4")]
+    AwsSigv4 {
+        /// AWS region of the endpoint, e.g. `eu-west-1`
+        region: String,
+        /// Service name used in the signature
+        #[serde(default = "default_sigv4_service")]
+        service: String,
+        /// Profile of the shared credentials file (defaults to `AWS_PROFILE` or `default`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        profile: Option<String>,
+    },
+    /// Login from a `.netrc` file: Basic auth, or a bearer token when the
+    /// entry has no `login`
+    Netrc {
+        /// netrc file to read (defaults to `$NETRC`, then `~/.netrc`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<String>,
+        /// Machine to look up (defaults to the host of the registry URL)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        machine: Option<String>,
+    },
+    /// Login from a git-credential style helper executable, e.g.
+    /// `git credential-store` or `git-credential-osxkeychain`
+    CredentialHelper {
+        /// Command run with `get` appended; split on whitespace
+        command: String,
+    },
+    /// Bearer token printed by a command, like a kubeconfig exec plugin
+    ///
+    /// The command runs before the first request to the registry and its
+    /// token is reused for the rest of the process.
+    Exec {
+        /// Program to run
+        command: String,
+        /// Its arguments
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
     },
 }
 
+impl AuthConfig {
+    /// Environment variables the credential is read from, with the field naming
+    /// them (`passwordEnv`, `tokenEnv`, or `secretRef` for an `env://` reference)
+    pub fn env_vars(&self) -> Vec<(&'static str, &str)> {
+        let (field, env_var, secret_ref) = match self {
+            AuthConfig::None
+            | AuthConfig::AwsSigv4 { .. }
+            | AuthConfig::Netrc { .. }
+            | AuthConfig::CredentialHelper { .. }
+            | AuthConfig::Exec { .. } => return Vec::new(),
+            AuthConfig::Basic {
+                password_env,
+                secret_ref,
+                ..
+            } => ("passwordEnv", password_env, secret_ref),
+            AuthConfig::Token {
+                token_env,
+                secret_ref,
+            }
+            | AuthConfig::Bearer {
+                token_env,
+                secret_ref,
+            } => ("tokenEnv", token_env, secret_ref),
+        };
+        match secret_ref {
+            Some(reference) => reference
+                .strip_prefix("env://")
+                .map(|var| vec![("secretRef", var)])
+                .unwrap_or_default(),
+            None if !env_var.is_empty() => vec![(field, env_var.as_str())],
+            None => Vec::new(),
+        }
+    }
+}
+
+fn default_sigv4_service() -> String {
+    "execute-api".to_string()
+}
+
 /// Dependency configuration for artifacts to fetch from registries
 ///
 /// Dependencies support semantic version ranges and are resolved to exact
@@ -290,6 +967,49 @@ pub struct DependencyConfig {
     /// Override reference resolution for this specific dependency
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resolve_references: Option<bool>,
+    /// Binary artifact stored base64-encoded: decoded on download, never given a
+    /// provenance header
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+    /// `Accept` media type to download with, instead of the one derived from the
+    /// output file extension or artifact type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Lock every artifact of a group instead of a single one: an exact group ID,
+    /// or a glob (`com.example.*`) over the group IDs of the registry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Lock every artifact carrying all of these registry labels, found through
+    /// the search API; `group`, when also set, narrows the search to those groups
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub labels: std::collections::BTreeMap<String, String>,
+    /// Glob patterns over artifact IDs to lock from `group` (all when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Glob patterns over artifact IDs to leave out of `group`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// What to do with artifacts of `group` that have no version matching `version`
+    #[serde(default, skip_serializing_if = "is_default_no_match_policy")]
+    pub if_no_match: NoMatchPolicy,
+    /// Name of the `group` dependency this one was expanded from, at lock time
+    #[serde(skip)]
+    pub member_of: Option<String>,
+}
+
+/// How a `group` dependency treats artifacts without a version matching its range
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum NoMatchPolicy {
+    /// Leave the artifact out of the lock
+    #[default]
+    Skip,
+    /// Fail the lock, naming the artifact
+    Error,
+}
+
+fn is_default_no_match_policy(policy: &NoMatchPolicy) -> bool {
+    *policy == NoMatchPolicy::default()
 }
 
 /// Publishing configuration for uploading artifacts to registries
@@ -303,8 +1023,16 @@ pub struct PublishConfig {
     pub name: String,
     /// Local path to the file to publish
     pub input_path: String,
-    /// Exact version to publish (no semver ranges allowed)
+    /// Exact version to publish (no semver ranges allowed), or a bump strategy:
+    /// `auto-patch`, `auto-minor`, `auto-major` or `git-describe`
+    ///
+    /// May be left out when a version source (`fromFile`, `fromCargo`,
+    /// `fromMaven`, `fromGitTag`) is set.
+    #[serde(default)]
     pub version: String,
+    /// Read the version from a file or git at publish time instead of `version`
+    #[serde(flatten)]
+    pub version_source: VersionSource,
     /// Target registry name
     pub registry: String,
 
@@ -330,6 +1058,66 @@ pub struct PublishConfig {
     /// References to other artifacts
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub references: Vec<ArtifactReference>,
+    /// Binary file (e.g. a descriptor set): read as bytes and sent base64-encoded
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+}
+
+/// Where a publish reads its version from at publish time
+///
+/// At most one field may be set; paths are relative to the project root.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionSource {
+    /// Plain-text file holding only the version, e.g. `VERSION`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_file: Option<String>,
+    /// `Cargo.toml` whose `package.version` (or `workspace.package.version`) is used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_cargo: Option<String>,
+    /// `pom.xml` whose project `<version>` is used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_maven: Option<String>,
+    /// The newest git tag reachable from HEAD (a leading `v` is dropped)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub from_git_tag: bool,
+}
+
+impl VersionSource {
+    /// The keys set, as written in the config
+    pub fn keys(&self) -> Vec<&'static str> {
+        let mut keys = Vec::new();
+        if self.from_file.is_some() {
+            keys.push("fromFile");
+        }
+        if self.from_cargo.is_some() {
+            keys.push("fromCargo");
+        }
+        if self.from_maven.is_some() {
+            keys.push("fromMaven");
+        }
+        if self.from_git_tag {
+            keys.push("fromGitTag");
+        }
+        keys
+    }
+
+    pub fn is_set(&self) -> bool {
+        !self.keys().is_empty()
+    }
+}
+
+/// How the version of a publish is computed when it is not given explicitly
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionStrategy {
+    /// Bump the patch of the newest registry version
+    AutoPatch,
+    /// Bump the minor of the newest registry version
+    AutoMinor,
+    /// Bump the major of the newest registry version
+    AutoMajor,
+    /// Use `git describe --tags` (a leading `v` is dropped)
+    GitDescribe,
 }
 
 /// Supported artifact types for publishing
@@ -408,9 +1196,98 @@ pub struct GlobalConfig {
     /// Shared registry definitions
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub registries: Vec<RegistryConfig>,
+    /// Default timeouts for every registry
+    #[serde(default, skip_serializing_if = "TimeoutConfig::is_empty")]
+    pub timeouts: TimeoutConfig,
 }
 
 impl RepoConfig {
+    /// Version forced by `overrides` for a transitive artifact
+    pub fn version_override(&self, group_id: &str, artifact_id: &str) -> Option<&String> {
+        self.overrides.get(&format!("{group_id}/{artifact_id}"))
+    }
+
+    /// Check that every publish has a version or exactly one version source
+    pub fn validate_publish_versions(&self) -> Result<()> {
+        for publish in &self.publishes {
+            match publish.version_source.keys().as_slice() {
+                [] if publish.version.is_empty() => {
+                    return Err(ApicurioError::config(format!(
+                        "publishes.{}: set `version` or one of fromFile, fromCargo, fromMaven, fromGitTag",
+                        publish.name
+                    )))
+                }
+                [] | [_] => {}
+                keys => {
+                    return Err(ApicurioError::config(format!(
+                        "publishes.{}: only one version source may be set, found {}",
+                        publish.name,
+                        keys.join(", ")
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every `overrides` entry names an artifact and an exact version
+    pub fn validate_overrides(&self) -> Result<()> {
+        for (key, version) in &self.overrides {
+            if !key.contains('/') {
+                return Err(ApicurioError::config(format!(
+                    "overrides key '{key}' must be \"groupId/artifactId\""
+                )));
+            }
+            semver::Version::parse(version).map_err(|e| {
+                ApicurioError::config_caused(
+                    format!("overrides.{key}: '{version}' is not an exact version"),
+                    e,
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Check every configured output pattern, reporting all invalid ones at once
+    pub fn validate_output_patterns(&self) -> Result<()> {
+        let mut patterns: Vec<(String, &String)> = Vec::new();
+        for (kind, pattern) in self.dependency_defaults.output_patterns.iter() {
+            patterns.push((format!("dependencyDefaults.outputPatterns.{kind}"), pattern));
+        }
+        for (kind, pattern) in self.reference_resolution.output_patterns.iter() {
+            patterns.push((
+                format!("referenceResolution.outputPatterns.{kind}"),
+                pattern,
+            ));
+        }
+        let mut overrides: Vec<_> = self.reference_resolution.output_overrides.iter().collect();
+        overrides.sort();
+        for (key, pattern) in overrides {
+            if let Some(pattern) = pattern {
+                patterns.push((
+                    format!("referenceResolution.outputOverrides[\"{key}\"]"),
+                    pattern,
+                ));
+            }
+        }
+
+        let problems: Vec<String> = patterns
+            .into_iter()
+            .filter_map(|(location, pattern)| {
+                crate::output_path::validate_pattern(pattern)
+                    .err()
+                    .map(|e| format!("  - {location}: {e}"))
+            })
+            .collect();
+        if !problems.is_empty() {
+            return Err(ApicurioError::config(format!(
+                "invalid output patterns:\n{}",
+                problems.join("\n")
+            )));
+        }
+        Ok(())
+    }
+
     /// Merge global, external, and repo-local registries
     ///
     /// Registry definitions are merged in the following order (later wins):
@@ -426,17 +1303,35 @@ impl RepoConfig {
     ///
     /// # Errors
     /// Returns error if external registries file cannot be read or parsed
-    pub fn merge_registries(&self, global: GlobalConfig) -> anyhow::Result<Vec<RegistryConfig>> {
+    pub fn merge_registries(&self, global: GlobalConfig) -> Result<Vec<RegistryConfig>> {
+        self.merge_registries_for_profile(global, active_profile().as_ref())
+    }
+
+    /// Merge registries like [`RepoConfig::merge_registries`], then apply the
+    /// overrides of the given profile (highest precedence)
+    pub fn merge_registries_for_profile(
+        &self,
+        global: GlobalConfig,
+        profile: Option<&ProfileSelection>,
+    ) -> Result<Vec<RegistryConfig>> {
         let mut map = std::collections::HashMap::new();
+        let default_timeouts = self.timeouts.or(global.timeouts);
         // 1) global
         for reg in global.registries {
             map.insert(reg.name.clone(), reg);
         }
-        // 2) external file
-        if let Some(path) = &self.external_registries_file {
-            let contents = fs::read_to_string(path)
-                .with_context(|| format!("reading external registries from {path}"))?;
-            let ext: GlobalConfig = serde_yaml::from_str(&contents)?;
+        // 2) external file (an unset `${VAR:-}` leaves it empty in JSON/TOML)
+        if let Some(path) = self
+            .external_registries_file
+            .as_ref()
+            .filter(|p| !p.is_empty())
+        {
+            let contents = fs::read_to_string(path).map_err(|e| {
+                ApicurioError::io("reading external registries from", Path::new(path), e)
+            })?;
+            let ext: GlobalConfig = serde_yaml::from_str(&contents).map_err(|e| {
+                ApicurioError::config_caused(format!("invalid external registries in {path}"), e)
+            })?;
             for reg in ext.registries {
                 map.insert(reg.name.clone(), reg);
             }
@@ -445,13 +1340,71 @@ impl RepoConfig {
         for reg in &self.registries {
             map.insert(reg.name.clone(), reg.clone());
         }
-        Ok(map.into_values().collect())
+        // 4) active profile
+        if let Some(profile) = self.profile(profile)? {
+            for over in &profile.registries {
+                let reg = map
+                    .entry(over.name.clone())
+                    .or_insert_with(|| RegistryConfig {
+                        name: over.name.clone(),
+                        ..Default::default()
+                    });
+                if let Some(url) = &over.url {
+                    reg.url = url.clone();
+                }
+                if let Some(auth) = &over.auth {
+                    reg.auth = auth.clone();
+                }
+                if reg.url.is_empty() {
+                    return Err(ApicurioError::config(format!(
+                        "profile registry '{}' is not defined elsewhere and has no url",
+                        over.name
+                    )));
+                }
+            }
+        }
+        Ok(map
+            .into_values()
+            .map(|mut reg| {
+                reg.timeouts = reg.timeouts.or(default_timeouts);
+                reg
+            })
+            .collect())
+    }
+
+    /// Look up the selected profile
+    ///
+    /// Returns `None` when no profile is selected, or when one named by
+    /// `APICURIO_ENV` meets a config without profiles; a profile named with
+    /// `--env` must exist.
+    pub fn profile(&self, selection: Option<&ProfileSelection>) -> Result<Option<&ProfileConfig>> {
+        let Some(selection) = selection else {
+            return Ok(None);
+        };
+        if self.profiles.is_empty() && !selection.explicit {
+            return Ok(None);
+        }
+        match self.profiles.get(&selection.name) {
+            Some(profile) => Ok(Some(profile)),
+            None if self.profiles.is_empty() => Err(ApicurioError::config(format!(
+                "unknown profile '{}'; the config defines no profiles",
+                selection.name
+            ))),
+            None => Err(ApicurioError::config(format!(
+                "unknown profile '{}'. Available profiles: {}",
+                selection.name,
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            ))),
+        }
     }
 
-    /// Return all dependencies parsed with defaults applied
-    pub fn dependencies_with_defaults(&self) -> anyhow::Result<Vec<crate::dependency::Dependency>> {
+    /// Return all single-artifact dependencies parsed with defaults applied
+    ///
+    /// `group` dependencies only become artifacts at lock time and are left out.
+    pub fn dependencies_with_defaults(&self) -> Result<Vec<crate::dependency::Dependency>> {
         self.dependencies
             .iter()
+            .filter(|cfg| !cfg.is_group())
             .map(|cfg| {
                 crate::dependency::Dependency::from_config_with_defaults(
                     cfg,
@@ -463,6 +1416,17 @@ impl RepoConfig {
 }
 
 impl PublishConfig {
+    /// The strategy named by `version`, `None` for an explicit version
+    pub fn version_strategy(&self) -> Option<VersionStrategy> {
+        match self.version.as_str() {
+            "auto-patch" => Some(VersionStrategy::AutoPatch),
+            "auto-minor" => Some(VersionStrategy::AutoMinor),
+            "auto-major" => Some(VersionStrategy::AutoMajor),
+            "git-describe" => Some(VersionStrategy::GitDescribe),
+            _ => None,
+        }
+    }
+
     /// Get the resolved group ID for this publish configuration
     ///
     /// If `group_id` is explicitly set, uses that value. Otherwise:
@@ -548,6 +1512,29 @@ impl PublishConfig {
 }
 
 impl DependencyConfig {
+    /// Whether this dependency stands for every (selected) artifact of a group
+    /// or label selector
+    pub fn is_group(&self) -> bool {
+        self.group.is_some() || !self.labels.is_empty()
+    }
+
+    /// Whether `group_id` is matched by `group` (any group when only `labels` is set)
+    pub fn matches_group(&self, group_id: &str) -> bool {
+        self.group
+            .as_deref()
+            .is_none_or(|pattern| glob_match(pattern.as_bytes(), group_id.as_bytes()))
+    }
+
+    /// Whether `artifact_id` of the group passes the `include`/`exclude` filters
+    pub fn selects_artifact(&self, artifact_id: &str) -> bool {
+        let matches = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| glob_match(pattern.as_bytes(), artifact_id.as_bytes()))
+        };
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+
     /// Get the resolved group ID for this dependency configuration
     ///
     /// If `group_id` is explicitly set, uses that value. Otherwise:
@@ -589,17 +1576,17 @@ impl DependencyConfig {
 
 impl ArtifactReference {
     /// Validate that the version is exact (no semver ranges)
-    pub fn validate_exact_version(&self) -> anyhow::Result<()> {
+    pub fn validate_exact_version(&self) -> Result<()> {
         if self.version.contains('^')
             || self.version.contains('~')
             || self.version.contains('*')
             || self.version.contains('>')
             || self.version.contains('<')
         {
-            anyhow::bail!(
+            return Err(ApicurioError::config(format!(
                 "Reference version must be exact, got '{}'. Use exact version like '1.2.3'",
                 self.version
-            );
+            )));
         }
         Ok(())
     }
@@ -633,55 +1620,427 @@ impl ArtifactReference {
     }
 }
 
-pub fn load_repo_config(path: &Path) -> anyhow::Result<RepoConfig> {
+/// Whether `path` is a JSON config or lock file (`.json`) rather than YAML
+pub fn is_json_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Whether `path` names a TOML file (by its extension)
+pub fn is_toml_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"))
+}
+
+/// Parse the text of a config file: JSON for `.json` files, TOML for `.toml`
+/// files, YAML otherwise
+pub(crate) fn parse_config_text<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    data: &str,
+) -> std::result::Result<T, crate::error::BoxError> {
+    if is_json_file(path) {
+        Ok(serde_json::from_str(data)?)
+    } else if is_toml_file(path) {
+        Ok(toml::from_str(data)?)
+    } else {
+        Ok(serde_yaml::from_str(data)?)
+    }
+}
+
+pub fn load_repo_config(path: &Path) -> Result<RepoConfig> {
     let preprocessed_data = preprocess_config(path)?; // Preprocess the YAML file to expand environment variables
-    let cfg: RepoConfig = serde_yaml::from_str(&preprocessed_data)?;
+    check_legacy_keys(path, &preprocessed_data)?;
+    let cfg: RepoConfig = parse_config_text(path, &preprocessed_data).map_err(|e| {
+        ApicurioError::config_caused(format!("invalid configuration in {}", path.display()), e)
+    })?;
+    cfg.validate_output_patterns()
+        .and_then(|_| cfg.validate_overrides())
+        .and_then(|_| cfg.validate_publish_versions())
+        .and_then(|_| {
+            crate::lint::validate_rule_names(&cfg.lint.rules)
+                .map_err(|e| ApicurioError::config(format!("{e:#}")))
+        })
+        .map_err(|e| e.context(format!("invalid configuration in {}", path.display())))?;
     Ok(cfg)
 }
 
-pub fn load_global_config() -> anyhow::Result<GlobalConfig> {
-    let path = env::var("APICURIO_REGISTRIES_PATH")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| {
-            let mut p = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
-            p.push("apicurio/registries.yaml");
-            p
-        });
+/// Refuse snake_case keys of older releases that would otherwise be ignored
+fn check_legacy_keys(path: &Path, data: &str) -> Result<()> {
+    let mut renames: Vec<String> = crate::migrate::ignored_config_keys(data)
+        .iter()
+        .map(|key| format!("{} -> {}", key.old, key.new))
+        .collect();
+    renames.sort();
+    renames.dedup();
+    if renames.is_empty() {
+        Ok(())
+    } else {
+        Err(crate::migrate::outdated(path, &renames))
+    }
+}
+
+/// Load only the repository config file itself, ignoring `include` fragments
+///
+/// Use this when the config is going to be modified and saved back, so values
+/// from overlays are not written into the base file.
+pub fn load_repo_config_without_includes(path: &Path) -> Result<RepoConfig> {
+    let raw_data =
+        fs::read_to_string(path).map_err(|e| ApicurioError::io("reading config from", path, e))?;
+    check_legacy_keys(path, &raw_data)?;
+    parse_config_text(path, &expand_env_placeholders(&raw_data)).map_err(|e| {
+        ApicurioError::config_caused(format!("invalid configuration in {}", path.display()), e)
+    })
+}
+
+/// Read a repository config and merge its `include` fragments over it
+///
+/// Environment placeholders are left untouched so the result can be used both for
+/// loading and for computing the lock's config hash.
+pub fn read_merged_config(path: &Path) -> Result<String> {
+    let raw_data =
+        fs::read_to_string(path).map_err(|e| ApicurioError::io("reading config from", path, e))?;
+    let mut base: serde_yaml::Value = parse_config_text(path, &raw_data).map_err(|e| {
+        ApicurioError::config_caused(format!("invalid configuration in {}", path.display()), e)
+    })?;
+    let includes: Vec<IncludeConfig> = match base.get("include") {
+        Some(value) => serde_yaml::from_value(value.clone()).map_err(|e| {
+            ApicurioError::config_caused(format!("invalid include list in {}", path.display()), e)
+        })?,
+        None => Vec::new(),
+    };
+    if includes.is_empty() {
+        return Ok(raw_data);
+    }
+
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    for include in &includes {
+        let include_path = dir.join(expand_env_placeholders(include.path()));
+        if !include_path.exists() {
+            if include.is_optional() {
+                continue;
+            }
+            return Err(ApicurioError::config(format!(
+                "included config {} (from {}) does not exist",
+                include_path.display(),
+                path.display()
+            )));
+        }
+        let data = fs::read_to_string(&include_path)
+            .map_err(|e| ApicurioError::io("reading included config", &include_path, e))?;
+        let overlay: serde_yaml::Value = parse_config_text(&include_path, &data).map_err(|e| {
+            ApicurioError::config_caused(
+                format!("parsing included config {}", include_path.display()),
+                e,
+            )
+        })?;
+        if overlay.get("include").is_some() {
+            return Err(ApicurioError::config(format!(
+                "nested includes are not supported (found in {})",
+                include_path.display()
+            )));
+        }
+        merge_config_values(&mut base, overlay);
+    }
+
+    // Keep the format of the base file so it parses the same way
+    if is_json_file(path) {
+        serde_json::to_string_pretty(&base)
+            .map_err(|e| ApicurioError::config_caused("merging included configs", e))
+    } else if is_toml_file(path) {
+        toml::to_string(&base)
+            .map_err(|e| ApicurioError::config_caused("merging included configs", e))
+    } else {
+        serde_yaml::to_string(&base)
+            .map_err(|e| ApicurioError::config_caused("merging included configs", e))
+    }
+}
+
+/// Merge a config fragment over a base config (see module docs for precedence)
+fn merge_config_values(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    use serde_yaml::Value;
+
+    match (base, overlay) {
+        (Value::Mapping(base_map), Value::Mapping(overlay_map)) => {
+            for (key, value) in overlay_map {
+                let is_named_list = matches!(
+                    key.as_str(),
+                    Some("registries") | Some("dependencies") | Some("publishes")
+                );
+                match base_map.get_mut(&key) {
+                    Some(existing) if is_named_list => merge_named_lists(existing, value),
+                    Some(existing) => merge_yaml_values(existing, value),
+                    None => {
+                        base_map.insert(key, value);
+                    }
+                }
+            }
+        }
+        (_, Value::Null) => {}
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Merge lists of `name`d entries: same name replaces, new names are appended
+fn merge_named_lists(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    use serde_yaml::Value;
+
+    match (base, overlay) {
+        (Value::Sequence(base_items), Value::Sequence(overlay_items)) => {
+            for item in overlay_items {
+                let existing = item
+                    .get("name")
+                    .and_then(|name| base_items.iter().position(|b| b.get("name") == Some(name)));
+                match existing {
+                    Some(pos) => base_items[pos] = item,
+                    None => base_items.push(item),
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Deep-merge mappings; any other overlay value replaces the base value
+fn merge_yaml_values(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
+    use serde_yaml::Value;
+
+    match (base, overlay) {
+        (Value::Mapping(base_map), Value::Mapping(overlay_map)) => {
+            for (key, value) in overlay_map {
+                match base_map.get_mut(&key) {
+                    Some(existing) => merge_yaml_values(existing, value),
+                    None => {
+                        base_map.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// An environment profile to resolve registries against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfileSelection {
+    pub name: String,
+    /// Named on the command line, so it must exist; a profile named by
+    /// `APICURIO_ENV` is ignored by configs without profiles
+    pub explicit: bool,
+}
+
+impl ProfileSelection {
+    /// A profile named with `--env`
+    pub fn explicit(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            explicit: true,
+        }
+    }
+
+    /// A profile named by `APICURIO_ENV`
+    pub fn ambient(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            explicit: false,
+        }
+    }
+}
+
+/// Global command line options that apply wherever registries are merged,
+/// installed once by the binary before any command runs
+#[derive(Debug, Clone, Default)]
+pub struct RunSettings {
+    /// Profile given with `--env`
+    pub profile: Option<String>,
+    /// Read timeout given with `--timeout`, in seconds
+    pub read_timeout: Option<u64>,
+    /// Project config given with `--config`
+    pub config_path: Option<PathBuf>,
+    /// Seconds to wait for the project lock, given with `--lock-timeout`
+    pub lock_timeout: Option<u64>,
+    /// Fixture file to record registry answers to, given with `--record`
+    pub record: Option<PathBuf>,
+    /// Fixture file to replay registry answers from, given with `--replay`
+    pub replay: Option<PathBuf>,
+}
+
+static RUN_SETTINGS: OnceLock<RunSettings> = OnceLock::new();
+
+impl RunSettings {
+    /// Make these the settings of the process; only the first call counts
+    pub fn install(self) {
+        let _ = RUN_SETTINGS.set(self);
+    }
+
+    /// The installed settings, empty when none were installed
+    pub fn current() -> &'static RunSettings {
+        RUN_SETTINGS.get_or_init(RunSettings::default)
+    }
+}
+
+/// The active environment profile: `--env`, else `APICURIO_ENV`
+pub fn active_profile() -> Option<ProfileSelection> {
+    match &RunSettings::current().profile {
+        Some(name) => Some(ProfileSelection::explicit(name)),
+        None => env::var("APICURIO_ENV")
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(ProfileSelection::ambient),
+    }
+}
+
+/// Name of the active environment profile, from `--env` or `APICURIO_ENV`
+pub fn active_profile_name() -> Option<String> {
+    active_profile().map(|profile| profile.name)
+}
+
+/// Path of the global registries file (`APICURIO_REGISTRIES_PATH` or the user config dir)
+pub fn global_registries_path() -> PathBuf {
+    crate::paths::registries_file().path
+}
+
+pub fn load_global_config() -> Result<GlobalConfig> {
+    let path = global_registries_path();
     if !path.exists() {
-        return Ok(GlobalConfig { registries: vec![] });
+        return Ok(GlobalConfig::default());
     }
-    let data = fs::read_to_string(&path)
-        .with_context(|| format!("reading global registries {}", path.display()))?;
-    let cfg: GlobalConfig = serde_yaml::from_str(&data)?;
-    Ok(cfg)
+    load_registries_file(&path)
 }
 
-pub fn save_global_config(cfg: &GlobalConfig) -> anyhow::Result<()> {
-    // same path logic as load_global_config
-    let path = env::var("APICURIO_REGISTRIES_PATH")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| {
-            let mut p = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
-            p.push("apicurio/registries.yaml");
-            p
-        });
+/// Save the global registries file, returning where it was written
+pub fn save_global_config(cfg: &GlobalConfig) -> Result<PathBuf> {
+    let path = global_registries_path();
+    save_registries_file(cfg, &path)?;
+    Ok(path)
+}
+
+/// Load a registries file (same format as the global registries file)
+pub fn load_registries_file(path: &Path) -> Result<GlobalConfig> {
+    let data =
+        fs::read_to_string(path).map_err(|e| ApicurioError::io("reading registries", path, e))?;
+    serde_yaml::from_str(&data).map_err(|e| {
+        ApicurioError::config_caused(format!("invalid registries file {}", path.display()), e)
+    })
+}
+
+/// Save a registries file, creating parent directories as needed
+pub fn save_registries_file(cfg: &GlobalConfig, path: &Path) -> Result<()> {
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent).map_err(|e| ApicurioError::io("creating", parent, e))?;
+        }
+    }
+    let data = serde_yaml::to_string(cfg)
+        .map_err(|e| ApicurioError::config_caused("serializing registries", e))?;
+    fs::write(path, data).map_err(|e| ApicurioError::io("writing", path, e))
+}
+
+pub fn save_repo_config(cfg: &RepoConfig, path: &Path) -> Result<()> {
+    let data = if is_json_file(path) {
+        serde_json::to_string_pretty(cfg)
+            .map(|json| json + "\n")
+            .map_err(|e| ApicurioError::config_caused("serializing the configuration", e))?
+    } else if is_toml_file(path) {
+        toml::to_string(cfg)
+            .map_err(|e| ApicurioError::config_caused("serializing the configuration", e))?
+    } else {
+        serde_yaml::to_string(cfg)
+            .map_err(|e| ApicurioError::config_caused("serializing the configuration", e))?
+    };
+    fs::write(path, data).map_err(|e| ApicurioError::io("writing", path, e))
+}
+
+fn placeholder_regex() -> Regex {
+    Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?:(:?[-+])([^}]*))?\}").unwrap()
+}
+
+/// `${VAR}` placeholders (without a default) whose variable is not set, as
+/// 1-based line numbers and variable names, ignoring comment lines
+pub fn unresolved_placeholders(input: &str) -> Vec<(usize, String)> {
+    let re = placeholder_regex();
+    let mut missing = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        for caps in re.captures_iter(line) {
+            let found = (index + 1, caps[1].to_string());
+            if caps.get(2).is_none() && env::var_os(&found.1).is_none() && !missing.contains(&found)
+            {
+                missing.push(found);
+            }
+        }
+    }
+    missing
+}
+
+/// A `${VAR}` placeholder of a config file whose variable is not set
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsetPlaceholder {
+    pub var: String,
+    pub file: PathBuf,
+    pub line: usize,
+}
+
+impl fmt::Display for UnsetPlaceholder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}:{})", self.var, self.file.display(), self.line)
     }
-    let data = serde_yaml::to_string(cfg)?;
-    fs::write(&path, data)?;
-    println!("Saved global registries to {}", path.display());
-    Ok(())
 }
 
-pub fn save_repo_config(cfg: &RepoConfig, path: &Path) -> anyhow::Result<()> {
-    let data = serde_yaml::to_string(cfg)?;
-    fs::write(path, data)?;
-    Ok(())
+/// Unset `${VAR}` placeholders of a repository config and its existing `include` fragments
+pub fn find_unset_placeholders(path: &Path) -> Result<Vec<UnsetPlaceholder>> {
+    let files = read_config_files(path)?;
+    Ok(files
+        .iter()
+        .flat_map(|(file, data)| {
+            unresolved_placeholders(data)
+                .into_iter()
+                .map(|(line, var)| UnsetPlaceholder {
+                    var,
+                    file: file.clone(),
+                    line,
+                })
+        })
+        .collect())
+}
+
+/// Read a repository config and the include fragments that exist, as `(path, text)`
+pub(crate) fn read_config_files(path: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let raw_data =
+        fs::read_to_string(path).map_err(|e| ApicurioError::io("reading config from", path, e))?;
+    let mut files = vec![(path.to_path_buf(), raw_data)];
+    let includes: Vec<IncludeConfig> = serde_yaml::from_str::<serde_yaml::Value>(&files[0].1)
+        .ok()
+        .and_then(|base| serde_yaml::from_value(base.get("include")?.clone()).ok())
+        .unwrap_or_default();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    for include in &includes {
+        let include_path = dir.join(expand_env_placeholders(include.path()));
+        if let Ok(data) = fs::read_to_string(&include_path) {
+            files.push((include_path, data));
+        }
+    }
+    Ok(files)
+}
+
+/// Fail when a `${VAR}` placeholder of the config (or its includes) has no
+/// default and its variable is unset, instead of silently expanding it to ""
+pub fn check_placeholders(path: &Path) -> Result<()> {
+    let unset = find_unset_placeholders(path)?;
+    if unset.is_empty() {
+        return Ok(());
+    }
+    let lines: Vec<String> = unset.iter().map(|p| format!("  {p}")).collect();
+    Err(ApicurioError::config(format!(
+        "unset environment variables in the configuration:\n{}\n\
+         set them, or write ${{VAR:-}} where an empty value is intended",
+        lines.join("\n")
+    )))
 }
 
 pub fn expand_env_placeholders(input: &str) -> String {
-    let re = Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)(?:(:?[-+])([^}]*))?\}").unwrap();
+    let re = placeholder_regex();
     re.replace_all(input, |caps: &regex::Captures| {
         let var_name = &caps[1];
         let op = caps.get(2).map_or("", |m| m.as_str());
@@ -708,9 +2067,9 @@ pub fn expand_env_placeholders(input: &str) -> String {
     .to_string()
 }
 
-pub fn preprocess_config(path: &Path) -> anyhow::Result<String> {
-    let raw_data = fs::read_to_string(path)?;
-    Ok(expand_env_placeholders(&raw_data))
+pub fn preprocess_config(path: &Path) -> Result<String> {
+    let merged_data = read_merged_config(path)?;
+    Ok(expand_env_placeholders(&merged_data))
 }
 
 #[cfg(test)]
@@ -728,6 +2087,14 @@ mod tests {
             registry: Some("test".to_string()),
             output_path: Some("out.proto".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         };
 
         assert_eq!(dep_with_slash.resolved_group_id(), "com.example");
@@ -742,6 +2109,14 @@ mod tests {
             registry: Some("test".to_string()),
             output_path: Some("out.proto".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         };
 
         assert_eq!(dep_simple.resolved_group_id(), "default");
@@ -756,6 +2131,14 @@ mod tests {
             registry: Some("test".to_string()),
             output_path: Some("out.proto".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         };
 
         assert_eq!(dep_explicit.resolved_group_id(), "custom.group");
@@ -770,6 +2153,14 @@ mod tests {
             registry: Some("nprod-apicurio".to_string()),
             output_path: Some("protos/sp/frame/frame.proto".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         };
 
         assert_eq!(dep_nprod.resolved_group_id(), "nprod");
@@ -787,6 +2178,14 @@ mod tests {
             registry: Some("test".to_string()),
             output_path: Some("out.proto".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         };
 
         assert_eq!(dep_multi_slash.resolved_group_id(), "com.example");
@@ -801,6 +2200,14 @@ mod tests {
             registry: Some("test".to_string()),
             output_path: Some("out.proto".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         };
 
         assert_eq!(dep_empty_group.resolved_group_id(), "");
@@ -815,6 +2222,14 @@ mod tests {
             registry: Some("test".to_string()),
             output_path: Some("out.proto".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         };
 
         assert_eq!(dep_empty_artifact.resolved_group_id(), "group.only");
@@ -829,6 +2244,14 @@ mod tests {
             registry: Some("test".to_string()),
             output_path: Some("out.proto".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         };
 
         assert_eq!(dep_partial_override.resolved_group_id(), "override.group");
@@ -843,6 +2266,14 @@ mod tests {
             registry: Some("test".to_string()),
             output_path: Some("out.proto".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         };
 
         assert_eq!(dep_partial_override2.resolved_group_id(), "com.example");
@@ -865,12 +2296,21 @@ mod tests {
             registry: Some("test".to_string()),
             output_path: Some("out.proto".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         };
 
         let publish = PublishConfig {
             name: name.to_string(),
             input_path: "input.proto".to_string(),
             version: "1.0.0".to_string(),
+            version_source: VersionSource::default(),
             registry: "test".to_string(),
             group_id: None,
             artifact_id: None,
@@ -879,6 +2319,7 @@ mod tests {
             description: None,
             labels: std::collections::HashMap::new(),
             references: Vec::new(),
+            binary: false,
         };
 
         assert_eq!(dep.resolved_group_id(), publish.resolved_group_id());
@@ -915,4 +2356,407 @@ mod tests {
         assert!(yaml_with_ref_patterns.contains("outputPatterns"));
         assert!(yaml_with_ref_patterns.contains("avro"));
     }
+
+    #[test]
+    fn test_validate_overrides() {
+        let cfg: RepoConfig =
+            serde_yaml::from_str("overrides:\n  com.example/common: 1.2.0\n").unwrap();
+        assert!(cfg.validate_overrides().is_ok());
+        assert_eq!(
+            cfg.version_override("com.example", "common").unwrap(),
+            "1.2.0"
+        );
+
+        let cfg: RepoConfig =
+            serde_yaml::from_str("overrides:\n  com.example/common: ^1.2\n").unwrap();
+        assert!(cfg.validate_overrides().is_err());
+        let cfg: RepoConfig = serde_yaml::from_str("overrides:\n  common: 1.2.0\n").unwrap();
+        assert!(cfg.validate_overrides().is_err());
+    }
+
+    #[test]
+    fn test_validate_publish_versions() {
+        let publishes = |entry: &str| -> RepoConfig {
+            serde_yaml::from_str(&format!(
+                "publishes:\n  - name: users\n    inputPath: users.proto\n    registry: dev\n{entry}"
+            ))
+            .unwrap()
+        };
+        let cfg = publishes("    fromCargo: Cargo.toml\n");
+        assert!(cfg.validate_publish_versions().is_ok());
+        assert_eq!(
+            cfg.publishes[0].version_source.from_cargo.as_deref(),
+            Some("Cargo.toml")
+        );
+        assert!(cfg.publishes[0].version.is_empty());
+        assert!(publishes("    version: 1.0.0\n    fromGitTag: true\n")
+            .validate_publish_versions()
+            .is_ok());
+        assert!(publishes("").validate_publish_versions().is_err());
+        assert!(publishes("    fromFile: VERSION\n    fromGitTag: true\n")
+            .validate_publish_versions()
+            .is_err());
+    }
+
+    #[test]
+    fn test_reference_exclude_patterns() {
+        let cfg: ReferenceResolutionConfig = serde_yaml::from_str(
+            "exclude:\n  - com.example.debug/*\n  - \"**/*-internal\"\n  - com.example/tmp-?\n",
+        )
+        .unwrap();
+        assert!(cfg.is_excluded("com.example.debug", "traces"));
+        assert!(!cfg.is_excluded("com.example.debugging", "traces"));
+        assert!(cfg.is_excluded("com.example", "orders-internal"));
+        assert!(cfg.is_excluded("com.example", "tmp-1"));
+        assert!(!cfg.is_excluded("com.example", "tmp-12"));
+        assert!(!cfg.is_excluded("com.example", "orders"));
+    }
+
+    #[test]
+    fn test_auth_config_field_names() {
+        let camel: AuthConfig =
+            serde_yaml::from_str("type: basic\nusername: admin\npasswordEnv: PW\n").unwrap();
+        let snake: AuthConfig =
+            serde_yaml::from_str("type: basic\nusername: admin\npassword_env: PW\n").unwrap();
+        assert_eq!(camel, snake);
+
+        let with_ref: AuthConfig =
+            serde_yaml::from_str("type: bearer\nsecretRef: vault://kv/apicurio#token\n").unwrap();
+        assert_eq!(
+            with_ref,
+            AuthConfig::Bearer {
+                token_env: String::new(),
+                secret_ref: Some("vault://kv/apicurio#token".to_string()),
+            }
+        );
+        let yaml = serde_yaml::to_string(&with_ref).unwrap();
+        assert_eq!(yaml, "type: bearer\nsecretRef: vault://kv/apicurio#token\n");
+
+        assert_eq!(camel.env_vars(), vec![("passwordEnv", "PW")]);
+        assert!(with_ref.env_vars().is_empty());
+        let env_ref: AuthConfig =
+            serde_yaml::from_str("type: token\nsecretRef: env://CI_TOKEN\n").unwrap();
+        assert_eq!(env_ref.env_vars(), vec![("secretRef", "CI_TOKEN")]);
+    }
+
+    #[test]
+    fn test_unresolved_placeholders() {
+        env::set_var("APICURIO_TEST_SET_PLACEHOLDER", "x");
+        let config = "url: ${APICURIO_TEST_UNSET_A}/${APICURIO_TEST_SET_PLACEHOLDER}\n\
+                      # url: ${APICURIO_TEST_UNSET_COMMENTED}\n\
+                      path: ${APICURIO_TEST_UNSET_B:-default} ${APICURIO_TEST_UNSET_A}\n";
+        assert_eq!(
+            unresolved_placeholders(config),
+            vec![
+                (1, "APICURIO_TEST_UNSET_A".to_string()),
+                (3, "APICURIO_TEST_UNSET_A".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_placeholders_names_file_and_line() {
+        let dir =
+            std::env::temp_dir().join(format!("apicurio-placeholders-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("apicurioconfig.yaml");
+        fs::write(
+            &config,
+            "include:\n  - path: local.yaml\n    optional: true\nexternalRegistriesFile: ${APICURIO_TEST_UNSET_OPT_OUT:-}\n",
+        )
+        .unwrap();
+        check_placeholders(&config).unwrap();
+
+        fs::write(
+            dir.join("local.yaml"),
+            "registries:\n  - name: dev\n    url: ${APICURIO_TEST_UNSET_URL}/apis\n",
+        )
+        .unwrap();
+        let err = check_placeholders(&config).unwrap_err();
+        assert!(matches!(err, ApicurioError::Config { .. }));
+        let message = err.to_string();
+        assert!(message.contains("APICURIO_TEST_UNSET_URL ("));
+        assert!(message.contains("local.yaml:3)"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_output_patterns_rejected_at_load() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_path = dir.path().join("apicurioconfig.yaml");
+        fs::write(
+            &config_path,
+            r#"
+registries: []
+dependencies: []
+dependencyDefaults:
+  outputPatterns:
+    protobuf: "protos/{artifactid}.proto"
+referenceResolution:
+  outputOverrides:
+    "nprod/a.B": "refs/{artifactId|shout}.proto"
+    "nprod/c.D": null
+"#,
+        )
+        .unwrap();
+
+        let err = format!("{:#}", load_repo_config(&config_path).unwrap_err());
+        assert!(err.contains("dependencyDefaults.outputPatterns.protobuf"));
+        assert!(err.contains("unknown placeholder '{artifactid}'"));
+        assert!(err.contains("referenceResolution.outputOverrides[\"nprod/a.B\"]"));
+        assert!(!err.contains("c.D"));
+    }
+
+    #[test]
+    fn test_include_fragments_are_merged_in_order() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_path = dir.path().join("apicurioconfig.yaml");
+        fs::write(
+            &config_path,
+            r#"
+include:
+  - overlays/team.yaml
+  - path: apicurioconfig.local.yaml
+    optional: true
+  - path: missing.yaml
+    optional: true
+registries:
+  - name: prod
+    url: https://prod.example.com
+dependencies:
+  - name: com.example/users
+    version: ^1.0.0
+    registry: prod
+dependencyDefaults:
+  registry: prod
+"#,
+        )
+        .unwrap();
+        fs::create_dir_all(dir.path().join("overlays")).unwrap();
+        fs::write(
+            dir.path().join("overlays/team.yaml"),
+            r#"
+dependencies:
+  - name: com.example/orders
+    version: ^2.0.0
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("apicurioconfig.local.yaml"),
+            r#"
+registries:
+  - name: prod
+    url: http://localhost:8080
+dependencies:
+  - name: com.example/users
+    version: ^1.5.0
+"#,
+        )
+        .unwrap();
+
+        let cfg = load_repo_config(&config_path).unwrap();
+        assert_eq!(cfg.registries.len(), 1);
+        assert_eq!(cfg.registries[0].url, "http://localhost:8080");
+        assert_eq!(cfg.dependencies.len(), 2);
+        assert_eq!(cfg.dependencies[0].name, "com.example/users");
+        assert_eq!(cfg.dependencies[0].version, "^1.5.0");
+        assert_eq!(cfg.dependencies[0].registry, None);
+        assert_eq!(cfg.dependencies[1].name, "com.example/orders");
+        assert_eq!(cfg.dependency_defaults.registry.as_deref(), Some("prod"));
+
+        let base = load_repo_config_without_includes(&config_path).unwrap();
+        assert_eq!(base.dependencies.len(), 1);
+        assert_eq!(base.include.len(), 3);
+    }
+
+    #[test]
+    fn test_json_config_with_yaml_include() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_path = dir.path().join("apicurioconfig.json");
+        fs::write(
+            &config_path,
+            r#"{
+	"include": ["team.yaml"],
+	"registries": [{ "name": "prod", "url": "${APICURIO_TEST_JSON_URL:-https://prod.example.com}" }],
+	"dependencies": [{ "name": "com.example/users", "version": "^1.0.0", "registry": "prod" }]
+}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("team.yaml"),
+            "dependencies:\n  - name: com.example/orders\n    version: ^2.0.0\n    registry: prod\n",
+        )
+        .unwrap();
+
+        let cfg = load_repo_config(&config_path).unwrap();
+        assert_eq!(cfg.registries[0].url, "https://prod.example.com");
+        assert_eq!(cfg.dependencies.len(), 2);
+        // The merged text stays JSON, with placeholders untouched for hashing
+        let merged: serde_json::Value =
+            serde_json::from_str(&read_merged_config(&config_path).unwrap()).unwrap();
+        assert_eq!(
+            merged["registries"][0]["url"],
+            "${APICURIO_TEST_JSON_URL:-https://prod.example.com}"
+        );
+
+        let saved = dir.path().join("saved.json");
+        save_repo_config(&cfg, &saved).unwrap();
+        let reloaded = load_repo_config_without_includes(&saved).unwrap();
+        assert_eq!(reloaded.dependencies.len(), 2);
+    }
+
+    #[test]
+    fn test_toml_config() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_path = dir.path().join("apicurioconfig.toml");
+        fs::write(
+            &config_path,
+            r#"include = ["team.yaml"]
+
+[[registries]]
+name = "prod"
+url = "${APICURIO_TEST_TOML_URL:-https://prod.example.com}"
+
+[[dependencies]]
+name = "com.example/users"
+version = "^1.0.0"
+registry = "prod"
+"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("team.yaml"),
+            "dependencies:\n  - name: com.example/orders\n    version: ^2.0.0\n    registry: prod\n",
+        )
+        .unwrap();
+
+        let cfg = load_repo_config(&config_path).unwrap();
+        assert_eq!(cfg.registries[0].url, "https://prod.example.com");
+        assert_eq!(cfg.dependencies.len(), 2);
+        let merged: toml::Value =
+            toml::from_str(&read_merged_config(&config_path).unwrap()).unwrap();
+        assert_eq!(merged["dependencies"].as_array().unwrap().len(), 2);
+
+        let saved = dir.path().join("saved.toml");
+        save_repo_config(&cfg, &saved).unwrap();
+        let reloaded = load_repo_config_without_includes(&saved).unwrap();
+        assert_eq!(reloaded.dependencies.len(), 2);
+    }
+
+    #[test]
+    fn test_missing_required_include_fails() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_path = dir.path().join("apicurioconfig.yaml");
+        fs::write(&config_path, "include:\n  - does-not-exist.yaml\n").unwrap();
+
+        let err = load_repo_config(&config_path).unwrap_err();
+        assert!(err.to_string().contains("does-not-exist.yaml"));
+    }
+
+    #[test]
+    fn test_profile_overrides_registries() {
+        let cfg: RepoConfig = serde_yaml::from_str(
+            r#"
+registries:
+  - name: main
+    url: https://dev.example.com
+    auth:
+      type: bearer
+      token_env: DEV_TOKEN
+profiles:
+  prod:
+    registries:
+      - name: main
+        url: https://prod.example.com
+      - name: extra
+        url: https://extra.example.com
+"#,
+        )
+        .unwrap();
+
+        let dev = cfg
+            .merge_registries_for_profile(GlobalConfig::default(), None)
+            .unwrap();
+        assert_eq!(dev.len(), 1);
+        assert_eq!(dev[0].url, "https://dev.example.com");
+
+        let mut prod = cfg
+            .merge_registries_for_profile(
+                GlobalConfig::default(),
+                Some(&ProfileSelection::explicit("prod")),
+            )
+            .unwrap();
+        prod.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(prod.len(), 2);
+        assert_eq!(prod[1].name, "main");
+        assert_eq!(prod[1].url, "https://prod.example.com");
+        assert_eq!(
+            prod[1].auth,
+            AuthConfig::Bearer {
+                token_env: "DEV_TOKEN".to_string(),
+                secret_ref: None,
+            }
+        );
+        assert_eq!(prod[0].url, "https://extra.example.com");
+
+        for qa in [
+            ProfileSelection::explicit("qa"),
+            ProfileSelection::ambient("qa"),
+        ] {
+            assert!(cfg
+                .merge_registries_for_profile(GlobalConfig::default(), Some(&qa))
+                .is_err());
+        }
+        // Configs without profiles only ignore a profile from APICURIO_ENV
+        assert!(RepoConfig::default()
+            .merge_registries_for_profile(
+                GlobalConfig::default(),
+                Some(&ProfileSelection::ambient("qa"))
+            )
+            .is_ok());
+        let err = RepoConfig::default()
+            .merge_registries_for_profile(
+                GlobalConfig::default(),
+                Some(&ProfileSelection::explicit("staging")),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown profile 'staging'"));
+    }
+
+    #[test]
+    fn test_timeouts_fall_back_per_field() {
+        let cfg: RepoConfig = serde_yaml::from_str(
+            r#"
+timeouts:
+  read: 30
+registries:
+  - name: slow
+    url: https://slow.example.com
+    timeouts:
+      read: 300
+  - name: plain
+    url: https://plain.example.com
+"#,
+        )
+        .unwrap();
+        let global = GlobalConfig {
+            timeouts: TimeoutConfig {
+                connect: Some(3),
+                read: Some(10),
+            },
+            ..Default::default()
+        };
+
+        let mut regs = cfg.merge_registries_for_profile(global, None).unwrap();
+        regs.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(regs[0].timeouts.connect, Some(3));
+        assert_eq!(regs[0].timeouts.read, Some(30));
+        assert_eq!(regs[1].timeouts.connect, Some(3));
+        assert_eq!(regs[1].timeouts.read, Some(300));
+        assert_eq!(
+            TimeoutConfig::default().connect_timeout(),
+            std::time::Duration::from_secs(crate::constants::DEFAULT_CONNECT_TIMEOUT_SECS)
+        );
+    }
 }
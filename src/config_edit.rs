@@ -0,0 +1,642 @@
+//! Comment-preserving edits of the repository configuration
+//!
+//! `serde_yaml` drops comments, so saving a re-serialized `RepoConfig` loses
+//! everything a user wrote by hand besides the values themselves. The helpers in
+//! this module edit top-level lists of named entries (such as `registries:`) and
+//! values at dotted key paths (such as `dependencyDefaults.registry`) in place:
+//! only the lines of the touched entry are rewritten, everything else —
+//! comments, blank lines, key order — is kept verbatim.
+
+use crate::config::{is_json_file, is_toml_file};
+use anyhow::{anyhow, bail, Context, Result};
+use serde_yaml::Value;
+use std::{fs, path::Path};
+
+/// Default indentation used for entries of a list that has none yet
+const DEFAULT_INDENT: usize = 2;
+
+/// Location of a top-level block list in the config text
+struct Section {
+    /// Line holding `<key>:`
+    key_line: usize,
+    /// Whether the list is written inline as `<key>: []`
+    inline_empty: bool,
+    /// One past the last content line of the block
+    end: usize,
+    /// Indentation of the `- ` item markers
+    indent: usize,
+    /// Line ranges of the entries, including comments directly above them
+    items: Vec<(usize, usize)>,
+}
+
+fn is_trivia(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_item_start(line: &str, indent: usize) -> bool {
+    if indent_of(line) != indent {
+        return false;
+    }
+    let rest = &line[indent..];
+    rest == "-" || rest.starts_with("- ")
+}
+
+fn find_section(lines: &[&str], key: &str) -> Result<Option<Section>> {
+    let prefix = format!("{key}:");
+    let Some(key_line) = lines.iter().position(|l| l.starts_with(&prefix)) else {
+        return Ok(None);
+    };
+
+    let rest = lines[key_line][prefix.len()..].trim();
+    let rest = rest.split(" #").next().unwrap_or("").trim();
+    let inline_empty = match rest {
+        "" => false,
+        _ if rest.starts_with('#') => false,
+        "[]" => true,
+        _ => bail!("'{key}' is written in flow style and cannot be edited in place"),
+    };
+
+    // The block continues while lines are indented (or are `- ` items at column 0)
+    let mut end = key_line + 1;
+    if !inline_empty {
+        for (i, line) in lines.iter().enumerate().skip(key_line + 1) {
+            if is_trivia(line) {
+                continue;
+            }
+            if line.starts_with(' ') || line.starts_with('-') {
+                end = i + 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    let indent = lines[key_line + 1..end]
+        .iter()
+        .find(|l| !is_trivia(l))
+        .map(|l| indent_of(l))
+        .unwrap_or(DEFAULT_INDENT);
+
+    let starts: Vec<usize> = (key_line + 1..end)
+        .filter(|&i| is_item_start(lines[i], indent))
+        .collect();
+    let mut items = Vec::with_capacity(starts.len());
+    for (n, &start) in starts.iter().enumerate() {
+        let next = starts.get(n + 1).copied().unwrap_or(end);
+        // Trailing comments belong to whatever follows; leading ones to this entry
+        let mut last = next;
+        while last > start + 1 && is_trivia(lines[last - 1]) {
+            last -= 1;
+        }
+        let mut first = start;
+        while first > key_line + 1 && lines[first - 1].trim_start().starts_with('#') {
+            first -= 1;
+        }
+        items.push((first, last));
+    }
+
+    Ok(Some(Section {
+        key_line,
+        inline_empty,
+        end,
+        indent,
+        items,
+    }))
+}
+
+fn parse_item(lines: &[&str]) -> Result<Value> {
+    let mut parsed: Vec<Value> = serde_yaml::from_str(&lines.join("\n"))?;
+    parsed
+        .pop()
+        .ok_or_else(|| anyhow!("empty list entry in configuration"))
+}
+
+fn entry_name(entry: &Value) -> Option<&str> {
+    entry.get("name").and_then(Value::as_str)
+}
+
+fn render_item(entry: &Value, indent: usize) -> Result<Vec<String>> {
+    let yaml = serde_yaml::to_string(&vec![entry])?;
+    let pad = " ".repeat(indent);
+    Ok(yaml.lines().map(|l| format!("{pad}{l}")).collect())
+}
+
+fn join_lines(lines: Vec<String>) -> String {
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+/// Return the raw (un-expanded) entries of a top-level list such as `registries`
+pub fn list_named_entries(content: &str, key: &str) -> Result<Vec<Value>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(section) = find_section(&lines, key)? else {
+        return Ok(Vec::new());
+    };
+    section
+        .items
+        .iter()
+        .map(|&(first, last)| parse_item(&lines[first..last]))
+        .collect()
+}
+
+/// Replace the entry named like `entry` in a top-level list, or append it
+///
+/// The list is created at the end of the document if it does not exist yet.
+pub fn upsert_named_entry(content: &str, key: &str, entry: &Value) -> Result<String> {
+    let name = entry_name(entry).ok_or_else(|| anyhow!("entry has no name"))?;
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+
+    let Some(section) = find_section(&lines, key)? else {
+        if out.last().is_some_and(|l| !l.trim().is_empty()) {
+            out.push(String::new());
+        }
+        out.push(format!("{key}:"));
+        out.extend(render_item(entry, DEFAULT_INDENT)?);
+        return Ok(join_lines(out));
+    };
+
+    let rendered = render_item(entry, section.indent)?;
+    if section.inline_empty {
+        out.splice(
+            section.key_line..=section.key_line,
+            std::iter::once(format!("{key}:")).chain(rendered),
+        );
+        return Ok(join_lines(out));
+    }
+
+    for &(first, last) in &section.items {
+        let existing = parse_item(&lines[first..last])?;
+        if entry_name(&existing) == Some(name) {
+            // Keep comments placed above the entry
+            let start = (first..last)
+                .find(|&i| is_item_start(lines[i], section.indent))
+                .unwrap_or(first);
+            out.splice(start..last, rendered);
+            return Ok(join_lines(out));
+        }
+    }
+
+    out.splice(section.end..section.end, rendered);
+    Ok(join_lines(out))
+}
+
+/// Replace the entry called `name` in a top-level list with `entry`, in place
+///
+/// Unlike [`upsert_named_entry`], `entry` may carry another name. Returns
+/// `None` if no such entry exists.
+pub fn replace_named_entry(
+    content: &str,
+    key: &str,
+    name: &str,
+    entry: &Value,
+) -> Result<Option<String>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(section) = find_section(&lines, key)? else {
+        return Ok(None);
+    };
+
+    for &(first, last) in &section.items {
+        let existing = parse_item(&lines[first..last])?;
+        if entry_name(&existing) != Some(name) {
+            continue;
+        }
+        let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        // Keep comments placed above the entry
+        let start = (first..last)
+            .find(|&i| is_item_start(lines[i], section.indent))
+            .unwrap_or(first);
+        out.splice(start..last, render_item(entry, section.indent)?);
+        return Ok(Some(join_lines(out)));
+    }
+    Ok(None)
+}
+
+/// Remove the entry with the given name from a top-level list
+///
+/// Returns `None` if no such entry exists.
+pub fn remove_named_entry(content: &str, key: &str, name: &str) -> Result<Option<String>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(section) = find_section(&lines, key)? else {
+        return Ok(None);
+    };
+
+    for &(first, last) in &section.items {
+        let existing = parse_item(&lines[first..last])?;
+        if entry_name(&existing) != Some(name) {
+            continue;
+        }
+        let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        out.drain(first..last);
+        if section.items.len() == 1 {
+            // An empty block would parse as null rather than an empty list
+            out[section.key_line] = format!("{key}: []");
+        }
+        return Ok(Some(join_lines(out)));
+    }
+    Ok(None)
+}
+
+/// Split a dotted key path such as `dependencyDefaults.registry` into its keys
+fn key_path(path: &str) -> Result<Vec<&str>> {
+    let keys: Vec<&str> = path.split('.').collect();
+    if keys.iter().any(|k| k.is_empty()) {
+        bail!("invalid key path '{path}'");
+    }
+    Ok(keys)
+}
+
+/// Find the line holding `<key>:` at exactly `indent` within `start..end`
+fn find_key(lines: &[&str], start: usize, end: usize, indent: usize, key: &str) -> Option<usize> {
+    (start..end).find(|&i| {
+        let line = lines[i];
+        !is_trivia(line)
+            && indent_of(line) == indent
+            && line[indent..].strip_prefix(key).is_some_and(|rest| {
+                rest == ":" || rest.starts_with(": ") || rest.starts_with(":\t")
+            })
+    })
+}
+
+/// One past the last content line of the value that starts at `key_line`
+fn value_end(lines: &[&str], key_line: usize, indent: usize) -> usize {
+    let mut end = key_line + 1;
+    for (i, line) in lines.iter().enumerate().skip(key_line + 1) {
+        if is_trivia(line) {
+            continue;
+        }
+        // Block sequences may sit at the same indentation as their key
+        if indent_of(line) > indent || is_item_start(line, indent) {
+            end = i + 1;
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+/// The value written on the key line itself, without a trailing comment
+fn inline_value<'a>(line: &'a str, indent: usize, key: &str) -> (&'a str, Option<&'a str>) {
+    let rest = &line[indent + key.len() + 1..];
+    match rest.find(" #") {
+        Some(pos) => (rest[..pos].trim(), Some(&rest[pos..])),
+        None => (rest.trim(), None),
+    }
+}
+
+fn render_key_value(key: &str, value: &Value, indent: usize) -> Result<Vec<String>> {
+    let mut mapping = serde_yaml::Mapping::new();
+    mapping.insert(Value::String(key.to_string()), value.clone());
+    let yaml = serde_yaml::to_string(&mapping)?;
+    let pad = " ".repeat(indent);
+    Ok(yaml.lines().map(|l| format!("{pad}{l}")).collect())
+}
+
+/// Read the value at a dotted key path, `None` if it is not set
+pub fn get_value(content: &str, path: &str) -> Result<Option<Value>> {
+    let mut current: Value = serde_yaml::from_str(content)?;
+    for key in key_path(path)? {
+        match current.get(key) {
+            Some(value) => current = value.clone(),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(current))
+}
+
+/// Set the value at a dotted key path, creating missing parent mappings
+pub fn set_value(content: &str, path: &str, value: &Value) -> Result<String> {
+    let keys = key_path(path)?;
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+    let (mut start, mut end, mut indent) = (0, lines.len(), 0);
+
+    for (depth, key) in keys.iter().enumerate() {
+        let Some(key_line) = find_key(&lines, start, end, indent, key) else {
+            // Append the missing keys at the end of the deepest existing mapping
+            let mut insert_at = end;
+            while insert_at > start && is_trivia(lines[insert_at - 1]) {
+                insert_at -= 1;
+            }
+            let mut rendered = Vec::new();
+            let mut pad = indent;
+            for parent in &keys[depth..keys.len() - 1] {
+                rendered.push(format!("{}{parent}:", " ".repeat(pad)));
+                pad += DEFAULT_INDENT;
+            }
+            rendered.extend(render_key_value(keys[keys.len() - 1], value, pad)?);
+            out.splice(insert_at..insert_at, rendered);
+            return Ok(join_lines(out));
+        };
+
+        let block_end = value_end(&lines, key_line, indent);
+        let (inline, comment) = inline_value(lines[key_line], indent, key);
+
+        if depth == keys.len() - 1 {
+            let mut rendered = render_key_value(key, value, indent)?;
+            if let (1, Some(comment)) = (rendered.len(), comment) {
+                rendered[0].push_str(comment);
+            }
+            out.splice(key_line..block_end, rendered);
+            return Ok(join_lines(out));
+        }
+
+        let parent = keys[..=depth].join(".");
+        match inline {
+            "" => {}
+            "{}" => {
+                // Turn `key: {}` into a block mapping holding the new value
+                let mut rendered = vec![format!("{}{key}:", " ".repeat(indent))];
+                let mut pad = indent + DEFAULT_INDENT;
+                for child in &keys[depth + 1..keys.len() - 1] {
+                    rendered.push(format!("{}{child}:", " ".repeat(pad)));
+                    pad += DEFAULT_INDENT;
+                }
+                rendered.extend(render_key_value(keys[keys.len() - 1], value, pad)?);
+                out.splice(key_line..=key_line, rendered);
+                return Ok(join_lines(out));
+            }
+            _ => bail!("'{parent}' is not a mapping and cannot hold '{path}'"),
+        }
+        let child = lines[key_line + 1..block_end]
+            .iter()
+            .find(|l| !is_trivia(l));
+        if child.is_some_and(|l| is_item_start(l, indent_of(l))) {
+            bail!("'{parent}' is a list; only mapping keys can be set");
+        }
+        start = key_line + 1;
+        end = block_end;
+        indent = child
+            .map(|l| indent_of(l))
+            .unwrap_or(indent + DEFAULT_INDENT);
+    }
+    unreachable!("key path has at least one key")
+}
+
+/// Remove the value at a dotted key path
+///
+/// Returns `None` if the key is not set. A parent mapping left without keys is
+/// written as `{}` so it keeps parsing as a mapping.
+pub fn unset_value(content: &str, path: &str) -> Result<Option<String>> {
+    let keys = key_path(path)?;
+    let lines: Vec<&str> = content.lines().collect();
+    let (mut start, mut end, mut indent) = (0, lines.len(), 0);
+    let mut parent: Option<(usize, usize)> = None;
+
+    for (depth, key) in keys.iter().enumerate() {
+        let Some(key_line) = find_key(&lines, start, end, indent, key) else {
+            return Ok(None);
+        };
+        let block_end = value_end(&lines, key_line, indent);
+
+        if depth == keys.len() - 1 {
+            let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+            out.drain(key_line..block_end);
+            if let Some((parent_line, parent_indent)) = parent {
+                let has_children = (start..end)
+                    .filter(|&i| i < key_line || i >= block_end)
+                    .any(|i| !is_trivia(lines[i]));
+                if !has_children {
+                    out[parent_line] =
+                        format!("{}{}: {{}}", " ".repeat(parent_indent), keys[depth - 1]);
+                }
+            }
+            return Ok(Some(join_lines(out)));
+        }
+
+        if !inline_value(lines[key_line], indent, key).0.is_empty() {
+            return Ok(None);
+        }
+        let Some(child) = lines[key_line + 1..block_end]
+            .iter()
+            .find(|l| !is_trivia(l))
+        else {
+            return Ok(None);
+        };
+        parent = Some((key_line, indent));
+        start = key_line + 1;
+        end = block_end;
+        indent = indent_of(child);
+    }
+    unreachable!("key path has at least one key")
+}
+
+/// Read a config file as the YAML text the helpers above edit
+///
+/// A JSON config (no comments to keep) is converted to YAML; [`write_edited`]
+/// converts the edited text back. So is a TOML config, losing its comments.
+pub fn read_editable(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("reading config from {}", path.display()))?;
+    let value: Value = if is_json_file(path) {
+        serde_json::from_str(&content)
+    } else if is_toml_file(path) {
+        toml::from_str(&content).map_err(serde::de::Error::custom)
+    } else {
+        return Ok(content);
+    }
+    .with_context(|| format!("invalid configuration in {}", path.display()))?;
+    Ok(serde_yaml::to_string(&value)?)
+}
+
+/// Write text produced by the helpers above, as JSON or TOML again for a JSON
+/// or TOML config
+pub fn write_edited(path: &Path, content: &str) -> Result<()> {
+    let data = if is_json_file(path) {
+        let value: Value = serde_yaml::from_str(content)?;
+        serde_json::to_string_pretty(&value)? + "\n"
+    } else if is_toml_file(path) {
+        let value: Value = serde_yaml::from_str(content)?;
+        toml::to_string(&value)?
+    } else {
+        content.to_string()
+    };
+    fs::write(path, data).with_context(|| format!("writing {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+# Project registries
+registries:
+  # Main registry
+  - name: main
+    url: https://main.example.com # primary
+  # Legacy one, to be removed
+  - name: legacy
+    url: https://legacy.example.com
+
+# Dependencies below
+dependencies: []
+";
+
+    fn registry(name: &str, url: &str) -> Value {
+        serde_yaml::from_str(&format!("name: {name}\nurl: {url}\n")).unwrap()
+    }
+
+    #[test]
+    fn test_list_named_entries() {
+        let entries = list_named_entries(CONFIG, "registries").unwrap();
+        let names: Vec<_> = entries.iter().filter_map(entry_name).collect();
+        assert_eq!(names, vec!["main", "legacy"]);
+        assert!(list_named_entries(CONFIG, "publishes").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_upsert_appends_and_keeps_comments() {
+        let updated =
+            upsert_named_entry(CONFIG, "registries", &registry("dev", "http://dev")).unwrap();
+        assert!(updated.contains("# Main registry"));
+        assert!(updated.contains("# primary"));
+        assert!(updated.contains("# Dependencies below"));
+        assert!(updated.contains("  - name: dev\n    url: http://dev\n"));
+
+        let names: Vec<_> = list_named_entries(&updated, "registries")
+            .unwrap()
+            .iter()
+            .filter_map(|e| entry_name(e).map(str::to_string))
+            .collect();
+        assert_eq!(names, vec!["main", "legacy", "dev"]);
+    }
+
+    #[test]
+    fn test_upsert_replaces_existing_entry() {
+        let updated =
+            upsert_named_entry(CONFIG, "registries", &registry("main", "http://new")).unwrap();
+        assert!(updated.contains("# Main registry\n  - name: main\n    url: http://new\n"));
+        assert!(!updated.contains("https://main.example.com"));
+        assert!(updated.contains("https://legacy.example.com"));
+    }
+
+    #[test]
+    fn test_upsert_creates_missing_or_inline_list() {
+        let created =
+            upsert_named_entry("dependencies: []\n", "registries", &registry("a", "u")).unwrap();
+        assert_eq!(
+            created,
+            "dependencies: []\n\nregistries:\n  - name: a\n    url: u\n"
+        );
+
+        let inline =
+            upsert_named_entry("registries: []\n", "registries", &registry("a", "u")).unwrap();
+        assert_eq!(inline, "registries:\n  - name: a\n    url: u\n");
+    }
+
+    #[test]
+    fn test_replace_named_entry_renames_in_place() {
+        let updated = replace_named_entry(
+            CONFIG,
+            "registries",
+            "main",
+            &registry("primary", "http://new"),
+        )
+        .unwrap()
+        .unwrap();
+        assert!(updated.contains("# Main registry\n  - name: primary\n    url: http://new\n"));
+        let names: Vec<_> = list_named_entries(&updated, "registries")
+            .unwrap()
+            .iter()
+            .filter_map(|e| entry_name(e).map(str::to_string))
+            .collect();
+        assert_eq!(names, vec!["primary", "legacy"]);
+
+        assert!(
+            replace_named_entry(CONFIG, "registries", "missing", &registry("a", "u"))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_remove_named_entry() {
+        let updated = remove_named_entry(CONFIG, "registries", "legacy")
+            .unwrap()
+            .unwrap();
+        assert!(!updated.contains("legacy"));
+        assert!(updated.contains("# Main registry"));
+        assert!(updated.contains("\n# Dependencies below"));
+
+        let emptied = remove_named_entry(&updated, "registries", "main")
+            .unwrap()
+            .unwrap();
+        assert!(emptied.contains("registries: []"));
+        assert!(list_named_entries(&emptied, "registries")
+            .unwrap()
+            .is_empty());
+
+        assert!(remove_named_entry(CONFIG, "registries", "missing")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_get_value() {
+        let content = "dependencyDefaults:\n  registry: prod # default\n";
+        assert_eq!(
+            get_value(content, "dependencyDefaults.registry").unwrap(),
+            Some(Value::from("prod"))
+        );
+        assert_eq!(
+            get_value(content, "dependencyDefaults.missing").unwrap(),
+            None
+        );
+        assert!(get_value(content, "dependencyDefaults.").is_err());
+    }
+
+    #[test]
+    fn test_set_value_replaces_in_place() {
+        let content = "# top\ndependencyDefaults:\n  # which one\n  registry: prod # default\n\nregistries: []\n";
+        let updated =
+            set_value(content, "dependencyDefaults.registry", &Value::from("dev")).unwrap();
+        assert_eq!(
+            updated,
+            "# top\ndependencyDefaults:\n  # which one\n  registry: dev # default\n\nregistries: []\n"
+        );
+    }
+
+    #[test]
+    fn test_set_value_creates_missing_keys() {
+        let content = "dependencyDefaults:\n  registry: prod\n\n# Registries\nregistries: []\n";
+        let updated = set_value(
+            content,
+            "dependencyDefaults.outputPatterns.protobuf",
+            &Value::from("protos/{artifactId}.proto"),
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            "dependencyDefaults:\n  registry: prod\n  outputPatterns:\n    protobuf: protos/{artifactId}.proto\n\n# Registries\nregistries: []\n"
+        );
+
+        let created = set_value("registries: []\n", "timeouts.read", &Value::from(30)).unwrap();
+        assert_eq!(created, "registries: []\ntimeouts:\n  read: 30\n");
+
+        let from_empty = set_value("timeouts: {}\n", "timeouts.connect", &Value::from(5)).unwrap();
+        assert_eq!(from_empty, "timeouts:\n  connect: 5\n");
+
+        assert!(set_value(CONFIG, "registries.main", &Value::from(1)).is_err());
+    }
+
+    #[test]
+    fn test_unset_value() {
+        let content = "timeouts:\n  connect: 5\n  read: 30\nregistries: []\n";
+        let updated = unset_value(content, "timeouts.connect").unwrap().unwrap();
+        assert_eq!(updated, "timeouts:\n  read: 30\nregistries: []\n");
+
+        let emptied = unset_value(&updated, "timeouts.read").unwrap().unwrap();
+        assert_eq!(emptied, "timeouts: {}\nregistries: []\n");
+
+        let top_level = unset_value(content, "timeouts").unwrap().unwrap();
+        assert_eq!(top_level, "registries: []\n");
+
+        assert!(unset_value(content, "timeouts.missing").unwrap().is_none());
+    }
+}
@@ -1,2 +1,20 @@
 pub const APICURIO_CONFIG: &str = "apicurioconfig.yaml";
 pub const APICURIO_LOCK: &str = "apicuriolock.yaml";
+/// JSON form of the config, used when no YAML config exists
+pub const APICURIO_CONFIG_JSON: &str = "apicurioconfig.json";
+/// TOML form of the config, used when no YAML or JSON config exists
+pub const APICURIO_CONFIG_TOML: &str = "apicurioconfig.toml";
+/// Lock file kept next to a JSON config
+pub const APICURIO_LOCK_JSON: &str = "apicuriolock.json";
+/// Prefix of the temporary directory downloads are staged in before being moved into place
+pub const APICURIO_STAGING_DIR: &str = ".apicurio-staging";
+/// Advisory lock file held by commands writing the lock file or output files
+pub const APICURIO_PROCESS_LOCK: &str = ".apicurio.lock";
+
+/// Default time allowed to connect to a registry, in seconds
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+/// Default time a registry may stay silent while answering (waiting for the
+/// response or between two reads of its body), in seconds
+pub const DEFAULT_READ_TIMEOUT_SECS: u64 = 60;
+/// Default time to wait for another process holding the project lock, in seconds
+pub const DEFAULT_PROCESS_LOCK_TIMEOUT_SECS: u64 = 300;
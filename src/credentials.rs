@@ -0,0 +1,324 @@
+//! Registry credentials from `.netrc` files and credential helpers
+//!
+//! `netrc` auth looks the registry host up in a `.netrc` file (`$NETRC`, then
+//! `~/.netrc`, or `~/_netrc` on Windows). `credentialHelper` auth asks an
+//! executable speaking the git credential protocol: it is run with `get`
+//! appended, receives `protocol=`, `host=` and `path=` lines on stdin and
+//! answers with `username=` and `password=` lines. Both reuse whatever already
+//! distributes secrets on the machine (`git-credential-store`, the macOS
+//! keychain helper, a company-provided helper).
+//!
+//! `exec` auth runs a command printing a bearer token, either bare or as a
+//! Kubernetes `ExecCredential` (`status.token`), so tools such as
+//! `kubectl oidc-login get-token` hand their short-lived SSO tokens over
+//! without an environment variable.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex, OnceLock},
+};
+use tokio::sync::OnceCell;
+
+/// A username and password, or a token when there is no username
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Login {
+    pub username: Option<String>,
+    pub password: String,
+}
+
+/// The login for `url` in a `.netrc` file
+///
+/// `machine` overrides the host looked up; `file` the default netrc location.
+pub fn from_netrc(url: &str, file: Option<&str>, machine: Option<&str>) -> Result<Login> {
+    let path = match file {
+        Some(file) => expand_home(file),
+        None => default_netrc().ok_or_else(|| anyhow!("no home directory to find .netrc in"))?,
+    };
+    let host = match machine {
+        Some(machine) => machine.to_string(),
+        None => host_of(url)?,
+    };
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    parse_netrc(&content, &host)
+        .ok_or_else(|| anyhow!("{} has no login for machine '{host}'", path.display()))
+}
+
+/// The entry of `host` in netrc `content`, falling back to its `default` entry
+pub fn parse_netrc(content: &str, host: &str) -> Option<Login> {
+    let mut tokens = content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(str::split_whitespace);
+    let mut found: Option<Login> = None;
+    let mut fallback: Option<Login> = None;
+    // (is the current entry the one looked up, is it `default`)
+    let mut current: Option<(bool, bool)> = None;
+    let mut entry = Login {
+        username: None,
+        password: String::new(),
+    };
+    let mut finish = |current: Option<(bool, bool)>, entry: &mut Login| {
+        let login = std::mem::replace(
+            entry,
+            Login {
+                username: None,
+                password: String::new(),
+            },
+        );
+        match current {
+            Some((true, _)) if found.is_none() => found = Some(login),
+            Some((_, true)) if fallback.is_none() => fallback = Some(login),
+            _ => {}
+        }
+    };
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => {
+                finish(current, &mut entry);
+                current = Some((tokens.next() == Some(host), false));
+            }
+            "default" => {
+                finish(current, &mut entry);
+                current = Some((false, true));
+            }
+            "login" => entry.username = tokens.next().map(str::to_string),
+            "password" => entry.password = tokens.next().unwrap_or_default().to_string(),
+            "account" => {
+                tokens.next();
+            }
+            // Macro definitions run to the next empty line, which the token
+            // stream cannot see; netrc files for registries do not use them
+            "macdef" => {
+                finish(current, &mut entry);
+                current = None;
+            }
+            _ => {}
+        }
+    }
+    finish(current, &mut entry);
+    found.or(fallback)
+}
+
+/// Ask the git-credential style helper `command` for the login of `url`
+pub fn from_helper(command: &str, url: &str) -> Result<Login> {
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| anyhow!("the credential helper command is empty"))?;
+    let parsed = reqwest::Url::parse(url).with_context(|| format!("invalid registry URL {url}"))?;
+    let mut request = format!("protocol={}\nhost={}\n", parsed.scheme(), host_of(url)?);
+    let path = parsed.path().trim_start_matches('/');
+    if !path.is_empty() {
+        request.push_str(&format!("path={path}\n"));
+    }
+    request.push('\n');
+
+    let mut child = Command::new(program)
+        .args(words)
+        .arg("get")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("running credential helper '{program}'"))?;
+    child
+        .stdin
+        .take()
+        .expect("piped stdin")
+        .write_all(request.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!(
+            "credential helper '{program}' failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    parse_helper_answer(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| anyhow!("credential helper '{program}' returned no password"))
+}
+
+/// The `username=`/`password=` lines of a credential helper answer
+fn parse_helper_answer(answer: &str) -> Option<Login> {
+    let mut username = None;
+    let mut password = None;
+    for line in answer.lines() {
+        match line.split_once('=') {
+            Some(("username", value)) => username = Some(value.to_string()),
+            Some(("password", value)) => password = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    Some(Login {
+        username: username.filter(|u| !u.is_empty()),
+        password: password.filter(|p| !p.is_empty())?,
+    })
+}
+
+/// A command printing a bearer token, run when the token is first needed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExecPlugin {
+    pub command: String,
+    pub args: Vec<String>,
+}
+
+impl ExecPlugin {
+    /// The token of the command, running it on the first call of the process
+    ///
+    /// Clients of registries sharing the command share its token; a failed run
+    /// is retried on the next call.
+    pub async fn token(&self) -> Result<String> {
+        static TOKENS: OnceLock<Mutex<HashMap<ExecPlugin, Arc<OnceCell<String>>>>> =
+            OnceLock::new();
+        let cell = TOKENS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(self.clone())
+            .or_default()
+            .clone();
+        // Concurrent requests wait on the cell, so the command runs once
+        cell.get_or_try_init(|| self.run()).await.cloned()
+    }
+
+    async fn run(&self) -> Result<String> {
+        let output = tokio::process::Command::new(&self.command)
+            .args(&self.args)
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .output()
+            .await
+            .with_context(|| format!("running exec auth command '{}'", self.command))?;
+        if !output.status.success() {
+            bail!(
+                "exec auth command '{}' failed ({})",
+                self.command,
+                output.status
+            );
+        }
+        parse_exec_output(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| anyhow!("exec auth command '{}' printed no token", self.command))
+    }
+}
+
+/// The token of an `ExecCredential` document, or the trimmed output itself
+fn parse_exec_output(output: &str) -> Option<String> {
+    let output = output.trim();
+    let token = match serde_json::from_str::<Value>(output) {
+        Ok(document) if document.is_object() => document
+            .pointer("/status/token")
+            .and_then(Value::as_str)?
+            .to_string(),
+        _ => output.to_string(),
+    };
+    (!token.is_empty()).then_some(token)
+}
+
+/// Host, with a non-default port, of a registry URL
+fn host_of(url: &str) -> Result<String> {
+    let parsed = reqwest::Url::parse(url).with_context(|| format!("invalid registry URL {url}"))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| anyhow!("registry URL {url} has no host"))?;
+    Ok(match parsed.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_string(),
+    })
+}
+
+fn default_netrc() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("NETRC").filter(|p| !p.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    let home = dirs::home_dir()?;
+    let name = if cfg!(windows) { "_netrc" } else { ".netrc" };
+    Some(home.join(name))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netrc_helper_and_exec_output() {
+        let netrc = "# registries\nmachine registry.example.com login ci password s3cret\n\
+                     machine other.example.com\n  login bob\n  password hunter2\n\
+                     default login anonymous password guest\n";
+        let login = |username: &str, password: &str| Login {
+            username: Some(username.to_string()),
+            password: password.to_string(),
+        };
+        assert_eq!(
+            parse_netrc(netrc, "registry.example.com"),
+            Some(login("ci", "s3cret"))
+        );
+        assert_eq!(
+            parse_netrc(netrc, "other.example.com"),
+            Some(login("bob", "hunter2"))
+        );
+        assert_eq!(
+            parse_netrc(netrc, "unknown.example.com"),
+            Some(login("anonymous", "guest"))
+        );
+        assert_eq!(parse_netrc("machine a login x password y", "b"), None);
+
+        assert_eq!(
+            parse_helper_answer("protocol=https\nhost=r\nusername=ci\npassword=tok\n"),
+            Some(login("ci", "tok"))
+        );
+        assert_eq!(
+            parse_helper_answer("password=tok\n"),
+            Some(Login {
+                username: None,
+                password: "tok".to_string()
+            })
+        );
+        assert_eq!(parse_helper_answer("quit=1\n"), None);
+
+        assert_eq!(parse_exec_output("  eyJabc\n").as_deref(), Some("eyJabc"));
+        let exec_credential = r#"{"kind":"ExecCredential","status":{"token":"eyJdef"}}"#;
+        assert_eq!(
+            parse_exec_output(exec_credential).as_deref(),
+            Some("eyJdef")
+        );
+        assert_eq!(parse_exec_output(r#"{"status":{}}"#), None);
+        assert_eq!(parse_exec_output("\n"), None);
+        assert_eq!(
+            host_of("http://localhost:8080/apis").unwrap(),
+            "localhost:8080"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_exec_command_runs_once_per_process() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let runs = dir.path().join("runs");
+        let plugin = ExecPlugin {
+            command: "sh".to_string(),
+            args: vec![
+                "-c".to_string(),
+                format!("echo run >> '{}'; echo eyJonce", runs.display()),
+            ],
+        };
+        let (first, second) = tokio::join!(plugin.token(), plugin.token());
+        assert_eq!(first.unwrap(), "eyJonce");
+        assert_eq!(second.unwrap(), "eyJonce");
+        assert_eq!(plugin.token().await.unwrap(), "eyJonce");
+        assert_eq!(fs::read_to_string(&runs).unwrap(), "run\n");
+    }
+}
@@ -1,5 +1,5 @@
 use crate::config::{DependencyConfig, DependencyDefaultsConfig};
-use anyhow::Result;
+use crate::error::{ApicurioError, Result};
 use semver::VersionReq;
 
 pub struct Dependency {
@@ -16,19 +16,36 @@ impl Dependency {
         cfg: &DependencyConfig,
         defaults: &DependencyDefaultsConfig,
     ) -> Result<Self> {
+        if cfg.is_group() {
+            return Err(ApicurioError::config(format!(
+                "dependency '{}' is a group; name one of its artifacts (`{}/<artifactId>`) instead",
+                cfg.name, cfg.name
+            )));
+        }
         let registry = cfg
             .registry
             .clone()
             .or_else(|| defaults.registry.clone())
             .ok_or_else(|| {
-                anyhow::anyhow!("No registry specified for dependency '{}'", cfg.name)
+                ApicurioError::config(format!(
+                    "No registry specified for dependency '{}'",
+                    cfg.name
+                ))
             })?;
 
         Ok(Dependency {
             name: cfg.name.clone(),
             group_id: cfg.resolved_group_id(),
             artifact_id: cfg.resolved_artifact_id(),
-            req: VersionReq::parse(&cfg.version)?,
+            req: VersionReq::parse(&cfg.version).map_err(|e| {
+                ApicurioError::config_caused(
+                    format!(
+                        "invalid version '{}' of dependency '{}'",
+                        cfg.version, cfg.name
+                    ),
+                    e,
+                )
+            })?,
             registry,
             output_path: cfg.output_path.clone(),
         })
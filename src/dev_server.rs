@@ -0,0 +1,1111 @@
+//! In-memory registry serving the subset of the Apicurio Registry v3 API the
+//! CLI uses
+//!
+//! `apicurio dev-server` runs it for workshops and demos; tests (including
+//! those of crates embedding the library) start one on a free port and point a
+//! [`RegistryConfig`] at it:
+//!
+//! ```no_run
+//! # async fn example() -> std::io::Result<()> {
+//! use apicurio_cli::{dev_server::DevServer, registry::RegistryClient};
+//!
+//! let server = DevServer::start("127.0.0.1:0").await?;
+//! server.add_version("com.example", "users", "PROTOBUF", "1.0.0", "syntax = \"proto3\";");
+//! let client = RegistryClient::new(&server.registry_config("dev")).unwrap();
+//! let versions = client.list_versions("com.example", "users").await.unwrap();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Groups, artifacts, versions, labels, references, states and rules are kept
+//! in memory; rules are stored but not enforced. Every connection answers one
+//! request and is closed.
+
+use crate::{config::RegistryConfig, registry::accept_for};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    task::JoinHandle,
+};
+
+/// Prefix of every API path
+const API_PREFIX: &str = "/apis/registry/v3";
+/// Page size when a list request has no `limit`, as in Apicurio Registry
+const DEFAULT_PAGE_SIZE: usize = 20;
+/// Largest request (headers and body) accepted
+const MAX_REQUEST_BYTES: usize = 16 * 1024 * 1024;
+
+/// Configuration properties served under `/admin/config/properties`, with their defaults
+const CONFIG_PROPERTIES: &[(&str, &str, &str)] = &[
+    (
+        "apicurio.rest.deletion.artifact.enabled",
+        "true",
+        "Delete artifact",
+    ),
+    (
+        "apicurio.rest.deletion.artifact-version.enabled",
+        "true",
+        "Delete artifact version",
+    ),
+    (
+        "apicurio.rest.deletion.group.enabled",
+        "true",
+        "Delete group",
+    ),
+];
+
+/// A running in-memory registry; stops when dropped
+pub struct DevServer {
+    addr: SocketAddr,
+    store: Arc<Mutex<Store>>,
+    task: JoinHandle<()>,
+}
+
+impl DevServer {
+    /// Listen on `addr` (port 0 picks a free port) and serve in the background
+    pub async fn start(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let store = Arc::new(Mutex::new(Store::default()));
+        let shared = Arc::clone(&store);
+        let task = tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                let store = Arc::clone(&shared);
+                tokio::spawn(async move {
+                    // A client hanging up mid-request is not the server's problem
+                    let _ = serve_connection(socket, &store).await;
+                });
+            }
+        });
+        Ok(Self { addr, store, task })
+    }
+
+    /// Address the server listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Base URL to configure as a registry `url`
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// A registry named `name` pointing at this server, without auth
+    pub fn registry_config(&self, name: &str) -> RegistryConfig {
+        RegistryConfig {
+            name: name.to_string(),
+            url: self.url(),
+            ..Default::default()
+        }
+    }
+
+    /// Store a version (creating its artifact), as a publish would
+    ///
+    /// The content type follows `artifact_type` (e.g. `PROTOBUF`, `AVRO`).
+    pub fn add_version(
+        &self,
+        group_id: &str,
+        artifact_id: &str,
+        artifact_type: &str,
+        version: &str,
+        content: impl Into<Vec<u8>>,
+    ) {
+        let content_type = accept_for(Some(artifact_type), None).unwrap_or("application/json");
+        let mut store = self.store.lock().unwrap();
+        store.artifact_or_insert(group_id, artifact_id, artifact_type);
+        store.insert_version(
+            group_id,
+            artifact_id,
+            NewVersion {
+                version: Some(version.to_string()),
+                content: content.into(),
+                content_type: content_type.to_string(),
+                references: Vec::new(),
+                name: None,
+                description: None,
+                draft: false,
+            },
+        );
+    }
+
+    /// Serve until the process ends
+    pub async fn wait(mut self) {
+        let _ = (&mut self.task).await;
+    }
+}
+
+impl Drop for DevServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// A version reference, as sent and answered by the registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Reference {
+    #[serde(default)]
+    group_id: Option<String>,
+    artifact_id: String,
+    version: String,
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Store {
+    groups: BTreeMap<String, BTreeMap<String, Artifact>>,
+    global_rules: BTreeMap<String, String>,
+    /// Overridden configuration properties
+    config_properties: BTreeMap<String, String>,
+    last_id: i64,
+}
+
+#[derive(Debug, Default)]
+struct Artifact {
+    artifact_type: String,
+    name: Option<String>,
+    description: Option<String>,
+    labels: BTreeMap<String, String>,
+    rules: BTreeMap<String, String>,
+    versions: Vec<StoredVersion>,
+}
+
+#[derive(Debug)]
+struct StoredVersion {
+    version: String,
+    content: Vec<u8>,
+    content_type: String,
+    references: Vec<Reference>,
+    name: Option<String>,
+    description: Option<String>,
+    state: String,
+    global_id: i64,
+    content_id: i64,
+    created_on: String,
+}
+
+/// A version about to be stored
+struct NewVersion {
+    /// `None` numbers the version after the existing ones
+    version: Option<String>,
+    content: Vec<u8>,
+    content_type: String,
+    references: Vec<Reference>,
+    name: Option<String>,
+    description: Option<String>,
+    draft: bool,
+}
+
+impl Store {
+    fn artifact(&self, group_id: &str, artifact_id: &str) -> Option<&Artifact> {
+        self.groups.get(group_id)?.get(artifact_id)
+    }
+
+    fn artifact_mut(&mut self, group_id: &str, artifact_id: &str) -> Option<&mut Artifact> {
+        self.groups.get_mut(group_id)?.get_mut(artifact_id)
+    }
+
+    fn artifact_or_insert(
+        &mut self,
+        group_id: &str,
+        artifact_id: &str,
+        artifact_type: &str,
+    ) -> &mut Artifact {
+        self.groups
+            .entry(group_id.to_string())
+            .or_default()
+            .entry(artifact_id.to_string())
+            .or_insert_with(|| Artifact {
+                artifact_type: artifact_type.to_string(),
+                ..Default::default()
+            })
+    }
+
+    /// Add a version to an existing artifact (replacing one with the same
+    /// number) and return its number
+    fn insert_version(&mut self, group_id: &str, artifact_id: &str, new: NewVersion) -> String {
+        // Identical content shares a content ID, as in the registry
+        let content_id = self
+            .groups
+            .values()
+            .flat_map(BTreeMap::values)
+            .flat_map(|a| &a.versions)
+            .find(|v| v.content == new.content)
+            .map(|v| v.content_id);
+        self.last_id += 1;
+        let global_id = self.last_id;
+        let content_id = content_id.unwrap_or(global_id);
+        let artifact = self
+            .artifact_mut(group_id, artifact_id)
+            .expect("artifact exists");
+        let version = new
+            .version
+            .unwrap_or_else(|| (artifact.versions.len() + 1).to_string());
+        artifact.versions.retain(|v| v.version != version);
+        artifact.versions.push(StoredVersion {
+            version: version.clone(),
+            content: new.content,
+            content_type: new.content_type,
+            references: new.references,
+            name: new.name,
+            description: new.description,
+            state: if new.draft { "DRAFT" } else { "ENABLED" }.to_string(),
+            global_id,
+            content_id,
+            created_on: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        });
+        version
+    }
+}
+
+/// A parsed HTTP request
+#[derive(Debug, Default)]
+struct Request {
+    method: String,
+    /// Decoded path segments after [`API_PREFIX`]
+    segments: Vec<String>,
+    query: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Status, content type and body of an answer
+#[derive(Debug)]
+struct Answer {
+    status: u16,
+    content_type: String,
+    body: Vec<u8>,
+}
+
+impl Answer {
+    fn json(status: u16, value: &Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json".to_string(),
+            body: value.to_string().into_bytes(),
+        }
+    }
+
+    fn no_content() -> Self {
+        Self {
+            status: 204,
+            content_type: "application/json".to_string(),
+            body: Vec::new(),
+        }
+    }
+
+    /// An Apicurio-style problem answer
+    fn error(status: u16, name: &str, detail: impl Into<String>) -> Self {
+        let detail = detail.into();
+        Self::json(
+            status,
+            &json!({ "status": status, "name": name, "title": detail, "detail": detail }),
+        )
+    }
+}
+
+async fn serve_connection(mut socket: TcpStream, store: &Mutex<Store>) -> io::Result<()> {
+    let (answer, head_only) = match read_request(&mut socket).await? {
+        // HEAD answers the headers GET would, without the body
+        Some(mut request) if request.method == "HEAD" => {
+            request.method = "GET".to_string();
+            (route(store, &request), true)
+        }
+        Some(request) => (route(store, &request), false),
+        None => (
+            Answer::error(400, "BadRequestException", "malformed request"),
+            false,
+        ),
+    };
+    let reason = match answer.status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Error",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {reason}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        answer.status,
+        answer.content_type,
+        answer.body.len()
+    );
+    socket.write_all(head.as_bytes()).await?;
+    if !head_only {
+        socket.write_all(&answer.body).await?;
+    }
+    socket.shutdown().await
+}
+
+/// Read one request; `None` when it cannot be parsed
+async fn read_request(socket: &mut TcpStream) -> io::Result<Option<Request>> {
+    let mut data = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let head_end = loop {
+        if let Some(i) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        let n = socket.read(&mut chunk).await?;
+        if n == 0 || data.len() > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+        data.extend_from_slice(&chunk[..n]);
+    };
+    let head = String::from_utf8_lossy(&data[..head_end]).into_owned();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Ok(None);
+    };
+    let length = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    if length > MAX_REQUEST_BYTES {
+        return Ok(None);
+    }
+    while data.len() < head_end + length {
+        let n = socket.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        data.extend_from_slice(&chunk[..n]);
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let Some(path) = path.strip_prefix(API_PREFIX) else {
+        return Ok(Some(Request {
+            method: method.to_string(),
+            ..Default::default()
+        }));
+    };
+    Ok(Some(Request {
+        method: method.to_string(),
+        segments: path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(percent_decode)
+            .collect(),
+        query: query
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let (key, value) = p.split_once('=').unwrap_or((p, ""));
+                (
+                    percent_decode(key),
+                    percent_decode(&value.replace('+', " ")),
+                )
+            })
+            .collect(),
+        body: data[head_end..head_end + length].to_vec(),
+    }))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn route(store: &Mutex<Store>, request: &Request) -> Answer {
+    let mut store = store.lock().unwrap();
+    let segments: Vec<&str> = request.segments.iter().map(String::as_str).collect();
+    let deletion = match (request.method.as_str(), segments.as_slice()) {
+        ("DELETE", ["groups", _]) => Some("group"),
+        ("DELETE", ["groups", _, "artifacts", _]) => Some("artifact"),
+        ("DELETE", ["groups", _, "artifacts", _, "versions", _]) => Some("artifact-version"),
+        _ => None,
+    };
+    if let Some(kind) = deletion {
+        let name = format!("apicurio.rest.deletion.{kind}.enabled");
+        if store.config_properties.get(&name).map(String::as_str) == Some("false") {
+            return Answer::error(
+                405,
+                "MethodNotAllowedException",
+                format!("Deleting this resource is disabled by {name}"),
+            );
+        }
+    }
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["system", "info"]) => Answer::json(
+            200,
+            &json!({
+                "name": "apicurio dev-server",
+                "description": "In-memory registry of apicurio-cli",
+                "version": env!("CARGO_PKG_VERSION"),
+                "builtOn": "",
+            }),
+        ),
+        // Without authentication everybody is an anonymous admin, as in the registry
+        ("GET", ["users", "me"]) => Answer::json(
+            200,
+            &json!({
+                "username": "",
+                "displayName": "",
+                "admin": true,
+                "developer": true,
+                "viewer": true,
+            }),
+        ),
+        ("GET", ["admin", "roleMappings"]) => page(request, "roleMappings", Vec::new()),
+        ("GET", ["groups"]) => {
+            let groups: Vec<Value> = store
+                .groups
+                .keys()
+                .map(|g| json!({ "groupId": g }))
+                .collect();
+            page(request, "groups", groups)
+        }
+        ("GET", ["groups", group_id, "artifacts"]) => {
+            let artifacts: Vec<Value> = store
+                .groups
+                .get(*group_id)
+                .into_iter()
+                .flatten()
+                .map(|(id, a)| artifact_json(group_id, id, a))
+                .collect();
+            page(request, "artifacts", artifacts)
+        }
+        ("DELETE", ["groups", group_id]) => match store.groups.remove(*group_id) {
+            Some(_) => Answer::no_content(),
+            None => Answer::error(
+                404,
+                "GroupNotFoundException",
+                format!("No group '{group_id}' was found."),
+            ),
+        },
+        ("POST", ["groups", group_id, "artifacts"]) => {
+            create_artifact(&mut store, request, group_id)
+        }
+        ("GET", ["groups", group_id, "artifacts", artifact_id]) => {
+            match store.artifact(group_id, artifact_id) {
+                Some(artifact) => {
+                    Answer::json(200, &artifact_json(group_id, artifact_id, artifact))
+                }
+                None => artifact_not_found(group_id, artifact_id),
+            }
+        }
+        ("PUT", ["groups", group_id, "artifacts", artifact_id]) => {
+            #[derive(Deserialize)]
+            struct EditableMetadata {
+                name: Option<String>,
+                description: Option<String>,
+                labels: Option<BTreeMap<String, String>>,
+            }
+            let Ok(edit) = serde_json::from_slice::<EditableMetadata>(&request.body) else {
+                return Answer::error(400, "BadRequestException", "invalid artifact metadata");
+            };
+            let Some(artifact) = store.artifact_mut(group_id, artifact_id) else {
+                return artifact_not_found(group_id, artifact_id);
+            };
+            artifact.name = edit.name.or(artifact.name.take());
+            artifact.description = edit.description.or(artifact.description.take());
+            if let Some(labels) = edit.labels {
+                artifact.labels = labels;
+            }
+            Answer::no_content()
+        }
+        ("DELETE", ["groups", group_id, "artifacts", artifact_id]) => {
+            match store
+                .groups
+                .get_mut(*group_id)
+                .and_then(|artifacts| artifacts.remove(*artifact_id))
+            {
+                Some(_) => Answer::no_content(),
+                None => artifact_not_found(group_id, artifact_id),
+            }
+        }
+        (method, ["groups", group_id, "artifacts", artifact_id, "rules", rule @ ..]) => {
+            match store.artifact_mut(group_id, artifact_id) {
+                Some(artifact) => rules(&mut artifact.rules, method, rule, &request.body),
+                None => artifact_not_found(group_id, artifact_id),
+            }
+        }
+        ("GET", ["groups", group_id, "artifacts", artifact_id, "versions"]) => {
+            let Some(artifact) = store.artifact(group_id, artifact_id) else {
+                return artifact_not_found(group_id, artifact_id);
+            };
+            let versions: Vec<Value> = artifact
+                .versions
+                .iter()
+                .map(|v| version_json(group_id, artifact_id, artifact, v))
+                .collect();
+            page(request, "versions", versions)
+        }
+        ("POST", ["groups", group_id, "artifacts", artifact_id, "versions"]) => {
+            let Some(artifact_type) = store
+                .artifact(group_id, artifact_id)
+                .map(|a| a.artifact_type.clone())
+            else {
+                return artifact_not_found(group_id, artifact_id);
+            };
+            let Ok(payload) = serde_json::from_slice::<CreateVersion>(&request.body) else {
+                return Answer::error(400, "BadRequestException", "invalid version payload");
+            };
+            add_version(
+                &mut store,
+                request,
+                group_id,
+                artifact_id,
+                &artifact_type,
+                payload,
+            )
+        }
+        (
+            method,
+            ["groups", group_id, "artifacts", artifact_id, "versions", version, rest @ ..],
+        ) => version_route(
+            &mut store,
+            method,
+            request,
+            group_id,
+            artifact_id,
+            version,
+            rest,
+        ),
+        ("GET", ["admin", "config", "properties"]) => {
+            let properties: Vec<Value> = CONFIG_PROPERTIES
+                .iter()
+                .map(|(name, _, _)| config_property_json(&store.config_properties, name))
+                .collect();
+            Answer::json(200, &json!(properties))
+        }
+        (method, ["admin", "config", "properties", name]) => {
+            if !CONFIG_PROPERTIES.iter().any(|(known, _, _)| known == name) {
+                return Answer::error(
+                    404,
+                    "ConfigPropertyNotFoundException",
+                    format!("No configuration property named '{name}' was found."),
+                );
+            }
+            match method {
+                "GET" => Answer::json(200, &config_property_json(&store.config_properties, name)),
+                "PUT" => match serde_json::from_slice::<Value>(&request.body)
+                    .ok()
+                    .and_then(|body| body["value"].as_str().map(str::to_string))
+                {
+                    Some(value) => {
+                        store.config_properties.insert(name.to_string(), value);
+                        Answer::no_content()
+                    }
+                    None => Answer::error(400, "BadRequestException", "invalid property payload"),
+                },
+                "DELETE" => {
+                    store.config_properties.remove(*name);
+                    Answer::no_content()
+                }
+                _ => Answer::error(
+                    405,
+                    "MethodNotAllowedException",
+                    "unsupported property request",
+                ),
+            }
+        }
+        (method, ["admin", "rules", rule @ ..]) => {
+            rules(&mut store.global_rules, method, rule, &request.body)
+        }
+        ("GET", ["search", "artifacts"]) => {
+            let wanted: Vec<(&str, &str)> = request
+                .query
+                .iter()
+                .filter(|(key, _)| key == "labels")
+                .map(|(_, label)| label.split_once(':').unwrap_or((label, "")))
+                .collect();
+            let group = request.param("groupId");
+            let artifact_id = request.param("artifactId");
+            let name = request.param("name");
+            let artifacts: Vec<Value> = store
+                .groups
+                .iter()
+                .filter(|(g, _)| group.is_none_or(|group| group == g.as_str()))
+                .flat_map(|(g, artifacts)| artifacts.iter().map(move |(id, a)| (g, id, a)))
+                .filter(|(_, id, a)| {
+                    wanted
+                        .iter()
+                        .all(|(key, value)| a.labels.get(*key).map(String::as_str) == Some(*value))
+                        && artifact_id.is_none_or(|wanted| wanted == id.as_str())
+                        && name.is_none_or(|wanted| a.name.as_deref() == Some(wanted))
+                })
+                .map(|(g, id, a)| artifact_json(g, id, a))
+                .collect();
+            page(request, "artifacts", artifacts)
+        }
+        ("POST", ["search", "versions"]) => {
+            let versions: Vec<Value> = store
+                .groups
+                .iter()
+                .flat_map(|(g, artifacts)| artifacts.iter().map(move |(id, a)| (g, id, a)))
+                .flat_map(|(g, id, a)| a.versions.iter().map(move |v| (g, id, a, v)))
+                .filter(|(_, _, _, v)| v.content == request.body)
+                .map(|(g, id, a, v)| version_json(g, id, a, v))
+                .collect();
+            page(request, "versions", versions)
+        }
+        _ => Answer::error(
+            404,
+            "NotFoundException",
+            format!(
+                "{} {API_PREFIX}/{} is not served by the dev server",
+                request.method,
+                request.segments.join("/")
+            ),
+        ),
+    }
+}
+
+/// `{ count, <key>: [...] }` honoring the `limit`/`offset` parameters
+fn page(request: &Request, key: &str, items: Vec<Value>) -> Answer {
+    let number = |name: &str| request.param(name).and_then(|v| v.parse::<usize>().ok());
+    let offset = number("offset").unwrap_or(0);
+    let limit = number("limit").unwrap_or(DEFAULT_PAGE_SIZE);
+    let count = items.len();
+    let items: Vec<Value> = items.into_iter().skip(offset).take(limit).collect();
+    Answer::json(200, &json!({ "count": count, key: items }))
+}
+
+fn artifact_json(group_id: &str, artifact_id: &str, artifact: &Artifact) -> Value {
+    json!({
+        "groupId": group_id,
+        "artifactId": artifact_id,
+        "artifactType": artifact.artifact_type,
+        "name": artifact.name,
+        "description": artifact.description,
+        "labels": artifact.labels,
+    })
+}
+
+fn version_json(
+    group_id: &str,
+    artifact_id: &str,
+    artifact: &Artifact,
+    version: &StoredVersion,
+) -> Value {
+    json!({
+        "groupId": group_id,
+        "artifactId": artifact_id,
+        "version": version.version,
+        "artifactType": artifact.artifact_type,
+        "globalId": version.global_id,
+        "contentId": version.content_id,
+        "name": version.name,
+        "description": version.description,
+        "owner": "",
+        "createdOn": version.created_on,
+        "labels": {},
+        "state": version.state,
+    })
+}
+
+fn artifact_not_found(group_id: &str, artifact_id: &str) -> Answer {
+    Answer::error(
+        404,
+        "ArtifactNotFoundException",
+        format!("No artifact with ID '{artifact_id}' in group '{group_id}' was found."),
+    )
+}
+
+fn version_not_found(artifact_id: &str, version: &str) -> Answer {
+    Answer::error(
+        404,
+        "VersionNotFoundException",
+        format!("No version '{version}' found for artifact with ID '{artifact_id}'."),
+    )
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VersionContent {
+    content: String,
+    content_type: Option<String>,
+    #[serde(default)]
+    references: Vec<Reference>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateVersion {
+    version: Option<String>,
+    content: VersionContent,
+    name: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    is_draft: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateArtifact {
+    artifact_id: String,
+    artifact_type: Option<String>,
+    name: Option<String>,
+    description: Option<String>,
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+    first_version: Option<CreateVersion>,
+}
+
+fn create_artifact(store: &mut Store, request: &Request, group_id: &str) -> Answer {
+    let Ok(payload) = serde_json::from_slice::<CreateArtifact>(&request.body) else {
+        return Answer::error(400, "BadRequestException", "invalid artifact payload");
+    };
+    let artifact_id = payload.artifact_id.as_str();
+    let artifact_type = payload.artifact_type.as_deref().unwrap_or("JSON");
+    if let Some(existing) = store.artifact(group_id, artifact_id) {
+        let if_exists = request.param("ifExists").unwrap_or("FAIL");
+        let Some(first) = payload.first_version.filter(|_| if_exists != "FAIL") else {
+            return Answer::error(
+                409,
+                "ArtifactAlreadyExistsException",
+                format!(
+                    "An artifact with ID '{artifact_id}' in group '{group_id}' already exists."
+                ),
+            );
+        };
+        if if_exists == "FIND_OR_CREATE_VERSION" {
+            let content = first.content.content.as_bytes();
+            if let Some(found) = existing.versions.iter().find(|v| v.content == content) {
+                return Answer::json(
+                    200,
+                    &json!({
+                        "artifact": artifact_json(group_id, artifact_id, existing),
+                        "version": version_json(group_id, artifact_id, existing, found),
+                    }),
+                );
+            }
+        }
+        let artifact_type = existing.artifact_type.clone();
+        return add_version(store, request, group_id, artifact_id, &artifact_type, first);
+    }
+    if request.param("dryRun") == Some("true") {
+        return Answer::json(200, &json!({}));
+    }
+
+    let artifact = store.artifact_or_insert(group_id, artifact_id, artifact_type);
+    artifact.name = payload.name;
+    artifact.description = payload.description;
+    artifact.labels = payload.labels;
+    let mut answer = json!({ "artifact": artifact_json(group_id, artifact_id, artifact) });
+    if let Some(first) = payload.first_version {
+        let added = add_version(store, request, group_id, artifact_id, artifact_type, first);
+        if added.status != 200 {
+            return added;
+        }
+        answer["version"] = serde_json::from_slice(&added.body).unwrap_or_default();
+    }
+    Answer::json(200, &answer)
+}
+
+fn add_version(
+    store: &mut Store,
+    request: &Request,
+    group_id: &str,
+    artifact_id: &str,
+    artifact_type: &str,
+    payload: CreateVersion,
+) -> Answer {
+    let artifact = store
+        .artifact(group_id, artifact_id)
+        .expect("artifact exists");
+    if let Some(version) = &payload.version {
+        if artifact.versions.iter().any(|v| &v.version == version) {
+            return Answer::error(
+                409,
+                "VersionAlreadyExistsException",
+                format!("Version '{version}' of artifact '{artifact_id}' already exists."),
+            );
+        }
+    }
+    if request.param("dryRun") == Some("true") {
+        return Answer::json(200, &json!({}));
+    }
+    let content_type = payload
+        .content
+        .content_type
+        .or_else(|| accept_for(Some(artifact_type), None).map(str::to_string))
+        .unwrap_or_else(|| "application/json".to_string());
+    let version = store.insert_version(
+        group_id,
+        artifact_id,
+        NewVersion {
+            version: payload.version,
+            content: payload.content.content.into_bytes(),
+            content_type,
+            references: payload.content.references,
+            name: payload.name,
+            description: payload.description,
+            draft: payload.is_draft,
+        },
+    );
+    let artifact = store
+        .artifact(group_id, artifact_id)
+        .expect("artifact exists");
+    let stored = artifact.versions.iter().find(|v| v.version == version);
+    let stored = stored.expect("version was just stored");
+    Answer::json(200, &version_json(group_id, artifact_id, artifact, stored))
+}
+
+/// Routes below `/groups/{g}/artifacts/{a}/versions/{version}`
+fn version_route(
+    store: &mut Store,
+    method: &str,
+    request: &Request,
+    group_id: &str,
+    artifact_id: &str,
+    version: &str,
+    rest: &[&str],
+) -> Answer {
+    let Some(artifact) = store.artifact(group_id, artifact_id) else {
+        return artifact_not_found(group_id, artifact_id);
+    };
+    // `branch=latest` is the newest version that is not a draft
+    let found = if version == "branch=latest" {
+        artifact.versions.iter().rposition(|v| v.state != "DRAFT")
+    } else {
+        artifact.versions.iter().position(|v| v.version == version)
+    };
+    let Some(index) = found else {
+        return version_not_found(artifact_id, version);
+    };
+    let stored = &artifact.versions[index];
+    match (method, rest) {
+        ("GET", []) => Answer::json(200, &version_json(group_id, artifact_id, artifact, stored)),
+        ("GET", ["content"]) => Answer {
+            status: 200,
+            content_type: stored.content_type.clone(),
+            body: stored.content.clone(),
+        },
+        ("GET", ["references"]) => {
+            let references: Vec<&Reference> = if request.param("refType") == Some("INBOUND") {
+                // Versions of any artifact referencing this one
+                store
+                    .groups
+                    .values()
+                    .flat_map(BTreeMap::values)
+                    .flat_map(|a| &a.versions)
+                    .flat_map(|v| &v.references)
+                    .filter(|r| {
+                        r.artifact_id == artifact_id
+                            && r.group_id.as_deref().unwrap_or("default") == group_id
+                            && r.version == stored.version
+                    })
+                    .collect()
+            } else {
+                stored.references.iter().collect()
+            };
+            Answer::json(200, &json!(references))
+        }
+        ("PUT", ["content"]) => {
+            let Ok(content) = serde_json::from_slice::<VersionContent>(&request.body) else {
+                return Answer::error(400, "BadRequestException", "invalid version content");
+            };
+            if stored.state != "DRAFT" {
+                return Answer::error(
+                    409,
+                    "ConflictException",
+                    format!("Version '{version}' is not a draft; its content cannot change."),
+                );
+            }
+            let artifact = store.artifact_mut(group_id, artifact_id).expect("exists");
+            let stored = &mut artifact.versions[index];
+            stored.content = content.content.into_bytes();
+            stored.references = content.references;
+            if let Some(content_type) = content.content_type {
+                stored.content_type = content_type;
+            }
+            Answer::no_content()
+        }
+        ("PUT", ["state"]) => {
+            #[derive(Deserialize)]
+            struct State {
+                state: String,
+            }
+            let Ok(State { state }) = serde_json::from_slice(&request.body) else {
+                return Answer::error(400, "BadRequestException", "invalid state payload");
+            };
+            let artifact = store.artifact_mut(group_id, artifact_id).expect("exists");
+            artifact.versions[index].state = state;
+            Answer::no_content()
+        }
+        ("DELETE", []) => {
+            let artifact = store.artifact_mut(group_id, artifact_id).expect("exists");
+            artifact.versions.remove(index);
+            Answer::no_content()
+        }
+        _ => Answer::error(
+            405,
+            "MethodNotAllowedException",
+            "unsupported version request",
+        ),
+    }
+}
+
+/// Rule endpoints of an artifact or of the registry (`rule` is empty for the list)
+fn config_property_json(overrides: &BTreeMap<String, String>, name: &str) -> Value {
+    let (name, default, label) = CONFIG_PROPERTIES
+        .iter()
+        .find(|(known, _, _)| *known == name)
+        .expect("known property");
+    json!({
+        "name": name,
+        "value": overrides.get(*name).map(String::as_str).unwrap_or(default),
+        "type": "java.lang.Boolean",
+        "label": label,
+        "description": format!("Whether the `{label}` operation is allowed"),
+    })
+}
+
+fn rules(rules: &mut BTreeMap<String, String>, method: &str, rule: &[&str], body: &[u8]) -> Answer {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Rule {
+        rule_type: Option<String>,
+        config: String,
+    }
+    let parsed = serde_json::from_slice::<Rule>(body);
+    match (method, rule) {
+        ("GET", []) => Answer::json(200, &json!(rules.keys().collect::<Vec<_>>())),
+        ("POST", []) => match parsed {
+            Ok(Rule {
+                rule_type: Some(rule_type),
+                config,
+            }) => {
+                if rules.contains_key(&rule_type) {
+                    return Answer::error(
+                        409,
+                        "RuleAlreadyExistsException",
+                        format!("A rule named '{rule_type}' already exists."),
+                    );
+                }
+                rules.insert(rule_type, config);
+                Answer::no_content()
+            }
+            _ => Answer::error(400, "BadRequestException", "invalid rule payload"),
+        },
+        ("DELETE", []) => {
+            rules.clear();
+            Answer::no_content()
+        }
+        (method, [name]) => {
+            if !rules.contains_key(*name) {
+                return Answer::error(
+                    404,
+                    "RuleNotFoundException",
+                    format!("No rule named '{name}' was found."),
+                );
+            }
+            match method {
+                "GET" => Answer::json(200, &json!({ "ruleType": name, "config": rules[*name] })),
+                "PUT" => match parsed {
+                    Ok(rule) => {
+                        rules.insert(name.to_string(), rule.config);
+                        Answer::json(200, &json!({ "ruleType": name, "config": rules[*name] }))
+                    }
+                    Err(_) => Answer::error(400, "BadRequestException", "invalid rule payload"),
+                },
+                "DELETE" => {
+                    rules.remove(*name);
+                    Answer::no_content()
+                }
+                _ => Answer::error(405, "MethodNotAllowedException", "unsupported rule request"),
+            }
+        }
+        _ => Answer::error(405, "MethodNotAllowedException", "unsupported rule request"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::PublishConfig, events::NullSink, registry::RegistryClient};
+
+    #[tokio::test]
+    async fn test_publish_then_read_back() {
+        let server = DevServer::start("127.0.0.1:0").await.unwrap();
+        server.add_version(
+            "com.example",
+            "common",
+            "PROTOBUF",
+            "1.0.0",
+            "syntax = \"proto3\";",
+        );
+        let client = RegistryClient::new(&server.registry_config("dev")).unwrap();
+
+        let publish: PublishConfig = serde_yaml::from_str(
+            "name: users\ninputPath: users.proto\nversion: 1.1.0\nregistry: dev\ngroupId: com.example\ntype: protobuf\n",
+        )
+        .unwrap();
+        let content = b"syntax = \"proto3\";\nmessage User {}\n";
+        client
+            .publish_artifact(&publish, content, false, &NullSink)
+            .await
+            .unwrap();
+        // Publishing the same content again is a no-op
+        client
+            .publish_artifact(&publish, content, false, &NullSink)
+            .await
+            .unwrap();
+
+        let versions = client.list_versions("com.example", "users").await.unwrap();
+        assert_eq!(versions, [semver::Version::new(1, 1, 0)]);
+        let (content_type, bytes) = client
+            .download_with_content_type("com.example", "users", "1.1.0")
+            .await
+            .unwrap();
+        assert_eq!(content_type.as_deref(), Some("application/x-protobuf"));
+        assert_eq!(&bytes[..], content);
+        assert_eq!(
+            client
+                .get_content_type("com.example", "users", "1.1.0")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("application/x-protobuf")
+        );
+        assert_eq!(
+            client.list_artifacts("com.example").await.unwrap(),
+            ["common", "users"]
+        );
+        let err = client
+            .get_version_metadata("com.example", "users", &"9.9.9")
+            .await
+            .unwrap_err();
+        assert!(err.is_not_found());
+    }
+}
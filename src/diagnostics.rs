@@ -0,0 +1,438 @@
+//! Network diagnostics for registry connectivity
+//!
+//! When a registry cannot be reached, a bare reqwest error rarely tells whether
+//! the problem is DNS, a firewall, TLS, credentials or a wrong URL. This module
+//! probes each layer in turn and produces a per-registry report:
+//!
+//! 1. **DNS** - resolve the registry host
+//! 2. **TCP** - open a connection to the resolved address
+//! 3. **TLS** - complete a handshake (HTTPS registries only)
+//! 4. **Auth** - call an authenticated endpoint and classify 401/403/404
+//! 5. **API** - read `system/info` and check the registry serves the v3 API
+//!
+//! A failing step causes the steps that depend on it to be skipped.
+
+use crate::config::RegistryConfig;
+use crate::error::ApicurioError;
+use crate::registry::{RegistryClient, RegistryPool, SystemInfo};
+use reqwest::{Client, StatusCode, Url};
+use serde::Serialize;
+use std::fmt;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// Time allowed for each individual network probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Outcome of a single diagnostic step
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+impl CheckStatus {
+    fn icon(&self) -> &'static str {
+        match self {
+            CheckStatus::Ok => "✅",
+            CheckStatus::Warn => "⚠️ ",
+            CheckStatus::Fail => "❌",
+            CheckStatus::Skipped => "⏭️ ",
+        }
+    }
+}
+
+/// Result of one diagnostic step
+#[derive(Serialize, Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        CheckResult {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Diagnostic report for one registry
+#[derive(Serialize, Debug, Clone)]
+pub struct RegistryDiagnostics {
+    pub registry: String,
+    pub url: String,
+    pub checks: Vec<CheckResult>,
+    /// Round trip of the authenticated request, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Version reported by `system/info`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+}
+
+impl RegistryDiagnostics {
+    /// Whether no step failed (warnings are tolerated)
+    pub fn is_healthy(&self) -> bool {
+        self.first_failure().is_none()
+    }
+
+    /// The first failed step, if any
+    pub fn first_failure(&self) -> Option<&CheckResult> {
+        self.checks.iter().find(|c| c.status == CheckStatus::Fail)
+    }
+
+    /// Status of the named step, if it ran
+    pub fn status_of(&self, name: &str) -> Option<CheckStatus> {
+        self.checks
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| c.status)
+    }
+
+    fn push(&mut self, name: &'static str, status: CheckStatus, detail: impl Into<String>) {
+        self.checks.push(CheckResult::new(name, status, detail));
+    }
+
+    fn skip_remaining(&mut self, names: &[&'static str]) {
+        for name in names {
+            self.push(name, CheckStatus::Skipped, "skipped (previous step failed)");
+        }
+    }
+}
+
+impl fmt::Display for RegistryDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "🌐 Registry '{}' ({})", self.registry, self.url)?;
+        for check in &self.checks {
+            writeln!(
+                f,
+                "  {} {:<5} {}",
+                check.status.icon(),
+                check.name,
+                check.detail
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Run every diagnostic step against a registry
+pub async fn diagnose_registry(registry: &RegistryConfig) -> RegistryDiagnostics {
+    let mut report = RegistryDiagnostics {
+        registry: registry.name.clone(),
+        url: registry.url.clone(),
+        checks: Vec::new(),
+        latency_ms: None,
+        api_version: None,
+    };
+
+    let url = match Url::parse(&registry.url) {
+        Ok(url) => url,
+        Err(e) => {
+            report.push("DNS", CheckStatus::Fail, format!("invalid URL: {e}"));
+            report.skip_remaining(&["TCP", "TLS", "Auth", "API"]);
+            return report;
+        }
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        report.push("DNS", CheckStatus::Fail, "URL has no host or port");
+        report.skip_remaining(&["TCP", "TLS", "Auth", "API"]);
+        return report;
+    };
+
+    // 1) DNS
+    let addrs = match resolve(host, port).await {
+        Ok(addrs) if !addrs.is_empty() => {
+            let shown: Vec<String> = addrs.iter().map(|a| a.ip().to_string()).collect();
+            report.push(
+                "DNS",
+                CheckStatus::Ok,
+                format!("{host} → {}", shown.join(", ")),
+            );
+            addrs
+        }
+        Ok(_) => {
+            report.push("DNS", CheckStatus::Fail, format!("{host} has no addresses"));
+            report.skip_remaining(&["TCP", "TLS", "Auth", "API"]);
+            return report;
+        }
+        Err(e) => {
+            report.push(
+                "DNS",
+                CheckStatus::Fail,
+                format!("cannot resolve {host}: {e}"),
+            );
+            report.skip_remaining(&["TCP", "TLS", "Auth", "API"]);
+            return report;
+        }
+    };
+
+    // 2) TCP
+    let (status, detail) = connect_any(&addrs).await;
+    report.push("TCP", status, detail);
+    if status == CheckStatus::Fail {
+        report.skip_remaining(&["TLS", "Auth", "API"]);
+        return report;
+    }
+
+    // 3) TLS
+    if url.scheme() == "https" {
+        let (status, detail) = check_tls(&url).await;
+        report.push("TLS", status, detail);
+        if status == CheckStatus::Fail {
+            report.skip_remaining(&["Auth", "API"]);
+            return report;
+        }
+    } else {
+        report.push("TLS", CheckStatus::Skipped, "plain HTTP, no TLS in use");
+    }
+
+    // 4) Auth
+    let client = match RegistryPool::global().client(registry) {
+        Ok(client) => client,
+        Err(e) => {
+            report.push(
+                "Auth",
+                CheckStatus::Fail,
+                format!("cannot build credentials: {e}"),
+            );
+            report.skip_remaining(&["API"]);
+            return report;
+        }
+    };
+    let groups_url = format!("{}/apis/registry/v3/groups?limit=1", client.base_url);
+    let started = Instant::now();
+    match client
+        .send(client.client.get(&groups_url).timeout(PROBE_TIMEOUT))
+        .await
+    {
+        Ok(resp) => {
+            report.latency_ms = Some(started.elapsed().as_millis() as u64);
+            let (status, detail) = classify_auth_status(resp.status());
+            report.push("Auth", status, detail);
+        }
+        Err(e @ ApicurioError::Auth { .. }) => {
+            report.push("Auth", CheckStatus::Fail, e.to_string())
+        }
+        Err(e) => report.push("Auth", CheckStatus::Fail, format!("request failed: {e}")),
+    }
+
+    // 5) API version
+    let (status, detail, version) = check_api_version(&client).await;
+    report.push("API", status, detail);
+    report.api_version = version;
+
+    report
+}
+
+async fn resolve(host: &str, port: u16) -> std::io::Result<Vec<SocketAddr>> {
+    let lookup = tokio::net::lookup_host((host, port));
+    match tokio::time::timeout(PROBE_TIMEOUT, lookup).await {
+        Ok(result) => Ok(result?.collect()),
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "lookup timed out",
+        )),
+    }
+}
+
+async fn connect_any(addrs: &[SocketAddr]) -> (CheckStatus, String) {
+    let mut errors = Vec::new();
+    for addr in addrs {
+        let started = Instant::now();
+        match tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(addr)).await {
+            Ok(Ok(_)) => {
+                return (
+                    CheckStatus::Ok,
+                    format!("connected to {addr} in {}ms", started.elapsed().as_millis()),
+                )
+            }
+            Ok(Err(e)) => errors.push(format!("{addr}: {e}")),
+            Err(_) => errors.push(format!("{addr}: timed out")),
+        }
+    }
+    (
+        CheckStatus::Fail,
+        format!("cannot connect ({})", errors.join("; ")),
+    )
+}
+
+/// Probe the TLS handshake with an unauthenticated request; since TCP already
+/// succeeded, a connection error at this point comes from TLS
+async fn check_tls(url: &Url) -> (CheckStatus, String) {
+    let client = match Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => return (CheckStatus::Fail, format!("cannot build HTTP client: {e}")),
+    };
+    match client.head(url.clone()).send().await {
+        Ok(_) => (CheckStatus::Ok, "handshake succeeded".to_string()),
+        Err(e) if e.is_connect() => (
+            CheckStatus::Fail,
+            format!("handshake failed: {}", error_chain(&e)),
+        ),
+        Err(e) if e.is_timeout() => (CheckStatus::Fail, "handshake timed out".to_string()),
+        // The handshake itself worked, the server just answered oddly
+        Err(e) => (
+            CheckStatus::Warn,
+            format!("handshake succeeded, request failed: {e}"),
+        ),
+    }
+}
+
+async fn check_api_version(client: &RegistryClient) -> (CheckStatus, String, Option<String>) {
+    let info_url = format!("{}/apis/registry/v3/system/info", client.base_url);
+    let resp = match client
+        .send(client.client.get(&info_url).timeout(PROBE_TIMEOUT))
+        .await
+    {
+        Ok(resp) => resp,
+        Err(e) => return (CheckStatus::Fail, format!("request failed: {e}"), None),
+    };
+
+    if resp.status() == StatusCode::NOT_FOUND {
+        let v2_url = format!("{}/apis/registry/v2/system/info", client.base_url);
+        let serves_v2 = client
+            .send(client.client.get(&v2_url).timeout(PROBE_TIMEOUT))
+            .await
+            .is_ok_and(|r| r.status().is_success());
+        return if serves_v2 {
+            (
+                CheckStatus::Fail,
+                "registry only serves API v2; the CLI requires API v3 (Apicurio Registry 3.x)"
+                    .to_string(),
+                None,
+            )
+        } else {
+            (
+                CheckStatus::Fail,
+                format!("{info_url} not found; check the registry URL and any path prefix"),
+                None,
+            )
+        };
+    }
+    if !resp.status().is_success() {
+        return (
+            CheckStatus::Fail,
+            format!("system/info returned {}", resp.status()),
+            None,
+        );
+    }
+
+    match resp.json::<SystemInfo>().await {
+        Ok(info) if major_version(&info.version).is_some_and(|major| major >= 3) => (
+            CheckStatus::Ok,
+            format!("{} {}", info.name, info.version),
+            Some(info.version),
+        ),
+        Ok(info) => (
+            CheckStatus::Warn,
+            format!(
+                "{} {} (expected Apicurio Registry 3.x)",
+                info.name, info.version
+            ),
+            Some(info.version),
+        ),
+        Err(e) => (
+            CheckStatus::Fail,
+            format!("unexpected system/info response (is this an Apicurio Registry?): {e}"),
+            None,
+        ),
+    }
+}
+
+/// Translate the status of an authenticated request into a diagnosis
+pub fn classify_auth_status(status: StatusCode) -> (CheckStatus, String) {
+    match status {
+        s if s.is_success() => (
+            CheckStatus::Ok,
+            format!("authenticated request returned {s}"),
+        ),
+        StatusCode::UNAUTHORIZED => (
+            CheckStatus::Fail,
+            "401 Unauthorized: credentials are missing or invalid".to_string(),
+        ),
+        StatusCode::FORBIDDEN => (
+            CheckStatus::Fail,
+            "403 Forbidden: credentials are valid but lack read permission".to_string(),
+        ),
+        StatusCode::NOT_FOUND => (
+            CheckStatus::Fail,
+            "404 Not Found: the URL does not point at an Apicurio Registry v3 API".to_string(),
+        ),
+        s => (CheckStatus::Warn, format!("unexpected status {s}")),
+    }
+}
+
+fn major_version(version: &str) -> Option<u64> {
+    version.split('.').next()?.parse().ok()
+}
+
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut parts = vec![err.to_string()];
+    let mut source = err.source();
+    while let Some(cause) = source {
+        parts.push(cause.to_string());
+        source = cause.source();
+    }
+    parts.join(": ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_auth_status() {
+        assert_eq!(classify_auth_status(StatusCode::OK).0, CheckStatus::Ok);
+        assert_eq!(
+            classify_auth_status(StatusCode::UNAUTHORIZED).0,
+            CheckStatus::Fail
+        );
+        assert!(classify_auth_status(StatusCode::FORBIDDEN)
+            .1
+            .contains("permission"));
+        assert!(classify_auth_status(StatusCode::NOT_FOUND)
+            .1
+            .contains("404"));
+    }
+
+    #[tokio::test]
+    async fn test_diagnose_refused_connection_skips_later_steps() {
+        // Grab a free port, then close it so the connection is refused
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let registry = RegistryConfig {
+            name: "local".to_string(),
+            url: format!("http://127.0.0.1:{port}"),
+            auth: Default::default(),
+            ..Default::default()
+        };
+
+        let report = diagnose_registry(&registry).await;
+        let statuses: Vec<_> = report.checks.iter().map(|c| (c.name, c.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("DNS", CheckStatus::Ok),
+                ("TCP", CheckStatus::Fail),
+                ("TLS", CheckStatus::Skipped),
+                ("Auth", CheckStatus::Skipped),
+                ("API", CheckStatus::Skipped),
+            ]
+        );
+        assert!(!report.is_healthy());
+        assert_eq!(report.first_failure().unwrap().name, "TCP");
+        assert_eq!(report.status_of("Auth"), Some(CheckStatus::Skipped));
+        assert_eq!(report.latency_ms, None);
+    }
+}
@@ -0,0 +1,332 @@
+//! Schemas registered more than once under different coordinates
+//!
+//! Artifacts are compared twice: by the SHA-256 of their content, which finds
+//! byte-for-byte copies, and by the hash of a normalized form, which finds
+//! copies that only differ in formatting or documentation. Avro schemas are
+//! normalized to their Parsing Canonical Form, JSON and YAML documents to
+//! their structure without `description`, `title`, `doc` or `$comment`, and
+//! other text to its tokens without comments.
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::avro;
+
+/// One artifact version to compare
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub registry: String,
+    pub group_id: String,
+    pub artifact_id: String,
+    pub version: String,
+    /// Pulled file of a locked dependency; `None` for artifacts only found
+    /// by scanning a registry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<String>,
+    pub sha256: String,
+    /// Hash of the normalized content, when it could be normalized
+    #[serde(skip)]
+    pub normalized: Option<(Normalization, String)>,
+}
+
+impl Entry {
+    /// `registry/group/artifact@version`
+    pub fn label(&self) -> String {
+        format!(
+            "{}/{}/{}@{}",
+            self.registry, self.group_id, self.artifact_id, self.version
+        )
+    }
+
+    fn coordinates(&self) -> (&str, &str, &str) {
+        (&self.registry, &self.group_id, &self.artifact_id)
+    }
+}
+
+/// How content was normalized before comparing
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum Normalization {
+    /// Avro Parsing Canonical Form
+    AvroCanonical,
+    /// JSON/YAML structure without documentation keywords
+    Structure,
+    /// Tokens without comments or whitespace differences
+    Text,
+}
+
+impl Normalization {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Normalization::AvroCanonical => "Same Avro canonical form",
+            Normalization::Structure => "Same structure, ignoring documentation",
+            Normalization::Text => "Same text, ignoring comments and whitespace",
+        }
+    }
+}
+
+/// Artifacts with the same content under different coordinates
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Duplicate {
+    /// `None` for byte-for-byte copies
+    pub normalization: Option<Normalization>,
+    /// The hash the entries share
+    pub hash: String,
+    pub entries: Vec<Entry>,
+}
+
+/// The normalized form of the content of `path` and its hash
+pub fn normalize(path: &str, content: &[u8]) -> Option<(Normalization, String)> {
+    let text = std::str::from_utf8(content).ok()?;
+    let ext = path.rsplit('.').next().unwrap_or_default().to_lowercase();
+    let (normalization, normalized) = if let Some(canonical) = avro_canonical(&ext, text) {
+        (Normalization::AvroCanonical, canonical)
+    } else if matches!(ext.as_str(), "json" | "yaml" | "yml") {
+        let mut document: Value = serde_yaml::from_str(text).ok()?;
+        strip_documentation(&mut document);
+        // serde_json maps are sorted, so key order does not matter
+        (Normalization::Structure, document.to_string())
+    } else {
+        let comments = match ext.as_str() {
+            "proto" => Comments::Slashes,
+            "graphql" | "graphqls" | "gql" => Comments::Hash,
+            _ => Comments::None,
+        };
+        (Normalization::Text, strip_comments(text, comments))
+    };
+    Some((normalization, hex::encode(Sha256::digest(normalized))))
+}
+
+fn avro_canonical(ext: &str, text: &str) -> Option<String> {
+    let is_avro = ext == "avsc"
+        || (ext == "json"
+            && serde_json::from_str::<Value>(text).is_ok_and(|schema| {
+                matches!(
+                    schema.get("type").and_then(Value::as_str),
+                    Some("record" | "enum" | "fixed")
+                )
+            }));
+    is_avro.then(|| avro::canonical_form(text).ok()).flatten()
+}
+
+fn strip_documentation(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for key in ["description", "title", "doc", "$comment"] {
+                if map.get(key).is_some_and(Value::is_string) {
+                    map.remove(key);
+                }
+            }
+            map.values_mut().for_each(strip_documentation);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_documentation),
+        _ => {}
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Comments {
+    None,
+    /// `//` and `/* */`
+    Slashes,
+    /// `#`
+    Hash,
+}
+
+/// `text` without comments, its tokens separated by single spaces
+fn strip_comments(text: &str, comments: Comments) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars().peekable();
+    let mut quote: Option<char> = None;
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            out.push(c);
+            if c == '\\' {
+                out.extend(chars.next());
+            } else if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        match c {
+            '"' | '\'' => {
+                quote = Some(c);
+                out.push(c);
+            }
+            '/' if comments == Comments::Slashes && chars.peek() == Some(&'/') => {
+                chars.by_ref().find(|&c| c == '\n');
+                out.push(' ');
+            }
+            '/' if comments == Comments::Slashes && chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+                out.push(' ');
+            }
+            '#' if comments == Comments::Hash => {
+                chars.by_ref().find(|&c| c == '\n');
+                out.push(' ');
+            }
+            _ => out.push(c),
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Groups of `entries` sharing their content or their normalized content
+/// under at least two different coordinates
+///
+/// Versions of one artifact that share content are not duplicates. A
+/// normalized group is only reported when its entries are not all byte-for-byte
+/// copies, which the plain group already covers.
+pub fn find(entries: &[Entry]) -> Vec<Duplicate> {
+    let mut identical: BTreeMap<&str, Vec<&Entry>> = BTreeMap::new();
+    let mut equivalent: BTreeMap<(Normalization, &str), Vec<&Entry>> = BTreeMap::new();
+    for entry in entries {
+        identical.entry(&entry.sha256).or_default().push(entry);
+        if let Some((normalization, hash)) = &entry.normalized {
+            equivalent
+                .entry((*normalization, hash))
+                .or_default()
+                .push(entry);
+        }
+    }
+
+    let spread = |group: &[&Entry]| {
+        group
+            .iter()
+            .map(|e| e.coordinates())
+            .collect::<BTreeSet<_>>()
+            .len()
+            > 1
+    };
+    let mut duplicates: Vec<Duplicate> = identical
+        .into_iter()
+        .filter(|(_, group)| spread(group))
+        .map(|(hash, group)| Duplicate {
+            normalization: None,
+            hash: hash.to_string(),
+            entries: group.into_iter().cloned().collect(),
+        })
+        .collect();
+    duplicates.extend(
+        equivalent
+            .into_iter()
+            .filter(|(_, group)| spread(group) && group.iter().any(|e| e.sha256 != group[0].sha256))
+            .map(|((normalization, hash), group)| Duplicate {
+                normalization: Some(normalization),
+                hash: hash.to_string(),
+                entries: group.into_iter().cloned().collect(),
+            }),
+    );
+    duplicates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(group_id: &str, artifact_id: &str, path: &str, content: &str) -> Entry {
+        Entry {
+            registry: "dev".to_string(),
+            group_id: group_id.to_string(),
+            artifact_id: artifact_id.to_string(),
+            version: "1.0.0".to_string(),
+            output_path: Some(path.to_string()),
+            sha256: hex::encode(Sha256::digest(content)),
+            normalized: normalize(path, content.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_find_identical_and_equivalent_schemas() {
+        let user =
+            r#"{"type": "record", "name": "User", "fields": [{"name": "id", "type": "long"}]}"#;
+        let documented = r#"{"type": "record", "name": "User", "doc": "A user",
+            "fields": [{"name": "id", "type": "long", "doc": "Its ID"}]}"#;
+        let entries = [
+            entry("com.a", "user", "a/user.avsc", user),
+            entry("com.b", "user", "b/user.avsc", user),
+            entry("com.c", "person", "c/person.avsc", documented),
+            entry(
+                "com.a",
+                "order",
+                "a/order.proto",
+                "message Order { // the order\n  int64 id = 1;\n}",
+            ),
+            entry(
+                "com.b",
+                "order",
+                "b/order.proto",
+                "/* copy */ message Order {\n  int64 id = 1; }",
+            ),
+            entry(
+                "com.a",
+                "api",
+                "a/api.yaml",
+                "openapi: 3.0.3\ninfo:\n  title: A\n",
+            ),
+            entry(
+                "com.b",
+                "api",
+                "b/api.json",
+                r#"{"info": {"title": "B"}, "openapi": "3.0.3"}"#,
+            ),
+            entry("com.a", "misc", "a/misc.yaml", "kind: other\n"),
+        ];
+        let found = find(&entries);
+        let summary: Vec<(Option<Normalization>, Vec<String>)> = found
+            .iter()
+            .map(|d| {
+                (
+                    d.normalization,
+                    d.entries.iter().map(Entry::label).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(found.len(), 4, "{summary:?}");
+        assert!(summary.contains(&(
+            None,
+            vec![
+                "dev/com.a/user@1.0.0".to_string(),
+                "dev/com.b/user@1.0.0".to_string()
+            ]
+        )));
+        assert!(summary.contains(&(
+            Some(Normalization::AvroCanonical),
+            vec![
+                "dev/com.a/user@1.0.0".to_string(),
+                "dev/com.b/user@1.0.0".to_string(),
+                "dev/com.c/person@1.0.0".to_string()
+            ]
+        )));
+        assert!(summary.contains(&(
+            Some(Normalization::Text),
+            vec![
+                "dev/com.a/order@1.0.0".to_string(),
+                "dev/com.b/order@1.0.0".to_string()
+            ]
+        )));
+        assert!(summary.contains(&(
+            Some(Normalization::Structure),
+            vec![
+                "dev/com.a/api@1.0.0".to_string(),
+                "dev/com.b/api@1.0.0".to_string()
+            ]
+        )));
+
+        // versions of one artifact are not duplicates of each other
+        let mut again = entry("com.a", "user", "a/user.avsc", user);
+        again.version = "2.0.0".to_string();
+        assert!(find(&[entries[0].clone(), again]).is_empty());
+    }
+}
@@ -0,0 +1,254 @@
+//! Typed errors of the library surface
+//!
+//! The public functions of [`crate::config`], [`crate::lockfile`],
+//! [`crate::registry`] and [`crate::dependency`] return [`ApicurioError`], so
+//! code embedding the library can tell a broken configuration from rejected
+//! credentials or an unreachable registry without parsing messages:
+//!
+//! ```no_run
+//! # async fn example(client: apicurio_cli::registry::RegistryClient) {
+//! use apicurio_cli::error::ApicurioError;
+//!
+//! match client.list_versions("com.example", "users").await {
+//!     Ok(versions) => println!("{} versions", versions.len()),
+//!     Err(ApicurioError::Auth { registry, .. }) => eprintln!("log in to {registry}"),
+//!     Err(e) if e.is_not_found() => eprintln!("no such artifact"),
+//!     Err(e) => eprintln!("{e}"),
+//! }
+//! # }
+//! ```
+//!
+//! The CLI itself keeps using `anyhow`, which wraps these errors and prints
+//! their `source` chain.
+
+use std::{error::Error as StdError, path::Path};
+
+/// Underlying cause of an [`ApicurioError`]
+pub type BoxError = Box<dyn StdError + Send + Sync + 'static>;
+
+/// `Result` with [`ApicurioError`] as the default error type
+pub type Result<T, E = ApicurioError> = std::result::Result<T, E>;
+
+/// Error kinds of the library API
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum ApicurioError {
+    /// A configuration, registries or lock file is missing, malformed or invalid
+    #[error("{message}")]
+    Config {
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+    /// Reading or writing a local file failed
+    #[error("{message}")]
+    Io {
+        message: String,
+        #[source]
+        source: std::io::Error,
+    },
+    /// Credentials could not be resolved, or the registry rejected them (401/403)
+    #[error("{message}")]
+    Auth {
+        /// Registry name, or its URL when the name is not known
+        registry: String,
+        message: String,
+        #[source]
+        source: Option<BoxError>,
+    },
+    /// The registry could not be reached or gave an unexpected answer
+    #[error("{message}")]
+    Network {
+        message: String,
+        /// HTTP status of the answer, if one was received
+        status: Option<u16>,
+        #[source]
+        source: Option<BoxError>,
+    },
+    /// A group, artifact or version does not exist, or nothing matches a version range
+    #[error("{message}")]
+    Resolution {
+        message: String,
+        /// Set when the registry answered "404 Not Found"
+        not_found: bool,
+        #[source]
+        source: Option<BoxError>,
+    },
+    /// Content does not match its checksum, or conflicts with what is already published
+    #[error("{message}")]
+    Integrity { message: String },
+}
+
+impl ApicurioError {
+    pub fn config(message: impl Into<String>) -> Self {
+        ApicurioError::Config {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    pub fn config_caused(message: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        ApicurioError::Config {
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    /// Reading or writing `path` failed
+    pub fn io(action: &str, path: &Path, source: std::io::Error) -> Self {
+        ApicurioError::Io {
+            message: format!("{action} {}", path.display()),
+            source,
+        }
+    }
+
+    pub fn auth(
+        registry: impl Into<String>,
+        message: impl Into<String>,
+        source: impl Into<BoxError>,
+    ) -> Self {
+        ApicurioError::Auth {
+            registry: registry.into(),
+            message: message.into(),
+            source: Some(source.into()),
+        }
+    }
+
+    pub fn network(message: impl Into<String>, source: impl Into<BoxError>) -> Self {
+        ApicurioError::Network {
+            message: message.into(),
+            status: None,
+            source: Some(source.into()),
+        }
+    }
+
+    pub fn resolution(message: impl Into<String>) -> Self {
+        ApicurioError::Resolution {
+            message: message.into(),
+            not_found: false,
+            source: None,
+        }
+    }
+
+    pub fn integrity(message: impl Into<String>) -> Self {
+        ApicurioError::Integrity {
+            message: message.into(),
+        }
+    }
+
+    /// Classify a registry answer with a non-success `status`
+    pub fn from_status(url: &str, status: reqwest::StatusCode, body: Option<String>) -> Self {
+        let mut message = format!("{url}: HTTP {status}");
+        if let Some(body) = body.filter(|b| !b.trim().is_empty()) {
+            message = format!("{message} - {}", body.trim());
+        }
+        match status {
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+                ApicurioError::Auth {
+                    registry: url.to_string(),
+                    message,
+                    source: None,
+                }
+            }
+            reqwest::StatusCode::NOT_FOUND => ApicurioError::Resolution {
+                message,
+                not_found: true,
+                source: None,
+            },
+            _ => ApicurioError::Network {
+                message,
+                status: Some(status.as_u16()),
+                source: None,
+            },
+        }
+    }
+
+    /// Prefix the message with what was being done, keeping the error kind
+    pub fn context(mut self, context: impl std::fmt::Display) -> Self {
+        match &mut self {
+            ApicurioError::Config { message, .. }
+            | ApicurioError::Io { message, .. }
+            | ApicurioError::Auth { message, .. }
+            | ApicurioError::Network { message, .. }
+            | ApicurioError::Resolution { message, .. }
+            | ApicurioError::Integrity { message } => *message = format!("{context}: {message}"),
+        }
+        self
+    }
+
+    /// Whether this is a registry "404 Not Found" answer
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            ApicurioError::Resolution {
+                not_found: true,
+                ..
+            }
+        )
+    }
+}
+
+impl From<reqwest::Error> for ApicurioError {
+    fn from(e: reqwest::Error) -> Self {
+        let url = e.url().map(|u| u.to_string()).unwrap_or_default();
+        match e.status() {
+            Some(status) => ApicurioError::from_status(&url, status, None),
+            None if e.is_decode() => {
+                ApicurioError::network(format!("unexpected answer from {url}"), e)
+            }
+            None => ApicurioError::network(format!("request to {url} failed"), e),
+        }
+    }
+}
+
+/// Whether `err`, or any error it was caused by, is a registry "404 Not Found"
+pub fn is_not_found(err: &(dyn StdError + 'static)) -> bool {
+    let mut current = Some(err);
+    while let Some(e) = current {
+        let not_found = match e.downcast_ref::<ApicurioError>() {
+            Some(e) => e.is_not_found(),
+            None => {
+                e.downcast_ref::<reqwest::Error>()
+                    .and_then(reqwest::Error::status)
+                    == Some(reqwest::StatusCode::NOT_FOUND)
+            }
+        };
+        if not_found {
+            return true;
+        }
+        current = e.source();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_classification() {
+        let url = "http://registry/apis/registry/v3/groups/g/artifacts/a";
+        let err = ApicurioError::from_status(url, reqwest::StatusCode::UNAUTHORIZED, None);
+        assert!(matches!(err, ApicurioError::Auth { .. }));
+
+        let err = ApicurioError::from_status(url, reqwest::StatusCode::NOT_FOUND, None);
+        assert!(err.is_not_found());
+        let wrapped = anyhow::Error::from(err.context("reading users"));
+        assert!(is_not_found(wrapped.as_ref()));
+        assert!(wrapped.to_string().starts_with("reading users: "));
+
+        let err = ApicurioError::from_status(
+            url,
+            reqwest::StatusCode::CONFLICT,
+            Some("exists\n".to_string()),
+        );
+        assert!(matches!(
+            err,
+            ApicurioError::Network {
+                status: Some(409),
+                ..
+            }
+        ));
+        assert!(err.to_string().ends_with("HTTP 409 Conflict - exists"));
+    }
+}
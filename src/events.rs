@@ -0,0 +1,177 @@
+//! Progress events emitted while resolving, downloading and publishing
+//!
+//! Operations report what they do through an [`EventSink`] instead of printing,
+//! so embedders (e.g. a GUI) can receive structured progress. The CLI uses
+//! [`ConsoleSink`]; [`crate::api::Workspace`] is silent unless a sink is set.
+
+use std::sync::Mutex;
+
+/// Something that happened during an operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A dependency was resolved to an exact version
+    Resolved {
+        name: String,
+        /// Registry the version was found in
+        registry: String,
+        version: String,
+        transitive: bool,
+        /// Bytes downloaded to hash the content, `None` when the hash of the
+        /// previous lock entry was reused
+        bytes: Option<usize>,
+    },
+    /// A transitive dependency was left out of the lock file
+    Skipped { name: String, reason: String },
+    /// Downloading a locked dependency started
+    DownloadStarted { name: String, version: String },
+    /// The output file of a locked dependency already holds the locked content,
+    /// so it was not downloaded
+    UpToDate { name: String, output_path: String },
+    /// A locked dependency was downloaded and staged for writing
+    DownloadFinished {
+        name: String,
+        version: String,
+        output_path: String,
+        bytes: usize,
+    },
+    /// An output file that is no longer locked was deleted
+    OutputRemoved {
+        path: String,
+        /// Set if the dependency was removed from the config entirely
+        removed_dependency: Option<String>,
+    },
+    /// An output directory left empty was deleted
+    DirectoryRemoved { path: String },
+    /// The version of a publish was read from its version source (`fromFile`, ...)
+    VersionSourced {
+        name: String,
+        version: String,
+        /// File the version was read from, or `git tag`
+        source: String,
+    },
+    /// Publishing an artifact version started
+    PublishStarted {
+        name: String,
+        version: String,
+        registry: String,
+    },
+    /// An artifact version was created in the registry
+    Published {
+        artifact_id: String,
+        version: String,
+        /// Created (or updated) as a `DRAFT` version
+        draft: bool,
+    },
+    /// The version already exists in the registry with identical content
+    AlreadyPublished {
+        artifact_id: String,
+        version: String,
+    },
+    /// A git tag was created for a published version
+    Tagged { tag: String },
+    /// The managed block of `.gitignore` was rewritten (`gitignore.manage`)
+    GitignoreUpdated,
+    /// Something unexpected that did not stop the operation
+    Warning(String),
+}
+
+/// Receiver of [`Event`]s
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: Event);
+}
+
+/// Discards every event
+pub struct NullSink;
+
+impl EventSink for NullSink {
+    fn emit(&self, _event: Event) {}
+}
+
+/// Prints events for a person watching the terminal
+pub struct ConsoleSink;
+
+impl EventSink for ConsoleSink {
+    fn emit(&self, event: Event) {
+        match event {
+            // Too chatty for the console; the final summary covers these
+            Event::Resolved { .. } | Event::UpToDate { .. } | Event::DownloadStarted { .. } => {}
+            Event::Skipped { name, reason } => {
+                println!("  ⏭️  Skipping transitive dependency {name} ({reason})")
+            }
+            Event::DownloadFinished {
+                name,
+                version,
+                output_path,
+                ..
+            } => println!("  📥 {name}@{version} → {output_path}"),
+            Event::OutputRemoved {
+                path,
+                removed_dependency: None,
+            } => println!("🗑️  Removed old output file: {path}"),
+            Event::OutputRemoved {
+                path,
+                removed_dependency: Some(name),
+            } => println!("🗑️  Removed output file for removed dependency '{name}': {path}"),
+            Event::DirectoryRemoved { path } => println!("🗑️  Removed empty directory: {path}"),
+            Event::VersionSourced {
+                name,
+                version,
+                source,
+            } => println!("🔖 Version of {name} read from {source}: {version}"),
+            Event::PublishStarted {
+                name,
+                version,
+                registry,
+            } => println!("Publishing {name}@{version} to registry '{registry}'..."),
+            Event::Published {
+                artifact_id,
+                version,
+                draft: false,
+            } => println!("  ✅ Published {artifact_id}@{version}"),
+            Event::Published {
+                artifact_id,
+                version,
+                draft: true,
+            } => println!("  📝 Published draft {artifact_id}@{version}"),
+            Event::AlreadyPublished {
+                artifact_id,
+                version,
+            } => println!(
+                "  ℹ️  Version {artifact_id}@{version} already published with identical content"
+            ),
+            Event::Tagged { tag } => println!("  🏷️  Tagged {tag}"),
+            Event::GitignoreUpdated => println!("📝 Updated managed block in .gitignore"),
+            Event::Warning(message) => eprintln!("⚠️  {message}"),
+        }
+    }
+}
+
+/// Prints only warnings, to stderr, leaving stdout to `--format json` output
+pub struct WarningSink;
+
+impl EventSink for WarningSink {
+    fn emit(&self, event: Event) {
+        if let Event::Warning(message) = event {
+            eprintln!("⚠️  {message}");
+        }
+    }
+}
+
+/// Keeps every event in memory, e.g. for tests or to report them later
+#[derive(Default)]
+pub struct CollectingSink {
+    events: Mutex<Vec<Event>>,
+}
+
+impl CollectingSink {
+    /// The events received so far
+    pub fn events(&self) -> Vec<Event> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl EventSink for CollectingSink {
+    fn emit(&self, event: Event) {
+        self.events.lock().unwrap().push(event);
+    }
+}
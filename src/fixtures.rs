@@ -0,0 +1,278 @@
+//! Recording and replaying registry traffic
+//!
+//! With `--record <file>` (`APICURIO_RECORD`) every answer a [`RegistryClient`]
+//! receives is appended to a JSON fixture file; with `--replay <file>`
+//! (`APICURIO_REPLAY`) the answers are served from that file instead and the
+//! network is never touched. A replayed request is matched by method, URL and
+//! body; the same request sent several times gets the recorded answers in order,
+//! the last one repeating once they run out. Request headers (credentials) are
+//! not recorded.
+//!
+//! [`RegistryClient`]: crate::registry::RegistryClient
+
+use crate::{
+    config::RunSettings,
+    error::{ApicurioError, Result},
+};
+use reqwest::{Client, Request, Response, ResponseBuilderExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// Environment variable naming the fixture file to record to (like `--record`)
+pub const RECORD_ENV: &str = "APICURIO_RECORD";
+/// Environment variable naming the fixture file to replay (like `--replay`)
+pub const REPLAY_ENV: &str = "APICURIO_REPLAY";
+
+/// Answer headers worth keeping; the others vary between runs
+const KEPT_HEADERS: [&str; 3] = ["content-type", "content-range", "www-authenticate"];
+
+/// One request and the answer the registry gave
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Exchange {
+    pub method: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    pub status: u16,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Answer body, base64-encoded when `base64` is set (non-UTF-8 content)
+    #[serde(default)]
+    pub body: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub base64: bool,
+}
+
+/// Content of a fixture file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FixtureFile {
+    pub exchanges: Vec<Exchange>,
+}
+
+/// A fixture file being recorded or replayed, shared by every client
+#[derive(Debug)]
+pub enum Fixtures {
+    Record {
+        path: PathBuf,
+        file: Mutex<FixtureFile>,
+    },
+    Replay {
+        path: PathBuf,
+        file: FixtureFile,
+        /// Answers already served per request (method, URL and body)
+        served: Mutex<HashMap<String, usize>>,
+    },
+}
+
+/// Fixtures opened from the environment, reused by every client of the process
+static ACTIVE: Mutex<Option<Arc<Fixtures>>> = Mutex::new(None);
+
+impl Fixtures {
+    /// Record to `path`, starting from an empty file
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Fixtures::Record {
+            path: path.into(),
+            file: Mutex::new(FixtureFile::default()),
+        }
+    }
+
+    /// Replay the exchanges recorded in `path`
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let data = fs::read_to_string(&path).map_err(|e| ApicurioError::io("reading", &path, e))?;
+        let file = serde_json::from_str(&data).map_err(|e| {
+            ApicurioError::config_caused(format!("invalid fixture file {}", path.display()), e)
+        })?;
+        Ok(Fixtures::Replay {
+            path,
+            file,
+            served: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The fixtures selected by `--record`/`--replay`, else by [`RECORD_ENV`]
+    /// or [`REPLAY_ENV`], if any
+    pub fn selected() -> Result<Option<Arc<Fixtures>>> {
+        let settings = RunSettings::current();
+        let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+        let (record, replay) = match (&settings.record, &settings.replay) {
+            (None, None) => (
+                var(RECORD_ENV).map(PathBuf::from),
+                var(REPLAY_ENV).map(PathBuf::from),
+            ),
+            (record, replay) => (record.clone(), replay.clone()),
+        };
+        let (record, path) = match (record, replay) {
+            (None, None) => return Ok(None),
+            (Some(_), Some(_)) => {
+                return Err(ApicurioError::config(format!(
+                    "{RECORD_ENV} and {REPLAY_ENV} cannot be used together"
+                )))
+            }
+            (Some(path), None) => (true, path),
+            (None, Some(path)) => (false, path),
+        };
+        let mut active = ACTIVE.lock().unwrap();
+        if let Some(fixtures) = active.as_ref() {
+            if fixtures.path() == path && fixtures.is_recording() == record {
+                return Ok(Some(Arc::clone(fixtures)));
+            }
+        }
+        let fixtures = Arc::new(if record {
+            Fixtures::record(path)
+        } else {
+            Fixtures::replay(path)?
+        });
+        *active = Some(Arc::clone(&fixtures));
+        Ok(Some(fixtures))
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            Fixtures::Record { path, .. } | Fixtures::Replay { path, .. } => path,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        matches!(self, Fixtures::Record { .. })
+    }
+
+    /// Answer `request`: sent with `client` and recorded, or looked up in the
+    /// replayed file
+    pub(crate) async fn exchange(&self, client: &Client, request: Request) -> Result<Response> {
+        let method = request.method().to_string();
+        let url = request.url().to_string();
+        let request_body = request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .map(|b| String::from_utf8_lossy(b).into_owned());
+        match self {
+            Fixtures::Record { path, file } => {
+                let response = client.execute(request).await?;
+                let status = response.status().as_u16();
+                let headers = response
+                    .headers()
+                    .iter()
+                    .filter(|(name, _)| KEPT_HEADERS.contains(&name.as_str()))
+                    .filter_map(|(name, value)| {
+                        Some((name.to_string(), value.to_str().ok()?.to_string()))
+                    })
+                    .collect();
+                let bytes = response.bytes().await?;
+                let (body, base64) = match std::str::from_utf8(&bytes) {
+                    Ok(text) => (text.to_string(), false),
+                    Err(_) => (base64::encode(&bytes), true),
+                };
+                let exchange = Exchange {
+                    method,
+                    url,
+                    request_body,
+                    status,
+                    headers,
+                    body,
+                    base64,
+                };
+                let response = exchange.to_response()?;
+                let mut file = file.lock().unwrap();
+                file.exchanges.push(exchange);
+                // Written after every exchange: the process may end at any point
+                let data = serde_json::to_string_pretty(&*file).map_err(|e| {
+                    ApicurioError::config_caused("serializing the recorded exchanges", e)
+                })?;
+                fs::write(path, data + "\n").map_err(|e| ApicurioError::io("writing", path, e))?;
+                Ok(response)
+            }
+            Fixtures::Replay { path, file, served } => {
+                let matching: Vec<&Exchange> = file
+                    .exchanges
+                    .iter()
+                    .filter(|e| {
+                        e.method == method && e.url == url && e.request_body == request_body
+                    })
+                    .collect();
+                let Some(last) = matching.last() else {
+                    return Err(ApicurioError::config(format!(
+                        "no answer to {method} {url} was recorded in {}",
+                        path.display()
+                    )));
+                };
+                let key = format!("{method} {url}\n{}", request_body.unwrap_or_default());
+                let mut served = served.lock().unwrap();
+                let count = served.entry(key).or_default();
+                let exchange = matching.get(*count).unwrap_or(last);
+                *count += 1;
+                exchange.to_response()
+            }
+        }
+    }
+}
+
+impl Exchange {
+    fn to_response(&self) -> Result<Response> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            ApicurioError::config(format!("invalid fixture answer for {}: {e}", self.url))
+        };
+        let body = if self.base64 {
+            base64::decode(&self.body).map_err(|e| invalid(&e))?
+        } else {
+            self.body.clone().into_bytes()
+        };
+        let url = reqwest::Url::parse(&self.url).map_err(|e| invalid(&e))?;
+        let mut builder = http::Response::builder().status(self.status).url(url);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        Ok(Response::from(builder.body(body).map_err(|e| invalid(&e))?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dev_server::DevServer, registry::RegistryClient};
+
+    #[tokio::test]
+    async fn test_record_then_replay_offline() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixtures.json");
+        let server = DevServer::start("127.0.0.1:0").await.unwrap();
+        server.add_version("com.example", "users", "PROTOBUF", "1.0.0", "v1");
+        server.add_version("com.example", "users", "PROTOBUF", "1.1.0", "v2");
+        let config = server.registry_config("dev");
+
+        let recorder = Arc::new(Fixtures::record(&path));
+        let client = RegistryClient::new(&config)
+            .unwrap()
+            .with_fixtures(recorder);
+        let versions = client.list_versions("com.example", "users").await.unwrap();
+        let content = client
+            .get_version_content("com.example", "users", "1.1.0")
+            .await
+            .unwrap();
+        drop(server);
+
+        let replayer = Arc::new(Fixtures::replay(&path).unwrap());
+        let client = RegistryClient::new(&config)
+            .unwrap()
+            .with_fixtures(replayer);
+        assert_eq!(
+            client.list_versions("com.example", "users").await.unwrap(),
+            versions
+        );
+        assert_eq!(
+            client
+                .get_version_content("com.example", "users", "1.1.0")
+                .await
+                .unwrap(),
+            content
+        );
+        let err = client.list_groups().await.unwrap_err();
+        assert!(err.to_string().contains("no answer to GET"));
+    }
+}
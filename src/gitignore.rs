@@ -0,0 +1,239 @@
+//! Keeping pulled files out of (or in) git
+//!
+//! With `gitignore.manage: true`, `lock` and `pull` maintain a block in the
+//! project's `.gitignore` between two marker comments, listing every locked
+//! output path (or the single `gitignore.directory`). Lines outside the block
+//! are never touched, and the block is removed again once management is turned
+//! off.
+//!
+//! Teams that vendor schemas instead set `gitignore.ensureCommitted: true` (or
+//! pass `pull --ensure-committed`): pulling then fails while a pulled file is
+//! ignored, untracked or differs from what is committed.
+
+use anyhow::{bail, Context, Result};
+use std::{fs, path::Path};
+
+use crate::{config::GitignoreConfig, lockfile::LockedDependency};
+
+/// First line of the managed block
+pub const BEGIN_MARKER: &str = "# BEGIN apicurio-cli managed block - DO NOT EDIT";
+/// Last line of the managed block
+pub const END_MARKER: &str = "# END apicurio-cli managed block";
+
+/// Patterns listed in the managed block, anchored to the project directory
+pub fn entries(config: &GitignoreConfig, paths: &[&str]) -> Vec<String> {
+    let anchor = |path: &str| {
+        let path = crate::output_path::portable(path);
+        format!("/{}", path.trim_start_matches("./").trim_start_matches('/'))
+    };
+    if let Some(directory) = &config.directory {
+        return vec![format!("{}/", anchor(directory).trim_end_matches('/'))];
+    }
+    let mut entries: Vec<String> = paths.iter().map(|p| anchor(p)).collect();
+    entries.sort();
+    entries.dedup();
+    entries
+}
+
+/// Replace the managed block of a `.gitignore`, append it, or remove it when
+/// `entries` is empty
+pub fn update_block(existing: &str, entries: &[String]) -> String {
+    let mut outside = Vec::new();
+    let mut block_at = None;
+    let mut in_block = false;
+    for line in existing.lines() {
+        if line.trim_end() == BEGIN_MARKER {
+            in_block = true;
+            block_at.get_or_insert(outside.len());
+        } else if in_block {
+            in_block = line.trim_end() != END_MARKER;
+        } else {
+            outside.push(line);
+        }
+    }
+
+    let mut block = Vec::new();
+    if !entries.is_empty() {
+        block.push(BEGIN_MARKER);
+        block.extend(entries.iter().map(String::as_str));
+        block.push(END_MARKER);
+    }
+    if block.is_empty() && block_at == Some(outside.len()) {
+        // Drop the separator line appended along with the block
+        while outside.last().is_some_and(|l| l.trim().is_empty()) {
+            outside.pop();
+        }
+        block_at = Some(outside.len());
+    }
+    let at = block_at.unwrap_or_else(|| {
+        // Appended blocks are set apart from the user's own entries
+        if !block.is_empty() && outside.last().is_some_and(|l| !l.trim().is_empty()) {
+            outside.push("");
+        }
+        outside.len()
+    });
+    let lines: Vec<&str> = outside[..at]
+        .iter()
+        .chain(&block)
+        .chain(&outside[at..])
+        .copied()
+        .collect();
+    let mut out = lines.join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Bring the managed block of `<root>/.gitignore` in line with the locked outputs
+///
+/// `extra` lists generated files that are not lock entries (e.g. the descriptor
+/// set). Returns whether `.gitignore` was changed.
+pub fn sync(
+    root: &Path,
+    config: &GitignoreConfig,
+    dependencies: &[LockedDependency],
+    extra: &[&str],
+) -> Result<bool> {
+    let path = root.join(".gitignore");
+    let existing = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
+    };
+    let entries = if config.manage && !config.ensure_committed {
+        let paths: Vec<&str> = dependencies
+            .iter()
+            .map(|d| d.output_path.as_str())
+            .chain(extra.iter().copied())
+            .collect();
+        entries(config, &paths)
+    } else {
+        Vec::new()
+    };
+    let updated = update_block(&existing, &entries);
+    if updated == existing {
+        return Ok(false);
+    }
+    fs::write(&path, updated).with_context(|| format!("writing {}", path.display()))?;
+    Ok(true)
+}
+
+/// Fail unless every path is committed: not ignored, tracked and unmodified
+pub fn ensure_committed(root: &Path, paths: &[&str]) -> Result<()> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let dir = if root.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        root
+    };
+    let output = std::process::Command::new("git")
+        .args([
+            "status",
+            "--porcelain",
+            "--ignored",
+            "--untracked-files=all",
+            "--",
+        ])
+        .args(paths)
+        .current_dir(dir)
+        .output()
+        .context("running git status")?;
+    if !output.status.success() {
+        bail!(
+            "git status failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let problems = uncommitted(&String::from_utf8_lossy(&output.stdout));
+    if !problems.is_empty() {
+        bail!(
+            "pulled files are not committed (ensureCommitted):\n{}\n\
+             commit them, or remove them from .gitignore",
+            problems.join("\n")
+        );
+    }
+    Ok(())
+}
+
+/// Describe the entries of `git status --porcelain --ignored` output
+fn uncommitted(porcelain: &str) -> Vec<String> {
+    porcelain
+        .lines()
+        .filter(|line| line.len() > 3)
+        .map(|line| {
+            let (status, path) = line.split_at(3);
+            let reason = match status.trim() {
+                "!!" => "ignored",
+                "??" => "untracked",
+                _ => "modified",
+            };
+            format!("  {path} ({reason})")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_managed_block_round_trip() {
+        let user = "target/\n*.log\n";
+        let entries = vec!["/protos/a.proto".to_string(), "/schemas/b.avsc".to_string()];
+        let written = update_block(user, &entries);
+        assert_eq!(
+            written,
+            format!("target/\n*.log\n\n{BEGIN_MARKER}\n/protos/a.proto\n/schemas/b.avsc\n{END_MARKER}\n")
+        );
+
+        // The block is replaced in place, keeping lines around it
+        let with_more = format!("{written}dist/\n");
+        let replaced = update_block(&with_more, &["/protos/a.proto".to_string()]);
+        assert_eq!(
+            replaced,
+            format!("target/\n*.log\n\n{BEGIN_MARKER}\n/protos/a.proto\n{END_MARKER}\ndist/\n")
+        );
+        assert_eq!(
+            update_block(&replaced, &["/protos/a.proto".to_string()]),
+            replaced
+        );
+
+        assert_eq!(update_block(&written, &[]), user);
+        assert_eq!(update_block("", &[]), "");
+    }
+
+    #[test]
+    fn test_entries_and_uncommitted() {
+        let config = GitignoreConfig {
+            manage: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            entries(
+                &config,
+                &["protos/b.proto", "./protos/a.proto", "protos/b.proto"]
+            ),
+            vec!["/protos/a.proto", "/protos/b.proto"]
+        );
+        let config = GitignoreConfig {
+            directory: Some("vendor/schemas/".to_string()),
+            ..config
+        };
+        assert_eq!(
+            entries(&config, &["protos/a.proto"]),
+            vec!["/vendor/schemas/"]
+        );
+
+        assert_eq!(
+            uncommitted("!! protos/a.proto\n?? protos/b.proto\n M protos/c.proto\n"),
+            vec![
+                "  protos/a.proto (ignored)",
+                "  protos/b.proto (untracked)",
+                "  protos/c.proto (modified)"
+            ]
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! Stitching check of pulled GraphQL schemas
+//!
+//! With `graphql.check` set, `pull` reads every locked `.graphql`/`.gql` file as
+//! one schema and reports what would break stitching them together: a type
+//! defined in more than one place, and references to types no pulled file
+//! defines. Only the type system (SDL) is read; operations are skipped.
+//! Types provided elsewhere, e.g. by a federation gateway, are listed under
+//! `graphql.knownTypes`.
+
+use anyhow::{Context, Result};
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{config::GraphqlConfig, lockfile::LockedDependency, output_path, provenance};
+
+/// Scalars every GraphQL schema has
+const BUILT_IN_TYPES: [&str; 5] = ["Int", "Float", "String", "Boolean", "ID"];
+
+/// One stitching problem, located in a pulled file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// Output path of the file
+    pub path: String,
+    /// 1-based line
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.path, self.line, self.message)
+    }
+}
+
+/// Whether `path` is a GraphQL schema file
+pub fn is_graphql(path: &str) -> bool {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    matches!(ext.as_str(), "graphql" | "graphqls" | "gql")
+}
+
+/// Check the GraphQL files among `dependencies`, relative to `root`
+///
+/// Returns no problems when the check is off or no GraphQL file is pulled.
+pub fn check(
+    root: &Path,
+    config: &GraphqlConfig,
+    dependencies: &[LockedDependency],
+) -> Result<Vec<Problem>> {
+    if !config.check.is_enabled() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<&str> = dependencies
+        .iter()
+        .map(|d| d.output_path.as_str())
+        .filter(|path| is_graphql(path))
+        .collect();
+    paths.sort_unstable();
+    paths.dedup();
+    let mut files = Vec::new();
+    for path in paths {
+        let file = output_path::native(root, path);
+        // a pull of one dependency leaves the files of others missing
+        let data = match fs::read(&file) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("reading {}", file.display())),
+        };
+        let text = String::from_utf8_lossy(&provenance::strip_header(&data)).into_owned();
+        files.push((path.to_string(), text));
+    }
+    Ok(check_sources(&files, &config.known_types))
+}
+
+/// Check GraphQL sources (`(path, content)`) as one schema
+pub fn check_sources(files: &[(String, String)], known_types: &[String]) -> Vec<Problem> {
+    let mut definitions: BTreeMap<String, Vec<(String, usize)>> = BTreeMap::new();
+    let mut references = Vec::new();
+    for (path, content) in files {
+        let schema = parse(content);
+        for (name, line) in schema.definitions {
+            definitions
+                .entry(name)
+                .or_default()
+                .push((path.clone(), line));
+        }
+        references.extend(
+            schema
+                .references
+                .into_iter()
+                .map(|(name, line)| (path.clone(), name, line)),
+        );
+    }
+
+    let mut problems = Vec::new();
+    for (name, places) in &definitions {
+        if let [(first_path, first_line), others @ ..] = places.as_slice() {
+            for (path, line) in others {
+                problems.push(Problem {
+                    path: path.clone(),
+                    line: *line,
+                    message: format!(
+                        "type '{name}' is already defined at {first_path}:{first_line}"
+                    ),
+                });
+            }
+        }
+    }
+    for (path, name, line) in references {
+        let known = definitions.contains_key(&name)
+            || BUILT_IN_TYPES.contains(&name.as_str())
+            || known_types.contains(&name);
+        if !known {
+            problems.push(Problem {
+                path,
+                line,
+                message: format!("type '{name}' is not defined by any pulled schema"),
+            });
+        }
+    }
+    problems.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    problems.dedup();
+    problems
+}
+
+/// Type names a document defines and refers to, with their lines
+#[derive(Debug, Default)]
+struct Schema {
+    definitions: Vec<(String, usize)>,
+    references: Vec<(String, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Punct(char),
+}
+
+/// Names and punctuation with their lines; comments, strings (descriptions),
+/// numbers and commas are dropped
+fn tokenize(content: &str) -> Vec<(Token, usize)> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        match c {
+            '\n' => line += 1,
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '"' if chars[i..].starts_with(&['"', '"']) => {
+                // block string, ends at the next unescaped `"""`
+                i += 2;
+                while i < chars.len() && !chars[i..].starts_with(&['"', '"', '"']) {
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i += 3;
+            }
+            '"' => {
+                while i < chars.len() && !matches!(chars[i], '"' | '\n') {
+                    i += if chars[i] == '\\' { 2 } else { 1 };
+                }
+                i += 1;
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i - 1;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push((Token::Name(chars[start..i].iter().collect()), line));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric() || matches!(chars[i], '.' | '+' | '-'))
+                {
+                    i += 1;
+                }
+            }
+            c if c == ',' || c.is_whitespace() => {}
+            c => tokens.push((Token::Punct(c), line)),
+        }
+    }
+    tokens
+}
+
+/// Walks the tokens of one document
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    schema: Schema,
+}
+
+fn parse(content: &str) -> Schema {
+    let mut parser = Parser {
+        tokens: tokenize(content),
+        pos: 0,
+        schema: Schema::default(),
+    };
+    parser.document();
+    parser.schema
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |(_, line)| *line)
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let found = self.is_punct(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn name(&mut self) -> Option<(String, usize)> {
+        match self.peek() {
+            Some(Token::Name(name)) => {
+                let found = (name.clone(), self.line());
+                self.pos += 1;
+                Some(found)
+            }
+            _ => None,
+        }
+    }
+
+    fn document(&mut self) {
+        while let Some(token) = self.peek().cloned() {
+            match token {
+                Token::Name(keyword) => {
+                    self.pos += 1;
+                    match keyword.as_str() {
+                        "extend" => {
+                            if let Some((kind, _)) = self.name() {
+                                self.definition(&kind, true);
+                            }
+                        }
+                        "type" | "interface" | "input" | "enum" | "union" | "scalar" => {
+                            self.definition(&keyword, false)
+                        }
+                        "schema" => {
+                            self.directives();
+                            self.fields();
+                        }
+                        "directive" => {
+                            self.eat_punct('@');
+                            self.name();
+                            self.arguments();
+                        }
+                        // operations and fragments are not part of the schema
+                        "query" | "mutation" | "subscription" | "fragment" => {
+                            while !self.is_punct('{') && self.peek().is_some() {
+                                self.pos += 1;
+                            }
+                            self.skip_balanced();
+                        }
+                        _ => {}
+                    }
+                }
+                Token::Punct('{') => self.skip_balanced(),
+                Token::Punct(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn definition(&mut self, kind: &str, extension: bool) {
+        let Some((name, line)) = self.name() else {
+            return;
+        };
+        if extension {
+            self.schema.references.push((name, line));
+        } else {
+            self.schema.definitions.push((name, line));
+        }
+        if self.peek() == Some(&Token::Name("implements".to_string())) {
+            self.pos += 1;
+            loop {
+                self.eat_punct('&');
+                match self.peek() {
+                    Some(Token::Name(_)) => {
+                        let reference = self.name().unwrap_or_default();
+                        self.schema.references.push(reference);
+                    }
+                    _ => break,
+                }
+            }
+        }
+        self.directives();
+        match kind {
+            "union" if self.eat_punct('=') => loop {
+                self.eat_punct('|');
+                match self.peek() {
+                    Some(Token::Name(member)) if !is_keyword(member) => {
+                        let reference = self.name().unwrap_or_default();
+                        self.schema.references.push(reference);
+                    }
+                    _ => break,
+                }
+            },
+            "enum" => self.skip_balanced(),
+            "type" | "interface" | "input" => self.fields(),
+            _ => {}
+        }
+    }
+
+    /// `{ name(args): Type = default @directive ... }`
+    fn fields(&mut self) {
+        if !self.eat_punct('{') {
+            return;
+        }
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Punct('}') => {
+                    self.pos += 1;
+                    return;
+                }
+                Token::Name(_) => {
+                    self.pos += 1;
+                    self.arguments();
+                    if self.eat_punct(':') {
+                        self.type_reference();
+                    }
+                    self.default_value();
+                    self.directives();
+                }
+                Token::Punct(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// `(name: Type = default @directive, ...)`
+    fn arguments(&mut self) {
+        if !self.eat_punct('(') {
+            return;
+        }
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Punct(')') => {
+                    self.pos += 1;
+                    return;
+                }
+                Token::Name(_) => {
+                    self.pos += 1;
+                    if self.eat_punct(':') {
+                        self.type_reference();
+                    }
+                    self.default_value();
+                    self.directives();
+                }
+                Token::Punct(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// `Name`, `Name!`, `[Name!]!`, ...
+    fn type_reference(&mut self) {
+        if self.eat_punct('[') {
+            self.type_reference();
+            self.eat_punct(']');
+        } else if let Some(reference) = self.name() {
+            self.schema.references.push(reference);
+        }
+        self.eat_punct('!');
+    }
+
+    fn default_value(&mut self) {
+        if !self.eat_punct('=') {
+            return;
+        }
+        if self.is_punct('{') || self.is_punct('[') {
+            self.skip_balanced();
+        } else if matches!(self.peek(), Some(Token::Name(_))) {
+            self.pos += 1;
+        }
+    }
+
+    /// `@name(arg: value ...)`, whose values are not type references
+    fn directives(&mut self) {
+        while self.eat_punct('@') {
+            self.name();
+            if self.is_punct('(') {
+                self.skip_balanced();
+            }
+        }
+    }
+
+    /// Skip a `{...}`, `[...]` or `(...)` group with everything nested in it
+    fn skip_balanced(&mut self) {
+        if !matches!(self.peek(), Some(Token::Punct('{' | '[' | '('))) {
+            return;
+        }
+        let mut depth = 0usize;
+        while let Some(token) = self.peek().cloned() {
+            self.pos += 1;
+            match token {
+                Token::Punct('{' | '[' | '(') => depth += 1,
+                Token::Punct('}' | ']' | ')') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Keywords starting a new definition, ending a union member list
+fn is_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "type"
+            | "interface"
+            | "input"
+            | "enum"
+            | "union"
+            | "scalar"
+            | "schema"
+            | "directive"
+            | "extend"
+            | "query"
+            | "mutation"
+            | "subscription"
+            | "fragment"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stitching_reports_duplicates_and_unknown_types() {
+        let users = r#"
+"""
+A user, with "quotes" in its description
+"""
+type User implements Node @key(fields: "id") {
+  id: ID!
+  # orders: [Ghost]
+  orders(first: Int = 10, sort: SortOrder = ASC, filter: OrderFilter = {status: OPEN}): [Order!]!
+}
+interface Node { id: ID! }
+enum SortOrder { ASC DESC }
+"#
+        .to_string();
+        let orders = r#"
+type Order {
+  id: ID!
+  total: Money @deprecated(reason: "use amount")
+  buyer: User
+}
+input OrderFilter { status: OrderStatus }
+union SearchResult = User | Order
+type User { id: ID! }
+extend type Query { orders: [Order] }
+directive @audit(level: AuditLevel) on FIELD_DEFINITION
+"#
+        .to_string();
+        let files = vec![
+            ("graphql/users.graphql".to_string(), users),
+            ("graphql/orders.graphql".to_string(), orders),
+        ];
+
+        let problems: Vec<String> = check_sources(&files, &["Money".to_string()])
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            problems,
+            [
+                "graphql/orders.graphql:7: type 'OrderStatus' is not defined by any pulled schema",
+                "graphql/orders.graphql:9: type 'User' is already defined at graphql/users.graphql:5",
+                "graphql/orders.graphql:10: type 'Query' is not defined by any pulled schema",
+                "graphql/orders.graphql:11: type 'AuditLevel' is not defined by any pulled schema",
+            ]
+        );
+        assert!(is_graphql("graphql/users.gql"));
+        assert!(!is_graphql("protos/users.proto"));
+    }
+}
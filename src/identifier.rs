@@ -95,12 +95,12 @@ impl Identifier {
                     .interact()?;
                 self.registry = Some(available_registries[selection].clone());
             }
-        } else {
+        } else if let Some(registry) = &self.registry {
             // Validate the provided registry
-            if !available_registries.contains(self.registry.as_ref().unwrap()) {
+            if !available_registries.contains(registry) {
                 return Err(anyhow!(
                     "Registry '{}' not found. Available registries: {}",
-                    self.registry.as_ref().unwrap(),
+                    registry,
                     available_registries.join(", ")
                 ));
             }
@@ -317,7 +317,9 @@ impl Identifier {
         registry_client: &crate::registry::RegistryClient,
     ) -> Result<bool> {
         if let (Some(group_id), Some(artifact_id)) = (&self.group_id, &self.artifact_id) {
-            registry_client.artifact_exists(group_id, artifact_id).await
+            Ok(registry_client
+                .artifact_exists(group_id, artifact_id)
+                .await?)
         } else {
             Ok(false)
         }
@@ -455,6 +457,143 @@ impl Identifier {
     }
 }
 
+/// Find the single configured dependency an identifier refers to
+///
+/// An exact dependency `name` wins; otherwise the identifier is parsed and must
+/// match exactly one dependency, so non-interactive commands never guess.
+pub fn find_dependency<'a>(
+    input: &str,
+    dependencies: &'a [crate::config::DependencyConfig],
+) -> Result<&'a crate::config::DependencyConfig> {
+    if let Some(dep) = dependencies.iter().find(|d| d.name == input) {
+        return Ok(dep);
+    }
+
+    let matches = Identifier::parse(input).find_matches(dependencies);
+    match matches.as_slice() {
+        [] => Err(anyhow!(
+            "No dependency found matching identifier: '{}'",
+            input
+        )),
+        [dep] => Ok(dep),
+        _ => Err(anyhow!(
+            "Identifier '{}' is ambiguous, it matches: {}",
+            input,
+            matches
+                .iter()
+                .map(|d| d.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// A registry artifact that a command-line identifier points at
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtifactTarget {
+    pub registry: String,
+    pub group_id: String,
+    pub artifact_id: String,
+    /// Version given with `@version`, if any
+    pub version: Option<String>,
+    /// The configured dependency (name and version range) the identifier refers to
+    pub dependency: Option<(String, String)>,
+}
+
+/// Resolve an identifier to a registry artifact without prompting
+///
+/// The identifier may name a configured dependency, or spell out
+/// `[registry/]group/artifact` for artifacts that are not in the config. Without an
+/// explicit registry, `dependencyDefaults.registry` or the only configured registry
+/// is used.
+pub fn resolve_artifact(
+    input: &str,
+    repo_cfg: &crate::config::RepoConfig,
+    registries: &[String],
+) -> Result<ArtifactTarget> {
+    let (base, version) = match input.rsplit_once('@') {
+        Some((base, version)) if !version.is_empty() => (base, Some(version.to_string())),
+        Some((base, _)) => (base, None),
+        None => (input, None),
+    };
+
+    if let Ok(dep_cfg) = find_dependency(base, &repo_cfg.dependencies) {
+        let dep = crate::dependency::Dependency::from_config_with_defaults(
+            dep_cfg,
+            &repo_cfg.dependency_defaults,
+        )?;
+        return Ok(ArtifactTarget {
+            registry: dep.registry,
+            group_id: dep.group_id,
+            artifact_id: dep.artifact_id,
+            version,
+            dependency: Some((dep_cfg.name.clone(), dep_cfg.version.clone())),
+        });
+    }
+
+    let id = Identifier::parse(base);
+    let (Some(group_id), Some(artifact_id)) = (id.group_id, id.artifact_id) else {
+        return Err(anyhow!(
+            "'{}' is not a configured dependency; use [registry/]group/artifact",
+            input
+        ));
+    };
+    let registry = pick_registry(id.registry, input, repo_cfg, registries)?;
+
+    Ok(ArtifactTarget {
+        registry,
+        group_id,
+        artifact_id,
+        version,
+        dependency: None,
+    })
+}
+
+/// Resolve `[registry/]group` to a registry name and group ID
+///
+/// The registry defaults like in [`resolve_artifact`].
+pub fn resolve_group(
+    input: &str,
+    repo_cfg: &crate::config::RepoConfig,
+    registries: &[String],
+) -> Result<(String, String)> {
+    let (registry, group_id) = match input.split_once('/') {
+        Some((registry, group_id)) => (Some(registry.to_string()), group_id),
+        None => (None, input),
+    };
+    if group_id.is_empty() || group_id.contains('/') {
+        return Err(anyhow!("'{}' is not a group; use [registry/]group", input));
+    }
+    let registry = pick_registry(registry, input, repo_cfg, registries)?;
+    Ok((registry, group_id.to_string()))
+}
+
+/// The explicit registry, else `dependencyDefaults.registry` or the only
+/// configured registry
+fn pick_registry(
+    explicit: Option<String>,
+    input: &str,
+    repo_cfg: &crate::config::RepoConfig,
+    registries: &[String],
+) -> Result<String> {
+    let registry = match (explicit, &repo_cfg.dependency_defaults.registry) {
+        (Some(registry), _) => registry,
+        (None, Some(registry)) => registry.clone(),
+        (None, None) if registries.len() == 1 => registries[0].clone(),
+        (None, None) => {
+            return Err(anyhow!(
+                "Cannot tell which registry to use for '{}'; prefix it with one of: {}",
+                input,
+                registries.join(", ")
+            ))
+        }
+    };
+    if !registries.contains(&registry) {
+        return Err(anyhow!("Registry '{}' not found", registry));
+    }
+    Ok(registry)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -497,4 +636,86 @@ mod tests {
         id.version = None;
         assert_eq!(id.to_display_string(), "myregistry/com.example/myartifact");
     }
+
+    #[test]
+    fn test_find_dependency() {
+        let deps = vec![
+            crate::config::DependencyConfig {
+                name: "user-protos".to_string(),
+                group_id: Some("com.example".to_string()),
+                artifact_id: Some("user-service".to_string()),
+                version: "^1.0.0".to_string(),
+                ..Default::default()
+            },
+            crate::config::DependencyConfig {
+                name: "com.example/order-service".to_string(),
+                version: "^2.0.0".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(
+            find_dependency("user-protos", &deps).unwrap().name,
+            "user-protos"
+        );
+        assert_eq!(
+            find_dependency("com.example/order-service", &deps)
+                .unwrap()
+                .name,
+            "com.example/order-service"
+        );
+        assert_eq!(
+            find_dependency("com.example/user-service", &deps)
+                .unwrap()
+                .name,
+            "user-protos"
+        );
+        assert!(find_dependency("com.example/service", &deps).is_err());
+        assert!(find_dependency("unknown/thing", &deps).is_err());
+    }
+
+    #[test]
+    fn test_resolve_artifact() {
+        let repo_cfg: crate::config::RepoConfig = serde_yaml::from_str(
+            r#"
+dependencyDefaults:
+  registry: main
+dependencies:
+  - name: users
+    groupId: com.example
+    artifactId: user-service
+    version: ^1.2.0
+    outputPath: protos/users.proto
+"#,
+        )
+        .unwrap();
+        let registries = vec!["main".to_string(), "other".to_string()];
+
+        let dep = resolve_artifact("users@1.2.3", &repo_cfg, &registries).unwrap();
+        assert_eq!(dep.registry, "main");
+        assert_eq!(dep.artifact_id, "user-service");
+        assert_eq!(dep.version.as_deref(), Some("1.2.3"));
+        assert_eq!(
+            dep.dependency,
+            Some(("users".to_string(), "^1.2.0".to_string()))
+        );
+
+        let adhoc = resolve_artifact("other/com.acme/orders", &repo_cfg, &registries).unwrap();
+        assert_eq!(adhoc.registry, "other");
+        assert_eq!(adhoc.group_id, "com.acme");
+        assert_eq!(adhoc.dependency, None);
+
+        assert!(resolve_artifact("unknown", &repo_cfg, &registries).is_err());
+        assert!(resolve_artifact("nope/com.acme/orders", &repo_cfg, &registries).is_err());
+
+        assert_eq!(
+            resolve_group("com.acme", &repo_cfg, &registries).unwrap(),
+            ("main".to_string(), "com.acme".to_string())
+        );
+        assert_eq!(
+            resolve_group("other/com.acme", &repo_cfg, &registries).unwrap(),
+            ("other".to_string(), "com.acme".to_string())
+        );
+        assert!(resolve_group("other/com.acme/orders", &repo_cfg, &registries).is_err());
+    }
 }
@@ -0,0 +1,322 @@
+//! `$ref`s and structural checks of locked JSON Schema artifacts
+//!
+//! JSON Schemas often point at other schemas of the same registry by URL
+//! (`$ref: https://registry.example.com/apis/registry/v3/groups/g/artifacts/a/versions/1.0.0/content`)
+//! instead of declaring registry references. With `jsonSchema.resolveRefs`,
+//! `lock` follows those like any other reference, so the referenced schemas are
+//! locked and pulled as transitive dependencies. With `jsonSchema.validate`,
+//! each locked schema is checked for malformed keywords. Either option makes
+//! `lock` report dangling `$ref`s: internal pointers that select nothing and
+//! relative references that name no reference of the artifact.
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::local_refs::{self, RefSyntax};
+
+/// A `$ref` naming an artifact of a registry by URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryRef {
+    pub group_id: String,
+    pub artifact_id: String,
+    /// `None` for the latest version
+    pub version: Option<String>,
+    /// The `$ref` without its fragment, the name the content uses
+    pub name: String,
+}
+
+/// What walking a schema found
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchemaCheck {
+    /// Every `$ref` value
+    pub refs: Vec<String>,
+    /// Malformed keywords, prefixed with their location (`#/properties/id/type`)
+    pub problems: Vec<String>,
+}
+
+/// Parse the content of a pulled file as a JSON Schema; `None` for files that
+/// are not JSON or YAML, or documents that are not schemas (OpenAPI, AsyncAPI,
+/// Avro)
+pub fn parse(path: &str, content: &[u8]) -> Option<Value> {
+    if local_refs::syntax_for(path) != Some(RefSyntax::Json) {
+        return None;
+    }
+    // JSON documents are YAML too
+    let document: Value = serde_yaml::from_slice(content).ok()?;
+    let map = document.as_object()?;
+    if ["openapi", "swagger", "asyncapi"]
+        .iter()
+        .any(|key| map.contains_key(*key))
+        || matches!(
+            map.get("type").and_then(Value::as_str),
+            Some("record" | "enum" | "fixed")
+        )
+    {
+        return None;
+    }
+    let is_schema = map.contains_key("$schema")
+        || [
+            "type",
+            "properties",
+            "$ref",
+            "$defs",
+            "definitions",
+            "allOf",
+            "anyOf",
+            "oneOf",
+        ]
+        .iter()
+        .any(|key| map.contains_key(*key));
+    is_schema.then_some(document)
+}
+
+/// Collect the `$ref`s of `schema` and check its keywords
+///
+/// Only keywords holding subschemas are descended into, so a property named
+/// `type` is not mistaken for the keyword.
+pub fn check(schema: &Value) -> SchemaCheck {
+    let mut found = SchemaCheck::default();
+    walk(schema, "#", &mut found);
+    found
+}
+
+fn walk(schema: &Value, at: &str, found: &mut SchemaCheck) {
+    let map = match schema {
+        Value::Object(map) => map,
+        Value::Bool(_) => return,
+        _ => {
+            found
+                .problems
+                .push(format!("{at}: a schema must be an object or a boolean"));
+            return;
+        }
+    };
+    for (key, value) in map {
+        let here = format!("{at}/{}", key.replace('~', "~0").replace('/', "~1"));
+        let mut problem = |message: &str| found.problems.push(format!("{here}: {message}"));
+        match key.as_str() {
+            "$ref" => match value.as_str() {
+                Some(reference) => found.refs.push(reference.to_string()),
+                None => problem("must be a string"),
+            },
+            "$schema" | "$id" | "$anchor" | "$comment" | "title" | "description" | "format"
+                if !value.is_string() =>
+            {
+                problem("must be a string")
+            }
+            "pattern" => match value.as_str() {
+                Some(pattern) if Regex::new(pattern).is_err() => {
+                    problem(&format!("'{pattern}' is not a valid regular expression"))
+                }
+                Some(_) => {}
+                None => problem("must be a string"),
+            },
+            "type" => check_type(value, &mut problem),
+            "required" => {
+                let names = value
+                    .as_array()
+                    .filter(|items| items.iter().all(Value::is_string));
+                // draft 3 marked required properties with a boolean
+                if names.is_none() && !value.is_boolean() {
+                    problem("must be an array of property names");
+                }
+            }
+            "enum" if !value.is_array() => problem("must be an array"),
+            "properties" | "patternProperties" | "$defs" | "definitions" | "dependentSchemas" => {
+                match value.as_object() {
+                    Some(schemas) => {
+                        for (name, schema) in schemas {
+                            let name = name.replace('~', "~0").replace('/', "~1");
+                            walk(schema, &format!("{here}/{name}"), found);
+                        }
+                    }
+                    None => problem("must be an object of schemas"),
+                }
+            }
+            "items" if value.is_array() => walk_all(value, &here, found),
+            "prefixItems" | "allOf" | "anyOf" | "oneOf" => match value.as_array() {
+                Some(schemas) if !schemas.is_empty() => walk_all(value, &here, found),
+                _ => problem("must be a non-empty array of schemas"),
+            },
+            "items"
+            | "not"
+            | "additionalProperties"
+            | "additionalItems"
+            | "contains"
+            | "propertyNames"
+            | "if"
+            | "then"
+            | "else"
+            | "unevaluatedProperties"
+            | "unevaluatedItems" => walk(value, &here, found),
+            "minLength" | "maxLength" | "minItems" | "maxItems" | "minProperties"
+            | "maxProperties" | "minContains" | "maxContains"
+                if value.as_u64().is_none() =>
+            {
+                problem("must be a non-negative integer")
+            }
+            // draft 4 made the exclusive bounds booleans
+            "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum"
+                if !value.is_number() && !value.is_boolean() =>
+            {
+                problem("must be a number")
+            }
+            "multipleOf" if !value.as_f64().is_some_and(|n| n > 0.0) => {
+                problem("must be a number greater than 0")
+            }
+            _ => {}
+        }
+    }
+}
+
+fn walk_all(schemas: &Value, at: &str, found: &mut SchemaCheck) {
+    for (i, schema) in schemas.as_array().into_iter().flatten().enumerate() {
+        walk(schema, &format!("{at}/{i}"), found);
+    }
+}
+
+fn check_type(value: &Value, problem: &mut impl FnMut(&str)) {
+    const TYPES: [&str; 7] = [
+        "null", "boolean", "object", "array", "number", "string", "integer",
+    ];
+    let names: Vec<&Value> = match value {
+        Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    for name in names {
+        match name.as_str() {
+            Some(name) if TYPES.contains(&name) => {}
+            Some(name) => problem(&format!("'{name}' is not a JSON type")),
+            None => problem("must be a type name or an array of type names"),
+        }
+    }
+}
+
+/// The artifact a `$ref` names by URL on the registry at `base_url`
+///
+/// Accepts the content URLs of the v3 and v2 APIs, with or without a version:
+/// `{base}/apis/registry/v3/groups/{g}/artifacts/{a}[/versions/{v}[/content]]`.
+pub fn registry_ref(base_url: &str, reference: &str) -> Option<RegistryRef> {
+    let name = reference.split('#').next().unwrap_or_default();
+    let path = name
+        .strip_prefix(base_url.trim_end_matches('/'))?
+        .split('?')
+        .next()
+        .unwrap_or_default();
+    let path = path
+        .strip_prefix("/apis/registry/v3/groups/")
+        .or_else(|| path.strip_prefix("/apis/registry/v2/groups/"))?;
+    let parts: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+    let (group_id, artifact_id, version) = match parts.as_slice() {
+        [group, "artifacts", artifact] => (group, artifact, None),
+        [group, "artifacts", artifact, "versions", version]
+        | [group, "artifacts", artifact, "versions", version, "content"] => {
+            (group, artifact, Some(version.to_string()))
+        }
+        _ => return None,
+    };
+    Some(RegistryRef {
+        group_id: group_id.to_string(),
+        artifact_id: artifact_id.to_string(),
+        version: version.filter(|v| v != "latest" && v != "branch=latest"),
+        name: name.to_string(),
+    })
+}
+
+/// `$ref`s of `schema` that lead nowhere: internal pointers selecting nothing
+/// and relative references not among the reference `names` of the artifact
+///
+/// Absolute URLs are left to whoever hosts them, as are relative references of
+/// a schema with an `$id`, which resolve against it.
+pub fn dangling(schema: &Value, refs: &[String], names: &[String]) -> Vec<String> {
+    let has_id = schema.get("$id").is_some_and(Value::is_string);
+    let mut problems = Vec::new();
+    for reference in refs {
+        let (document, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+        if document.is_empty() {
+            if fragment.starts_with('/') && schema.pointer(fragment).is_none() {
+                problems.push(format!(
+                    "$ref '{reference}' points to nothing in the schema"
+                ));
+            }
+        } else if !document.contains("://")
+            && !document.starts_with("urn:")
+            && !has_id
+            && !names.iter().any(|name| name == document)
+        {
+            problems.push(format!(
+                "$ref '{reference}' names no reference of the artifact"
+            ));
+        }
+    }
+    problems.dedup();
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_finds_refs_problems_and_dangling_refs() {
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": {"type": "int"},
+                "type": {"type": "string", "minLength": -1},
+                "owner": {"$ref": "http://registry:8080/apis/registry/v3/groups/com.example/artifacts/user/versions/1.0.0/content#/$defs/User"},
+                "address": {"$ref": "address.json"},
+                "tags": {"$ref": "#/$defs/Tags"},
+                "missing": {"$ref": "#/$defs/Missing"}
+            },
+            "$defs": {"Tags": {"type": "array", "items": {"type": "string"}}}
+        });
+        let found = check(&schema);
+        assert_eq!(found.refs.len(), 4);
+        assert_eq!(
+            found.problems,
+            [
+                "#/properties/id/type: 'int' is not a JSON type",
+                "#/properties/type/minLength: must be a non-negative integer",
+            ]
+        );
+
+        let url = found.refs.iter().find(|r| r.starts_with("http")).unwrap();
+        let owner = registry_ref("http://registry:8080/", url).unwrap();
+        assert_eq!(
+            owner,
+            RegistryRef {
+                group_id: "com.example".to_string(),
+                artifact_id: "user".to_string(),
+                version: Some("1.0.0".to_string()),
+                name: "http://registry:8080/apis/registry/v3/groups/com.example/artifacts/user/versions/1.0.0/content".to_string(),
+            }
+        );
+        assert!(registry_ref("http://other:8080", url).is_none());
+        let latest = registry_ref(
+            "http://registry:8080",
+            "http://registry:8080/apis/registry/v2/groups/g/artifacts/a",
+        )
+        .unwrap();
+        assert_eq!(latest.version, None);
+
+        assert_eq!(
+            dangling(&schema, &found.refs, &[]),
+            [
+                "$ref 'address.json' names no reference of the artifact",
+                "$ref '#/$defs/Missing' points to nothing in the schema",
+            ]
+        );
+        assert_eq!(
+            dangling(&schema, &found.refs, &["address.json".to_string()]).len(),
+            1
+        );
+
+        assert!(parse("api.yaml", b"openapi: 3.0.3\npaths: {}\n").is_none());
+        assert!(parse("user.json", br#"{"type": "object"}"#).is_some());
+        assert!(parse("user.proto", b"syntax = \"proto3\";").is_none());
+    }
+}
@@ -4,14 +4,44 @@
 
 use clap::Parser;
 
+pub mod api;
+pub mod artifact_writer;
+pub mod avro;
 pub mod commands;
 pub mod config;
+pub mod config_edit;
 pub mod constants;
+pub mod credentials;
 pub mod dependency;
+pub mod dev_server;
+pub mod diagnostics;
+pub mod duplicates;
+pub mod error;
+pub mod events;
+pub mod fixtures;
+pub mod gitignore;
+pub mod graphql;
 pub mod identifier;
+pub mod json_schema;
+pub mod lint;
+pub mod local_refs;
 pub mod lockfile;
+pub mod migrate;
+pub mod naming;
+pub mod notify;
+pub mod openapi;
 pub mod output_path;
+pub mod paths;
+pub mod plugins;
+pub mod process_lock;
+pub mod protobuf;
+pub mod provenance;
 pub mod registry;
+pub mod schema_diff;
+pub mod secrets;
+#[cfg(feature = "aws-sigv4")]
+pub mod sigv4;
+pub mod summary;
 
 /// CLI tool for managing schema artifacts from Apicurio Registry
 ///
@@ -26,6 +56,28 @@ pub mod registry;
     long_about = "A powerful Rust-based command-line tool for managing schema artifacts from Apicurio Registry.\n\nFeatures lockfile-based dependency management, multi-registry support, flexible authentication,\nand semantic version resolution for Protobuf, Avro, JSON Schema, OpenAPI, and other schema types."
 )]
 pub struct Cli {
+    /// Run as if started in DIR
+    #[arg(short = 'C', long = "cwd", global = true, value_name = "DIR")]
+    pub cwd: Option<std::path::PathBuf>,
+    /// Project config file to use instead of `apicurioconfig.yaml` (the lock file is kept next to it)
+    #[arg(long = "config", global = true, value_name = "FILE")]
+    pub config_file: Option<std::path::PathBuf>,
+    /// Environment profile to use (overrides `APICURIO_ENV`)
+    #[arg(long, global = true, value_name = "PROFILE")]
+    pub env: Option<String>,
+    /// Record registry answers to FILE, for replaying them with `--replay`
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<std::path::PathBuf>,
+    /// Serve registry answers from FILE (written by `--record`) instead of the network
+    #[arg(long, global = true, value_name = "FILE")]
+    pub replay: Option<std::path::PathBuf>,
+    /// Seconds a registry may stay silent while answering (overrides `APICURIO_TIMEOUT`)
+    #[arg(long, global = true, value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+    /// Seconds to wait for another apicurio process working on the same project
+    /// (overrides `APICURIO_LOCK_TIMEOUT`; 0 fails at once)
+    #[arg(long, global = true, value_name = "SECONDS")]
+    pub lock_timeout: Option<u64>,
     #[command(subcommand)]
     pub cmd: Option<commands::Commands>,
 }
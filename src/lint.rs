@@ -0,0 +1,513 @@
+//! Local schema linting
+//!
+//! Built-in checks run on files before they are published, so naming and
+//! structural mistakes are caught without a round trip to the registry:
+//!
+//! - **Protobuf** - `syntax` declaration, `package` presence and casing, file naming
+//! - **Avro** - PascalCase type names, lowercase dotted namespaces
+//! - **OpenAPI** - `openapi`/`swagger` and `info.version` fields
+//!
+//! Every rule has a default [`Severity`] that can be changed (or turned `off`)
+//! under `lint.rules` in `apicurioconfig.yaml`.
+
+use anyhow::{bail, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, path::Path};
+
+/// How a rule violation is reported
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The rule is not checked
+    Off,
+    /// Reported, but does not fail `lint` or `publish --lint`
+    Warning,
+    /// Reported and fails `lint` or `publish --lint`
+    Error,
+}
+
+/// A built-in lint rule
+#[derive(Debug, Clone, Copy)]
+pub struct Rule {
+    pub name: &'static str,
+    pub default_severity: Severity,
+    pub description: &'static str,
+}
+
+/// Every built-in rule
+pub const RULES: &[Rule] = &[
+    Rule {
+        name: "invalid-content",
+        default_severity: Severity::Error,
+        description: "the file cannot be parsed as its schema type",
+    },
+    Rule {
+        name: "proto-syntax",
+        default_severity: Severity::Error,
+        description: "Protobuf files declare `syntax` (or `edition`)",
+    },
+    Rule {
+        name: "proto-package",
+        default_severity: Severity::Warning,
+        description: "Protobuf files declare a `package`",
+    },
+    Rule {
+        name: "proto-package-case",
+        default_severity: Severity::Warning,
+        description: "Protobuf packages are lowercase, dot-separated identifiers",
+    },
+    Rule {
+        name: "proto-file-name",
+        default_severity: Severity::Warning,
+        description: "Protobuf file names are lower_snake_case",
+    },
+    Rule {
+        name: "avro-name",
+        default_severity: Severity::Warning,
+        description: "Avro record, enum and fixed names are PascalCase",
+    },
+    Rule {
+        name: "avro-namespace",
+        default_severity: Severity::Warning,
+        description: "top-level Avro types have a lowercase, dot-separated namespace",
+    },
+    Rule {
+        name: "openapi-version",
+        default_severity: Severity::Error,
+        description: "OpenAPI documents declare `openapi` (or `swagger`)",
+    },
+    Rule {
+        name: "openapi-info-version",
+        default_severity: Severity::Warning,
+        description: "OpenAPI documents declare `info.version`",
+    },
+];
+
+/// Check that every configured rule exists
+///
+/// Rules of plugins (`<plugin>/<rule>`) are only known once the plugin runs
+/// and are accepted as is.
+pub fn validate_rule_names(rules: &BTreeMap<String, Severity>) -> Result<()> {
+    for name in rules.keys() {
+        if !name.contains('/') && !RULES.iter().any(|r| r.name == name) {
+            bail!(
+                "unknown lint rule '{}'. Available rules: {}",
+                name,
+                RULES.iter().map(|r| r.name).collect::<Vec<_>>().join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Schema types with built-in checks
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaKind {
+    Protobuf,
+    Avro,
+    Openapi,
+}
+
+impl SchemaKind {
+    /// Kind for a registry artifact type (`PROTOBUF`, `AVRO`, `OPENAPI`)
+    pub fn from_artifact_type(artifact_type: &str) -> Option<SchemaKind> {
+        match artifact_type.to_uppercase().as_str() {
+            "PROTOBUF" => Some(SchemaKind::Protobuf),
+            "AVRO" => Some(SchemaKind::Avro),
+            "OPENAPI" => Some(SchemaKind::Openapi),
+            _ => None,
+        }
+    }
+
+    /// Guess the kind of a file from its extension and, for JSON/YAML, its content
+    pub fn detect(path: &str, content: &str) -> Option<SchemaKind> {
+        let ext = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        match ext.as_str() {
+            "proto" => Some(SchemaKind::Protobuf),
+            "avsc" => Some(SchemaKind::Avro),
+            "json" | "yaml" | "yml" => {
+                let doc: serde_yaml::Value = serde_yaml::from_str(content).ok()?;
+                if ["openapi", "swagger", "paths"]
+                    .iter()
+                    .any(|key| doc.get(key).is_some())
+                {
+                    Some(SchemaKind::Openapi)
+                } else if matches!(
+                    doc.get("type").and_then(|t| t.as_str()),
+                    Some("record" | "enum" | "fixed")
+                ) {
+                    Some(SchemaKind::Avro)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// One rule violation
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// Rule name; `<plugin>/<rule>` for rules reported by plugins
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    /// 1-based line number, when the check can tell
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+/// Findings for one file
+#[derive(Serialize, Debug, Clone)]
+pub struct FileReport {
+    pub path: String,
+    /// `None` if the file type has no built-in checks
+    pub kind: Option<SchemaKind>,
+    pub findings: Vec<Finding>,
+}
+
+/// Result of linting a set of files
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct LintReport {
+    pub files: Vec<FileReport>,
+}
+
+impl LintReport {
+    fn count(&self, severity: Severity) -> usize {
+        self.files
+            .iter()
+            .flat_map(|f| &f.findings)
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    /// Whether any finding has [`Severity::Error`]
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+}
+
+/// Runs the built-in rules with configured severities
+#[derive(Debug, Clone, Default)]
+pub struct Linter {
+    severities: BTreeMap<String, Severity>,
+}
+
+impl Linter {
+    /// Linter using `rules` to override default severities
+    pub fn new(rules: &BTreeMap<String, Severity>) -> Result<Self> {
+        validate_rule_names(rules)?;
+        Ok(Linter {
+            severities: rules.clone(),
+        })
+    }
+
+    fn severity(&self, rule: &str) -> Severity {
+        self.severities.get(rule).copied().unwrap_or_else(|| {
+            RULES
+                .iter()
+                .find(|r| r.name == rule)
+                .map_or(Severity::Error, |r| r.default_severity)
+        })
+    }
+
+    /// Finding for a rule reported by a plugin, keeping the severity the plugin
+    /// asked for unless `lint.rules` configures another one
+    pub fn plugin_finding(
+        &self,
+        plugin: &str,
+        rule: &str,
+        severity: Severity,
+        message: String,
+        line: Option<usize>,
+    ) -> Option<Finding> {
+        let rule = format!("{plugin}/{rule}");
+        let severity = self.severities.get(&rule).copied().unwrap_or(severity);
+        (severity != Severity::Off).then_some(Finding {
+            rule,
+            severity,
+            message,
+            line,
+        })
+    }
+
+    /// Lint the content of one file; `kind` is detected when not given
+    pub fn lint(&self, path: &str, content: &str, kind: Option<SchemaKind>) -> FileReport {
+        let kind = kind.or_else(|| SchemaKind::detect(path, content));
+        let raw = match kind {
+            Some(SchemaKind::Protobuf) => lint_protobuf(path, content),
+            Some(SchemaKind::Avro) => lint_avro(content),
+            Some(SchemaKind::Openapi) => lint_openapi(content),
+            None => Vec::new(),
+        };
+        let findings = raw
+            .into_iter()
+            .filter_map(|(rule, message, line)| {
+                let severity = self.severity(rule);
+                (severity != Severity::Off).then_some(Finding {
+                    rule: rule.to_string(),
+                    severity,
+                    message,
+                    line,
+                })
+            })
+            .collect();
+        FileReport {
+            path: path.to_string(),
+            kind,
+            findings,
+        }
+    }
+}
+
+type RawFinding = (&'static str, String, Option<usize>);
+
+fn is_dotted_lowercase(name: &str) -> bool {
+    Regex::new(r"^[a-z][a-z0-9_]*(\.[a-z][a-z0-9_]*)*$")
+        .unwrap()
+        .is_match(name)
+}
+
+fn is_pascal_case(name: &str) -> bool {
+    Regex::new(r"^[A-Z][A-Za-z0-9]*$").unwrap().is_match(name)
+}
+
+fn lint_protobuf(path: &str, content: &str) -> Vec<RawFinding> {
+    let mut findings = Vec::new();
+    let statement = Regex::new(r"^\s*(syntax|edition|package)\s*(=\s*)?([^;]*);").unwrap();
+    let mut has_syntax = false;
+    let mut package = None;
+    for (i, line) in content.lines().enumerate() {
+        let code = line.split("//").next().unwrap_or("");
+        if let Some(caps) = statement.captures(code) {
+            match &caps[1] {
+                "package" => package = Some((caps[3].trim().to_string(), i + 1)),
+                _ => has_syntax = true,
+            }
+        }
+    }
+
+    if !has_syntax {
+        findings.push((
+            "proto-syntax",
+            "missing `syntax = \"proto3\";` (or `edition`) declaration".to_string(),
+            Some(1),
+        ));
+    }
+    match package {
+        None => findings.push((
+            "proto-package",
+            "missing `package` declaration".to_string(),
+            None,
+        )),
+        Some((name, line)) if !is_dotted_lowercase(&name) => findings.push((
+            "proto-package-case",
+            format!("package '{name}' should be lowercase and dot-separated"),
+            Some(line),
+        )),
+        Some(_) => {}
+    }
+
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("");
+    if !Regex::new(r"^[a-z][a-z0-9_]*$").unwrap().is_match(stem) {
+        findings.push((
+            "proto-file-name",
+            format!("file name '{stem}.proto' should be lower_snake_case"),
+            None,
+        ));
+    }
+    findings
+}
+
+fn lint_avro(content: &str) -> Vec<RawFinding> {
+    let schema: Value = match serde_json::from_str(content) {
+        Ok(schema) => schema,
+        Err(e) => return vec![("invalid-content", format!("invalid Avro JSON: {e}"), None)],
+    };
+    let mut findings = Vec::new();
+
+    if let Some(name) = schema.get("name").and_then(Value::as_str) {
+        let namespace = schema
+            .get("namespace")
+            .and_then(Value::as_str)
+            .or_else(|| name.rsplit_once('.').map(|(ns, _)| ns));
+        match namespace {
+            None | Some("") => findings.push((
+                "avro-namespace",
+                format!("top-level type '{name}' has no namespace"),
+                None,
+            )),
+            Some(ns) if !is_dotted_lowercase(ns) => findings.push((
+                "avro-namespace",
+                format!("namespace '{ns}' should be lowercase and dot-separated"),
+                None,
+            )),
+            Some(_) => {}
+        }
+    }
+    check_avro_names(&schema, &mut findings);
+    findings
+}
+
+/// Check the names of every named type declared in a schema
+fn check_avro_names(schema: &Value, findings: &mut Vec<RawFinding>) {
+    match schema {
+        Value::Array(union) => union.iter().for_each(|s| check_avro_names(s, findings)),
+        Value::Object(map) => {
+            let is_named = matches!(
+                map.get("type").and_then(Value::as_str),
+                Some("record" | "error" | "enum" | "fixed")
+            );
+            if let (true, Some(name)) = (is_named, map.get("name").and_then(Value::as_str)) {
+                let simple = name.rsplit('.').next().unwrap_or(name);
+                if !is_pascal_case(simple) {
+                    findings.push((
+                        "avro-name",
+                        format!("type name '{simple}' should be PascalCase"),
+                        None,
+                    ));
+                }
+            }
+            if let Some(fields) = map.get("fields").and_then(Value::as_array) {
+                for field in fields {
+                    if let Some(ty) = field.get("type") {
+                        check_avro_names(ty, findings);
+                    }
+                }
+            }
+            for key in ["type", "items", "values"] {
+                if let Some(nested @ (Value::Object(_) | Value::Array(_))) = map.get(key) {
+                    check_avro_names(nested, findings);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn lint_openapi(content: &str) -> Vec<RawFinding> {
+    let doc: serde_yaml::Value = match serde_yaml::from_str(content) {
+        Ok(doc) => doc,
+        Err(e) => {
+            return vec![(
+                "invalid-content",
+                format!("invalid OpenAPI document: {e}"),
+                None,
+            )]
+        }
+    };
+    let mut findings = Vec::new();
+    if doc.get("openapi").is_none() && doc.get("swagger").is_none() {
+        findings.push((
+            "openapi-version",
+            "missing `openapi` version field (e.g. `openapi: 3.0.3`)".to_string(),
+            None,
+        ));
+    }
+    if doc
+        .get("info")
+        .and_then(|info| info.get("version"))
+        .is_none()
+    {
+        findings.push((
+            "openapi-info-version",
+            "missing `info.version` field".to_string(),
+            None,
+        ));
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(findings: &FileReport) -> Vec<&str> {
+        findings.findings.iter().map(|f| f.rule.as_str()).collect()
+    }
+
+    #[test]
+    fn test_protobuf_rules() {
+        let linter = Linter::default();
+        let good = "syntax = \"proto3\";\n\npackage com.example.users.v1;\n";
+        assert!(rules(&linter.lint("protos/user_service.proto", good, None)).is_empty());
+
+        let bad = "// package ignored.in.comment;\npackage Com.Example;\nmessage A {}\n";
+        let report = linter.lint("protos/UserService.proto", bad, None);
+        assert_eq!(
+            rules(&report),
+            ["proto-syntax", "proto-package-case", "proto-file-name"]
+        );
+        assert_eq!(report.findings[1].line, Some(2));
+        assert_eq!(report.findings[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_avro_rules() {
+        let linter = Linter::default();
+        let schema = r#"{
+            "type": "record", "name": "user_created", "namespace": "Com.Example",
+            "fields": [
+                {"name": "address", "type": {"type": "record", "name": "address", "fields": []}},
+                {"name": "kind", "type": ["null", {"type": "enum", "name": "Kind", "symbols": ["A"]}]}
+            ]
+        }"#;
+        let report = linter.lint("user.avsc", schema, None);
+        assert_eq!(rules(&report), ["avro-namespace", "avro-name", "avro-name"]);
+
+        let report = linter.lint("user.avsc", "{not json", None);
+        assert_eq!(rules(&report), ["invalid-content"]);
+    }
+
+    #[test]
+    fn test_openapi_rules_and_detection() {
+        let linter = Linter::default();
+        let report = linter.lint("api.yaml", "paths: {}\ninfo:\n  title: x\n", None);
+        assert_eq!(report.kind, Some(SchemaKind::Openapi));
+        assert_eq!(rules(&report), ["openapi-version", "openapi-info-version"]);
+
+        // Plain YAML that is no OpenAPI document is not checked
+        let report = linter.lint("values.yaml", "replicas: 2\n", None);
+        assert_eq!(report.kind, None);
+        assert!(report.findings.is_empty());
+    }
+
+    #[test]
+    fn test_configured_severities() {
+        let mut config = BTreeMap::new();
+        config.insert("proto-package".to_string(), Severity::Error);
+        config.insert("proto-file-name".to_string(), Severity::Off);
+        let linter = Linter::new(&config).unwrap();
+        let report = linter.lint("Bad.proto", "syntax = \"proto3\";\n", None);
+        assert_eq!(rules(&report), ["proto-package"]);
+        assert_eq!(report.findings[0].severity, Severity::Error);
+
+        let lint_report = LintReport {
+            files: vec![report],
+        };
+        assert!(lint_report.has_errors());
+
+        config.insert("no-such-rule".to_string(), Severity::Warning);
+        let err = Linter::new(&config).unwrap_err();
+        assert!(err.to_string().contains("unknown lint rule 'no-such-rule'"));
+    }
+}
@@ -0,0 +1,302 @@
+//! References between pulled files, pointed at their local copies
+//!
+//! Documents refer to other registry artifacts by the name of the registry
+//! reference (`$ref: common.yaml#/components/schemas/Money`, or
+//! `<xs:import schemaLocation="common.xsd"/>`). With `openapi.refs: local` (JSON
+//! and YAML) or `xsd.refs: local` (XSD and WSDL), `pull` rewrites those names to
+//! the path of the pulled file, relative to the referencing one, so tooling
+//! resolves them offline. Only the reference itself changes; the rest of the
+//! file is kept byte for byte.
+//!
+//! The lock keeps the hash of the registry content and records the reference
+//! names (`referenceNames`), so [`restore`] can undo the rewriting before a
+//! pulled file is compared with its lock entry. The names are recorded as well
+//! when `openapi.bundle` is set, for [`crate::openapi`] to find referenced files.
+
+use std::path::{Component, Path};
+
+use crate::{
+    config::{RefTargets, RepoConfig},
+    lockfile::{find_reference, LockedDependency},
+};
+
+/// How a file type spells its references
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefSyntax {
+    /// `$ref` values of JSON and YAML documents (OpenAPI, AsyncAPI, JSON Schema)
+    Json,
+    /// `schemaLocation` and `location` attributes of XSD imports and includes
+    /// and WSDL imports
+    Xml,
+}
+
+/// The reference syntax of a pulled file, from its extension
+pub fn syntax_for(path: &str) -> Option<RefSyntax> {
+    let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "json" | "yaml" | "yml" => Some(RefSyntax::Json),
+        "xsd" | "wsdl" => Some(RefSyntax::Xml),
+        _ => None,
+    }
+}
+
+/// Reference syntaxes `pull` rewrites in this project
+pub fn rewritten_syntaxes(repo_cfg: &RepoConfig) -> Vec<RefSyntax> {
+    [
+        (RefSyntax::Json, repo_cfg.openapi.refs),
+        (RefSyntax::Xml, repo_cfg.xsd.refs),
+    ]
+    .into_iter()
+    .filter(|(_, targets)| *targets == RefTargets::Local)
+    .map(|(syntax, _)| syntax)
+    .collect()
+}
+
+/// Whether the lock records the reference names of the file at `path`
+pub fn records_names(repo_cfg: &RepoConfig, path: &str) -> bool {
+    match syntax_for(path) {
+        Some(syntax) => {
+            rewritten_syntaxes(repo_cfg).contains(&syntax)
+                || (syntax == RefSyntax::Json && repo_cfg.openapi.bundle)
+        }
+        None => false,
+    }
+}
+
+/// The content of `dependency` as `pull` writes it, its references pointed at
+/// the pulled files among `dependencies`; unchanged unless the lock recorded
+/// reference names for it. Only applied to [`rewritten_syntaxes`].
+pub fn to_local(
+    dependencies: &[LockedDependency],
+    dependency: &LockedDependency,
+    content: &[u8],
+) -> Vec<u8> {
+    match syntax_for(&dependency.output_path) {
+        Some(syntax) if !dependency.reference_names.is_empty() => {
+            rewrite(syntax, content, &local_targets(dependencies, dependency))
+        }
+        _ => content.to_vec(),
+    }
+}
+
+/// Undo [`to_local`], giving back the registry content the lock hashed
+pub fn to_registry(
+    dependencies: &[LockedDependency],
+    dependency: &LockedDependency,
+    content: &[u8],
+) -> Vec<u8> {
+    match syntax_for(&dependency.output_path) {
+        Some(syntax) if !dependency.reference_names.is_empty() => {
+            restore(syntax, content, &local_targets(dependencies, dependency))
+        }
+        _ => content.to_vec(),
+    }
+}
+
+/// Reference names of `dependency` with the local path each is rewritten to
+pub fn local_targets(
+    dependencies: &[LockedDependency],
+    dependency: &LockedDependency,
+) -> Vec<(String, String)> {
+    dependency
+        .reference_names
+        .iter()
+        .filter_map(|(reference, name)| {
+            let target = find_reference(dependencies, dependency, reference)?;
+            Some((
+                name.clone(),
+                relative_path(&dependency.output_path, &target.output_path),
+            ))
+        })
+        .collect()
+}
+
+/// `content` with every reference named in `targets` pointed at its local path
+pub fn rewrite(syntax: RefSyntax, content: &[u8], targets: &[(String, String)]) -> Vec<u8> {
+    replace_refs(syntax, content, |name| {
+        targets
+            .iter()
+            .find(|(from, _)| from == name)
+            .map(|(_, to)| to.as_str())
+    })
+}
+
+/// Undo [`rewrite`]: `content` with the local paths of `targets` replaced by
+/// the reference names again
+pub fn restore(syntax: RefSyntax, content: &[u8], targets: &[(String, String)]) -> Vec<u8> {
+    replace_refs(syntax, content, |path| {
+        targets
+            .iter()
+            .find(|(_, to)| to == path)
+            .map(|(from, _)| from.as_str())
+    })
+}
+
+/// Replace the document part (before `#`) of every reference value for which
+/// `replacement` has an answer
+fn replace_refs<'a>(
+    syntax: RefSyntax,
+    content: &[u8],
+    replacement: impl Fn(&str) -> Option<&'a str>,
+) -> Vec<u8> {
+    let Ok(text) = std::str::from_utf8(content) else {
+        return content.to_vec();
+    };
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some((start, end)) = next_ref(syntax, rest) {
+        let value = &rest[start..end];
+        let document = value.split('#').next().unwrap_or_default();
+        out.push_str(&rest[..start]);
+        match replacement(document).filter(|_| !document.is_empty()) {
+            Some(replaced) => {
+                out.push_str(replaced);
+                out.push_str(&value[document.len()..]);
+            }
+            None => out.push_str(value),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out.into_bytes()
+}
+
+/// Byte range of the next reference value in `text`
+fn next_ref(syntax: RefSyntax, text: &str) -> Option<(usize, usize)> {
+    match syntax {
+        RefSyntax::Json => {
+            let mut from = 0;
+            loop {
+                let key = from + text[from..].find("$ref")?;
+                from = key + "$ref".len();
+                // `"$ref": "x"`, `$ref: x` or `$ref: 'x'`
+                let after = text[from..].trim_start_matches(['"', '\'']).trim_start();
+                let Some(after) = after.strip_prefix(':') else {
+                    continue;
+                };
+                let after = after.trim_start_matches([' ', '\t']);
+                let value_start = text.len() - after.len();
+                return Some(match after.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let start = value_start + 1;
+                        let len = text[start..].find(quote)?;
+                        (start, start + len)
+                    }
+                    _ => {
+                        let len = after
+                            .find(|c: char| c.is_whitespace() || c == ',' || c == '}')
+                            .unwrap_or(after.len());
+                        (value_start, value_start + len)
+                    }
+                });
+            }
+        }
+        RefSyntax::Xml => {
+            let mut from = 0;
+            loop {
+                let attribute = from + text[from..].find("ocation")?;
+                from = attribute + "ocation".len();
+                // `schemaLocation="x"`, or `location='x'` after whitespace
+                let name_ok = text[..attribute].ends_with("schemaL")
+                    || (text[..attribute].ends_with('l')
+                        && text[..attribute - 1].ends_with(char::is_whitespace));
+                if !name_ok {
+                    continue;
+                }
+                let after = text[from..].trim_start();
+                let Some(after) = after.strip_prefix('=') else {
+                    continue;
+                };
+                let after = after.trim_start();
+                let Some(quote @ ('"' | '\'')) = after.chars().next() else {
+                    continue;
+                };
+                let start = text.len() - after.len() + 1;
+                let len = text[start..].find(quote)?;
+                return Some((start, start + len));
+            }
+        }
+    }
+}
+
+/// Path of `to` relative to the directory of `from`, both relative to the
+/// project directory, with `/` separators
+pub fn relative_path(from: &str, to: &str) -> String {
+    let components = |path: &str| -> Vec<String> {
+        Path::new(path)
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect()
+    };
+    let from = components(from);
+    let to = components(to);
+    let from_dir = &from[..from.len().saturating_sub(1)];
+    let common = from_dir.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = vec!["..".to_string(); from_dir.len() - common];
+    parts.extend(to[common..].iter().cloned());
+    let path = parts.join("/");
+    if path.starts_with("..") {
+        path
+    } else {
+        format!("./{path}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_and_restore_refs() {
+        assert_eq!(
+            relative_path("openapi/orders/api.yaml", "openapi/common/money.yaml"),
+            "../common/money.yaml"
+        );
+        assert_eq!(
+            relative_path("openapi/api.yaml", "openapi/money.yaml"),
+            "./money.yaml"
+        );
+
+        let targets = vec![("money.yaml".to_string(), "../common/money.yaml".to_string())];
+        let yaml = "price:\n  $ref: money.yaml#/components/schemas/Money\n\
+                    local:\n  $ref: '#/components/schemas/Order'\n\
+                    other:\n  $ref: \"other.yaml\"\n";
+        let rewritten = rewrite(RefSyntax::Json, yaml.as_bytes(), &targets);
+        assert_eq!(
+            String::from_utf8(rewritten.clone()).unwrap(),
+            "price:\n  $ref: ../common/money.yaml#/components/schemas/Money\n\
+             local:\n  $ref: '#/components/schemas/Order'\n\
+             other:\n  $ref: \"other.yaml\"\n"
+        );
+        assert_eq!(
+            restore(RefSyntax::Json, &rewritten, &targets),
+            yaml.as_bytes()
+        );
+
+        let json = r##"{"price": {"$ref": "money.yaml#/Money"}, "$ref":"money.yaml"}"##;
+        assert_eq!(
+            String::from_utf8(rewrite(RefSyntax::Json, json.as_bytes(), &targets)).unwrap(),
+            r##"{"price": {"$ref": "../common/money.yaml#/Money"}, "$ref":"../common/money.yaml"}"##
+        );
+
+        let targets = vec![("money.xsd".to_string(), "../common/money.xsd".to_string())];
+        let xsd = "<xs:import namespace=\"urn:money\" schemaLocation=\"money.xsd\"/>\n\
+                   <wsdl:import location='money.xsd'/>\n\
+                   <soap:address location=\"http://example.com/orders\"/>\n";
+        let rewritten = rewrite(RefSyntax::Xml, xsd.as_bytes(), &targets);
+        assert_eq!(
+            String::from_utf8(rewritten.clone()).unwrap(),
+            "<xs:import namespace=\"urn:money\" schemaLocation=\"../common/money.xsd\"/>\n\
+             <wsdl:import location='../common/money.xsd'/>\n\
+             <soap:address location=\"http://example.com/orders\"/>\n"
+        );
+        assert_eq!(
+            restore(RefSyntax::Xml, &rewritten, &targets),
+            xsd.as_bytes()
+        );
+        assert_eq!(syntax_for("wsdl/orders.wsdl"), Some(RefSyntax::Xml));
+    }
+}
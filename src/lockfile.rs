@@ -13,6 +13,11 @@
 //! - Metadata about when the lock was generated
 //! - Hash of the configuration that generated the lock
 //!
+//! The file starts with a generated-by comment. Entries are written in a fixed
+//! order (direct dependencies first, then by name, registry, group, artifact and
+//! version; references sorted), and a lock whose content did not change is not
+//! rewritten, so regenerating it produces no diff.
+//!
 //! ## Integrity Verification
 //!
 //! Lock files include multiple layers of integrity verification:
@@ -21,58 +26,64 @@
 //! - SHA256 checksums of downloaded content
 //! - Lockfile format version for compatibility
 
-use crate::output_path::{expand_pattern, extension_for_type, generate_output_path};
+use crate::avro::AvroFingerprint;
+use crate::error::{ApicurioError, Result};
+use crate::output_path::{
+    expand_pattern, extension_for_type, generate_output_path, PatternMetadata,
+};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::{fs, path::Path};
+use std::{collections::BTreeMap, fs, path::Path};
 
 /// Check output overrides and mappings to determine the final output path
 /// Returns None if the artifact should be skipped (mapped to null)
 pub fn resolve_output_path(
     base_pattern: &str,
     output_overrides: &std::collections::HashMap<String, Option<String>>,
-    registry: &str,
     group_id: &str,
     artifact_id: &str,
     version: &str,
-    artifact_type: &str,
-) -> Option<String> {
+    metadata: &PatternMetadata,
+) -> Result<Option<String>> {
     // Check for exact matches in order of specificity:
     // 1. registry:groupId/artifactId
     // 2. groupId/artifactId
 
-    let registry_key = format!("{registry}:{group_id}/{artifact_id}");
+    let registry_key = format!("{}:{group_id}/{artifact_id}", metadata.registry);
     let group_key = format!("{group_id}/{artifact_id}");
 
     if let Some(override_pattern) = output_overrides.get(&registry_key) {
-        override_pattern.as_ref().map(|pattern| {
-            expand_pattern(
-                pattern,
-                group_id,
-                artifact_id,
-                version,
-                extension_for_type(artifact_type),
-            )
-        })
+        override_pattern
+            .as_ref()
+            .map(|pattern| {
+                expand_pattern(
+                    pattern,
+                    group_id,
+                    artifact_id,
+                    version,
+                    extension_for_type(&metadata.artifact_type),
+                    metadata,
+                )
+            })
+            .transpose()
     } else if let Some(override_pattern) = output_overrides.get(&group_key) {
-        override_pattern.as_ref().map(|pattern| {
-            expand_pattern(
-                pattern,
-                group_id,
-                artifact_id,
-                version,
-                extension_for_type(artifact_type),
-            )
-        })
+        override_pattern
+            .as_ref()
+            .map(|pattern| {
+                expand_pattern(
+                    pattern,
+                    group_id,
+                    artifact_id,
+                    version,
+                    extension_for_type(&metadata.artifact_type),
+                    metadata,
+                )
+            })
+            .transpose()
     } else {
-        Some(generate_output_path(
-            base_pattern,
-            group_id,
-            artifact_id,
-            version,
-            artifact_type,
-        ))
+        generate_output_path(base_pattern, group_id, artifact_id, version, metadata).map(Some)
     }
+    .map_err(|e| ApicurioError::config(format!("{e:#}")))
 }
 
 /// A locked dependency with exact version and integrity information
@@ -90,6 +101,10 @@ pub struct LockedDependency {
     pub resolved_version: String,
     /// Full URL used to download the artifact
     pub download_url: String,
+    /// Base URL of the registry when the dependency was locked; `download_url`
+    /// starts with it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry_url: Option<String>,
     /// SHA256 checksum of the downloaded content
     pub sha256: String,
     /// Local path where the artifact is stored
@@ -103,13 +118,213 @@ pub struct LockedDependency {
     /// Whether this dependency was resolved transitively from references
     #[serde(default)]
     pub is_transitive: bool,
+    /// Artifacts referenced by this dependency (`groupId/artifactId`, same registry)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    /// Name each reference goes by in the content (`groupId/artifactId` → name),
+    /// recorded when `pull` rewrites references to local paths
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub reference_names: std::collections::BTreeMap<String, String>,
+    /// Binary artifact: the registry content is base64-decoded before hashing
+    /// and writing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub binary: bool,
+    /// Fingerprints of the canonical form, for Avro schemas
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub avro_fingerprint: Option<AvroFingerprint>,
+    /// `Content-Type` the registry answered with; sent as `Accept` when pulling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Name of the `group` dependency this artifact was locked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub member_of: Option<String>,
+}
+
+impl LockedDependency {
+    /// Base URL of the registry the dependency was locked from: `registryUrl`,
+    /// or for older lock files the part of `downloadUrl` before `/apis/registry/`
+    pub fn locked_registry_url(&self) -> Option<&str> {
+        self.registry_url.as_deref().or_else(|| {
+            self.download_url
+                .find("/apis/registry/")
+                .map(|i| &self.download_url[..i])
+        })
+    }
+
+    /// Key identifying the locked artifact (`registry:groupId:artifactId`)
+    pub fn artifact_key(&self) -> String {
+        format!("{}:{}:{}", self.registry, self.group_id, self.artifact_id)
+    }
+
+    /// Key identifying the lock entry: the name of a direct dependency (several may
+    /// lock the same artifact), the artifact key of a transitive one
+    pub fn entry_key(&self) -> String {
+        if self.is_transitive {
+            self.artifact_key()
+        } else {
+            format!("name:{}", self.name)
+        }
+    }
+
+    /// Whether this entry was locked for the configured dependency `name`, itself
+    /// or as a member of the `group` dependency `name`
+    pub fn locked_for(&self, name: &str) -> bool {
+        self.name == name || self.member_of.as_deref() == Some(name)
+    }
+
+    /// Registry content as written to `output_path`: base64-decoded for binary
+    /// artifacts, unchanged otherwise
+    pub fn file_content(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if self.binary {
+            crate::registry::decode_binary_content(data)
+                .map_err(|e| ApicurioError::integrity(format!("{}: {e}", self.name)))
+        } else {
+            Ok(data.to_vec())
+        }
+    }
+}
+
+/// Find the locked entry for one of `parent`'s references (`groupId/artifactId`)
+///
+/// An entry in the parent's registry is preferred; references found through
+/// `registryFallbacks` are locked under another registry.
+pub fn find_reference<'a>(
+    dependencies: &'a [LockedDependency],
+    parent: &LockedDependency,
+    reference: &str,
+) -> Option<&'a LockedDependency> {
+    let matches = |d: &&LockedDependency| format!("{}/{}", d.group_id, d.artifact_id) == reference;
+    dependencies
+        .iter()
+        .filter(matches)
+        .find(|d| d.registry == parent.registry)
+        .or_else(|| dependencies.iter().find(matches))
+}
+
+/// Sort locked dependencies for stable output (direct deps first, then alphabetical)
+pub fn sort_locked_dependencies(dependencies: &mut [LockedDependency]) {
+    dependencies.sort_by(|a, b| {
+        a.is_transitive
+            .cmp(&b.is_transitive)
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.registry.cmp(&b.registry))
+            .then_with(|| a.group_id.cmp(&b.group_id))
+            .then_with(|| a.artifact_id.cmp(&b.artifact_id))
+            .then_with(|| a.resolved_version.cmp(&b.resolved_version))
+    });
+}
+
+/// Two outputs that would overwrite each other
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputConflict {
+    /// Owner of the first output (dependency name or description)
+    pub first: String,
+    pub first_path: String,
+    /// Owner of the second output
+    pub second: String,
+    pub second_path: String,
+}
+
+impl std::fmt::Display for OutputConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if normalized_output_path(&self.first_path) == normalized_output_path(&self.second_path) {
+            write!(
+                f,
+                "'{}' and '{}' both write to {}",
+                self.first, self.second, self.first_path
+            )
+        } else {
+            write!(
+                f,
+                "'{}' writes {} inside {}, which is the output file of '{}'",
+                self.second, self.second_path, self.first_path, self.first
+            )
+        }
+    }
+}
+
+/// Normalize an output path for comparison (drops `.` components and separators)
+fn normalized_output_path(path: &str) -> std::path::PathBuf {
+    Path::new(path)
+        .components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect()
+}
+
+/// Find outputs that collide: the same file claimed by different owners, or one
+/// owner writing beneath another owner's output file
+///
+/// `outputs` yields `(owner, path)` pairs; repeated pairs from the same owner are fine.
+pub fn find_output_conflicts<'a>(
+    outputs: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<OutputConflict> {
+    let mut claimed: Vec<(&str, &str, std::path::PathBuf)> = Vec::new();
+    let mut conflicts = Vec::new();
+    for (owner, path) in outputs {
+        let normalized = normalized_output_path(path);
+        if claimed
+            .iter()
+            .any(|(o, _, n)| *o == owner && *n == normalized)
+        {
+            continue;
+        }
+        for (other_owner, other_path, other_normalized) in &claimed {
+            if *other_owner == owner {
+                continue;
+            }
+            let conflict =
+                if normalized == *other_normalized || normalized.starts_with(other_normalized) {
+                    Some((*other_owner, *other_path, owner, path))
+                } else if other_normalized.starts_with(&normalized) {
+                    Some((owner, path, *other_owner, *other_path))
+                } else {
+                    None
+                };
+            if let Some((first, first_path, second, second_path)) = conflict {
+                conflicts.push(OutputConflict {
+                    first: first.to_string(),
+                    first_path: first_path.to_string(),
+                    second: second.to_string(),
+                    second_path: second_path.to_string(),
+                });
+            }
+        }
+        claimed.push((owner, path, normalized));
+    }
+    conflicts
+}
+
+/// Fail if any two locked entries would write to the same output
+pub fn check_output_conflicts(dependencies: &[LockedDependency]) -> Result<()> {
+    let conflicts = find_output_conflicts(
+        dependencies
+            .iter()
+            .map(|d| (d.name.as_str(), d.output_path.as_str())),
+    );
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<String> = conflicts.iter().map(|c| format!("  - {c}")).collect();
+    Err(ApicurioError::config(format!(
+        "conflicting output paths; set a distinct outputPath or output override:\n{}",
+        details.join("\n")
+    )))
+}
+
+/// Comment written at the top of the lock file
+pub fn lock_header() -> String {
+    format!(
+        "# Generated by apicurio-cli {}. Do not edit by hand;\n\
+         # run `apicurio lock` to regenerate it.\n",
+        env!("CARGO_PKG_VERSION")
+    )
 }
 
 /// Lock file containing all resolved dependencies and metadata
 ///
 /// The lock file ensures reproducible builds by recording exact versions
 /// and integrity information for all dependencies.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct LockFile {
     /// List of all locked dependencies
@@ -122,6 +337,38 @@ pub struct LockFile {
     pub generated_at: String,
     /// Configuration file modification time (nanoseconds since epoch)
     pub config_modified: Option<String>,
+    /// Checksum of each input of `config_hash`, to tell which one changed
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub config_inputs: BTreeMap<String, String>,
+}
+
+/// How a config hash input differs from the one recorded in the lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigInputChange {
+    Added(String),
+    Removed(String),
+    Changed(String),
+}
+
+impl ConfigInputChange {
+    /// The input that differs, e.g. `dependency users`
+    pub fn input(&self) -> &str {
+        match self {
+            ConfigInputChange::Added(input)
+            | ConfigInputChange::Removed(input)
+            | ConfigInputChange::Changed(input) => input,
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigInputChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigInputChange::Added(input) => write!(f, "{input} added"),
+            ConfigInputChange::Removed(input) => write!(f, "{input} removed"),
+            ConfigInputChange::Changed(input) => write!(f, "{input} changed"),
+        }
+    }
 }
 
 impl LockFile {
@@ -134,24 +381,83 @@ impl LockFile {
     /// Parsed lock file structure
     ///
     /// # Errors
-    /// Returns error if file cannot be read or parsed as valid YAML
-    pub fn load(path: &Path) -> anyhow::Result<Self> {
-        let data = fs::read_to_string(path)?;
-        let lf: LockFile = serde_yaml::from_str(&data)?;
-        Ok(lf)
+    /// Returns error if file cannot be read or parsed as valid YAML (JSON for
+    /// a `.json` path)
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path).map_err(|e| ApicurioError::io("reading", path, e))?;
+        crate::config::parse_config_text(path, &data).map_err(|e| {
+            let changes = crate::migrate::lock_changes(&data);
+            if changes.is_empty() {
+                ApicurioError::config_caused(format!("invalid lock file {}", path.display()), e)
+            } else {
+                crate::migrate::outdated(path, &changes)
+            }
+        })
     }
 
-    /// Save the lock file to disk
+    /// Save the lock file to disk, in stable order and with the generated-by header
+    ///
+    /// A lock file at `path` with the same content (ignoring `generatedAt`) is
+    /// left untouched, keeping its modification time.
     ///
     /// # Arguments
     /// * `path` - Path where to save the lock file
     ///
+    /// # Returns
+    /// Whether the file was written
+    ///
     /// # Errors
     /// Returns error if file cannot be written or serialized
-    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
-        let data = serde_yaml::to_string(self)?;
-        fs::write(path, data)?;
-        Ok(())
+    pub fn save(&self, path: &Path) -> Result<bool> {
+        if let Ok(existing) = LockFile::load(path) {
+            if existing.same_content(self) {
+                return Ok(false);
+            }
+        }
+        self.force_save(path)?;
+        Ok(true)
+    }
+
+    /// Save the lock file even if its content did not change (refreshing `generatedAt`)
+    ///
+    /// A `.json` path is written as JSON, which has no room for the header.
+    pub fn force_save(&self, path: &Path) -> Result<()> {
+        let data = if crate::config::is_json_file(path) {
+            serde_json::to_string_pretty(&self.normalized())
+                .map(|json| json + "\n")
+                .map_err(|e| ApicurioError::config_caused("serializing the lock file", e))?
+        } else {
+            let yaml = serde_yaml::to_string(&self.normalized())
+                .map_err(|e| ApicurioError::config_caused("serializing the lock file", e))?;
+            lock_header() + &yaml
+        };
+        fs::write(path, data).map_err(|e| ApicurioError::io("writing", path, e))
+    }
+
+    /// Copy with entries in their stable order and references sorted
+    fn normalized(&self) -> LockFile {
+        let mut lock = self.clone();
+        sort_locked_dependencies(&mut lock.locked_dependencies);
+        for dependency in &mut lock.locked_dependencies {
+            dependency.references.sort();
+            dependency.references.dedup();
+        }
+        lock
+    }
+
+    /// Whether both locks resolved the same configuration to the same entries,
+    /// regardless of entry order, `configModified` and `generatedAt`
+    pub fn same_resolution(&self, other: &LockFile) -> bool {
+        let (a, b) = (self.normalized(), other.normalized());
+        a.locked_dependencies == b.locked_dependencies
+            && a.lockfile_version == b.lockfile_version
+            && a.config_hash == b.config_hash
+    }
+
+    /// Whether both locks record the same thing, regardless of entry order and
+    /// of when they were generated
+    pub fn same_content(&self, other: &LockFile) -> bool {
+        self.same_resolution(other) && self.config_modified == other.config_modified
     }
 
     /// Create a new lockfile with current timestamp and version
@@ -182,11 +488,60 @@ impl LockFile {
 
         Self {
             locked_dependencies,
-            lockfile_version: 1,
+            lockfile_version: crate::migrate::LOCKFILE_VERSION,
             config_hash,
             generated_at: now,
             config_modified,
+            config_inputs: BTreeMap::new(),
+        }
+    }
+
+    /// Registries whose entries were locked from another URL than the one
+    /// `current_url` gives them now: `(registry, locked URL, current URL)`
+    ///
+    /// Registries `current_url` does not know are left out.
+    pub fn moved_registries<'a>(
+        &self,
+        current_url: impl Fn(&str) -> Option<&'a str>,
+    ) -> Vec<(String, String, String)> {
+        let mut moved: Vec<(String, String, String)> = Vec::new();
+        for dep in &self.locked_dependencies {
+            let (Some(locked), Some(current)) =
+                (dep.locked_registry_url(), current_url(&dep.registry))
+            else {
+                continue;
+            };
+            if locked.trim_end_matches('/') == current.trim_end_matches('/')
+                || moved
+                    .iter()
+                    .any(|(registry, from, _)| *registry == dep.registry && from == locked)
+            {
+                continue;
+            }
+            moved.push((
+                dep.registry.clone(),
+                locked.to_string(),
+                current.to_string(),
+            ));
+        }
+        moved
+    }
+
+    /// Point the entries of `registry` locked from `from` at the registry URL
+    /// `to`, rewriting their `downloadUrl`; returns how many were rewritten
+    pub fn rebase_registry_url(&mut self, registry: &str, from: &str, to: &str) -> usize {
+        let mut rebased = 0;
+        for dep in &mut self.locked_dependencies {
+            if dep.registry != registry || dep.locked_registry_url() != Some(from) {
+                continue;
+            }
+            if let Some(rest) = dep.download_url.strip_prefix(from) {
+                dep.download_url = format!("{to}{rest}");
+            }
+            dep.registry_url = Some(to.to_string());
+            rebased += 1;
         }
+        rebased
     }
 
     /// Check if this lockfile is compatible with the given config hash
@@ -195,7 +550,7 @@ impl LockFile {
     }
 
     /// Check if the lockfile is up-to-date based on config file modification time
-    pub fn is_newer_than_config(&self, config_path: &Path) -> anyhow::Result<bool> {
+    pub fn is_newer_than_config(&self, config_path: &Path) -> Result<bool> {
         if let Some(config_modified_str) = &self.config_modified {
             if let Ok(config_modified_nanos) = config_modified_str.parse::<i64>() {
                 if let Ok(metadata) = fs::metadata(config_path) {
@@ -219,7 +574,7 @@ impl LockFile {
         config_path: &Path,
         current_config_hash: &str,
         dependencies: &[LockedDependency],
-    ) -> anyhow::Result<bool> {
+    ) -> Result<bool> {
         // 1. Check config hash compatibility
         if !self.is_compatible_with_config(current_config_hash) {
             return Ok(false);
@@ -263,78 +618,327 @@ impl LockFile {
             })
     }
 
+    /// Return the direct dependency named `name` (every artifact of a `group`
+    /// dependency) along with every locked entry reachable through its references
+    pub fn dependency_closure(&self, name: &str) -> Vec<&LockedDependency> {
+        let mut closure: Vec<&LockedDependency> = Vec::new();
+        let mut queue: Vec<&LockedDependency> = self
+            .locked_dependencies
+            .iter()
+            .filter(|d| d.locked_for(name) && !d.is_transitive)
+            .collect();
+
+        while let Some(dep) = queue.pop() {
+            if closure.iter().any(|c| c.entry_key() == dep.entry_key()) {
+                continue;
+            }
+            for reference in &dep.references {
+                if let Some(child) = find_reference(&self.locked_dependencies, dep, reference) {
+                    queue.push(child);
+                }
+            }
+            closure.push(dep);
+        }
+        closure
+    }
+
+    /// Replace the entries of a single direct dependency (and its transitive
+    /// references) with freshly resolved ones, keeping every other entry intact
+    ///
+    /// Old transitive entries are dropped unless another direct dependency still
+    /// references them.
+    pub fn replace_dependency_closure(&mut self, name: &str, replacement: Vec<LockedDependency>) {
+        let old_keys: std::collections::HashSet<String> = self
+            .dependency_closure(name)
+            .into_iter()
+            .map(LockedDependency::entry_key)
+            .collect();
+        let still_needed: std::collections::HashSet<String> = self
+            .locked_dependencies
+            .iter()
+            .filter(|d| !d.is_transitive && !d.locked_for(name))
+            .flat_map(|d| self.dependency_closure(&d.name))
+            .map(LockedDependency::entry_key)
+            .collect();
+
+        self.locked_dependencies.retain(|d| {
+            let key = d.entry_key();
+            !old_keys.contains(&key) || still_needed.contains(&key)
+        });
+
+        for new_dep in replacement {
+            let artifact_key = new_dep.artifact_key();
+            if new_dep.is_transitive {
+                // Never demote another direct dependency to a transitive entry
+                if self
+                    .locked_dependencies
+                    .iter()
+                    .any(|d| !d.is_transitive && d.artifact_key() == artifact_key)
+                {
+                    continue;
+                }
+            } else {
+                // A direct dependency replaces a transitive entry of its artifact
+                self.locked_dependencies
+                    .retain(|d| !(d.is_transitive && d.artifact_key() == artifact_key));
+            }
+            let key = new_dep.entry_key();
+            match self
+                .locked_dependencies
+                .iter()
+                .position(|d| d.entry_key() == key)
+            {
+                Some(pos) => self.locked_dependencies[pos] = new_dep,
+                None => self.locked_dependencies.push(new_dep),
+            }
+        }
+        sort_locked_dependencies(&mut self.locked_dependencies);
+    }
+
     /// Compute a hash of the relevant configuration that affects locking
     /// This focuses only on the dependency specifications, not formatting/comments
     pub fn compute_config_hash(
         config_content: &str,
         dependencies: &[crate::config::DependencyConfig],
+    ) -> String {
+        Self::compute_config_hash_for_profile(
+            config_content,
+            dependencies,
+            crate::config::active_profile_name().as_deref(),
+        )
+    }
+
+    /// Compute the config hash with an explicit environment profile
+    ///
+    /// The profile's registry overrides are hashed as well, so switching to an
+    /// environment whose registries differ invalidates the lock.
+    pub fn compute_config_hash_for_profile(
+        config_content: &str,
+        dependencies: &[crate::config::DependencyConfig],
+        profile: Option<&str>,
     ) -> String {
         let mut hasher = Sha256::new();
+        for (_, value) in
+            Self::config_hash_inputs_for_profile(config_content, dependencies, profile)
+        {
+            hasher.update(value.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// What the config hash is computed from, as `(input, hashed text)` pairs
+    /// in hashing order, e.g. `("dependency users", "users:com.example:users:^1:main:")`
+    pub fn config_hash_inputs(
+        config_content: &str,
+        dependencies: &[crate::config::DependencyConfig],
+    ) -> Vec<(String, String)> {
+        Self::config_hash_inputs_for_profile(
+            config_content,
+            dependencies,
+            crate::config::active_profile_name().as_deref(),
+        )
+    }
+
+    /// [`LockFile::config_hash_inputs`] with an explicit environment profile
+    pub fn config_hash_inputs_for_profile(
+        config_content: &str,
+        dependencies: &[crate::config::DependencyConfig],
+        profile: Option<&str>,
+    ) -> Vec<(String, String)> {
+        let mut inputs = Vec::new();
 
         // Only hash the dependency specifications in a deterministic order
         // This avoids regeneration due to formatting/comment changes
-        let mut dep_specs: Vec<String> = dependencies
+        let mut dep_specs: Vec<(String, String)> = dependencies
             .iter()
             .map(|d| {
-                format!(
-                    "{}:{}:{}:{}:{}:{}",
-                    d.name,
-                    d.resolved_group_id(),
-                    d.resolved_artifact_id(),
-                    d.version,
-                    d.registry.clone().unwrap_or_default(),
-                    d.output_path.clone().unwrap_or_default()
+                (
+                    format!("dependency {}", d.name),
+                    format!(
+                        "{}:{}:{}:{}:{}:{}",
+                        d.name,
+                        d.resolved_group_id(),
+                        d.resolved_artifact_id(),
+                        d.version,
+                        d.registry.clone().unwrap_or_default(),
+                        d.output_path.clone().unwrap_or_default()
+                    ),
                 )
             })
             .collect();
-        dep_specs.sort();
-
-        for spec in dep_specs {
-            hasher.update(spec.as_bytes());
+        dep_specs.sort_by(|a, b| a.1.cmp(&b.1));
+        inputs.extend(dep_specs);
+
+        // Settings that change how a dependency resolves, only when set so
+        // locks of configs not using them keep their hash
+        let mut dep_settings: Vec<(String, String)> = Vec::new();
+        for d in dependencies {
+            let mut setting = |field: &str, value: String| {
+                dep_settings.push((format!("dependency {}.{field}", d.name), value));
+            };
+            if d.binary {
+                setting("binary", "true".to_string());
+            }
+            if let Some(content_type) = &d.content_type {
+                setting("contentType", content_type.clone());
+            }
+            if !d.labels.is_empty() {
+                let labels: Vec<String> =
+                    d.labels.iter().map(|(k, v)| format!("{k}={v}")).collect();
+                setting("labels", labels.join(","));
+            }
+            if !d.include.is_empty() {
+                setting("include", d.include.join(","));
+            }
+            if !d.exclude.is_empty() {
+                setting("exclude", d.exclude.join(","));
+            }
+            if d.if_no_match != crate::config::NoMatchPolicy::default() {
+                setting("ifNoMatch", format!("{:?}", d.if_no_match));
+            }
         }
+        dep_settings.sort();
+        inputs.extend(dep_settings);
 
         // Also include a simplified version of other config that affects dependency resolution
         // Parse the config to extract only relevant fields
-        if let Ok(config) = serde_yaml::from_str::<crate::config::RepoConfig>(config_content) {
+        // (JSON configs are mostly valid YAML; tab-indented ones need the JSON
+        // parser, and TOML configs the TOML one)
+        let parsed = serde_yaml::from_str::<crate::config::RepoConfig>(config_content)
+            .ok()
+            .or_else(|| serde_json::from_str(config_content).ok())
+            .or_else(|| toml::from_str(config_content).ok());
+        if let Some(config) = parsed {
             // Include registry configurations as they affect resolution
-            let mut registry_specs: Vec<String> = config
+            let mut registry_specs: Vec<(String, String)> = config
                 .registries
                 .iter()
-                .map(|r| format!("{}:{}", r.name, r.url))
+                .map(|r| {
+                    (
+                        format!("registry {}", r.name),
+                        format!("{}:{}", r.name, r.url),
+                    )
+                })
                 .collect();
-            registry_specs.sort();
-
-            for spec in registry_specs {
-                hasher.update(spec.as_bytes());
-            }
+            registry_specs.sort_by(|a, b| a.1.cmp(&b.1));
+            inputs.extend(registry_specs);
 
             // Include external registries file path if present
             if let Some(ext_file) = &config.external_registries_file {
-                hasher.update(ext_file.as_bytes());
+                inputs.push(("externalRegistriesFile".to_string(), ext_file.clone()));
             }
 
             if let Some(default_registry) = &config.dependency_defaults.registry {
-                hasher.update(default_registry.as_bytes());
+                inputs.push((
+                    "dependencyDefaults.registry".to_string(),
+                    default_registry.clone(),
+                ));
             }
             let patterns = &config.dependency_defaults.output_patterns;
-            hasher.update(patterns.resolve("protobuf", None).as_bytes());
-            hasher.update(patterns.resolve("avro", None).as_bytes());
-            hasher.update(patterns.resolve("json", None).as_bytes());
-            hasher.update(patterns.resolve("openapi", None).as_bytes());
-            hasher.update(patterns.resolve("asyncapi", None).as_bytes());
-            hasher.update(patterns.resolve("graphql", None).as_bytes());
-            hasher.update(patterns.resolve("xml", None).as_bytes());
-            hasher.update(patterns.resolve("wsdl", None).as_bytes());
-            hasher.update(patterns.resolve("other", None).as_bytes());
+            for kind in [
+                "protobuf", "avro", "json", "openapi", "asyncapi", "graphql", "xml", "wsdl",
+                "other",
+            ] {
+                inputs.push((
+                    format!("dependencyDefaults.outputPatterns.{kind}"),
+                    patterns.resolve(kind, None),
+                ));
+            }
+
+            for (artifact, version) in &config.overrides {
+                inputs.push((format!("override {artifact}"), version.clone()));
+            }
+            let references = &config.reference_resolution;
+            if !references.exclude.is_empty() {
+                inputs.push((
+                    "referenceResolution.exclude".to_string(),
+                    references.exclude.join(","),
+                ));
+            }
+            if !references.registry_fallbacks.is_empty() {
+                inputs.push((
+                    "referenceResolution.registryFallbacks".to_string(),
+                    references.registry_fallbacks.join(","),
+                ));
+            }
+            if references.conflict_policy != crate::config::ConflictPolicy::default() {
+                inputs.push((
+                    "referenceResolution.conflictPolicy".to_string(),
+                    format!("{:?}", references.conflict_policy),
+                ));
+            }
+
+            if let Some(active) = profile.and_then(|name| config.profiles.get(name)) {
+                let mut profile_specs: Vec<(String, String)> = active
+                    .registries
+                    .iter()
+                    .map(|r| {
+                        (
+                            format!("profile registry {}", r.name),
+                            format!(
+                                "{}:{}:{:?}",
+                                r.name,
+                                r.url.clone().unwrap_or_default(),
+                                r.auth
+                            ),
+                        )
+                    })
+                    .collect();
+                profile_specs.sort_by(|a, b| a.1.cmp(&b.1));
+
+                inputs.push((
+                    "profile".to_string(),
+                    profile.unwrap_or_default().to_string(),
+                ));
+                inputs.extend(profile_specs);
+            }
         }
 
-        hex::encode(hasher.finalize())
+        inputs
+    }
+
+    /// Checksum of each config hash input, as recorded in `configInputs`
+    pub fn config_input_checksums(inputs: &[(String, String)]) -> BTreeMap<String, String> {
+        inputs
+            .iter()
+            .map(|(input, value)| {
+                let digest = hex::encode(Sha256::digest(value.as_bytes()));
+                (input.clone(), digest[..16].to_string())
+            })
+            .collect()
+    }
+
+    /// The config hash inputs that differ from those recorded in `configInputs`
+    ///
+    /// Empty when the lock predates `configInputs` and so cannot tell.
+    pub fn changed_config_inputs(&self, inputs: &[(String, String)]) -> Vec<ConfigInputChange> {
+        if self.config_inputs.is_empty() {
+            return Vec::new();
+        }
+        let current = Self::config_input_checksums(inputs);
+        let mut changes = Vec::new();
+        for (input, checksum) in &current {
+            match self.config_inputs.get(input) {
+                None => changes.push(ConfigInputChange::Added(input.clone())),
+                Some(recorded) if recorded != checksum => {
+                    changes.push(ConfigInputChange::Changed(input.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        for input in self.config_inputs.keys() {
+            if !current.contains_key(input) {
+                changes.push(ConfigInputChange::Removed(input.clone()));
+            }
+        }
+        changes
     }
 
     /// Get the modification time of a config file as nanoseconds since epoch
-    pub fn get_config_modification_time(config_path: &Path) -> anyhow::Result<String> {
-        let metadata = fs::metadata(config_path)?;
-        let modified = metadata.modified()?;
+    pub fn get_config_modification_time(config_path: &Path) -> Result<String> {
+        let modified = fs::metadata(config_path)
+            .and_then(|m| m.modified())
+            .map_err(|e| ApicurioError::io("reading the modification time of", config_path, e))?;
         let nanos = chrono::DateTime::<chrono::Utc>::from(modified)
             .timestamp_nanos_opt()
             .unwrap_or(0);
@@ -383,12 +987,19 @@ dependencies:{deps}"#
             download_url: format!(
                 "https://example.com/{group_id}/{artifact_id}/{resolved_version}"
             ),
+            registry_url: None,
             sha256: "dummy_hash".to_string(),
             output_path: "./protos".to_string(),
             group_id: group_id.to_string(),
             artifact_id: artifact_id.to_string(),
             version_spec: version_spec.to_string(),
             is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
         }
     }
 
@@ -430,6 +1041,14 @@ dependencies:{deps}"#
             registry: Some("registry1".to_string()),
             output_path: Some("./protos".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         }];
 
         let deps3 = vec![DependencyConfig {
@@ -440,6 +1059,14 @@ dependencies:{deps}"#
             registry: Some("registry1".to_string()),
             output_path: Some("./protos".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         }];
 
         let hash1 = LockFile::compute_config_hash(&config1, &deps1);
@@ -453,6 +1080,31 @@ dependencies:{deps}"#
         );
     }
 
+    #[test]
+    fn test_config_hash_changes_with_profile() {
+        let config = r#"
+registries:
+  - name: main
+    url: https://dev.example.com
+profiles:
+  dev: {}
+  prod:
+    registries:
+      - name: main
+        url: https://prod.example.com
+"#;
+
+        let base = LockFile::compute_config_hash_for_profile(config, &[], None);
+        let dev = LockFile::compute_config_hash_for_profile(config, &[], Some("dev"));
+        let prod = LockFile::compute_config_hash_for_profile(config, &[], Some("prod"));
+
+        assert_ne!(
+            base, prod,
+            "Profile registry overrides should change the hash"
+        );
+        assert_ne!(dev, prod);
+    }
+
     #[test]
     fn test_dependencies_match_order_independence() {
         let dep1 = create_test_locked_dependency(
@@ -564,6 +1216,157 @@ dependencies:{deps}"#
         assert!(lockfile.dependencies_match(&deserialized.locked_dependencies));
     }
 
+    #[test]
+    fn test_save_is_stable_and_skips_unchanged_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("apicuriolock.yaml");
+        let mut transitive =
+            create_test_locked_dependency("a-ref", "reg1", "1.0.0", "com.example", "ref", "1.0.0");
+        transitive.is_transitive = true;
+        let mut direct =
+            create_test_locked_dependency("z-dep", "reg1", "2.0.0", "com.example", "z", "^2");
+        direct.references = vec!["com.example/ref".to_string(), "com.example/b".to_string()];
+        let lockfile = LockFile::new(vec![transitive, direct], "hash".to_string());
+
+        assert!(lockfile.save(&path).unwrap());
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with(&lock_header()));
+        let loaded = LockFile::load(&path).unwrap();
+        let names: Vec<&str> = loaded
+            .locked_dependencies
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["z-dep", "a-ref"]);
+        assert_eq!(
+            loaded.locked_dependencies[0].references,
+            vec!["com.example/b", "com.example/ref"]
+        );
+
+        // Regenerating the same lock later changes only `generatedAt`: not rewritten
+        let mut regenerated = loaded.clone();
+        regenerated.generated_at = "0".to_string();
+        regenerated.locked_dependencies.reverse();
+        assert!(!regenerated.save(&path).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), written);
+
+        regenerated.config_hash = "other".to_string();
+        assert!(regenerated.save(&path).unwrap());
+
+        // A `.json` lock file is written and read as JSON
+        let json_path = dir.path().join("apicuriolock.json");
+        assert!(regenerated.save(&json_path).unwrap());
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(json["lockedDependencies"][0]["name"], "z-dep");
+        assert!(LockFile::load(&json_path)
+            .unwrap()
+            .same_content(&regenerated));
+    }
+
+    #[test]
+    fn test_same_resolution_ignores_timestamps() {
+        let dep = create_test_locked_dependency("dep", "reg1", "1.0.0", "g", "a", "^1");
+        let old = LockFile::with_config_modified(vec![dep.clone()], "h".to_string(), None);
+        let mut new =
+            LockFile::with_config_modified(vec![dep.clone()], "h".to_string(), Some("1".into()));
+        new.generated_at = "later".to_string();
+        assert!(old.same_resolution(&new));
+        assert!(!old.same_content(&new));
+
+        let mut bumped = dep;
+        bumped.resolved_version = "1.0.1".to_string();
+        let changed = LockFile::with_config_modified(vec![bumped], "h".to_string(), None);
+        assert!(!old.same_resolution(&changed));
+    }
+
+    #[test]
+    fn test_changed_config_inputs() {
+        let config = create_test_config(&[
+            ("users", "com.example", "users", "^1", "main", ""),
+            ("orders", "com.example", "orders", "^2", "main", ""),
+        ]);
+        let repo: crate::config::RepoConfig = serde_yaml::from_str(&config).unwrap();
+        let inputs = LockFile::config_hash_inputs_for_profile(&config, &repo.dependencies, None);
+        let mut hasher = Sha256::new();
+        for (_, value) in &inputs {
+            hasher.update(value.as_bytes());
+        }
+        assert_eq!(
+            hex::encode(hasher.finalize()),
+            LockFile::compute_config_hash_for_profile(&config, &repo.dependencies, None)
+        );
+
+        let mut lock = LockFile::new(vec![], "hash".to_string());
+        // Older locks do not record their inputs
+        assert!(lock.changed_config_inputs(&inputs).is_empty());
+        lock.config_inputs = LockFile::config_input_checksums(&inputs);
+        assert!(lock.changed_config_inputs(&inputs).is_empty());
+
+        let changed = create_test_config(&[
+            ("users", "com.example", "users", "^1.2", "main", ""),
+            ("billing", "com.example", "billing", "^1", "main", ""),
+        ]);
+        let repo: crate::config::RepoConfig = serde_yaml::from_str(&changed).unwrap();
+        let inputs = LockFile::config_hash_inputs_for_profile(&changed, &repo.dependencies, None);
+        let changes: Vec<String> = lock
+            .changed_config_inputs(&inputs)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                "dependency billing added",
+                "dependency users changed",
+                "dependency orders removed"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_moved_registries_and_rebase() {
+        let mut pinned = create_test_locked_dependency("a", "main", "1.0.0", "g", "a", "^1");
+        pinned.download_url =
+            "https://old.example/apis/registry/v3/groups/g/artifacts/a/versions/1.0.0/content"
+                .to_string();
+        pinned.registry_url = Some("https://old.example".to_string());
+        // Locked before `registryUrl` was recorded
+        let mut legacy = create_test_locked_dependency("b", "main", "1.0.0", "g", "b", "^1");
+        legacy.download_url =
+            "https://old.example/apis/registry/v3/groups/g/artifacts/b/versions/1.0.0/content"
+                .to_string();
+        let other = create_test_locked_dependency("c", "other", "1.0.0", "g", "c", "^1");
+        let mut lock = LockFile::new(vec![pinned, legacy, other], "hash".to_string());
+
+        assert!(lock
+            .moved_registries(|_| Some("https://old.example/"))
+            .is_empty());
+        let moved = lock.moved_registries(|r| (r == "main").then_some("https://new.example"));
+        assert_eq!(
+            moved,
+            [(
+                "main".to_string(),
+                "https://old.example".to_string(),
+                "https://new.example".to_string()
+            )]
+        );
+
+        assert_eq!(
+            lock.rebase_registry_url("main", "https://old.example", "https://new.example"),
+            2
+        );
+        assert!(lock
+            .locked_dependencies
+            .iter()
+            .filter(|d| d.registry == "main")
+            .all(|d| d.download_url.starts_with("https://new.example/apis/")
+                && d.registry_url.as_deref() == Some("https://new.example")));
+        assert!(lock
+            .moved_registries(|r| (r == "main").then_some("https://new.example"))
+            .is_empty());
+    }
+
     #[test]
     fn test_empty_dependencies() {
         let lockfile = LockFile::new(vec![], "test_hash".to_string());
@@ -618,6 +1421,14 @@ dependencies:{deps}"#
                 registry: Some("registry1".to_string()),
                 output_path: Some("./protos".to_string()),
                 resolve_references: None,
+                binary: false,
+                content_type: None,
+                group: None,
+                labels: Default::default(),
+                include: Vec::new(),
+                exclude: Vec::new(),
+                if_no_match: Default::default(),
+                member_of: None,
             },
             crate::config::DependencyConfig {
                 name: "dep_b".to_string(),
@@ -627,6 +1438,14 @@ dependencies:{deps}"#
                 registry: Some("registry1".to_string()),
                 output_path: Some("./protos".to_string()),
                 resolve_references: None,
+                binary: false,
+                content_type: None,
+                group: None,
+                labels: Default::default(),
+                include: Vec::new(),
+                exclude: Vec::new(),
+                if_no_match: Default::default(),
+                member_of: None,
             },
         ];
 
@@ -650,6 +1469,14 @@ dependencies:{deps}"#
             registry: Some("registry1".to_string()),
             output_path: Some("./protos".to_string()),
             resolve_references: None,
+            binary: false,
+            content_type: None,
+            group: None,
+            labels: Default::default(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            if_no_match: Default::default(),
+            member_of: None,
         }];
 
         // These configs have different formatting but same semantic content
@@ -768,6 +1595,103 @@ lockedDependencies:
         assert_eq!(lockfile.locked_dependencies.len(), 1);
     }
 
+    #[test]
+    fn test_dependency_closure_follows_references() {
+        let mut parent = create_test_locked_dependency(
+            "parent",
+            "reg1",
+            "1.0.0",
+            "com.example",
+            "parent",
+            "^1.0",
+        );
+        parent.references = vec!["com.example/child".to_string()];
+        let mut child = create_test_locked_dependency(
+            "com.example/child",
+            "reg1",
+            "1.2.0",
+            "com.example",
+            "child",
+            "1.2.0",
+        );
+        child.is_transitive = true;
+        child.references = vec!["com.example/grandchild".to_string()];
+        let mut grandchild = create_test_locked_dependency(
+            "com.example/grandchild",
+            "reg1",
+            "0.1.0",
+            "com.example",
+            "grandchild",
+            "0.1.0",
+        );
+        grandchild.is_transitive = true;
+        let other =
+            create_test_locked_dependency("other", "reg1", "2.0.0", "com.example", "other", "^2.0");
+
+        let lockfile = LockFile::new(
+            vec![parent, child, grandchild, other],
+            "test_hash".to_string(),
+        );
+
+        let mut names: Vec<&str> = lockfile
+            .dependency_closure("parent")
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["com.example/child", "com.example/grandchild", "parent"]
+        );
+        assert_eq!(lockfile.dependency_closure("other").len(), 1);
+        assert!(lockfile.dependency_closure("missing").is_empty());
+    }
+
+    #[test]
+    fn test_replace_dependency_closure_keeps_shared_references() {
+        let mut a = create_test_locked_dependency("a", "reg1", "1.0.0", "g", "a", "^1.0");
+        a.references = vec!["g/shared".to_string(), "g/only-a".to_string()];
+        let mut b = create_test_locked_dependency("b", "reg1", "1.0.0", "g", "b", "^1.0");
+        b.references = vec!["g/shared".to_string()];
+        let mut shared =
+            create_test_locked_dependency("g/shared", "reg1", "1.0.0", "g", "shared", "1.0.0");
+        shared.is_transitive = true;
+        let mut only_a =
+            create_test_locked_dependency("g/only-a", "reg1", "1.0.0", "g", "only-a", "1.0.0");
+        only_a.is_transitive = true;
+
+        let mut lockfile = LockFile::new(vec![a, b, shared, only_a], "test_hash".to_string());
+
+        let mut new_a = create_test_locked_dependency("a", "reg1", "1.1.0", "g", "a", "^1.0");
+        new_a.references = vec!["g/shared".to_string()];
+        lockfile.replace_dependency_closure("a", vec![new_a]);
+
+        let names: Vec<&str> = lockfile
+            .locked_dependencies
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["a", "b", "g/shared"]);
+        assert_eq!(lockfile.locked_dependencies[0].resolved_version, "1.1.0");
+    }
+
+    #[test]
+    fn test_replace_dependency_closure_keeps_other_aliases() {
+        let v1 = create_test_locked_dependency("api-v1", "reg1", "1.4.0", "g", "api", "^1");
+        let v2 = create_test_locked_dependency("api-v2", "reg1", "2.1.0", "g", "api", "^2");
+        let mut lockfile = LockFile::new(vec![v1, v2], "test_hash".to_string());
+
+        let new_v1 = create_test_locked_dependency("api-v1", "reg1", "1.5.0", "g", "api", "^1");
+        lockfile.replace_dependency_closure("api-v1", vec![new_v1]);
+
+        let entries: Vec<(&str, &str)> = lockfile
+            .locked_dependencies
+            .iter()
+            .map(|d| (d.name.as_str(), d.resolved_version.as_str()))
+            .collect();
+        assert_eq!(entries, vec![("api-v1", "1.5.0"), ("api-v2", "2.1.0")]);
+    }
+
     #[test]
     fn test_robust_dependency_matching() {
         let dep1_v1 = create_test_locked_dependency(
@@ -805,7 +1729,7 @@ lockedDependencies:
         assert!(!lockfile.dependencies_match(&[dep1_v2, dep2.clone()]));
 
         // Missing dependency should fail
-        assert!(!lockfile.dependencies_match(&[dep1_v1.clone()]));
+        assert!(!lockfile.dependencies_match(std::slice::from_ref(&dep1_v1)));
 
         // Extra dependency should fail
         let dep3 = create_test_locked_dependency(
@@ -818,6 +1742,33 @@ lockedDependencies:
         );
         assert!(!lockfile.dependencies_match(&[dep1_v1.clone(), dep2.clone(), dep3]));
     }
+
+    #[test]
+    fn test_find_output_conflicts() {
+        let conflicts = find_output_conflicts([
+            ("users", "protos/users.proto"),
+            ("orders", "./protos/users.proto"),
+            ("common", "protos/common"),
+            ("common", "protos/common"),
+            ("nested", "protos/common/types.proto"),
+            ("billing", "protos/billing.proto"),
+        ]);
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].first, "users");
+        assert_eq!(conflicts[0].second, "orders");
+        assert!(conflicts[0].to_string().contains("both write to"));
+        assert_eq!(conflicts[1].first, "common");
+        assert_eq!(conflicts[1].second, "nested");
+
+        let mut dep1 = create_test_locked_dependency("a", "r", "1.0.0", "g", "a", "^1");
+        let mut dep2 = create_test_locked_dependency("b", "r", "1.0.0", "g", "b", "^1");
+        dep1.output_path = "out/a.proto".to_string();
+        dep2.output_path = "out/b.proto".to_string();
+        assert!(check_output_conflicts(&[dep1.clone(), dep2.clone()]).is_ok());
+        dep2.output_path = "out/a.proto".to_string();
+        let err = check_output_conflicts(&[dep1, dep2]).unwrap_err();
+        assert!(err.to_string().contains("'a' and 'b'"));
+    }
 }
 
 #[cfg(test)]
@@ -833,7 +1784,9 @@ mod pattern_tests {
             "sp.frame.Frame",
             "4.3.1",
             "proto",
-        );
+            &PatternMetadata::default(),
+        )
+        .unwrap();
         assert_eq!(result, "protos/sp/frame/frame.proto");
 
         // Test artifactId.fullPath (includes last part)
@@ -843,7 +1796,9 @@ mod pattern_tests {
             "sp.frame.Frame",
             "4.3.1",
             "avsc",
-        );
+            &PatternMetadata::default(),
+        )
+        .unwrap();
         assert_eq!(result, "schemas/sp/frame/Frame.avsc");
 
         // Test single part artifact ID
@@ -853,7 +1808,9 @@ mod pattern_tests {
             "SimpleMessage",
             "1.0.0",
             "proto",
-        );
+            &PatternMetadata::default(),
+        )
+        .unwrap();
         assert_eq!(result, "protos//simplemessage.proto"); // Empty path when no dots
 
         // Test empty artifact ID edge case
@@ -863,7 +1820,9 @@ mod pattern_tests {
             "",
             "1.0.0",
             "proto",
-        );
+            &PatternMetadata::default(),
+        )
+        .unwrap();
         assert_eq!(result, "protos//.proto");
 
         // Test artifactId.lastSnakeCase conversion
@@ -873,7 +1832,9 @@ mod pattern_tests {
             "sp.frame.PingService",
             "1.0.0",
             "proto",
-        );
+            &PatternMetadata::default(),
+        )
+        .unwrap();
         assert_eq!(result, "protos/sp/frame/ping_service.proto");
 
         // Test snake_case with already snake_case name
@@ -883,7 +1844,9 @@ mod pattern_tests {
             "already_snake_case",
             "1.0.0",
             "proto",
-        );
+            &PatternMetadata::default(),
+        )
+        .unwrap();
         assert_eq!(result, "protos/already_snake_case.proto");
 
         // Test snake_case with mixed case
@@ -893,10 +1856,96 @@ mod pattern_tests {
             "com.example.XMLHttpRequest",
             "1.0.0",
             "proto",
-        );
+            &PatternMetadata::default(),
+        )
+        .unwrap();
         assert_eq!(result, "protos/xml_http_request.proto");
     }
 
+    #[test]
+    fn test_registry_metadata_placeholders() {
+        let metadata = PatternMetadata {
+            registry: "main".to_string(),
+            artifact_type: "OPENAPI".to_string(),
+            labels: [("team".to_string(), "payments".to_string())].into(),
+        };
+        let result = expand_pattern(
+            "{registry}/{groupId.path}/{labels.team}/{artifactType}/{artifactId}.{ext}",
+            "com.example",
+            "orders",
+            "1.0.0",
+            "yaml",
+            &metadata,
+        )
+        .unwrap();
+        assert_eq!(result, "main/com/example/payments/OPENAPI/orders.yaml");
+
+        // Unknown labels expand to nothing
+        let result = expand_pattern(
+            "specs/{labels.owner}{artifactId}.{ext}",
+            "default",
+            "orders",
+            "1.0.0",
+            "yaml",
+            &metadata,
+        )
+        .unwrap();
+        assert_eq!(result, "specs/orders.yaml");
+    }
+
+    #[test]
+    fn test_pattern_filters() {
+        let expand = |pattern: &str| {
+            expand_pattern(
+                pattern,
+                "com.example",
+                "sp.frame.PingService",
+                "1.0.0",
+                "proto",
+                &PatternMetadata::default(),
+            )
+        };
+        assert_eq!(
+            expand("{groupId|path}/{artifactId.last|kebab}.{ext}").unwrap(),
+            "com/example/ping-service.proto"
+        );
+        assert_eq!(
+            expand("{artifactId.last|snake|upper}").unwrap(),
+            "PING_SERVICE"
+        );
+        assert_eq!(
+            expand("{artifactId|lower}").unwrap(),
+            "sp.frame.pingservice"
+        );
+        // Unknown keys are left untouched
+        assert_eq!(expand("{unknown}/x").unwrap(), "{unknown}/x");
+
+        let err = expand("{artifactId|shout}").unwrap_err().to_string();
+        assert!(err.contains("unknown filter 'shout'"));
+        assert!(err.contains("available filters: lower"));
+        assert!(expand("protos/{artifactId").is_err());
+    }
+
+    #[test]
+    fn test_validate_pattern() {
+        use crate::output_path::validate_pattern;
+
+        assert!(
+            validate_pattern("protos/{artifactId.path}/{artifactId.lastSnakeCase}.proto").is_ok()
+        );
+        assert!(validate_pattern("{labels.team}/{artifactParts[1]|kebab}.{ext}").is_ok());
+
+        let err = validate_pattern("schemas/{artifactid}.{ext}")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown placeholder '{artifactid}'"));
+        assert!(err.contains("schemas/{artifactid}.{ext}"));
+        assert!(err.contains("valid placeholders: groupId"));
+
+        assert!(validate_pattern("{artifactId|shout}").is_err());
+        assert!(validate_pattern("{labels.}").is_err());
+    }
+
     #[test]
     fn test_resolve_output_path_with_null_override() {
         use std::collections::HashMap;
@@ -907,41 +1956,46 @@ mod pattern_tests {
             Some("protos/sp/frame/frame.{ext}".to_string()),
         );
         overrides.insert("nprod/sp.internal.Debug".to_string(), None); // Skip this one
+        let metadata = PatternMetadata {
+            registry: "nprod-apicurio".to_string(),
+            artifact_type: "PROTOBUF".to_string(),
+            ..Default::default()
+        };
 
         // Should return mapped path
         let result = resolve_output_path(
             "references/{groupId}/{artifactId}.{ext}",
             &overrides,
-            "nprod-apicurio",
             "nprod",
             "sp.frame.Frame",
             "4.3.1",
-            "PROTOBUF",
-        );
+            &metadata,
+        )
+        .unwrap();
         assert_eq!(result, Some("protos/sp/frame/frame.proto".to_string()));
 
         // Should return None for null override
         let result = resolve_output_path(
             "references/{groupId}/{artifactId}.{ext}",
             &overrides,
-            "nprod-apicurio",
             "nprod",
             "sp.internal.Debug",
             "1.0.0",
-            "PROTOBUF",
-        );
+            &metadata,
+        )
+        .unwrap();
         assert_eq!(result, None);
 
         // Should use default pattern when no override
         let result = resolve_output_path(
             "references/{groupId}/{artifactId}.{ext}",
             &overrides,
-            "nprod-apicurio",
             "nprod",
             "sp.other.Service",
             "2.0.0",
-            "PROTOBUF",
-        );
+            &metadata,
+        )
+        .unwrap();
         assert_eq!(
             result,
             Some("references/nprod/sp.other.Service.proto".to_string())
@@ -37,20 +37,33 @@
 //!
 //! Global registries can be configured in `~/.config/apicurio/registries.yaml`.
 
-use anyhow::Result;
-use apicurio_cli::{commands, Cli};
+use anyhow::{Context, Result};
+use apicurio_cli::{commands, config::RunSettings, Cli};
 use clap::Parser;
 
 /// Main entry point for the Apicurio CLI
 ///
 /// Parses command-line arguments and delegates to the appropriate command handler.
-/// If no command is provided, displays an error message and exits.
-#[tokio::main]
-async fn main() -> Result<()> {
+/// If no command is provided, displays an error message and exits. The
+/// working directory is only changed before the runtime and its threads start.
+fn main() -> Result<()> {
     let cli = Cli::parse();
+    if let Some(dir) = &cli.cwd {
+        std::env::set_current_dir(dir)
+            .with_context(|| format!("changing directory to {}", dir.display()))?;
+    }
+    RunSettings {
+        profile: cli.env.clone(),
+        read_timeout: cli.timeout,
+        config_path: cli.config_file.clone(),
+        lock_timeout: cli.lock_timeout,
+        record: cli.record.clone(),
+        replay: cli.replay.clone(),
+    }
+    .install();
     let cmd = cli.cmd.unwrap_or_else(|| {
         eprintln!("No command provided. Use --help to see available commands.");
         std::process::exit(1);
     });
-    commands::run(cmd).await
+    tokio::runtime::Runtime::new()?.block_on(commands::run(cmd))
 }
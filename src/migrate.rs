@@ -0,0 +1,374 @@
+//! Upgrades of configuration and lock files written in older formats
+//!
+//! Older releases wrote snake_case keys (`group_id`, `output_path`, ...) and a
+//! lock file without `lockfileVersion`, from before references were resolved and
+//! locked as transitive entries. Current releases ignore unknown config keys, so
+//! a legacy `output_path:` would be dropped silently; the loaders use
+//! [`ignored_config_keys`] and [`lock_changes`] to refuse such files and point at
+//! `apicurio migrate`, which rewrites them with [`migrate_config`] and
+//! [`migrate_lock`].
+//!
+//! Config files are rewritten line by line so comments and layout survive; only
+//! the renamed keys change.
+
+use crate::error::{ApicurioError, Result};
+use crate::lockfile::LockFile;
+use serde_yaml::{Mapping, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Current lock file format
+pub const LOCKFILE_VERSION: u32 = 1;
+
+/// Config keys of older releases and their current names
+const CONFIG_RENAMES: &[(&str, &str)] = &[
+    ("external_registries_file", "externalRegistriesFile"),
+    ("reference_resolution", "referenceResolution"),
+    ("dependency_defaults", "dependencyDefaults"),
+    ("output_patterns", "outputPatterns"),
+    ("output_overrides", "outputOverrides"),
+    ("max_depth", "maxDepth"),
+    ("registry_fallbacks", "registryFallbacks"),
+    ("conflict_policy", "conflictPolicy"),
+    ("group_id", "groupId"),
+    ("artifact_id", "artifactId"),
+    ("output_path", "outputPath"),
+    ("resolve_references", "resolveReferences"),
+    ("input_path", "inputPath"),
+    ("if_exists", "ifExists"),
+    ("name_alias", "nameAlias"),
+    ("content_type", "contentType"),
+    ("password_env", "passwordEnv"),
+    ("token_env", "tokenEnv"),
+    ("secret_ref", "secretRef"),
+    ("aws_sigv4", "awsSigv4"),
+];
+
+/// Legacy config keys the current loader still accepts as aliases
+const ACCEPTED_ALIASES: &[&str] = &["password_env", "token_env", "secret_ref", "aws_sigv4"];
+
+/// Config maps whose keys are user-chosen names rather than settings
+const FREE_FORM_KEYS: &[&str] = &[
+    "labels",
+    "outputOverrides",
+    "output_overrides",
+    "overrides",
+    "rules",
+];
+
+/// Lock file keys of older releases and their current names
+const LOCK_RENAMES: &[(&str, &str)] = &[
+    ("locked_dependencies", "lockedDependencies"),
+    ("lockfile_version", "lockfileVersion"),
+    ("config_hash", "configHash"),
+    ("generated_at", "generatedAt"),
+    ("config_modified", "configModified"),
+    ("resolved_version", "resolvedVersion"),
+    ("download_url", "downloadUrl"),
+    ("output_path", "outputPath"),
+    ("group_id", "groupId"),
+    ("artifact_id", "artifactId"),
+    ("version_spec", "versionSpec"),
+    ("is_transitive", "isTransitive"),
+    ("avro_fingerprint", "avroFingerprint"),
+    ("content_type", "contentType"),
+];
+
+/// A legacy key found in a config file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegacyKey {
+    /// 1-based line number
+    pub line: usize,
+    pub old: &'static str,
+    pub new: &'static str,
+}
+
+/// A file `apicurio migrate` would rewrite
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub path: PathBuf,
+    /// Human-readable list of the changes
+    pub changes: Vec<String>,
+    pub(crate) content: MigratedContent,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum MigratedContent {
+    Config(String),
+    Lock(Box<LockFile>),
+}
+
+/// The key of a `key: value` (or `- key: value`) line, with its byte range
+fn line_key(line: &str) -> Option<(usize, &str)> {
+    let mut start = line.len() - line.trim_start_matches(' ').len();
+    while line[start..].starts_with("- ") {
+        start += 2;
+        start += line[start..].len() - line[start..].trim_start_matches(' ').len();
+    }
+    let rest = &line[start..];
+    let colon = rest.find(':')?;
+    let key = &rest[..colon];
+    let after = &rest[colon + 1..];
+    if key.is_empty()
+        || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        || !(after.is_empty() || after.starts_with([' ', '\t']))
+    {
+        return None;
+    }
+    Some((start, key))
+}
+
+/// Legacy snake_case keys of a config file, skipping user-named map entries
+pub fn legacy_config_keys(input: &str) -> Vec<LegacyKey> {
+    let mut found = Vec::new();
+    let mut free_form_indent: Option<usize> = None;
+    for (index, line) in input.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        match free_form_indent {
+            Some(parent) if indent > parent => continue,
+            _ => free_form_indent = None,
+        }
+        let Some((start, key)) = line_key(line) else {
+            continue;
+        };
+        if FREE_FORM_KEYS.contains(&key) {
+            free_form_indent = Some(start);
+        }
+        if let Some((old, new)) = CONFIG_RENAMES.iter().find(|(old, _)| *old == key) {
+            found.push(LegacyKey {
+                line: index + 1,
+                old,
+                new,
+            });
+        }
+    }
+    found
+}
+
+/// Legacy keys of a config file that the current loader would ignore
+pub fn ignored_config_keys(input: &str) -> Vec<LegacyKey> {
+    legacy_config_keys(input)
+        .into_iter()
+        .filter(|key| !ACCEPTED_ALIASES.contains(&key.old))
+        .collect()
+}
+
+/// Rename the legacy keys of a config file, keeping everything else verbatim
+pub fn migrate_config(input: &str) -> (String, Vec<String>) {
+    let keys = legacy_config_keys(input);
+    if keys.is_empty() {
+        return (input.to_string(), Vec::new());
+    }
+    let mut changes = Vec::new();
+    let mut out = String::with_capacity(input.len());
+    for (index, line) in input.split_inclusive('\n').enumerate() {
+        match keys.iter().find(|key| key.line == index + 1) {
+            Some(key) => {
+                let (start, _) = line_key(line.trim_end_matches(['\r', '\n']))
+                    .expect("legacy key line has a key");
+                out.push_str(&line[..start]);
+                out.push_str(key.new);
+                out.push_str(&line[start + key.old.len()..]);
+                changes.push(format!("line {}: {} -> {}", key.line, key.old, key.new));
+            }
+            None => out.push_str(line),
+        }
+    }
+    (out, changes)
+}
+
+fn rename_lock_keys(mapping: &mut Mapping, changes: &mut Vec<String>) {
+    for (old, new) in LOCK_RENAMES {
+        if let Some(value) = mapping.remove(*old) {
+            mapping.insert(Value::from(*new), value);
+            let change = format!("{old} -> {new}");
+            if !changes.contains(&change) {
+                changes.push(change);
+            }
+        }
+    }
+}
+
+/// The upgrades an older lock file needs, renamed keys and the missing version
+fn upgrade_lock_value(value: &mut Value) -> Vec<String> {
+    let mut changes = Vec::new();
+    let Some(root) = value.as_mapping_mut() else {
+        return changes;
+    };
+    rename_lock_keys(root, &mut changes);
+    if let Some(Value::Sequence(entries)) = root.get_mut("lockedDependencies") {
+        for entry in entries.iter_mut().filter_map(Value::as_mapping_mut) {
+            rename_lock_keys(entry, &mut changes);
+        }
+    }
+    if !root.contains_key("lockfileVersion") {
+        root.insert(
+            Value::from("lockfileVersion"),
+            Value::from(LOCKFILE_VERSION),
+        );
+        changes.push(format!("lockfileVersion: {LOCKFILE_VERSION}"));
+    }
+    changes
+}
+
+/// The upgrades a lock file needs, empty for a current one
+pub fn lock_changes(input: &str) -> Vec<String> {
+    serde_yaml::from_str::<Value>(input)
+        .map(|mut value| upgrade_lock_value(&mut value))
+        .unwrap_or_default()
+}
+
+/// Upgrade an older lock file, `None` when it is already current
+pub fn migrate_lock(input: &str) -> Result<Option<(LockFile, Vec<String>)>> {
+    let mut value: Value = serde_yaml::from_str(input)
+        .map_err(|e| ApicurioError::config_caused("invalid lock file", e))?;
+    let changes = upgrade_lock_value(&mut value);
+    if changes.is_empty() {
+        return Ok(None);
+    }
+    let lock = serde_yaml::from_value(value)
+        .map_err(|e| ApicurioError::config_caused("cannot upgrade the lock file", e))?;
+    Ok(Some((lock, changes)))
+}
+
+/// Files among the config (with its includes) and the lock that need upgrading
+pub fn plan(config_path: &Path, lock_path: &Path) -> Result<Vec<Migration>> {
+    let mut migrations = Vec::new();
+    if config_path.exists() {
+        for (path, data) in crate::config::read_config_files(config_path)? {
+            let (migrated, changes) = migrate_config(&data);
+            if !changes.is_empty() {
+                migrations.push(Migration {
+                    path,
+                    changes,
+                    content: MigratedContent::Config(migrated),
+                });
+            }
+        }
+    }
+    if lock_path.exists() {
+        let data = fs::read_to_string(lock_path)
+            .map_err(|e| ApicurioError::io("reading", lock_path, e))?;
+        if let Some((lock, changes)) = migrate_lock(&data)
+            .map_err(|e| e.context(format!("upgrading {}", lock_path.display())))?
+        {
+            migrations.push(Migration {
+                path: lock_path.to_path_buf(),
+                changes,
+                content: MigratedContent::Lock(Box::new(lock)),
+            });
+        }
+    }
+    Ok(migrations)
+}
+
+/// Path of the backup `apply` keeps of `path`
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+impl Migration {
+    /// Copy the file to its backup, then rewrite it in place
+    ///
+    /// Returns the backup path.
+    pub fn apply(&self) -> Result<PathBuf> {
+        let backup = backup_path(&self.path);
+        fs::copy(&self.path, &backup)
+            .map_err(|e| ApicurioError::io("backing up", &self.path, e))?;
+        match &self.content {
+            MigratedContent::Config(text) => fs::write(&self.path, text)
+                .map_err(|e| ApicurioError::io("writing", &self.path, e))?,
+            MigratedContent::Lock(lock) => lock.force_save(&self.path)?,
+        }
+        Ok(backup)
+    }
+}
+
+/// Error for a file that has to go through `apicurio migrate` first
+pub(crate) fn outdated(path: &Path, changes: &[String]) -> ApicurioError {
+    ApicurioError::config(format!(
+        "{} uses an older format ({}); run `apicurio migrate` to upgrade it",
+        path.display(),
+        changes.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate_config_renames_keys_and_keeps_comments() {
+        let input = "# project config\n\
+                     dependency_defaults:\n  registry: main\n\
+                     dependencies:\n  # the user service\n  - name: users\n    group_id: com.example\n    version: ^1\n    output_path: protos/users.proto\n\
+                     publishes:\n  - name: api\n    labels:\n      group_id: kept\n    input_path: api.proto\n";
+        let (migrated, changes) = migrate_config(input);
+        assert_eq!(
+            migrated,
+            "# project config\n\
+             dependencyDefaults:\n  registry: main\n\
+             dependencies:\n  # the user service\n  - name: users\n    groupId: com.example\n    version: ^1\n    outputPath: protos/users.proto\n\
+             publishes:\n  - name: api\n    labels:\n      group_id: kept\n    inputPath: api.proto\n"
+        );
+        assert_eq!(changes.len(), 4);
+        assert_eq!(
+            changes[0],
+            "line 2: dependency_defaults -> dependencyDefaults"
+        );
+        assert!(migrate_config(&migrated).1.is_empty());
+    }
+
+    #[test]
+    fn test_ignored_config_keys_skip_accepted_aliases() {
+        let input = "registries:\n  - name: main\n    url: https://r\n    auth:\n      type: bearer\n      token_env: TOKEN\n\
+                     dependencies:\n  - name: users\n    artifact_id: users\n    version: ^1\n";
+        let ignored = ignored_config_keys(input);
+        assert_eq!(ignored.len(), 1);
+        assert_eq!((ignored[0].line, ignored[0].old), (9, "artifact_id"));
+        assert_eq!(legacy_config_keys(input).len(), 2);
+    }
+
+    #[test]
+    fn test_migrate_lock_from_unversioned_snake_case() {
+        let old = "config_hash: abc\ngenerated_at: '1'\nlocked_dependencies:\n  - name: users\n    registry: main\n    resolved_version: 1.0.0\n    download_url: https://r/users\n    sha256: deadbeef\n    output_path: protos/users.proto\n    group_id: com.example\n    artifact_id: users\n    version_spec: ^1\n";
+        let (lock, changes) = migrate_lock(old).unwrap().unwrap();
+        assert_eq!(lock.lockfile_version, LOCKFILE_VERSION);
+        assert_eq!(
+            lock.locked_dependencies[0].output_path,
+            "protos/users.proto"
+        );
+        assert!(!lock.locked_dependencies[0].is_transitive);
+        assert!(changes.contains(&"lockfileVersion: 1".to_string()));
+
+        let current = serde_yaml::to_string(&lock).unwrap();
+        assert!(migrate_lock(&current).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_apply_keeps_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = dir.path().join("apicurioconfig.yaml");
+        fs::write(
+            &config,
+            "dependencies:\n  - name: a\n    group_id: g\n    version: '1'\n",
+        )
+        .unwrap();
+        let migrations = plan(&config, &dir.path().join("apicuriolock.yaml")).unwrap();
+        assert_eq!(migrations.len(), 1);
+        let backup = migrations[0].apply().unwrap();
+        assert!(fs::read_to_string(backup).unwrap().contains("group_id: g"));
+        assert!(fs::read_to_string(&config).unwrap().contains("groupId: g"));
+        assert!(plan(&config, &dir.path().join("apicuriolock.yaml"))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+//! Per-registry naming conventions for artifact coordinates
+//!
+//! A registry can declare a `defaultGroup`, an `artifactIdCase` and the
+//! `groupPrefixes`/`artifactIdPrefixes` its coordinates must start with. `add`
+//! and `publish` fill a missing group from `defaultGroup`; `publish` refuses
+//! coordinates that break the conventions and `add` warns about them, since the
+//! artifact already exists.
+
+use convert_case::{Boundary, Case, Converter};
+
+use crate::config::{ArtifactIdCase, PublishConfig, RegistryConfig};
+
+/// Where artifact IDs are split into words; unlike convert_case's defaults,
+/// not between letters and digits
+const WORD_BOUNDARIES: [Boundary; 6] = [
+    Boundary::HYPHEN,
+    Boundary::UNDERSCORE,
+    Boundary::SPACE,
+    Boundary::from_delim("."),
+    Boundary::LOWER_UPPER,
+    Boundary::DIGIT_UPPER,
+];
+
+impl ArtifactIdCase {
+    /// The name used in the configuration
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ArtifactIdCase::Kebab => "kebab-case",
+            ArtifactIdCase::Snake => "snake_case",
+            ArtifactIdCase::Camel => "camelCase",
+            ArtifactIdCase::Pascal => "PascalCase",
+        }
+    }
+
+    /// `id` in this case: `OrderService v2` → `order-service-v2`
+    ///
+    /// Words are split at separators (`-`, `_`, `.`, space) and before an
+    /// uppercase letter following a lowercase one or a digit, never at the
+    /// digits themselves, so version suffixes such as `v2` stay one word.
+    pub fn apply(&self, id: &str) -> String {
+        Converter::new()
+            .set_boundaries(&WORD_BOUNDARIES)
+            .to_case(match self {
+                ArtifactIdCase::Kebab => Case::Kebab,
+                ArtifactIdCase::Snake => Case::Snake,
+                ArtifactIdCase::Camel => Case::Camel,
+                ArtifactIdCase::Pascal => Case::Pascal,
+            })
+            .convert(id)
+    }
+}
+
+/// The conventions of `registry` that `group_id` and `artifact_id` break
+pub fn violations(registry: &RegistryConfig, group_id: &str, artifact_id: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    if !registry.group_prefixes.is_empty()
+        && !registry
+            .group_prefixes
+            .iter()
+            .any(|prefix| group_id.starts_with(prefix.as_str()))
+    {
+        problems.push(format!(
+            "group '{group_id}' does not start with {}",
+            quoted(&registry.group_prefixes)
+        ));
+    }
+    if !registry.artifact_id_prefixes.is_empty()
+        && !registry
+            .artifact_id_prefixes
+            .iter()
+            .any(|prefix| artifact_id.starts_with(prefix.as_str()))
+    {
+        problems.push(format!(
+            "artifact ID '{artifact_id}' does not start with {}",
+            quoted(&registry.artifact_id_prefixes)
+        ));
+    }
+    if let Some(case) = registry.artifact_id_case {
+        // the prefix is the organization's, only the rest follows the case
+        let rest = registry
+            .artifact_id_prefixes
+            .iter()
+            .find_map(|prefix| artifact_id.strip_prefix(prefix.as_str()))
+            .unwrap_or(artifact_id);
+        let expected = case.apply(rest);
+        if expected != rest {
+            problems.push(format!(
+                "artifact ID '{artifact_id}' is not {} (expected '{}')",
+                case.as_str(),
+                artifact_id.replacen(rest, &expected, 1)
+            ));
+        }
+    }
+    problems
+}
+
+fn quoted(prefixes: &[String]) -> String {
+    let quoted: Vec<String> = prefixes.iter().map(|p| format!("'{p}'")).collect();
+    quoted.join(" or ")
+}
+
+/// `publish` with its group taken from the `defaultGroup` of `registry` when
+/// neither `groupId` nor its name gives one
+pub fn with_default_group(registry: &RegistryConfig, publish: &PublishConfig) -> PublishConfig {
+    match &registry.default_group {
+        Some(group) if publish.group_id.is_none() && !publish.name.contains('/') => PublishConfig {
+            group_id: Some(group.clone()),
+            ..publish.clone()
+        },
+        _ => publish.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_violations_and_default_group() {
+        assert_eq!(
+            ArtifactIdCase::Kebab.apply("OrderService v2"),
+            "order-service-v2"
+        );
+        assert_eq!(
+            ArtifactIdCase::Kebab.apply("orderV2Service"),
+            "order-v2-service"
+        );
+        assert_eq!(
+            ArtifactIdCase::Snake.apply("order.service"),
+            "order_service"
+        );
+        assert_eq!(
+            ArtifactIdCase::Snake.apply("order-service"),
+            "order_service"
+        );
+        assert_eq!(ArtifactIdCase::Camel.apply("order_service"), "orderService");
+        assert_eq!(ArtifactIdCase::Pascal.apply("orderService"), "OrderService");
+
+        let registry = RegistryConfig {
+            name: "acme".to_string(),
+            default_group: Some("com.acme.orders".to_string()),
+            artifact_id_case: Some(ArtifactIdCase::Kebab),
+            group_prefixes: vec!["com.acme.".to_string()],
+            artifact_id_prefixes: vec!["acme-".to_string()],
+            ..Default::default()
+        };
+        assert!(violations(&registry, "com.acme.orders", "acme-order-service").is_empty());
+        assert!(violations(&registry, "com.acme.orders", "acme-order-service-v2").is_empty());
+        let unprefixed = RegistryConfig {
+            artifact_id_prefixes: Vec::new(),
+            ..registry.clone()
+        };
+        assert!(violations(&unprefixed, "com.acme.orders", "order-service-v2").is_empty());
+        let snake = RegistryConfig {
+            artifact_id_case: Some(ArtifactIdCase::Snake),
+            ..unprefixed.clone()
+        };
+        assert!(violations(&snake, "com.acme.orders", "order_service_v2").is_empty());
+        assert_eq!(
+            violations(&registry, "com.other", "OrderService"),
+            [
+                "group 'com.other' does not start with 'com.acme.'",
+                "artifact ID 'OrderService' does not start with 'acme-'",
+                "artifact ID 'OrderService' is not kebab-case (expected 'order-service')",
+            ]
+        );
+        assert_eq!(
+            violations(&registry, "com.acme.orders", "acme-OrderService"),
+            ["artifact ID 'acme-OrderService' is not kebab-case (expected 'acme-order-service')"]
+        );
+
+        let publish = PublishConfig {
+            name: "order-service".to_string(),
+            ..Default::default()
+        };
+        let filled = with_default_group(&registry, &publish);
+        assert_eq!(filled.resolved_group_id(), "com.acme.orders");
+        let named = PublishConfig {
+            name: "com.acme.billing/invoice".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            with_default_group(&registry, &named).resolved_group_id(),
+            "com.acme.billing"
+        );
+    }
+}
@@ -0,0 +1,133 @@
+//! Posting `status` and `update` summaries to chat or other services
+//!
+//! `status --notify` and `update --notify` post a [`Notice`] to every entry of
+//! the `notifications` config: Slack incoming webhooks get a formatted `text`
+//! message, generic webhooks the notice itself as JSON.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use serde_json::json;
+use std::path::Path;
+
+use crate::config::{NotificationConfig, NotificationKind};
+
+/// Summary of one `status` or `update` run
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Notice {
+    /// Command that produced the notice (`status` or `update`)
+    pub command: String,
+    /// Project directory name
+    pub project: String,
+    pub title: String,
+    pub items: Vec<NoticeItem>,
+}
+
+/// One dependency mentioned in a [`Notice`]
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NoticeItem {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Notice {
+    pub fn new(command: &str, root: &Path, title: String, items: Vec<NoticeItem>) -> Self {
+        let project = root
+            .canonicalize()
+            .ok()
+            .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .unwrap_or_default();
+        Notice {
+            command: command.to_string(),
+            project,
+            title,
+            items,
+        }
+    }
+
+    /// Slack `mrkdwn` rendering: the title, then one bullet per item
+    pub fn slack_text(&self) -> String {
+        let mut text = format!("*{}* ({})", self.title, self.project);
+        for item in &self.items {
+            text.push_str(&format!("\n• `{}`", item.name));
+            match (&item.from, &item.to) {
+                (Some(from), Some(to)) => text.push_str(&format!(" {from} → {to}")),
+                (Some(version), None) | (None, Some(version)) => {
+                    text.push_str(&format!(" {version}"))
+                }
+                (None, None) => {}
+            }
+            if let Some(note) = &item.note {
+                text.push_str(&format!(" ({note})"));
+            }
+        }
+        text
+    }
+}
+
+/// Post `notice` to every target, failing if any of them rejects it
+pub async fn send(targets: &[NotificationConfig], notice: &Notice) -> Result<()> {
+    if targets.is_empty() {
+        bail!("--notify needs a `notifications` entry in the config");
+    }
+    let client = reqwest::Client::new();
+    for target in targets {
+        let body = match target.kind {
+            NotificationKind::Slack => json!({ "text": notice.slack_text() }),
+            NotificationKind::Webhook => serde_json::to_value(notice)?,
+        };
+        let mut request = client.post(&target.url).json(&body);
+        for (name, value) in &target.headers {
+            request = request.header(name, value);
+        }
+        // The URL often embeds a secret, so only its host is named in errors
+        let host = reqwest::Url::parse(&target.url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| anyhow!("posting the notification to {host}: {}", e.without_url()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slack_text() {
+        let notice = Notice {
+            command: "update".to_string(),
+            project: "billing".to_string(),
+            title: "2 dependencies updated".to_string(),
+            items: vec![
+                NoticeItem {
+                    name: "users".to_string(),
+                    from: Some("1.0.0".to_string()),
+                    to: Some("1.1.0".to_string()),
+                    note: None,
+                },
+                NoticeItem {
+                    name: "orders".to_string(),
+                    from: None,
+                    to: Some("2.0.0".to_string()),
+                    note: Some("added".to_string()),
+                },
+            ],
+        };
+        assert_eq!(
+            notice.slack_text(),
+            "*2 dependencies updated* (billing)\n• `users` 1.0.0 → 1.1.0\n• `orders` 2.0.0 (added)"
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! Bundled documents for pulled OpenAPI dependencies
+//!
+//! With `openapi.bundle: true`, `pull` writes `<file>.bundle.<ext>` next to every
+//! direct JSON or YAML dependency that references other artifacts: the same
+//! document with each external `$ref` replaced by the schema it points to, so
+//! tools that cannot follow references across files get one self-contained
+//! spec. References inside the bundled document itself (`#/components/...`)
+//! are kept; those inside inlined files are inlined as well.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde_yaml::{Mapping, Value};
+use std::{collections::HashMap, fs, path::Path};
+
+use crate::{
+    config::OpenApiConfig,
+    local_refs::{self, relative_path, RefSyntax},
+    lockfile::{find_reference, LockedDependency},
+    output_path, provenance,
+};
+
+/// Output paths of the bundles `pull` writes for `dependencies`
+pub fn bundle_paths(config: &OpenApiConfig, dependencies: &[LockedDependency]) -> Vec<String> {
+    if !config.bundle {
+        return Vec::new();
+    }
+    dependencies
+        .iter()
+        .filter(|d| is_bundled(d))
+        .map(|d| bundle_path(&d.output_path))
+        .collect()
+}
+
+/// Write a bundle for every direct dependency with references
+///
+/// Output paths are relative to `root`; returns the paths written.
+pub fn write_bundles(
+    root: &Path,
+    config: &OpenApiConfig,
+    dependencies: &[LockedDependency],
+) -> Result<Vec<String>> {
+    if !config.bundle {
+        return Ok(Vec::new());
+    }
+    let mut bundler = Bundler {
+        root,
+        dependencies,
+        documents: HashMap::new(),
+        stack: Vec::new(),
+    };
+    let mut written = Vec::new();
+    for dependency in dependencies.iter().filter(|d| is_bundled(d)) {
+        let document = bundler.document(dependency)?.clone();
+        let bundled = bundler.inline(dependency, document, true)?;
+        let path = bundle_path(&dependency.output_path);
+        let data = if path.to_lowercase().ends_with(".json") {
+            serde_json::to_vec_pretty(&bundled)?
+        } else {
+            serde_yaml::to_string(&bundled)?.into_bytes()
+        };
+        let target = output_path::native(root, &path);
+        fs::write(&target, data).with_context(|| format!("writing {}", target.display()))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn is_bundled(dependency: &LockedDependency) -> bool {
+    !dependency.is_transitive
+        && !dependency.reference_names.is_empty()
+        && local_refs::syntax_for(&dependency.output_path) == Some(RefSyntax::Json)
+}
+
+/// `api.yaml` → `api.bundle.yaml`
+fn bundle_path(path: &str) -> String {
+    match path.rsplit_once('.') {
+        Some((stem, ext)) if !ext.contains('/') => format!("{stem}.bundle.{ext}"),
+        _ => format!("{path}.bundle"),
+    }
+}
+
+struct Bundler<'a> {
+    root: &'a Path,
+    dependencies: &'a [LockedDependency],
+    /// Parsed pulled files by output path
+    documents: HashMap<String, Value>,
+    /// `path#fragment` of the references being inlined, to stop at cycles
+    stack: Vec<String>,
+}
+
+impl<'a> Bundler<'a> {
+    /// The pulled file of `dependency`, without its provenance header
+    fn document(&mut self, dependency: &LockedDependency) -> Result<&Value> {
+        let path = &dependency.output_path;
+        if !self.documents.contains_key(path) {
+            let file = output_path::native(self.root, path);
+            let data = provenance::strip_header(
+                &fs::read(&file).with_context(|| format!("reading {}", file.display()))?,
+            );
+            // JSON documents are YAML too
+            let document: Value = serde_yaml::from_slice(&data)
+                .with_context(|| format!("parsing {}", file.display()))?;
+            self.documents.insert(path.clone(), document);
+        }
+        Ok(&self.documents[path])
+    }
+
+    /// The lock entry a `$ref` document part of `dependency` names, either by
+    /// its registry reference name or by its local path
+    fn target(
+        &self,
+        dependency: &'a LockedDependency,
+        document: &str,
+    ) -> Option<&'a LockedDependency> {
+        dependency
+            .reference_names
+            .iter()
+            .filter_map(|(reference, name)| {
+                let target = find_reference(self.dependencies, dependency, reference)?;
+                Some((name, target))
+            })
+            .find(|(name, target)| {
+                name.as_str() == document
+                    || relative_path(&dependency.output_path, &target.output_path) == document
+            })
+            .map(|(_, target)| target)
+    }
+
+    /// `value`, found in the file of `dependency`, with its references inlined;
+    /// internal references are kept in the `root` document
+    fn inline(
+        &mut self,
+        dependency: &'a LockedDependency,
+        value: Value,
+        root: bool,
+    ) -> Result<Value> {
+        match value {
+            Value::Mapping(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    let (document, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+                    if !(document.is_empty() && root) {
+                        let target = if document.is_empty() {
+                            dependency
+                        } else {
+                            self.target(dependency, document).ok_or_else(|| {
+                                anyhow!(
+                                    "{}: $ref '{reference}' does not name a pulled file",
+                                    dependency.output_path
+                                )
+                            })?
+                        };
+                        let key = format!("{}#{fragment}", target.output_path);
+                        if self.stack.contains(&key) {
+                            bail!(
+                                "{}: $ref '{reference}' is circular and cannot be inlined",
+                                dependency.output_path
+                            );
+                        }
+                        let pointed = pointer(self.document(target)?, fragment)
+                            .cloned()
+                            .ok_or_else(|| {
+                                anyhow!(
+                                    "{}: $ref '{reference}' points to nothing in {}",
+                                    dependency.output_path,
+                                    target.output_path
+                                )
+                            })?;
+                        self.stack.push(key);
+                        let inlined = self.inline(target, pointed, false);
+                        self.stack.pop();
+                        return inlined;
+                    }
+                }
+                map.into_iter()
+                    .map(|(key, value)| Ok((key, self.inline(dependency, value, root)?)))
+                    .collect::<Result<Mapping>>()
+                    .map(Value::Mapping)
+            }
+            Value::Sequence(items) => items
+                .into_iter()
+                .map(|item| self.inline(dependency, item, root))
+                .collect::<Result<_>>()
+                .map(Value::Sequence),
+            other => Ok(other),
+        }
+    }
+}
+
+/// The value a JSON pointer (`/components/schemas/Money`) selects in `document`
+fn pointer<'v>(document: &'v Value, pointer: &str) -> Option<&'v Value> {
+    if pointer.is_empty() {
+        return Some(document);
+    }
+    pointer
+        .strip_prefix('/')?
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .try_fold(document, |value, token| match value {
+            Value::Mapping(map) => map.get(token.as_str()),
+            Value::Sequence(items) => items.get(token.parse::<usize>().ok()?),
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn locked(artifact_id: &str, output_path: &str, is_transitive: bool) -> LockedDependency {
+        LockedDependency {
+            name: artifact_id.to_string(),
+            registry: "main".to_string(),
+            resolved_version: "1.0.0".to_string(),
+            download_url: String::new(),
+            registry_url: None,
+            sha256: String::new(),
+            output_path: output_path.to_string(),
+            group_id: "default".to_string(),
+            artifact_id: artifact_id.to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
+        }
+    }
+
+    #[test]
+    fn test_bundle_inlines_external_refs() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("openapi/common")).unwrap();
+        fs::write(
+            temp.path().join("openapi/common/money.yaml"),
+            "components:\n  schemas:\n    Money:\n      type: object\n      properties:\n        \
+             currency:\n          $ref: '#/components/schemas/Currency'\n    \
+             Currency:\n      type: string\n",
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("openapi/orders.yaml"),
+            "openapi: 3.0.3\ncomponents:\n  schemas:\n    Order:\n      properties:\n        \
+             total:\n          $ref: money.yaml#/components/schemas/Money\n        \
+             lines:\n          $ref: '#/components/schemas/Lines'\n    Lines:\n      type: array\n",
+        )
+        .unwrap();
+        let mut orders = locked("orders", "openapi/orders.yaml", false);
+        orders
+            .reference_names
+            .insert("default/money".to_string(), "money.yaml".to_string());
+        let deps = [orders, locked("money", "openapi/common/money.yaml", true)];
+        let config = OpenApiConfig {
+            bundle: true,
+            ..Default::default()
+        };
+
+        assert_eq!(bundle_paths(&config, &deps), ["openapi/orders.bundle.yaml"]);
+        let written = write_bundles(temp.path(), &config, &deps).unwrap();
+        assert_eq!(written, ["openapi/orders.bundle.yaml"]);
+
+        let text = fs::read_to_string(temp.path().join("openapi/orders.bundle.yaml")).unwrap();
+        assert!(
+            text.starts_with("openapi: 3.0.3\n"),
+            "keeps key order: {text}"
+        );
+        let bundle: Value = serde_yaml::from_str(&text).unwrap();
+        let order = &bundle["components"]["schemas"]["Order"]["properties"];
+        assert_eq!(order["total"]["type"], "object");
+        assert_eq!(order["total"]["properties"]["currency"]["type"], "string");
+        assert_eq!(order["lines"]["$ref"], "#/components/schemas/Lines");
+
+        // the same file with references already pointed at the local copy
+        let rewritten = fs::read_to_string(temp.path().join("openapi/orders.yaml"))
+            .unwrap()
+            .replace("money.yaml#", "./common/money.yaml#");
+        fs::write(temp.path().join("openapi/orders.yaml"), rewritten).unwrap();
+        write_bundles(temp.path(), &config, &deps).unwrap();
+        let again: Value = serde_yaml::from_slice(
+            &fs::read(temp.path().join("openapi/orders.bundle.yaml")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(again, bundle);
+    }
+}
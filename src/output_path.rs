@@ -1,6 +1,22 @@
 // Utilities for generating output paths based on artifact metadata
 
+use anyhow::{anyhow, bail, Result};
 use convert_case::{Case, Casing};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+/// Registry-side metadata available to output patterns
+///
+/// Provides `{registry}`, `{artifactType}` and `{labels.<key>}`. A label missing
+/// from the artifact expands to an empty string.
+#[derive(Debug, Default, Clone)]
+pub struct PatternMetadata {
+    pub registry: String,
+    pub artifact_type: String,
+    pub labels: HashMap<String, String>,
+}
 
 /// Determine file extension for a given artifact type
 pub fn extension_for_type(artifact_type: &str) -> &'static str {
@@ -17,72 +33,190 @@ pub fn extension_for_type(artifact_type: &str) -> &'static str {
     }
 }
 
-/// Expand an output pattern using group/artifact/version and extension
-pub fn expand_pattern(
-    pattern: &str,
-    group_id: &str,
-    artifact_id: &str,
-    version: &str,
-    ext: &str,
-) -> String {
-    let mut result = pattern.to_string();
-    result = result.replace("{groupId}", group_id);
-    result = result.replace("{artifactId}", artifact_id);
-    result = result.replace("{version}", version);
-    result = result.replace("{ext}", ext);
+/// Filters that can be appended to any placeholder, e.g. `{artifactId.last|kebab}`
+pub const PATTERN_FILTERS: &[&str] = &[
+    "lower", "upper", "snake", "kebab", "camel", "pascal", "path",
+];
 
-    let artifact_parts: Vec<&str> = artifact_id.split('.').collect();
+fn apply_filter(value: &str, filter: &str) -> Option<String> {
+    Some(match filter {
+        "lower" => value.to_lowercase(),
+        "upper" => value.to_uppercase(),
+        "snake" => value.to_case(Case::Snake),
+        "kebab" => value.to_case(Case::Kebab),
+        "camel" => value.to_case(Case::Camel),
+        "pascal" => value.to_case(Case::Pascal),
+        "path" => value.replace('.', "/"),
+        _ => return None,
+    })
+}
 
-    if result.contains("{artifactId.path}") {
-        let path_version = if artifact_parts.len() > 1 {
-            artifact_parts[..artifact_parts.len() - 1].join("/")
-        } else {
-            String::new()
+/// Placeholder keys understood by output patterns, besides `labels.<key>` and
+/// `artifactParts[<n>]`
+pub const PATTERN_PLACEHOLDERS: &[&str] = &[
+    "groupId",
+    "groupId.path",
+    "artifactId",
+    "artifactId.path",
+    "artifactId.fullPath",
+    "artifactId.last",
+    "artifactId.lastLowercase",
+    "artifactId.lastSnakeCase",
+    "artifactId.snake_case",
+    "artifactId.kebab_case",
+    "artifactId.lowercase",
+    "version",
+    "ext",
+    "registry",
+    "artifactType",
+];
+
+fn is_known_placeholder(key: &str) -> bool {
+    PATTERN_PLACEHOLDERS.contains(&key)
+        || key.strip_prefix("labels.").is_some_and(|k| !k.is_empty())
+        || key
+            .strip_prefix("artifactParts[")
+            .and_then(|rest| rest.strip_suffix(']'))
+            .is_some_and(|index| index.parse::<usize>().is_ok())
+}
+
+/// Split a pattern into literal text and `{...}` placeholder bodies
+fn parse_pattern(pattern: &str) -> Result<Vec<(bool, &str)>> {
+    let mut segments = Vec::new();
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        if open > 0 {
+            segments.push((false, &rest[..open]));
+        }
+        let Some(close) = rest[open..].find('}') else {
+            bail!("unclosed '{{' in output pattern '{pattern}'");
         };
-        result = result.replace("{artifactId.path}", &path_version);
+        segments.push((true, &rest[open + 1..open + close]));
+        rest = &rest[open + close + 1..];
     }
-
-    if result.contains("{artifactId.fullPath}") {
-        let full_path_version = artifact_parts.join("/");
-        result = result.replace("{artifactId.fullPath}", &full_path_version);
+    if !rest.is_empty() {
+        segments.push((false, rest));
     }
+    Ok(segments)
+}
 
-    if result.contains("{artifactId.snake_case}") {
-        let snake_case = artifact_id.replace('.', "_").to_lowercase();
-        result = result.replace("{artifactId.snake_case}", &snake_case);
+/// Check that a pattern only uses known placeholders and filters
+pub fn validate_pattern(pattern: &str) -> Result<()> {
+    for (is_placeholder, body) in parse_pattern(pattern)? {
+        if !is_placeholder {
+            continue;
+        }
+        let mut parts = body.split('|').map(str::trim);
+        let key = parts.next().unwrap_or_default();
+        if !is_known_placeholder(key) {
+            bail!(
+                "unknown placeholder '{{{key}}}' in output pattern '{pattern}'; valid placeholders: {}, labels.<key>, artifactParts[<n>]",
+                PATTERN_PLACEHOLDERS.join(", ")
+            );
+        }
+        if let Some(filter) = parts.find(|f| !PATTERN_FILTERS.contains(f)) {
+            bail!(
+                "unknown filter '{filter}' in placeholder '{{{body}}}' of output pattern '{pattern}'; available filters: {}",
+                PATTERN_FILTERS.join(", ")
+            );
+        }
     }
+    Ok(())
+}
 
-    if result.contains("{artifactId.kebab_case}") {
-        let kebab_case = artifact_id.replace('.', "-").to_lowercase();
-        result = result.replace("{artifactId.kebab_case}", &kebab_case);
-    }
+/// Value of a single placeholder key (without filters), `None` if the key is unknown
+fn placeholder_value(
+    key: &str,
+    group_id: &str,
+    artifact_id: &str,
+    version: &str,
+    ext: &str,
+    metadata: &PatternMetadata,
+) -> Option<String> {
+    let artifact_parts: Vec<&str> = artifact_id.split('.').collect();
+    let last = artifact_parts.last().copied().unwrap_or(artifact_id);
 
-    if result.contains("{artifactId.lowercase}") {
-        result = result.replace("{artifactId.lowercase}", &artifact_id.to_lowercase());
+    if let Some(label) = key.strip_prefix("labels.") {
+        return Some(metadata.labels.get(label).cloned().unwrap_or_default());
     }
-
-    if result.contains("{artifactId.last}") {
-        let last_part = artifact_parts.last().unwrap_or(&artifact_id);
-        result = result.replace("{artifactId.last}", last_part);
+    if let Some(index) = key
+        .strip_prefix("artifactParts[")
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        return index
+            .parse::<usize>()
+            .ok()
+            .and_then(|i| artifact_parts.get(i))
+            .map(|part| part.to_string());
     }
 
-    if result.contains("{artifactId.lastLowercase}") {
-        let last_part = artifact_parts.last().unwrap_or(&artifact_id).to_lowercase();
-        result = result.replace("{artifactId.lastLowercase}", &last_part);
-    }
+    Some(match key {
+        "groupId" => group_id.to_string(),
+        "groupId.path" => group_id.replace('.', "/"),
+        "artifactId" => artifact_id.to_string(),
+        "version" => version.to_string(),
+        "ext" => ext.to_string(),
+        "registry" => metadata.registry.clone(),
+        "artifactType" => metadata.artifact_type.clone(),
+        "artifactId.path" if artifact_parts.len() > 1 => {
+            artifact_parts[..artifact_parts.len() - 1].join("/")
+        }
+        "artifactId.path" => String::new(),
+        "artifactId.fullPath" => artifact_parts.join("/"),
+        "artifactId.snake_case" => artifact_id.replace('.', "_").to_lowercase(),
+        "artifactId.kebab_case" => artifact_id.replace('.', "-").to_lowercase(),
+        "artifactId.lowercase" => artifact_id.to_lowercase(),
+        "artifactId.last" => last.to_string(),
+        "artifactId.lastLowercase" => last.to_lowercase(),
+        "artifactId.lastSnakeCase" => last.to_case(Case::Snake),
+        _ => return None,
+    })
+}
 
-    if result.contains("{artifactId.lastSnakeCase}") {
-        let last_part = artifact_parts.last().unwrap_or(&artifact_id);
-        let snake_case_part = last_part.to_case(Case::Snake);
-        result = result.replace("{artifactId.lastSnakeCase}", &snake_case_part);
-    }
+/// Expand an output pattern using group/artifact/version, extension and metadata
+///
+/// Placeholders are written as `{key}` or `{key|filter|...}`; filters are applied
+/// left to right. Unknown keys are kept verbatim (see [`validate_pattern`]), unknown
+/// filters are an error.
+pub fn expand_pattern(
+    pattern: &str,
+    group_id: &str,
+    artifact_id: &str,
+    version: &str,
+    ext: &str,
+    metadata: &PatternMetadata,
+) -> Result<String> {
+    let mut result = String::with_capacity(pattern.len());
+
+    for (is_placeholder, placeholder) in parse_pattern(pattern)? {
+        if !is_placeholder {
+            result.push_str(placeholder);
+            continue;
+        }
+        let mut segments = placeholder.split('|').map(str::trim);
+        let key = segments.next().unwrap_or_default();
 
-    for (i, part) in artifact_parts.iter().enumerate() {
-        let placeholder = format!("{{artifactParts[{i}]}}");
-        result = result.replace(&placeholder, part);
+        match placeholder_value(key, group_id, artifact_id, version, ext, metadata) {
+            Some(mut value) => {
+                for filter in segments {
+                    value = apply_filter(&value, filter).ok_or_else(|| {
+                        anyhow!(
+                            "unknown filter '{filter}' in placeholder '{{{placeholder}}}' of output pattern '{pattern}'; available filters: {}",
+                            PATTERN_FILTERS.join(", ")
+                        )
+                    })?;
+                }
+                result.push_str(&value);
+            }
+            None => {
+                result.push('{');
+                result.push_str(placeholder);
+                result.push('}');
+            }
+        }
     }
 
-    result
+    Ok(result)
 }
 
 /// Generate an output path using an output pattern
@@ -91,8 +225,136 @@ pub fn generate_output_path(
     group_id: &str,
     artifact_id: &str,
     version: &str,
-    artifact_type: &str,
-) -> String {
-    let ext = extension_for_type(artifact_type);
-    expand_pattern(pattern, group_id, artifact_id, version, ext)
+    metadata: &PatternMetadata,
+) -> Result<String> {
+    let ext = extension_for_type(&metadata.artifact_type);
+    expand_pattern(pattern, group_id, artifact_id, version, ext, metadata)
+}
+
+/// Lock-file form of an output path: `/`-separated on every platform, so a
+/// pattern written with `\` on Windows locks the same path as on Unix
+pub fn portable(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+/// The file an output path (`/`-separated, see [`portable`]) refers to below
+/// `root`, with the separators of the current platform
+pub fn native(root: &Path, path: &str) -> PathBuf {
+    let path = portable(path);
+    if Path::new(&path).is_absolute() {
+        return PathBuf::from(path);
+    }
+    path.split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .fold(root.to_path_buf(), |native, segment| native.join(segment))
+}
+
+/// Longest path the platform reliably accepts (`MAX_PATH` on Windows without
+/// long path support, `PATH_MAX` elsewhere)
+pub const MAX_PATH_LEN: usize = if cfg!(windows) { 260 } else { 4096 };
+/// Longest file or directory name most filesystems accept
+pub const MAX_NAME_LEN: usize = 255;
+
+/// Whether `path` stays inside `root` once `.` and `..` are resolved
+///
+/// Placeholders filled from registry metadata (such as `{groupId.path}`) can
+/// otherwise walk out of the project with `..` segments or a leading `/`.
+pub fn within_root(root: &Path, path: &str) -> bool {
+    let target = native(root, path);
+    let mut resolved = PathBuf::new();
+    for component in target.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                if !resolved.pop() {
+                    return false;
+                }
+            }
+            other => resolved.push(other),
+        }
+    }
+    let root: PathBuf = root
+        .components()
+        .filter(|c| *c != std::path::Component::CurDir)
+        .collect();
+    resolved.starts_with(&root) && resolved != root
+}
+
+/// Why the file an output path refers to below `root` could not be created on
+/// this platform, if its full path or one of its names is too long
+pub fn length_problem(root: &Path, path: &str) -> Option<String> {
+    let target = native(root, path);
+    let length = target.as_os_str().len();
+    if length > MAX_PATH_LEN {
+        return Some(format!(
+            "path is {length} characters long, over the {MAX_PATH_LEN} allowed on this platform"
+        ));
+    }
+    target
+        .components()
+        .map(|c| c.as_os_str().len())
+        .find(|len| *len > MAX_NAME_LEN)
+        .map(|len| {
+            format!("a name in the path is {len} characters long, over the {MAX_NAME_LEN} allowed")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_portable_and_native_paths() {
+        assert_eq!(portable("protos\\common\\a.proto"), "protos/common/a.proto");
+        assert_eq!(
+            native(Path::new("project"), "./protos//a.proto"),
+            Path::new("project").join("protos").join("a.proto")
+        );
+        assert_eq!(native(Path::new(""), "a.avsc"), PathBuf::from("a.avsc"));
+    }
+
+    #[test]
+    fn test_paths_escaping_the_root() {
+        let root = Path::new("/work/project");
+        assert!(within_root(root, "protos/a.proto"));
+        assert!(within_root(root, "protos/../schemas/a.avsc"));
+        assert!(within_root(root, "/work/project/protos/a.proto"));
+        assert!(!within_root(root, "../../etc/passwd"));
+        assert!(!within_root(root, "protos/../../sibling/a.proto"));
+        assert!(!within_root(root, "/etc/a.proto"));
+        assert!(!within_root(root, "."));
+        assert!(within_root(Path::new(""), "a.proto"));
+        assert!(!within_root(Path::new(""), "../a.proto"));
+    }
+
+    #[test]
+    fn test_length_problems() {
+        let root = Path::new("project");
+        assert_eq!(length_problem(root, "protos/a.proto"), None);
+        let long_name = format!("protos/{}.proto", "a".repeat(MAX_NAME_LEN));
+        assert!(length_problem(root, &long_name)
+            .unwrap()
+            .contains("a name in the path"));
+        let long_path = "abcdefghi/".repeat(MAX_PATH_LEN / 10 + 1) + "a.proto";
+        assert!(length_problem(root, &long_path)
+            .unwrap()
+            .starts_with("path is"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_native_paths_use_backslashes() {
+        assert_eq!(
+            native(Path::new(r"C:\project"), "protos/common/a.proto"),
+            PathBuf::from(r"C:\project\protos\common\a.proto")
+        );
+        assert_eq!(
+            native(Path::new("ignored"), "C:/schemas/a.avsc"),
+            PathBuf::from(r"C:/schemas/a.avsc")
+        );
+        assert_eq!(
+            native(Path::new(""), "protos/a.proto").to_str(),
+            Some(r"protos\a.proto")
+        );
+    }
 }
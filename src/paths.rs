@@ -0,0 +1,205 @@
+//! Global locations used by the CLI
+//!
+//! Each directory follows the platform convention (XDG base directories on
+//! Linux, `%APPDATA%`/`%LOCALAPPDATA%` on Windows, `~/Library` on macOS) and can
+//! be moved with an environment variable, e.g. for hermetic builds where the
+//! home directory is not writable:
+//!
+//! | Directory | Override | Linux default |
+//! |-----------|----------|---------------|
+//! | config | `APICURIO_CONFIG_DIR` | `$XDG_CONFIG_HOME/apicurio` (`~/.config/apicurio`) |
+//! | cache | `APICURIO_CACHE_DIR` | `$XDG_CACHE_HOME/apicurio` (`~/.cache/apicurio`) |
+//! | state | `APICURIO_STATE_DIR` | `$XDG_STATE_HOME/apicurio` (`~/.local/state/apicurio`) |
+//!
+//! The global registries file is `registries.yaml` in the config directory,
+//! unless `APICURIO_REGISTRIES_PATH` names another file.
+//!
+//! The project config is `apicurioconfig.yaml` (or `apicurioconfig.json`, then
+//! `apicurioconfig.toml`, when only that exists) in the current directory unless
+//! `--config` or `APICURIO_CONFIG_PATH` names another file; the lock file always
+//! sits next to it, as `apicuriolock.json` for a JSON config, and output paths
+//! are relative to its directory.
+
+use crate::{
+    config::RunSettings,
+    constants::{
+        APICURIO_CONFIG, APICURIO_CONFIG_JSON, APICURIO_CONFIG_TOML, APICURIO_LOCK,
+        APICURIO_LOCK_JSON,
+    },
+};
+use serde::Serialize;
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+pub const CONFIG_DIR_ENV: &str = "APICURIO_CONFIG_DIR";
+pub const CACHE_DIR_ENV: &str = "APICURIO_CACHE_DIR";
+pub const STATE_DIR_ENV: &str = "APICURIO_STATE_DIR";
+pub const REGISTRIES_PATH_ENV: &str = "APICURIO_REGISTRIES_PATH";
+pub const PROJECT_CONFIG_ENV: &str = "APICURIO_CONFIG_PATH";
+
+/// A location and what decided it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EffectivePath {
+    pub name: &'static str,
+    pub path: PathBuf,
+    /// Environment variable the path was taken from, `None` for the platform default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<&'static str>,
+}
+
+/// `var` if set to a non-empty value, otherwise `<platform dir>/apicurio`
+fn resolve(
+    name: &'static str,
+    var: &'static str,
+    platform: Option<PathBuf>,
+    windows_var: &str,
+) -> EffectivePath {
+    if let Some(path) = env::var_os(var).filter(|v| !v.is_empty()) {
+        return EffectivePath {
+            name,
+            path: PathBuf::from(path),
+            env: Some(var),
+        };
+    }
+    let base = platform
+        .or_else(|| env::var_os(windows_var).map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("."));
+    EffectivePath {
+        name,
+        path: base.join("apicurio"),
+        env: None,
+    }
+}
+
+/// Directory of global configuration such as the registries file
+pub fn config_dir() -> EffectivePath {
+    resolve("configDir", CONFIG_DIR_ENV, dirs::config_dir(), "APPDATA")
+}
+
+/// Directory for data that can be recreated, such as downloaded artifacts
+pub fn cache_dir() -> EffectivePath {
+    resolve("cacheDir", CACHE_DIR_ENV, dirs::cache_dir(), "LOCALAPPDATA")
+}
+
+/// Directory for data that should survive between runs but is not configuration
+pub fn state_dir() -> EffectivePath {
+    resolve(
+        "stateDir",
+        STATE_DIR_ENV,
+        dirs::state_dir().or_else(dirs::data_local_dir),
+        "LOCALAPPDATA",
+    )
+}
+
+/// The global registries file
+pub fn registries_file() -> EffectivePath {
+    match env::var_os(REGISTRIES_PATH_ENV).filter(|v| !v.is_empty()) {
+        Some(path) => EffectivePath {
+            name: "registriesFile",
+            path: PathBuf::from(path),
+            env: Some(REGISTRIES_PATH_ENV),
+        },
+        None => {
+            let dir = config_dir();
+            EffectivePath {
+                name: "registriesFile",
+                path: dir.path.join("registries.yaml"),
+                env: dir.env,
+            }
+        }
+    }
+}
+
+/// The project config file, relative to the current directory unless the
+/// override is absolute
+pub fn project_config() -> PathBuf {
+    config_override().unwrap_or_else(|| config_in(Path::new("")))
+}
+
+/// The project config given with `--config`, else `APICURIO_CONFIG_PATH`
+fn config_override() -> Option<PathBuf> {
+    RunSettings::current().config_path.clone().or_else(|| {
+        env::var_os(PROJECT_CONFIG_ENV)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// The config of the project in `dir`: the first of the YAML, JSON and TOML
+/// configs that exists (YAML when none does)
+pub fn config_in(dir: &Path) -> PathBuf {
+    [APICURIO_CONFIG, APICURIO_CONFIG_JSON, APICURIO_CONFIG_TOML]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| dir.join(APICURIO_CONFIG))
+}
+
+/// Whether `--config`/`APICURIO_CONFIG_PATH` moved the project config
+pub fn project_config_overridden() -> bool {
+    config_override().is_some()
+}
+
+/// Directory of the project config, which output paths, `.gitignore` and the
+/// staging directory are relative to
+pub fn project_root() -> PathBuf {
+    project_config()
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// The lock file of the project config
+pub fn project_lock() -> PathBuf {
+    lock_beside(&project_config())
+}
+
+/// `apicuriolock.yaml` in the directory of `config` (`apicuriolock.json` for a
+/// JSON config)
+pub fn lock_beside(config: &Path) -> PathBuf {
+    if crate::config::is_json_file(config) {
+        config.with_file_name(APICURIO_LOCK_JSON)
+    } else {
+        config.with_file_name(APICURIO_LOCK)
+    }
+}
+
+/// Every global location, in the order `apicurio env` prints them
+pub fn effective() -> Vec<EffectivePath> {
+    vec![config_dir(), registries_file(), cache_dir(), state_dir()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_overrides() {
+        env::set_var(CACHE_DIR_ENV, "/tmp/hermetic/cache");
+        let cache = cache_dir();
+        assert_eq!(cache.path, PathBuf::from("/tmp/hermetic/cache"));
+        assert_eq!(cache.env, Some(CACHE_DIR_ENV));
+
+        env::set_var(STATE_DIR_ENV, "");
+        let state = state_dir();
+        assert_eq!(state.env, None);
+        assert!(state.path.ends_with("apicurio"));
+        env::remove_var(CACHE_DIR_ENV);
+        env::remove_var(STATE_DIR_ENV);
+
+        assert_eq!(
+            lock_beside(Path::new(APICURIO_CONFIG)),
+            PathBuf::from(APICURIO_LOCK)
+        );
+        assert_eq!(
+            lock_beside(Path::new("services/users/apicurio.yaml")),
+            PathBuf::from("services/users/apicuriolock.yaml")
+        );
+        assert_eq!(
+            lock_beside(Path::new(APICURIO_CONFIG_JSON)),
+            PathBuf::from(APICURIO_LOCK_JSON)
+        );
+    }
+}
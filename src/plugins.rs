@@ -0,0 +1,339 @@
+//! External plugins
+//!
+//! A plugin is an executable named `apicurio-plugin-<name>` found on `PATH`.
+//! The CLI runs it once per call, writes a JSON request to its stdin and reads a
+//! JSON response from its stdout; stderr is passed through to the terminal. Every
+//! request carries `protocolVersion` and `hook`:
+//!
+//! | Hook | Request | Response |
+//! |------|---------|----------|
+//! | `describe` | - | `{ "description", "hooks": [...] }` |
+//! | `lint` | `files: [{ path, kind, content }]` | `{ "findings": [{ path, rule, severity, message, line }] }` |
+//! | `publish` | `artifact: { name, registry, groupId, artifactId, version, path, binary, content }` (base64 if `binary`) | `{ "allow": bool, "message" }` |
+//! | `generate` | `root`, `dependencies` (lock entries) | `{ "files": [{ path, content }], "messages": [...] }` |
+//!
+//! Plugins listed under `plugins:` in `apicurioconfig.yaml` take part in `lint`
+//! and `publish` automatically; `apicurio plugin run <name>` invokes `generate`.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    env, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::lint::{LintReport, Linter, Severity};
+
+/// Prefix of plugin executables
+pub const PLUGIN_PREFIX: &str = "apicurio-plugin-";
+
+/// Version of the JSON protocol sent in every request
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A plugin executable
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plugin {
+    /// Name without the `apicurio-plugin-` prefix
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Answer to the `describe` hook
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct PluginInfo {
+    pub description: Option<String>,
+    /// Hooks the plugin handles (`lint`, `publish`, `generate`)
+    pub hooks: Vec<String>,
+}
+
+/// A finding reported by a plugin's `lint` hook
+#[derive(Deserialize, Debug, Clone)]
+pub struct PluginFinding {
+    pub path: String,
+    pub rule: String,
+    #[serde(default = "default_plugin_severity")]
+    pub severity: Severity,
+    pub message: String,
+    #[serde(default)]
+    pub line: Option<usize>,
+}
+
+fn default_plugin_severity() -> Severity {
+    Severity::Warning
+}
+
+#[derive(Deserialize)]
+struct LintResponse {
+    #[serde(default)]
+    findings: Vec<PluginFinding>,
+}
+
+/// Answer to the `publish` hook
+#[derive(Deserialize, Debug, Clone)]
+pub struct GateDecision {
+    pub allow: bool,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// A file produced by a plugin's `generate` hook
+#[derive(Deserialize, Debug, Clone)]
+pub struct GeneratedFile {
+    pub path: String,
+    pub content: String,
+}
+
+/// Answer to the `generate` hook
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct GenerateResponse {
+    pub files: Vec<GeneratedFile>,
+    pub messages: Vec<String>,
+}
+
+impl Plugin {
+    /// Run a hook, sending `request` (extended with `protocolVersion` and `hook`)
+    pub fn call<T: DeserializeOwned>(&self, hook: &str, mut request: Value) -> Result<T> {
+        request["protocolVersion"] = json!(PROTOCOL_VERSION);
+        request["hook"] = json!(hook);
+
+        let mut child = Command::new(&self.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("starting plugin {}", self.path.display()))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // A plugin may exit without reading its input
+            let _ = stdin.write_all(request.to_string().as_bytes());
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            bail!(
+                "plugin '{}' failed on hook '{hook}' ({})",
+                self.name,
+                output.status
+            );
+        }
+        serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("invalid response from plugin '{}' to '{hook}'", self.name))
+    }
+
+    pub fn describe(&self) -> Result<PluginInfo> {
+        self.call("describe", json!({}))
+    }
+
+    /// Whether the plugin handles `hook`, according to `describe`
+    pub fn supports(&self, hook: &str) -> Result<bool> {
+        Ok(self.describe()?.hooks.iter().any(|h| h == hook))
+    }
+}
+
+/// Find plugins in the directories of `PATH`
+pub fn discover() -> Vec<Plugin> {
+    let paths = env::var_os("PATH").unwrap_or_default();
+    discover_in(env::split_paths(&paths))
+}
+
+/// Find plugins in `dirs`; the first directory providing a name wins
+pub fn discover_in(dirs: impl IntoIterator<Item = PathBuf>) -> Vec<Plugin> {
+    let mut plugins: Vec<Plugin> = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut found: Vec<Plugin> = entries
+            .flatten()
+            .filter(|e| is_executable(&e.path()))
+            .filter_map(|e| {
+                let file_name = e.file_name().into_string().ok()?;
+                let name = file_name
+                    .strip_prefix(PLUGIN_PREFIX)?
+                    .trim_end_matches(".exe")
+                    .to_string();
+                Some(Plugin {
+                    name,
+                    path: e.path(),
+                })
+            })
+            .filter(|p| !p.name.is_empty() && !plugins.iter().any(|q| q.name == p.name))
+            .collect();
+        found.sort_by(|a, b| a.name.cmp(&b.name));
+        plugins.extend(found);
+    }
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    plugins
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("exe"))
+}
+
+/// Look up a plugin by name on `PATH`
+pub fn find(name: &str) -> Result<Plugin> {
+    discover()
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| anyhow!("plugin '{name}' not found: no {PLUGIN_PREFIX}{name} on PATH"))
+}
+
+/// The plugins enabled under `plugins:`, failing if one is not installed
+pub fn enabled(names: &[String]) -> Result<Vec<Plugin>> {
+    names.iter().map(|name| find(name)).collect()
+}
+
+/// Run the `lint` hook of every plugin handling it and add their findings to
+/// the reports of the linted `files` (path and content)
+pub fn lint(
+    plugins: &[Plugin],
+    linter: &Linter,
+    files: &[(String, String)],
+    report: &mut LintReport,
+) -> Result<()> {
+    let request_files: Vec<Value> = files
+        .iter()
+        .map(|(path, content)| {
+            let kind = report
+                .files
+                .iter()
+                .find(|f| &f.path == path)
+                .and_then(|f| f.kind);
+            json!({ "path": path, "kind": kind, "content": content })
+        })
+        .collect();
+    for plugin in plugins {
+        if !plugin.supports("lint")? {
+            continue;
+        }
+        let response: LintResponse = plugin.call("lint", json!({ "files": request_files }))?;
+        for finding in response.findings {
+            let Some(file) = report.files.iter_mut().find(|f| f.path == finding.path) else {
+                continue;
+            };
+            file.findings.extend(linter.plugin_finding(
+                &plugin.name,
+                &finding.rule,
+                finding.severity,
+                finding.message,
+                finding.line,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Ask every plugin handling `publish` whether `artifact` may be published
+pub fn check_publish(plugins: &[Plugin], artifact: &Value) -> Result<()> {
+    for plugin in plugins {
+        if !plugin.supports("publish")? {
+            continue;
+        }
+        let decision: GateDecision = plugin.call("publish", json!({ "artifact": artifact }))?;
+        if !decision.allow {
+            bail!(
+                "publish of {} blocked by plugin '{}': {}",
+                artifact["name"].as_str().unwrap_or_default(),
+                plugin.name,
+                decision.message.as_deref().unwrap_or("no reason given")
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn write_plugin(dir: &Path, name: &str, script: &str) -> PathBuf {
+        let path = dir.join(format!("{PLUGIN_PREFIX}{name}"));
+        fs::write(&path, format!("#!/bin/sh\n{script}")).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_discover_prefers_first_directory() {
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let path = write_plugin(first.path(), "naming", "exit 0\n");
+        write_plugin(second.path(), "naming", "exit 0\n");
+        write_plugin(second.path(), "docs", "exit 0\n");
+        // Not executable, so not a plugin
+        fs::write(second.path().join("apicurio-plugin-data"), "").unwrap();
+
+        let plugins = discover_in([first.path().to_path_buf(), second.path().to_path_buf()]);
+        let names: Vec<&str> = plugins.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["docs", "naming"]);
+        assert_eq!(plugins[1].path, path);
+    }
+
+    #[test]
+    fn test_lint_hook_adds_findings() {
+        let dir = TempDir::new().unwrap();
+        let script = r#"input=$(cat)
+case "$input" in
+  *'"hook":"describe"'*) echo '{"description":"naming rules","hooks":["lint"]}' ;;
+  *) echo '{"findings":[{"path":"a.proto","rule":"no-todo","severity":"error","message":"TODO left"},{"path":"other.proto","rule":"x","message":"ignored"}]}' ;;
+esac
+"#;
+        let path = write_plugin(dir.path(), "naming", script);
+        let plugin = Plugin {
+            name: "naming".to_string(),
+            path,
+        };
+        assert_eq!(plugin.describe().unwrap().hooks, ["lint"]);
+
+        let linter = Linter::default();
+        let content = "syntax = \"proto3\";\npackage a;\n// TODO\n".to_string();
+        let mut report = LintReport {
+            files: vec![linter.lint("a.proto", &content, None)],
+        };
+        lint(
+            &[plugin],
+            &linter,
+            &[("a.proto".to_string(), content)],
+            &mut report,
+        )
+        .unwrap();
+        let finding = &report.files[0].findings[0];
+        assert_eq!(finding.rule, "naming/no-todo");
+        assert_eq!(finding.severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_publish_gate_blocks() {
+        let dir = TempDir::new().unwrap();
+        let script = r#"input=$(cat)
+case "$input" in
+  *'"hook":"describe"'*) echo '{"hooks":["publish"]}' ;;
+  *) echo '{"allow":false,"message":"missing owner label"}' ;;
+esac
+"#;
+        let plugin = Plugin {
+            name: "gate".to_string(),
+            path: write_plugin(dir.path(), "gate", script),
+        };
+        let err = check_publish(&[plugin], &json!({ "name": "users" })).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "publish of users blocked by plugin 'gate': missing owner label"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Advisory lock keeping concurrent `apicurio` processes out of each other's way
+//!
+//! Commands that write the lock file or output files (`pull`, `update`, `lock`, ...)
+//! hold an exclusive lock on `.apicurio.lock` next to the project config, so two
+//! CI jobs sharing a workspace run one after the other instead of interleaving
+//! their writes. The lock is released when the [`ProcessLock`] is dropped, or by
+//! the operating system when the process dies.
+
+use anyhow::{bail, Context, Result};
+use std::{
+    fs::{File, OpenOptions, TryLockError},
+    io::{Read, Seek, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::RunSettings,
+    constants::{APICURIO_PROCESS_LOCK, DEFAULT_PROCESS_LOCK_TIMEOUT_SECS},
+};
+
+/// Environment variable overriding how long to wait for the lock, in seconds
+pub const LOCK_TIMEOUT_ENV: &str = "APICURIO_LOCK_TIMEOUT";
+
+/// How often a waiting process checks the lock again
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An exclusive lock on a project, held until dropped
+#[derive(Debug)]
+pub struct ProcessLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl ProcessLock {
+    /// The lock file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// How long to wait for the lock: `--lock-timeout`, else `APICURIO_LOCK_TIMEOUT`,
+/// else the default of five minutes
+pub fn timeout() -> Duration {
+    let secs = RunSettings::current()
+        .lock_timeout
+        .or_else(|| {
+            std::env::var(LOCK_TIMEOUT_ENV)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        })
+        .unwrap_or(DEFAULT_PROCESS_LOCK_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Lock the project in `dir`, waiting up to `timeout` for another process to
+/// release it; `waiting` is called once if the lock is taken
+pub fn acquire(dir: &Path, timeout: Duration, waiting: impl FnOnce(&str)) -> Result<ProcessLock> {
+    let path = dir.join(APICURIO_PROCESS_LOCK);
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .with_context(|| format!("opening {}", path.display()))?;
+    let started = Instant::now();
+    let mut waiting = Some(waiting);
+    loop {
+        match file.try_lock() {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) => {
+                let holder = holder(&mut file);
+                if started.elapsed() >= timeout {
+                    bail!(
+                        "another apicurio process ({holder}) still holds {} after {}s; \
+                         wait for it or raise --lock-timeout",
+                        path.display(),
+                        timeout.as_secs()
+                    );
+                }
+                if let Some(waiting) = waiting.take() {
+                    waiting(&holder);
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("locking {}", path.display()))
+            }
+        }
+    }
+    // Tell waiting processes who they are waiting for
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "pid {}", std::process::id())?;
+    Ok(ProcessLock { path, _file: file })
+}
+
+/// Who holds the lock, as written by [`acquire`]
+fn holder(file: &mut File) -> String {
+    let mut content = String::new();
+    let _ = file
+        .rewind()
+        .and_then(|_| file.read_to_string(&mut content));
+    match content.trim() {
+        "" => "unknown pid".to_string(),
+        holder => holder.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_second_lock_waits_then_times_out() {
+        let temp = TempDir::new().unwrap();
+        let held = acquire(temp.path(), Duration::ZERO, |_| {}).unwrap();
+        assert_eq!(held.path(), temp.path().join(APICURIO_PROCESS_LOCK));
+
+        let mut waited_for = None;
+        let err = acquire(temp.path(), Duration::from_millis(250), |holder| {
+            waited_for = Some(holder.to_string())
+        })
+        .unwrap_err();
+        assert_eq!(
+            waited_for.as_deref(),
+            Some(format!("pid {}", std::process::id()).as_str())
+        );
+        assert!(err.to_string().contains("still holds"), "{err}");
+
+        drop(held);
+        assert!(acquire(temp.path(), Duration::ZERO, |_| {}).is_ok());
+    }
+}
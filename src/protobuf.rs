@@ -0,0 +1,171 @@
+//! Descriptor sets for pulled Protobuf dependencies
+//!
+//! With `protobuf.descriptorSet: true`, `pull` compiles every locked `.proto`
+//! file with the embedded [protox](https://docs.rs/protox) compiler and writes a
+//! `FileDescriptorSet`, so downstream tooling (gRPC reflection, code generators,
+//! `buf`) does not need `protoc` installed. Imports are resolved against
+//! `protobuf.includePaths` and the well-known `google/protobuf/*` types.
+
+use anyhow::{anyhow, Context, Result};
+use std::{fs, path::Path};
+
+use crate::{config::ProtobufConfig, lockfile::LockedDependency, output_path};
+
+/// Output path of the descriptor set unless `descriptorSetPath` is set
+pub const DEFAULT_DESCRIPTOR_SET_PATH: &str = "descriptors.binpb";
+
+/// Import root unless `includePaths` is set, matching the default output pattern
+pub const DEFAULT_INCLUDE_PATH: &str = "protos";
+
+/// A descriptor set written after pulling
+#[derive(Debug, Clone, PartialEq)]
+pub struct DescriptorSet {
+    /// Path relative to the project directory
+    pub path: String,
+    /// Number of pulled `.proto` files compiled into it (imports not counted)
+    pub files: usize,
+}
+
+/// Compile the `.proto` files among `dependencies` and write their descriptor set
+///
+/// Output paths are relative to `root`. Returns `None` when descriptor sets are
+/// not enabled or no Protobuf file is locked.
+pub fn write_descriptor_set(
+    root: &Path,
+    config: &ProtobufConfig,
+    dependencies: &[LockedDependency],
+) -> Result<Option<DescriptorSet>> {
+    if !config.descriptor_set {
+        return Ok(None);
+    }
+    let mut files: Vec<&str> = dependencies
+        .iter()
+        .map(|d| d.output_path.as_str())
+        .filter(|path| path.ends_with(".proto"))
+        .collect();
+    files.sort_unstable();
+    files.dedup();
+    if files.is_empty() {
+        return Ok(None);
+    }
+
+    let includes: Vec<_> = if config.include_paths.is_empty() {
+        vec![root.join(DEFAULT_INCLUDE_PATH)]
+    } else {
+        config.include_paths.iter().map(|p| root.join(p)).collect()
+    };
+    let mut compiler = protox::Compiler::new(&includes)?;
+    compiler
+        .include_imports(true)
+        .include_source_info(config.include_source_info);
+    for file in &files {
+        compiler.open_file(output_path::native(root, file)).map_err(|e| {
+            anyhow!("compiling {file} into a descriptor set: {e} (is it under one of protobuf.includePaths?)")
+        })?;
+    }
+
+    let path = config
+        .descriptor_set_path
+        .clone()
+        .unwrap_or_else(|| DEFAULT_DESCRIPTOR_SET_PATH.to_string());
+    let target = output_path::native(root, &path);
+    if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&target, compiler.encode_file_descriptor_set())
+        .with_context(|| format!("writing {}", target.display()))?;
+    Ok(Some(DescriptorSet {
+        path,
+        files: files.len(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn locked(output_path: &str) -> LockedDependency {
+        LockedDependency {
+            name: output_path.to_string(),
+            registry: "main".to_string(),
+            resolved_version: "1.0.0".to_string(),
+            download_url: String::new(),
+            registry_url: None,
+            sha256: String::new(),
+            output_path: output_path.to_string(),
+            group_id: "default".to_string(),
+            artifact_id: output_path.to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
+        }
+    }
+
+    #[test]
+    fn test_descriptor_set_covers_pulled_protos() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("protos/common")).unwrap();
+        fs::write(
+            temp.path().join("protos/common/money.proto"),
+            "syntax = \"proto3\";\npackage common;\nmessage Money { int64 units = 1; }\n",
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("protos/orders.proto"),
+            "syntax = \"proto3\";\npackage orders;\nimport \"common/money.proto\";\n\
+             import \"google/protobuf/timestamp.proto\";\n\
+             message Order { common.Money total = 1; google.protobuf.Timestamp at = 2; }\n",
+        )
+        .unwrap();
+        let config = ProtobufConfig {
+            descriptor_set: true,
+            descriptor_set_path: Some("build/descriptors.binpb".to_string()),
+            ..Default::default()
+        };
+        let deps = [
+            locked("protos/orders.proto"),
+            locked("protos/common/money.proto"),
+            locked("schemas/user.avsc"),
+        ];
+
+        let written = write_descriptor_set(temp.path(), &config, &deps)
+            .unwrap()
+            .unwrap();
+        assert_eq!(written.path, "build/descriptors.binpb");
+        assert_eq!(written.files, 2);
+        let data = fs::read(temp.path().join("build/descriptors.binpb")).unwrap();
+        // Every file name ends up in the encoded set, imports included
+        for name in [
+            "orders.proto",
+            "common/money.proto",
+            "google/protobuf/timestamp.proto",
+        ] {
+            assert!(data.windows(name.len()).any(|w| w == name.as_bytes()));
+        }
+
+        let disabled = ProtobufConfig::default();
+        assert!(write_descriptor_set(temp.path(), &disabled, &deps)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_file_outside_include_paths_is_reported() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("vendor")).unwrap();
+        fs::write(temp.path().join("vendor/a.proto"), "syntax = \"proto3\";\n").unwrap();
+        let config = ProtobufConfig {
+            descriptor_set: true,
+            ..Default::default()
+        };
+        let err =
+            write_descriptor_set(temp.path(), &config, &[locked("vendor/a.proto")]).unwrap_err();
+        assert!(err.to_string().contains("protobuf.includePaths"));
+    }
+}
@@ -0,0 +1,203 @@
+//! Provenance headers for pulled artifacts
+//!
+//! When enabled through `provenanceHeader` in the repository configuration, text
+//! artifacts are written with a short "do not edit" comment naming where they came
+//! from. The lock file keeps the hash of the registry content, so `verify
+//! --ignore-header` strips the header again before comparing.
+
+use crate::{config::ProvenanceHeaderConfig, lockfile::LockedDependency};
+use std::path::Path;
+
+/// Text identifying a provenance header written by this tool
+pub const HEADER_MARKER: &str = "Generated by apicurio-cli - DO NOT EDIT";
+
+/// Number of lines a provenance header takes
+const HEADER_LINES: usize = 2;
+
+/// Comment syntax used for the header, chosen from the output file extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CommentStyle {
+    /// `// ...` (Protobuf)
+    Slashes,
+    /// `# ...` (YAML, GraphQL)
+    Hash,
+    /// `<!-- ... -->` (XML, XSD, WSDL)
+    Xml,
+}
+
+fn comment_style(path: &str) -> Option<CommentStyle> {
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())?
+        .to_lowercase();
+    match ext.as_str() {
+        "proto" => Some(CommentStyle::Slashes),
+        "yaml" | "yml" | "graphql" | "gql" => Some(CommentStyle::Hash),
+        "xml" | "xsd" | "wsdl" => Some(CommentStyle::Xml),
+        _ => None,
+    }
+}
+
+fn comment(style: CommentStyle, text: &str) -> String {
+    match style {
+        CommentStyle::Slashes => format!("// {text}\n"),
+        CommentStyle::Hash => format!("# {text}\n"),
+        CommentStyle::Xml => format!("<!-- {text} -->\n"),
+    }
+}
+
+/// Length of the leading `<?xml ...?>` declaration line, which must stay first
+fn xml_declaration_len(data: &[u8]) -> Option<usize> {
+    if !data.starts_with(b"<?xml") {
+        return None;
+    }
+    data.iter().position(|&b| b == b'\n').map(|i| i + 1)
+}
+
+/// Render the header lines for a locked dependency
+pub fn render_header(dependency: &LockedDependency) -> Option<String> {
+    let style = comment_style(&dependency.output_path)?;
+    let source = format!(
+        "Source: {}/{}/{}@{} sha256={}",
+        dependency.registry,
+        dependency.group_id,
+        dependency.artifact_id,
+        dependency.resolved_version,
+        dependency.sha256
+    );
+    Some(comment(style, HEADER_MARKER) + &comment(style, &source))
+}
+
+/// Prepend the provenance header to downloaded content if configured for its type
+///
+/// Content that cannot carry a comment safely (binary artifacts, unknown extension, or an XML
+/// declaration without a line break) is returned unchanged.
+pub fn apply_header(
+    config: &ProvenanceHeaderConfig,
+    dependency: &LockedDependency,
+    data: &[u8],
+) -> Vec<u8> {
+    if dependency.binary || !config.enabled_for_path(&dependency.output_path) {
+        return data.to_vec();
+    }
+    let Some(header) = render_header(dependency) else {
+        return data.to_vec();
+    };
+    let insert_at = match comment_style(&dependency.output_path) {
+        Some(CommentStyle::Xml) if data.starts_with(b"<?xml") => match xml_declaration_len(data) {
+            Some(len) => len,
+            None => return data.to_vec(),
+        },
+        _ => 0,
+    };
+    let mut out = Vec::with_capacity(data.len() + header.len());
+    out.extend_from_slice(&data[..insert_at]);
+    out.extend_from_slice(header.as_bytes());
+    out.extend_from_slice(&data[insert_at..]);
+    out
+}
+
+/// Remove a provenance header written by [`apply_header`], if present
+pub fn strip_header(data: &[u8]) -> Vec<u8> {
+    let start = xml_declaration_len(data).unwrap_or(0);
+    let mut end = start;
+    for n in 0..HEADER_LINES {
+        let rest = &data[end..];
+        let Some(len) = rest.iter().position(|&b| b == b'\n').map(|i| i + 1) else {
+            return data.to_vec();
+        };
+        let line = String::from_utf8_lossy(&rest[..len]);
+        if n == 0 && !line.contains(HEADER_MARKER) {
+            return data.to_vec();
+        }
+        end += len;
+    }
+    let mut out = Vec::with_capacity(data.len() - (end - start));
+    out.extend_from_slice(&data[..start]);
+    out.extend_from_slice(&data[end..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependency(output_path: &str) -> LockedDependency {
+        LockedDependency {
+            name: "users".to_string(),
+            registry: "main".to_string(),
+            resolved_version: "1.2.0".to_string(),
+            download_url: String::new(),
+            registry_url: None,
+            sha256: "abc123".to_string(),
+            output_path: output_path.to_string(),
+            group_id: "com.example".to_string(),
+            artifact_id: "users".to_string(),
+            version_spec: "^1".to_string(),
+            is_transitive: false,
+            references: Vec::new(),
+            reference_names: Default::default(),
+            binary: false,
+            avro_fingerprint: None,
+            content_type: None,
+            member_of: None,
+        }
+    }
+
+    fn all_enabled() -> ProvenanceHeaderConfig {
+        ProvenanceHeaderConfig {
+            protobuf: true,
+            openapi: true,
+            asyncapi: true,
+            graphql: true,
+            xml: true,
+            wsdl: true,
+        }
+    }
+
+    #[test]
+    fn test_apply_and_strip_round_trip() {
+        let content = b"syntax = \"proto3\";\n";
+        let written = apply_header(&all_enabled(), &dependency("protos/users.proto"), content);
+        let text = String::from_utf8(written.clone()).unwrap();
+        assert!(text.starts_with(&format!("// {HEADER_MARKER}\n")));
+        assert!(text.contains("// Source: main/com.example/users@1.2.0 sha256=abc123\n"));
+        assert_eq!(strip_header(&written), content);
+
+        // Files without a header are left alone
+        assert_eq!(strip_header(content), content);
+    }
+
+    #[test]
+    fn test_xml_header_follows_declaration() {
+        let content = b"<?xml version=\"1.0\"?>\n<schema/>\n";
+        let written = apply_header(&all_enabled(), &dependency("schemas/users.xsd"), content);
+        let text = String::from_utf8(written.clone()).unwrap();
+        assert!(text.starts_with("<?xml version=\"1.0\"?>\n<!-- "));
+        assert_eq!(strip_header(&written), content);
+    }
+
+    #[test]
+    fn test_header_only_for_enabled_types() {
+        let content = b"{\"type\": \"record\"}";
+        let config = ProvenanceHeaderConfig {
+            protobuf: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            apply_header(&config, &dependency("schemas/users.avsc"), content),
+            content
+        );
+        assert_eq!(
+            apply_header(&config, &dependency("api/users.yaml"), b"openapi: 3.0.0\n"),
+            b"openapi: 3.0.0\n"
+        );
+
+        let mut binary = dependency("protos/users.proto");
+        binary.binary = true;
+        assert_eq!(
+            apply_header(&all_enabled(), &binary, b"\x0a\x05"),
+            b"\x0a\x05"
+        );
+    }
+}
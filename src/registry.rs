@@ -1,13 +1,137 @@
-use crate::config::{AuthConfig, IfExistsAction, PublishConfig, RegistryConfig};
-use anyhow::Result;
+use crate::error::{ApicurioError, Result};
+use crate::{
+    config::{AuthConfig, IfExistsAction, PublishConfig, RegistryConfig},
+    events::{Event, EventSink, NullSink},
+    fixtures::Fixtures,
+    output_path::PatternMetadata,
+    secrets::SecretResolver,
+};
 use reqwest::{
     header::{HeaderMap, HeaderValue, AUTHORIZATION},
-    Client,
+    Client, RequestBuilder, Response,
 };
 use semver::Version;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::env;
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+/// Number of items requested per page from list endpoints
+const LIST_PAGE_SIZE: usize = 100;
+/// Safety bound on the number of items read from a single list endpoint
+const MAX_LIST_ITEMS: usize = 10_000;
+/// Attempts `download_to` makes before giving up on a broken connection
+const DOWNLOAD_ATTEMPTS: usize = 3;
+
+pub use crate::error::is_not_found;
+
+/// Content of a publish as sent in the JSON payload: text as-is, binary files
+/// (`binary: true`) base64-encoded
+pub fn encode_publish_content(publish: &PublishConfig, content: &[u8]) -> Result<String> {
+    if publish.binary {
+        return Ok(base64::encode(content));
+    }
+    String::from_utf8(content.to_vec()).map_err(|_| {
+        ApicurioError::config(format!(
+            "{} is not valid UTF-8; set `binary: true` to publish it base64-encoded",
+            publish.input_path
+        ))
+    })
+}
+
+/// The `content` object of a version payload: the encoded content with its
+/// content type and references
+fn content_payload(publish: &PublishConfig, content: &[u8]) -> Result<Value> {
+    let references: Vec<Value> = publish
+        .references
+        .iter()
+        .map(|r| {
+            json!({
+                "groupId": r.resolved_group_id(),
+                "artifactId": r.resolved_artifact_id(),
+                "version": r.version,
+                "name": r.name_alias.as_deref().unwrap_or(&r.resolved_artifact_id())
+            })
+        })
+        .collect();
+    Ok(json!({
+        "content": encode_publish_content(publish, content)?,
+        "contentType": publish.resolved_content_type(),
+        "references": references
+    }))
+}
+
+/// Undo the base64 wrapping of binary content downloaded from the registry
+///
+/// `binary: true` artifacts are always published base64-encoded, so content
+/// that does not decode is an integrity error rather than raw bytes to keep.
+pub fn decode_binary_content(data: &[u8]) -> Result<Vec<u8>> {
+    let trimmed: Vec<u8> = data
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    base64::decode(&trimmed).map_err(|e| {
+        ApicurioError::integrity(format!(
+            "content of a `binary: true` artifact is not base64 ({e})"
+        ))
+    })
+}
+
+/// Whether registry content matches local content; text is compared ignoring
+/// surrounding whitespace, binary content byte for byte after decoding
+pub fn content_matches(binary: bool, registry: &[u8], local: &[u8]) -> bool {
+    if binary {
+        decode_binary_content(registry).is_ok_and(|decoded| decoded == local)
+    } else {
+        String::from_utf8_lossy(registry).trim() == String::from_utf8_lossy(local).trim()
+    }
+}
+
+/// `Accept` header for downloading an artifact: derived from the extension of
+/// the file it is written to, else from its artifact type
+///
+/// Registries that content-negotiate (e.g. OpenAPI as YAML or JSON) otherwise
+/// answer in whatever format they prefer.
+pub fn accept_for(artifact_type: Option<&str>, output_path: Option<&str>) -> Option<&'static str> {
+    let ext = output_path
+        .and_then(|p| std::path::Path::new(p).extension())
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase);
+    let by_extension = match ext.as_deref() {
+        Some("yaml" | "yml") => Some("application/x-yaml"),
+        Some("json" | "avsc") => Some("application/json"),
+        Some("proto") => Some("application/x-protobuf"),
+        Some("graphql" | "gql") => Some("application/graphql"),
+        Some("xml" | "xsd" | "wsdl") => Some("application/xml"),
+        _ => None,
+    };
+    by_extension.or_else(|| match artifact_type?.to_uppercase().as_str() {
+        "PROTOBUF" => Some("application/x-protobuf"),
+        "AVRO" | "JSON" | "OPENAPI" | "ASYNCAPI" | "KCONNECT" => Some("application/json"),
+        "GRAPHQL" => Some("application/graphql"),
+        "XML" | "XSD" | "WSDL" => Some("application/xml"),
+        _ => None,
+    })
+}
+
+/// Whether a `Content-Type` answer has the media type asked for with `accept`
+pub fn content_type_matches(content_type: Option<&str>, accept: &str) -> bool {
+    let media_type = |value: &str| {
+        value
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+    };
+    content_type.is_some_and(|ct| media_type(ct) == media_type(accept))
+}
 
 /// Suggest a version bump for a given version string
 fn suggest_version_bump(version: &str) -> String {
@@ -32,13 +156,28 @@ fn suggest_version_bump(version: &str) -> String {
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ArtifactMetadata {
     pub artifact_id: String,
     pub artifact_type: String,
     #[serde(default, alias = "groupId", alias = "group")]
     pub group_id: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub labels: Option<std::collections::HashMap<String, String>>,
+}
+
+impl ArtifactMetadata {
+    /// Metadata exposed to output patterns for an artifact of `registry`
+    pub fn pattern_metadata(&self, registry: &str) -> PatternMetadata {
+        PatternMetadata {
+            registry: registry.to_string(),
+            artifact_type: self.artifact_type.clone(),
+            labels: self.labels.clone().unwrap_or_default(),
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -51,11 +190,54 @@ pub struct SystemInfo {
     pub built_on: String,
 }
 
+/// The caller as the registry sees it (`/users/me`)
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfo {
+    #[serde(default)]
+    pub username: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub admin: bool,
+    #[serde(default)]
+    pub developer: bool,
+    #[serde(default)]
+    pub viewer: bool,
+}
+
+/// A registry role granted to a principal (`/admin/roleMappings`)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleMapping {
+    pub principal_id: String,
+    /// `READ_ONLY`, `DEVELOPER` or `ADMIN`
+    pub role: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal_name: Option<String>,
+}
+
+/// A dynamic configuration property of the registry (`/admin/config/properties`)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigProperty {
+    pub name: String,
+    pub value: String,
+    /// Java type of the value, e.g. `java.lang.Boolean`
+    #[serde(rename = "type", default)]
+    pub property_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
 #[allow(dead_code)]
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ArtifactVersionMetadata {
     pub version: String,
+    #[serde(default)]
     pub artifact_type: String,
     pub global_id: Option<i64>,
     pub content_id: Option<i64>,
@@ -64,6 +246,9 @@ pub struct ArtifactVersionMetadata {
     pub owner: Option<String>,
     pub created_on: Option<String>,
     pub labels: Option<std::collections::HashMap<String, String>>,
+    /// Version state (`ENABLED`, `DISABLED`, `DEPRECATED` or `DRAFT`)
+    #[serde(default)]
+    pub state: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -82,7 +267,7 @@ impl ReferenceType {
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ArtifactVersionReference {
     pub group_id: Option<String>,
@@ -91,69 +276,609 @@ pub struct ArtifactVersionReference {
     pub name: Option<String>,
 }
 
+/// Kind of rule the registry enforces when content is added
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleType {
+    Validity,
+    Compatibility,
+    Integrity,
+}
+
+impl RuleType {
+    pub const ALL: [RuleType; 3] = [
+        RuleType::Validity,
+        RuleType::Compatibility,
+        RuleType::Integrity,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleType::Validity => "VALIDITY",
+            RuleType::Compatibility => "COMPATIBILITY",
+            RuleType::Integrity => "INTEGRITY",
+        }
+    }
+
+    /// Configurations the registry accepts for this rule
+    pub fn configs(&self) -> &'static [&'static str] {
+        match self {
+            RuleType::Validity => &["NONE", "SYNTAX_ONLY", "FULL"],
+            RuleType::Compatibility => &[
+                "NONE",
+                "BACKWARD",
+                "BACKWARD_TRANSITIVE",
+                "FORWARD",
+                "FORWARD_TRANSITIVE",
+                "FULL",
+                "FULL_TRANSITIVE",
+            ],
+            RuleType::Integrity => &[
+                "NONE",
+                "REFS_EXIST",
+                "ALL_REFS_MAPPED",
+                "NO_DUPLICATES",
+                "FULL",
+            ],
+        }
+    }
+}
+
+impl std::str::FromStr for RuleType {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        RuleType::ALL
+            .into_iter()
+            .find(|rule| rule.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                format!("unknown rule '{s}'; expected validity, compatibility or integrity")
+            })
+    }
+}
+
+/// What a rule applies to
+#[derive(Debug, Clone, Copy)]
+pub enum RuleScope<'a> {
+    /// Every artifact without a rule of its own
+    Global,
+    Artifact {
+        group_id: &'a str,
+        artifact_id: &'a str,
+    },
+}
+
+/// A registry rule rejecting content, as reported by a dry run
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct RuleViolation {
+    pub description: String,
+    /// Location of the problem in the content (e.g. a JSON pointer or field path)
+    #[serde(default)]
+    pub context: Option<String>,
+}
+
+/// Where the credentials of a registry come from, to explain rejected requests
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CredentialSource {
+    /// Auth type (`basic`, `token`, ...); `None` without auth
+    pub kind: Option<&'static str>,
+    /// `$VAR` or the secret reference the credential was read from
+    pub origin: Option<String>,
+    /// `exp` claim of a JWT token, in seconds since the Unix epoch
+    pub expires_at: Option<u64>,
+}
+
+impl CredentialSource {
+    /// Describe a 401/403 answer to `request` (`METHOD url`) as an actionable auth error
+    ///
+    /// `answer` (the `WWW-Authenticate` header and body) is searched for an
+    /// expiry hint, which a past JWT `exp` claim also gives.
+    pub fn rejection(
+        &self,
+        registry: &str,
+        request: &str,
+        status: reqwest::StatusCode,
+        answer: &str,
+        now: u64,
+    ) -> ApicurioError {
+        let origin = self
+            .origin
+            .as_deref()
+            .map(|o| format!(" from {o}"))
+            .unwrap_or_default();
+        let fix = match self.origin.as_deref() {
+            Some(var) if var.starts_with('$') => format!("set a valid credential in {var}"),
+            Some(reference) => format!("refresh the secret at {reference}"),
+            None => format!("update its auth with `apicurio registry edit {registry}`"),
+        };
+        let expired = self.expires_at.is_some_and(|exp| exp <= now)
+            || answer.to_lowercase().contains("expired");
+
+        let message = if status == reqwest::StatusCode::FORBIDDEN {
+            format!(
+                "registry '{registry}' accepted the {} credentials{origin} but denies {request} \
+                 (HTTP 403 Forbidden); ask for the missing permission or use other credentials",
+                self.kind.unwrap_or("anonymous")
+            )
+        } else if self.kind.is_none() {
+            format!(
+                "registry '{registry}' requires authentication (HTTP 401 Unauthorized on {request}) \
+                 but none is configured; {fix}"
+            )
+        } else if expired {
+            let when = match self.expires_at {
+                Some(exp) if exp <= now => format!(" {} ago", format_age(now - exp)),
+                _ => String::new(),
+            };
+            format!(
+                "the {} token of registry '{registry}'{origin} expired{when} \
+                 (HTTP 401 Unauthorized on {request}); {fix}",
+                self.kind.unwrap_or("auth")
+            )
+        } else {
+            format!(
+                "registry '{registry}' rejected the {} credentials{origin} \
+                 (HTTP 401 Unauthorized on {request}); check them, or {fix}",
+                self.kind.unwrap_or("auth")
+            )
+        };
+        ApicurioError::Auth {
+            registry: registry.to_string(),
+            message,
+            source: None,
+        }
+    }
+}
+
+/// `exp` claim of a JWT, without verifying its signature
+fn jwt_expiry(token: &str) -> Option<u64> {
+    let payload = token.split('.').nth(1)?;
+    let decoded =
+        base64::decode_config(payload.trim_end_matches('='), base64::URL_SAFE_NO_PAD).ok()?;
+    serde_json::from_slice::<Value>(&decoded).ok()?["exp"].as_u64()
+}
+
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        s if s < 120 => format!("{s}s"),
+        s if s < 2 * 3600 => format!("{}m", s / 60),
+        s if s < 2 * 86400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    }
+}
+
+fn now_unix() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Registry clients built once per registry and process
+///
+/// A [`RegistryClient`] clone shares the connection pool of the client it was
+/// cloned from, so handing out clones lets identifier completion, `add`, `lock`,
+/// `pull`, `status` and `doctor` reuse open connections and resolve credentials
+/// once. Clients are keyed by the whole [`RegistryConfig`]: a registry whose URL,
+/// auth or timeouts differ gets a client of its own.
+#[derive(Default)]
+pub struct RegistryPool {
+    clients: Mutex<Vec<(RegistryConfig, RegistryClient)>>,
+}
+
+impl RegistryPool {
+    /// The pool shared by the whole process
+    pub fn global() -> &'static RegistryPool {
+        static POOL: OnceLock<RegistryPool> = OnceLock::new();
+        POOL.get_or_init(RegistryPool::default)
+    }
+
+    /// The client of `cfg`, built on first use
+    pub fn client(&self, cfg: &RegistryConfig) -> Result<RegistryClient> {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, client)) = clients.iter().find(|(c, _)| c == cfg) {
+            return Ok(client.clone());
+        }
+        let client = RegistryClient::new(cfg)?;
+        clients.push((cfg.clone(), client.clone()));
+        Ok(client)
+    }
+
+    /// Clients of all `registries`, by name
+    pub fn clients(
+        &self,
+        registries: &[RegistryConfig],
+    ) -> Result<HashMap<String, RegistryClient>> {
+        registries
+            .iter()
+            .map(|r| Ok((r.name.clone(), self.client(r)?)))
+            .collect()
+    }
+}
+
+#[derive(Clone)]
 pub struct RegistryClient {
     #[allow(dead_code)]
     pub name: String,
     pub base_url: String,
     pub client: Client,
+    /// Used to explain 401/403 answers
+    pub credentials: CredentialSource,
+    /// Signs every request when the registry uses `awsSigv4` auth
+    #[cfg(feature = "aws-sigv4")]
+    signer: Option<crate::sigv4::SigV4Signer>,
+    /// Gives the bearer token of every request when the registry uses `exec` auth
+    exec: Option<crate::credentials::ExecPlugin>,
+    /// Records answers to, or replays them from, a fixture file
+    fixtures: Option<std::sync::Arc<Fixtures>>,
+    /// Longest the registry may stay silent before answering or between two
+    /// reads of an answer
+    read_timeout: std::time::Duration,
+    /// Receives warnings about answers that were only partly read
+    events: Arc<dyn EventSink>,
 }
 
 impl RegistryClient {
     pub fn new(cfg: &RegistryConfig) -> Result<Self> {
         let mut headers = HeaderMap::new();
+        let secrets = SecretResolver::default();
+        let mut credentials = CredentialSource::default();
+        let mut credential = |kind: &'static str, env_var: &str, secret_ref: &Option<String>| {
+            let origin = secret_ref.clone().unwrap_or_else(|| format!("${env_var}"));
+            let value = secrets
+                .resolve_credential(env_var, secret_ref.as_deref())
+                .map_err(|e| {
+                    ApicurioError::auth(
+                        &cfg.name,
+                        format!("resolving credentials for registry '{}'", cfg.name),
+                        e,
+                    )
+                })?;
+            if value.trim().is_empty() {
+                return Err(ApicurioError::Auth {
+                    registry: cfg.name.clone(),
+                    message: format!(
+                        "the {kind} credential of registry '{}' ({origin}) is empty",
+                        cfg.name
+                    ),
+                    source: None,
+                });
+            }
+            credentials = CredentialSource {
+                kind: Some(kind),
+                origin: Some(origin),
+                expires_at: jwt_expiry(value.trim()),
+            };
+            Ok(value)
+        };
+        let header = |value: String| {
+            HeaderValue::from_str(&value).map_err(|e| {
+                ApicurioError::auth(
+                    &cfg.name,
+                    format!(
+                        "credentials of registry '{}' are not a valid header",
+                        cfg.name
+                    ),
+                    e,
+                )
+            })
+        };
+        // A login with a username is sent as Basic auth, a lone password as a
+        // bearer token
+        let login_header = |login: crate::credentials::Login| match login.username {
+            Some(username) => {
+                let token = base64::encode_config(
+                    format!("{username}:{}", login.password),
+                    base64::STANDARD,
+                );
+                header(format!("Basic {token}"))
+            }
+            None => header(format!("Bearer {}", login.password)),
+        };
+        let login_error = |e: anyhow::Error| {
+            ApicurioError::auth(
+                &cfg.name,
+                format!("resolving credentials for registry '{}'", cfg.name),
+                e,
+            )
+        };
         match &cfg.auth {
             AuthConfig::None => {}
             AuthConfig::Basic {
                 username,
                 password_env,
+                secret_ref,
             } => {
-                let pw = env::var(password_env)?;
+                let pw = credential("basic", password_env, secret_ref)?;
                 let token = base64::encode_config(format!("{username}:{pw}"), base64::STANDARD);
-                let hv = HeaderValue::from_str(&format!("Basic {token}"))?;
+                let hv = header(format!("Basic {token}"))?;
                 headers.insert(AUTHORIZATION, hv);
             }
-            AuthConfig::Token { token_env } => {
-                let tok = env::var(token_env)?;
-                let hv = HeaderValue::from_str(&tok)?;
+            AuthConfig::Token {
+                token_env,
+                secret_ref,
+            } => {
+                let tok = credential("token", token_env, secret_ref)?;
+                let hv = header(tok)?;
                 headers.insert(AUTHORIZATION, hv);
             }
-            AuthConfig::Bearer { token_env } => {
-                let tok = env::var(token_env)?;
-                let hv = HeaderValue::from_str(&format!("Bearer {tok}"))?;
+            AuthConfig::Bearer {
+                token_env,
+                secret_ref,
+            } => {
+                let tok = credential("bearer", token_env, secret_ref)?;
+                let hv = header(format!("Bearer {tok}"))?;
                 headers.insert(AUTHORIZATION, hv);
             }
+            AuthConfig::Netrc { file, machine } => {
+                let login =
+                    crate::credentials::from_netrc(&cfg.url, file.as_deref(), machine.as_deref())
+                        .map_err(login_error)?;
+                credentials = CredentialSource {
+                    kind: Some("netrc"),
+                    origin: Some(file.clone().unwrap_or_else(|| ".netrc".to_string())),
+                    expires_at: jwt_expiry(&login.password),
+                };
+                headers.insert(AUTHORIZATION, login_header(login)?);
+            }
+            AuthConfig::CredentialHelper { command } => {
+                let login =
+                    crate::credentials::from_helper(command, &cfg.url).map_err(login_error)?;
+                credentials = CredentialSource {
+                    kind: Some("credential helper"),
+                    origin: Some(format!("credential helper '{command}'")),
+                    expires_at: jwt_expiry(&login.password),
+                };
+                headers.insert(AUTHORIZATION, login_header(login)?);
+            }
+            // Run by `send` when the first request needs the token
+            AuthConfig::Exec { command, .. } => {
+                credentials = CredentialSource {
+                    kind: Some("exec"),
+                    origin: Some(format!("exec command '{command}'")),
+                    expires_at: None,
+                };
+            }
+            #[cfg(feature = "aws-sigv4")]
+            AuthConfig::AwsSigv4 { profile, .. } => {
+                credentials = CredentialSource {
+                    kind: Some("aws-sigv4"),
+                    origin: profile.as_ref().map(|p| format!("AWS profile '{p}'")),
+                    expires_at: None,
+                };
+            }
+            #[cfg(not(feature = "aws-sigv4"))]
+            AuthConfig::AwsSigv4 { .. } => {
+                return Err(ApicurioError::config(format!(
+                "registry '{}' uses awsSigv4 auth, but this build lacks the `aws-sigv4` feature \
+                     (reinstall with `cargo install apicurio-cli --features aws-sigv4`)",
+                cfg.name
+            )))
+            }
         }
+        #[cfg(feature = "aws-sigv4")]
+        let signer = match &cfg.auth {
+            AuthConfig::AwsSigv4 {
+                region,
+                service,
+                profile,
+            } => Some(crate::sigv4::SigV4Signer {
+                region: region.clone(),
+                service: service.clone(),
+                credentials: crate::sigv4::AwsCredentials::load(profile.as_deref()).map_err(
+                    |e| {
+                        ApicurioError::auth(
+                            &cfg.name,
+                            format!("resolving credentials for registry '{}'", cfg.name),
+                            e,
+                        )
+                    },
+                )?,
+            }),
+            _ => None,
+        };
 
-        let client = Client::builder().default_headers(headers).build()?;
+        let client = Client::builder()
+            .default_headers(headers)
+            .connect_timeout(cfg.timeouts.connect_timeout())
+            .build()
+            .map_err(|e| ApicurioError::network("building the HTTP client", e))?;
         Ok(RegistryClient {
             name: cfg.name.clone(),
             base_url: cfg.url.clone(),
             client,
+            credentials,
+            #[cfg(feature = "aws-sigv4")]
+            signer,
+            exec: match &cfg.auth {
+                AuthConfig::Exec { command, args } => Some(crate::credentials::ExecPlugin {
+                    command: command.clone(),
+                    args: args.clone(),
+                }),
+                _ => None,
+            },
+            fixtures: Fixtures::selected()?,
+            read_timeout: cfg.timeouts.read_timeout(),
+            events: Arc::new(NullSink),
         })
     }
 
+    /// Record answers to, or replay them from, `fixtures` instead of what
+    /// `--record`/`--replay` selected
+    pub fn with_fixtures(mut self, fixtures: std::sync::Arc<Fixtures>) -> Self {
+        self.fixtures = Some(fixtures);
+        self
+    }
+
+    /// Report warnings (e.g. a truncated listing) to `events` instead of
+    /// discarding them
+    pub fn with_events(mut self, events: Arc<dyn EventSink>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Send a request built from [`RegistryClient::client`], signing it if needed
+    ///
+    /// A 401/403 answer becomes an [`ApicurioError::Auth`] naming the registry
+    /// and where its credentials come from.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let mut credentials = self.credentials.clone();
+        let request = match &self.exec {
+            Some(exec) => {
+                let token = exec.token().await.map_err(|e| {
+                    ApicurioError::auth(
+                        &self.name,
+                        format!("resolving credentials for registry '{}'", self.name),
+                        e,
+                    )
+                })?;
+                credentials.expires_at = jwt_expiry(&token);
+                request.bearer_auth(token)
+            }
+            None => request,
+        };
+        #[allow(unused_mut)]
+        let mut request = request.build()?;
+        #[cfg(feature = "aws-sigv4")]
+        if let Some(signer) = &self.signer {
+            signer.sign(&mut request).map_err(|e| {
+                ApicurioError::auth(&self.name, format!("signing a request to {}", self.name), e)
+            })?;
+        }
+        let (method, url) = (request.method().clone(), request.url().clone());
+        let response = match &self.fixtures {
+            Some(fixtures) => fixtures.exchange(&self.client, request).await?,
+            None => self.read(self.client.execute(request)).await?,
+        };
+        let status = response.status();
+        if status != reqwest::StatusCode::UNAUTHORIZED && status != reqwest::StatusCode::FORBIDDEN {
+            return Ok(response);
+        }
+        let challenge = response
+            .headers()
+            .get(reqwest::header::WWW_AUTHENTICATE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let body = self.text(response).await.unwrap_or_default();
+        Err(credentials.rejection(
+            &self.name,
+            &format!("{method} {url}"),
+            status,
+            &format!("{challenge}\n{body}"),
+            now_unix(),
+        ))
+    }
+
+    /// Await one read from the registry, failing once it stays silent for the
+    /// read timeout
+    async fn read<T>(
+        &self,
+        read: impl std::future::Future<Output = reqwest::Result<T>>,
+    ) -> Result<T> {
+        match tokio::time::timeout(self.read_timeout, read).await {
+            Ok(result) => Ok(result?),
+            Err(elapsed) => Err(ApicurioError::network(
+                format!(
+                    "registry '{}' sent nothing for {}s",
+                    self.name,
+                    self.read_timeout.as_secs()
+                ),
+                elapsed,
+            )),
+        }
+    }
+
+    /// The whole body of `response`, with the read timeout applied to each
+    /// chunk rather than to the transfer as a whole
+    async fn body(&self, mut response: Response) -> Result<bytes::Bytes> {
+        let mut body = Vec::new();
+        while let Some(chunk) = self.read(response.chunk()).await? {
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body.into())
+    }
+
+    /// The body of `response` as text
+    async fn text(&self, response: Response) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.body(response).await?).into_owned())
+    }
+
+    /// The body of `response` parsed as JSON
+    async fn json<T: DeserializeOwned>(&self, response: Response) -> Result<T> {
+        let url = response.url().to_string();
+        serde_json::from_slice(&self.body(response).await?)
+            .map_err(|e| ApicurioError::network(format!("unexpected answer from {url}"), e))
+    }
+
+    /// Fetch every page of a list endpoint (`{ count, <items_key>: [...] }`)
+    ///
+    /// Stops once `count` items were read, a page comes back empty, or
+    /// [`MAX_LIST_ITEMS`] is reached.
+    async fn get_paginated<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        items_key: &str,
+    ) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        loop {
+            let offset = items.len();
+            let request = self
+                .client
+                .get(url)
+                .query(&[("limit", LIST_PAGE_SIZE), ("offset", offset)]);
+            let page: Value = self
+                .json(self.send(request).await?.error_for_status()?)
+                .await?;
+            let count = page.get("count").and_then(Value::as_u64).unwrap_or(0) as usize;
+            let page_items: Vec<T> = match page.get(items_key) {
+                Some(list) => serde_json::from_value(list.clone()).map_err(|e| {
+                    ApicurioError::network(format!("unexpected answer from {url}"), e)
+                })?,
+                None => Vec::new(),
+            };
+            if page_items.is_empty() {
+                break;
+            }
+            items.extend(page_items);
+            if items.len() >= count {
+                break;
+            }
+            if items.len() >= MAX_LIST_ITEMS {
+                self.events.emit(Event::Warning(format!(
+                    "{url} has {count} entries; only the first {MAX_LIST_ITEMS} were read"
+                )));
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// List every version of an artifact with its extended metadata
+    pub async fn list_version_metadata(
+        &self,
+        group_id: &str,
+        artifact_id: &str,
+    ) -> Result<Vec<ArtifactVersionMetadata>> {
+        let url = format!(
+            "{}/apis/registry/v3/groups/{}/artifacts/{}/versions",
+            self.base_url, group_id, artifact_id
+        );
+        self.get_paginated(&url, "versions").await
+    }
+
     /// List all published versions for a given artifact
     pub async fn list_versions(&self, group_id: &str, artifact_id: &str) -> Result<Vec<Version>> {
         let url = format!(
             "{}/apis/registry/v3/groups/{}/artifacts/{}/versions",
             self.base_url, group_id, artifact_id
         );
-        let resp = self.client.get(&url).send().await?.error_for_status()?;
-        #[derive(Deserialize)]
-        struct ApiResponse {
-            #[allow(dead_code)]
-            count: usize,
-            versions: Vec<ApiVersion>,
-        }
-
         #[derive(Deserialize)]
         struct ApiVersion {
             version: String,
         }
 
-        let api_response: ApiResponse = resp.json().await?;
+        let versions: Vec<ApiVersion> = self.get_paginated(&url, "versions").await?;
         let mut semver_versions = Vec::new();
-        for v in api_response.versions {
+        for v in versions {
             if let Ok(parsed) = Version::parse(&v.version) {
                 semver_versions.push(parsed);
             }
@@ -168,29 +893,9 @@ impl RegistryClient {
         )
     }
 
-    /// Download the raw content for a specific version
-    pub async fn download(
-        &self,
-        group_id: &str,
-        artifact_id: &str,
-        version: &Version,
-    ) -> Result<bytes::Bytes> {
-        let url = self.get_download_url(group_id, artifact_id, version);
-        let resp = self.client.get(&url).send().await?.error_for_status()?;
-        Ok(resp.bytes().await?)
-    }
-
     /// List all groups in the registry
     pub async fn list_groups(&self) -> Result<Vec<String>> {
         let url = format!("{}/apis/registry/v3/groups", self.base_url);
-        let resp = self.client.get(&url).send().await?.error_for_status()?;
-
-        #[derive(Deserialize)]
-        struct ApiResponse {
-            #[allow(dead_code)]
-            count: usize,
-            groups: Vec<ApiGroup>,
-        }
 
         #[derive(Deserialize)]
         struct ApiGroup {
@@ -198,12 +903,8 @@ impl RegistryClient {
             group_id: String,
         }
 
-        let api_response: ApiResponse = resp.json().await?;
-        Ok(api_response
-            .groups
-            .into_iter()
-            .map(|g| g.group_id)
-            .collect())
+        let groups: Vec<ApiGroup> = self.get_paginated(&url, "groups").await?;
+        Ok(groups.into_iter().map(|g| g.group_id).collect())
     }
 
     /// List all artifacts in a specific group
@@ -212,26 +913,112 @@ impl RegistryClient {
             "{}/apis/registry/v3/groups/{}/artifacts",
             self.base_url, group_id
         );
-        let resp = self.client.get(&url).send().await?.error_for_status()?;
 
         #[derive(Deserialize)]
-        struct ApiResponse {
-            #[allow(dead_code)]
-            count: usize,
-            artifacts: Vec<ApiArtifact>,
+        struct ApiArtifact {
+            #[serde(rename = "artifactId")]
+            artifact_id: String,
         }
 
+        let artifacts: Vec<ApiArtifact> = self.get_paginated(&url, "artifacts").await?;
+        Ok(artifacts.into_iter().map(|a| a.artifact_id).collect())
+    }
+
+    /// Find the artifacts carrying every one of `labels`, optionally within one
+    /// group, as `(groupId, artifactId)` pairs
+    pub async fn search_artifacts_by_labels(
+        &self,
+        labels: &std::collections::BTreeMap<String, String>,
+        group_id: Option<&str>,
+    ) -> Result<Vec<(String, String)>> {
+        let mut params: Vec<(&str, String)> = labels
+            .iter()
+            .map(|(key, value)| ("labels", format!("{key}:{value}")))
+            .collect();
+        if let Some(group_id) = group_id {
+            params.push(("groupId", group_id.to_string()));
+        }
+        self.search_artifacts(&params).await
+    }
+
+    /// Find the artifacts whose ID or name is `name`, as `(groupId, artifactId)`
+    /// pairs
+    pub async fn search_artifacts_by_name(&self, name: &str) -> Result<Vec<(String, String)>> {
+        let mut found = self
+            .search_artifacts(&[("artifactId", name.to_string())])
+            .await?;
+        for artifact in self.search_artifacts(&[("name", name.to_string())]).await? {
+            if !found.contains(&artifact) {
+                found.push(artifact);
+            }
+        }
+        Ok(found)
+    }
+
+    async fn search_artifacts(&self, params: &[(&str, String)]) -> Result<Vec<(String, String)>> {
+        let url = reqwest::Url::parse_with_params(
+            &format!("{}/apis/registry/v3/search/artifacts", self.base_url),
+            params,
+        )
+        .map_err(|e| {
+            ApicurioError::network(format!("invalid registry URL {}", self.base_url), e)
+        })?;
+
         #[derive(Deserialize)]
-        struct ApiArtifact {
-            #[serde(rename = "artifactId")]
+        #[serde(rename_all = "camelCase")]
+        struct SearchedArtifact {
+            group_id: Option<String>,
+            artifact_id: String,
+        }
+
+        let artifacts: Vec<SearchedArtifact> =
+            self.get_paginated(url.as_str(), "artifacts").await?;
+        Ok(artifacts
+            .into_iter()
+            .map(|a| {
+                let group_id = a.group_id.unwrap_or_else(|| "default".to_string());
+                (group_id, a.artifact_id)
+            })
+            .collect())
+    }
+
+    /// Find the versions whose content is exactly `content`, as
+    /// `(groupId, artifactId, version)`
+    pub async fn search_versions_by_content(
+        &self,
+        content: &[u8],
+        content_type: &str,
+    ) -> Result<Vec<(String, String, String)>> {
+        let url = format!("{}/apis/registry/v3/search/versions", self.base_url);
+        let request = self
+            .client
+            .post(&url)
+            .query(&[("limit", LIST_PAGE_SIZE)])
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(content.to_vec());
+        let page: Value = self
+            .json(self.send(request).await?.error_for_status()?)
+            .await?;
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SearchedVersion {
+            group_id: Option<String>,
             artifact_id: String,
+            version: String,
         }
 
-        let api_response: ApiResponse = resp.json().await?;
-        Ok(api_response
-            .artifacts
+        let versions: Vec<SearchedVersion> = match page.get("versions") {
+            Some(list) => serde_json::from_value(list.clone())
+                .map_err(|e| ApicurioError::network(format!("unexpected answer from {url}"), e))?,
+            None => Vec::new(),
+        };
+        Ok(versions
             .into_iter()
-            .map(|a| a.artifact_id)
+            .map(|v| {
+                let group_id = v.group_id.unwrap_or_else(|| "default".to_string());
+                (group_id, v.artifact_id, v.version)
+            })
             .collect())
     }
 
@@ -242,8 +1029,9 @@ impl RegistryClient {
             self.base_url, group_id, artifact_id
         );
 
-        match self.client.get(&url).send().await {
+        match self.send(self.client.get(&url)).await {
             Ok(resp) => Ok(resp.status().is_success()),
+            Err(e @ ApicurioError::Auth { .. }) => Err(e),
             Err(_) => Ok(false),
         }
     }
@@ -258,9 +1046,9 @@ impl RegistryClient {
             "{}/apis/registry/v3/groups/{}/artifacts/{}",
             self.base_url, group_id, artifact_id
         );
-        let resp = self.client.get(&url).send().await?.error_for_status()?;
+        let resp = self.send(self.client.get(&url)).await?.error_for_status()?;
 
-        let mut metadata: ArtifactMetadata = resp.json().await?;
+        let mut metadata: ArtifactMetadata = self.json(resp).await?;
         // Ensure group_id is set even if not provided by the API response
         if metadata.group_id.is_none() {
             metadata.group_id = Some(group_id.to_string());
@@ -268,67 +1056,211 @@ impl RegistryClient {
         Ok(metadata)
     }
 
+    /// Replace the labels of an artifact, leaving its other metadata alone
+    pub async fn set_artifact_labels(
+        &self,
+        group_id: &str,
+        artifact_id: &str,
+        labels: &std::collections::HashMap<String, String>,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/apis/registry/v3/groups/{}/artifacts/{}",
+            self.base_url, group_id, artifact_id
+        );
+        let request = self.client.put(&url).json(&json!({ "labels": labels }));
+        self.send(request).await?.error_for_status()?;
+        Ok(())
+    }
+
+    fn rules_url(&self, scope: RuleScope) -> String {
+        match scope {
+            RuleScope::Global => format!("{}/apis/registry/v3/admin/rules", self.base_url),
+            RuleScope::Artifact {
+                group_id,
+                artifact_id,
+            } => format!(
+                "{}/apis/registry/v3/groups/{}/artifacts/{}/rules",
+                self.base_url, group_id, artifact_id
+            ),
+        }
+    }
+
+    /// List the rules configured for `scope` with their configuration
+    pub async fn list_rules(&self, scope: RuleScope<'_>) -> Result<Vec<(RuleType, String)>> {
+        let url = self.rules_url(scope);
+        let resp = self.send(self.client.get(&url)).await?.error_for_status()?;
+        let names: Vec<String> = self.json(resp).await?;
+
+        #[derive(Deserialize)]
+        struct ApiRule {
+            config: String,
+        }
+
+        let mut rules = Vec::new();
+        for rule in RuleType::ALL {
+            if !names.iter().any(|name| name == rule.as_str()) {
+                continue;
+            }
+            let url = format!("{url}/{}", rule.as_str());
+            let resp = self.send(self.client.get(&url)).await?.error_for_status()?;
+            let api_rule: ApiRule = self.json(resp).await?;
+            rules.push((rule, api_rule.config));
+        }
+        Ok(rules)
+    }
+
+    /// Create or update a rule of `scope`
+    pub async fn set_rule(&self, scope: RuleScope<'_>, rule: RuleType, config: &str) -> Result<()> {
+        let exists = self
+            .list_rules(scope)
+            .await?
+            .iter()
+            .any(|(existing, _)| *existing == rule);
+        let url = self.rules_url(scope);
+        let body = json!({ "ruleType": rule.as_str(), "config": config });
+        let request = if exists {
+            self.client
+                .put(format!("{url}/{}", rule.as_str()))
+                .json(&body)
+        } else {
+            self.client.post(&url).json(&body)
+        };
+        self.send(request).await?.error_for_status()?;
+        Ok(())
+    }
+
+    /// Delete a rule of `scope`, falling back to the global rule (if any)
+    pub async fn delete_rule(&self, scope: RuleScope<'_>, rule: RuleType) -> Result<()> {
+        let url = format!("{}/{}", self.rules_url(scope), rule.as_str());
+        self.send(self.client.delete(&url))
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Delete a group and every artifact in it
+    pub async fn delete_group(&self, group_id: &str) -> Result<()> {
+        let url = format!("{}/apis/registry/v3/groups/{}", self.base_url, group_id);
+        self.send(self.client.delete(&url))
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Delete an artifact with all of its versions
+    pub async fn delete_artifact(&self, group_id: &str, artifact_id: &str) -> Result<()> {
+        let url = format!(
+            "{}/apis/registry/v3/groups/{}/artifacts/{}",
+            self.base_url, group_id, artifact_id
+        );
+        self.send(self.client.delete(&url))
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Delete one version of an artifact
+    pub async fn delete_version(
+        &self,
+        group_id: &str,
+        artifact_id: &str,
+        version: &str,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/apis/registry/v3/groups/{}/artifacts/{}/versions/{}",
+            self.base_url, group_id, artifact_id, version
+        );
+        self.send(self.client.delete(&url))
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
     /// Publish an artifact to the registry
-    pub async fn publish_artifact(&self, publish: &PublishConfig, content: &str) -> Result<()> {
+    ///
+    /// With `draft`, the version is created in the `DRAFT` state (see
+    /// [`RegistryClient::set_version_state`]); publishing a draft again replaces
+    /// its content instead of failing.
+    pub async fn publish_artifact(
+        &self,
+        publish: &PublishConfig,
+        content: &[u8],
+        draft: bool,
+        events: &dyn EventSink,
+    ) -> Result<()> {
         let group_id = publish.resolved_group_id();
         let artifact_id = publish.resolved_artifact_id();
-        let content_type = publish.resolved_content_type();
         let artifact_type = publish.resolved_artifact_type();
+        let version_content = content_payload(publish, content)?;
 
         // Check if the version already exists
         if self
             .version_exists(&group_id, &artifact_id, &publish.version)
             .await?
         {
+            let state = self
+                .get_version_metadata(&group_id, &artifact_id, &publish.version)
+                .await
+                .ok()
+                .and_then(|m| m.state);
+            if draft && state.as_deref() == Some("DRAFT") {
+                let url = format!(
+                    "{}/apis/registry/v3/groups/{}/artifacts/{}/versions/{}/content",
+                    self.base_url.trim_end_matches('/'),
+                    group_id,
+                    artifact_id,
+                    publish.version
+                );
+                self.send(self.client.put(&url).json(&version_content))
+                    .await?
+                    .error_for_status()
+                    .map_err(|e| {
+                        ApicurioError::from(e).context(format!(
+                            "updating draft {}@{}",
+                            artifact_id, publish.version
+                        ))
+                    })?;
+                events.emit(Event::Published {
+                    artifact_id,
+                    version: publish.version.clone(),
+                    draft,
+                });
+                return Ok(());
+            }
+
             // Version exists, compare content
             match self
-                .get_version_content(&group_id, &artifact_id, &publish.version)
+                .get_version_bytes(&group_id, &artifact_id, &publish.version)
                 .await
             {
                 Ok(existing_content) => {
-                    if existing_content.trim() == content.trim() {
-                        println!(
-                            "  ℹ️  Version {}@{} already published with identical content",
-                            artifact_id, publish.version
-                        );
+                    if content_matches(publish.binary, &existing_content, content) {
+                        events.emit(Event::AlreadyPublished {
+                            artifact_id,
+                            version: publish.version.clone(),
+                        });
                         return Ok(());
                     } else {
                         // Content is different, suggest version bump
-                        println!(
-                            "  ⚠️  Version {}@{} already exists with different content",
-                            artifact_id, publish.version
-                        );
-                        println!(
-                            "     Consider bumping the version (e.g., {}) to publish the updated content",
+                        return Err(ApicurioError::integrity(format!(
+                            "Cannot publish different content with same version: {}@{} already exists. \
+                             Consider bumping the version (e.g., {}) to publish the updated content",
+                            artifact_id,
+                            publish.version,
                             suggest_version_bump(&publish.version)
-                        );
-                        anyhow::bail!("Cannot publish different content with same version");
+                        )));
                     }
                 }
                 Err(_) => {
                     // Could not retrieve existing content, proceed with normal flow
-                    println!(
-                        "  ⚠️  Version {}@{} exists but content comparison failed, proceeding with publish",
+                    events.emit(Event::Warning(format!(
+                        "Version {}@{} exists but content comparison failed, proceeding with publish",
                         artifact_id, publish.version
-                    );
+                    )));
                 }
             }
         }
 
-        // Build references array for the API
-        let references: Vec<Value> = publish
-            .references
-            .iter()
-            .map(|r| {
-                json!({
-                    "groupId": r.resolved_group_id(),
-                    "artifactId": r.resolved_artifact_id(),
-                    "version": r.version,
-                    "name": r.name_alias.as_deref().unwrap_or(&r.resolved_artifact_id())
-                })
-            })
-            .collect();
-
         // Check if artifact exists to determine which endpoint to use
         let artifact_exists = self.artifact_exists(&group_id, &artifact_id).await?;
 
@@ -336,14 +1268,11 @@ impl RegistryClient {
             // Artifact exists, create a new version using the versions endpoint
             let version_payload = json!({
                 "version": publish.version,
-                "content": {
-                    "content": content,
-                    "contentType": content_type,
-                    "references": references
-                },
+                "content": version_content,
                 "name": &publish.name,
                 "description": publish.description.as_deref().unwrap_or(""),
-                "labels": {}
+                "labels": {},
+                "isDraft": draft
             });
 
             let url = format!(
@@ -353,30 +1282,29 @@ impl RegistryClient {
                 artifact_id
             );
 
-            let response = self
+            let request = self
                 .client
                 .post(&url)
                 .header("Content-Type", "application/json")
-                .json(&version_payload)
-                .send()
-                .await?;
+                .json(&version_payload);
+            let response = self.send(request).await?;
 
             if response.status().is_success() {
-                println!("  ✅ Published {}@{}", artifact_id, publish.version);
+                events.emit(Event::Published {
+                    artifact_id,
+                    version: publish.version.clone(),
+                    draft,
+                });
                 Ok(())
             } else {
                 let status = response.status();
-                let body = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Unknown error".to_string());
-                anyhow::bail!(
-                    "Failed to publish {}@{}: HTTP {} - {}",
-                    artifact_id,
-                    publish.version,
-                    status,
-                    body
-                );
+                let body = self.text(response).await.ok();
+                Err(
+                    ApicurioError::from_status(&url, status, body).context(format!(
+                        "Failed to publish {}@{}",
+                        artifact_id, publish.version
+                    )),
+                )
             }
         } else {
             // Artifact doesn't exist, create new artifact with first version
@@ -388,14 +1316,11 @@ impl RegistryClient {
                 "labels": publish.labels,
                 "firstVersion": {
                     "version": publish.version,
-                    "content": {
-                        "content": content,
-                        "contentType": content_type,
-                        "references": references
-                    },
+                    "content": version_content,
                     "name": &publish.name,
                     "description": publish.description.as_deref().unwrap_or(""),
-                    "labels": {}
+                    "labels": {},
+                    "isDraft": draft
                 }
             });
 
@@ -414,34 +1339,84 @@ impl RegistryClient {
                 if_exists_param
             );
 
-            let response = self
+            let request = self
                 .client
                 .post(&url)
                 .header("Content-Type", "application/json")
-                .json(&payload)
-                .send()
-                .await?;
+                .json(&payload);
+            let response = self.send(request).await?;
 
             if response.status().is_success() {
-                println!("  ✅ Published {}@{}", artifact_id, publish.version);
+                events.emit(Event::Published {
+                    artifact_id,
+                    version: publish.version.clone(),
+                    draft,
+                });
                 Ok(())
             } else {
                 let status = response.status();
-                let body = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Unknown error".to_string());
-                anyhow::bail!(
-                    "Failed to publish {}@{}: HTTP {} - {}",
-                    artifact_id,
-                    publish.version,
-                    status,
-                    body
-                );
+                let body = self.text(response).await.ok();
+                Err(
+                    ApicurioError::from_status(&url, status, body).context(format!(
+                        "Failed to publish {}@{}",
+                        artifact_id, publish.version
+                    )),
+                )
             }
         }
     }
 
+    /// Ask the registry whether `content` would be accepted as a new version of
+    /// `publish`, without creating it (`dryRun=true`)
+    ///
+    /// Returns the violated rules; an empty list means the content passes the
+    /// artifact's rules (or the global rules for an artifact that does not exist yet).
+    pub async fn test_publish(
+        &self,
+        publish: &PublishConfig,
+        content: &[u8],
+    ) -> Result<Vec<RuleViolation>> {
+        let group_id = publish.resolved_group_id();
+        let artifact_id = publish.resolved_artifact_id();
+        let version_content = content_payload(publish, content)?;
+        // No version: an existing version number must not hide rule violations
+        let (url, payload) = if self.artifact_exists(&group_id, &artifact_id).await? {
+            (
+                format!(
+                    "{}/apis/registry/v3/groups/{}/artifacts/{}/versions?dryRun=true",
+                    self.base_url.trim_end_matches('/'),
+                    group_id,
+                    artifact_id
+                ),
+                json!({ "content": version_content }),
+            )
+        } else {
+            (
+                format!(
+                    "{}/apis/registry/v3/groups/{}/artifacts?dryRun=true",
+                    self.base_url.trim_end_matches('/'),
+                    group_id
+                ),
+                json!({
+                    "artifactId": artifact_id,
+                    "artifactType": publish.resolved_artifact_type(),
+                    "firstVersion": { "content": version_content }
+                }),
+            )
+        };
+        let response = self.send(self.client.post(&url).json(&payload)).await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(Vec::new());
+        }
+        let body = self.text(response).await.ok();
+        match body.as_deref().and_then(rule_violations) {
+            Some(violations) => Ok(violations),
+            None => Err(ApicurioError::from_status(&url, status, body)
+                .context(format!("Failed to test {artifact_id}"))),
+        }
+    }
+
     /// Check if a specific artifact version exists
     pub async fn version_exists(
         &self,
@@ -454,12 +1429,30 @@ impl RegistryClient {
             self.base_url, group_id, artifact_id, version
         );
 
-        match self.client.get(&url).send().await {
+        match self.send(self.client.get(&url)).await {
             Ok(resp) => Ok(resp.status().is_success()),
+            Err(e @ ApicurioError::Auth { .. }) => Err(e),
             Err(_) => Ok(false),
         }
     }
 
+    /// Change the state of a version, e.g. promote a `DRAFT` to `ENABLED`
+    pub async fn set_version_state(
+        &self,
+        group_id: &str,
+        artifact_id: &str,
+        version: &str,
+        state: &str,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/apis/registry/v3/groups/{}/artifacts/{}/versions/{}/state",
+            self.base_url, group_id, artifact_id, version
+        );
+        let request = self.client.put(&url).json(&json!({ "state": state }));
+        self.send(request).await?.error_for_status()?;
+        Ok(())
+    }
+
     /// Get the content of a specific artifact version as a string
     pub async fn get_version_content(
         &self,
@@ -471,31 +1464,212 @@ impl RegistryClient {
             "{}/apis/registry/v3/groups/{}/artifacts/{}/versions/{}/content",
             self.base_url, group_id, artifact_id, version
         );
-        let resp = self.client.get(&url).send().await?.error_for_status()?;
-        Ok(resp.text().await?)
+        let resp = self.send(self.client.get(&url)).await?.error_for_status()?;
+        self.text(resp).await
+    }
+
+    /// Get the raw content of a specific artifact version
+    pub async fn get_version_bytes(
+        &self,
+        group_id: &str,
+        artifact_id: &str,
+        version: &str,
+    ) -> Result<bytes::Bytes> {
+        let url = format!(
+            "{}/apis/registry/v3/groups/{}/artifacts/{}/versions/{}/content",
+            self.base_url, group_id, artifact_id, version
+        );
+        let resp = self.send(self.client.get(&url)).await?.error_for_status()?;
+        self.body(resp).await
+    }
+
+    /// Download `url` into the file `part`, resuming what an earlier attempt left there
+    ///
+    /// Bytes already in `part` are kept and only the rest is asked for with an
+    /// HTTP `Range` request; a registry answering with the full content (`200`)
+    /// starts the file over. A connection that breaks while the body is read is
+    /// retried from where it stopped. `part` is kept on failure, so the next call
+    /// resumes it.
+    pub async fn download_to(&self, url: &str, accept: Option<&str>, part: &Path) -> Result<()> {
+        let mut attempt = 1;
+        loop {
+            match self.download_remaining(url, accept, part).await {
+                Err(e) if attempt < DOWNLOAD_ATTEMPTS && is_transient(&e) => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+    async fn download_remaining(&self, url: &str, accept: Option<&str>, part: &Path) -> Result<()> {
+        let offset = fs::metadata(part).map(|m| m.len()).unwrap_or(0);
+        let mut request = self.client.get(url);
+        if let Some(accept) = accept {
+            request = request.header(reqwest::header::ACCEPT, accept);
+        }
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={offset}-"));
+        }
+        let response = self.send(request).await?;
+        if offset > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            // Nothing past the end: an earlier attempt got the whole content
+            return Ok(());
+        }
+        let mut response = response.error_for_status()?;
+        let resumed = response.status() == reqwest::StatusCode::PARTIAL_CONTENT
+            && response
+                .headers()
+                .get(reqwest::header::CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|range| range.starts_with(&format!("bytes {offset}-")));
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(resumed)
+            .truncate(!resumed)
+            .open(part)
+            .map_err(|e| ApicurioError::io("opening", part, e))?;
+        while let Some(chunk) = self.read(response.chunk()).await? {
+            file.write_all(&chunk)
+                .map_err(|e| ApicurioError::io("writing", part, e))?;
+        }
+        Ok(())
+    }
+
+    /// Download a version's content along with the `Content-Type` the registry reports
+    pub async fn download_with_content_type(
+        &self,
+        group_id: &str,
+        artifact_id: &str,
+        version: &str,
+    ) -> Result<(Option<String>, bytes::Bytes)> {
+        self.download_accepting(group_id, artifact_id, version, None)
+            .await
+    }
+
+    /// Download a version's content, asking for the `accept` media type, along
+    /// with the `Content-Type` the registry answers with
+    pub async fn download_accepting(
+        &self,
+        group_id: &str,
+        artifact_id: &str,
+        version: &str,
+        accept: Option<&str>,
+    ) -> Result<(Option<String>, bytes::Bytes)> {
+        let url = format!(
+            "{}/apis/registry/v3/groups/{}/artifacts/{}/versions/{}/content",
+            self.base_url, group_id, artifact_id, version
+        );
+        let mut request = self.client.get(&url);
+        if let Some(accept) = accept {
+            request = request.header(reqwest::header::ACCEPT, accept);
+        }
+        let resp = self.send(request).await?.error_for_status()?;
+        let content_type = resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        Ok((content_type, self.body(resp).await?))
+    }
+
+    /// The `Content-Type` a version's content is served with, asked with a
+    /// `HEAD` request so the content itself is not downloaded
+    pub async fn get_content_type(
+        &self,
+        group_id: &str,
+        artifact_id: &str,
+        version: &str,
+    ) -> Result<Option<String>> {
+        let url = format!(
+            "{}/apis/registry/v3/groups/{}/artifacts/{}/versions/{}/content",
+            self.base_url, group_id, artifact_id, version
+        );
+        let resp = self
+            .send(self.client.head(&url))
+            .await?
+            .error_for_status()?;
+        Ok(resp
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string))
     }
 
     /// Get system information from the registry
     pub async fn get_system_info(&self) -> Result<SystemInfo> {
         let url = format!("{}/apis/registry/v3/system/info", self.base_url);
-        let resp = self.client.get(&url).send().await?.error_for_status()?;
-        let system_info: SystemInfo = resp.json().await?;
+        let resp = self.send(self.client.get(&url)).await?.error_for_status()?;
+        let system_info: SystemInfo = self.json(resp).await?;
         Ok(system_info)
     }
 
+    /// The authenticated principal and its roles
+    pub async fn get_current_user(&self) -> Result<UserInfo> {
+        let url = format!("{}/apis/registry/v3/users/me", self.base_url);
+        let resp = self.send(self.client.get(&url)).await?.error_for_status()?;
+        self.json(resp).await
+    }
+
+    /// Every role mapping of the registry (needs the admin role)
+    pub async fn list_role_mappings(&self) -> Result<Vec<RoleMapping>> {
+        let url = format!("{}/apis/registry/v3/admin/roleMappings", self.base_url);
+        self.get_paginated(&url, "roleMappings").await
+    }
+
+    /// Every dynamic configuration property of the registry (needs the admin role)
+    pub async fn list_config_properties(&self) -> Result<Vec<ConfigProperty>> {
+        let url = format!("{}/apis/registry/v3/admin/config/properties", self.base_url);
+        let resp = self.send(self.client.get(&url)).await?.error_for_status()?;
+        self.json(resp).await
+    }
+
+    /// A single configuration property
+    pub async fn get_config_property(&self, name: &str) -> Result<ConfigProperty> {
+        let resp = self
+            .send(self.client.get(self.config_property_url(name)))
+            .await?
+            .error_for_status()?;
+        self.json(resp).await
+    }
+
+    /// Override a configuration property
+    pub async fn set_config_property(&self, name: &str, value: &str) -> Result<()> {
+        let request = self
+            .client
+            .put(self.config_property_url(name))
+            .json(&json!({ "value": value }));
+        self.send(request).await?.error_for_status()?;
+        Ok(())
+    }
+
+    /// Drop the override of a configuration property, restoring its default
+    pub async fn reset_config_property(&self, name: &str) -> Result<()> {
+        self.send(self.client.delete(self.config_property_url(name)))
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    fn config_property_url(&self, name: &str) -> String {
+        format!(
+            "{}/apis/registry/v3/admin/config/properties/{}",
+            self.base_url, name
+        )
+    }
+
     /// Get version metadata including references
     pub async fn get_version_metadata(
         &self,
         group_id: &str,
         artifact_id: &str,
-        version: &semver::Version,
+        version: &impl std::fmt::Display,
     ) -> Result<ArtifactVersionMetadata> {
         let url = format!(
             "{}/apis/registry/v3/groups/{}/artifacts/{}/versions/{}",
             self.base_url, group_id, artifact_id, version
         );
-        let resp = self.client.get(&url).send().await?.error_for_status()?;
-        let metadata: ArtifactVersionMetadata = resp.json().await?;
+        let resp = self.send(self.client.get(&url)).await?.error_for_status()?;
+        let metadata: ArtifactVersionMetadata = self.json(resp).await?;
         Ok(metadata)
     }
 
@@ -504,7 +1678,7 @@ impl RegistryClient {
         &self,
         group_id: &str,
         artifact_id: &str,
-        version: &semver::Version,
+        version: &impl std::fmt::Display,
         ref_type: Option<ReferenceType>,
     ) -> Result<Vec<ArtifactVersionReference>> {
         let url = format!(
@@ -519,8 +1693,556 @@ impl RegistryClient {
             request = request.query(&[("refType", ref_type.as_str())]);
         }
 
-        let resp = request.send().await?.error_for_status()?;
-        let references: Vec<ArtifactVersionReference> = resp.json().await?;
+        let resp = self.send(request).await?.error_for_status()?;
+        let references: Vec<ArtifactVersionReference> = self.json(resp).await?;
         Ok(references)
     }
 }
+
+/// The rule violations described by an error answer, `None` when the answer
+/// is not a rule violation
+fn rule_violations(body: &str) -> Option<Vec<RuleViolation>> {
+    #[derive(Deserialize)]
+    struct ApiError {
+        #[serde(default)]
+        name: Option<String>,
+        #[serde(default)]
+        detail: Option<String>,
+        #[serde(default)]
+        causes: Vec<RuleViolation>,
+    }
+
+    let error: ApiError = serde_json::from_str(body).ok()?;
+    if !error.causes.is_empty() {
+        return Some(error.causes);
+    }
+    match (error.name.as_deref(), error.detail) {
+        (Some("RuleViolationException"), Some(detail)) => Some(vec![RuleViolation {
+            description: detail,
+            context: None,
+        }]),
+        _ => None,
+    }
+}
+
+/// Whether a failed request is worth retrying: no answer, or a server error
+fn is_transient(error: &ApicurioError) -> bool {
+    matches!(error, ApicurioError::Network { status, .. } if status.is_none_or(|s| s >= 500))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Serve `total` groups, honoring the `limit`/`offset` query parameters
+    async fn serve_groups(total: usize) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or("");
+                let param = |name: &str| -> usize {
+                    path.split(['?', '&'])
+                        .find_map(|p| p.strip_prefix(&format!("{name}=")))
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(0)
+                };
+                let (limit, offset) = (param("limit"), param("offset"));
+                let groups: Vec<_> = (offset..total.min(offset + limit))
+                    .map(|i| json!({ "groupId": format!("g{i}") }))
+                    .collect();
+                let body = json!({ "count": total, "groups": groups }).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn test_list_groups_reads_every_page() {
+        let url = serve_groups(LIST_PAGE_SIZE * 2 + 5).await;
+        let client = RegistryClient::new(&RegistryConfig {
+            name: "mock".to_string(),
+            url,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let groups = client.list_groups().await.unwrap();
+        assert_eq!(groups.len(), LIST_PAGE_SIZE * 2 + 5);
+        assert_eq!(groups.first().unwrap(), "g0");
+        assert_eq!(
+            groups.last().unwrap(),
+            &format!("g{}", LIST_PAGE_SIZE * 2 + 4)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_to_resumes_a_broken_transfer() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for answer in [
+                // Announces 10 bytes, sends 4 and hangs up
+                "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nConnection: close\r\n\r\nsynt",
+                "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 4-9/10\r\nContent-Length: 6\r\nConnection: close\r\n\r\nax = 3",
+            ] {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                requests.push(String::from_utf8_lossy(&buf[..n]).to_lowercase());
+                socket.write_all(answer.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        let client = RegistryClient::new(&RegistryConfig {
+            name: "mock".to_string(),
+            url: format!("http://{addr}"),
+            ..Default::default()
+        })
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let part = dir.path().join("users.proto.part");
+
+        client
+            .download_to(&format!("http://{addr}/content"), None, &part)
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(&part).unwrap(), "syntax = 3");
+        let requests = server.await.unwrap();
+        assert!(!requests[0].contains("range:"));
+        assert!(requests[1].contains("range: bytes=4-"));
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout_applies_between_reads() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            // A body trickling in for ~2.5s, then one that stops after 2 bytes
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let _ = socket.read(&mut buf).await;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            for byte in b"syntax = 3" {
+                tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+                socket.write_all(&[*byte]).await.unwrap();
+            }
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket.read(&mut buf).await;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\nConnection: close\r\n\r\n{\"")
+                .await
+                .unwrap();
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        });
+        let client = RegistryClient::new(&RegistryConfig {
+            name: "mock".to_string(),
+            url: format!("http://{addr}"),
+            timeouts: crate::config::TimeoutConfig {
+                connect: None,
+                read: Some(1),
+            },
+            ..Default::default()
+        })
+        .unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let part = dir.path().join("users.proto.part");
+
+        // Slow but steady: longer than the timeout overall, never silent that long
+        client
+            .download_to(&format!("http://{addr}/content"), None, &part)
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(&part).unwrap(), "syntax = 3");
+
+        let started = std::time::Instant::now();
+        let err = client.get_system_info().await.unwrap_err();
+        assert!(err.to_string().contains("sent nothing for 1s"), "{err}");
+        assert!(started.elapsed() < std::time::Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn test_set_version_state_puts_new_state() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+        let client = RegistryClient::new(&RegistryConfig {
+            name: "mock".to_string(),
+            url: format!("http://{addr}"),
+            ..Default::default()
+        })
+        .unwrap();
+
+        client
+            .set_version_state("com.example", "users", "1.2.0", "ENABLED")
+            .await
+            .unwrap();
+        let request = server.await.unwrap();
+        assert!(request.starts_with(
+            "PUT /apis/registry/v3/groups/com.example/artifacts/users/versions/1.2.0/state "
+        ));
+        assert!(request.ends_with(r#"{"state":"ENABLED"}"#));
+    }
+
+    #[tokio::test]
+    async fn test_set_rule_creates_or_updates() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                let line = request.lines().next().unwrap_or("").to_string();
+                let body = if line.starts_with("GET ") && line.contains("/rules/VALIDITY ") {
+                    r#"{"ruleType":"VALIDITY","config":"FULL"}"#
+                } else if line.starts_with("GET ") {
+                    r#"["VALIDITY"]"#
+                } else {
+                    ""
+                };
+                seen.lock().unwrap().push(line);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        let client = RegistryClient::new(&RegistryConfig {
+            name: "mock".to_string(),
+            url: format!("http://{addr}"),
+            ..Default::default()
+        })
+        .unwrap();
+        let scope = RuleScope::Artifact {
+            group_id: "com.example",
+            artifact_id: "users",
+        };
+
+        assert_eq!(
+            client.list_rules(scope).await.unwrap(),
+            vec![(RuleType::Validity, "FULL".to_string())]
+        );
+        client
+            .set_rule(scope, RuleType::Validity, "SYNTAX_ONLY")
+            .await
+            .unwrap();
+        client
+            .set_rule(RuleScope::Global, RuleType::Compatibility, "BACKWARD")
+            .await
+            .unwrap();
+        let requests = requests.lock().unwrap();
+        let writes: Vec<String> = requests
+            .iter()
+            .filter(|line| !line.starts_with("GET "))
+            .map(|line| {
+                line.split_whitespace()
+                    .take(2)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        assert_eq!(
+            writes,
+            vec![
+                "PUT /apis/registry/v3/groups/com.example/artifacts/users/rules/VALIDITY",
+                "POST /apis/registry/v3/admin/rules",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_dry_run_reports_rule_violations() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let mut posted = String::new();
+            for _ in 0..2 {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                let response = if request.starts_with("GET ") {
+                    "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"
+                        .to_string()
+                } else {
+                    posted = request;
+                    let body = r#"{"name":"RuleViolationException","detail":"Incompatible","causes":[{"description":"field 'id' removed","context":"/fields"}]}"#;
+                    format!(
+                        "HTTP/1.1 409 Conflict\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                };
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            posted
+        });
+        let client = RegistryClient::new(&RegistryConfig {
+            name: "mock".to_string(),
+            url: format!("http://{addr}"),
+            ..Default::default()
+        })
+        .unwrap();
+        let publish = PublishConfig {
+            name: "users".to_string(),
+            group_id: Some("com.example".to_string()),
+            artifact_id: Some("users".to_string()),
+            version: "2.0.0".to_string(),
+            ..Default::default()
+        };
+
+        let violations = client
+            .test_publish(&publish, b"{\"type\":\"record\"}")
+            .await
+            .unwrap();
+        assert_eq!(
+            violations,
+            vec![RuleViolation {
+                description: "field 'id' removed".to_string(),
+                context: Some("/fields".to_string()),
+            }]
+        );
+        let posted = server.await.unwrap();
+        assert!(posted.starts_with(
+            "POST /apis/registry/v3/groups/com.example/artifacts/users/versions?dryRun=true "
+        ));
+        assert!(!posted.contains("2.0.0"));
+
+        assert_eq!(
+            rule_violations(r#"{"name":"ArtifactNotFoundException"}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_binary_content_encoding() {
+        let bytes = [0x0a, 0xff, 0x00, 0x12];
+        let mut publish = PublishConfig {
+            input_path: "users.desc".to_string(),
+            ..Default::default()
+        };
+        let err = encode_publish_content(&publish, &bytes).unwrap_err();
+        assert!(err.to_string().contains("set `binary: true`"));
+
+        publish.binary = true;
+        let encoded = encode_publish_content(&publish, &bytes).unwrap();
+        assert_eq!(encoded, "Cv8AEg==");
+        assert_eq!(decode_binary_content(b"Cv8AEg==\n").unwrap(), bytes);
+        // Raw bytes are never passed off as decoded content
+        assert!(matches!(
+            decode_binary_content(&bytes),
+            Err(ApicurioError::Integrity { .. })
+        ));
+
+        assert!(content_matches(true, b"Cv8AEg==", &bytes));
+        assert!(content_matches(false, b"a: 1\n", b"a: 1"));
+        assert!(!content_matches(true, b"Cv8AEw==", &bytes));
+    }
+
+    #[test]
+    fn test_accept_header_negotiation() {
+        // The output extension wins over the artifact type
+        assert_eq!(
+            accept_for(Some("OPENAPI"), Some("api/users.yaml")),
+            Some("application/x-yaml")
+        );
+        assert_eq!(
+            accept_for(Some("OPENAPI"), Some("api/users")),
+            Some("application/json")
+        );
+        assert_eq!(
+            accept_for(Some("PROTOBUF"), None),
+            Some("application/x-protobuf")
+        );
+        assert_eq!(accept_for(None, Some("notes.txt")), None);
+
+        assert!(content_type_matches(
+            Some("application/x-yaml; charset=utf-8"),
+            "application/x-yaml"
+        ));
+        assert!(!content_type_matches(
+            Some("application/json"),
+            "application/x-yaml"
+        ));
+        assert!(!content_type_matches(None, "application/json"));
+    }
+
+    #[test]
+    fn test_auth_rejection_messages() {
+        // {"sub":"ci","exp":1000}
+        let jwt = format!(
+            "e30.{}.sig",
+            base64::encode_config(r#"{"sub":"ci","exp":1000}"#, base64::URL_SAFE_NO_PAD)
+        );
+        assert_eq!(jwt_expiry(&jwt), Some(1000));
+        assert_eq!(jwt_expiry("opaque-token"), None);
+
+        let url = "http://registry/apis/registry/v3/groups";
+        let get = format!("GET {url}");
+        let unauthorized = reqwest::StatusCode::UNAUTHORIZED;
+        let bearer = CredentialSource {
+            kind: Some("bearer"),
+            origin: Some("$REGISTRY_TOKEN".to_string()),
+            expires_at: jwt_expiry(&jwt),
+        };
+        let err = bearer.rejection("prod", &get, unauthorized, "", 1000 + 3 * 3600);
+        assert!(matches!(err, ApicurioError::Auth { ref registry, .. } if registry == "prod"));
+        assert_eq!(
+            err.to_string(),
+            "the bearer token of registry 'prod' from $REGISTRY_TOKEN expired 3h ago \
+             (HTTP 401 Unauthorized on GET http://registry/apis/registry/v3/groups); \
+             set a valid credential in $REGISTRY_TOKEN"
+        );
+
+        // Without a local expiry the server's challenge is the hint
+        let opaque = CredentialSource {
+            expires_at: None,
+            origin: Some("vault://kv/registry#token".to_string()),
+            ..bearer.clone()
+        };
+        let challenge = r#"Bearer error="invalid_token", error_description="Token expired""#;
+        let err = opaque.rejection("prod", &get, unauthorized, challenge, 0);
+        assert!(err.to_string().contains("expired (HTTP 401"));
+        assert!(err
+            .to_string()
+            .ends_with("refresh the secret at vault://kv/registry#token"));
+        let err = opaque.rejection("prod", &get, unauthorized, "", 0);
+        assert!(err
+            .to_string()
+            .starts_with("registry 'prod' rejected the bearer credentials"));
+
+        let err = CredentialSource::default().rejection("dev", &get, unauthorized, "", 0);
+        assert!(err.to_string().contains("none is configured"));
+        assert!(err.to_string().contains("apicurio registry edit dev"));
+
+        let delete = format!("DELETE {url}");
+        let err = bearer.rejection("prod", &delete, reqwest::StatusCode::FORBIDDEN, "", 0);
+        assert!(err.to_string().contains("denies DELETE"));
+    }
+
+    #[tokio::test]
+    async fn test_delete_version_artifact_and_group() {
+        let server = crate::dev_server::DevServer::start("127.0.0.1:0")
+            .await
+            .unwrap();
+        server.add_version("com.example", "users", "PROTOBUF", "1.0.0", "v1");
+        server.add_version("com.example", "users", "PROTOBUF", "1.1.0", "v2");
+        server.add_version("com.example", "orders", "PROTOBUF", "1.0.0", "v1");
+        let client = RegistryClient::new(&server.registry_config("dev")).unwrap();
+
+        client
+            .delete_version("com.example", "users", "1.0.0")
+            .await
+            .unwrap();
+        let versions = client.list_versions("com.example", "users").await.unwrap();
+        assert_eq!(versions, [Version::new(1, 1, 0)]);
+
+        client
+            .delete_artifact("com.example", "users")
+            .await
+            .unwrap();
+        let artifacts = client.list_artifacts("com.example").await.unwrap();
+        assert_eq!(artifacts, ["orders"]);
+        assert!(client
+            .delete_artifact("com.example", "users")
+            .await
+            .unwrap_err()
+            .is_not_found());
+
+        client.delete_group("com.example").await.unwrap();
+        assert!(client.list_groups().await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_pool_builds_one_client_per_registry() {
+        let pool = RegistryPool::default();
+        let cfg = RegistryConfig {
+            name: "dev".into(),
+            url: "http://localhost:8080".into(),
+            ..Default::default()
+        };
+        pool.client(&cfg).unwrap();
+        let clients = pool.clients(&[cfg.clone(), cfg.clone()]).unwrap();
+        assert_eq!(clients["dev"].base_url, cfg.url);
+        assert_eq!(pool.clients.lock().unwrap().len(), 1);
+
+        let moved = RegistryConfig {
+            url: "http://localhost:9090".into(),
+            ..cfg
+        };
+        assert_eq!(pool.client(&moved).unwrap().base_url, moved.url);
+        assert_eq!(pool.clients.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_config_properties() {
+        let server = crate::dev_server::DevServer::start("127.0.0.1:0")
+            .await
+            .unwrap();
+        server.add_version("com.example", "users", "PROTOBUF", "1.0.0", "v1");
+        let client = RegistryClient::new(&server.registry_config("dev")).unwrap();
+        let name = "apicurio.rest.deletion.artifact.enabled";
+
+        let properties = client.list_config_properties().await.unwrap();
+        assert!(properties
+            .iter()
+            .any(|p| p.name == name && p.value == "true"));
+
+        client.set_config_property(name, "false").await.unwrap();
+        assert_eq!(
+            client.get_config_property(name).await.unwrap().value,
+            "false"
+        );
+        let err = client
+            .delete_artifact("com.example", "users")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ApicurioError::Network {
+                status: Some(405),
+                ..
+            }
+        ));
+
+        client.reset_config_property(name).await.unwrap();
+        assert_eq!(
+            client.get_config_property(name).await.unwrap().value,
+            "true"
+        );
+        client
+            .delete_artifact("com.example", "users")
+            .await
+            .unwrap();
+        assert!(client
+            .get_config_property("no.such.property")
+            .await
+            .unwrap_err()
+            .is_not_found());
+    }
+}
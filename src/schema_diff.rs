@@ -0,0 +1,586 @@
+//! Structural comparison of schema versions
+//!
+//! Each supported schema kind has an analyzer that flattens a document into an
+//! outline of named elements, for example `field users.v1.User.email` with the
+//! signature `string = 3`. Comparing the outlines of two versions yields the
+//! elements that were added, removed or changed:
+//!
+//! - **Protobuf** - messages, fields, enums, enum values, services and RPCs
+//! - **Avro** - records, fields, enums and enum symbols
+//! - **OpenAPI** - endpoints (method and path), component schemas and their properties
+//!
+//! Other content (or content an analyzer cannot parse) is compared line by line.
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::lint::SchemaKind;
+
+/// How an element differs between two versions
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One difference between two versions of a schema
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// What changed: `message`, `field`, `endpoint`, ...
+    pub element: String,
+    /// Qualified name of the element, e.g. `users.v1.User.email` or `GET /users`
+    pub name: String,
+    /// Signature before the change (removed and changed elements)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub before: Option<String>,
+    /// Signature after the change (added and changed elements)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<String>,
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let signature = |s: &Option<String>| match s.as_deref() {
+            Some(s) if !s.is_empty() => format!(" ({s})"),
+            _ => String::new(),
+        };
+        match self.kind {
+            ChangeKind::Added => write!(
+                f,
+                "Added {} `{}`{}",
+                self.element,
+                self.name,
+                signature(&self.after)
+            ),
+            ChangeKind::Removed => write!(
+                f,
+                "Removed {} `{}`{}",
+                self.element,
+                self.name,
+                signature(&self.before)
+            ),
+            ChangeKind::Changed => write!(
+                f,
+                "Changed {} `{}`: {} → {}",
+                self.element,
+                self.name,
+                self.before.as_deref().unwrap_or("-"),
+                self.after.as_deref().unwrap_or("-")
+            ),
+        }
+    }
+}
+
+/// Elements of a schema keyed by (element kind, qualified name), with their signature
+type Outline = BTreeMap<(&'static str, String), String>;
+
+/// Compare two versions of a schema of the given kind
+pub fn diff(kind: Option<SchemaKind>, old: &str, new: &str) -> Vec<Change> {
+    let outlines = match kind {
+        Some(SchemaKind::Protobuf) => Some((protobuf_outline(old), protobuf_outline(new))),
+        Some(SchemaKind::Avro) => avro_outline(old).zip(avro_outline(new)),
+        Some(SchemaKind::Openapi) => openapi_outline(old).zip(openapi_outline(new)),
+        None => None,
+    };
+    match outlines {
+        Some((old, new)) => diff_outlines(&old, &new),
+        None => diff_lines(old, new),
+    }
+}
+
+fn diff_outlines(old: &Outline, new: &Outline) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for ((element, name), before) in old {
+        match new.get(&(*element, name.clone())) {
+            None => changes.push(Change {
+                kind: ChangeKind::Removed,
+                element: element.to_string(),
+                name: name.clone(),
+                before: Some(before.clone()),
+                after: None,
+            }),
+            Some(after) if after != before => changes.push(Change {
+                kind: ChangeKind::Changed,
+                element: element.to_string(),
+                name: name.clone(),
+                before: Some(before.clone()),
+                after: Some(after.clone()),
+            }),
+            Some(_) => {}
+        }
+    }
+    for ((element, name), after) in new {
+        if !old.contains_key(&(*element, name.clone())) {
+            changes.push(Change {
+                kind: ChangeKind::Added,
+                element: element.to_string(),
+                name: name.clone(),
+                before: None,
+                after: Some(after.clone()),
+            });
+        }
+    }
+    // Added, removed and changed elements of the same name read best together
+    changes.sort_by(|a, b| a.name.cmp(&b.name).then(a.element.cmp(&b.element)));
+    changes
+}
+
+/// Fallback for content without an analyzer: count added and removed lines
+fn diff_lines(old: &str, new: &str) -> Vec<Change> {
+    let old_lines: BTreeSet<&str> = old.lines().map(str::trim).collect();
+    let new_lines: BTreeSet<&str> = new.lines().map(str::trim).collect();
+    let added = new_lines.difference(&old_lines).count();
+    let removed = old_lines.difference(&new_lines).count();
+    if added == 0 && removed == 0 {
+        return Vec::new();
+    }
+    vec![Change {
+        kind: ChangeKind::Changed,
+        element: "content".to_string(),
+        name: "lines".to_string(),
+        before: Some(format!("{removed} removed")),
+        after: Some(format!("{added} added")),
+    }]
+}
+
+/// Remove `//` and `/* */` comments from Protobuf source
+fn strip_proto_comments(content: &str) -> String {
+    let block = Regex::new(r"(?s)/\*.*?\*/").unwrap();
+    let without_blocks = block.replace_all(content, " ");
+    without_blocks
+        .lines()
+        .map(|line| line.split("//").next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn protobuf_outline(content: &str) -> Outline {
+    let field = Regex::new(
+        r"^(?:(optional|repeated|required)\s+)?(map\s*<[^>]+>|[\w.]+)\s+(\w+)\s*=\s*(\d+)",
+    )
+    .unwrap();
+    let enum_value = Regex::new(r"^(\w+)\s*=\s*(-?\w+)").unwrap();
+    let rpc = Regex::new(
+        r"^rpc\s+(\w+)\s*\(\s*(stream\s+)?([\w.]+)\s*\)\s*returns\s*\(\s*(stream\s+)?([\w.]+)\s*\)",
+    )
+    .unwrap();
+    let scope_header = Regex::new(r"^(message|enum|service|oneof|extend)\s+([\w.]+)").unwrap();
+    let package = Regex::new(r"^package\s+([\w.]+)").unwrap();
+
+    let mut outline = Outline::new();
+    let mut prefix = String::new();
+    // (kind, qualified name); `None` kind for blocks that are not tracked (options, rpc bodies)
+    let mut scopes: Vec<(Option<&'static str>, String)> = Vec::new();
+    let source = strip_proto_comments(content);
+    let mut statement = String::new();
+    for c in source.chars() {
+        if c != '{' && c != '}' && c != ';' {
+            statement.push(c);
+            continue;
+        }
+        let text = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+        statement.clear();
+        let parent = scopes.iter().rev().find_map(|(kind, name)| match kind {
+            Some("oneof") => None,
+            Some(kind) => Some((*kind, name.clone())),
+            None => None,
+        });
+        match c {
+            '{' => {
+                if let Some(caps) = scope_header.captures(&text) {
+                    let kind = match &caps[1] {
+                        "message" => "message",
+                        "enum" => "enum",
+                        "service" => "service",
+                        "oneof" => "oneof",
+                        _ => "extend",
+                    };
+                    let name = match (&parent, kind) {
+                        (_, "oneof" | "extend") => String::new(),
+                        (Some((_, parent)), _) => format!("{parent}.{}", &caps[2]),
+                        (None, _) => format!("{prefix}{}", &caps[2]),
+                    };
+                    if matches!(kind, "message" | "enum" | "service") {
+                        outline.insert((kind, name.clone()), String::new());
+                    }
+                    scopes.push((Some(kind), name));
+                } else {
+                    if let (Some(("service", service)), Some(caps)) = (&parent, rpc.captures(&text))
+                    {
+                        outline.insert(
+                            ("rpc", format!("{service}.{}", &caps[1])),
+                            rpc_signature(&caps),
+                        );
+                    }
+                    scopes.push((None, String::new()));
+                }
+            }
+            '}' => {
+                scopes.pop();
+            }
+            _ => {
+                let in_oneof = matches!(scopes.last(), Some((Some("oneof"), _)));
+                match &parent {
+                    None => {
+                        if let Some(caps) = package.captures(&text) {
+                            prefix = format!("{}.", &caps[1]);
+                        }
+                    }
+                    Some(("message", message)) => {
+                        if text.starts_with("option ") || text.starts_with("reserved ") {
+                            continue;
+                        }
+                        if let Some(caps) = field.captures(&text) {
+                            let label = caps.get(1).map(|m| format!("{} ", m.as_str()));
+                            let label = if in_oneof {
+                                Some("oneof ".to_string())
+                            } else {
+                                label
+                            };
+                            let ty = caps[2].split_whitespace().collect::<String>();
+                            outline.insert(
+                                ("field", format!("{message}.{}", &caps[3])),
+                                format!("{}{ty} = {}", label.unwrap_or_default(), &caps[4]),
+                            );
+                        }
+                    }
+                    Some(("enum", name)) => {
+                        if text.starts_with("option ") || text.starts_with("reserved ") {
+                            continue;
+                        }
+                        if let Some(caps) = enum_value.captures(&text) {
+                            outline.insert(
+                                ("enum value", format!("{name}.{}", &caps[1])),
+                                caps[2].to_string(),
+                            );
+                        }
+                    }
+                    Some(("service", service)) => {
+                        if let Some(caps) = rpc.captures(&text) {
+                            outline.insert(
+                                ("rpc", format!("{service}.{}", &caps[1])),
+                                rpc_signature(&caps),
+                            );
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    outline
+}
+
+fn rpc_signature(caps: &regex::Captures) -> String {
+    format!(
+        "({}{}) returns ({}{})",
+        caps.get(2).map_or("", |_| "stream "),
+        &caps[3],
+        caps.get(4).map_or("", |_| "stream "),
+        &caps[5]
+    )
+}
+
+fn avro_outline(content: &str) -> Option<Outline> {
+    let schema: Value = serde_json::from_str(content).ok()?;
+    let mut outline = Outline::new();
+    avro_named_types(&schema, None, &mut outline);
+    Some(outline)
+}
+
+/// Short description of an Avro type: the name of named types, `array<...>`,
+/// `map<...>` and `a | b` for unions
+fn avro_type_name(ty: &Value) -> String {
+    match ty {
+        Value::String(name) => name.clone(),
+        Value::Array(union) => union
+            .iter()
+            .map(avro_type_name)
+            .collect::<Vec<_>>()
+            .join(" | "),
+        Value::Object(map) => match map.get("type").and_then(Value::as_str) {
+            Some("array") => format!("array<{}>", avro_type_name(&map["items"])),
+            Some("map") => format!("map<{}>", avro_type_name(&map["values"])),
+            Some("record" | "error" | "enum" | "fixed") => map
+                .get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("?")
+                .to_string(),
+            Some(other) => match map.get("logicalType").and_then(Value::as_str) {
+                Some(logical) => format!("{other} ({logical})"),
+                None => other.to_string(),
+            },
+            None => "?".to_string(),
+        },
+        _ => "?".to_string(),
+    }
+}
+
+fn avro_named_types(schema: &Value, namespace: Option<&str>, outline: &mut Outline) {
+    match schema {
+        Value::Array(union) => union
+            .iter()
+            .for_each(|s| avro_named_types(s, namespace, outline)),
+        Value::Object(map) => {
+            let ty = map.get("type").and_then(Value::as_str);
+            if let (Some("array"), Some(items)) = (ty, map.get("items")) {
+                return avro_named_types(items, namespace, outline);
+            }
+            if let (Some("map"), Some(values)) = (ty, map.get("values")) {
+                return avro_named_types(values, namespace, outline);
+            }
+            let Some(name) = map.get("name").and_then(Value::as_str) else {
+                return;
+            };
+            let namespace = map.get("namespace").and_then(Value::as_str).or(namespace);
+            let full_name = match namespace {
+                Some(ns) if !name.contains('.') && !ns.is_empty() => format!("{ns}.{name}"),
+                _ => name.to_string(),
+            };
+            let namespace = full_name.rsplit_once('.').map(|(ns, _)| ns);
+            match ty {
+                Some("record" | "error") => {
+                    outline.insert(("record", full_name.clone()), String::new());
+                    for field in map
+                        .get("fields")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                    {
+                        let (Some(field_name), Some(field_ty)) =
+                            (field.get("name").and_then(Value::as_str), field.get("type"))
+                        else {
+                            continue;
+                        };
+                        let mut signature = avro_type_name(field_ty);
+                        if let Some(default) = field.get("default") {
+                            signature.push_str(&format!(" = {default}"));
+                        }
+                        outline.insert(("field", format!("{full_name}.{field_name}")), signature);
+                        avro_named_types(field_ty, namespace, outline);
+                    }
+                }
+                Some("enum") => {
+                    outline.insert(("enum", full_name.clone()), String::new());
+                    for symbol in map
+                        .get("symbols")
+                        .and_then(Value::as_array)
+                        .into_iter()
+                        .flatten()
+                    {
+                        if let Some(symbol) = symbol.as_str() {
+                            outline
+                                .insert(("symbol", format!("{full_name}.{symbol}")), String::new());
+                        }
+                    }
+                }
+                Some("fixed") => {
+                    let size = map.get("size").map(|s| s.to_string()).unwrap_or_default();
+                    outline.insert(("fixed", full_name), format!("size {size}"));
+                }
+                _ => {}
+            }
+        }
+        _ => {}
+    }
+}
+
+const HTTP_METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+fn openapi_outline(content: &str) -> Option<Outline> {
+    let doc: serde_yaml::Value = serde_yaml::from_str(content).ok()?;
+    let mut outline = Outline::new();
+    if let Some(paths) = doc.get("paths").and_then(|p| p.as_mapping()) {
+        for (path, item) in paths {
+            let Some(path) = path.as_str() else { continue };
+            for method in HTTP_METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                let summary = operation
+                    .get("operationId")
+                    .or_else(|| operation.get("summary"))
+                    .and_then(|s| s.as_str())
+                    .unwrap_or_default();
+                let mut signature = summary.to_string();
+                if let Some(responses) = operation.get("responses").and_then(|r| r.as_mapping()) {
+                    let codes: Vec<String> = responses
+                        .keys()
+                        .filter_map(|k| match k {
+                            serde_yaml::Value::String(s) => Some(s.clone()),
+                            serde_yaml::Value::Number(n) => Some(n.to_string()),
+                            _ => None,
+                        })
+                        .collect();
+                    if !codes.is_empty() {
+                        if !signature.is_empty() {
+                            signature.push_str("; ");
+                        }
+                        signature.push_str(&format!("responses {}", codes.join(", ")));
+                    }
+                }
+                outline.insert(
+                    ("endpoint", format!("{} {path}", method.to_uppercase())),
+                    signature,
+                );
+            }
+        }
+    }
+    // OpenAPI 3 keeps schemas under components, Swagger 2 under definitions
+    let schemas = doc
+        .get("components")
+        .and_then(|c| c.get("schemas"))
+        .or_else(|| doc.get("definitions"))
+        .and_then(|s| s.as_mapping());
+    for (name, schema) in schemas.into_iter().flatten() {
+        let Some(name) = name.as_str() else { continue };
+        outline.insert(("schema", name.to_string()), String::new());
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(|r| r.as_sequence())
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.as_str())
+            .collect();
+        let properties = schema.get("properties").and_then(|p| p.as_mapping());
+        for (property, definition) in properties.into_iter().flatten() {
+            let Some(property) = property.as_str() else {
+                continue;
+            };
+            let mut signature = openapi_type_name(definition);
+            if required.contains(&property) {
+                signature.push_str(", required");
+            }
+            outline.insert(("property", format!("{name}.{property}")), signature);
+        }
+    }
+    Some(outline)
+}
+
+fn openapi_type_name(schema: &serde_yaml::Value) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+        return reference
+            .rsplit('/')
+            .next()
+            .unwrap_or(reference)
+            .to_string();
+    }
+    match schema.get("type").and_then(|t| t.as_str()) {
+        Some("array") => format!(
+            "array<{}>",
+            schema
+                .get("items")
+                .map(openapi_type_name)
+                .unwrap_or_else(|| "?".to_string())
+        ),
+        Some(ty) => match schema.get("format").and_then(|f| f.as_str()) {
+            Some(format) => format!("{ty} ({format})"),
+            None => ty.to_string(),
+        },
+        None => "object".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(changes: &[Change]) -> Vec<String> {
+        changes.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_protobuf_diff() {
+        let old = r#"syntax = "proto3";
+package users.v1;
+// message Ignored {}
+message User {
+  string id = 1;
+  int32 age = 2;
+  oneof contact { string phone = 4; }
+}
+service Users {
+  rpc Get(GetRequest) returns (User);
+  rpc Delete(DeleteRequest) returns (Empty) { option deprecated = true; }
+}
+"#;
+        let new = r#"syntax = "proto3";
+package users.v1;
+message User {
+  string id = 1;
+  int64 age = 2;
+  repeated string emails = 3;
+  message Address { string city = 1; }
+}
+enum Status { STATUS_UNSPECIFIED = 0; }
+service Users {
+  rpc Get(GetRequest) returns (User);
+  rpc Watch(WatchRequest) returns (stream User);
+}
+"#;
+        assert_eq!(
+            summary(&diff(Some(SchemaKind::Protobuf), old, new)),
+            [
+                "Added enum `users.v1.Status`",
+                "Added enum value `users.v1.Status.STATUS_UNSPECIFIED` (0)",
+                "Added message `users.v1.User.Address`",
+                "Added field `users.v1.User.Address.city` (string = 1)",
+                "Changed field `users.v1.User.age`: int32 = 2 → int64 = 2",
+                "Added field `users.v1.User.emails` (repeated string = 3)",
+                "Removed field `users.v1.User.phone` (oneof string = 4)",
+                "Removed rpc `users.v1.Users.Delete` ((DeleteRequest) returns (Empty))",
+                "Added rpc `users.v1.Users.Watch` ((WatchRequest) returns (stream User))",
+            ]
+        );
+        assert!(diff(Some(SchemaKind::Protobuf), old, old).is_empty());
+    }
+
+    #[test]
+    fn test_avro_diff() {
+        let old = r#"{"type": "record", "name": "User", "namespace": "com.example",
+            "fields": [{"name": "id", "type": "string"},
+                       {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A", "B"]}}]}"#;
+        let new = r#"{"type": "record", "name": "User", "namespace": "com.example",
+            "fields": [{"name": "id", "type": "string"},
+                       {"name": "email", "type": ["null", "string"], "default": null},
+                       {"name": "kind", "type": {"type": "enum", "name": "Kind", "symbols": ["A"]}}]}"#;
+        assert_eq!(
+            summary(&diff(Some(SchemaKind::Avro), old, new)),
+            [
+                "Removed symbol `com.example.Kind.B`",
+                "Added field `com.example.User.email` (null | string = null)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_openapi_diff() {
+        let old = "openapi: 3.0.3\npaths:\n  /users:\n    get:\n      operationId: listUsers\n      responses:\n        200: {}\ncomponents:\n  schemas:\n    User:\n      properties:\n        id: {type: string}\n";
+        let new = "openapi: 3.0.3\npaths:\n  /users:\n    get:\n      operationId: listUsers\n      responses:\n        200: {}\n    post:\n      operationId: createUser\ncomponents:\n  schemas:\n    User:\n      required: [id]\n      properties:\n        id: {type: string}\n        tags: {type: array, items: {type: string}}\n";
+        assert_eq!(
+            summary(&diff(Some(SchemaKind::Openapi), old, new)),
+            [
+                "Added endpoint `POST /users` (createUser)",
+                "Changed property `User.id`: string → string, required",
+                "Added property `User.tags` (array<string>)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fallback_line_diff() {
+        let changes = diff(None, "a\nb\n", "a\nc\nd\n");
+        assert_eq!(
+            summary(&changes),
+            ["Changed content `lines`: 1 removed → 2 added"]
+        );
+        assert!(diff(None, "a\n", "a\n").is_empty());
+    }
+}
@@ -0,0 +1,281 @@
+//! Secret references for registry credentials
+//!
+//! Besides environment variables, credentials can be given as a `secretRef` URI
+//! that is resolved by a [`SecretProvider`] chosen from its scheme:
+//!
+//! - `env://VAR` - an environment variable
+//! - `file://path` - the (trimmed) content of a file, `~` expands to the home directory
+//! - `vault://<path>#<field>` - a HashiCorp Vault KV secret, read with the `vault` CLI
+//! - `op://<vault>/<item>/<field>` - a 1Password secret, read with the `op` CLI
+//!
+//! The CLI-based providers reuse whatever session the developer is already logged
+//! into, so no token has to be stored for the tool itself.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::{env, fs, process::Command};
+
+/// A parsed secret reference such as `vault://kv/apicurio#token`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecretRef {
+    /// Scheme selecting the provider (`vault`, `op`, ...)
+    pub scheme: String,
+    /// Everything after `://`, without the fragment
+    pub path: String,
+    /// Fragment after `#`, used as the field name by some providers
+    pub field: Option<String>,
+    /// The reference as written in the config
+    pub raw: String,
+}
+
+impl SecretRef {
+    pub fn parse(raw: &str) -> Result<Self> {
+        let (scheme, rest) = raw
+            .split_once("://")
+            .ok_or_else(|| anyhow!("invalid secret reference '{raw}': expected <scheme>://..."))?;
+        if scheme.is_empty() || rest.is_empty() {
+            bail!("invalid secret reference '{raw}': expected <scheme>://...");
+        }
+        let (path, field) = match rest.split_once('#') {
+            Some((path, field)) => (path, Some(field.to_string())),
+            None => (rest, None),
+        };
+        Ok(SecretRef {
+            scheme: scheme.to_string(),
+            path: path.to_string(),
+            field,
+            raw: raw.to_string(),
+        })
+    }
+}
+
+/// Source of secret values for one reference scheme
+pub trait SecretProvider: Send + Sync {
+    /// Scheme handled by this provider, e.g. `vault`
+    fn scheme(&self) -> &str;
+    /// Look up the secret value for a reference of this scheme
+    fn resolve(&self, reference: &SecretRef) -> Result<String>;
+}
+
+/// Environment variables (`env://VAR`)
+pub struct EnvProvider;
+
+impl SecretProvider for EnvProvider {
+    fn scheme(&self) -> &str {
+        "env"
+    }
+
+    fn resolve(&self, reference: &SecretRef) -> Result<String> {
+        env::var(&reference.path)
+            .with_context(|| format!("environment variable '{}' is not set", reference.path))
+    }
+}
+
+/// File contents (`file://path`)
+pub struct FileProvider;
+
+impl SecretProvider for FileProvider {
+    fn scheme(&self) -> &str {
+        "file"
+    }
+
+    fn resolve(&self, reference: &SecretRef) -> Result<String> {
+        let path = match reference.path.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir()
+                .ok_or_else(|| anyhow!("cannot determine the home directory"))?
+                .join(rest),
+            None => reference.path.clone().into(),
+        };
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("reading secret file {}", path.display()))?;
+        Ok(content.trim().to_string())
+    }
+}
+
+/// HashiCorp Vault KV secrets (`vault://<path>#<field>`) via `vault kv get`
+pub struct VaultProvider;
+
+impl SecretProvider for VaultProvider {
+    fn scheme(&self) -> &str {
+        "vault"
+    }
+
+    fn resolve(&self, reference: &SecretRef) -> Result<String> {
+        let field = reference.field.as_deref().ok_or_else(|| {
+            anyhow!(
+                "vault reference '{}' needs a field, e.g. vault://kv/apicurio#token",
+                reference.raw
+            )
+        })?;
+        run_cli(
+            "vault",
+            &["kv", "get", &format!("-field={field}"), &reference.path],
+            reference,
+        )
+    }
+}
+
+/// 1Password secrets (`op://<vault>/<item>/<field>`) via `op read`
+pub struct OnePasswordProvider;
+
+impl SecretProvider for OnePasswordProvider {
+    fn scheme(&self) -> &str {
+        "op"
+    }
+
+    fn resolve(&self, reference: &SecretRef) -> Result<String> {
+        run_cli("op", &["read", "--no-newline", &reference.raw], reference)
+    }
+}
+
+/// Run a secret manager CLI and return its trimmed standard output
+fn run_cli(program: &str, args: &[&str], reference: &SecretRef) -> Result<String> {
+    let output = Command::new(program).args(args).output().with_context(|| {
+        format!(
+            "running `{program}` to resolve '{}'; is it installed and on PATH?",
+            reference.raw
+        )
+    })?;
+    if !output.status.success() {
+        bail!(
+            "`{program}` failed to resolve '{}': {}",
+            reference.raw,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(output.stdout)
+        .with_context(|| format!("secret '{}' is not valid UTF-8", reference.raw))?
+        .trim_end_matches(['\r', '\n'])
+        .to_string())
+}
+
+/// Set of providers used to resolve secret references
+pub struct SecretResolver {
+    providers: Vec<Box<dyn SecretProvider>>,
+}
+
+impl Default for SecretResolver {
+    /// Resolver with the built-in `env`, `file`, `vault` and `op` providers
+    fn default() -> Self {
+        SecretResolver {
+            providers: vec![
+                Box::new(EnvProvider),
+                Box::new(FileProvider),
+                Box::new(VaultProvider),
+                Box::new(OnePasswordProvider),
+            ],
+        }
+    }
+}
+
+impl SecretResolver {
+    /// Add a provider; it takes precedence over existing ones for its scheme
+    pub fn register(&mut self, provider: Box<dyn SecretProvider>) {
+        self.providers.insert(0, provider);
+    }
+
+    /// Resolve a secret reference URI
+    pub fn resolve(&self, raw: &str) -> Result<String> {
+        let reference = SecretRef::parse(raw)?;
+        let provider = self
+            .providers
+            .iter()
+            .find(|p| p.scheme() == reference.scheme)
+            .ok_or_else(|| {
+                anyhow!(
+                    "no secret provider for scheme '{}' in '{}' (supported: {})",
+                    reference.scheme,
+                    raw,
+                    self.providers
+                        .iter()
+                        .map(|p| p.scheme())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+        provider.resolve(&reference)
+    }
+
+    /// Resolve a credential from its secret reference, or else its env variable
+    pub fn resolve_credential(&self, env_var: &str, secret_ref: Option<&str>) -> Result<String> {
+        match secret_ref {
+            Some(reference) => self.resolve(reference),
+            None if !env_var.is_empty() => env::var(env_var)
+                .with_context(|| format!("environment variable '{env_var}' is not set")),
+            None => bail!("credential has neither an environment variable nor a secretRef"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticProvider;
+
+    impl SecretProvider for StaticProvider {
+        fn scheme(&self) -> &str {
+            "static"
+        }
+
+        fn resolve(&self, reference: &SecretRef) -> Result<String> {
+            Ok(format!(
+                "{}:{}",
+                reference.path,
+                reference.field.clone().unwrap_or_default()
+            ))
+        }
+    }
+
+    #[test]
+    fn test_parse_secret_ref() {
+        let reference = SecretRef::parse("vault://kv/apicurio#token").unwrap();
+        assert_eq!(reference.scheme, "vault");
+        assert_eq!(reference.path, "kv/apicurio");
+        assert_eq!(reference.field.as_deref(), Some("token"));
+
+        let reference = SecretRef::parse("op://Dev/Registry/password").unwrap();
+        assert_eq!(reference.path, "Dev/Registry/password");
+        assert_eq!(reference.field, None);
+
+        assert!(SecretRef::parse("not-a-reference").is_err());
+        assert!(SecretRef::parse("vault://").is_err());
+    }
+
+    #[test]
+    fn test_resolver_dispatches_by_scheme() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("token");
+        fs::write(&file, "s3cret\n").unwrap();
+
+        let mut resolver = SecretResolver::default();
+        resolver.register(Box::new(StaticProvider));
+        assert_eq!(
+            resolver.resolve("static://kv/app#token").unwrap(),
+            "kv/app:token"
+        );
+        assert_eq!(
+            resolver
+                .resolve(&format!("file://{}", file.display()))
+                .unwrap(),
+            "s3cret"
+        );
+
+        let err = resolver.resolve("aws://x").unwrap_err().to_string();
+        assert!(err.contains("no secret provider for scheme 'aws'"));
+        assert!(resolver.resolve("vault://kv/app").is_err());
+    }
+
+    #[test]
+    fn test_resolve_credential_prefers_secret_ref() {
+        let resolver = SecretResolver::default();
+        assert!(resolver
+            .resolve_credential("APICURIO_TEST_UNSET_SECRET", None)
+            .is_err());
+        assert!(resolver.resolve_credential("", None).is_err());
+        let err = resolver
+            .resolve_credential("IGNORED", Some("env://APICURIO_TEST_UNSET_SECRET"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("APICURIO_TEST_UNSET_SECRET"));
+    }
+}
@@ -0,0 +1,212 @@
+//! Summary printed at the end of `pull`, `update` and `lock`
+//!
+//! [`SummarySink`] counts the progress events of a run while passing them on to
+//! another sink; [`SummarySink::summary`] adds the lock entry changes and the
+//! elapsed time. The result is printed as a short text block or, with
+//! `--format json`, as a JSON object.
+
+use serde::Serialize;
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use crate::{
+    commands::{update::UpdateDelta, OutputFormat},
+    events::{ConsoleSink, Event, EventSink, WarningSink},
+    lockfile::LockedDependency,
+};
+
+/// What a `pull`, `update` or `lock` run did
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSummary {
+    /// Entries of the lock file after the run
+    pub locked: usize,
+    /// Artifacts resolved against the registries (none when the lock was up to date)
+    pub resolved: usize,
+    /// Artifact downloads, to hash new versions or to write output files
+    pub downloaded: usize,
+    pub downloaded_bytes: u64,
+    /// Downloads avoided: hashes reused from the previous lock file and output
+    /// files already holding the locked content
+    pub cache_hits: usize,
+    pub lock_entries: LockChanges,
+    pub elapsed_ms: u64,
+}
+
+/// Lock entries added, moved to another version or removed by a run
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct LockChanges {
+    pub added: usize,
+    pub changed: usize,
+    pub removed: usize,
+}
+
+impl LockChanges {
+    /// Compare lock entries by [`LockedDependency::entry_key`]
+    pub fn between(before: &[LockedDependency], after: &[LockedDependency]) -> Self {
+        Self::from(&UpdateDelta::between(before, after))
+    }
+}
+
+impl From<&UpdateDelta> for LockChanges {
+    fn from(delta: &UpdateDelta) -> Self {
+        LockChanges {
+            added: delta.added.len(),
+            changed: delta.updated.len(),
+            removed: delta.removed.len(),
+        }
+    }
+}
+
+impl RunSummary {
+    /// Print the summary block
+    pub fn print(&self) {
+        let changes = self.lock_entries;
+        println!("📊 Summary");
+        println!(
+            "   Artifacts:     {} locked, {} resolved",
+            self.locked, self.resolved
+        );
+        println!(
+            "   Downloaded:    {} file(s), {} ({} cache hit(s))",
+            self.downloaded,
+            format_bytes(self.downloaded_bytes),
+            self.cache_hits
+        );
+        println!(
+            "   Lock entries:  {} new, {} changed, {} removed",
+            changes.added, changes.changed, changes.removed
+        );
+        println!("   Elapsed:       {:.2}s", self.elapsed_ms as f64 / 1000.0);
+    }
+}
+
+/// Counts the events of a run, passing them on to `inner`
+pub struct SummarySink {
+    inner: Arc<dyn EventSink>,
+    started: Instant,
+    counts: Mutex<RunSummary>,
+}
+
+impl SummarySink {
+    /// Start counting; the elapsed time of the summary starts now
+    pub fn new(inner: Arc<dyn EventSink>) -> Arc<Self> {
+        Arc::new(SummarySink {
+            inner,
+            started: Instant::now(),
+            counts: Mutex::default(),
+        })
+    }
+
+    /// Count the events of a run printing its progress, or only its warnings
+    /// when the summary is printed as JSON
+    pub fn for_format(format: OutputFormat) -> Arc<Self> {
+        match format {
+            OutputFormat::Text => Self::new(Arc::new(ConsoleSink)),
+            OutputFormat::Json => Self::new(Arc::new(WarningSink)),
+        }
+    }
+
+    /// The counts so far, for a run that left `after` in the lock file where
+    /// `before` was
+    pub fn summary(&self, before: &[LockedDependency], after: &[LockedDependency]) -> RunSummary {
+        self.summary_with(after.len(), LockChanges::between(before, after))
+    }
+
+    /// The counts so far, with the lock file size and changes already known
+    pub fn summary_with(&self, locked: usize, lock_entries: LockChanges) -> RunSummary {
+        let counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        RunSummary {
+            locked,
+            lock_entries,
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            ..counts.clone()
+        }
+    }
+}
+
+impl EventSink for SummarySink {
+    fn emit(&self, event: Event) {
+        {
+            let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+            match &event {
+                Event::Resolved { bytes, .. } => {
+                    counts.resolved += 1;
+                    match bytes {
+                        Some(bytes) => {
+                            counts.downloaded += 1;
+                            counts.downloaded_bytes += *bytes as u64;
+                        }
+                        None => counts.cache_hits += 1,
+                    }
+                }
+                Event::DownloadFinished { bytes, .. } => {
+                    counts.downloaded += 1;
+                    counts.downloaded_bytes += *bytes as u64;
+                }
+                Event::UpToDate { .. } => counts.cache_hits += 1,
+                _ => {}
+            }
+        }
+        self.inner.emit(event);
+    }
+}
+
+/// A byte count for people, e.g. `512 B` or `4.2 KiB`
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::CollectingSink;
+
+    #[test]
+    fn test_summary_counts_downloads_and_cache_hits() {
+        let inner = Arc::new(CollectingSink::default());
+        let sink = SummarySink::new(inner.clone());
+        let resolved = |name: &str, bytes: Option<usize>| Event::Resolved {
+            name: name.to_string(),
+            registry: "main".to_string(),
+            version: "1.0.0".to_string(),
+            transitive: false,
+            bytes,
+        };
+        sink.emit(resolved("users", Some(1000)));
+        sink.emit(resolved("orders", None));
+        sink.emit(Event::DownloadFinished {
+            name: "users".to_string(),
+            version: "1.0.0".to_string(),
+            output_path: "protos/users.proto".to_string(),
+            bytes: 1000,
+        });
+        sink.emit(Event::UpToDate {
+            name: "orders".to_string(),
+            output_path: "protos/orders.proto".to_string(),
+        });
+        assert_eq!(inner.events().len(), 4);
+
+        let summary = sink.summary_with(2, LockChanges::default());
+        assert_eq!(
+            (summary.locked, summary.resolved, summary.downloaded),
+            (2, 2, 2)
+        );
+        assert_eq!(summary.downloaded_bytes, 2000);
+        assert_eq!(summary.cache_hits, 2);
+        assert_eq!(format_bytes(summary.downloaded_bytes), "2.0 KiB");
+        assert_eq!(format_bytes(512), "512 B");
+    }
+}
@@ -39,6 +39,14 @@ dependencies:
         registry: Some("default".to_string()),
         output_path: Some("./protos/service1.proto".to_string()),
         resolve_references: None,
+        binary: false,
+        content_type: None,
+        group: None,
+        labels: Default::default(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+        if_no_match: Default::default(),
+        member_of: None,
     }];
 
     // Create lockfile
@@ -50,12 +58,19 @@ dependencies:
         registry: "default".to_string(),
         resolved_version: "1.0.5".to_string(),
         download_url: "https://example.com/service1/1.0.5".to_string(),
+        registry_url: None,
         sha256: "abcd1234".to_string(),
         output_path: "./protos/service1.proto".to_string(),
         group_id: "com.example".to_string(),
         artifact_id: "service1".to_string(),
         version_spec: "^1.0.0".to_string(),
         is_transitive: false,
+        references: Vec::new(),
+        reference_names: Default::default(),
+        binary: false,
+        avro_fingerprint: None,
+        content_type: None,
+        member_of: None,
     };
 
     let lockfile = lockfile::LockFile::with_config_modified(
@@ -73,7 +88,7 @@ dependencies:
     assert!(lockfile.is_newer_than_config(&config_path).unwrap());
 
     // Test 3: Dependencies match
-    assert!(lockfile.dependencies_match(&[locked_dep.clone()]));
+    assert!(lockfile.dependencies_match(std::slice::from_ref(&locked_dep)));
 
     // Test 4: Modified config should trigger regeneration
     let modified_config = r#"
@@ -94,6 +109,14 @@ dependencies:
         registry: Some("default".to_string()),
         output_path: Some("./protos/service1.proto".to_string()),
         resolve_references: None,
+        binary: false,
+        content_type: None,
+        group: None,
+        labels: Default::default(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+        if_no_match: Default::default(),
+        member_of: None,
     }];
 
     let new_config_hash = lockfile::LockFile::compute_config_hash(modified_config, &modified_deps);
@@ -116,6 +139,14 @@ fn test_formatting_changes_dont_trigger_regeneration() {
         registry: Some("default".to_string()),
         output_path: Some("./protos/service1.proto".to_string()),
         resolve_references: None,
+        binary: false,
+        content_type: None,
+        group: None,
+        labels: Default::default(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+        if_no_match: Default::default(),
+        member_of: None,
     }];
 
     // Original config
@@ -161,6 +192,14 @@ fn test_registry_changes_trigger_regeneration() {
         registry: Some("default".to_string()),
         output_path: Some("./protos".to_string()),
         resolve_references: None,
+        binary: false,
+        content_type: None,
+        group: None,
+        labels: Default::default(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+        if_no_match: Default::default(),
+        member_of: None,
     }];
 
     // Config with one registry
@@ -207,6 +246,14 @@ fn test_external_registry_file_changes_trigger_regeneration() {
         registry: Some("default".to_string()),
         output_path: Some("./protos".to_string()),
         resolve_references: None,
+        binary: false,
+        content_type: None,
+        group: None,
+        labels: Default::default(),
+        include: Vec::new(),
+        exclude: Vec::new(),
+        if_no_match: Default::default(),
+        member_of: None,
     }];
 
     // Config without external registries file